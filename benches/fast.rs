@@ -0,0 +1,126 @@
+// Compares the slice-based converters with the streaming ones on small inputs,
+// and times hex, bin and oct on a large buffer.
+// Run with `cargo bench --bench fast`, and with `--features simd` for the vectorized hex kernels.
+
+use bytary::builtins::{
+    bin_to_hex, bytes_to_bin, bytes_to_hex, bytes_to_oct, hex_to_bytes, oct_to_bytes,
+};
+use bytary::error::BytaryResult;
+use bytary::fast;
+use regex::Regex;
+use std::hint::black_box;
+use std::io::{Read, Write};
+use std::time::Instant;
+
+const ITERATIONS: u32 = 200_000;
+
+/// Size of the large buffer, of which fewer runs are timed
+const LARGE: usize = 1 << 20;
+
+type Streaming = fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()>;
+type Slice = fn(&[u8], &mut [u8]) -> BytaryResult<usize>;
+
+/// Runs `f` many times, printing the average time of one run
+fn bench(name: &str, f: impl FnMut()) {
+    bench_n(name, ITERATIONS, f);
+}
+
+fn bench_n(name: &str, iterations: u32, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    println!(
+        "{:<24} {:>8.1} ns",
+        name,
+        start.elapsed().as_nanos() as f64 / iterations as f64
+    );
+}
+
+/// Decodes bin text the way [`bin_to_hex`] did before its state machine, for comparison
+fn bin_to_hex_regex(input: &mut dyn Read, output: &mut dyn Write) {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes).unwrap();
+    assert!(bytes.is_ascii());
+    let text = String::from_utf8(bytes).unwrap();
+    let re = Regex::new(r"[^0-9]").unwrap();
+    let skipped = re
+        .find_iter(&text)
+        .filter(|m| !m.as_str().trim().is_empty())
+        .count();
+    assert_eq!(skipped, 0);
+    let digits = re.replace_all(&text, "");
+    let mut decoded = vec![0u8; fast::bin::decoded_len(digits.len())];
+    fast::bin::decode_into(digits.as_bytes(), &mut decoded).unwrap();
+    let mut hex = vec![0u8; fast::hex::encoded_len(decoded.len())];
+    fast::hex::encode_into(&decoded, &mut hex).unwrap();
+    output.write_all(&hex).unwrap();
+}
+
+fn main() {
+    let message = (0..64u8).collect::<Vec<u8>>();
+    let encoded = ::hex::encode(&message).into_bytes();
+
+    let cases: [(&str, &[u8], Slice, Streaming); 4] = [
+        ("hex encode", &message, fast::hex::encode_into, bytes_to_hex),
+        ("hex decode", &encoded, fast::hex::decode_into, hex_to_bytes),
+        ("bin encode", &message, fast::bin::encode_into, bytes_to_bin),
+        ("oct encode", &message, fast::oct::encode_into, bytes_to_oct),
+    ];
+
+    let mut buffer = [0u8; fast::bin::encoded_len(64)];
+    for (name, input, slice, streaming) in cases {
+        bench(&format!("{} (slice)", name), || {
+            black_box(slice(black_box(input), &mut buffer).unwrap());
+        });
+        bench(&format!("{} (streaming)", name), || {
+            let mut output = Vec::new();
+            streaming(&mut black_box(input), &mut output).unwrap();
+            black_box(output);
+        });
+    }
+    let large = (0..LARGE)
+        .map(|i| (i * 7 + i / 251) as u8)
+        .collect::<Vec<u8>>();
+    let digits = ::hex::encode(&large).into_bytes();
+    let mut encoded = vec![0u8; fast::hex::encoded_len(LARGE)];
+    let mut decoded = vec![0u8; LARGE];
+    bench_n("hex encode 1 MiB", 200, || {
+        black_box(fast::hex::encode_into(black_box(&large), &mut encoded).unwrap());
+    });
+    bench_n("hex decode 1 MiB", 200, || {
+        black_box(fast::hex::decode_into(black_box(&digits), &mut decoded).unwrap());
+    });
+    bench_n("hex_to_bytes 1 MiB", 50, || {
+        let mut output = Vec::with_capacity(LARGE);
+        hex_to_bytes(&mut black_box(&digits[..]), &mut output).unwrap();
+        black_box(output);
+    });
+
+    // Bin and oct text as it is usually laid out, a space after every byte
+    let spaced = |encode: Streaming| {
+        let mut text = Vec::new();
+        for byte in &large {
+            encode(&mut &[*byte][..], &mut text).unwrap();
+            text.push(b' ');
+        }
+        text
+    };
+    let bin = spaced(bytes_to_bin);
+    let oct = spaced(bytes_to_oct);
+    bench_n("bin_to_hex 1 MiB", 10, || {
+        let mut output = Vec::with_capacity(LARGE * 2);
+        bin_to_hex(&mut black_box(&bin[..]), &mut output).unwrap();
+        black_box(output);
+    });
+    bench_n("bin_to_hex 1 MiB (regex)", 10, || {
+        let mut output = Vec::with_capacity(LARGE * 2);
+        bin_to_hex_regex(&mut black_box(&bin[..]), &mut output);
+        black_box(output);
+    });
+    bench_n("oct_to_bytes 1 MiB", 10, || {
+        let mut output = Vec::with_capacity(LARGE);
+        oct_to_bytes(&mut black_box(&oct[..]), &mut output).unwrap();
+        black_box(output);
+    });
+}
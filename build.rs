@@ -0,0 +1,19 @@
+// Passes `git describe` to the crate as BYTARY_GIT_DESCRIBE, for `bytary::build_info`.
+// Builds outside of a git checkout, like from crates.io, go without it.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-changed=.git/index");
+    let describe = Command::new("git")
+        .args(["describe", "--tags", "--always", "--dirty"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    if let Some(describe) = describe {
+        println!("cargo:rustc-env=BYTARY_GIT_DESCRIBE={}", describe.trim());
+    }
+}
@@ -0,0 +1,72 @@
+//! Feeds arbitrary bytes to the decoders of `bytary::builtins`.
+//!
+//! A decoder may reject the input, but only with `BytaryError::InvalidInputData`:
+//! panics, and any other error for an in-memory input and output, are bugs.
+
+#![no_main]
+
+use bytary::builtins::{
+    Padding, PemOptions, base64_to_bytes, bin_to_hex_with_padding, hex_to_bytes,
+    oct_to_bytes_with_padding, pem_to_bytes_with_options,
+};
+use bytary::error::{BytaryError, BytaryResult};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use std::io::Cursor;
+
+#[derive(Arbitrary, Debug)]
+enum Pad {
+    Strict,
+    Left,
+    Right,
+    Token,
+}
+
+impl From<Pad> for Padding {
+    fn from(pad: Pad) -> Self {
+        match pad {
+            Pad::Strict => Padding::Strict,
+            Pad::Left => Padding::Left,
+            Pad::Right => Padding::Right,
+            Pad::Token => Padding::Token,
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+enum Decoder {
+    BinToHex(Pad),
+    OctToBytes(Pad),
+    HexToBytes,
+    Base64ToBytes,
+    PemToBytes { all_blocks: bool },
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    decoder: Decoder,
+    data: Vec<u8>,
+}
+
+fn decode(decoder: Decoder, data: &[u8]) -> BytaryResult<Vec<u8>> {
+    let mut input = Cursor::new(data);
+    let mut output = Vec::new();
+    match decoder {
+        Decoder::BinToHex(pad) => bin_to_hex_with_padding(pad.into())(&mut input, &mut output),
+        Decoder::OctToBytes(pad) => oct_to_bytes_with_padding(pad.into())(&mut input, &mut output),
+        Decoder::HexToBytes => hex_to_bytes(&mut input, &mut output),
+        Decoder::Base64ToBytes => base64_to_bytes(&mut input, &mut output),
+        Decoder::PemToBytes { all_blocks } => pem_to_bytes_with_options(PemOptions {
+            all_blocks,
+            ..PemOptions::default()
+        })(&mut input, &mut output),
+    }?;
+    Ok(output)
+}
+
+fuzz_target!(|input: Input| {
+    match decode(input.decoder, &input.data) {
+        Ok(_) | Err(BytaryError::InvalidInputData(_)) => {}
+        Err(e) => panic!("unexpected error: {:?}", e),
+    }
+});
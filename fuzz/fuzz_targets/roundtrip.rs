@@ -0,0 +1,80 @@
+//! Differential target for the encoders and decoders of `bytary::builtins`.
+//!
+//! Encoding arbitrary bytes and decoding the result gives the bytes back, and text that decodes
+//! successfully decodes to the same bytes again once re-encoded.
+
+#![no_main]
+
+use bytary::builtins::{
+    base64_to_bytes, bin_to_hex, bytes_to_base64, bytes_to_bin, bytes_to_hex, bytes_to_oct,
+    bytes_to_pem, hex_to_bytes, oct_to_bytes, pem_to_bytes,
+};
+use bytary::error::BytaryResult;
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use std::io::{Read, Write};
+
+type Function = fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()>;
+
+#[derive(Arbitrary, Debug)]
+enum Codec {
+    Bin,
+    Oct,
+    Hex,
+    Base64,
+    Pem,
+}
+
+impl Codec {
+    fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+        let encoder: Function = match self {
+            Codec::Bin => bytes_to_bin,
+            Codec::Oct => bytes_to_oct,
+            Codec::Hex => bytes_to_hex,
+            Codec::Base64 => bytes_to_base64,
+            Codec::Pem => bytes_to_pem,
+        };
+        run(encoder, bytes).expect("encoding never fails")
+    }
+
+    fn decode(&self, text: &[u8]) -> BytaryResult<Vec<u8>> {
+        match self {
+            // bin decodes to hex
+            Codec::Bin => run(hex_to_bytes, &run(bin_to_hex, text)?),
+            Codec::Oct => run(oct_to_bytes, text),
+            Codec::Hex => run(hex_to_bytes, text),
+            Codec::Base64 => run(base64_to_bytes, text),
+            Codec::Pem => run(pem_to_bytes, text),
+        }
+    }
+}
+
+fn run(function: Function, input: &[u8]) -> BytaryResult<Vec<u8>> {
+    let mut output = Vec::new();
+    function(&mut &input[..], &mut output)?;
+    Ok(output)
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    codec: Codec,
+    data: Vec<u8>,
+}
+
+fuzz_target!(|input: Input| {
+    let Input { codec, data } = input;
+
+    // encode, then decode
+    let decoded = codec
+        .decode(&codec.encode(&data))
+        .expect("encoded data decodes");
+    assert_eq!(decoded, data);
+
+    // decode, then encode and decode again
+    if let Ok(decoded) = codec.decode(&data) {
+        let again = codec
+            .decode(&codec.encode(&decoded))
+            .expect("encoded data decodes");
+        assert_eq!(again, decoded);
+    }
+});
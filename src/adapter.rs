@@ -0,0 +1,232 @@
+use crate::convert::Converter;
+use crate::error::{BytaryError, BytaryResult};
+use crate::utils::{FramedWriter, Framing};
+use std::io;
+use std::io::{BufRead, Read, Write};
+use std::rc::Rc;
+
+#[cfg(test)]
+mod test;
+
+/// Run `converter` on each record of `size` input bytes independently, writing one line per record.
+///
+/// The last record may be shorter than `size`.
+/// If `index` is true, each line is prefixed by the 0-based record index, like `#0 `.
+///
+/// Unlike line wrapping, which counts output bytes, records count input bytes,
+/// so each line corresponds to the same amount of input regardless of how much the converter expands it.
+///
+/// ## Panics
+///
+/// Panics if `size` is 0.
+///
+/// ```rust
+/// use bytary::adapter::record_wise;
+/// use bytary::convert::ConversionGraph;
+/// use bytary::format::Format::*;
+///
+/// let hex = ConversionGraph::default().get_converter(&Bytes, &Hex).unwrap();
+/// let conv = record_wise(2, hex, true);
+///
+/// let mut output = Vec::new();
+/// conv.convert(&mut &b"ABC"[..], &mut output).unwrap();
+/// assert_eq!(output, b"#0 4142\n#1 43\n");
+/// ```
+pub fn record_wise(size: usize, converter: Rc<dyn Converter>, index: bool) -> Rc<dyn Converter> {
+    record_wise_framed(size, converter, index, Framing::Newline)
+}
+
+/// Like [`record_wise`], but separates the output records according to `framing`.
+///
+/// ## Panics
+///
+/// Panics if `size` is 0.
+pub fn record_wise_framed(
+    size: usize,
+    converter: Rc<dyn Converter>,
+    index: bool,
+    framing: Framing,
+) -> Rc<dyn Converter> {
+    assert!(size > 0, "record size must not be 0");
+
+    Rc::new(move |input: &mut dyn Read, output: &mut dyn Write| {
+        let mut record = Vec::with_capacity(size);
+        let mut output = FramedWriter::new(output, framing);
+
+        for k in 0.. {
+            record.clear();
+            read_record(input, &mut record, size)?;
+            if record.is_empty() {
+                break;
+            }
+
+            output.begin_record();
+            if index {
+                write!(output, "#{} ", k)?;
+            }
+            convert_record(converter.as_ref(), &record, &mut output)?;
+        }
+        Ok(())
+    })
+}
+
+/// Run `converter` on each line of the input independently, separating the output records according to `framing`.
+///
+/// Line breaks (`\n` or `\r\n`) are removed before conversion, and blank lines are skipped.
+///
+/// ```rust
+/// use bytary::adapter::line_wise;
+/// use bytary::convert::ConversionGraph;
+/// use bytary::format::Format::*;
+/// use bytary::utils::Framing;
+///
+/// let hex = ConversionGraph::default().get_converter(&Hex, &Bytes).unwrap();
+/// let conv = line_wise(hex, Framing::Nul);
+///
+/// let mut output = Vec::new();
+/// conv.convert(&mut &b"4142\n\n43\n"[..], &mut output).unwrap();
+/// assert_eq!(output, b"AB\0C\0");
+/// ```
+pub fn line_wise(converter: Rc<dyn Converter>, framing: Framing) -> Rc<dyn Converter> {
+    Rc::new(move |input: &mut dyn Read, output: &mut dyn Write| {
+        let mut reader = io::BufReader::new(input);
+        let mut output = FramedWriter::new(output, framing);
+        let mut line = Vec::new();
+
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let end = line.len() - line.ends_with(b"\n") as usize;
+            let end = end - line[..end].ends_with(b"\r") as usize;
+
+            output.begin_record();
+            convert_record(converter.as_ref(), &line[..end], &mut output)?;
+        }
+        Ok(())
+    })
+}
+
+/// Splits the input into paragraphs separated by one or more blank lines, reading one paragraph at a time.
+///
+/// A paragraph is its lines joined by `\n`, without their line breaks. Lines of only whitespace are blank,
+/// and blank lines at the start or the end of the input do not make empty paragraphs.
+///
+/// ```rust
+/// use bytary::adapter::split_paragraphs;
+///
+/// let paragraphs = split_paragraphs(&b"SGk=\n\n\nSGVs\nbG8=\n\n"[..])
+///     .collect::<Result<Vec<Vec<u8>>, _>>()
+///     .unwrap();
+/// assert_eq!(paragraphs, [&b"SGk="[..], b"SGVs\nbG8="]);
+/// ```
+pub fn split_paragraphs<R: Read>(reader: R) -> impl Iterator<Item = BytaryResult<Vec<u8>>> {
+    let mut reader = io::BufReader::new(reader);
+    let mut line = Vec::new();
+    let mut failed = false;
+
+    std::iter::from_fn(move || {
+        let mut paragraph = Vec::new();
+        while !failed {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    failed = true;
+                    return Some(Err(e.into()));
+                }
+            }
+            if line.iter().all(u8::is_ascii_whitespace) {
+                match paragraph.is_empty() {
+                    true => continue,
+                    false => break,
+                }
+            }
+            if !paragraph.is_empty() {
+                paragraph.push(b'\n');
+            }
+            let end = line.len() - line.ends_with(b"\n") as usize;
+            let end = end - line[..end].ends_with(b"\r") as usize;
+            paragraph.extend_from_slice(&line[..end]);
+        }
+        (!paragraph.is_empty()).then_some(Ok(paragraph))
+    })
+}
+
+/// Run `converter` on each paragraph of the input independently, see [`split_paragraphs`].
+///
+/// The outputs are written back to back, or as records separated according to `framing`.
+/// If a paragraph fails, the error is wrapped in [`BytaryError::ParagraphFailed`] with its 1-based index.
+///
+/// ```rust
+/// use bytary::adapter::paragraph_wise;
+/// use bytary::convert::ConversionGraph;
+/// use bytary::format::Format::*;
+/// use bytary::utils::Framing;
+///
+/// let hex = ConversionGraph::default().get_converter(&Hex, &Bytes).unwrap();
+/// let conv = paragraph_wise(hex, Some(Framing::Nul));
+///
+/// let mut output = Vec::new();
+/// conv.convert(&mut &b"41\n42\n\n43\n"[..], &mut output).unwrap();
+/// assert_eq!(output, b"AB\0C\0");
+/// ```
+pub fn paragraph_wise(converter: Rc<dyn Converter>, framing: Option<Framing>) -> Rc<dyn Converter> {
+    Rc::new(
+        move |input: &mut dyn Read, output: &mut dyn Write| match framing {
+            Some(framing) => {
+                let mut output = FramedWriter::new(output, framing);
+                for_each_paragraph(input, |paragraph| {
+                    output.begin_record();
+                    convert_record(converter.as_ref(), paragraph, &mut output)
+                })
+            }
+            None => for_each_paragraph(input, |mut paragraph| {
+                converter.convert(&mut paragraph, output)
+            }),
+        },
+    )
+}
+
+/// Calls `convert` on each paragraph of `input`, attributing its errors to the paragraph
+fn for_each_paragraph(
+    input: &mut dyn Read,
+    mut convert: impl FnMut(&[u8]) -> BytaryResult<()>,
+) -> BytaryResult<()> {
+    for (index, paragraph) in split_paragraphs(input).enumerate() {
+        convert(&paragraph?).map_err(|e| {
+            // Offsets are those of the paragraph, without the line breaks
+            BytaryError::ParagraphFailed(index + 1, Box::new(e.without_offset()))
+        })?;
+    }
+    Ok(())
+}
+
+/// Converts one record into `output`, then ends it.
+///
+/// The offsets of the errors are those of the record, so they are dropped.
+fn convert_record<W: Write>(
+    converter: &dyn Converter,
+    mut record: &[u8],
+    output: &mut FramedWriter<W>,
+) -> BytaryResult<()> {
+    let result = converter
+        .convert(&mut record, output)
+        .map_err(BytaryError::without_offset);
+    // The converter may have failed, or silently lost data, because the record was too large
+    match output.overflowed() {
+        true => output.end_record(),
+        false => result.and_then(|_| output.end_record()),
+    }
+}
+
+/// Reads up to `size` bytes into `record`, stopping early only at the end of input
+fn read_record(input: &mut dyn Read, record: &mut Vec<u8>, size: usize) -> BytaryResult<()> {
+    input.take(size as u64).read_to_end(record)?;
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use crate::adapter::{
+    line_wise, paragraph_wise, record_wise, record_wise_framed, split_paragraphs,
+};
+use crate::convert::ConversionGraph;
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format::*;
+use crate::testsupport::FaultyReader;
+use crate::utils::Framing;
+
+#[test]
+fn test_record_wise() -> BytaryResult<()> {
+    let hex = ConversionGraph::default()
+        .get_converter(&Bytes, &Hex)
+        .unwrap();
+    let data = (0..50u8).collect::<Vec<u8>>();
+
+    let mut output = Vec::new();
+    record_wise(24, hex.clone(), false).convert(&mut data.as_slice(), &mut output)?;
+    let lines = String::from_utf8(output).unwrap();
+    let lines = lines.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], hex::encode(&data[..24]));
+    assert_eq!(lines[1], hex::encode(&data[24..48]));
+    assert_eq!(lines[2], hex::encode(&data[48..]));
+
+    let mut output = Vec::new();
+    record_wise(24, hex, true).convert(&mut data.as_slice(), &mut output)?;
+    let lines = String::from_utf8(output).unwrap();
+    let labels = lines
+        .lines()
+        .map(|line| line.split_once(' ').unwrap().0)
+        .collect::<Vec<&str>>();
+    assert_eq!(labels, ["#0", "#1", "#2"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_record_wise_expansion() -> BytaryResult<()> {
+    let bin = ConversionGraph::default()
+        .get_converter(&Bytes, &Bin)
+        .unwrap();
+
+    let mut output = Vec::new();
+    record_wise(1, bin, true).convert(&mut &b"\x01\x80"[..], &mut output)?;
+    assert_eq!(output, b"#0 00000001\n#1 10000000\n");
+    Ok(())
+}
+
+#[test]
+fn test_record_wise_empty() -> BytaryResult<()> {
+    let copy = ConversionGraph::get_copy_converter();
+
+    let mut output = Vec::new();
+    record_wise(4, copy, true).convert(&mut &b""[..], &mut output)?;
+    assert!(output.is_empty());
+    Ok(())
+}
+
+/// Splits len32-framed output into its records
+fn parse_frames(mut output: &[u8]) -> Vec<&[u8]> {
+    let mut records = Vec::new();
+    while !output.is_empty() {
+        let (length, rest) = output.split_at(4);
+        let length = u32::from_be_bytes(length.try_into().unwrap()) as usize;
+        let (record, rest) = rest.split_at(length);
+        records.push(record);
+        output = rest;
+    }
+    records
+}
+
+#[test]
+fn test_line_wise_len32() -> BytaryResult<()> {
+    let hex = ConversionGraph::default()
+        .get_converter(&Hex, &Bytes)
+        .unwrap();
+    let converter = line_wise(hex, Framing::Len32 { max_size: 16 });
+
+    let mut output = Vec::new();
+    converter.convert(
+        &mut &b"48 65 6c 6c 6f\r\n\n21\n0x00,0x01,0x0a"[..],
+        &mut output,
+    )?;
+    assert_eq!(
+        parse_frames(&output),
+        [&b"Hello"[..], &b"!"[..], &b"\x00\x01\n"[..]]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_line_wise_max_size() {
+    let hex = ConversionGraph::default()
+        .get_converter(&Hex, &Bytes)
+        .unwrap();
+    let converter = line_wise(hex, Framing::Len32 { max_size: 4 });
+
+    let mut output = Vec::new();
+    let err = converter
+        .convert(&mut &b"0102\n0102030405\n"[..], &mut output)
+        .unwrap_err();
+    assert!(matches!(err, BytaryError::RecordTooLarge(4)), "{}", err);
+    assert_eq!(err.to_string(), "Record larger than the maximum of 4 bytes");
+    // Records before the large one are written
+    assert_eq!(output, b"\0\0\0\x02\x01\x02");
+}
+
+#[test]
+fn test_record_wise_framed() -> BytaryResult<()> {
+    let copy = ConversionGraph::get_copy_converter();
+
+    let mut output = Vec::new();
+    record_wise_framed(2, copy.clone(), false, Framing::Nul)
+        .convert(&mut &b"ABC"[..], &mut output)?;
+    assert_eq!(output, b"AB\0C\0");
+
+    let mut output = Vec::new();
+    let framing = Framing::Len32 { max_size: 1024 };
+    record_wise_framed(2, copy, true, framing).convert(&mut &b"ABC"[..], &mut output)?;
+    assert_eq!(parse_frames(&output), [&b"#0 AB"[..], &b"#1 C"[..]]);
+    Ok(())
+}
+
+#[test]
+fn test_split_paragraphs() -> BytaryResult<()> {
+    let input = b"\n48 69\r\n21\n\n \n0a\n0b 0c\n\n\n";
+    let paragraphs = split_paragraphs(&input[..]).collect::<BytaryResult<Vec<Vec<u8>>>>()?;
+    assert_eq!(paragraphs, [&b"48 69\n21"[..], b"0a\n0b 0c"]);
+
+    assert_eq!(split_paragraphs(&b""[..]).count(), 0);
+    assert_eq!(split_paragraphs(&b"\n \n\n"[..]).count(), 0);
+
+    // Paragraphs are yielded before the rest of the input is read
+    let failing = FaultyReader::new(&b"41\n\n"[..], 1);
+    let mut paragraphs = split_paragraphs(failing);
+    assert_eq!(paragraphs.next().unwrap()?, b"41");
+    assert!(matches!(paragraphs.next(), Some(Err(BytaryError::Io(_)))));
+    assert!(paragraphs.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn test_paragraph_wise() -> BytaryResult<()> {
+    let hex = ConversionGraph::default()
+        .get_converter(&Hex, &Bytes)
+        .unwrap();
+    let input = b"48\n69\n\n21 0a\n0b\n\n\n0c 0d\n0e\n\n\n";
+
+    let mut output = Vec::new();
+    paragraph_wise(hex.clone(), Some(Framing::Nul)).convert(&mut &input[..], &mut output)?;
+    assert_eq!(output, b"Hi\0!\x0a\x0b\0\x0c\x0d\x0e\0");
+
+    let mut output = Vec::new();
+    paragraph_wise(hex.clone(), None).convert(&mut &input[..], &mut output)?;
+    assert_eq!(output, b"Hi!\x0a\x0b\x0c\x0d\x0e");
+
+    let result = paragraph_wise(hex, None).convert(&mut &b"41\n\n4g\n\n43\n"[..], &mut Vec::new());
+    match result {
+        Err(BytaryError::ParagraphFailed(2, error)) => {
+            assert!(matches!(*error, BytaryError::InvalidInputData(_)))
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use crate::chunks::{Edge, feed_steps, finish_steps};
+use crate::convert::ConversionGraph;
+use crate::error::BytaryResult;
+use crate::format::Format;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+#[cfg(test)]
+mod test;
+
+/// An async input stream accepted by [`AsyncConvertFn`]
+pub type AsyncInput<'a> = dyn AsyncRead + Unpin + Send + 'a;
+/// An async output stream accepted by [`AsyncConvertFn`]
+pub type AsyncOutput<'a> = dyn AsyncWrite + Unpin + Send + 'a;
+
+/// A future returned by [`AsyncConvertFn`]
+pub type AsyncConvertFuture<'a> = Pin<Box<dyn Future<Output = BytaryResult<()>> + Send + 'a>>;
+
+/// A function that asynchronously converts from one format to another.
+pub type AsyncConvertFn = dyn for<'a> Fn(&'a mut AsyncInput<'a>, &'a mut AsyncOutput<'a>) -> AsyncConvertFuture<'a>
+    + Send
+    + Sync;
+
+/// Runs `edges` over the whole of `input`, passing each chunk through all of them as soon as it is read,
+/// so that memory stays bounded however long the path is
+fn run_path<'a>(
+    edges: Arc<[Edge]>,
+    input: &'a mut AsyncInput<'a>,
+    output: &'a mut AsyncOutput<'a>,
+) -> AsyncConvertFuture<'a> {
+    Box::pin(async move {
+        if edges.is_empty() {
+            tokio::io::copy(input, output).await?;
+            return Ok(());
+        }
+
+        let mut steps = edges.iter().map(Edge::start).collect::<Vec<_>>();
+        let mut buffer = [0u8; 1024];
+        loop {
+            let length = input.read(&mut buffer).await?;
+            let bytes = match length {
+                0 => finish_steps(&mut steps)?,
+                _ => feed_steps(&mut steps, &buffer[..length])?,
+            };
+            output.write_all(&bytes).await?;
+            if length == 0 {
+                break;
+            }
+        }
+        output.flush().await?;
+        Ok(())
+    })
+}
+
+impl ConversionGraph {
+    /// Get an async converter from `from` to `to`.
+    ///
+    /// The path is searched in this graph, but every step of it must have an async-native implementation,
+    /// which is only available for the built-in conversions between bytes, bin, hex and oct, as long
+    /// as the graph did not replace them.
+    ///
+    /// Returns `None` if there is no path, or if any step of it has no async implementation.
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    ///
+    /// let graph = ConversionGraph::default();
+    /// let conv = graph.get_async_converter(&Bytes, &Hex).unwrap();
+    /// ```
+    pub fn get_async_converter(&self, from: &Format, to: &Format) -> Option<Arc<AsyncConvertFn>> {
+        let path = match from == to {
+            true => vec![from.clone()],
+            false => self.find_shortest_path(from, to)?,
+        };
+
+        let edges = path
+            .windows(2)
+            .map(|w| Edge::get(self, &w[0], &w[1]))
+            .collect::<Option<Arc<[Edge]>>>()?;
+
+        Some(Arc::new(
+            move |input: &mut AsyncInput, output: &mut AsyncOutput| {
+                run_path(edges.clone(), input, output)
+            },
+        ))
+    }
+}
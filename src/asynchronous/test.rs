@@ -0,0 +1,164 @@
+use crate::convert::ConversionGraph;
+use crate::error::BytaryResult;
+use crate::format::Format::*;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn test_duplex() -> BytaryResult<()> {
+    let conv = ConversionGraph::default()
+        .get_async_converter(&Bytes, &Hex)
+        .unwrap();
+
+    let (mut input, mut writer) = tokio::io::duplex(4);
+    let (mut reader, mut output) = tokio::io::duplex(4);
+
+    let feed = async {
+        writer
+            .write_all(&[0x1b, 0x34, 0x8f, 0xff, 0x00, 0x0e])
+            .await?;
+        writer.shutdown().await?;
+        drop(writer);
+        BytaryResult::Ok(())
+    };
+    let convert = async {
+        conv(&mut input, &mut output).await?;
+        output.shutdown().await?;
+        drop(output);
+        BytaryResult::Ok(())
+    };
+    let collect = async {
+        let mut result = String::new();
+        reader.read_to_string(&mut result).await?;
+        BytaryResult::Ok(result)
+    };
+
+    let (fed, converted, result) = tokio::join!(feed, convert, collect);
+    fed?;
+    converted?;
+    assert_eq!(result?, "1b348fff000e");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multi_hop() -> BytaryResult<()> {
+    let graph = ConversionGraph::default();
+
+    let mut output = Vec::new();
+    let conv = graph.get_async_converter(&Hex, &Bin).unwrap();
+    conv(&mut &b"1b\n34"[..], &mut output).await?;
+    assert_eq!(output, b"0001101100110100");
+
+    let mut output = Vec::new();
+    let conv = graph.get_async_converter(&Oct, &Hex).unwrap();
+    conv(&mut &b"016 070"[..], &mut output).await?;
+    assert_eq!(output, b"0e38");
+
+    // Only the built-in conversions have an async implementation
+    let mut graph = ConversionGraph::default();
+    graph.add_direct(Bytes, Hex, |_, _| Ok(()), 1).unwrap();
+    assert!(graph.get_async_converter(&Bytes, &Hex).is_none());
+    assert!(graph.get_async_converter(&Hex, &Bytes).is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multi_hop_streaming() -> BytaryResult<()> {
+    // hex => bytes => bin
+    let conv = ConversionGraph::default()
+        .get_async_converter(&Hex, &Bin)
+        .unwrap();
+
+    let (mut input, mut writer) = tokio::io::duplex(16);
+    let (mut reader, mut output) = tokio::io::duplex(16);
+
+    let convert = async {
+        conv(&mut input, &mut output).await?;
+        output.shutdown().await?;
+        BytaryResult::Ok(())
+    };
+    let feed = async {
+        writer.write_all(b"1b").await?;
+        // The first byte comes out of both hops while the input is still open
+        let mut first = [0u8; 8];
+        tokio::time::timeout(Duration::from_secs(5), reader.read_exact(&mut first))
+            .await
+            .expect("no output before the end of the input")?;
+        assert_eq!(&first, b"00011011");
+
+        writer.write_all(b"34").await?;
+        writer.shutdown().await?;
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).await?;
+        assert_eq!(rest, b"00110100");
+        BytaryResult::Ok(())
+    };
+
+    let (converted, fed) = tokio::join!(convert, feed);
+    converted?;
+    fed
+}
+
+#[tokio::test]
+async fn test_matches_sync() -> BytaryResult<()> {
+    let graph = ConversionGraph::default();
+    let data = (0..=255u8).cycle().take(5000).collect::<Vec<u8>>();
+
+    for (from, to) in [(Bytes, Bin), (Bytes, Oct), (Bytes, Hex), (Bin, Hex)] {
+        let mut input = Vec::new();
+        graph
+            .get_converter(&Bytes, &from)
+            .unwrap()
+            .convert(&mut data.as_slice(), &mut input)?;
+
+        let mut expected = Vec::new();
+        graph
+            .get_converter(&from, &to)
+            .unwrap()
+            .convert(&mut input.as_slice(), &mut expected)?;
+
+        let mut output = Vec::new();
+        graph.get_async_converter(&from, &to).unwrap()(&mut input.as_slice(), &mut output).await?;
+
+        assert_eq!(output, expected, "{} => {}", from, to);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_input() {
+    let conv = ConversionGraph::default()
+        .get_async_converter(&Hex, &Bytes)
+        .unwrap();
+    let mut output = Vec::new();
+    assert!(conv(&mut &b"1g"[..], &mut output).await.is_err());
+}
+
+#[tokio::test]
+async fn test_unsupported() {
+    let mut graph = ConversionGraph::new();
+    graph.add_direct(Bytes, Base64, |_, _| Ok(()), 1).unwrap();
+    assert!(graph.get_async_converter(&Bytes, &Base64).is_none());
+    assert!(graph.get_async_converter(&Bytes, &Hex).is_none());
+    assert!(graph.get_async_converter(&Hex, &Hex).is_some());
+}
+
+#[tokio::test]
+async fn test_cancellation() {
+    let conv = ConversionGraph::default()
+        .get_async_converter(&Bytes, &Hex)
+        .unwrap();
+
+    // The writer half is kept open, so the conversion never sees EOF
+    let (mut input, mut writer) = tokio::io::duplex(16);
+    writer.write_all(b"partial").await.unwrap();
+    let mut output = Vec::new();
+
+    let result =
+        tokio::time::timeout(Duration::from_millis(50), conv(&mut input, &mut output)).await;
+    assert!(result.is_err());
+
+    // The future has been dropped, so both ends are usable again
+    drop(input);
+    assert!(writer.write_all(b"more").await.is_err());
+}
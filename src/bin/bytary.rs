@@ -1,14 +1,61 @@
-use bytary::convert::ConversionGraph;
+use bytary::adapter::{line_wise, paragraph_wise, record_wise, record_wise_framed};
+use bytary::bits::{BitOrder, bit_slice};
+use bytary::builtins::{
+    Base64Alphabet, Base64Options, Base64ToBytes, BinOptions, BinToHex, ByteSetOptions,
+    BytesToBase64, BytesToBin, BytesToByteSet, BytesToOct, BytesToPem, BytesToStats, OctOptions,
+    OctToBytes, Padding, PemOptions, PemToBytes, StatsOptions, base64_with_alphabet, hex_to_bytes,
+    reformat_text,
+};
+#[cfg(feature = "config")]
+use bytary::config::Config;
+use bytary::convert::{
+    ComposedConverter, ConversionGraph, ConversionPlan, Converter, EdgeInfo, StreamingClass,
+    decode_any,
+};
+use bytary::diff::{DiffOptions, diff_streams};
 use bytary::error::{BytaryError, BytaryResult};
-use bytary::format::Format;
-use bytary::utils::FormattedWriter;
-use clap::Parser;
+use bytary::format::{FORMATS, Format};
+#[cfg(feature = "json")]
+use bytary::json;
+use bytary::leb128::Leb128Options;
+use bytary::numeric::{NumericOptions, numeric_converter};
+use bytary::observe::StageTiming;
+use bytary::patch::{PatchOptions, PatchingWriter, parse_patches};
+use bytary::selftest;
+use bytary::text::TextOptions;
+use bytary::transform::{Deinterleave, byteswap, deinterleave};
+use bytary::utils::{
+    AnnotatedFormatter, BroadcastWriter, ColoredWriter, CommentStrippingReader, CountingReader,
+    CountingWriter, DelimitedWriter, ExpectWriter, FormattedWriter, Framing, GroupCheckingReader,
+    GroupedWriter, HeadWriter, HexStyle, IntervalFlushWriter, LimitedReader, LineTimestamps,
+    PatternWindow, PatternWindowReader, SequenceReader, SplittingWriter, TailWriter, TeeReader,
+    TeeWriter, TimedReader, TimestampStyle, cleanup_pasted_text, format_size, parse_size, plural,
+};
+use bytary::warning;
+use clap::builder::{PossibleValue, TypedValueParser};
+use clap::error::ErrorKind;
+#[cfg(feature = "config")]
+use clap::parser::ValueSource;
+use clap::{CommandFactory, Parser, ValueEnum};
+use clap_complete::Shell;
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter};
+use std::fs;
 use std::io;
-use strum::IntoEnumIterator;
+use std::io::{IsTerminal, Read};
+use std::net::{TcpListener, TcpStream};
+use std::num::{NonZeroU64, NonZeroUsize};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
 
 /// A simple CLI tool for binary data manipulation
 #[derive(Parser)]
-#[command(version, about, long_about)]
+#[command(version, about, long_about, after_help = EXIT_STATUS)]
 struct BytaryArgs {
     /// List all supported formats and exit
     ///
@@ -16,15 +63,50 @@ struct BytaryArgs {
     #[arg(short, long, default_value_t = false)]
     list_formats: bool,
 
+    /// Print the format list as JSON, including all direct conversions and their costs
+    #[cfg(feature = "serde")]
+    #[arg(long, default_value_t = false, requires = "list_formats")]
+    json: bool,
+
+    /// Print the version, git commit, enabled features and number of conversions of this build and exit
+    #[arg(long, default_value_t = false)]
+    build_info: bool,
+
     /// Output format
-    #[arg(default_value = "bytes")]
-    to: String,
+    #[arg(default_value = "bytes", value_parser = FormatParser)]
+    to: Format,
 
     /// Input format
     ///
     /// Default is bytes
-    #[arg(default_value = "bytes")]
-    from: String,
+    #[arg(default_value = "bytes", value_parser = FormatParser)]
+    from: Format,
+
+    /// Round-trip a fixed set of buffers through every pair of formats, print one line per pair and exit
+    ///
+    /// The input is not read. Exits with a nonzero status if any pair fails.
+    #[arg(long, default_value_t = false)]
+    self_test: bool,
+
+    /// Use the options of this preset of the config file, which options given here override
+    #[cfg(feature = "config")]
+    #[arg(long, value_name = "NAME")]
+    preset: Option<String>,
+
+    /// Config file of --preset, instead of $XDG_CONFIG_HOME/bytary/config.toml
+    #[cfg(feature = "config")]
+    #[arg(long, value_name = "PATH", requires = "preset")]
+    config: Option<PathBuf>,
+
+    /// Print a completion script for this shell and exit
+    #[arg(long, value_name = "SHELL")]
+    completions: Option<Shell>,
+
+    /// Print the conversion graph in Graphviz DOT and exit
+    ///
+    /// Edges are labeled with the converter name and cost, and lossy ones are dashed.
+    #[arg(long, default_value_t = false)]
+    dump_graph: bool,
 
     /// Space interval between bytes
     ///
@@ -38,113 +120,4049 @@ struct BytaryArgs {
     #[arg(short, long = "wrap", default_value_t = 0)]
     wrap_interval: usize,
 
+    /// Break lines after the output of every N input bytes, whatever the output format
+    ///
+    /// For base64, N must be a multiple of 3.
+    #[arg(long, value_name = "N", conflicts_with_all = ["wrap_interval", "no_pad"])]
+    wrap_bytes: Option<NonZeroUsize>,
+
+    /// Allow lines that are not a whole number of --space groups
+    #[arg(long, default_value_t = false)]
+    allow_odd_formatting: bool,
+
+    /// Follow each line of bin, hex or oct output with the printable ASCII of its bytes
+    ///
+    /// Other bytes are shown as `.`, and the last line is padded to keep the column aligned.
+    #[arg(long, requires = "wrap_bytes", conflicts_with_all = ["style", "color", "record_size", "paragraph", "bit_skip", "bit_length", "channel", "byteswap", "frame", "patch"])]
+    with_ascii: bool,
+
+    /// Check that the input is well-formed for the input format [FROM], without converting it
+    ///
+    /// Exits with a nonzero status and a one-line report if the input is invalid.
+    #[arg(short, long, default_value_t = false)]
+    check: bool,
+
+    /// Ignore comments in hex, bin or oct input, from `#` or `//` to the end of the line
+    #[arg(long, default_value_t = false)]
+    comments: bool,
+
+    /// Remove characters that rich-text editors add to pasted text before decoding
+    ///
+    /// These are byte order marks, no-break spaces, soft hyphens, zero-width spaces and joiners,
+    /// and curly quotes. Any other invalid character is still an error. Reports how many were removed.
+    #[arg(long, default_value_t = false)]
+    paste_cleanup: bool,
+
+    /// With --paste-cleanup, also replace full-width forms of ASCII characters, like ＡＢ１２, with ASCII ones
+    ///
+    /// Other look-alikes of digits, like Arabic-Indic or superscript digits, are still an error.
+    #[arg(long, default_value_t = false, requires = "paste_cleanup")]
+    fold_fullwidth: bool,
+
+    /// With --paste-cleanup, also skip other non-ASCII characters and bytes instead of rejecting them
+    ///
+    /// Look-alikes of digits are still an error. Reports how many characters were skipped.
+    #[arg(long, default_value_t = false, requires = "paste_cleanup")]
+    skip_non_ascii: bool,
+
+    /// Decode the input with the first of these formats that decodes all of it, instead of [FROM]
+    ///
+    /// The input is read into memory first, up to 64 MiB. --verbose prints the format that was chosen.
+    #[arg(long, value_name = "FORMATS", value_delimiter = ',', value_parser = FormatParser, conflicts_with_all = ["from", "inputs", "check", "dry_run"])]
+    from_any: Vec<Format>,
+
+    /// Decode the string at this JSON Pointer of the input, like `/payload` or `/data/0/blob`
+    ///
+    /// The input is read into memory first, up to 64 MiB.
+    #[cfg(feature = "json")]
+    #[arg(long, value_name = "POINTER", conflicts_with_all = ["from_any", "also"])]
+    json_field: Option<String>,
+
+    /// Skip the bytes input up to this hex pattern, like `ff d8`
+    #[arg(long, value_name = "HEX")]
+    start_pattern: Option<String>,
+
+    /// End the bytes input at the first occurrence of this hex pattern after the start
+    #[arg(long, value_name = "HEX")]
+    end_pattern: Option<String>,
+
+    /// Start at the Nth occurrence of --start-pattern
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "1",
+        requires = "start_pattern"
+    )]
+    nth: NonZeroUsize,
+
+    /// Leave the start pattern out of the input
+    #[arg(long, default_value_t = false, requires = "start_pattern")]
+    exclude_start: bool,
+
+    /// Leave the end pattern out of the input
+    #[arg(long, default_value_t = false, requires = "end_pattern")]
+    exclude_end: bool,
+
+    /// Fail if the conversion reports warnings, like a dropped trailing hex digit or skipped characters
+    ///
+    /// Warnings are printed to stderr after the conversion either way.
+    #[arg(long, default_value_t = false)]
+    warnings_as_errors: bool,
+
+    /// Fail if --start-pattern or --end-pattern is not found, instead of printing a warning
+    #[arg(long, default_value_t = false)]
+    require_patterns: bool,
+
+    /// Compare the raw input with FILE instead of converting it, writing one line per range of differing bytes
+    ///
+    /// FILE is the old side and the input the new one.
+    /// Exits with status 0 if they are identical, 1 if they differ, and 2 on errors.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["check", "inputs"])]
+    compare: Option<PathBuf>,
+
+    /// Compare the converted output with VALUE instead of writing it, writing only `match` or `mismatch`
+    ///
+    /// The output is compared in constant time and never shown, only its length.
+    /// Exits with status 0 if it matches and 1 if it does not.
+    #[arg(long, value_name = "VALUE", conflicts_with_all = ["check", "compare", "expect_file", "output", "connect", "output_dir"])]
+    expect: Option<String>,
+
+    /// Like --expect, with the contents of FILE, for outputs that are not text
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["check", "compare", "output", "connect", "output_dir"])]
+    expect_file: Option<PathBuf>,
+
+    /// Also write the output to FILE, exactly as it is written to the output
+    ///
+    /// Writing to FILE goes on if the output is closed early, unless --tee-strict.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["check", "compare", "expect", "expect_file"])]
+    tee: Option<PathBuf>,
+
+    /// With --tee, stop writing to FILE as soon as the output is closed
+    #[arg(long, default_value_t = false, requires = "tee")]
+    tee_strict: bool,
+
+    /// Write only the first N bytes of the output, and stop converting once they are written
+    ///
+    /// N counts everything written, like spaces and line breaks. Like `head -c N`.
+    #[arg(long, value_name = "N", value_parser = size_arg::<u64>, conflicts_with_all = ["tail_bytes", "compare", "expect", "expect_file", "output_dir"])]
+    head_bytes: Option<u64>,
+
+    /// Write only the last N bytes of the output, like `tail -c N`
+    ///
+    /// The whole input is still converted, but no more than N bytes of output are held in memory.
+    #[arg(long, value_name = "N", value_parser = size_arg::<usize>, conflicts_with_all = ["compare", "expect", "expect_file", "output_dir"])]
+    tail_bytes: Option<usize>,
+
+    /// Also encode the decoded input to FORMAT in PATH, like `bin:out.bin` or `stats:report.txt`, in the same pass
+    ///
+    /// Repeatable. The input is decoded once, and the bytes are encoded by each target as they come,
+    /// so the input may be a pipe. The files get the encoding alone, without the output options like --space.
+    /// With --stats, the input bytes are the decoded ones.
+    #[arg(
+        long,
+        value_name = "FORMAT:PATH",
+        value_parser = also_arg,
+        conflicts_with_all = [
+            "inputs", "from_any", "check", "dry_run", "reformat", "bit_skip", "bit_length", "channel",
+            "byteswap", "record_size", "paragraph", "comments", "paste_cleanup", "expect_style",
+            "start_pattern", "end_pattern",
+        ]
+    )]
+    also: Vec<(Format, PathBuf)>,
+
+    /// Convert every N input bytes independently, writing one line per record
+    #[arg(long, value_name = "N", value_parser = nonzero_size_arg)]
+    record_size: Option<NonZeroUsize>,
+
+    /// Prefix each record line with its index, like `#0 `
+    #[arg(long, default_value_t = false, requires = "record_size")]
+    record_index: bool,
+
+    /// Convert each input paragraph independently, splitting the input on blank lines
+    ///
+    /// The outputs are written back to back, or as separate records with --frame.
+    #[arg(long, default_value_t = false, conflicts_with = "record_size")]
+    paragraph: bool,
+
+    /// Convert each input line, or each record with --record-size or paragraph with --paragraph, into a framed bytes record
+    ///
+    /// nul: write a NUL byte after each record; len32: write the length of each record as 4 big-endian bytes before it
+    #[arg(long, value_parser = ["nul", "len32"])]
+    frame: Option<String>,
+
+    /// Maximum size of a len32-framed record in bytes
+    #[arg(long, value_name = "N", default_value = "16MiB", value_parser = size_arg::<usize>, requires = "frame")]
+    max_record_size: usize,
+
+    /// Skip this many bits of the decoded input, keeping the rest, see --bit-length
+    #[arg(long, value_name = "N")]
+    bit_skip: Option<u64>,
+
+    /// Keep only this many bits of the decoded input, packed into bytes with the last one padded with zeros
+    #[arg(long, value_name = "N")]
+    bit_length: Option<u64>,
+
+    /// Number the bits of each byte from the least significant one for --bit-skip and --bit-length
+    #[arg(long, default_value_t = false)]
+    lsb_first: bool,
+
+    /// Write or read the bits of each byte of bin output or input from the most or least significant one
+    ///
+    /// Bin written with lsb only decodes back to the same bytes with lsb.
+    #[arg(long, value_name = "ORDER", value_parser = ["msb", "lsb"])]
+    bit_order: Option<String>,
+
+    /// Keep one of several interleaved channels of the decoded input, given as SELECT/CHANNELS[:UNIT]
+    /// like `1/2:2` for the right channel of 16-bit stereo audio. Channels are counted from 0 and UNIT
+    /// bytes long, 1 by default
+    #[arg(long, value_name = "CHANNEL")]
+    channel: Option<String>,
+
+    /// Reverse the order of the bytes in each word of N bytes of the decoded input, to change its endianness
+    ///
+    /// An incomplete last word is left unchanged, with a warning.
+    #[arg(long, value_name = "N", value_parser = ["2", "4", "8"])]
+    byteswap: Option<String>,
+
+    /// Pad incomplete bin or oct input with zeros instead of rejecting it
+    ///
+    /// left: insert zeros before the first digit; right: append zeros after the last digit;
+    /// token: insert zeros before each space-separated token, e.g. to decode --no-pad output
+    #[arg(long, value_parser = ["left", "right", "token"])]
+    pad: Option<String>,
+
+    /// Keep the low 8 bits of octal groups above 377 instead of rejecting them
+    #[arg(long, default_value_t = false)]
+    mask_overflow: bool,
+
+    /// Read or write leb128 values as signed, two's complement numbers
+    #[arg(long, default_value_t = false)]
+    signed: bool,
+
+    /// Write bin or oct output with as few digits per byte as possible, or base64 without padding
+    ///
+    /// Bin or oct output would be ambiguous without separators, so this requires `--space 1`,
+    /// which then separates the digits of each byte. With base64 input, padding is then rejected.
+    #[arg(long, default_value_t = false)]
+    no_pad: bool,
+
+    /// With stats output, write the entropy of every N bytes instead of a full report, one window per line
+    #[arg(long, value_name = "N")]
+    window: Option<NonZeroUsize>,
+
+    /// With byteset output, also write the printable ASCII characters that occur
+    #[arg(long, default_value_t = false)]
+    byteset_ascii: bool,
+
+    /// Label of the blocks written with pem output
+    #[arg(long, value_name = "LABEL", default_value = "DATA")]
+    pem_label: String,
+
+    /// With pem input, decode every block and concatenate them instead of only the first
+    #[arg(long, default_value_t = false)]
+    all_blocks: bool,
+
+    /// With base64 or pem input, fail if anything but whitespace follows the padding or the END line
+    #[arg(long, default_value_t = false)]
+    expect_eof: bool,
+
+    /// With base64 input or output, the 64 characters of the digits in order, instead of A-Za-z0-9+/
+    #[arg(long, value_name = "CHARS")]
+    b64_alphabet: Option<String>,
+
+    /// With utf8, utf16le, utf16be or codepoints input or output, drop a byte order mark at the start of the input
+    #[arg(long, default_value_t = false)]
+    strip_bom: bool,
+
+    /// With utf8, utf16le, utf16be or codepoints input or output, replace invalid text with U+FFFD instead of failing
+    #[arg(long, default_value_t = false)]
+    replace_invalid: bool,
+
+    /// Convert the whole input as one number from base [FROM] to base [TO], instead of byte by byte
+    ///
+    /// Bases are bin, oct, dec or hex. Whitespace and `_` separators are allowed in the input.
+    #[arg(long, default_value_t = false, conflicts_with = "check")]
+    numeric: bool,
+
+    /// With --numeric, keep the leading zeros of the input, padding the output to the width of the input
+    #[arg(long, default_value_t = false, requires = "numeric")]
+    keep_width: bool,
+
+    /// Lay out hex, bin or oct input again with --space and --wrap, without decoding it
+    ///
+    /// [FROM] and [TO] must be the same. Whitespace is dropped, while the case of hex digits is kept
+    /// and the digits need not fill whole bytes.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["numeric", "check", "with_ascii", "bit_skip", "bit_length", "channel", "byteswap"])]
+    reformat: bool,
+
+    /// Print the conversion path and the estimated output size, without converting
+    ///
+    /// The size is only estimated for input files, or stdin redirected from a file on Unix,
+    /// and for output whose length does not depend on the data.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["check", "compare"])]
+    dry_run: bool,
+
+    /// Time every conversion on this machine before choosing the path, preferring fast ones
+    ///
+    /// This is slow, as it runs every conversion once over a sample. The path it chooses can be
+    /// seen with --dry-run.
+    #[arg(long, default_value_t = false)]
+    calibrate: bool,
+
+    /// Refuse conversions that hold the whole input or output in memory, as --dry-run shows
+    ///
+    /// Paths of several conversions hold the output of each one but the last.
+    #[arg(long, default_value_t = false)]
+    require_streaming: bool,
+
+    /// Fail as soon as an input turns out to be larger than N bytes, to bound the memory of conversions
+    #[arg(long, value_name = "N", value_parser = size_arg::<u64>)]
+    max_input_size: Option<u64>,
+
+    /// Read the input from files instead of stdin, converting each one separately
+    ///
+    /// Errors in one file do not stop the others, and a summary is printed at the end.
+    /// More than one file needs --output-dir, unless with --check or --dry-run.
+    #[arg(short, long = "input", value_name = "FILE", num_args = 1..)]
+    inputs: Vec<PathBuf>,
+
+    /// Memory-map the files of -i instead of reading them, which is faster for large files
+    ///
+    /// Files that cannot be mapped, like pipes or empty files, are read as usual.
+    #[cfg(feature = "mmap")]
+    #[arg(long, default_value_t = false, requires = "inputs")]
+    mmap: bool,
+
+    /// Use this string as the input instead of stdin, exactly as given, without a trailing newline
+    #[arg(short, long, value_name = "STRING", conflicts_with_all = ["inputs", "data_hex"])]
+    data: Option<String>,
+
+    /// Use these bytes, written in hex, as the input instead of stdin
+    ///
+    /// For raw bytes that cannot be typed as an argument, like `--data-hex 00ff0a`.
+    #[arg(long, value_name = "HEX", conflicts_with = "inputs")]
+    data_hex: Option<String>,
+
+    /// Accept exactly one connection on ADDR and read the input from it instead of stdin
+    ///
+    /// ADDR is `host:port`, or on unix the path of a socket to create if it contains a `/`, like `./bytary.sock`.
+    #[arg(long, value_name = "ADDR", conflicts_with_all = ["inputs", "data", "data_hex"])]
+    listen: Option<String>,
+
+    /// Connect to ADDR and write the output to it instead of stdout
+    ///
+    /// ADDR is `host:port`, or on unix the path of a socket if it contains a `/`.
+    #[arg(long, value_name = "ADDR", conflicts_with_all = ["output", "output_dir"])]
+    connect: Option<String>,
+
+    /// Fail if a socket of --listen or --connect is not readable or writable for MS milliseconds
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: Option<u64>,
+
+    /// Write the output of each input file to its own file in this directory
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "inputs",
+        conflicts_with = "output"
+    )]
+    output_dir: Option<PathBuf>,
+
+    /// Extension of the files written to --output-dir
+    ///
+    /// Default is the output format name, or `bin` for bytes
+    #[arg(long, value_name = "EXT", requires = "output_dir")]
+    output_ext: Option<String>,
+
+    /// Write this text before the output, supports `\n`, `\t` and `\\` escapes
+    ///
+    /// Nothing is written for empty output, unless --always-delimit is set.
+    #[arg(long, value_name = "STR")]
+    prefix: Option<String>,
+
+    /// Write this text after the output, supports `\n`, `\t` and `\\` escapes
+    #[arg(long, value_name = "STR")]
+    suffix: Option<String>,
+
+    /// Write --prefix and --suffix even if the output is empty
+    #[arg(long, default_value_t = false)]
+    always_delimit: bool,
+
+    /// Terminate the output with a line break, unless it already ends with one
+    ///
+    /// Nothing is written for empty output, unless --eol-always is set.
+    #[arg(long, value_name = "EOL", value_parser = ["none", "lf", "crlf"], default_value = "none")]
+    eol: String,
+
+    /// Write the --eol line break even if the output is empty
+    #[arg(long, default_value_t = false)]
+    eol_always: bool,
+
+    /// Armor base64 output as a PEM block with this label, like `CERTIFICATE`
+    ///
+    /// Lines are wrapped at 64 characters unless --wrap is given.
+    #[arg(long, value_name = "LABEL", conflicts_with_all = ["prefix", "suffix"])]
+    pem: Option<String>,
+
+    /// Write the text output as a string in a JSON object, at this JSON Pointer like `/payload`
+    #[cfg(feature = "json")]
+    #[arg(long, value_name = "POINTER")]
+    json_wrap: Option<String>,
+
+    /// Color the bytes of hex, bin or oct output: NUL dim, printable ASCII green, 0xff red, other high bytes yellow
+    ///
+    /// auto: only when the output is a terminal
+    #[arg(long, value_name = "WHEN", value_parser = ["auto", "always", "never"], default_value = "auto")]
+    color: String,
+
+    /// Group hex output like a MAC address, a UUID, pairs separated by spaces or continuous digits
+    ///
+    /// uuid needs exactly 16 bytes of input.
+    #[arg(long, value_name = "STYLE", value_parser = ["mac", "uuid", "spaced", "continuous"], conflicts_with_all = ["space_interval", "wrap_interval", "wrap_bytes", "no_pad"])]
+    style: Option<String>,
+
+    /// Check that hex input is grouped like --style STYLE
+    #[arg(long, value_name = "STYLE", value_parser = ["mac", "uuid", "spaced", "continuous"])]
+    expect_style: Option<String>,
+
+    /// Apply the patches in this file to bytes output, one `offset: bytes` per line in hex
+    ///
+    /// Like `0x1a2b: de ad be ef`. Patches must be sorted and must not overlap.
+    #[arg(long, value_name = "FILE", conflicts_with = "check")]
+    patch: Option<PathBuf>,
+
+    /// Allow patches past the end of the output, which extend it
+    #[arg(long, default_value_t = false, requires = "patch")]
+    allow_extend: bool,
+
+    /// Write the output to a file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Split the output into files of N bytes, named after -o like `out.000`, `out.001`, ...
+    #[arg(long, value_name = "N", value_parser = size_arg::<NonZeroU64>, requires = "output")]
+    split_size: Option<NonZeroU64>,
+
+    /// Read the input from the files PREFIX.000, PREFIX.001, ... of --split-size, concatenated
+    #[arg(long, value_name = "PREFIX", conflicts_with_all = ["inputs", "data", "data_hex", "listen"])]
+    join: Option<PathBuf>,
+
+    /// Write raw bytes even if the output is a terminal
+    #[arg(short, long, default_value_t = false)]
+    force: bool,
+
+    /// Flush the output at every line break, for following a live input
+    ///
+    /// The output is always flushed when the input pauses,
+    /// but conversions over more than one step only write their output at the end of the input.
+    #[arg(long, default_value_t = false)]
+    line_buffered: bool,
+
+    /// Flush the output at least every MS milliseconds while it is being written
+    #[arg(long, value_name = "MS")]
+    flush_interval: Option<u64>,
+
+    /// Start every line of wrapped text output with the time its first input byte was read
+    ///
+    /// relative: seconds since the start, like `+12.034s`. absolute: UTC date and time, like `2024-05-01T09:30:12.034Z`.
+    /// Meant for following a live input with --line-buffered. The output is not colored.
+    #[arg(long, value_name = "CLOCK", num_args = 0..=1, require_equals = true, default_missing_value = "relative", value_parser = ["relative", "absolute"], conflicts_with_all = ["with_ascii", "style", "record_size", "paragraph"])]
+    timestamps: Option<String>,
+
+    /// Exit with status 141 when the output is closed early, like a process killed by SIGPIPE
+    ///
+    /// By default, a closed output (e.g. `bytary hex | head -c 10`) stops the conversion and exits with status 0.
+    #[arg(long, default_value_t = false)]
+    sigpipe_status: bool,
+
+    /// Put stdin and stdout in binary mode even if neither format is bytes
+    ///
+    /// Only matters on Windows, where they are otherwise in text mode, which translates line breaks and stops at 0x1A.
+    /// Bytes input or output always uses binary mode.
+    #[arg(long, default_value_t = false)]
+    binary_stdio: bool,
+
+    /// Print a one-line summary of each conversion to stderr: path, bytes in and out, time and throughput
+    ///
+    /// Output bytes include separators, line breaks and delimiters. Implied by --verbose.
+    ///
+    /// A conversion of several hops is followed by a line per hop, like `bytes -> bin: 2 B in, 16 B out, 0.001s, 2.0 KiB/s`.
+    #[arg(long, default_value_t = false, conflicts_with = "check")]
+    stats: bool,
+
+    /// Print the conversion summary as a JSON object instead
+    #[cfg(feature = "serde")]
+    #[arg(long, default_value_t = false, conflicts_with = "check")]
+    json_stats: bool,
+
     /// Use verbose output
     #[arg(short, long, default_value_t = false)]
     verbose: bool,
 }
 
-fn bytary_cli(
-    args: BytaryArgs,
-    input: &mut dyn io::Read,
-    output: &mut dyn io::Write,
-) -> BytaryResult<()> {
-    let graph = ConversionGraph::default();
+/// Refuses to write raw bytes to an interactive terminal, which they could garble
+fn check_terminal_output(to: &Format, is_tty: bool, force: bool) -> BytaryResult<()> {
+    if *to == Format::Bytes && is_tty && !force {
+        return BytaryError::TerminalOutput.err();
+    }
+    Ok(())
+}
 
-    if args.list_formats {
-        println!(
-            "Available formats: {}",
-            Format::iter()
-                .filter(|to| graph.can_convert_between(&Format::default(), &to))
-                .map(|f| f.to_string())
-                .collect::<Vec<String>>()
-                .join(", ")
-        );
-        return Ok(());
+/// Remembers whether writing to the target failed because it was closed
+struct PipeWatcher<'a> {
+    target: &'a mut dyn io::Write,
+    broken: bool,
+}
+
+impl<'a> PipeWatcher<'a> {
+    fn new(target: &'a mut dyn io::Write) -> Self {
+        Self {
+            target,
+            broken: false,
+        }
+    }
+
+    fn watch<T>(&mut self, result: io::Result<T>) -> io::Result<T> {
+        if let Err(e) = &result {
+            self.broken |= e.kind() == io::ErrorKind::BrokenPipe;
+        }
+        result
     }
+}
 
-    let to = Format::try_from(args.to.as_str())?;
-    let from = Format::try_from(args.from.as_str())?;
+impl io::Write for PipeWatcher<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.target.write(buf);
+        self.watch(result)
+    }
 
-    let path = graph
-        .find_shortest_path(&from, &to)
-        .ok_or(BytaryError::UnsupportedConversion(from, to))?;
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.target.flush();
+        self.watch(result)
+    }
+}
 
-    let converters = graph.path_to_converters(&path).unwrap();
+/// The input given on the command line with --data or --data-hex, if any
+fn data_arg(args: &BytaryArgs) -> BytaryResult<Option<Vec<u8>>> {
+    if let Some(data) = &args.data {
+        return Ok(Some(data.as_bytes().to_vec()));
+    }
+    let Some(hex) = &args.data_hex else {
+        return Ok(None);
+    };
+    let mut bytes = Vec::new();
+    match hex_to_bytes(&mut hex.as_bytes(), &mut bytes) {
+        Ok(()) => Ok(Some(bytes)),
+        Err(
+            BytaryError::InvalidInputData(message) | BytaryError::InvalidInputAt { message, .. },
+        ) => BytaryError::InvalidArgument(format!("--data-hex: {}", message)).err(),
+        Err(e) => Err(e),
+    }
+}
 
-    if args.verbose {
-        if converters.is_empty() {
-            eprintln!("Operation: Copy data")
-        } else {
-            eprintln!(
-                "Operation: {}",
-                path.iter()
-                    .map(|f| f.to_string())
-                    .collect::<Vec<String>>()
-                    .join(" => ")
-            )
+/// Chooses the input of a run: the --data or --data-hex argument, a connection accepted with --listen,
+/// the files of --join, or else `stdin`.
+///
+/// Input files given with -i are read by [`run_batch`] instead.
+fn resolve_input<'a>(
+    args: &BytaryArgs,
+    stdin: &'a mut dyn io::Read,
+) -> BytaryResult<Box<dyn io::Read + 'a>> {
+    if let Some(addr) = &args.listen {
+        return listen(addr, args.timeout);
+    }
+    if let Some(prefix) = &args.join {
+        return Ok(Box::new(SequenceReader::new(prefix)?));
+    }
+    Ok(match data_arg(args)? {
+        Some(data) => Box::new(io::Cursor::new(data)),
+        None => Box::new(stdin),
+    })
+}
+
+/// A file of -i, memory-mapped with --mmap if possible
+enum InputFile {
+    File(fs::File),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl InputFile {
+    fn open(path: &Path, mmap: bool) -> io::Result<Self> {
+        let file = fs::File::open(path)?;
+        Ok(match mmap {
+            true => InputFile::map(file),
+            false => InputFile::File(file),
+        })
+    }
+
+    /// Maps `file` if it is a regular file that is not empty, or keeps it to be read otherwise
+    #[cfg(feature = "mmap")]
+    fn map(file: fs::File) -> Self {
+        if !file
+            .metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.len() > 0)
+        {
+            return InputFile::File(file);
+        }
+        // SAFETY: the mapping is only read, through the slice of `reader`, which borrows it.
+        // Another process that truncates the file meanwhile makes the reads fail with SIGBUS,
+        // the risk that --mmap accepts for skipping the read syscalls.
+        match unsafe { memmap2::Mmap::map(&file) } {
+            Ok(mapping) => InputFile::Mapped(mapping),
+            Err(_) => InputFile::File(file),
         }
-        eprintln!(
-            "Formatting: space every {} bytes, break line every {} bytes",
-            args.space_interval, args.wrap_interval
-        );
     }
 
-    let converter = match converters.is_empty() {
-        true => ConversionGraph::get_copy_converter(),
-        false => ConversionGraph::compose(converters),
-    };
+    #[cfg(not(feature = "mmap"))]
+    fn map(file: fs::File) -> Self {
+        InputFile::File(file)
+    }
+
+    fn reader(&mut self) -> Box<dyn io::Read + '_> {
+        match self {
+            InputFile::File(file) => Box::new(file),
+            #[cfg(feature = "mmap")]
+            InputFile::Mapped(mapping) => Box::new(io::Cursor::new(&mapping[..])),
+        }
+    }
+}
 
-    let mut writer = FormattedWriter::new(output, args.space_interval, args.wrap_interval);
-    Ok(converter(input, &mut writer)?)
+/// The standard streams that carry raw bytes, and must not be translated
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct BinaryStdio {
+    stdin: bool,
+    stdout: bool,
 }
 
-fn main() {
-    if let Err(e) = bytary_cli(BytaryArgs::parse(), &mut io::stdin(), &mut io::stdout()) {
-        eprintln!("{}", e);
+impl BinaryStdio {
+    /// Binary stdin for bytes input, binary stdout for bytes output, and both with --binary-stdio
+    fn of(args: &BytaryArgs) -> Self {
+        let bytes_in = match args.from_any.is_empty() {
+            true => args.from == Format::Bytes,
+            false => args.from_any.contains(&Format::Bytes),
+        };
+        Self {
+            stdin: args.binary_stdio || bytes_in,
+            stdout: args.binary_stdio || args.to == Format::Bytes,
+        }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use crate::*;
-    use std::io::Cursor;
+/// Puts the standard streams of `modes` in binary mode, calling `set_binary` with their file descriptors
+fn prepare_stdio(
+    modes: BinaryStdio,
+    set_binary: &mut dyn FnMut(i32) -> io::Result<()>,
+) -> io::Result<()> {
+    if modes.stdin {
+        set_binary(0)?;
+    }
+    if modes.stdout {
+        set_binary(1)?;
+    }
+    Ok(())
+}
 
-    #[test]
-    fn test_cli_bytes_to_hex() {
-        let mut output = Vec::new();
-        bytary_cli(
-            BytaryArgs {
-                list_formats: false,
-                to: "hex".to_string(),
-                from: "bytes".to_string(),
-                space_interval: 0,
-                wrap_interval: 0,
-                verbose: true,
-            },
-            &mut Cursor::new(vec![0x1b, 0x34, 0x8f, 0xff, 0x00, 0x0e]),
-            &mut output,
-        )
-        .unwrap();
-        assert_eq!(output, b"1b348fff000e");
+/// Puts a file descriptor of the C runtime in binary mode
+#[cfg(windows)]
+fn set_binary_mode(fd: i32) -> io::Result<()> {
+    const O_BINARY: i32 = 0x8000;
+    unsafe extern "C" {
+        fn _setmode(fd: i32, mode: i32) -> i32;
+    }
+    // SAFETY: _setmode only changes the translation mode of the descriptor, and fails on invalid ones
+    match unsafe { _setmode(fd, O_BINARY) } {
+        -1 => Err(io::Error::other(format!(
+            "can not put file descriptor {} in binary mode",
+            fd
+        ))),
+        _ => Ok(()),
     }
+}
 
-    #[test]
-    fn test_bytes_to_bytes() {
-        let mut output = Vec::new();
-        let data = [0x1b, 0x34, 0x8f, 0xff, 0x00, 0x0e];
-        bytary_cli(
-            BytaryArgs {
-                list_formats: false,
-                to: "bytes".to_string(),
-                from: "bytes".to_string(),
-                space_interval: 0,
-                wrap_interval: 0,
-                verbose: true,
-            },
-            &mut Cursor::new(&data),
-            &mut output,
-        )
-        .unwrap();
-        assert_eq!(output, &data);
+/// Other platforms do not translate the standard streams
+#[cfg(not(windows))]
+fn set_binary_mode(_fd: i32) -> io::Result<()> {
+    Ok(())
+}
+
+/// Chooses the output of a run: the file of -o, or the files starting with its name with --split-size,
+/// a connection made with --connect, or `None` for stdout
+fn resolve_output(args: &BytaryArgs) -> BytaryResult<Option<Box<dyn io::Write>>> {
+    Ok(match (&args.output, &args.connect) {
+        (Some(path), _) => match args.split_size {
+            Some(size) => Some(Box::new(SplittingWriter::new(path, size.get())?)),
+            None => Some(Box::new(fs::File::create(path)?)),
+        },
+        (None, Some(addr)) => Some(connect(addr, args.timeout)?),
+        (None, None) => None,
+    })
+}
+
+/// Whether ADDR of --listen or --connect is the path of a unix socket rather than `host:port`
+fn is_socket_path(addr: &str) -> bool {
+    cfg!(unix) && addr.contains('/')
+}
+
+/// Accepts exactly one connection on `addr`, for --listen
+///
+/// A unix socket file is removed once the connection is accepted.
+fn listen(addr: &str, timeout: Option<u64>) -> BytaryResult<Box<dyn io::Read>> {
+    let duration = timeout.map(Duration::from_millis);
+    #[cfg(unix)]
+    if is_socket_path(addr) {
+        let listener = UnixListener::bind(addr)?;
+        let accepted = listener.accept();
+        fs::remove_file(addr)?;
+        let (stream, _) = accepted?;
+        stream.set_read_timeout(duration)?;
+        return Ok(Box::new(Socket { stream, timeout }));
+    }
+    let (stream, _) = TcpListener::bind(addr)?.accept()?;
+    stream.set_read_timeout(duration)?;
+    Ok(Box::new(Socket { stream, timeout }))
+}
+
+/// Connects to `addr`, for --connect
+fn connect(addr: &str, timeout: Option<u64>) -> BytaryResult<Box<dyn io::Write>> {
+    let duration = timeout.map(Duration::from_millis);
+    #[cfg(unix)]
+    if is_socket_path(addr) {
+        let stream = UnixStream::connect(addr)?;
+        stream.set_write_timeout(duration)?;
+        return Ok(Box::new(Socket { stream, timeout }));
+    }
+    let stream = TcpStream::connect(addr)?;
+    stream.set_write_timeout(duration)?;
+    Ok(Box::new(Socket { stream, timeout }))
+}
+
+/// A socket of --listen or --connect, whose timeouts fail with an error naming --timeout
+struct Socket<S> {
+    stream: S,
+    timeout: Option<u64>,
+}
+
+impl<S> Socket<S> {
+    /// Replaces the error of an expired timeout, which is [`io::ErrorKind::WouldBlock`] on some platforms
+    /// and [`io::ErrorKind::TimedOut`] on others
+    fn check<T>(&self, result: io::Result<T>) -> io::Result<T> {
+        result.map_err(|e| match (e.kind(), self.timeout) {
+            (io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut, Some(ms)) => io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("socket timed out after {} ms (--timeout)", ms),
+            ),
+            _ => e,
+        })
+    }
+}
+
+impl<S: io::Read> io::Read for Socket<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.stream.read(buf);
+        self.check(result)
+    }
+}
+
+impl<S: io::Write> io::Write for Socket<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.stream.write(buf);
+        self.check(result)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.stream.flush();
+        self.check(result)
+    }
+}
+
+/// Runs the CLI, treating a closed output as an early successful exit
+fn bytary_cli(
+    args: BytaryArgs,
+    stdin: &mut dyn io::Read,
+    output: &mut dyn io::Write,
+    output_is_tty: bool,
+) -> BytaryResult<ExitCode> {
+    let sigpipe_status = args.sigpipe_status;
+    let mut input = resolve_input(&args, stdin)?;
+    let input = input.as_mut();
+    let mut output = PipeWatcher::new(output);
+
+    let result = {
+        let mut writer: Box<dyn io::Write + '_> = Box::new(&mut output);
+        if let Some(path) = &args.tee {
+            let file = fs::File::create(path)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+            let tee = TeeWriter::new(
+                writer,
+                "output",
+                io::BufWriter::new(file),
+                path.display().to_string(),
+            );
+            writer = Box::new(tee.lenient(!args.tee_strict));
+        }
+        if args.line_buffered {
+            writer = Box::new(io::LineWriter::new(writer));
+        }
+        if let Some(ms) = args.flush_interval {
+            writer = Box::new(IntervalFlushWriter::new(writer, Duration::from_millis(ms)));
+        }
+        if let Some(limit) = args.head_bytes {
+            writer = Box::new(HeadWriter::new(writer, limit));
+        }
+
+        match (&args.compare, &args.expect, &args.expect_file) {
+            (Some(path), _, _) => compare(&args, path, input, &mut writer),
+            (None, Some(value), _) => {
+                let expected = value.as_bytes().to_vec();
+                expect(args, expected, input, &mut writer)
+            }
+            (None, None, Some(path)) => match fs::read(path) {
+                Ok(expected) => expect(args, expected, input, &mut writer),
+                Err(e) => Err(e.into()),
+            },
+            (None, None, None) => match args.tail_bytes {
+                // The last bytes go through the other writers once the conversion ends
+                Some(size) => {
+                    let mut tail = TailWriter::new(&mut writer, size);
+                    run(args, input, &mut tail, output_is_tty).and_then(|_| Ok(tail.finish()?))
+                }
+                None => run(args, input, &mut writer, output_is_tty),
+            }
+            .map(|_| ExitCode::SUCCESS),
+        }
+        // Reaching --head-bytes stops the conversion early, which is a success
+        .or_else(|e| match e {
+            BytaryError::OutputLimitReached => Ok(ExitCode::SUCCESS),
+            e => Err(e),
+        })
+        .and_then(|code| Ok(writer.flush().map(|_| code)?))
+    };
+    match result {
+        Err(e) if e.is_broken_pipe() && output.broken => match sigpipe_status {
+            true => Ok(ExitCode::from(141)),
+            false => Ok(ExitCode::SUCCESS),
+        },
+        result => result,
+    }
+}
+
+/// Reports the ranges where `input` differs from the file at `path`, exiting with 1 if there are any
+fn compare(
+    args: &BytaryArgs,
+    path: &Path,
+    input: &mut dyn io::Read,
+    output: &mut dyn io::Write,
+) -> BytaryResult<ExitCode> {
+    let mut old = io::BufReader::new(fs::File::open(path)?);
+    let summary = diff_streams(&mut old, input, output, &DiffOptions::default())?;
+
+    if args.verbose {
+        eprintln!(
+            "{} differ, {} in total, old length {}, new length {}",
+            plural(summary.ranges, "range"),
+            plural(summary.bytes_differ, "byte"),
+            summary.old_length,
+            summary.new_length
+        );
+    }
+    match summary.is_identical() {
+        true => Ok(ExitCode::SUCCESS),
+        false => Ok(ExitCode::from(1)),
+    }
+}
+
+/// Converts `input` without writing it, only whether it is `expected`, exiting with 1 if it is not
+fn expect(
+    args: BytaryArgs,
+    expected: Vec<u8>,
+    input: &mut dyn io::Read,
+    output: &mut dyn io::Write,
+) -> BytaryResult<ExitCode> {
+    let mut writer = ExpectWriter::new(expected);
+    run(args, input, &mut writer, false)?;
+
+    match writer.matches() {
+        true => {
+            writeln!(output, "match, {}", plural(writer.count(), "byte"))?;
+            Ok(ExitCode::SUCCESS)
+        }
+        false => {
+            writeln!(
+                output,
+                "mismatch, expected {}, got {}",
+                plural(writer.expected_len(), "byte"),
+                writer.count()
+            )?;
+            Ok(ExitCode::from(1))
+        }
+    }
+}
+
+fn run(
+    args: BytaryArgs,
+    input: &mut dyn io::Read,
+    output: &mut dyn io::Write,
+    output_is_tty: bool,
+) -> BytaryResult<()> {
+    let padding = match args.pad.as_deref() {
+        Some("left") => Padding::Left,
+        Some("right") => Padding::Right,
+        Some("token") => Padding::Token,
+        _ => Padding::Strict,
+    };
+    let mut graph = ConversionGraph::with_padding(padding);
+
+    if let Some(shell) = args.completions {
+        clap_complete::generate(shell, &mut BytaryArgs::command(), "bytary", output);
+        return Ok(());
+    }
+
+    if args.build_info {
+        writeln!(output, "{}", bytary::build_info())?;
+        return Ok(());
+    }
+
+    if args.list_formats {
+        #[cfg(feature = "serde")]
+        if args.json {
+            serde_json::to_writer(&mut *output, &graph.capabilities()).map_err(io::Error::from)?;
+            writeln!(output)?;
+            return Ok(());
+        }
+        writeln!(
+            output,
+            "Available formats: {}",
+            FORMATS
+                .iter()
+                .filter(|spec| graph.can_convert_between(&Format::default(), &spec.format))
+                .map(|spec| spec.name)
+                .collect::<Vec<&str>>()
+                .join(", ")
+        )?;
+        return Ok(());
+    }
+
+    if args.dump_graph {
+        output.write_all(graph.to_dot().as_bytes())?;
+        return Ok(());
+    }
+
+    if args.self_test {
+        let report = selftest::run(&graph);
+        for pair in &report.pairs {
+            writeln!(output, "{}", pair)?;
+        }
+        let failed = report.failed().count();
+        return match failed {
+            0 => Ok(()),
+            _ => BytaryError::SelfTestFailed(failed, report.pairs.len()).err(),
+        };
+    }
+
+    let to = args.to.clone();
+    // With --from-any, the input is decoded to bytes before anything else
+    let from = match args.from_any.is_empty() {
+        true => args.from.clone(),
+        false => Format::Bytes,
+    };
+
+    let bit_order = match args.bit_order.as_deref() {
+        Some("lsb") => BitOrder::LsbFirst,
+        _ => BitOrder::MsbFirst,
+    };
+    if bit_order == BitOrder::LsbFirst {
+        if from != Format::Bin && to != Format::Bin {
+            return BytaryError::InvalidArgument(format!(
+                "--bit-order only applies to bin input or output, not {} to {}",
+                from, to
+            ))
+            .err();
+        }
+        graph.add_converter(
+            Format::Bytes,
+            Format::Bin,
+            EdgeInfo {
+                name: "bytes_to_bin",
+                lossy: false,
+                cost: 1,
+            },
+            BytesToBin {
+                options: BinOptions {
+                    bit_order,
+                    ..BinOptions::default()
+                },
+            },
+        )?;
+        graph.add_converter(
+            Format::Bin,
+            Format::Hex,
+            EdgeInfo {
+                name: "bin_to_hex",
+                lossy: false,
+                cost: 1,
+            },
+            BinToHex { padding, bit_order },
+        )?;
+    }
+
+    let mut space_interval = args.space_interval;
+    if args.no_pad && from != Format::Base64 && to != Format::Base64 {
+        if space_interval != 1 {
+            return BytaryError::InvalidArgument(
+                "--no-pad requires --space 1, otherwise the output is ambiguous".to_string(),
+            )
+            .err();
+        }
+        let info = |name| EdgeInfo {
+            name,
+            lossy: false,
+            cost: 1,
+        };
+        match to {
+            Format::Oct => graph.add_converter(
+                Format::Bytes,
+                Format::Oct,
+                info("bytes_to_oct"),
+                BytesToOct {
+                    options: OctOptions { pad: false },
+                },
+            )?,
+            Format::Bin => graph.add_converter(
+                Format::Bytes,
+                Format::Bin,
+                info("bytes_to_bin"),
+                BytesToBin {
+                    options: BinOptions {
+                        pad: false,
+                        bit_order,
+                    },
+                },
+            )?,
+            _ => {
+                return BytaryError::InvalidArgument(format!(
+                    "--no-pad only applies to bin, oct or base64 output, or base64 input, not {}",
+                    to
+                ))
+                .err();
+            }
+        }
+        // The converter separates the bytes itself
+        space_interval = 0;
+    }
+    if !args.allow_odd_formatting && args.style.is_none() {
+        let wrap_interval = match args.wrap_bytes {
+            // Its errors are reported with the conversion
+            Some(bytes) => wrap_bytes_interval(&to, bytes.get()).unwrap_or(0),
+            None => args.wrap_interval,
+        };
+        check_layout(space_interval, wrap_interval)?;
+    }
+    if args.mask_overflow {
+        if from != Format::Oct {
+            return BytaryError::InvalidArgument(format!(
+                "--mask-overflow only applies to oct input, not {}",
+                from
+            ))
+            .err();
+        }
+        graph.add_converter(
+            Format::Oct,
+            Format::Bytes,
+            EdgeInfo {
+                name: "oct_to_bytes",
+                lossy: false,
+                cost: 1,
+            },
+            OctToBytes {
+                padding,
+                mask: true,
+            },
+        )?;
+    }
+    if args.signed {
+        if from != Format::Leb128 && to != Format::Leb128 {
+            return BytaryError::InvalidArgument(format!(
+                "--signed only applies to leb128 input or output, not {} to {}",
+                from, to
+            ))
+            .err();
+        }
+        graph.add_leb128_edges(Leb128Options { signed: true });
+    }
+    if args.byteset_ascii {
+        if to != Format::ByteSet {
+            return BytaryError::InvalidArgument(format!(
+                "--byteset-ascii only applies to byteset output, not {}",
+                to
+            ))
+            .err();
+        }
+        graph.add_converter(
+            Format::Bytes,
+            Format::ByteSet,
+            EdgeInfo {
+                name: "bytes_to_byteset",
+                lossy: true,
+                cost: 1,
+            },
+            BytesToByteSet {
+                options: ByteSetOptions { ascii: true },
+            },
+        )?;
+    }
+    if let Some(window) = args.window {
+        if to != Format::Stats {
+            return BytaryError::InvalidArgument(format!(
+                "--window only applies to stats output, not {}",
+                to
+            ))
+            .err();
+        }
+        graph.add_converter(
+            Format::Bytes,
+            Format::Stats,
+            EdgeInfo {
+                name: "bytes_to_stats",
+                lossy: true,
+                cost: 1,
+            },
+            BytesToStats {
+                options: StatsOptions {
+                    window: Some(window.get()),
+                },
+            },
+        )?;
+    }
+
+    let alphabet = base64_alphabet(&args)?;
+    if alphabet != Base64Alphabet::default() {
+        if from != Format::Base64 && to != Format::Base64 {
+            return BytaryError::InvalidArgument(format!(
+                "--b64-alphabet only applies to base64 input or output, not {} to {}",
+                from, to
+            ))
+            .err();
+        }
+        graph.add_converter(
+            Format::Bytes,
+            Format::Base64,
+            EdgeInfo {
+                name: "bytes_to_base64",
+                lossy: false,
+                cost: 1,
+            },
+            BytesToBase64 { alphabet },
+        )?;
+    }
+    if args.expect_eof && from != Format::Base64 && from != Format::Pem {
+        return BytaryError::InvalidArgument(format!(
+            "--expect-eof only applies to base64 or pem input, not {}",
+            from
+        ))
+        .err();
+    }
+    if args.expect_eof || alphabet != Base64Alphabet::default() {
+        graph.add_converter(
+            Format::Base64,
+            Format::Bytes,
+            EdgeInfo {
+                name: "base64_to_bytes",
+                lossy: false,
+                cost: 1,
+            },
+            Base64ToBytes {
+                options: Base64Options {
+                    expect_eof: args.expect_eof,
+                    alphabet,
+                },
+            },
+        )?;
+    }
+    if args.all_blocks || args.expect_eof {
+        if args.all_blocks && from != Format::Pem {
+            return BytaryError::InvalidArgument(format!(
+                "--all-blocks only applies to pem input, not {}",
+                from
+            ))
+            .err();
+        }
+        graph.add_converter(
+            Format::Pem,
+            Format::Bytes,
+            EdgeInfo {
+                name: "pem_to_bytes",
+                lossy: false,
+                cost: 1,
+            },
+            PemToBytes {
+                options: PemOptions {
+                    all_blocks: args.all_blocks,
+                    expect_eof: args.expect_eof,
+                    ..PemOptions::default()
+                },
+            },
+        )?;
+    }
+    if to == Format::Pem {
+        graph.add_converter(
+            Format::Bytes,
+            Format::Pem,
+            EdgeInfo {
+                name: "bytes_to_pem",
+                lossy: false,
+                cost: 1,
+            },
+            BytesToPem {
+                options: PemOptions {
+                    label: args.pem_label.clone(),
+                    ..PemOptions::default()
+                },
+            },
+        )?;
+    }
+
+    if args.strip_bom || args.replace_invalid {
+        if !from.is_text() && !to.is_text() {
+            let flag = match args.strip_bom {
+                true => "--strip-bom",
+                false => "--replace-invalid",
+            };
+            return BytaryError::InvalidArgument(format!(
+                "{} only applies to utf8, utf16le, utf16be or codepoints input or output, not {} to {}",
+                flag, from, to
+            ))
+            .err();
+        }
+        graph.add_text_edges(TextOptions {
+            replace_invalid: args.replace_invalid,
+            strip_bom: args.strip_bom,
+        });
+    }
+
+    if args.numeric {
+        if from == to {
+            return BytaryError::InvalidArgument(format!(
+                "--numeric needs two different bases, not {} to {}",
+                from, to
+            ))
+            .err();
+        }
+        let converter = numeric_converter(
+            &from,
+            &to,
+            NumericOptions {
+                keep_width: args.keep_width,
+            },
+        )
+        .map_err(|_| {
+            BytaryError::InvalidArgument(format!(
+                "--numeric only supports bin, oct, dec or hex, not {} to {}",
+                from, to
+            ))
+        })?;
+        graph.add_converter(
+            from.clone(),
+            to.clone(),
+            EdgeInfo {
+                name: "numeric",
+                lossy: false,
+                cost: 1,
+            },
+            converter,
+        )?;
+    }
+
+    if args.reformat && (from != to || reformat_alphabet(&from).is_none()) {
+        return BytaryError::InvalidArgument(format!(
+            "--reformat only applies from hex, hexswap, bin or oct to the same format, not {} to {}",
+            from, to
+        ))
+        .err();
+    }
+
+    if args.calibrate {
+        let sample = CALIBRATION_SAMPLE.repeat(CALIBRATION_SAMPLE_SIZE / CALIBRATION_SAMPLE.len());
+        graph.calibrate(&sample);
+    }
+
+    if args.require_streaming {
+        let class = graph
+            .plan(&from, &to)
+            .map_or(StreamingClass::BuffersInput, |plan| plan.streaming_class());
+        if class != StreamingClass::Streaming {
+            return BytaryError::InvalidArgument(format!(
+                "--require-streaming refuses the conversion from {} to {}, which {}",
+                from, to, class
+            ))
+            .err();
+        }
+    }
+
+    for (format, _) in &args.also {
+        graph.try_get_converter(&Format::Bytes, format)?;
+    }
+
+    if args.dry_run {
+        return dry_run(&args, &graph, &from, &to, space_interval, output);
+    }
+
+    if args.inputs.len() > 1 && args.output_dir.is_none() && !args.check {
+        return BytaryError::InvalidArgument(
+            "the outputs of several -i files cannot be told apart on one stream, use --output-dir"
+                .to_string(),
+        )
+        .err();
+    }
+
+    let process = match args.check {
+        true => check_process(&args, &from, padding),
+        false => {
+            let color = match args.color.as_str() {
+                "always" => true,
+                "never" => false,
+                _ => output_is_tty && args.output_dir.is_none(),
+            };
+            // With --also, the output is encoded from the bytes decoded by convert_also
+            let source = match args.also.is_empty() {
+                true => &from,
+                false => &Format::Bytes,
+            };
+            convert_process(&args, &graph, source, &to, space_interval, color)?
+        }
+    };
+
+    let process = match args.expect_style.as_deref() {
+        Some(style) => {
+            if from != Format::Hex {
+                return BytaryError::InvalidArgument(format!(
+                    "--expect-style only applies to hex input, not {}",
+                    from
+                ))
+                .err();
+            }
+            let style = HexStyle::try_from(style)?;
+            Rc::new(
+                move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+                    let mut reader = GroupCheckingReader::new(input, style.schedule());
+                    let result = process.convert(&mut reader, output);
+                    match reader.mismatch() {
+                        Some(mismatch) => BytaryError::InvalidInputData(format!(
+                            "input does not match the {} style: {}",
+                            style, mismatch
+                        ))
+                        .err(),
+                        None => result,
+                    }
+                },
+            )
+        }
+        None => process,
+    };
+
+    let process = match args.comments {
+        true => {
+            if !matches!(from, Format::Hex | Format::Bin | Format::Oct) {
+                return BytaryError::InvalidArgument(format!(
+                    "--comments only applies to hex, bin or oct input, not {}",
+                    from
+                ))
+                .err();
+            }
+            Rc::new(
+                move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+                    // The offsets of the errors are those of the input without its comments
+                    process
+                        .convert(&mut CommentStrippingReader::new(input), output)
+                        .map_err(BytaryError::without_offset)
+                },
+            )
+        }
+        false => process,
+    };
+
+    let process = match args.paste_cleanup {
+        true => {
+            if from == Format::Bytes {
+                return BytaryError::InvalidArgument(
+                    "--paste-cleanup only applies to text input, not bytes".to_string(),
+                )
+                .err();
+            }
+            let (fold_fullwidth, skip_non_ascii) = (args.fold_fullwidth, args.skip_non_ascii);
+            Rc::new(
+                move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+                    process
+                        .convert(
+                            &mut cleanup_pasted_text(input)
+                                .fullwidth(fold_fullwidth)
+                                .skip_non_ascii(skip_non_ascii),
+                            output,
+                        )
+                        .map_err(BytaryError::without_offset)
+                },
+            )
+        }
+        false => process,
+    };
+
+    let process = match (&args.start_pattern, &args.end_pattern) {
+        (None, None) => process,
+        (start, end) => {
+            if from != Format::Bytes {
+                return BytaryError::InvalidArgument(format!(
+                    "--start-pattern and --end-pattern require bytes input, not {}",
+                    from
+                ))
+                .err();
+            }
+            let window = PatternWindow {
+                start: parse_pattern(start.as_deref().unwrap_or_default())?,
+                end: parse_pattern(end.as_deref().unwrap_or_default())?,
+                include_start: !args.exclude_start,
+                include_end: !args.exclude_end,
+                nth: args.nth.get(),
+            };
+            let require = args.require_patterns;
+            Rc::new(
+                move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+                    let mut reader = PatternWindowReader::new(input, window.clone());
+                    process.convert(&mut reader, output)?;
+
+                    let missing = match (reader.found_start(), reader.found_end()) {
+                        (false, _) => "start",
+                        (true, false) if !window.end.is_empty() => "end",
+                        _ => return Ok(()),
+                    };
+                    // Otherwise the reader reported it as a warning
+                    match require {
+                        true => {
+                            BytaryError::InvalidInputData(format!("{} pattern not found", missing))
+                                .err()
+                        }
+                        false => Ok(()),
+                    }
+                },
+            )
+        }
+    };
+
+    #[cfg(feature = "json")]
+    let process = match args.json_field.clone() {
+        Some(pointer) => {
+            json::parse_pointer(&pointer)?;
+            Rc::new(
+                move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+                    let document = read_limited(input, JSON_FIELD_LIMIT, "--json-field")?;
+                    let text = json::extract_string(&document, &pointer)?;
+                    process
+                        .convert(&mut text.as_bytes(), output)
+                        .map_err(BytaryError::without_offset)
+                },
+            )
+        }
+        None => process,
+    };
+
+    #[cfg(feature = "json")]
+    let process = match args.json_wrap.clone() {
+        Some(pointer) => {
+            if to == Format::Bytes {
+                return BytaryError::InvalidArgument(
+                    "--json-wrap requires text output, not bytes".to_string(),
+                )
+                .err();
+            }
+            json::parse_pointer(&pointer)?;
+            Rc::new(
+                move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+                    let mut text = Vec::new();
+                    process.convert(input, &mut text)?;
+                    let text = String::from_utf8(text).map_err(|_| {
+                        BytaryError::InvalidInputData(
+                            "--json-wrap requires UTF-8 output".to_string(),
+                        )
+                    })?;
+                    output.write_all(json::wrap_string(&text, &pointer)?.as_bytes())?;
+                    Ok(())
+                },
+            )
+        }
+        None => process,
+    };
+
+    // The outermost wrapper, so that the limit applies to the input as read.
+    // With --also, convert_also applies it before decoding.
+    let process = match args.max_input_size {
+        Some(limit) if args.also.is_empty() => Rc::new(
+            move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+                process.convert(&mut LimitedReader::new(input, limit), output)
+            },
+        ),
+        _ => process,
+    };
+
+    if !args.check && args.output_dir.is_none() {
+        check_terminal_output(&to, output_is_tty, args.force)?;
+    }
+
+    let mut decoded;
+    let input = match args.from_any.is_empty() {
+        true => input,
+        false => {
+            decoded = io::Cursor::new(decode_any_input(&args, &graph, input)?);
+            &mut decoded as &mut dyn io::Read
+        }
+    };
+
+    let (result, warnings) = warning::collect(|| match args.inputs.is_empty() {
+        true if !args.also.is_empty() => {
+            let mut limited;
+            let input = match args.max_input_size {
+                Some(limit) => {
+                    limited = LimitedReader::new(input, limit);
+                    &mut limited as &mut dyn io::Read
+                }
+                None => input,
+            };
+            convert_also(&graph, &from, &args.also, process.as_ref(), input, output)
+        }
+        true => process.convert(input, output),
+        false => run_batch(&args, &to, process.as_ref(), output),
+    });
+    result?;
+
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    match args.warnings_as_errors && !warnings.is_empty() {
+        true => BytaryError::WarningsAsErrors(warnings.len()).err(),
+        false => Ok(()),
+    }
+}
+
+/// Text that --calibrate repeats to time each conversion, valid in every text format
+const CALIBRATION_SAMPLE: &[u8] = b"The quick brown fox jumps over the lazy dog. 0123456789\n";
+
+/// Approximate size of the sample of --calibrate
+const CALIBRATION_SAMPLE_SIZE: usize = 64 << 10;
+
+/// Maximum size of the input of --from-any, which is read into memory to try each format
+const FROM_ANY_LIMIT: u64 = 64 << 20;
+
+/// Maximum size of the input of --json-field, which is parsed in memory
+#[cfg(feature = "json")]
+const JSON_FIELD_LIMIT: u64 = 64 << 20;
+
+/// Reads the whole input into memory for `option`, failing if it is larger than `limit`
+fn read_limited(input: &mut dyn io::Read, limit: u64, option: &str) -> BytaryResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+    input.take(limit + 1).read_to_end(&mut buffer)?;
+    if buffer.len() as u64 > limit {
+        return BytaryError::InvalidInputData(format!(
+            "{} reads the input into memory, which is larger than the maximum of {}",
+            option,
+            format_size(limit)
+        ))
+        .err();
+    }
+    Ok(buffer)
+}
+
+/// Decodes the whole input with the first format of --from-any that succeeds
+fn decode_any_input(
+    args: &BytaryArgs,
+    graph: &ConversionGraph,
+    input: &mut dyn io::Read,
+) -> BytaryResult<Vec<u8>> {
+    let buffer = read_limited(input, FROM_ANY_LIMIT, "--from-any")?;
+    let (format, bytes) = decode_any(&args.from_any, &buffer, graph)?;
+    if args.verbose {
+        eprintln!("Input format: {}", format);
+    }
+    Ok(bytes)
+}
+
+/// Prints the conversion path and the estimated output size of each input
+/// Decodes `input` from `from` once, for `primary` to encode to `output` and each target of --also to its file.
+///
+/// An error of a target, including creating its file, is [`BytaryError::BranchFailed`] named like `bin:out.bin`.
+fn convert_also(
+    graph: &ConversionGraph,
+    from: &Format,
+    also: &[(Format, PathBuf)],
+    primary: &dyn Converter,
+    input: &mut dyn io::Read,
+    output: &mut dyn io::Write,
+) -> BytaryResult<()> {
+    let mut branches = BroadcastWriter::new();
+    for (format, path) in also {
+        let name = format!("{}:{}", format, path.display());
+        let file = fs::File::create(path)
+            .map_err(|e| BytaryError::BranchFailed(name.clone(), Box::new(e.into())))?;
+        branches.push(
+            name,
+            graph.chunk_writer(&Format::Bytes, format, io::BufWriter::new(file))?,
+        );
+    }
+
+    let decoded = graph.chunk_reader(from, &Format::Bytes, input)?;
+    primary.convert(&mut TeeReader::new(decoded, &mut branches), output)?;
+    for (name, branch) in branches.into_inner() {
+        branch
+            .finish()
+            .map_err(|e| BytaryError::BranchFailed(name, Box::new(e)))?;
+    }
+    Ok(())
+}
+
+fn dry_run(
+    args: &BytaryArgs,
+    graph: &ConversionGraph,
+    from: &Format,
+    to: &Format,
+    space_interval: usize,
+    output: &mut dyn io::Write,
+) -> BytaryResult<()> {
+    let plan = graph
+        .plan(from, to)
+        .ok_or(BytaryError::UnsupportedConversion(from.clone(), to.clone()))?;
+    let wrap_interval = match args.wrap_bytes {
+        Some(bytes) => wrap_bytes_interval(to, bytes.get())?,
+        None => args.wrap_interval,
+    };
+    writeln!(output, "Path: {}", plan)?;
+    writeln!(output, "Memory: {}", plan.streaming_class())?;
+
+    let estimate = |input_len: Option<u64>| match input_len
+        .and_then(|length| output_size_hint(args, &plan, length, space_interval, wrap_interval))
+    {
+        Some(size) => plural(size, "byte"),
+        None => "unknown".to_string(),
+    };
+    if args.inputs.is_empty() {
+        #[cfg(unix)]
+        let stdin_len = || file_size(Path::new("/dev/stdin"));
+        #[cfg(not(unix))]
+        let stdin_len = || None;
+        let input_len = match data_arg(args)? {
+            Some(data) => Some(data.len() as u64),
+            None => stdin_len(),
+        };
+        writeln!(output, "Output size: {}", estimate(input_len))?;
+    }
+    for path in &args.inputs {
+        writeln!(
+            output,
+            "Output size of {}: {}",
+            path.display(),
+            estimate(file_size(path))
+        )?;
+    }
+    Ok(())
+}
+
+/// The length of the file at `path`, `None` if it is not a regular file
+fn file_size(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+}
+
+/// Number of spaces that [`FormattedWriter`] writes in `size` bytes: one between every two groups of
+/// a line, none at the end of a line or of the output
+fn spaces(size: u64, space_interval: usize, wrap_interval: usize) -> u64 {
+    let (space, wrap) = (space_interval as u64, wrap_interval as u64);
+    if space == 0 || size == 0 {
+        return 0;
+    }
+    let boundaries = |interval: u64| (size - 1) / interval;
+    match wrap {
+        0 => boundaries(space),
+        // Where a line breaks on a group boundary, only the line break is written
+        _ => boundaries(space) - boundaries(space / gcd(space, wrap) * wrap),
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+/// The length of the output for `input_len` bytes of input, with spaces, line breaks and delimiters.
+///
+/// `None` if options make it depend on the data.
+fn output_size_hint(
+    args: &BytaryArgs,
+    plan: &ConversionPlan,
+    input_len: u64,
+    space_interval: usize,
+    wrap_interval: usize,
+) -> Option<u64> {
+    let data_dependent = args.no_pad
+        || args.color == "always"
+        || args.comments
+        || args.paste_cleanup
+        || args.start_pattern.is_some()
+        || args.end_pattern.is_some()
+        || args.record_size.is_some()
+        || args.paragraph
+        || args.bit_skip.is_some()
+        || args.bit_length.is_some()
+        || args.channel.is_some()
+        || args.frame.is_some()
+        || args.patch.is_some()
+        || args.pem.is_some()
+        || args.with_ascii
+        || args.timestamps.is_some()
+        // UTF-8 text is laid out in characters, whose number depends on the data
+        || (plan.path.last().is_some_and(|to| to.is_text() && to.is_utf8())
+            && (space_interval, wrap_interval) != (0, 0));
+    if data_dependent {
+        return None;
+    }
+
+    let size = plan.output_size_hint(input_len)?;
+    let breaks = |interval: usize| match interval {
+        0 => 0,
+        interval => size / interval as u64,
+    };
+    let formatted = match args.style.as_deref() {
+        Some(style) => {
+            let schedule = HexStyle::try_from(style).ok()?.schedule();
+            if schedule.total().is_some_and(|total| total as u64 != size) {
+                return None;
+            }
+            size + schedule.separators(size) * schedule.separator.len() as u64
+        }
+        None => size + spaces(size, space_interval, wrap_interval) + breaks(wrap_interval),
+    };
+    let prefix = unescape(args.prefix.as_deref().unwrap_or_default());
+    let suffix = unescape(args.suffix.as_deref().unwrap_or_default());
+    let delimited = size > 0 || args.always_delimit;
+    let payload = match delimited {
+        true => formatted + (prefix.len() + suffix.len()) as u64,
+        false => formatted,
+    };
+
+    // Whether the payload ends with a line break, which --eol does not double
+    let ends_with_line_break = match (delimited, suffix.is_empty()) {
+        (true, false) => suffix.ends_with('\n'),
+        _ if size > 0 => {
+            args.style.is_none() && wrap_interval > 0 && size.is_multiple_of(wrap_interval as u64)
+        }
+        _ => delimited && prefix.ends_with('\n'),
+    };
+    let eol = match payload {
+        0 if args.eol_always => eol_bytes(args).len(),
+        0 => 0,
+        _ if ends_with_line_break => 0,
+        _ => eol_bytes(args).len(),
+    };
+    Some(payload + eol as u64)
+}
+
+/// The line break of --eol
+fn eol_bytes(args: &BytaryArgs) -> &'static [u8] {
+    match args.eol.as_str() {
+        "lf" => b"\n",
+        "crlf" => b"\r\n",
+        _ => b"",
+    }
+}
+
+/// Checks that lines of `wrap_interval` characters hold a whole number of groups of `space_interval`,
+/// suggesting values that do otherwise
+fn check_layout(space_interval: usize, wrap_interval: usize) -> BytaryResult<()> {
+    if space_interval == 0 || wrap_interval == 0 || wrap_interval.is_multiple_of(space_interval) {
+        return Ok(());
+    }
+    let or = |values: Vec<usize>| {
+        values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<String>>()
+            .join(" or ")
+    };
+    let divisors = (1..=wrap_interval).filter(|n| wrap_interval.is_multiple_of(*n));
+    let spaces = [
+        divisors.clone().rfind(|n| *n < space_interval),
+        divisors.clone().find(|n| *n > space_interval),
+    ];
+    let below = wrap_interval / space_interval * space_interval;
+    let wraps = [(below > 0).then_some(below), Some(below + space_interval)];
+    BytaryError::InvalidArgument(format!(
+        "lines of {} do not hold a whole number of --space {} groups, \
+        use --space {}, lines of {} characters, or --allow-odd-formatting",
+        plural(wrap_interval, "character"),
+        space_interval,
+        or(spaces.into_iter().flatten().collect()),
+        or(wraps.into_iter().flatten().collect()),
+    ))
+    .err()
+}
+
+/// Converts --wrap-bytes to the number of characters per line of `to` output
+fn wrap_bytes_interval(to: &Format, bytes: usize) -> BytaryResult<usize> {
+    let (chars, per) = to.chars_per_byte().ok_or_else(|| {
+        BytaryError::InvalidArgument(format!(
+            "--wrap-bytes only applies to bin, hex, oct or base64 output, not {}",
+            to
+        ))
+    })?;
+    if !bytes.is_multiple_of(per) {
+        return BytaryError::InvalidArgument(format!(
+            "--wrap-bytes for {} output must be a multiple of {}",
+            to, per
+        ))
+        .err();
+    }
+    Ok(bytes / per * chars)
+}
+
+/// Parses a size argument like `4096`, `64k` or `1.5MiB`, see [`parse_size`]
+fn size_arg<T: TryFrom<u64>>(value: &str) -> Result<T, String> {
+    let size = parse_size(value).map_err(|e| e.to_string())?;
+    T::try_from(size).map_err(|_| match size {
+        0 => "size must not be 0".to_string(),
+        _ => format!("size {} is too large", format_size(size)),
+    })
+}
+
+/// Parses a size argument like [`size_arg`], rejecting 0
+fn nonzero_size_arg(value: &str) -> Result<NonZeroUsize, String> {
+    NonZeroUsize::new(size_arg(value)?).ok_or_else(|| "size must not be 0".to_string())
+}
+
+/// Parses a format name like [`Format::try_from`], so that mistyped names get the suggestions of
+/// [`Format::suggest`], while listing the names and aliases in help and completions
+#[derive(Clone)]
+struct FormatParser;
+
+impl TypedValueParser for FormatParser {
+    type Value = Format;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Format, clap::Error> {
+        let name = value.to_string_lossy();
+        Format::try_from(name.as_ref()).map_err(|e| {
+            let arg = arg.map_or_else(|| "...".to_string(), |arg| arg.to_string());
+            let names = FORMATS.iter().map(|spec| spec.name).collect::<Vec<&str>>();
+            clap::Error::raw(
+                ErrorKind::InvalidValue,
+                format!(
+                    "invalid value '{}' for '{}': {}\n  [possible values: {}]\n",
+                    name,
+                    arg,
+                    e,
+                    names.join(", ")
+                ),
+            )
+            .with_cmd(cmd)
+        })
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            Format::value_variants()
+                .iter()
+                .filter_map(ValueEnum::to_possible_value),
+        ))
+    }
+}
+
+/// Parses a target of --also like `bin:out.bin`
+fn also_arg(value: &str) -> Result<(Format, PathBuf), String> {
+    match value.split_once(':') {
+        Some((format, path)) if !path.is_empty() => Ok((
+            format.parse().map_err(|e: BytaryError| e.to_string())?,
+            PathBuf::from(path),
+        )),
+        _ => Err(format!(
+            "expected FORMAT:PATH, like bin:out.bin, not '{}'",
+            value
+        )),
+    }
+}
+
+/// Parses a hex pattern like `ff d8` or `ffd8`
+fn parse_pattern(pattern: &str) -> BytaryResult<Vec<u8>> {
+    let digits = pattern.split_whitespace().collect::<String>();
+    hex::decode(digits)
+        .map_err(|_| BytaryError::InvalidArgument(format!("invalid hex pattern '{}'", pattern)))
+}
+
+/// The base64 alphabet given by `--b64-alphabet` and `--no-pad`
+fn base64_alphabet(args: &BytaryArgs) -> BytaryResult<Base64Alphabet> {
+    let default = Base64Alphabet::default();
+    let chars = match &args.b64_alphabet {
+        Some(chars) => chars.as_bytes().try_into().map_err(|_| {
+            BytaryError::InvalidArgument(format!(
+                "--b64-alphabet needs 64 characters, not {}",
+                chars.chars().count()
+            ))
+        })?,
+        None => *default.chars(),
+    };
+    let base64 = args.from == Format::Base64 || args.to == Format::Base64;
+    let pad = match args.no_pad && base64 {
+        true => None,
+        false => default.pad(),
+    };
+    base64_with_alphabet(chars, pad)
+}
+
+/// Parses a channel selection like `1/2:2`, see `--channel`
+fn parse_channel(channel: &str) -> BytaryResult<Deinterleave> {
+    let invalid = || {
+        BytaryError::InvalidArgument(format!(
+            "invalid channel '{}', expected SELECT/CHANNELS[:UNIT]",
+            channel
+        ))
+    };
+    let (select, rest) = channel.split_once('/').ok_or_else(invalid)?;
+    let (channels, unit) = rest.split_once(':').unwrap_or((rest, "1"));
+    let number = |text: &str| text.parse::<usize>().map_err(|_| invalid());
+    deinterleave(number(channels)?, number(unit)?, number(select)?)
+}
+
+/// Creates a process that validates its input as the decoders with `padding` read it, without writing any output
+fn check_process(args: &BytaryArgs, from: &Format, padding: Padding) -> Rc<dyn Converter> {
+    let from = from.clone();
+    let verbose = args.verbose;
+
+    Rc::new(move |input: &mut dyn io::Read, _: &mut dyn io::Write| {
+        let report = from.validate_with_padding(input, padding)?;
+        if let Some(e) = report.error {
+            return BytaryError::InvalidInputData(e.to_string()).err();
+        }
+        if verbose {
+            eprintln!("Valid {} input: {}", from, plural(report.units, "byte"));
+        }
+        Ok(())
+    })
+}
+
+/// How the output of a conversion is laid out
+struct Layout {
+    style: Option<HexStyle>,
+    space_interval: usize,
+    wrap_interval: usize,
+    /// Count the intervals in characters, see [`Format::is_utf8`]
+    utf8: bool,
+    timestamps: Option<LineTimestamps>,
+}
+
+impl Layout {
+    fn write(
+        &self,
+        converter: &dyn Converter,
+        input: &mut dyn io::Read,
+        target: &mut dyn io::Write,
+    ) -> BytaryResult<()> {
+        match self.style {
+            Some(style) => {
+                let schedule = style.schedule();
+                let total = schedule.total();
+                let mut grouped = GroupedWriter::new(target, schedule);
+                converter.convert(input, &mut grouped)?;
+                if let Some(total) = total
+                    && grouped.count() != total
+                {
+                    return BytaryError::InvalidInputData(format!(
+                        "--style {} needs exactly {}, not {}",
+                        style,
+                        plural(total / 2, "byte"),
+                        grouped.count() / 2
+                    ))
+                    .err();
+                }
+                grouped.finish()?;
+            }
+            None => {
+                let mut formatted =
+                    FormattedWriter::new(target, self.space_interval, self.wrap_interval)
+                        .utf8(self.utf8);
+                if let Some(timestamps) = &self.timestamps {
+                    formatted = formatted.timestamps(timestamps.clone());
+                }
+                converter.convert(input, &mut formatted)?;
+                formatted.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the output of `converter` through a [`FormattedWriter`], which starts over with every call
+fn formatted(
+    converter: Rc<dyn Converter>,
+    space_interval: usize,
+    wrap_interval: usize,
+    utf8: bool,
+) -> Rc<dyn Converter> {
+    Rc::new(
+        move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+            let mut formatted =
+                FormattedWriter::new(output, space_interval, wrap_interval).utf8(utf8);
+            converter.convert(input, &mut formatted)?;
+            Ok(formatted.finish()?)
+        },
+    )
+}
+
+/// The characters that --reformat keeps in `format`, `None` if it can not be reformatted
+fn reformat_alphabet(format: &Format) -> Option<&'static str> {
+    match format {
+        Format::Hex | Format::HexNibbleSwapped => Some("0123456789abcdefABCDEF"),
+        Format::Bin => Some("01"),
+        Format::Oct => Some("01234567"),
+        _ => None,
+    }
+}
+
+/// Converts `from` to bytes, then writes them to `to` with an [`AnnotatedFormatter`], for --with-ascii
+fn annotated(
+    graph: &ConversionGraph,
+    from: &Format,
+    to: &Format,
+    space_interval: usize,
+    bytes_per_line: usize,
+) -> BytaryResult<Rc<dyn Converter>> {
+    let chars_per_byte = match to {
+        Format::Hex | Format::Bin | Format::Oct => {
+            to.chars_per_byte().map_or(1, |(chars, _)| chars)
+        }
+        _ => {
+            return BytaryError::InvalidArgument(format!(
+                "--with-ascii only applies to bin, hex or oct output, not {}",
+                to
+            ))
+            .err();
+        }
+    };
+    let decoder = graph.try_get_converter(from, &Format::Bytes)?;
+    let encoder = graph.try_get_converter(&Format::Bytes, to)?;
+    Ok(Rc::new(
+        move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+            let mut writer = AnnotatingWriter {
+                encoder: encoder.as_ref(),
+                encoded: Vec::new(),
+                formatter: AnnotatedFormatter::new(
+                    output,
+                    chars_per_byte,
+                    space_interval,
+                    bytes_per_line,
+                ),
+            };
+            decoder.convert(input, &mut writer)?;
+            writer.formatter.finish()?;
+            Ok(())
+        },
+    ))
+}
+
+/// Encodes the bytes written to it and feeds both to an [`AnnotatedFormatter`]
+struct AnnotatingWriter<'a, W: io::Write> {
+    encoder: &'a dyn Converter,
+    encoded: Vec<u8>,
+    formatter: AnnotatedFormatter<W>,
+}
+
+impl<W: io::Write> io::Write for AnnotatingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.encoded.clear();
+        self.encoder
+            .convert(&mut &buf[..], &mut self.encoded)
+            .map_err(|e| match e {
+                BytaryError::Io(e) => e,
+                e => io::Error::other(e.to_string()),
+            })?;
+        self.formatter.write_annotated(buf, &self.encoded)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.formatter.flush()
+    }
+}
+
+/// Creates a process that converts its input and formats the output
+fn convert_process(
+    args: &BytaryArgs,
+    graph: &ConversionGraph,
+    from: &Format,
+    to: &Format,
+    mut space_interval: usize,
+    color: bool,
+) -> BytaryResult<Rc<dyn Converter>> {
+    let path = graph
+        .find_shortest_path(from, to)
+        .ok_or(BytaryError::UnsupportedConversion(from.clone(), to.clone()))?;
+
+    let mut wrap_interval = match args.wrap_bytes {
+        Some(bytes) => wrap_bytes_interval(to, bytes.get())?,
+        None => args.wrap_interval,
+    };
+
+    if args.verbose {
+        if path.len() <= 1 {
+            eprintln!("Operation: Copy data")
+        } else {
+            eprintln!(
+                "Operation: {}",
+                path.iter()
+                    .map(|f| f.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" => ")
+            );
+            eprintln!(
+                "Converters: {}",
+                path.windows(2)
+                    .map(|w| match graph.get_edge_info(&w[0], &w[1]) {
+                        Some(info) if !info.name.is_empty() => info.name.to_string(),
+                        _ => format!("{} => {}", w[0], w[1]),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+            eprintln!(
+                "Cost: {}",
+                path.windows(2)
+                    .filter_map(|w| graph.get_edge_info(&w[0], &w[1]))
+                    .map(|info| info.cost)
+                    .sum::<u32>()
+            );
+        }
+        // Mistakes in the graph are only reported, the conversion goes on
+        for issue in graph.validate() {
+            eprintln!("Warning: {}", issue);
+        }
+        eprintln!(
+            "Formatting: space every {}, break line every {}",
+            plural(args.space_interval, "byte"),
+            plural(wrap_interval, "byte")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    let json_stats = args.json_stats;
+    #[cfg(not(feature = "serde"))]
+    let json_stats = false;
+    let stats = args.stats || args.verbose || json_stats;
+
+    // The time and bytes of each hop, printed with the stats
+    let hops = Rc::new(RefCell::new(Vec::new()));
+    let mut converter = match (args.bit_skip, args.bit_length) {
+        _ if args.with_ascii => {
+            // The formatter lays out the lines itself, from the bytes and their encoding
+            let bytes_per_line = args.wrap_bytes.map_or(0, NonZeroUsize::get);
+            let interval = space_interval;
+            (space_interval, wrap_interval) = (0, 0);
+            annotated(graph, from, to, interval, bytes_per_line)?
+        }
+        _ if args.reformat => Rc::new(reformat_text(
+            reformat_alphabet(from).expect("checked by run"),
+        )),
+        (None, None) if args.channel.is_none() && args.byteswap.is_none() => {
+            let converter = graph.try_get_converter(from, to)?;
+            match stats && path.len() > 2 {
+                true => timed(graph, &path, hops.clone())?,
+                false => converter,
+            }
+        }
+        (skip, length) => {
+            let mut stages = vec![graph.try_get_converter(from, &Format::Bytes)?];
+            if let Some(channel) = &args.channel {
+                stages.push(Rc::new(parse_channel(channel)?));
+            }
+            if let Some(width) = &args.byteswap {
+                let width = width.parse().expect("checked by clap");
+                stages.push(Rc::new(byteswap(width)?));
+            }
+            if skip.is_some() || length.is_some() {
+                let order = match args.lsb_first {
+                    true => BitOrder::LsbFirst,
+                    false => BitOrder::MsbFirst,
+                };
+                let slice = bit_slice(skip.unwrap_or(0), length.unwrap_or(u64::MAX), order);
+                stages.push(Rc::new(slice));
+            }
+            stages.push(graph.try_get_converter(&Format::Bytes, to)?);
+            ConversionGraph::compose(stages)
+        }
+    };
+    let framing = match args.frame.as_deref() {
+        Some(frame) if *to != Format::Bytes => {
+            return BytaryError::InvalidArgument(format!(
+                "--frame {} requires bytes output, not {}",
+                frame, to
+            ))
+            .err();
+        }
+        Some("nul") => Some(Framing::Nul),
+        Some(_) => Some(Framing::Len32 {
+            max_size: args.max_record_size,
+        }),
+        None => None,
+    };
+    if (args.record_size.is_some() || args.paragraph) && (space_interval, wrap_interval) != (0, 0) {
+        // Each record is laid out on its own, so that its spaces and line breaks start over
+        let layout = (space_interval, wrap_interval);
+        (space_interval, wrap_interval) = (0, 0);
+        converter = formatted(converter, layout.0, layout.1, to.is_utf8());
+    }
+    let converter = match (args.record_size, framing) {
+        _ if args.paragraph => paragraph_wise(converter, framing),
+        (Some(size), Some(framing)) => {
+            record_wise_framed(size.get(), converter, args.record_index, framing)
+        }
+        (Some(size), None) => record_wise(size.get(), converter, args.record_index),
+        (None, Some(framing)) => line_wise(converter, framing),
+        (None, None) => converter,
+    };
+    let converter = match &args.patch {
+        Some(file) => {
+            if *to != Format::Bytes {
+                return BytaryError::InvalidArgument(format!(
+                    "--patch requires bytes output, not {}",
+                    to
+                ))
+                .err();
+            }
+            let patches = parse_patches(&fs::read_to_string(file)?)?;
+            let options = PatchOptions {
+                allow_extend: args.allow_extend,
+            };
+            Rc::new(
+                move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+                    let mut writer = PatchingWriter::new(output, &patches, options.clone())?;
+                    converter.convert(input, &mut writer)?;
+                    writer.finish()
+                },
+            )
+        }
+        None => converter,
+    };
+
+    let (prefix, suffix) = match &args.pem {
+        Some(label) => {
+            if *to != Format::Base64 {
+                return BytaryError::InvalidArgument(format!(
+                    "--pem requires base64 output, not {}",
+                    to
+                ))
+                .err();
+            }
+            if wrap_interval == 0 {
+                wrap_interval = 64;
+            }
+            (
+                format!("-----BEGIN {}-----\n", label),
+                format!("-----END {}-----\n", label),
+            )
+        }
+        None => (
+            unescape(args.prefix.as_deref().unwrap_or_default()),
+            unescape(args.suffix.as_deref().unwrap_or_default()),
+        ),
+    };
+    let pem = args.pem.is_some();
+
+    // Radix and digits per byte of the output, or `None` if --no-pad separates the bytes
+    let color = match (color, to) {
+        (false, _) => None,
+        // Timestamps are not digits to color
+        _ if args.timestamps.is_some() => None,
+        (true, Format::Hex) => Some((16, Some(2))),
+        (true, Format::Bin) => Some((2, (!args.no_pad).then_some(8))),
+        (true, Format::Oct) => Some((8, (!args.no_pad).then_some(3))),
+        (true, _) if args.color == "auto" => None,
+        (true, _) => {
+            return BytaryError::InvalidArgument(format!(
+                "--color only applies to hex, bin or oct output, not {}",
+                to
+            ))
+            .err();
+        }
+    };
+    let always = args.always_delimit;
+    let (eol, eol_always) = (eol_bytes(args), args.eol_always);
+    let utf8 = to.is_utf8();
+    let absolute = match args.timestamps.as_deref() {
+        Some(_) if *to == Format::Bytes => {
+            return BytaryError::InvalidArgument(
+                "--timestamps only applies to text output, not bytes".to_string(),
+            )
+            .err();
+        }
+        Some(_) if wrap_interval == 0 => {
+            return BytaryError::InvalidArgument(
+                "--timestamps needs wrapped output, with --wrap or --wrap-bytes".to_string(),
+            )
+            .err();
+        }
+        Some(clock) => Some(clock == "absolute"),
+        None => None,
+    };
+    // The position of a line in the output only tells its input byte if the input is read as is
+    let ratio = match from {
+        Format::Bytes => converter.expansion_ratio(),
+        _ => None,
+    };
+    let style = match args.style.as_deref() {
+        Some(style) if *to != Format::Hex => {
+            return BytaryError::InvalidArgument(format!(
+                "--style {} only applies to hex output, not {}",
+                style, to
+            ))
+            .err();
+        }
+        Some(style) => Some(HexStyle::try_from(style)?),
+        None => None,
+    };
+
+    Ok(Rc::new(
+        move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+            let start = Instant::now();
+            let mut input = CountingReader::new(input);
+            let mut output = CountingWriter::new(output);
+            let mut timed;
+            let (reader, timestamps): (&mut dyn io::Read, _) = match absolute {
+                Some(absolute) => {
+                    timed = TimedReader::new(&mut input);
+                    let style = match absolute {
+                        true => TimestampStyle::Absolute(SystemTime::now()),
+                        false => TimestampStyle::Relative,
+                    };
+                    let timestamps = LineTimestamps::new(timed.arrivals(), style).ratio(ratio);
+                    (&mut timed, Some(timestamps))
+                }
+                None => (&mut input, None),
+            };
+
+            // The line break of --eol goes after everything else
+            let mut terminated =
+                DelimitedWriter::new(&mut output, b"", eol, eol_always).suffix_unless_new_line();
+            let mut writer = DelimitedWriter::new(
+                &mut terminated,
+                prefix.as_bytes(),
+                suffix.as_bytes(),
+                always,
+            );
+            if pem {
+                writer = writer.suffix_on_new_line();
+            }
+            let layout = Layout {
+                style,
+                space_interval,
+                wrap_interval,
+                utf8,
+                timestamps,
+            };
+            match color {
+                Some((radix, digits)) => {
+                    let mut colored = ColoredWriter::new(&mut writer, radix, digits);
+                    layout.write(converter.as_ref(), reader, &mut colored)?;
+                    colored.finish()?;
+                }
+                None => layout.write(converter.as_ref(), reader, &mut writer)?,
+            }
+            writer.finish()?;
+            drop(writer);
+            terminated.finish()?;
+            drop(terminated);
+
+            if stats {
+                let stats =
+                    Stats::new(path.clone(), input.count(), output.count(), start.elapsed());
+                match json_stats {
+                    #[cfg(feature = "serde")]
+                    true => eprintln!(
+                        "{}",
+                        serde_json::to_string(&stats).map_err(io::Error::from)?
+                    ),
+                    _ => {
+                        eprintln!("{}", stats);
+                        for hop in hops.borrow().iter() {
+                            eprintln!("{}", hop);
+                        }
+                    }
+                }
+                hops.borrow_mut().clear();
+            }
+            Ok(())
+        },
+    ))
+}
+
+/// Runs the hops of `path` one by one, adding the time and bytes of each one to `hops`
+fn timed(
+    graph: &ConversionGraph,
+    path: &[Format],
+    hops: Rc<RefCell<Vec<StageTiming>>>,
+) -> BytaryResult<Rc<dyn Converter>> {
+    let converters = graph
+        .path_to_converters(path)
+        .ok_or_else(|| BytaryError::UnsupportedConversion(path[0].clone(), path[1].clone()))?;
+    let converter = ComposedConverter::new(converters);
+    let path = path.to_vec();
+    Ok(Rc::new(
+        move |input: &mut dyn io::Read, output: &mut dyn io::Write| {
+            // Records and inputs are converted one at a time, their hops add up
+            converter.convert_timed(
+                input,
+                output,
+                &mut |index, bytes_in, bytes_out, duration| {
+                    let mut hops = hops.borrow_mut();
+                    match hops.get_mut(index) {
+                        Some(hop) => {
+                            hop.bytes_in += bytes_in;
+                            hop.bytes_out += bytes_out;
+                            hop.duration += duration;
+                        }
+                        None => hops.push(StageTiming {
+                            from: path[index].clone(),
+                            to: path[index + 1].clone(),
+                            bytes_in,
+                            bytes_out,
+                            duration,
+                        }),
+                    }
+                },
+            )
+        },
+    ))
+}
+
+/// Summary of one conversion, printed with --stats
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+struct Stats {
+    path: Vec<Format>,
+    bytes_in: u64,
+    bytes_out: u64,
+    seconds: f64,
+    /// Input bytes per second
+    throughput: f64,
+}
+
+impl Stats {
+    fn new(path: Vec<Format>, bytes_in: u64, bytes_out: u64, elapsed: Duration) -> Self {
+        let seconds = elapsed.as_secs_f64();
+        Self {
+            path,
+            bytes_in,
+            bytes_out,
+            seconds,
+            throughput: match seconds > 0.0 {
+                true => bytes_in as f64 / seconds,
+                false => 0.0,
+            },
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Stats: path={} in={} out={} time={:.6}s throughput={}/s",
+            self.path
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+            self.bytes_in,
+            self.bytes_out,
+            self.seconds,
+            format_size(self.throughput as u64)
+        )
+    }
+}
+
+/// Replaces the escapes `\n`, `\t` and `\\` in a command line argument, leaving others as is
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Runs `process` on each input file, continuing past errors, then prints a summary to stderr.
+///
+/// The outputs are written to `--output-dir` if given, otherwise all to `output`.
+fn run_batch(
+    args: &BytaryArgs,
+    to: &Format,
+    process: &dyn Converter,
+    output: &mut dyn io::Write,
+) -> BytaryResult<()> {
+    let extension = match &args.output_ext {
+        Some(extension) => extension.clone(),
+        None if *to == Format::Bytes => "bin".to_string(),
+        None => to.to_string(),
+    };
+
+    #[cfg(feature = "mmap")]
+    let mmap = args.mmap;
+    #[cfg(not(feature = "mmap"))]
+    let mmap = false;
+
+    if let Some(dir) = args.output_dir.as_ref().filter(|_| !args.check) {
+        fs::create_dir_all(dir)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", dir.display(), e)))?;
+    }
+
+    let mut results = Vec::new();
+    for path in &args.inputs {
+        let result = (|| {
+            // The mapping of --mmap lives until the conversion is done
+            let mut file = InputFile::open(path, mmap)?;
+            let mut input = file.reader();
+            match &args.output_dir {
+                Some(dir) if !args.check => {
+                    let target = batch_output_path(path, dir, &extension);
+                    let result = fs::File::create(&target)
+                        .map_err(|e| {
+                            io::Error::new(e.kind(), format!("{}: {}", target.display(), e)).into()
+                        })
+                        .and_then(|file| {
+                            let mut writer = io::BufWriter::new(file);
+                            process.convert(&mut input, &mut writer)?;
+                            Ok(io::Write::flush(&mut writer)?)
+                        });
+                    if result.is_err() {
+                        let _ = fs::remove_file(&target);
+                    }
+                    result.map(|_| Some(target))
+                }
+                _ => process.convert(&mut input, output).map(|_| None),
+            }
+        })();
+
+        match result {
+            Err(e) if e.is_broken_pipe() => return Err(e),
+            result => results.push((path, result)),
+        }
+    }
+
+    let width = results
+        .iter()
+        .map(|(path, _)| path.display().to_string().len())
+        .max()
+        .unwrap_or(0);
+    for (path, result) in &results {
+        match result {
+            Ok(Some(target)) => {
+                eprintln!("{:width$}  ok      {}", path.display(), target.display())
+            }
+            Ok(None) => eprintln!("{:width$}  ok", path.display()),
+            Err(e) => {
+                eprintln!("{:width$}  failed  {}", path.display(), e);
+                let context = e
+                    .offset()
+                    .and_then(|offset| error_context(path, offset).ok().flatten());
+                if let Some(context) = context {
+                    eprintln!("{}", context);
+                }
+            }
+        }
+    }
+
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+    eprintln!("{}, {} failed", plural(results.len(), "file"), failed);
+    match failed {
+        0 => Ok(()),
+        _ => BytaryError::BatchFailed(failed, results.len()).err(),
+    }
+}
+
+/// Longest part of a line shown by [`error_context`], in characters
+const CONTEXT_WIDTH: usize = 120;
+
+/// Locates `offset` in the file at `path`, as `path:line:column` followed by the line
+/// and a caret under the character at the offset, or `None` if the file is shorter.
+///
+/// Lines and columns count from 1, and columns count characters. Lines longer than
+/// [`CONTEXT_WIDTH`] are cut around the offset.
+fn error_context(path: &Path, offset: u64) -> io::Result<Option<String>> {
+    let data = fs::read(path)?;
+    let Some(offset) = usize::try_from(offset).ok().filter(|&o| o <= data.len()) else {
+        return Ok(None);
+    };
+    let start = data[..offset]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    let end = data[offset..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(data.len(), |i| offset + i);
+    let number = data[..start].iter().filter(|&&b| b == b'\n').count() + 1;
+    let column = String::from_utf8_lossy(&data[start..offset])
+        .chars()
+        .count();
+
+    let line = String::from_utf8_lossy(&data[start..end]);
+    let line = line
+        .strip_suffix('\r')
+        .unwrap_or(&line)
+        .chars()
+        .collect::<Vec<char>>();
+    let (first, last) = match line.len() > CONTEXT_WIDTH {
+        true => {
+            let first = column
+                .saturating_sub(CONTEXT_WIDTH / 2)
+                .min(line.len() - CONTEXT_WIDTH);
+            (first, first + CONTEXT_WIDTH)
+        }
+        false => (0, line.len()),
+    };
+    let cut = |cut: bool| match cut {
+        true => "...",
+        false => "",
+    };
+    // Tabs are kept under tabs, for the caret to line up
+    let indent = line[first..column.min(line.len())]
+        .iter()
+        .map(|&c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+    Ok(Some(format!(
+        "{}:{}:{}\n    {}{}{}\n    {}{}^",
+        path.display(),
+        number,
+        column + 1,
+        cut(first > 0),
+        line[first..last].iter().collect::<String>(),
+        cut(last < line.len()),
+        " ".repeat(cut(first > 0).len()),
+        indent
+    )))
+}
+
+/// Names the output of `input` in `dir`, replacing its extension with `extension`
+fn batch_output_path(input: &Path, dir: &Path, extension: &str) -> PathBuf {
+    let mut name = input.file_stem().unwrap_or_default().to_os_string();
+    if !extension.is_empty() {
+        name.push(".");
+        name.push(extension);
+    }
+    dir.join(name)
+}
+
+/// Exit statuses, shown at the end of --help
+const EXIT_STATUS: &str = "\
+Exit status:
+  0    Success
+  1    Invalid input data or another failure, or differences with --compare or --expect
+  2    Invalid arguments, formats or conversions, or any error with --compare
+  3    Reading or writing failed
+  130  Cancelled
+  141  The output was closed early, with --sigpipe-status";
+
+/// The exit status of a failed run, as listed in [`EXIT_STATUS`]
+fn exit_code(error: &BytaryError, compare: bool) -> u8 {
+    match error {
+        // Like cmp, errors are told apart from differences
+        _ if compare => 2,
+        BytaryError::InvalidArgument(_)
+        | BytaryError::InvalidFormat(..)
+        | BytaryError::UnsupportedConversion(_, _)
+        | BytaryError::PathTooLong(_, _, _, _)
+        | BytaryError::TerminalOutput => 2,
+        BytaryError::Io(_) => 3,
+        BytaryError::Cancelled => 130,
+        BytaryError::StageFailed(_, _, error)
+        | BytaryError::ParagraphFailed(_, error)
+        | BytaryError::BranchFailed(_, error) => exit_code(error, compare),
+        _ => 1,
+    }
+}
+
+/// Adds the options of `--preset` that `argv` does not give to it, before it is parsed, so that
+/// they are validated like the others: a preset option that conflicts with a given one is an error.
+#[cfg(feature = "config")]
+fn with_preset(argv: Vec<OsString>) -> BytaryResult<Vec<OsString>> {
+    let matches = match BytaryArgs::command()
+        .ignore_errors(true)
+        .try_get_matches_from(&argv)
+    {
+        Ok(matches) => matches,
+        // Like --help, left to the real parse
+        Err(_) => return Ok(argv),
+    };
+    let Some(name) = matches.get_one::<String>("preset") else {
+        return Ok(argv);
+    };
+    let config = match matches.get_one::<PathBuf>("config") {
+        Some(path) => Config::load(path)?,
+        None => Config::load(Config::default_path().ok_or_else(|| {
+            BytaryError::InvalidArgument("no config file, use --config".to_string())
+        })?)?,
+    };
+    let preset = config.preset(name)?;
+    let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    let mut options = Vec::new();
+    let mut option = |id: &str, long: &str, value: Option<String>| {
+        if let Some(value) = value.filter(|_| !given(id)) {
+            options.extend([format!("--{}", long), value]);
+        }
+    };
+    option(
+        "space_interval",
+        "space",
+        preset.space.map(|n| n.to_string()),
+    );
+    option("wrap_interval", "wrap", preset.wrap.map(|n| n.to_string()));
+    option(
+        "wrap_bytes",
+        "wrap-bytes",
+        preset.wrap_bytes.map(|n| n.to_string()),
+    );
+    option(
+        "style",
+        "style",
+        preset.style.map(|style| style.name().to_string()),
+    );
+    if preset.warnings_as_errors == Some(true) && !given("warnings_as_errors") {
+        options.push("--warnings-as-errors".to_string());
+    }
+
+    // Positional formats are appended, so FROM needs TO before it
+    let mut formats = Vec::new();
+    let from = preset.from.as_ref().filter(|_| !given("from"));
+    if !given("to") {
+        match (&preset.to, from) {
+            (Some(to), _) => formats.push(to.to_string()),
+            (None, Some(_)) => formats.push(matches.get_one::<Format>("to").unwrap().to_string()),
+            (None, None) => {}
+        }
+    }
+    formats.extend(from.map(Format::to_string));
+
+    let mut argv = argv.into_iter();
+    let mut result = argv.next().into_iter().collect::<Vec<OsString>>();
+    result.extend(options.into_iter().map(OsString::from));
+    result.extend(argv);
+    if !formats.is_empty() {
+        if !result.iter().skip(1).any(|arg| arg == "--") {
+            result.push("--".into());
+        }
+        result.extend(formats.into_iter().map(OsString::from));
+    }
+    Ok(result)
+}
+
+#[cfg(not(feature = "config"))]
+fn with_preset(argv: Vec<OsString>) -> BytaryResult<Vec<OsString>> {
+    Ok(argv)
+}
+
+fn main() -> ExitCode {
+    let mut compare = false;
+    let result = with_preset(std::env::args_os().collect()).and_then(|argv| {
+        let args = BytaryArgs::parse_from(argv);
+        compare = args.compare.is_some();
+        prepare_stdio(BinaryStdio::of(&args), &mut set_binary_mode)?;
+        match resolve_output(&args)? {
+            // bytary_cli flushes the output, so that errors of the last write are reported
+            Some(target) => bytary_cli(
+                args,
+                &mut io::stdin(),
+                &mut io::BufWriter::new(target),
+                false,
+            ),
+            None => {
+                let stdout = io::stdout();
+                let is_tty = stdout.is_terminal();
+                bytary_cli(
+                    args,
+                    &mut io::stdin(),
+                    &mut io::BufWriter::new(stdout.lock()),
+                    is_tty,
+                )
+            }
+        }
+    });
+
+    result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        ExitCode::from(exit_code(&e, compare))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::io::Cursor;
+
+    /// Parse command line arguments, without the program name
+    fn args(line: &str) -> BytaryArgs {
+        BytaryArgs::parse_from(std::iter::once("bytary").chain(line.split_whitespace()))
+    }
+
+    #[test]
+    fn test_cli_bytes_to_hex() {
+        let mut output = Vec::new();
+        bytary_cli(
+            args("hex bytes --verbose"),
+            &mut Cursor::new(vec![0x1b, 0x34, 0x8f, 0xff, 0x00, 0x0e]),
+            &mut output,
+            false,
+        )
+        .unwrap();
+        assert_eq!(output, b"1b348fff000e");
+    }
+
+    #[test]
+    fn test_bytes_to_bytes() {
+        let mut output = Vec::new();
+        let data = [0x1b, 0x34, 0x8f, 0xff, 0x00, 0x0e];
+        bytary_cli(
+            args("bytes bytes --verbose"),
+            &mut Cursor::new(&data),
+            &mut output,
+            false,
+        )
+        .unwrap();
+        assert_eq!(output, &data);
+    }
+
+    #[test]
+    fn test_build_info() {
+        let mut output = Vec::new();
+        bytary_cli(
+            args("--build-info"),
+            &mut Cursor::new(b""),
+            &mut output,
+            false,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output.starts_with(&format!("bytary {}", env!("CARGO_PKG_VERSION"))),
+            "{}",
+            output
+        );
+        assert!(output.contains("simd"), "{}", output);
+        assert!(output.contains("Conversions: "), "{}", output);
+    }
+
+    #[test]
+    fn test_list_formats() {
+        let list = || {
+            let mut output = Vec::new();
+            bytary_cli(args("-l"), &mut Cursor::new(vec![]), &mut output, false).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let first = list();
+        assert_eq!(
+            first,
+            "Available formats: bytes, bin, hex, hexswap, oct, leb128, rle, base64, pem, utf8, utf16le, utf16be, codepoints\n"
+        );
+        assert_eq!(list(), first);
+
+        // Every listed format has conversions of its own, not only a way to bytes through others
+        let graph = ConversionGraph::default();
+        let names = first.trim_end().trim_start_matches("Available formats: ");
+        for name in names.split(", ") {
+            let format = Format::try_from(name).unwrap();
+            assert!(
+                graph
+                    .edges()
+                    .any(|(from, to, _, _)| *from == format || *to == format),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_list_formats_json() {
+        let mut output = Vec::new();
+        bytary_cli(
+            args("--list-formats --json"),
+            &mut Cursor::new(vec![]),
+            &mut output,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r#"{"formats":["bytes","bin","hex","hexswap","oct","dec","leb128","rle","base64","pem","utf8","utf16le","utf16be","codepoints","stats","byteset"],"conversions":["#,
+                r#"{"from":"bytes","to":"bin","cost":1},"#,
+                r#"{"from":"bytes","to":"hex","cost":1},"#,
+                r#"{"from":"bytes","to":"hexswap","cost":1},"#,
+                r#"{"from":"bytes","to":"oct","cost":1},"#,
+                r#"{"from":"bytes","to":"leb128","cost":1},"#,
+                r#"{"from":"bytes","to":"rle","cost":1},"#,
+                r#"{"from":"bytes","to":"base64","cost":1},"#,
+                r#"{"from":"bytes","to":"pem","cost":1},"#,
+                r#"{"from":"bytes","to":"utf8","cost":1},"#,
+                r#"{"from":"bytes","to":"utf16le","cost":1},"#,
+                r#"{"from":"bytes","to":"utf16be","cost":1},"#,
+                r#"{"from":"bytes","to":"codepoints","cost":1},"#,
+                r#"{"from":"bytes","to":"stats","cost":1},"#,
+                r#"{"from":"bytes","to":"byteset","cost":1},"#,
+                r#"{"from":"bin","to":"hex","cost":1},"#,
+                r#"{"from":"hex","to":"bytes","cost":1},"#,
+                r#"{"from":"hex","to":"hexswap","cost":1},"#,
+                r#"{"from":"hexswap","to":"bytes","cost":1},"#,
+                r#"{"from":"hexswap","to":"hex","cost":1},"#,
+                r#"{"from":"oct","to":"bytes","cost":1},"#,
+                r#"{"from":"dec","to":"leb128","cost":1},"#,
+                r#"{"from":"leb128","to":"bytes","cost":1},"#,
+                r#"{"from":"leb128","to":"dec","cost":1},"#,
+                r#"{"from":"rle","to":"bytes","cost":1},"#,
+                r#"{"from":"base64","to":"bytes","cost":1},"#,
+                r#"{"from":"pem","to":"bytes","cost":1},"#,
+                r#"{"from":"utf8","to":"bytes","cost":1},"#,
+                r#"{"from":"utf8","to":"utf16le","cost":1},"#,
+                r#"{"from":"utf8","to":"utf16be","cost":1},"#,
+                r#"{"from":"utf16le","to":"bytes","cost":1},"#,
+                r#"{"from":"utf16le","to":"utf8","cost":1},"#,
+                r#"{"from":"utf16be","to":"bytes","cost":1},"#,
+                r#"{"from":"utf16be","to":"utf8","cost":1},"#,
+                r#"{"from":"codepoints","to":"bytes","cost":1}]}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_pad() {
+        let run = |line: &str| {
+            let mut output = Vec::new();
+            bytary_cli(args(line), &mut Cursor::new(b"101"), &mut output, false).map(|_| output)
+        };
+        assert!(matches!(
+            run("hex bin"),
+            Err(BytaryError::InvalidInputData(_))
+        ));
+        assert_eq!(run("hex bin --pad left").unwrap(), b"05");
+        assert_eq!(run("hex bin --pad right").unwrap(), b"a0");
+    }
+
+    #[test]
+    fn test_terminal_output() {
+        use crate::Format::*;
+
+        assert!(check_terminal_output(&Bytes, true, false).is_err());
+        assert!(check_terminal_output(&Bytes, true, true).is_ok());
+        assert!(check_terminal_output(&Bytes, false, false).is_ok());
+        assert!(check_terminal_output(&Hex, true, false).is_ok());
+
+        let mut output = Vec::new();
+        let result = bytary_cli(
+            args("bytes hex"),
+            &mut Cursor::new(b"41"),
+            &mut output,
+            true,
+        );
+        assert!(matches!(result, Err(BytaryError::TerminalOutput)));
+        assert!(output.is_empty());
+
+        bytary_cli(
+            args("bytes hex -f"),
+            &mut Cursor::new(b"41"),
+            &mut output,
+            true,
+        )
+        .unwrap();
+        assert_eq!(output, b"A");
+    }
+
+    /// A writer that accepts `capacity` bytes, then fails as if the reader had closed the pipe
+    struct ClosingPipe {
+        written: Vec<u8>,
+        capacity: usize,
+    }
+
+    impl io::Write for ClosingPipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let length = buf.len().min(self.capacity - self.written.len());
+            if length == 0 {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            self.written.extend_from_slice(&buf[..length]);
+            Ok(length)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_broken_pipe() {
+        let data = vec![0xabu8; 100_000];
+
+        let mut output = ClosingPipe {
+            written: Vec::new(),
+            capacity: 10,
+        };
+        let code = bytary_cli(args("hex"), &mut Cursor::new(&data), &mut output, false);
+        assert_eq!(code.unwrap(), ExitCode::SUCCESS);
+        assert_eq!(output.written, b"abababababab"[..10]);
+
+        let mut output = ClosingPipe {
+            written: Vec::new(),
+            capacity: 10,
+        };
+        let code = bytary_cli(
+            args("hex --sigpipe-status"),
+            &mut Cursor::new(&data),
+            &mut output,
+            false,
+        );
+        assert_eq!(code.unwrap(), ExitCode::from(141));
+    }
+
+    #[test]
+    fn test_tee() {
+        let dir = std::env::temp_dir().join(format!("bytary-tee-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let copy = dir.join("copy.hex");
+        let data = (0..=255).collect::<Vec<u8>>();
+
+        let mut output = Vec::new();
+        let line = format!("hex -s 2 -w 16 --tee {}", copy.display());
+        bytary_cli(args(&line), &mut Cursor::new(&data), &mut output, false).unwrap();
+        assert_eq!(output, fs::read(&copy).unwrap());
+        assert!(output.starts_with(b"00 01 02 03 04 05 06 07\n08 "));
+
+        // The copy is complete after the output is closed, unless --tee-strict
+        let data = vec![0xabu8; 100_000];
+        let mut output = ClosingPipe {
+            written: Vec::new(),
+            capacity: 10,
+        };
+        let code = bytary_cli(args(&line), &mut Cursor::new(&data), &mut output, false);
+        assert_eq!(code.unwrap(), ExitCode::SUCCESS);
+        assert_eq!(fs::read(&copy).unwrap().len(), 300_000);
+
+        let mut output = ClosingPipe {
+            written: Vec::new(),
+            capacity: 10,
+        };
+        let line = format!("hex --tee {} --tee-strict", copy.display());
+        let code = bytary_cli(args(&line), &mut Cursor::new(&data), &mut output, false);
+        assert_eq!(code.unwrap(), ExitCode::SUCCESS);
+        assert!(fs::read(&copy).unwrap().len() < 200_000);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A writer that accepts every write, but fails to flush as if the disk were full
+    struct FailingFlush(Vec<u8>);
+
+    impl io::Write for FailingFlush {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::ErrorKind::StorageFull.into())
+        }
+    }
+
+    #[test]
+    fn test_flush_error() {
+        // Exactly 1024 bytes, so that the converters never see a short read
+        let data = vec![0xabu8; 1024];
+        for line in ["hex", "bytes", "base64"] {
+            let mut output = io::BufWriter::new(FailingFlush(Vec::new()));
+            let result = bytary_cli(args(line), &mut Cursor::new(&data), &mut output, false);
+            assert!(
+                matches!(result, Err(BytaryError::Io(ref e)) if e.kind() == io::ErrorKind::StorageFull),
+                "{}: {:?}",
+                line,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_broken_input_pipe() {
+        struct ClosedInput;
+        impl io::Read for ClosedInput {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+
+        let result = bytary_cli(args("hex"), &mut ClosedInput, &mut Vec::new(), false);
+        assert!(result.unwrap_err().is_broken_pipe());
+    }
+
+    #[test]
+    fn test_record_size() {
+        let mut output = Vec::new();
+        bytary_cli(
+            args("hex --record-size 2 --record-index"),
+            &mut Cursor::new(b"ABCDE"),
+            &mut output,
+            false,
+        )
+        .unwrap();
+        assert_eq!(output, b"#0 4142\n#1 4344\n#2 45\n");
+
+        // Every record starts its spacing at zero
+        let mut output = Vec::new();
+        bytary_cli(
+            args("hex --record-size 3 --space 2"),
+            &mut Cursor::new(b"ABCDE"),
+            &mut output,
+            false,
+        )
+        .unwrap();
+        assert_eq!(output, b"41 42 43\n44 45\n");
+    }
+
+    #[test]
+    fn test_check() {
+        let check = |input: &[u8]| {
+            let mut output = Vec::new();
+            let result = bytary_cli(
+                args("bytes hex --check"),
+                &mut Cursor::new(input),
+                &mut output,
+                true,
+            );
+            assert!(output.is_empty());
+            result
+        };
+        assert!(check(b"1b 34").is_ok());
+        assert!(check(b"").is_ok());
+        assert_eq!(
+            check(b"1b 3g").unwrap_err().to_string(),
+            "Invalid input data: unexpected character 'g' at offset 4"
+        );
+    }
+
+    #[test]
+    fn test_no_pad() {
+        let run = |line: &str, input: &[u8]| {
+            let mut output = Vec::new();
+            bytary_cli(args(line), &mut Cursor::new(input), &mut output, false).map(|_| output)
+        };
+        let data = [0x00, 0x0b, 0xff];
+
+        let oct = run("oct --no-pad --space 1", &data).unwrap();
+        assert_eq!(oct, b"0 13 377");
+        assert_eq!(run("bytes oct --pad token", &oct).unwrap(), data);
+
+        let bin = run("bin --no-pad -s 1", &data).unwrap();
+        assert_eq!(bin, b"0 1011 11111111");
+        assert_eq!(run("bytes bin --pad token", &bin).unwrap(), data);
+
+        assert!(matches!(
+            run("oct --no-pad", &data),
+            Err(BytaryError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            run("oct --no-pad -s 2", &data),
+            Err(BytaryError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            run("hex --no-pad -s 1", &data),
+            Err(BytaryError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_batch_output_path() {
+        let path = |input: &str, extension: &str| {
+            batch_output_path(Path::new(input), Path::new("out"), extension)
+        };
+        assert_eq!(path("dumps/foo.hex", "bin"), Path::new("out/foo.bin"));
+        assert_eq!(path("foo", "bin"), Path::new("out/foo.bin"));
+        assert_eq!(path("foo.tar.hex", "txt"), Path::new("out/foo.tar.txt"));
+        assert_eq!(path("foo.hex", ""), Path::new("out/foo"));
+    }
+
+    #[test]
+    fn test_prefix_suffix() {
+        let run = |line: &str, input: &[u8]| {
+            let mut output = Vec::new();
+            bytary_cli(args(line), &mut Cursor::new(input), &mut output, false).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(run(r"hex --prefix [ --suffix ]\n", b"Hi"), "[4869]\n");
+        assert_eq!(run(r"hex --prefix [ --suffix ]\n", b""), "");
+        assert_eq!(
+            run(r"hex --prefix [ --suffix ]\n --always-delimit", b""),
+            "[]\n"
+        );
+        assert_eq!(run(r"hex --prefix \t\\\x", b"!"), "\t\\\\x21");
+    }
+
+    #[test]
+    fn test_eol() {
+        let run = |line: &str, input: &[u8]| {
+            let mut output = Vec::new();
+            bytary_cli(args(line), &mut Cursor::new(input), &mut output, false).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(run("hex", b"Hi"), "4869");
+        assert_eq!(run("hex --eol none", b"Hi"), "4869");
+        assert_eq!(run("hex --eol lf", b"Hi"), "4869\n");
+        assert_eq!(run("hex --eol crlf", b"Hi"), "4869\r\n");
+
+        assert_eq!(run("hex --eol lf", b""), "");
+        assert_eq!(run("hex --eol crlf --eol-always", b""), "\r\n");
+
+        // The line break of the last full line is not doubled
+        assert_eq!(run("hex -w 4 --eol lf", b"ABCD"), "4142\n4344\n");
+        assert_eq!(run("hex -w 4 --eol crlf", b"ABCD"), "4142\n4344\n");
+        assert_eq!(run("hex -w 4 --eol lf", b"ABC"), "4142\n43\n");
+
+        // After the suffix
+        assert_eq!(run(r"hex --suffix ] --eol lf", b"Hi"), "4869]\n");
+        assert_eq!(run(r"hex --suffix ]\n --eol crlf", b"Hi"), "4869]\n");
+    }
+
+    #[test]
+    fn test_pem() {
+        let mut output = Vec::new();
+        bytary_cli(
+            args("base64 --pem TEST"),
+            &mut Cursor::new([b'A'; 100]),
+            &mut output,
+            false,
+        )
+        .unwrap();
+        // Same as `head -c 100 /dev/zero | tr '\0' A | openssl base64`, between the PEM lines
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                "-----BEGIN TEST-----\n",
+                "QUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFB\n",
+                "QUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFBQUFB\n",
+                "QUFBQQ==\n",
+                "-----END TEST-----\n",
+            )
+        );
+
+        let mut output = Vec::new();
+        bytary_cli(
+            args("base64 --pem TEST"),
+            &mut Cursor::new([b'A'; 48]),
+            &mut output,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "-----BEGIN TEST-----\n".to_string() + &"QUFB".repeat(16) + "\n-----END TEST-----\n"
+        );
+
+        let result = bytary_cli(
+            args("hex --pem TEST"),
+            &mut Cursor::new(vec![]),
+            &mut Vec::new(),
+            false,
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Invalid argument: --pem requires base64 output, not hex"
+        );
+    }
+
+    #[test]
+    fn test_error_context() {
+        let dir = std::env::temp_dir().join(format!("bytary-context-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let context = |name: &str, data: &[u8], offset: u64| {
+            let path = dir.join(name);
+            fs::write(&path, data).unwrap();
+            let context = error_context(&path, offset).unwrap().unwrap();
+            context.replace(&dir.display().to_string(), "DIR")
+        };
+
+        assert_eq!(
+            context("first", b"1b 3g\nff\n", 4),
+            "DIR/first:1:5\n    1b 3g\n        ^"
+        );
+        assert_eq!(
+            context("last", b"1b 34\n00\nff z0", 12),
+            "DIR/last:3:4\n    ff z0\n       ^"
+        );
+        assert_eq!(
+            context("crlf", b"1b 34\r\n00 0z\r\n", 11),
+            "DIR/crlf:2:5\n    00 0z\n        ^"
+        );
+        assert_eq!(
+            context("tab", b"\t1b\tzz", 4),
+            "DIR/tab:1:5\n    \t1b\tzz\n    \t  \t^"
+        );
+
+        let long = [&b"00".repeat(100)[..], b"zz", &b"11".repeat(100)].concat();
+        let expected = format!(
+            "DIR/long:1:201\n    ...{}zz{}...\n    {}^",
+            "0".repeat(60),
+            "1".repeat(58),
+            " ".repeat(63)
+        );
+        assert_eq!(context("long", &long, 200), expected);
+        let expected = format!("DIR/long:1:2\n    {}...\n     ^", &"0".repeat(120));
+        assert_eq!(context("long", &long, 1), expected);
+
+        let path = dir.join("short");
+        fs::write(&path, b"00").unwrap();
+        assert!(error_context(&path, 3).unwrap().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let error = BytaryError::InvalidInputAt {
+            offset: 4,
+            message: "Invalid hex string: unexpected character 'g' at offset 4".to_string(),
+        };
+        assert_eq!(error.offset(), Some(4));
+        assert_eq!(BytaryError::TrailingData { offset: 9 }.offset(), Some(9));
+        assert_eq!(BytaryError::Cancelled.offset(), None);
+        // Offsets that are not those of the input are dropped, and messages are not parsed
+        let error = error.without_offset();
+        assert_eq!(error.offset(), None);
+        assert_eq!(
+            error.to_string(),
+            "Invalid input data: Invalid hex string: unexpected character 'g' at offset 4"
+        );
+    }
+
+    #[test]
+    fn test_byteswap() {
+        let run = |arguments: &str, input: &[u8]| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(arguments),
+                &mut Cursor::new(input.to_vec()),
+                &mut output,
+                false,
+            )
+            .map(|_| String::from_utf8(output).unwrap())
+            .map_err(|e| e.to_string())
+        };
+        assert_eq!(
+            run("hex hex --byteswap 2", b"0100ffff0200"),
+            Ok("0001ffff0002".into())
+        );
+        assert_eq!(
+            run("hex hex --byteswap 4 --channel 1/2:4", b"00000001 0a0b0c0d"),
+            Ok("0d0c0b0a".into())
+        );
+        // The incomplete word is kept
+        assert_eq!(run("hex hex --byteswap 4", b"010203"), Ok("010203".into()));
+        assert_eq!(
+            run("hex hex --byteswap 4 --warnings-as-errors", b"010203"),
+            Err("1 warning treated as errors".into())
+        );
+        assert!(BytaryArgs::try_parse_from(["bytary", "hex", "--byteswap", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_bit_order() {
+        let run = |arguments: &str| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(arguments),
+                &mut Cursor::new(vec![]),
+                &mut output,
+                false,
+            )
+            .map(|_| String::from_utf8(output).unwrap())
+            .map_err(|e| e.to_string())
+        };
+        assert_eq!(
+            run("bin --data-hex 01c0 --bit-order lsb"),
+            Ok("1000000000000011".into())
+        );
+        assert_eq!(
+            run("hex bin --data 1000000000000011 --bit-order lsb"),
+            Ok("01c0".into())
+        );
+        assert_eq!(
+            run("bin --data-hex 01 --bit-order lsb --no-pad -s 1"),
+            Ok("10000000".into())
+        );
+        assert_eq!(
+            run("bin --data-hex 01 --bit-order msb"),
+            Ok("00000001".into())
+        );
+        assert!(run("hex --data-hex 01 --bit-order lsb").is_err());
+    }
+
+    #[test]
+    fn test_reformat() {
+        let run = |arguments: &str, input: &[u8]| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(arguments),
+                &mut Cursor::new(input.to_vec()),
+                &mut output,
+                false,
+            )
+            .map(|_| String::from_utf8(output).unwrap())
+            .map_err(|e| e.to_string())
+        };
+        // The case and an odd number of digits are kept
+        assert_eq!(
+            run("hex hex --reformat -s 2 -w 4", b"DEADbe\nEF 0"),
+            Ok("DE AD\nbe EF\n0".into())
+        );
+        assert_eq!(
+            run("bin bin --reformat -s 4", b"0100 1\n"),
+            Ok("0100 1".into())
+        );
+        assert_eq!(
+            run("hex hex --reformat --data zz", b""),
+            Err("Invalid input data: unexpected character 'z' at offset 0".into())
+        );
+        assert!(run("hex hex --reformat", "１２".as_bytes()).is_err());
+        assert!(run("bytes hex --reformat", b"").is_err());
+        assert!(run("base64 base64 --reformat", b"").is_err());
+    }
+
+    #[test]
+    fn test_expect() {
+        let run = |arguments: &str, input: &[u8]| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(arguments),
+                &mut Cursor::new(input.to_vec()),
+                &mut output,
+                false,
+            )
+            .map(|code| (code, String::from_utf8(output).unwrap()))
+        };
+        assert_eq!(
+            run("hex --expect 4869", b"Hi").unwrap(),
+            (ExitCode::SUCCESS, "match, 4 bytes\n".into())
+        );
+        assert_eq!(
+            run("hex --expect 4868", b"Hi").unwrap(),
+            (
+                ExitCode::from(1),
+                "mismatch, expected 4 bytes, got 4\n".into()
+            )
+        );
+        assert_eq!(
+            run("hex --expect 486921", b"Hi").unwrap(),
+            (
+                ExitCode::from(1),
+                "mismatch, expected 6 bytes, got 4\n".into()
+            )
+        );
+        assert_eq!(
+            run("hex --expect 48 --space 2", b"Hi").unwrap(),
+            (
+                ExitCode::from(1),
+                "mismatch, expected 2 bytes, got 5\n".into()
+            )
+        );
+        assert!(matches!(
+            run("bytes hex --expect Hi", b"4g"),
+            Err(BytaryError::InvalidInputAt { offset: 1, .. })
+        ));
+
+        let dir = std::env::temp_dir().join(format!("bytary-expect-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("expected");
+        fs::write(&file, [0x00, 0xff]).unwrap();
+        let line = format!("bytes hex --expect-file {}", file.display());
+        assert_eq!(
+            run(&line, b"00ff").unwrap(),
+            (ExitCode::SUCCESS, "match, 2 bytes\n".into())
+        );
+        assert_eq!(run(&line, b"00fe").unwrap().0, ExitCode::from(1));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_max_input_size() {
+        let run = |arguments: &str| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(arguments),
+                &mut Cursor::new(b"SGVsbG8=".to_vec()),
+                &mut output,
+                false,
+            )
+            .map(|_| output)
+        };
+        assert!(matches!(
+            run("bytes base64 --max-input-size 7"),
+            Err(BytaryError::InputLimitExceeded(7))
+        ));
+        assert_eq!(run("bytes base64 --max-input-size 8").unwrap(), b"Hello");
+        assert_eq!(run("bytes base64 --max-input-size 1KiB").unwrap(), b"Hello");
+    }
+
+    #[test]
+    fn test_also() {
+        let parse = |arguments: &str| {
+            BytaryArgs::try_parse_from(format!("bytary {}", arguments).split_whitespace())
+                .map_err(|e| e.to_string())
+        };
+        assert_eq!(
+            parse("hex --also bin:out.bin --also STATS:a:b.txt")
+                .unwrap()
+                .also,
+            [
+                (Format::Bin, PathBuf::from("out.bin")),
+                (Format::Stats, PathBuf::from("a:b.txt"))
+            ]
+        );
+        assert!(parse("hex --also out.bin").is_err());
+        assert!(parse("hex --also nope:out.bin").is_err());
+        assert!(parse("hex --also bin:out.bin --check").is_err());
+        assert!(parse("hex --also bin:out.bin --from-any hex").is_err());
+
+        let run = |arguments: &str| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(arguments),
+                &mut Cursor::new(b"SGVsbG8=".to_vec()),
+                &mut output,
+                false,
+            )
+            .map(|_| output)
+        };
+        assert!(matches!(
+            run("hex base64 --also dec:out.dec"),
+            Err(BytaryError::UnsupportedConversion(
+                Format::Bytes,
+                Format::Dec
+            ))
+        ));
+        #[cfg(unix)]
+        {
+            // The limit applies to the input before it is decoded
+            assert!(matches!(
+                run("hex base64 --also bin:/dev/null --max-input-size 7"),
+                Err(BytaryError::InputLimitExceeded(7))
+            ));
+            assert_eq!(
+                run("hex base64 --also bin:/dev/null --max-input-size 8").unwrap(),
+                b"48656c6c6f"
+            );
+        }
+    }
+
+    #[test]
+    fn test_binary_stdio() {
+        let modes = |line: &str| {
+            let mut descriptors = Vec::new();
+            prepare_stdio(BinaryStdio::of(&args(line)), &mut |fd| {
+                descriptors.push(fd);
+                Ok(())
+            })
+            .unwrap();
+            descriptors
+        };
+        assert_eq!(modes("hex"), [0]);
+        assert_eq!(modes("bytes hex"), [1]);
+        assert_eq!(modes("bytes bytes"), [0, 1]);
+        assert!(modes("hex bin").is_empty());
+        assert_eq!(modes("hex bin --binary-stdio"), [0, 1]);
+        assert_eq!(modes("hex --from-any base64,bytes"), [0]);
+        assert_eq!(modes("bytes --from-any hex,base64"), [1]);
+
+        let result = prepare_stdio(BinaryStdio::of(&args("hex")), &mut |_| {
+            Err(io::Error::other("not a console"))
+        });
+        assert_eq!(result.unwrap_err().to_string(), "not a console");
+    }
+
+    #[test]
+    fn test_size_args() {
+        let parse = |arguments: &str| {
+            BytaryArgs::try_parse_from(format!("bytary {}", arguments).split_whitespace())
+                .map_err(|e| e.to_string())
+        };
+        let args = parse("hex --record-size 4k --max-input-size 1.5M").unwrap();
+        assert_eq!(args.record_size, NonZeroUsize::new(4096));
+        assert_eq!(args.max_input_size, Some(3 << 19));
+        assert_eq!(parse("hex --frame nul").unwrap().max_record_size, 16 << 20);
+        assert_eq!(
+            parse("hex --frame len32 --max-record-size 64KiB")
+                .unwrap()
+                .max_record_size,
+            64 << 10
+        );
+
+        let e = parse("hex --record-size 0").err().unwrap();
+        assert!(e.contains("size must not be 0"), "{}", e);
+        let e = parse("hex --max-input-size=-1").err().unwrap();
+        assert!(e.contains("size must not be negative"), "{}", e);
+        let e = parse("hex --max-input-size 20E").err().unwrap();
+        assert!(e.contains("size '20E' is too large"), "{}", e);
+        let e = parse("hex -o out --split-size 1x").err().unwrap();
+        assert!(e.contains("unknown size suffix 'X'"), "{}", e);
+    }
+
+    #[test]
+    fn test_b64_alphabet() {
+        let run = |arguments: &str, input: &[u8]| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(arguments),
+                &mut Cursor::new(input.to_vec()),
+                &mut output,
+                false,
+            )
+            .map(|_| String::from_utf8(output).unwrap())
+            .map_err(|e| e.to_string())
+        };
+        let crypt = "./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+        assert_eq!(
+            run(&format!("base64 --b64-alphabet {}", crypt), b"Hi"),
+            Ok("G4Y=".into())
+        );
+        assert_eq!(
+            run(&format!("bytes base64 --b64-alphabet {}", crypt), b"G4Y="),
+            Ok("Hi".into())
+        );
+        assert_eq!(run("base64 --no-pad", b"Hi"), Ok("SGk".into()));
+        assert_eq!(run("bytes base64 --no-pad", b"SGk"), Ok("Hi".into()));
+        assert!(run("bytes base64 --no-pad", b"SGk=").is_err());
+        assert_eq!(
+            run("base64 --b64-alphabet ABC", b"Hi"),
+            Err("Invalid argument: --b64-alphabet needs 64 characters, not 3".into())
+        );
+        assert_eq!(
+            run(&format!("hex --b64-alphabet {}", crypt), b"Hi"),
+            Err("Invalid argument: --b64-alphabet only applies to base64 input or output, not bytes to hex".into())
+        );
+    }
+
+    #[test]
+    fn test_expect_eof() {
+        let run = |arguments: &str, input: &[u8]| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(arguments),
+                &mut Cursor::new(input.to_vec()),
+                &mut output,
+                false,
+            )
+            .map(|_| String::from_utf8(output).unwrap())
+            .map_err(|e| e.to_string())
+        };
+        assert_eq!(run("hex base64 --expect-eof", b"SGk=\n"), Ok("4869".into()));
+        assert_eq!(
+            run("hex base64 --expect-eof", b"SGk=\n>"),
+            Err("Trailing data at offset 5".into())
+        );
+        let pem = b"-----BEGIN DATA-----\nSGk=\n-----END DATA-----\n";
+        assert_eq!(run("hex pem --expect-eof", pem), Ok("4869".into()));
+        let pem = [
+            &pem[..],
+            b"-----BEGIN DATA-----\nIQ==\n-----END DATA-----\n",
+        ]
+        .concat();
+        assert_eq!(
+            run("hex pem --expect-eof", &pem),
+            Err("Trailing data at offset 45".into())
+        );
+        assert_eq!(
+            run("hex pem --expect-eof --all-blocks", &pem),
+            Ok("486921".into())
+        );
+        assert_eq!(
+            run("bytes hex --expect-eof", b"4869"),
+            Err(
+                "Invalid argument: --expect-eof only applies to base64 or pem input, not hex"
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let run = |line: &str, input: &str| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(line),
+                &mut Cursor::new(input.as_bytes()),
+                &mut output,
+                false,
+            )
+            .map(|_| output)
+        };
+
+        let hex = "# header: 1b 34\n48 69 // greeting, not ab cd\n21#!\n";
+        assert_eq!(run("bytes hex --comments", hex).unwrap(), b"Hi!");
+        assert!(run("bytes hex", hex).is_err());
+
+        let bin = "// H\n01001000\n01101001 # 00100001\n";
+        assert_eq!(run("bytes bin --comments", bin).unwrap(), b"Hi");
+        assert!(run("bytes bin", bin).is_err());
+        assert!(run("bytes bin --check", bin).is_err());
+        assert!(run("bytes bin --check --comments", bin).is_ok());
+        assert!(run("bytes hex --comments --check", hex).is_ok());
+
+        // Stripping comments does not hide invalid characters around them
+        assert!(run("bytes hex --comments", "48 6g // 69").is_err());
+        assert!(run("bytes hex --comments", "48 / 69").is_err());
+
+        assert_eq!(
+            run("hex bytes --comments", "").unwrap_err().to_string(),
+            "Invalid argument: --comments only applies to hex, bin or oct input, not bytes"
+        );
+    }
+
+    #[test]
+    fn test_window() {
+        let mut output = Vec::new();
+        bytary_cli(
+            args("stats hex --window 2"),
+            &mut Cursor::new(b"0000 0102 03"),
+            &mut output,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            b"00000000 0.0000\n00000002 1.0000\n00000004 0.0000\n"
+        );
+
+        let result = bytary_cli(
+            args("hex --window 2"),
+            &mut Cursor::new(vec![]),
+            &mut Vec::new(),
+            false,
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Invalid argument: --window only applies to stats output, not hex"
+        );
+    }
+
+    #[test]
+    fn test_byteset() {
+        let run = |line: &str| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(line),
+                &mut Cursor::new(b"48 69 00 01 02 49"),
+                &mut output,
+                false,
+            )
+            .map(|_| String::from_utf8(output).unwrap())
+        };
+        assert_eq!(
+            run("byteset hex").unwrap(),
+            "Distinct: 6\nValues: 00-02, 48-49, 69\n"
+        );
+        assert_eq!(
+            run("byteset hex --byteset-ascii").unwrap(),
+            "Distinct: 6\nValues: 00-02, 48-49, 69\nASCII: HIi\n"
+        );
+        assert_eq!(
+            run("stats hex --byteset-ascii").unwrap_err().to_string(),
+            "Invalid argument: --byteset-ascii only applies to byteset output, not stats"
+        );
+    }
+
+    #[test]
+    fn test_check_layout() {
+        for space in 0..=8 {
+            for wrap in 0..=24 {
+                let whole = space == 0 || wrap == 0 || wrap % space == 0;
+                assert_eq!(
+                    check_layout(space, wrap).is_ok(),
+                    whole,
+                    "--space {} --wrap {}",
+                    space,
+                    wrap
+                );
+            }
+        }
+        let error = |space, wrap| check_layout(space, wrap).unwrap_err().to_string();
+        assert_eq!(
+            error(3, 8),
+            "Invalid argument: lines of 8 characters do not hold a whole number of --space 3 groups, \
+            use --space 2 or 4, lines of 6 or 9 characters, or --allow-odd-formatting"
+        );
+        // No line would hold a whole group
+        assert_eq!(
+            error(3, 2),
+            "Invalid argument: lines of 2 characters do not hold a whole number of --space 3 groups, \
+            use --space 2, lines of 3 characters, or --allow-odd-formatting"
+        );
+        assert!(error(4, 7).contains("use --space 1 or 7, lines of 4 or 8 characters"));
+
+        let run = |line: &str| {
+            let mut output = Vec::new();
+            bytary_cli(args(line), &mut Cursor::new(b"Hello"), &mut output, false)
+                .map(|_| String::from_utf8(output).unwrap())
+        };
+        assert!(matches!(
+            run("hex -s 3 -w 2"),
+            Err(BytaryError::InvalidArgument(_))
+        ));
+        assert!(run("hex -s 3 --wrap-bytes 2").is_err());
+        assert_eq!(
+            run("hex -s 3 -w 2 --allow-odd-formatting").unwrap(),
+            "48\n6 5\n6c\n6c\n6 f\n"
+        );
+    }
+
+    #[test]
+    fn test_spaces() {
+        // As many as FormattedWriter writes
+        for size in 0..40 {
+            for space in 0..6 {
+                for wrap in 0..10 {
+                    let mut formatted = FormattedWriter::new(Vec::new(), space, wrap);
+                    io::Write::write_all(&mut formatted, &vec![b'a'; size]).unwrap();
+                    let output = formatted.into_inner().unwrap();
+                    assert_eq!(
+                        spaces(size as u64, space, wrap),
+                        output.iter().filter(|&&byte| byte == b' ').count() as u64,
+                        "{} bytes, --space {} --wrap {}",
+                        size,
+                        space,
+                        wrap
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrap_bytes() {
+        let run = |line: &str| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(line),
+                &mut Cursor::new(b"Hello, World!"),
+                &mut output,
+                false,
+            )
+            .map(|_| String::from_utf8(output).unwrap())
+        };
+
+        // Every line has the output of 6 input bytes
+        assert_eq!(
+            run("hex --wrap-bytes 6").unwrap(),
+            "48656c6c6f2c\n20576f726c64\n21"
+        );
+        assert_eq!(
+            run("bin --wrap-bytes 6").unwrap(),
+            concat!(
+                "010010000110010101101100011011000110111100101100\n",
+                "001000000101011101101111011100100110110001100100\n",
+                "00100001"
+            )
+        );
+        assert_eq!(
+            run("base64 --wrap-bytes 6").unwrap(),
+            "SGVsbG8s\nIFdvcmxk\nIQ=="
+        );
+        // Spaces do not count
+        assert_eq!(
+            run("hex --wrap-bytes 4 --space 2").unwrap(),
+            "48 65 6c 6c\n6f 2c 20 57\n6f 72 6c 64\n21"
+        );
+
+        assert_eq!(
+            run("base64 --wrap-bytes 4").unwrap_err().to_string(),
+            "Invalid argument: --wrap-bytes for base64 output must be a multiple of 3"
+        );
+        assert_eq!(
+            run("stats --wrap-bytes 4").unwrap_err().to_string(),
+            "Invalid argument: --wrap-bytes only applies to bin, hex, oct or base64 output, not stats"
+        );
+        assert!(
+            BytaryArgs::try_parse_from(["bytary", "hex", "--wrap", "8", "--wrap-bytes", "4"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_with_ascii() {
+        let run = |line: &str, input: &[u8]| {
+            let mut output = Vec::new();
+            bytary_cli(args(line), &mut Cursor::new(input), &mut output, false)
+                .map(|_| String::from_utf8(output).unwrap())
+        };
+
+        assert_eq!(
+            run(
+                "hex --wrap-bytes 6 --space 2 --with-ascii",
+                b"Hello,\tWorld!"
+            )
+            .unwrap(),
+            concat!(
+                "48 65 6c 6c 6f 2c  Hello,\n",
+                "09 57 6f 72 6c 64  .World\n",
+                "21                 !",
+            )
+        );
+        assert_eq!(
+            run("bin --wrap-bytes 2 --with-ascii", b"AB\x00").unwrap(),
+            "0100000101000010  AB\n00000000          ."
+        );
+        assert_eq!(
+            run("oct hex --wrap-bytes 2 --with-ascii", b"41 00 1f").unwrap(),
+            "101000  A.\n037     ."
+        );
+        assert_eq!(
+            run("base64 --wrap-bytes 3 --with-ascii", b"abc")
+                .unwrap_err()
+                .to_string(),
+            "Invalid argument: --with-ascii only applies to bin, hex or oct output, not base64"
+        );
+        assert!(BytaryArgs::try_parse_from(["bytary", "hex", "--with-ascii"]).is_err());
+    }
+
+    #[test]
+    fn test_completions() {
+        let mut output = Vec::new();
+        bytary_cli(
+            args("--completions bash"),
+            &mut Cursor::new(vec![]),
+            &mut output,
+            false,
+        )
+        .unwrap();
+        let script = String::from_utf8(output).unwrap();
+        assert!(script.contains("_bytary()"));
+        assert!(script.contains(
+            "bytes bin hex hexswap oct dec leb128 rle base64 pem utf8 utf16le utf16be codepoints stats byteset"
+        ));
+
+        // Format names are case-insensitive, like Format::try_from
+        assert_eq!(args("HEX Bytes").to, Format::Hex);
+    }
+
+    #[test]
+    fn test_color() {
+        let run = |line: &str, is_tty: bool| {
+            let mut output = Vec::new();
+            bytary_cli(
+                args(line),
+                &mut Cursor::new(b"A\0\xff\x80\x01"),
+                &mut output,
+                is_tty,
+            )
+            .map(|_| String::from_utf8(output).unwrap())
+        };
+
+        assert_eq!(
+            run("hex --space 2 --color always", false).unwrap(),
+            concat!(
+                "\x1b[32m41\x1b[0m \x1b[2m00\x1b[0m \x1b[31mff\x1b[0m ",
+                "\x1b[33m80\x1b[0m 01"
+            )
+        );
+        // The spaces and line breaks are counted without the escape sequences
+        assert_eq!(
+            run("bin --space 4 --wrap 8 --color always", false).unwrap(),
+            concat!(
+                "\x1b[32m0100\x1b[0m \x1b[32m0001\x1b[0m\n",
+                "\x1b[2m0000\x1b[0m \x1b[2m0000\x1b[0m\n",
+                "\x1b[31m1111\x1b[0m \x1b[31m1111\x1b[0m\n",
+                "\x1b[33m1000\x1b[0m \x1b[33m0000\x1b[0m\n",
+                "0000 0001\n"
+            )
+        );
+        assert_eq!(
+            run("oct --no-pad --space 1 --color always", false).unwrap(),
+            "\x1b[32m101\x1b[0m \x1b[2m0\x1b[0m \x1b[31m377\x1b[0m \x1b[33m200\x1b[0m 1"
+        );
+
+        // auto only colors a terminal, and never is identical to the plain output
+        let plain = run("hex --space 2", false).unwrap();
+        assert_eq!(plain, "41 00 ff 80 01");
+        assert_eq!(run("hex --space 2 --color never", true).unwrap(), plain);
+        assert_eq!(run("hex --space 2 --color auto", false).unwrap(), plain);
+        assert_ne!(run("hex --space 2", true).unwrap(), plain);
+        assert_eq!(run("base64", true).unwrap(), "QQD/gAE=");
+
+        assert_eq!(
+            run("base64 --color always", false).unwrap_err().to_string(),
+            "Invalid argument: --color only applies to hex, bin or oct output, not base64"
+        );
+    }
+
+    #[test]
+    fn test_line_buffered() {
+        use std::sync::{Arc, Mutex, mpsc};
+        use std::time::{Duration, Instant};
+
+        struct ChannelReader(mpsc::Receiver<Vec<u8>>);
+        impl io::Read for ChannelReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let chunk = self.0.recv().unwrap_or_default();
+                buf[..chunk.len()].copy_from_slice(&chunk);
+                Ok(chunk.len())
+            }
+        }
+        struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+        impl io::Write for SharedWriter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut writer = SharedWriter(output.clone());
+        let thread = std::thread::spawn(move || {
+            bytary_cli(
+                args("hex -s 2 --line-buffered --flush-interval 1000 --prefix <"),
+                &mut ChannelReader(receiver),
+                &mut writer,
+                false,
+            )
+            .unwrap();
+        });
+
+        // The line is not complete, but the input paused
+        sender.send(b"Hi".to_vec()).unwrap();
+        let start = Instant::now();
+        while *output.lock().unwrap() != b"<48 69" {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "output held back"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        sender.send(b"!".to_vec()).unwrap();
+        drop(sender);
+        thread.join().unwrap();
+        assert_eq!(*output.lock().unwrap(), b"<48 69 21");
     }
 }
@@ -0,0 +1,157 @@
+use crate::convert::Converter;
+use crate::error::BytaryResult;
+use crate::utils::flush_if_short;
+use std::io::{Read, Write};
+
+#[cfg(test)]
+mod test;
+
+/// Size of the chunks read from the input
+const CHUNK_SIZE: usize = 1024;
+
+/// The order in which the bits of a byte are numbered
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BitOrder {
+    /// Bit 0 is the most significant bit of the first byte
+    #[default]
+    MsbFirst,
+    /// Bit 0 is the least significant bit of the first byte
+    LsbFirst,
+}
+
+/// Extracts `len_bits` bits from the stream, starting at bit `start_bit`, see [`BitSlice`].
+///
+/// ```rust
+/// use bytary::bits::{BitOrder, bit_slice};
+/// use bytary::convert::Converter;
+///
+/// // bits 4..=13 of 0xab 0xcd are 1011 110011
+/// let mut output = Vec::new();
+/// bit_slice(4, 10, BitOrder::MsbFirst)
+///     .convert(&mut &[0xab, 0xcd][..], &mut output)
+///     .unwrap();
+/// assert_eq!(output, [0b1011_1100, 0b1100_0000]);
+/// ```
+pub fn bit_slice(start_bit: u64, len_bits: u64, bit_order: BitOrder) -> BitSlice {
+    BitSlice {
+        start_bit,
+        len_bits,
+        bit_order,
+    }
+}
+
+/// A [`Converter`] that writes a range of the input bits, packed into bytes, streaming.
+///
+/// Bits are numbered and packed in `bit_order`. If the length is not a multiple of 8,
+/// the last byte is padded with `8 - len_bits % 8` zero bits after the last extracted bit,
+/// so on its least significant side with [`BitOrder::MsbFirst`] and on its most significant side with [`BitOrder::LsbFirst`].
+///
+/// If the input ends before the range does, only the available bits are written.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BitSlice {
+    pub start_bit: u64,
+    pub len_bits: u64,
+    pub bit_order: BitOrder,
+}
+
+impl Converter for BitSlice {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut skip = self.start_bit;
+        let mut remaining = self.len_bits;
+        let mut bits = BitBuffer::new(self.bit_order);
+        let mut buffer = [0u8; CHUNK_SIZE];
+        let mut packed = Vec::with_capacity(CHUNK_SIZE);
+
+        while remaining > 0 {
+            let length = input.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            for &byte in &buffer[..length] {
+                if skip >= 8 {
+                    skip -= 8;
+                    continue;
+                }
+                let offset = skip as u32;
+                skip = 0;
+                let count = remaining.min(8 - offset as u64) as u32;
+                remaining -= count as u64;
+                bits.push(byte, offset, count, &mut packed);
+                if remaining == 0 {
+                    break;
+                }
+            }
+            output.write_all(&packed)?;
+            packed.clear();
+            flush_if_short(output, length, buffer.len())?;
+        }
+        bits.finish(&mut packed);
+        output.write_all(&packed)?;
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        "bit_slice"
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// Bits waiting for enough others to fill a byte
+struct BitBuffer {
+    order: BitOrder,
+    /// The pending bits, the first of them in the most significant position with [`BitOrder::MsbFirst`]
+    /// and in the least significant position with [`BitOrder::LsbFirst`]
+    bits: u16,
+    count: u32,
+}
+
+impl BitBuffer {
+    fn new(order: BitOrder) -> Self {
+        Self {
+            order,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    /// Appends `count` bits of `byte`, skipping the first `offset` of them, writing full bytes to `packed`
+    fn push(&mut self, byte: u8, offset: u32, count: u32, packed: &mut Vec<u8>) {
+        let mask = (1u16 << count) - 1;
+        let byte = byte as u16;
+        match self.order {
+            BitOrder::MsbFirst => {
+                self.bits = (self.bits << count) | ((byte >> (8 - offset - count)) & mask);
+                self.count += count;
+                if self.count >= 8 {
+                    self.count -= 8;
+                    packed.push((self.bits >> self.count) as u8);
+                    self.bits &= (1 << self.count) - 1;
+                }
+            }
+            BitOrder::LsbFirst => {
+                self.bits |= ((byte >> offset) & mask) << self.count;
+                self.count += count;
+                if self.count >= 8 {
+                    self.count -= 8;
+                    packed.push(self.bits as u8);
+                    self.bits >>= 8;
+                }
+            }
+        }
+    }
+
+    /// Writes the pending bits to `packed` as a last byte padded with zeros
+    fn finish(&mut self, packed: &mut Vec<u8>) {
+        if self.count == 0 {
+            return;
+        }
+        let byte = match self.order {
+            BitOrder::MsbFirst => (self.bits << (8 - self.count)) as u8,
+            BitOrder::LsbFirst => self.bits as u8,
+        };
+        packed.push(byte);
+        self.bits = 0;
+        self.count = 0;
+    }
+}
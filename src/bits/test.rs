@@ -0,0 +1,88 @@
+use crate::bits::{BitOrder, bit_slice};
+use crate::convert::Converter;
+use crate::error::BytaryResult;
+
+fn slice(
+    input: &[u8],
+    start_bit: u64,
+    len_bits: u64,
+    bit_order: BitOrder,
+) -> BytaryResult<Vec<u8>> {
+    let mut output = Vec::new();
+    bit_slice(start_bit, len_bits, bit_order).convert(&mut &input[..], &mut output)?;
+    Ok(output)
+}
+
+/// The bits of `input` as `0` and `1`, in `bit_order`
+fn bit_string(input: &[u8], bit_order: BitOrder) -> String {
+    input
+        .iter()
+        .map(|byte| match bit_order {
+            BitOrder::MsbFirst => format!("{:08b}", byte),
+            BitOrder::LsbFirst => format!("{:08b}", byte.reverse_bits()),
+        })
+        .collect()
+}
+
+#[test]
+fn test_mid_byte() -> BytaryResult<()> {
+    // 1010_1011 1100_1101 1110_1111
+    let input = [0xab, 0xcd, 0xef];
+    assert_eq!(
+        slice(&input, 4, 10, BitOrder::MsbFirst)?,
+        [0b1011_1100, 0b1100_0000]
+    );
+    assert_eq!(slice(&input, 3, 8, BitOrder::MsbFirst)?, [0b0101_1110]);
+    assert_eq!(slice(&input, 13, 6, BitOrder::MsbFirst)?, [0b1011_1100]);
+    assert_eq!(slice(&input, 0, 24, BitOrder::MsbFirst)?, input);
+    Ok(())
+}
+
+#[test]
+fn test_lsb_first() -> BytaryResult<()> {
+    let input = [0xab, 0xcd];
+    // Bits 4..=13 counted from the least significant bit: 0101 of 0xab, then 101100 of 0xcd
+    assert_eq!(
+        slice(&input, 4, 10, BitOrder::LsbFirst)?,
+        [0b1101_1010, 0b0000_0000]
+    );
+    assert_eq!(slice(&input, 0, 16, BitOrder::LsbFirst)?, input);
+
+    // Both orders agree with the bits written out in that order
+    for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+        let output = slice(&input, 5, 7, order)?;
+        assert_eq!(
+            bit_string(&output, order),
+            format!("{}0", &bit_string(&input, order)[5..12])
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_chunk_boundary() -> BytaryResult<()> {
+    let input = (0..3000).map(|i| (i * 7) as u8).collect::<Vec<u8>>();
+    for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+        let bits = bit_string(&input, order);
+        // Crosses the boundary between the first two chunks of 1024 bytes
+        let (start, len) = (1020 * 8 + 3, 70);
+        let output = slice(&input, start as u64, len as u64, order)?;
+        assert_eq!(output.len(), 9);
+        assert_eq!(
+            &bit_string(&output, order)[..len],
+            &bits[start..start + len]
+        );
+        assert!(bit_string(&output, order)[len..].chars().all(|c| c == '0'));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_empty() -> BytaryResult<()> {
+    assert!(slice(&[0xff, 0xff], 3, 0, BitOrder::MsbFirst)?.is_empty());
+    assert!(slice(&[], 0, 8, BitOrder::MsbFirst)?.is_empty());
+    // The range is cut at the end of the input
+    assert_eq!(slice(&[0xff], 4, 100, BitOrder::MsbFirst)?, [0xf0]);
+    assert!(slice(&[0xff], 8, 8, BitOrder::MsbFirst)?.is_empty());
+    Ok(())
+}
@@ -1,34 +1,998 @@
-use crate::convert::ConversionGraph;
+use crate::bits::BitOrder;
+use crate::convert::{ConversionGraph, Converter, EdgeInfo, StreamingClass};
+use crate::digits::{digit_value, is_bin_digit, is_dec_digit, is_hex_digit, is_oct_digit};
 use crate::error::{BytaryError, BytaryResult};
-use crate::format::Format;
-use regex::Regex;
+use crate::fast;
+use crate::format::{FORMATS, Format};
+use crate::rle::{BytesToRle, RleToBytes};
+use crate::utils::{
+    RetryingReader, flush_if_short, is_ignorable, is_separator, plural, require_eof,
+};
+use crate::validate::{InvalidInput, char_at};
+use crate::warning;
+use crate::warning::Warning;
+use base64::Engine;
+use base64::alphabet::Alphabet;
+use base64::engine::DecodePaddingMode;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD};
 use std::io;
 use std::io::{Read, Write};
 
 #[cfg(test)]
 mod test;
 
+/// How decoders handle input whose digit count does not fill a whole number of bytes
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Padding {
+    /// Reject the input with [`BytaryError::InvalidInputData`]
+    #[default]
+    Strict,
+    /// Insert zeros before the first digit of the input
+    Left,
+    /// Append zeros after the last digit of the input
+    Right,
+    /// Insert zeros before each token of the input, between whitespace or [separators](is_separator),
+    /// so that tokens with fewer digits than a byte needs are decoded independently.
+    ///
+    /// The zeros are reported as a warning, except those of tokens without a leading zero,
+    /// like the bytes of unpadded output, see [`OctOptions::pad`].
+    Token,
+}
+
+/// Options of the bytes to oct encoder
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OctOptions {
+    /// Write every byte as exactly 3 digits.
+    ///
+    /// Otherwise every byte is written with as few digits as possible,
+    /// separated by spaces so that the output can be decoded with [`Padding::Token`].
+    pub pad: bool,
+}
+
+impl Default for OctOptions {
+    fn default() -> Self {
+        Self { pad: true }
+    }
+}
+
+/// Options of the bytes to bin encoder
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BinOptions {
+    /// Write every byte as exactly 8 digits.
+    ///
+    /// Otherwise every byte is written with as few digits as possible,
+    /// separated by spaces so that the output can be decoded with [`Padding::Token`].
+    pub pad: bool,
+    /// Write the bits of every byte from the most or the least significant one.
+    ///
+    /// Decoding gives the same bytes back if [`BinToHex::bit_order`] is the same.
+    pub bit_order: BitOrder,
+}
+
+impl Default for BinOptions {
+    fn default() -> Self {
+        Self {
+            pad: true,
+            bit_order: BitOrder::MsbFirst,
+        }
+    }
+}
+
+/// Options of the bytes to stats encoder
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StatsOptions {
+    /// Instead of a full report, write the entropy of every window of this many bytes, one per line
+    pub window: Option<usize>,
+}
+
+/// Options of the bytes to byteset encoder
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ByteSetOptions {
+    /// Also write the printable ASCII characters that occur
+    pub ascii: bool,
+}
+
+/// Options of the base64 decoder
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Base64Options {
+    /// Fail with [`BytaryError::TrailingData`] if anything but whitespace follows the padding
+    pub expect_eof: bool,
+    pub alphabet: Base64Alphabet,
+}
+
+/// The 64 characters of base64 digits, and the padding character, see [`base64_with_alphabet`].
+///
+/// The default is the standard alphabet `A-Za-z0-9+/`, padded with `=`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Base64Alphabet {
+    chars: [u8; 64],
+    pad: Option<u8>,
+}
+
+impl Default for Base64Alphabet {
+    fn default() -> Self {
+        let mut chars = [0u8; 64];
+        chars.copy_from_slice(b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/");
+        Self {
+            chars,
+            pad: Some(b'='),
+        }
+    }
+}
+
+impl Base64Alphabet {
+    /// The characters of the digits, in order of their value
+    pub fn chars(&self) -> &[u8; 64] {
+        &self.chars
+    }
+
+    /// The padding character, or `None` if the output is not padded
+    pub fn pad(&self) -> Option<u8> {
+        self.pad
+    }
+
+    /// An engine for the digits, which neither writes nor accepts padding
+    fn engine(&self) -> GeneralPurpose {
+        let chars = std::str::from_utf8(&self.chars).expect("the alphabet is ASCII");
+        let alphabet = Alphabet::new(chars).expect("the alphabet was validated");
+        let config = GeneralPurposeConfig::new()
+            .with_encode_padding(false)
+            .with_decode_padding_mode(DecodePaddingMode::RequireNone);
+        GeneralPurpose::new(&alphabet, config)
+    }
+}
+
+/// A base64 alphabet of the 64 digit characters `alphabet`, padded with `pad`, or not padded with `None`.
+///
+/// Characters must be distinct printable ASCII, and `=` may only be the padding.
+///
+/// ```rust
+/// use bytary::builtins::{BytesToBase64, base64_with_alphabet};
+/// use bytary::convert::Converter;
+///
+/// // The alphabet of crypt(3)
+/// let alphabet = *b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+/// let alphabet = base64_with_alphabet(alphabet, None).unwrap();
+/// let mut output = Vec::new();
+/// BytesToBase64 { alphabet }.convert(&mut &b"Hi"[..], &mut output).unwrap();
+/// assert_eq!(output, b"G4Y");
+///
+/// assert!(base64_with_alphabet([b'A'; 64], None).is_err());
+/// ```
+pub fn base64_with_alphabet(alphabet: [u8; 64], pad: Option<u8>) -> BytaryResult<Base64Alphabet> {
+    let invalid = |message: String| BytaryError::InvalidArgument(message).err();
+    for (i, &c) in alphabet.iter().enumerate() {
+        if !c.is_ascii_graphic() {
+            return invalid(format!(
+                "the base64 alphabet must be printable ASCII, not byte 0x{:02x} at {}",
+                c, i
+            ));
+        }
+        if c == b'=' {
+            return invalid("'=' can only be the padding of base64, not a digit".to_string());
+        }
+        if alphabet[..i].contains(&c) {
+            return invalid(format!(
+                "the base64 alphabet has '{}' more than once",
+                c as char
+            ));
+        }
+    }
+    if let Some(pad) = pad {
+        if !pad.is_ascii_graphic() {
+            return invalid(format!(
+                "the base64 padding must be printable ASCII, not byte 0x{:02x}",
+                pad
+            ));
+        }
+        if alphabet.contains(&pad) {
+            return invalid(format!(
+                "the base64 padding '{}' is also in the alphabet",
+                pad as char
+            ));
+        }
+    }
+    Ok(Base64Alphabet {
+        chars: alphabet,
+        pad,
+    })
+}
+
+/// Options of the pem encoder and decoder
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PemOptions {
+    /// Label of the blocks written by the encoder, like `CERTIFICATE`
+    pub label: String,
+    /// Decode every block in the input and concatenate them, instead of only the first one
+    pub all_blocks: bool,
+    /// Fail with [`BytaryError::TrailingData`] if anything but whitespace follows the last
+    /// decoded `END` line
+    pub expect_eof: bool,
+}
+
+impl Default for PemOptions {
+    fn default() -> Self {
+        Self {
+            label: "DATA".to_string(),
+            all_blocks: false,
+            expect_eof: false,
+        }
+    }
+}
+
+/// Create a bytes to oct converter with the given options
+pub fn bytes_to_oct_with_options(
+    options: OctOptions,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    move |input, output| BytesToOct { options }.convert(input, output)
+}
+
+/// Create a bytes to bin converter with the given options
+pub fn bytes_to_bin_with_options(
+    options: BinOptions,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    move |input, output| BytesToBin { options }.convert(input, output)
+}
+
+/// Create a bytes to stats converter with the given options
+pub fn bytes_to_stats_with_options(
+    options: StatsOptions,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    move |input, output| BytesToStats { options }.convert(input, output)
+}
+
+/// Create a bytes to byteset converter with the given options
+pub fn bytes_to_byteset_with_options(
+    options: ByteSetOptions,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    move |input, output| BytesToByteSet { options }.convert(input, output)
+}
+
+/// Create a base64 to bytes converter with the given options
+pub fn base64_to_bytes_with_options(
+    options: Base64Options,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    move |input, output| Base64ToBytes { options }.convert(input, output)
+}
+
+/// Create a bytes to pem converter with the given options
+pub fn bytes_to_pem_with_options(
+    options: PemOptions,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    let converter = BytesToPem { options };
+    move |input, output| converter.convert(input, output)
+}
+
+/// Create a pem to bytes converter with the given options
+pub fn pem_to_bytes_with_options(
+    options: PemOptions,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    let converter = PemToBytes { options };
+    move |input, output| converter.convert(input, output)
+}
+
+/// The [`Converter`] of [`bytes_to_bin`], or of [`bytes_to_bin_with_options`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BytesToBin {
+    pub options: BinOptions,
+}
+
+impl Converter for BytesToBin {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut reversed;
+        let input = match self.options.bit_order {
+            BitOrder::MsbFirst => input,
+            BitOrder::LsbFirst => {
+                reversed = BitReversed(input);
+                &mut reversed as &mut dyn Read
+            }
+        };
+        match self.options.pad {
+            true => bytes_to_bin(input, output),
+            false => bytes_to_unpadded(input, output, |byte| format!("{:b}", byte)),
+        }
+    }
+    fn name(&self) -> &str {
+        "bytes_to_bin"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        self.options.pad.then_some(8.0)
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`bin_to_hex_with_padding`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BinToHex {
+    pub padding: Padding,
+    /// The order of the bits of every byte in the input, see [`BinOptions::bit_order`]
+    pub bit_order: BitOrder,
+}
+
+impl Converter for BinToHex {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        bin_to_hex_padded(input, output, self.padding, self.bit_order)
+    }
+    fn name(&self) -> &str {
+        "bin_to_hex"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        Some(0.25)
+    }
+}
+
+/// The [`Converter`] of [`bytes_to_oct`], or of [`bytes_to_oct_with_options`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BytesToOct {
+    pub options: OctOptions,
+}
+
+impl Converter for BytesToOct {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        match self.options.pad {
+            true => bytes_to_oct(input, output),
+            false => bytes_to_unpadded(input, output, |byte| format!("{:o}", byte)),
+        }
+    }
+    fn name(&self) -> &str {
+        "bytes_to_oct"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        self.options.pad.then_some(3.0)
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`oct_to_bytes_with_padding`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct OctToBytes {
+    pub padding: Padding,
+    /// Keep the low 8 bits of groups above `377` instead of rejecting them, reporting a [`Warning::Masked`]
+    pub mask: bool,
+}
+
+impl Converter for OctToBytes {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        oct_to_bytes_padded(input, output, self.padding, self.mask)
+    }
+    fn name(&self) -> &str {
+        "oct_to_bytes"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        Some(1.0 / 3.0)
+    }
+}
+
+/// The [`Converter`] of [`bytes_to_hex`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BytesToHex;
+
+impl Converter for BytesToHex {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        bytes_to_hex(input, output)
+    }
+    fn name(&self) -> &str {
+        "bytes_to_hex"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        Some(2.0)
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`hex_to_bytes`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct HexToBytes;
+
+impl Converter for HexToBytes {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        hex_to_bytes(input, output)
+    }
+    fn name(&self) -> &str {
+        "hex_to_bytes"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        Some(0.5)
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`bytes_to_hexswap`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BytesToHexSwap;
+
+impl Converter for BytesToHexSwap {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        bytes_to_hexswap(input, output)
+    }
+    fn name(&self) -> &str {
+        "bytes_to_hexswap"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        Some(2.0)
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`hexswap_to_bytes`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct HexSwapToBytes;
+
+impl Converter for HexSwapToBytes {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        hexswap_to_bytes(input, output)
+    }
+    fn name(&self) -> &str {
+        "hexswap_to_bytes"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        Some(0.5)
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`swap_hex_nibbles`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SwapHexNibbles;
+
+impl Converter for SwapHexNibbles {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        swap_hex_nibbles(input, output)
+    }
+    fn name(&self) -> &str {
+        "swap_hex_nibbles"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        Some(1.0)
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`bytes_to_base64`], or of any other [`Base64Alphabet`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BytesToBase64 {
+    pub alphabet: Base64Alphabet,
+}
+
+impl Converter for BytesToBase64 {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut reader = io::BufReader::new(RetryingReader::new(input));
+        let engine = self.alphabet.engine();
+        let mut writer = base64::write::EncoderWriter::new(&mut *output, &engine);
+        let mut buffer = [0u8; 1024];
+        let mut total = 0;
+
+        loop {
+            let length = reader.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            total += length;
+            writer.write_all(&buffer[..length])?;
+            // Bytes that do not fill a group of 3 are held back until more input arrives
+            flush_if_short(&mut writer, length, buffer.len())?;
+        }
+        let output = writer.finish()?;
+        if let Some(pad) = self.alphabet.pad {
+            output.write_all(&[pad; 2][..(3 - total % 3) % 3])?;
+        }
+        output.flush()?;
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        "bytes_to_base64"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        Some(4.0 / 3.0)
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`base64_to_bytes`], or of [`base64_to_bytes_with_options`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Base64ToBytes {
+    pub options: Base64Options,
+}
+
+impl Converter for Base64ToBytes {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut buffer = Vec::new();
+        io::BufReader::new(RetryingReader::new(input)).read_to_end(&mut buffer)?;
+        let alphabet = self.options.alphabet;
+        if let (true, Some(pad)) = (self.options.expect_eof, alphabet.pad) {
+            // The data ends with its padding, if it has any
+            if let Some(start) = buffer.iter().position(|&b| b == pad) {
+                let end = start + buffer[start..].iter().take_while(|&&b| b == pad).count();
+                require_eof(&mut &buffer[end..], end as u64)?;
+                buffer.truncate(end);
+            }
+        }
+        decode_base64(buffer, &alphabet, output)
+    }
+    fn name(&self) -> &str {
+        "base64_to_bytes"
+    }
+    fn expansion_ratio(&self) -> Option<f64> {
+        Some(0.75)
+    }
+}
+
+/// The [`Converter`] of [`bytes_to_pem`], or of [`bytes_to_pem_with_options`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BytesToPem {
+    pub options: PemOptions,
+}
+
+impl Converter for BytesToPem {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        bytes_to_pem_labeled(input, output, &self.options.label)
+    }
+    fn name(&self) -> &str {
+        "bytes_to_pem"
+    }
+}
+
+/// The [`Converter`] of [`pem_to_bytes`], or of [`pem_to_bytes_with_options`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PemToBytes {
+    pub options: PemOptions,
+}
+
+impl Converter for PemToBytes {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        pem_to_bytes_blocks(input, output, &self.options)
+    }
+    fn name(&self) -> &str {
+        "pem_to_bytes"
+    }
+}
+
+/// The [`Converter`] of [`bytes_to_stats`], or of [`bytes_to_stats_with_options`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BytesToStats {
+    pub options: StatsOptions,
+}
+
+impl Converter for BytesToStats {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        match self.options.window {
+            Some(window) if window > 0 => bytes_to_window_entropy(input, output, window),
+            _ => bytes_to_stats(input, output),
+        }
+    }
+    fn name(&self) -> &str {
+        "bytes_to_stats"
+    }
+    /// The entropy of each window is written once the window is full
+    fn is_streaming(&self) -> bool {
+        self.options.window.is_some_and(|window| window > 0)
+    }
+    /// Only counts of the byte values are kept
+    fn streaming_class(&self) -> StreamingClass {
+        StreamingClass::Streaming
+    }
+}
+
+/// The [`Converter`] of [`bytes_to_byteset`], or of [`bytes_to_byteset_with_options`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BytesToByteSet {
+    pub options: ByteSetOptions,
+}
+
+impl Converter for BytesToByteSet {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut input = RetryingReader::new(input);
+        let mut present = [0u64; 4];
+        let mut buffer = [0u8; 1024];
+        loop {
+            let length = input.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            for &byte in &buffer[..length] {
+                present[byte as usize / 64] |= 1 << (byte % 64);
+            }
+        }
+        let is_present = |byte: u8| present[byte as usize / 64] & (1 << (byte % 64)) != 0;
+
+        // Runs of consecutive values, as (first, last)
+        let mut ranges: Vec<(u8, u8)> = Vec::new();
+        for byte in (0..=255u8).filter(|&byte| is_present(byte)) {
+            match ranges.last_mut() {
+                Some((_, last)) if *last + 1 == byte => *last = byte,
+                _ => ranges.push((byte, byte)),
+            }
+        }
+        let distinct = present.iter().map(|bits| bits.count_ones()).sum::<u32>();
+        let values = match ranges.is_empty() {
+            true => "none".to_string(),
+            false => ranges
+                .iter()
+                .map(|&(first, last)| match first == last {
+                    true => format!("{:02x}", first),
+                    false => format!("{:02x}-{:02x}", first, last),
+                })
+                .collect::<Vec<String>>()
+                .join(", "),
+        };
+
+        writeln!(output, "Distinct: {}", distinct)?;
+        writeln!(output, "Values: {}", values)?;
+        if self.options.ascii {
+            let ascii = (0x20..=0x7e)
+                .filter(|&byte| is_present(byte))
+                .map(char::from)
+                .collect::<String>();
+            writeln!(output, "ASCII: {}", ascii)?;
+        }
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        "bytes_to_byteset"
+    }
+    /// Only the set of byte values is kept
+    fn streaming_class(&self) -> StreamingClass {
+        StreamingClass::Streaming
+    }
+}
+
 impl Default for ConversionGraph {
     /// Create a new [`ConversionGraph`] with built-in conversions.
     fn default() -> Self {
+        Self::with_padding(Padding::default())
+    }
+}
+
+impl ConversionGraph {
+    /// Create a new [`ConversionGraph`] with built-in conversions,
+    /// whose decoders handle incomplete input according to `padding`.
+    ///
+    /// The conversions are those that each format of [`FORMATS`] registers.
+    pub fn with_padding(padding: Padding) -> Self {
         let mut graph = ConversionGraph::new();
-        graph.add_direct(Format::Bytes, Format::Bin, bytes_to_bin, 1);
-        graph.add_direct(Format::Bin, Format::Hex, bin_to_hex, 1);
+        for spec in FORMATS {
+            if let Some(register) = spec.register {
+                register(&mut graph, padding);
+            }
+        }
+        graph
+    }
+}
 
-        graph.add_direct(Format::Bytes, Format::Oct, bytes_to_oct, 1);
-        graph.add_direct(Format::Oct, Format::Bytes, oct_to_bytes, 1);
+/// Registers the built-in conversions of bin, see [`FormatSpec::register`](crate::format::FormatSpec::register)
+pub(crate) fn add_bin_edges(graph: &mut ConversionGraph, padding: Padding) {
+    graph.add_chunked_converter(
+        Format::Bytes,
+        Format::Bin,
+        lossless("bytes_to_bin"),
+        BytesToBin::default(),
+    );
+    let bin_to_hex = BinToHex {
+        padding,
+        bit_order: BitOrder::MsbFirst,
+    };
+    // Padding depends on the digit count of the whole input, so only strict decoding is chunked
+    match padding {
+        Padding::Strict => graph.add_chunked_converter(
+            Format::Bin,
+            Format::Hex,
+            lossless("bin_to_hex"),
+            bin_to_hex,
+        ),
+        _ => graph.add_builtin_converter(
+            Format::Bin,
+            Format::Hex,
+            lossless("bin_to_hex"),
+            bin_to_hex,
+        ),
+    }
+}
 
-        graph.add_direct(Format::Bytes, Format::Hex, bytes_to_hex, 1);
-        graph.add_direct(Format::Hex, Format::Bytes, hex_to_bytes, 1);
+pub(crate) fn add_hex_edges(graph: &mut ConversionGraph, _: Padding) {
+    graph.add_chunked_converter(
+        Format::Bytes,
+        Format::Hex,
+        lossless("bytes_to_hex"),
+        BytesToHex,
+    );
+    graph.add_chunked_converter(
+        Format::Hex,
+        Format::Bytes,
+        lossless("hex_to_bytes"),
+        HexToBytes,
+    );
+}
 
-        graph
+pub(crate) fn add_hexswap_edges(graph: &mut ConversionGraph, _: Padding) {
+    graph.add_builtin_converter(
+        Format::Bytes,
+        Format::HexNibbleSwapped,
+        lossless("bytes_to_hexswap"),
+        BytesToHexSwap,
+    );
+    graph.add_builtin_converter(
+        Format::HexNibbleSwapped,
+        Format::Bytes,
+        lossless("hexswap_to_bytes"),
+        HexSwapToBytes,
+    );
+    graph.add_builtin_converter(
+        Format::Hex,
+        Format::HexNibbleSwapped,
+        lossless("swap_hex_nibbles"),
+        SwapHexNibbles,
+    );
+    graph.add_builtin_converter(
+        Format::HexNibbleSwapped,
+        Format::Hex,
+        lossless("swap_hex_nibbles"),
+        SwapHexNibbles,
+    );
+}
+
+pub(crate) fn add_oct_edges(graph: &mut ConversionGraph, padding: Padding) {
+    graph.add_chunked_converter(
+        Format::Bytes,
+        Format::Oct,
+        lossless("bytes_to_oct"),
+        BytesToOct::default(),
+    );
+    let oct_to_bytes = OctToBytes {
+        padding,
+        mask: false,
+    };
+    // Like bin, only strict decoding is chunked
+    match padding {
+        Padding::Strict => graph.add_chunked_converter(
+            Format::Oct,
+            Format::Bytes,
+            lossless("oct_to_bytes"),
+            oct_to_bytes,
+        ),
+        _ => graph.add_builtin_converter(
+            Format::Oct,
+            Format::Bytes,
+            lossless("oct_to_bytes"),
+            oct_to_bytes,
+        ),
+    }
+}
+
+pub(crate) fn add_base64_edges(graph: &mut ConversionGraph, _: Padding) {
+    graph.add_builtin_converter(
+        Format::Bytes,
+        Format::Base64,
+        lossless("bytes_to_base64"),
+        BytesToBase64::default(),
+    );
+    graph.add_builtin_converter(
+        Format::Base64,
+        Format::Bytes,
+        lossless("base64_to_bytes"),
+        Base64ToBytes::default(),
+    );
+}
+
+pub(crate) fn add_pem_edges(graph: &mut ConversionGraph, _: Padding) {
+    graph.add_builtin_converter(
+        Format::Bytes,
+        Format::Pem,
+        lossless("bytes_to_pem"),
+        BytesToPem::default(),
+    );
+    graph.add_builtin_converter(
+        Format::Pem,
+        Format::Bytes,
+        lossless("pem_to_bytes"),
+        PemToBytes::default(),
+    );
+}
+
+pub(crate) fn add_rle_edges(graph: &mut ConversionGraph, _: Padding) {
+    graph.add_builtin_converter(
+        Format::Bytes,
+        Format::Rle,
+        lossless("bytes_to_rle"),
+        BytesToRle,
+    );
+    graph.add_builtin_converter(
+        Format::Rle,
+        Format::Bytes,
+        lossless("rle_to_bytes"),
+        RleToBytes,
+    );
+}
+
+pub(crate) fn add_stats_edges(graph: &mut ConversionGraph, _: Padding) {
+    graph.add_builtin_converter(
+        Format::Bytes,
+        Format::Stats,
+        EdgeInfo {
+            name: "bytes_to_stats",
+            lossy: true,
+            cost: 1,
+        },
+        BytesToStats::default(),
+    );
+}
+
+pub(crate) fn add_byteset_edges(graph: &mut ConversionGraph, _: Padding) {
+    graph.add_builtin_converter(
+        Format::Bytes,
+        Format::ByteSet,
+        EdgeInfo {
+            name: "bytes_to_byteset",
+            lossy: true,
+            cost: 1,
+        },
+        BytesToByteSet::default(),
+    );
+}
+
+fn lossless(name: &'static str) -> EdgeInfo {
+    EdgeInfo {
+        name,
+        lossy: false,
+        cost: 1,
+    }
+}
+
+/// Reads the rest of `input` into `text`, returning the number of bytes read.
+///
+/// Unlike [`Read::read_to_string`], invalid UTF-8 is invalid input data rather than an IO error.
+fn read_text(input: &mut dyn Read, text: &mut String) -> BytaryResult<usize> {
+    let mut bytes = Vec::new();
+    let length = RetryingReader::new(input).read_to_end(&mut bytes)?;
+    let decoded = String::from_utf8(bytes).map_err(|e| {
+        BytaryError::InvalidInputData(format!(
+            "invalid UTF-8 at offset {}",
+            e.utf8_error().valid_up_to()
+        ))
+    })?;
+    text.push_str(&decoded);
+    Ok(length)
+}
+
+/// Reads the rest of `input`.
+///
+/// Only ASCII is meaningful to the digit formats. Non-ASCII input is an error naming the first
+/// character, or byte if it is not valid UTF-8, and its offset. It can be skipped beforehand with
+/// [`PasteCleanupReader::skip_non_ascii`](crate::utils::PasteCleanupReader::skip_non_ascii).
+fn read_ascii(input: &mut dyn Read) -> BytaryResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    RetryingReader::new(input).read_to_end(&mut bytes)?;
+    match bytes.iter().position(|b| !b.is_ascii()) {
+        None => Ok(bytes),
+        // A character is named, so that look-alikes of digits are told apart
+        Some(offset) => BytaryError::InvalidInputAt {
+            offset: offset as u64,
+            message: match char_at(&bytes[offset..]) {
+                Some(character) if character != char::REPLACEMENT_CHARACTER => InvalidInput {
+                    offset,
+                    character: Some(character),
+                }
+                .to_string(),
+                _ => format!(
+                    "non-ASCII byte 0x{:02x} at offset {}",
+                    bytes[offset], offset
+                ),
+            },
+        }
+        .err(),
+    }
+}
+
+/// Whether `token` is a short group of output written without padding, like that of [`OctOptions::pad`]:
+/// fewer digits than the `group` of a byte, and no leading zero.
+///
+/// The tokens of nibbles like `0001 1011` have leading zeros, so they are not.
+pub(crate) fn is_short_group(token: &[u8], group: usize) -> bool {
+    token.len() < group && (token.len() == 1 || token[0] != b'0')
+}
+
+/// Calls `f` with the parts of `text` that hold the digits of `is_digit`, and with the zeros
+/// padding their digits to a multiple of `group` according to `padding`, which have no offset.
+///
+/// The parts are the whole text, or its tokens with [`Padding::Token`], and `f` skips their other
+/// characters, which are [whitespace](is_ignorable) or [separators](is_separator). Any other
+/// character is an error with its offset, even if warnings are [collected](warning::collect),
+/// as in [`hex_to_bytes`].
+/// The zeros added are reported as a [`Warning::Padded`], except those of [short groups](is_short_group)
+/// with [`Padding::Token`].
+fn for_each_padded_part(
+    text: &[u8],
+    is_digit: fn(u8) -> bool,
+    group: usize,
+    padding: Padding,
+    mut f: impl FnMut(&[u8], Option<usize>) -> BytaryResult<()>,
+) -> BytaryResult<()> {
+    const ZEROS: &[u8; 8] = b"00000000";
+
+    let is_blank = |b: u8| is_ignorable(b) || is_separator(b);
+    if let Some(offset) = text.iter().position(|&b| !is_digit(b) && !is_blank(b)) {
+        return BytaryError::InvalidInputAt {
+            offset: offset as u64,
+            message: InvalidInput {
+                offset,
+                character: Some(text[offset] as char),
+            }
+            .to_string(),
+        }
+        .err();
+    }
+
+    let parts = match padding {
+        Padding::Token => text
+            .split(|&b| is_blank(b))
+            .filter(|token| !token.is_empty())
+            .collect::<Vec<&[u8]>>(),
+        _ => vec![text],
+    };
+    let mut zeros = Vec::with_capacity(parts.len());
+    let (mut digits, mut added) = (0, 0);
+    for part in &parts {
+        // Counted in chunks whose counts fit in a byte, so that the loop is vectorized
+        let mut count = 0;
+        for chunk in part.chunks(255) {
+            count += usize::from(chunk.iter().map(|&b| u8::from(is_digit(b))).sum::<u8>());
+        }
+        let missing = (group - count % group) % group;
+        // The short groups of unpadded output are what Padding::Token is for
+        if padding != Padding::Token || !is_short_group(part, group) {
+            added += missing;
+        }
+        zeros.push(missing);
+        digits += count;
+    }
+    if padding == Padding::Strict && digits % group != 0 {
+        return BytaryError::InvalidInputData(format!(
+            "Got {}, which is not a multiple of {}",
+            plural(digits, "digit"),
+            group
+        ))
+        .err();
+    }
+    if added > 0 {
+        warning::report(Warning::Padded { zeros: added });
+    }
+
+    for (part, zeros) in parts.into_iter().zip(zeros) {
+        if padding != Padding::Right && zeros > 0 {
+            f(&ZEROS[..zeros], None)?;
+        }
+        f(part, Some(part.as_ptr() as usize - text.as_ptr() as usize))?;
+        if padding == Padding::Right && zeros > 0 {
+            f(&ZEROS[..zeros], None)?;
+        }
     }
+    Ok(())
 }
 
-fn bytes_to_bin(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
-    let mut reader = io::BufReader::new(input);
-    let mut writer = io::BufWriter::new(output);
+/// Writes every byte formatted by `format`, separated by spaces
+fn bytes_to_unpadded(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    format: fn(u8) -> String,
+) -> BytaryResult<()> {
+    let mut reader = io::BufReader::new(RetryingReader::new(input));
     let mut buffer = [0u8; 1024];
+    let mut first = true;
 
     loop {
         let length = reader.read(&mut buffer)?;
@@ -36,149 +1000,984 @@ fn bytes_to_bin(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()
             break;
         }
 
-        let bin_str = buffer[..length]
-            .iter()
-            .map(|byte| format!("{:08b}", byte))
-            .collect::<String>();
+        for &byte in &buffer[..length] {
+            if !first {
+                output.write_all(b" ")?;
+            }
+            first = false;
+            output.write_all(format(byte).as_bytes())?;
+        }
+        flush_if_short(output, length, buffer.len())?;
+    }
+    Ok(())
+}
+
+/// Encodes every byte as 8 binary digits
+///
+/// ```rust
+/// use bytary::builtins::bytes_to_bin;
+///
+/// let mut output = Vec::new();
+/// bytes_to_bin(&mut &b"\x1b\x34"[..], &mut output).unwrap();
+/// assert_eq!(output, b"0001101100110100");
+/// ```
+pub fn bytes_to_bin(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    let mut reader = io::BufReader::new(RetryingReader::new(input));
+    let mut buffer = [0u8; 1024];
+    let mut encoded = [0u8; fast::bin::encoded_len(1024)];
+
+    loop {
+        let length = reader.read(&mut buffer)?;
+        if length == 0 {
+            break;
+        }
 
-        writer.write_all(bin_str.as_bytes())?;
+        let digits = fast::bin::encode_into(&buffer[..length], &mut encoded)?;
+        output.write_all(&encoded[..digits])?;
+        flush_if_short(output, length, buffer.len())?;
     }
     Ok(())
 }
 
-fn bin_to_hex(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
-    let mut reader = io::BufReader::new(input);
-    let mut writer = io::BufWriter::new(output);
-    let mut buffer = String::new();
+/// Decodes binary digits into hex, rejecting input whose digits do not fill whole bytes.
+///
+/// Whitespace and [separators](is_separator) are ignored, any other character is an error,
+/// as in [`hex_to_bytes`].
+///
+/// ```rust
+/// use bytary::builtins::bin_to_hex;
+///
+/// let mut output = Vec::new();
+/// bin_to_hex(&mut &b"00011011 00110100"[..], &mut output).unwrap();
+/// assert_eq!(output, b"1b34");
+///
+/// assert!(bin_to_hex(&mut &b"101"[..], &mut Vec::new()).is_err());
+/// ```
+pub fn bin_to_hex(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    bin_to_hex_padded(input, output, Padding::Strict, BitOrder::MsbFirst)
+}
 
-    let re = Regex::new(r"[^0-9]").unwrap();
+/// Create a bin to hex converter that handles incomplete input according to `padding`
+///
+/// ```rust
+/// use bytary::builtins::{bin_to_hex_with_padding, Padding};
+///
+/// let mut output = Vec::new();
+/// bin_to_hex_with_padding(Padding::Left)(&mut &b"101"[..], &mut output).unwrap();
+/// assert_eq!(output, b"05");
+/// ```
+pub fn bin_to_hex_with_padding(
+    padding: Padding,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    move |input, output| bin_to_hex_padded(input, output, padding, BitOrder::MsbFirst)
+}
 
-    while reader.read_to_string(&mut buffer)? > 0 {
-        let clean_bin = re.replace_all(&buffer, "");
+fn bin_to_hex_padded(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    padding: Padding,
+    bit_order: BitOrder,
+) -> BytaryResult<()> {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let text = read_ascii(input)?;
+    let mut hex = Vec::with_capacity(text.len() / 4 + 2);
+    let mut push = |byte: u8| {
+        let byte = match bit_order {
+            BitOrder::MsbFirst => byte,
+            BitOrder::LsbFirst => byte.reverse_bits(),
+        };
+        hex.extend_from_slice(&[HEX[usize::from(byte >> 4)], HEX[usize::from(byte & 0xf)]]);
+    };
 
-        if clean_bin.is_empty() {
-            buffer.clear();
-            continue;
+    // Bits are shifted into `byte` until all 8 are there
+    let (mut byte, mut bits) = (0u8, 0);
+    // Every byte is represented by 8 binary digits
+    // Digits above 1 are rejected with their offset
+    for_each_padded_part(&text, is_dec_digit, 8, padding, |part, offset| {
+        let mut i = 0;
+        while i < part.len() {
+            // 8 digits that start a byte are decoded at once, if they are all 0 or 1
+            if bits == 0 && part.len() - i >= 8 {
+                let chunk = u64::from_le_bytes(part[i..i + 8].try_into().unwrap());
+                if chunk & 0xfefefefefefefefe == 0x3030303030303030 {
+                    // Moves the low bit of every byte, first digit first, into the top byte
+                    let packed = (chunk & 0x0101010101010101).wrapping_mul(0x8040201008040201);
+                    push((packed >> 56) as u8);
+                    i += 8;
+                    continue;
+                }
+            }
+            let digit = part[i];
+            match digit {
+                _ if is_bin_digit(digit) => {
+                    byte = byte << 1 | (digit - b'0');
+                    bits += 1;
+                    if bits == 8 {
+                        push(byte);
+                        (byte, bits) = (0, 0);
+                    }
+                }
+                _ if is_dec_digit(digit) => {
+                    let offset = offset.map_or(0, |offset| offset + i);
+                    return Err(BytaryError::InvalidInputAt {
+                        offset: offset as u64,
+                        message: InvalidInput {
+                            offset,
+                            character: Some(digit as char),
+                        }
+                        .to_string(),
+                    });
+                }
+                _ => {}
+            }
+            i += 1;
         }
+        Ok(())
+    })?;
 
-        let chunks = clean_bin
-            .as_bytes()
-            .chunks(4)
-            .map(|chunk| {
-                let mut padded = String::new();
-                if chunk.len() < 4 {
-                    padded.push_str(&"0".repeat(4 - chunk.len()));
-                }
-                padded.push_str(std::str::from_utf8(chunk).unwrap());
-                padded
-            })
-            .collect::<Vec<String>>();
+    output.write_all(&hex)?;
+    Ok(())
+}
 
-        let hex_str = chunks
-            .iter()
-            .map(|bin4| {
-                u8::from_str_radix(bin4, 2)
-                    .map(|n| format!("{:x}", n))
-                    .map_err(|e| BytaryError::InvalidInputData(e.to_string()))
-            })
-            .collect::<BytaryResult<String>>()?;
+/// Encodes every byte as 3 octal digits
+///
+/// A byte is at most `377`, so the output always decodes back to the input with [`oct_to_bytes`].
+///
+/// ```rust
+/// use bytary::builtins::bytes_to_oct;
+///
+/// let mut output = Vec::new();
+/// bytes_to_oct(&mut &b"\x0e\x38"[..], &mut output).unwrap();
+/// assert_eq!(output, b"016070");
+/// ```
+pub fn bytes_to_oct(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    let mut reader = io::BufReader::new(RetryingReader::new(input));
+    let mut buffer = [0u8; 1024];
+    let mut encoded = [0u8; fast::oct::encoded_len(1024)];
+
+    loop {
+        let length = reader.read(&mut buffer)?;
+        if length == 0 {
+            break;
+        }
+
+        let digits = fast::oct::encode_into(&buffer[..length], &mut encoded)?;
+        output.write_all(&encoded[..digits])?;
+        flush_if_short(output, length, buffer.len())?;
+    }
+    Ok(())
+}
+
+/// Decodes groups of 3 octal digits into bytes, rejecting input whose digits do not fill whole bytes.
+///
+/// Whitespace and [separators](is_separator) are ignored, any other character is an error,
+/// as in [`hex_to_bytes`].
+/// Groups above `377` do not fit in a byte and are rejected, unless [`OctToBytes::mask`] is set.
+///
+/// ```rust
+/// use bytary::builtins::oct_to_bytes;
+///
+/// let mut output = Vec::new();
+/// oct_to_bytes(&mut &b"016 070"[..], &mut output).unwrap();
+/// assert_eq!(output, [0o16, 0o70]);
+///
+/// let err = oct_to_bytes(&mut &b"016 777"[..], &mut Vec::new()).unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "Invalid input data: octal group 777 at offset 4 is above 377, which does not fit in a byte"
+/// );
+/// ```
+pub fn oct_to_bytes(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    oct_to_bytes_padded(input, output, Padding::Strict, false)
+}
+
+/// Create an oct to bytes converter that handles incomplete input according to `padding`
+///
+/// ```rust
+/// use bytary::builtins::{oct_to_bytes_with_padding, Padding};
+///
+/// let mut output = Vec::new();
+/// oct_to_bytes_with_padding(Padding::Token)(&mut &b"1 13 377"[..], &mut output).unwrap();
+/// assert_eq!(output, [1, 0o13, 0o377]);
+/// ```
+pub fn oct_to_bytes_with_padding(
+    padding: Padding,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    move |input, output| oct_to_bytes_padded(input, output, padding, false)
+}
+
+fn oct_to_bytes_padded(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    padding: Padding,
+    mask: bool,
+) -> BytaryResult<()> {
+    let text = read_ascii(input)?;
+    let mut bytes = Vec::with_capacity(text.len() / 3 + 1);
 
-        writer.write_all(hex_str.as_bytes())?;
-        buffer.clear();
+    // The digits of the current group, their number, and the offset of the first one
+    let (mut group, mut digits, mut start) = ([0u8; 3], 0, None);
+    let mut masked = 0;
+    // Each byte is represented by 3 octal digits
+    for_each_padded_part(&text, is_oct_digit, 3, padding, |part, offset| {
+        for (i, &digit) in part.iter().enumerate() {
+            if !is_oct_digit(digit) {
+                continue;
+            }
+            group[digits] = digit - b'0';
+            if digits == 0 {
+                start = offset.map(|offset| offset + i);
+            }
+            digits += 1;
+            if digits < 3 {
+                continue;
+            }
+            digits = 0;
+
+            // A group is above 377 exactly when its first digit is above 3
+            if group[0] > 3 {
+                if !mask {
+                    let offset = start.unwrap_or_default();
+                    return BytaryError::InvalidInputAt {
+                        offset: offset as u64,
+                        message: format!(
+                            "octal group {}{}{} at offset {} is above 377, which does not fit in a byte",
+                            group[0], group[1], group[2], offset
+                        ),
+                    }
+                    .err();
+                }
+                // Subtracting 400 keeps the low 8 bits
+                group[0] -= 4;
+                masked += 1;
+            }
+            bytes.push(group[0] << 6 | group[1] << 3 | group[2]);
+        }
+        Ok(())
+    })?;
+    if masked > 0 {
+        warning::report(Warning::Masked { count: masked });
     }
 
+    output.write_all(&bytes)?;
     Ok(())
 }
-fn bytes_to_oct(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
-    let mut reader = io::BufReader::new(input);
-    let mut writer = io::BufWriter::new(output);
+
+/// Encodes every byte as 2 lowercase hex digits
+///
+/// ```rust
+/// use bytary::builtins::bytes_to_hex;
+///
+/// let mut output = Vec::new();
+/// bytes_to_hex(&mut &b"Hi"[..], &mut output).unwrap();
+/// assert_eq!(output, b"4869");
+/// ```
+pub fn bytes_to_hex(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    let mut reader = io::BufReader::new(RetryingReader::new(input));
     let mut buffer = [0u8; 1024];
+    let mut encoded = [0u8; fast::hex::encoded_len(1024)];
 
     loop {
         let length = reader.read(&mut buffer)?;
         if length == 0 {
             break;
         }
+        let digits = fast::hex::encode_into(&buffer[..length], &mut encoded)?;
+        output.write_all(&encoded[..digits])?;
+        flush_if_short(output, length, buffer.len())?;
+    }
+    Ok(())
+}
 
-        let oct_str = buffer[..length]
-            .iter()
-            .map(|byte| format!("{:03o}", byte)) // Each byte as 3-digit octal
-            .collect::<String>();
+/// Decodes hex digits into bytes, streaming.
+///
+/// Whitespace, `0x` and `\x` prefixes and `:`, `-` or `,` separators are accepted, any other character is an error.
+/// An unpaired digit at the end is an error, or a [`Warning`] if warnings are [collected](warning::collect).
+///
+/// ```rust
+/// use bytary::builtins::hex_to_bytes;
+///
+/// let mut output = Vec::new();
+/// hex_to_bytes(&mut &b"0x48, 0x69"[..], &mut output).unwrap();
+/// assert_eq!(output, b"Hi");
+///
+/// assert!(hex_to_bytes(&mut &b"4g"[..], &mut Vec::new()).is_err());
+/// ```
+pub fn hex_to_bytes(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    let mut reader = io::BufReader::new(RetryingReader::new(input));
+    let mut buffer = [0u8; 1024];
+    let mut bytes = Vec::new();
+    let mut decoder = HexDecoder::new();
 
-        writer.write_all(oct_str.as_bytes())?;
+    loop {
+        let length = reader.read(&mut buffer)?;
+        if length == 0 {
+            break;
+        }
+        bytes.clear();
+        decoder
+            .feed(&buffer[..length], &mut bytes)
+            .map_err(invalid_hex)?;
+        output.write_all(&bytes)?;
+        flush_if_short(output, length, buffer.len())?;
+    }
+
+    bytes.clear();
+    match decoder.finish(&mut bytes) {
+        // A trailing unpaired digit
+        Err(InvalidInput {
+            character: None, ..
+        }) if warning::report(Warning::TrailingPartial {
+            digits: 1,
+            group: 2,
+        }) => {}
+        result => result.map_err(invalid_hex)?,
     }
+    output.write_all(&bytes)?;
     Ok(())
 }
 
-fn oct_to_bytes(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
-    let mut reader = io::BufReader::new(input);
-    let mut writer = io::BufWriter::new(output);
-    let mut buffer = String::new();
+/// Reverses the bits of every byte read from the inner reader
+struct BitReversed<R>(R);
 
-    // Remove any non-octal digits (0-7)
-    let re = Regex::new(r"[^0-7]").unwrap();
+impl<R: Read> Read for BitReversed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.0.read(buf)?;
+        buf[..length].iter_mut().for_each(|b| *b = b.reverse_bits());
+        Ok(length)
+    }
+}
 
-    while reader.read_to_string(&mut buffer)? > 0 {
-        let clean_oct = re.replace_all(&buffer, "");
+/// Swaps the nibbles of every byte read from or written to the inner reader or writer
+struct NibbleSwapped<T>(T);
 
-        if clean_oct.is_empty() {
-            buffer.clear();
-            continue;
+impl<R: Read> Read for NibbleSwapped<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.0.read(buf)?;
+        buf[..length].iter_mut().for_each(|b| *b = b.rotate_left(4));
+        Ok(length)
+    }
+}
+
+impl<W: Write> Write for NibbleSwapped<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let swapped = buf.iter().map(|b| b.rotate_left(4)).collect::<Vec<u8>>();
+        self.0.write_all(&swapped)?;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Encodes bytes as hex with the low nibble of each byte first, streaming.
+///
+/// ```rust
+/// use bytary::builtins::bytes_to_hexswap;
+///
+/// let mut output = Vec::new();
+/// bytes_to_hexswap(&mut &[0x1b, 0x20][..], &mut output).unwrap();
+/// assert_eq!(output, b"b102");
+/// ```
+pub fn bytes_to_hexswap(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    bytes_to_hex(&mut NibbleSwapped(input), output)
+}
+
+/// Decodes hex with the low nibble of each byte first into bytes, streaming.
+///
+/// Accepts the same input as [`hex_to_bytes`].
+///
+/// ```rust
+/// use bytary::builtins::hexswap_to_bytes;
+///
+/// let mut output = Vec::new();
+/// hexswap_to_bytes(&mut &b"b1 02"[..], &mut output).unwrap();
+/// assert_eq!(output, [0x1b, 0x20]);
+/// ```
+pub fn hexswap_to_bytes(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    hex_to_bytes(input, &mut NibbleSwapped(output))
+}
+
+/// Swaps each pair of hex digits without decoding them, streaming, which converts between hex and hexswap
+/// in both directions.
+///
+/// Only hex digits and whitespace are accepted, and whitespace is dropped. The case of the digits is kept.
+/// An unpaired digit at the end is an error, or a [`Warning`] if warnings are [collected](warning::collect).
+///
+/// ```rust
+/// use bytary::builtins::swap_hex_nibbles;
+///
+/// let mut output = Vec::new();
+/// swap_hex_nibbles(&mut &b"1B 2a"[..], &mut output).unwrap();
+/// assert_eq!(output, b"B1a2");
+///
+/// assert!(swap_hex_nibbles(&mut &b"1b2"[..], &mut Vec::new()).is_err());
+/// ```
+pub fn swap_hex_nibbles(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    let mut input = RetryingReader::new(input);
+    let mut buffer = [0u8; 1024];
+    let mut swapped = Vec::with_capacity(buffer.len());
+    let mut offset = 0;
+    // First digit of the pair being read
+    let mut high = None;
+
+    loop {
+        let length = input.read(&mut buffer)?;
+        if length == 0 {
+            break;
         }
+        swapped.clear();
+        for (i, &byte) in buffer[..length].iter().enumerate() {
+            match byte {
+                _ if is_ignorable(byte) => {}
+                _ if is_hex_digit(byte) => match high.take() {
+                    Some(high) => swapped.extend([byte, high]),
+                    None => high = Some(byte),
+                },
+                _ => {
+                    return Err(invalid_hex(InvalidInput {
+                        offset: offset + i,
+                        character: char_at(&buffer[i..length]),
+                    }));
+                }
+            }
+        }
+        offset += length;
+        output.write_all(&swapped)?;
+        flush_if_short(output, length, buffer.len())?;
+    }
+
+    if high.is_some()
+        && !warning::report(Warning::TrailingPartial {
+            digits: 1,
+            group: 2,
+        })
+    {
+        return Err(invalid_hex(InvalidInput {
+            offset,
+            character: None,
+        }));
+    }
+    Ok(())
+}
+
+/// Create a converter that copies the characters of `alphabet` as they are and drops whitespace,
+/// to lay out digits again without decoding them.
+///
+/// The case of the characters and their number are kept, even if they do not fill whole bytes.
+/// Any other character is an error with its offset, even if warnings are [collected](warning::collect).
+///
+/// ```rust
+/// use bytary::builtins::reformat_text;
+///
+/// let mut output = Vec::new();
+/// reformat_text("0123456789abcdefABCDEF")(&mut &b"1B 2a\n3"[..], &mut output).unwrap();
+/// assert_eq!(output, b"1B2a3");
+///
+/// assert!(reformat_text("01")(&mut &b"0102"[..], &mut Vec::new()).is_err());
+/// ```
+pub fn reformat_text(
+    alphabet: &str,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    let mut allowed = [false; 256];
+    alphabet.bytes().for_each(|b| allowed[b as usize] = true);
+    move |input, output| {
+        let mut input = RetryingReader::new(input);
+        let mut buffer = [0u8; 1024];
+        let mut kept = Vec::with_capacity(buffer.len());
+        let mut offset = 0;
+
+        loop {
+            let length = input.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            kept.clear();
+            for (i, &byte) in buffer[..length].iter().enumerate() {
+                match byte {
+                    _ if allowed[byte as usize] => kept.push(byte),
+                    _ if is_ignorable(byte) => {}
+                    _ => {
+                        return BytaryError::InvalidInputAt {
+                            offset: (offset + i) as u64,
+                            message: InvalidInput {
+                                offset: offset + i,
+                                character: char_at(&buffer[i..length]),
+                            }
+                            .to_string(),
+                        }
+                        .err();
+                    }
+                }
+            }
+            offset += length;
+            output.write_all(&kept)?;
+            flush_if_short(output, length, buffer.len())?;
+        }
+        Ok(())
+    }
+}
 
-        // Process in chunks of 3 digits (since each byte is represented by 3 octal digits)
-        let chunks = clean_oct
-            .as_bytes()
-            .chunks(3)
-            .map(|chunk| {
-                let oct_str = std::str::from_utf8(chunk)
-                    .map_err(|e| BytaryError::InvalidInputData(e.to_string()))?;
-
-                // Handle cases where the last chunk might be shorter than 3 digits
-                let oct_str = if oct_str.len() < 3 {
-                    // Pad with leading zeros if needed
-                    format!("{:0<3}", oct_str)
-                } else {
-                    oct_str.to_string()
-                };
-
-                u8::from_str_radix(&oct_str, 8)
-                    .map_err(|e| BytaryError::InvalidInputData(e.to_string()))
+/// Counts of every byte value
+struct Histogram {
+    counts: [u64; 256],
+    total: u64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            counts: [0; 256],
+            total: 0,
+        }
+    }
+
+    fn add(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.counts[byte as usize] += 1;
+        }
+        self.total += bytes.len() as u64;
+    }
+
+    /// Shannon entropy in bits per byte, from 0 to 8
+    fn entropy(&self) -> f64 {
+        let total = self.total as f64;
+        self.counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / total;
+                p * (1.0 / p).log2()
             })
-            .collect::<BytaryResult<Vec<u8>>>()?;
+            .sum()
+    }
+}
+
+/// Writes a report of the total size, entropy, printable bytes and the 10 most frequent byte values
+///
+/// ```rust
+/// use bytary::builtins::bytes_to_stats;
+///
+/// let mut output = Vec::new();
+/// bytes_to_stats(&mut &[0u8; 8][..], &mut output).unwrap();
+/// assert!(String::from_utf8(output).unwrap().starts_with("Total: 8 bytes\nEntropy: 0.0000 bits/byte\n"));
+/// ```
+pub fn bytes_to_stats(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    let mut reader = io::BufReader::new(RetryingReader::new(input));
+    let mut buffer = [0u8; 1024];
+    let mut histogram = Histogram::new();
 
-        writer.write_all(&chunks)?;
-        buffer.clear();
+    loop {
+        let length = reader.read(&mut buffer)?;
+        if length == 0 {
+            break;
+        }
+        histogram.add(&buffer[..length]);
+    }
+    if histogram.total == 0 {
+        return Ok(());
     }
 
+    let printable = (0x20..=0x7e).map(|b| histogram.counts[b]).sum::<u64>();
+    let mut frequent = (0..=255u8)
+        .filter(|&b| histogram.counts[b as usize] > 0)
+        .collect::<Vec<u8>>();
+    frequent.sort_by_key(|&b| std::cmp::Reverse(histogram.counts[b as usize]));
+
+    writeln!(output, "Total: {}", plural(histogram.total, "byte"))?;
+    writeln!(output, "Entropy: {:.4} bits/byte", histogram.entropy())?;
+    writeln!(
+        output,
+        "Printable: {}, non-printable: {}",
+        printable,
+        histogram.total - printable
+    )?;
+    writeln!(output, "Most frequent:")?;
+    for byte in frequent.into_iter().take(10) {
+        let count = histogram.counts[byte as usize];
+        let character = match byte {
+            0x20..=0x7e => format!("{:?}", byte as char),
+            _ => String::new(),
+        };
+        writeln!(
+            output,
+            "  {:02x} {:>3} {} {:.2}%",
+            byte,
+            character,
+            count,
+            count as f64 * 100.0 / histogram.total as f64
+        )?;
+    }
     Ok(())
 }
-fn bytes_to_hex(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
-    let mut reader = io::BufReader::new(input);
-    let mut writer = io::BufWriter::new(output);
+
+/// Writes the offset and entropy of every `window` bytes, one window per line.
+///
+/// The last window may be shorter.
+fn bytes_to_window_entropy(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    window: usize,
+) -> BytaryResult<()> {
+    let mut reader = io::BufReader::new(RetryingReader::new(input));
     let mut buffer = [0u8; 1024];
+    let mut histogram = Histogram::new();
+    let mut offset = 0u64;
 
     loop {
         let length = reader.read(&mut buffer)?;
         if length == 0 {
             break;
         }
-        let hex_str = hex::encode(&buffer[..length]);
-        writer.write_all(hex_str.as_bytes())?;
+        let mut rest = &buffer[..length];
+        while !rest.is_empty() {
+            let take = rest.len().min(window - histogram.total as usize);
+            histogram.add(&rest[..take]);
+            rest = &rest[take..];
+            if histogram.total as usize == window {
+                writeln!(output, "{:08x} {:.4}", offset, histogram.entropy())?;
+                offset += window as u64;
+                histogram = Histogram::new();
+            }
+        }
+        flush_if_short(output, length, buffer.len())?;
+    }
+    if histogram.total > 0 {
+        writeln!(output, "{:08x} {:.4}", offset, histogram.entropy())?;
     }
     Ok(())
 }
-fn hex_to_bytes(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
-    let mut reader = io::BufReader::new(input);
-    let mut writer = io::BufWriter::new(output);
-    let mut buffer = String::new();
-
-    let re = Regex::new(r"[\s\n]+").unwrap();
-    while reader.read_to_string(&mut buffer)? > 0 {
-        let bytes = hex::decode(re.replace_all(&buffer, "").as_ref())
-            .map_err(|e| BytaryError::InvalidInputData(format!("Invalid hex string: {}", e)))?;
-        writer.write_all(&bytes)?;
-        buffer.clear();
+
+/// Writes how many distinct byte values occur in the input, and which ones as hex ranges,
+/// using constant memory.
+///
+/// ```rust
+/// use bytary::builtins::bytes_to_byteset;
+///
+/// let mut output = Vec::new();
+/// bytes_to_byteset(&mut &b"ABCA\x00\xff"[..], &mut output).unwrap();
+/// assert_eq!(output, b"Distinct: 5\nValues: 00, 41-43, ff\n");
+/// ```
+pub fn bytes_to_byteset(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    BytesToByteSet::default().convert(input, output)
+}
+
+pub(crate) fn invalid_hex(e: InvalidInput) -> BytaryError {
+    BytaryError::InvalidInputAt {
+        offset: e.offset as u64,
+        message: format!("Invalid hex string: {}", e),
+    }
+}
+
+/// A streaming hex decoder.
+///
+/// Besides hex digits and whitespace, it accepts:
+///
+/// - `0x`, `0X`, `\x` and `\X` prefixes. `0x` is only recognized at the start of a token.
+/// - `:`, `-` and `,` as [separators](is_separator) between tokens.
+///
+/// Digits are paired regardless of separators, so `1 b` decodes to `0x1b`.
+pub(crate) struct HexDecoder {
+    /// Offset of the next input byte
+    offset: usize,
+    /// High nibble of the byte being decoded
+    high: Option<u8>,
+    /// Whether the next input byte starts a token
+    at_boundary: bool,
+    /// A `0` or `\` that may start a prefix, depending on the next input byte
+    pending: Option<u8>,
+}
+
+impl HexDecoder {
+    pub(crate) fn new() -> Self {
+        Self {
+            offset: 0,
+            high: None,
+            at_boundary: true,
+            pending: None,
+        }
+    }
+
+    /// Decodes the next chunk of input, appending complete bytes to `output`
+    pub(crate) fn feed(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), InvalidInput> {
+        let mut i = 0;
+        while i < input.len() {
+            // Inside a token, a run of digits can not start a prefix, so it is decoded at once
+            if !self.at_boundary && self.high.is_none() && self.pending.is_none() {
+                let decoded = fast::decode_hex_pairs(&input[i..], output);
+                self.offset += decoded;
+                i += decoded;
+                if i == input.len() {
+                    break;
+                }
+            }
+            self.step(input[i], output).map_err(|offset| InvalidInput {
+                offset,
+                character: match offset == self.offset {
+                    true => char_at(&input[i..]),
+                    false => Some('\\'),
+                },
+            })?;
+            self.offset += 1;
+            i += 1;
+        }
+        Ok(())
+    }
+
+    /// Ends decoding, failing if the input stopped in the middle of a byte or prefix
+    pub(crate) fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), InvalidInput> {
+        match self.pending.take() {
+            Some(b'\\') => {
+                return Err(InvalidInput {
+                    offset: self.offset - 1,
+                    character: Some('\\'),
+                });
+            }
+            Some(zero) => self.digit(zero, output),
+            None => {}
+        }
+        match self.high {
+            Some(_) => Err(InvalidInput {
+                offset: self.offset,
+                character: None,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Processes one input byte, returning the offset of the offending byte on error
+    fn step(&mut self, byte: u8, output: &mut Vec<u8>) -> Result<(), usize> {
+        match self.pending.take() {
+            Some(_) if matches!(byte, b'x' | b'X') => {
+                // A prefix in the middle of a byte
+                if self.high.is_some() {
+                    return Err(self.offset);
+                }
+                self.at_boundary = false;
+                return Ok(());
+            }
+            Some(b'\\') => return Err(self.offset - 1),
+            Some(zero) => self.digit(zero, output),
+            None => {}
+        }
+
+        match byte {
+            b'0' if self.at_boundary => self.pending = Some(byte),
+            b'\\' => self.pending = Some(byte),
+            _ if is_ignorable(byte) || is_separator(byte) => self.at_boundary = true,
+            _ if is_hex_digit(byte) => self.digit(byte, output),
+            _ => return Err(self.offset),
+        }
+        Ok(())
+    }
+
+    fn digit(&mut self, digit: u8, output: &mut Vec<u8>) {
+        let value = digit_value(digit, 16).unwrap();
+        self.at_boundary = false;
+        match self.high.take() {
+            Some(high) => output.push(high << 4 | value),
+            None => self.high = Some(value),
+        }
+    }
+}
+
+/// Encodes bytes as standard base64 with padding
+///
+/// ```rust
+/// use bytary::builtins::bytes_to_base64;
+///
+/// let mut output = Vec::new();
+/// bytes_to_base64(&mut &b"Hi"[..], &mut output).unwrap();
+/// assert_eq!(output, b"SGk=");
+/// ```
+pub fn bytes_to_base64(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    BytesToBase64::default().convert(input, output)
+}
+
+/// Decodes standard base64 with padding into bytes, ignoring whitespace
+///
+/// ```rust
+/// use bytary::builtins::base64_to_bytes;
+///
+/// let mut output = Vec::new();
+/// base64_to_bytes(&mut &b"SGVs\nbG8h\n"[..], &mut output).unwrap();
+/// assert_eq!(output, b"Hello!");
+/// ```
+pub fn base64_to_bytes(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    Base64ToBytes::default().convert(input, output)
+}
+
+/// Decodes `buffer` with `alphabet`, ignoring whitespace
+fn decode_base64(
+    mut buffer: Vec<u8>,
+    alphabet: &Base64Alphabet,
+    output: &mut dyn Write,
+) -> BytaryResult<()> {
+    let invalid = |message: String| {
+        BytaryError::InvalidInputData(format!("Invalid base64 string: {}", message))
+    };
+    // Only whitespace is skipped, and what is not in the alphabet is reported with its offset
+    // before the padding is checked
+    let unexpected = buffer
+        .iter()
+        .position(|&b| !is_ignorable(b) && Some(b) != alphabet.pad && !alphabet.chars.contains(&b));
+    if let Some(offset) = unexpected {
+        return BytaryError::InvalidInputAt {
+            offset: offset as u64,
+            message: format!(
+                "Invalid base64 string: {}, which is not in the alphabet",
+                InvalidInput {
+                    offset,
+                    character: char_at(&buffer[offset..]),
+                }
+            ),
+        }
+        .err();
+    }
+    // Offsets of the errors are those of the input, with its whitespace
+    let input = buffer.clone();
+    buffer.retain(|&b| !is_ignorable(b));
+
+    if let Some(pad) = alphabet.pad {
+        let padding = buffer.iter().rev().take_while(|&&b| b == pad).count();
+        if padding > 2 || !buffer.len().is_multiple_of(4) {
+            return invalid("Invalid padding".to_string()).err();
+        }
+        buffer.truncate(buffer.len() - padding);
+    }
+    let bytes = alphabet.engine().decode(&buffer).map_err(|e| match e {
+        base64::DecodeError::InvalidByte(offset, byte) => {
+            let offset = input
+                .iter()
+                .enumerate()
+                .filter(|&(_, &b)| !is_ignorable(b))
+                .nth(offset)
+                .map_or(input.len(), |(i, _)| i);
+            BytaryError::InvalidInputAt {
+                offset: offset as u64,
+                message: format!(
+                    "Invalid base64 string: unexpected character '{}' at offset {}, which is not in the alphabet",
+                    (byte as char).escape_default(),
+                    offset
+                ),
+            }
+        }
+        e => invalid(e.to_string()),
+    })?;
+    output.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Encodes bytes as a PEM block labeled `DATA`, with base64 lines of 64 characters
+///
+/// ```rust
+/// use bytary::builtins::bytes_to_pem;
+///
+/// let mut output = Vec::new();
+/// bytes_to_pem(&mut &b"Hi"[..], &mut output).unwrap();
+/// assert_eq!(output, b"-----BEGIN DATA-----\nSGk=\n-----END DATA-----\n");
+/// ```
+pub fn bytes_to_pem(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    bytes_to_pem_labeled(input, output, &PemOptions::default().label)
+}
+
+fn bytes_to_pem_labeled(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    label: &str,
+) -> BytaryResult<()> {
+    let mut bytes = Vec::new();
+    RetryingReader::new(input).read_to_end(&mut bytes)?;
+    if bytes.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(output, "-----BEGIN {}-----", label)?;
+    // 48 bytes are encoded to 64 characters
+    for chunk in bytes.chunks(48) {
+        writeln!(output, "{}", STANDARD.encode(chunk))?;
+    }
+    writeln!(output, "-----END {}-----", label)?;
+    output.flush()?;
+    Ok(())
+}
+
+/// Decodes the first PEM block in the input into bytes.
+///
+/// Anything before the `BEGIN` line is ignored, and the `END` line must have the same label.
+///
+/// ```rust
+/// use bytary::builtins::pem_to_bytes;
+///
+/// let mut output = Vec::new();
+/// let pem = b"Subject: test\n-----BEGIN DATA-----\nSGk=\n-----END DATA-----\n";
+/// pem_to_bytes(&mut &pem[..], &mut output).unwrap();
+/// assert_eq!(output, b"Hi");
+/// ```
+pub fn pem_to_bytes(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    pem_to_bytes_blocks(input, output, &PemOptions::default())
+}
+
+fn pem_to_bytes_blocks(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    options: &PemOptions,
+) -> BytaryResult<()> {
+    let invalid = |message: String| BytaryError::InvalidInputData(message);
+
+    let mut text = String::new();
+    read_text(input, &mut text)?;
+    if text.trim().is_empty() {
+        return Ok(());
+    }
+
+    let mut lines = text.lines().map(str::trim);
+    let mut blocks = 0;
+    // Offset of the end of the last END line
+    let mut after_end = 0;
+    while let Some(line) = lines.next() {
+        let Some(label) = line
+            .strip_prefix("-----BEGIN ")
+            .and_then(|rest| rest.strip_suffix("-----"))
+        else {
+            continue;
+        };
+
+        let mut body = String::new();
+        loop {
+            match lines.next() {
+                None => return Err(invalid(format!("Missing PEM END line for '{}'", label))),
+                Some(line) if line.starts_with("-----END ") => {
+                    let end = line
+                        .strip_prefix("-----END ")
+                        .and_then(|rest| rest.strip_suffix("-----"))
+                        .unwrap_or(line);
+                    if end != label {
+                        return Err(invalid(format!(
+                            "PEM END label '{}' does not match BEGIN label '{}'",
+                            end, label
+                        )));
+                    }
+                    after_end = line.as_ptr() as usize - text.as_ptr() as usize + line.len();
+                    break;
+                }
+                Some(line) => body.push_str(line),
+            }
+        }
+
+        // The body is the lines of the block joined, whose offsets are not those of the input
+        base64_to_bytes(&mut body.as_bytes(), output).map_err(BytaryError::without_offset)?;
+        blocks += 1;
+        if !options.all_blocks {
+            break;
+        }
+    }
+
+    if blocks == 0 {
+        return Err(invalid("No PEM BEGIN line found".to_string()));
+    }
+    if options.expect_eof {
+        require_eof(&mut &text.as_bytes()[after_end..], after_end as u64)?;
     }
     Ok(())
 }
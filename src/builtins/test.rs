@@ -1,6 +1,16 @@
-use crate::convert::ConversionGraph;
+use crate::builtins::{
+    Base64Alphabet, Base64Options, BinOptions, ByteSetOptions, BytesToBase64, BytesToByteSet,
+    OctOptions, Padding, PemOptions, StatsOptions, base64_to_bytes, base64_to_bytes_with_options,
+    base64_with_alphabet, bytes_to_base64, bytes_to_bin, bytes_to_bin_with_options,
+    bytes_to_byteset, bytes_to_hex, bytes_to_hexswap, bytes_to_oct, bytes_to_oct_with_options,
+    bytes_to_pem, bytes_to_pem_with_options, bytes_to_stats, bytes_to_stats_with_options,
+    hex_to_bytes, hexswap_to_bytes, oct_to_bytes, pem_to_bytes, pem_to_bytes_with_options,
+};
+use crate::conformance::{ConformanceSpec, check_converter};
+use crate::convert::{ConversionGraph, Converter};
 use crate::error::BytaryResult;
 use crate::format::Format;
+use crate::testsupport::ChunkedReader;
 use std::io::Cursor;
 use strum::IntoEnumIterator;
 
@@ -18,23 +28,183 @@ fn test_builtins() -> BytaryResult<()> {
     FromTo(Hex, Bytes).expect_ne(b"1b34", &[0x1b, 0x35])?;
     FromTo(Hex, Bytes).expect_eq(b"1b348fFf000e", &[0x1b, 0x34, 0x8f, 0xff, 0x00, 0x0e])?;
 
+    FromTo(Bytes, Base64).expect_eq(b"Hello!", b"SGVsbG8h")?;
+    FromTo(Bytes, Base64).expect_eq(b"Hi", b"SGk=")?;
+    FromTo(Base64, Bytes).expect_eq(b"SGVs\nbG8h\n", b"Hello!")?;
+    FromTo(Hex, Base64).expect_eq(b"4869", b"SGk=")?;
+    assert!(FromTo(Base64, Bytes).output(b"SGk").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_direct_call() -> BytaryResult<()> {
+    let mut bytes = Vec::new();
+    hex_to_bytes(&mut Cursor::new(b"1b 34\n8f"), &mut bytes)?;
+    assert_eq!(bytes, [0x1b, 0x34, 0x8f]);
+
+    let mut bin = Vec::new();
+    bytes_to_bin(&mut bytes.as_slice(), &mut bin)?;
+    assert_eq!(bin, b"000110110011010010001111");
+
+    // Same result as the graph
+    let mut hex = Vec::new();
+    bytes_to_hex(&mut bytes.as_slice(), &mut hex)?;
+    assert_eq!(hex, FromTo(Format::Bytes, Format::Hex).output(&bytes)?);
+    Ok(())
+}
+
+#[test]
+fn test_padding() -> BytaryResult<()> {
+    use crate::format::Format::*;
+
+    let err = FromTo(Bin, Hex).output(b"101").unwrap_err();
+    assert!(err.to_string().contains("Got 3 digits"));
+    assert!(FromTo(Bin, Hex).output(b"0000 0101 0101").is_err());
+    assert!(FromTo(Oct, Bytes).output(b"1").is_err());
+    assert!(FromTo(Oct, Bytes).output(b"0161").is_err());
+
+    let convert = |padding, from, to, input: &[u8]| -> BytaryResult<Vec<u8>> {
+        let mut output = Vec::new();
+        ConversionGraph::with_padding(padding)
+            .get_converter(&from, &to)
+            .unwrap()
+            .convert(&mut Cursor::new(input), &mut output)?;
+        Ok(output)
+    };
+
+    assert_eq!(convert(Padding::Left, Bin, Hex, b"101")?, b"05");
+    assert_eq!(convert(Padding::Right, Bin, Hex, b"101")?, b"a0");
+    assert_eq!(convert(Padding::Left, Bin, Hex, b"1 00000001")?, b"0101");
+    assert_eq!(convert(Padding::Left, Bin, Hex, b"00000001")?, b"01");
+
+    assert_eq!(convert(Padding::Left, Oct, Bytes, b"1")?, &[1]);
+    assert_eq!(convert(Padding::Right, Oct, Bytes, b"1")?, &[0o100]);
+    assert_eq!(convert(Padding::Left, Oct, Bytes, b"1 001")?, &[0o1, 0o1]);
+
+    Ok(())
+}
+
+#[test]
+fn test_empty_input() -> BytaryResult<()> {
+    let graph = ConversionGraph::default();
+
+    // The byteset report says that no value occurs
+    let expected = |from: &Format, to: &Format| match to {
+        Format::ByteSet if *from != Format::ByteSet => &b"Distinct: 0\nValues: none\n"[..],
+        _ => b"",
+    };
+
+    for (from, to, _, converter) in graph.edges() {
+        let mut output = Vec::new();
+        converter.convert(&mut Cursor::new(b""), &mut output)?;
+        assert_eq!(output, expected(from, to), "{} => {}", from, to);
+    }
+
+    for from in Format::iter() {
+        for to in Format::iter() {
+            if let Some(converter) = graph.get_converter(&from, &to) {
+                let mut output = Vec::new();
+                converter.convert(&mut Cursor::new(b""), &mut output)?;
+                assert_eq!(output, expected(&from, &to), "{} => {}", from, to);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_byteset() -> BytaryResult<()> {
+    let report = |input: &[u8], ascii: bool| -> BytaryResult<String> {
+        let mut output = Vec::new();
+        BytesToByteSet {
+            options: ByteSetOptions { ascii },
+        }
+        .convert(&mut &input[..], &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    };
+
+    let upper = (0x41..=0x5a).rev().cycle().take(100).collect::<Vec<u8>>();
+    assert_eq!(report(&upper, false)?, "Distinct: 26\nValues: 41-5a\n");
+    assert_eq!(
+        report(&upper, true)?,
+        "Distinct: 26\nValues: 41-5a\nASCII: ABCDEFGHIJKLMNOPQRSTUVWXYZ\n"
+    );
+
+    assert_eq!(report(b"", false)?, "Distinct: 0\nValues: none\n");
+    assert_eq!(report(b"", true)?, "Distinct: 0\nValues: none\nASCII: \n");
+
+    // Over more than one chunk of input
+    let all = (0..=255u8).cycle().take(3000).collect::<Vec<u8>>();
+    assert_eq!(report(&all, false)?, "Distinct: 256\nValues: 00-ff\n");
+
+    let mixed = [0x1f, 0x00, 0x10, b'a', b'c', b'b', 0xff, b' ', 0x7f, 0x80];
+    assert_eq!(
+        report(&mixed, true)?,
+        "Distinct: 10\nValues: 00, 10, 1f-20, 61-63, 7f-80, ff\nASCII:  abc\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_unpadded() -> BytaryResult<()> {
+    use crate::format::Format::*;
+    use crate::warning;
+
+    let data = [0x00, 0x0b, 0x08, 0xff];
+    let mut oct = Vec::new();
+    bytes_to_oct_with_options(OctOptions { pad: false })(&mut Cursor::new(data), &mut oct)?;
+    assert_eq!(oct, b"0 13 10 377");
+    let mut bin = Vec::new();
+    let options = BinOptions {
+        pad: false,
+        ..BinOptions::default()
+    };
+    bytes_to_bin_with_options(options)(&mut Cursor::new(data), &mut bin)?;
+    assert_eq!(bin, b"0 1011 1000 11111111");
+
+    let mut padded = Vec::new();
+    bytes_to_oct_with_options(OctOptions::default())(&mut Cursor::new(data), &mut padded)?;
+    assert_eq!(padded, b"000013010377");
+
+    // Round-trip through the token-aware decoders, without warnings
+    let graph = ConversionGraph::with_padding(Padding::Token);
+    for (format, encoded) in [(Oct, &oct), (Bin, &bin), (Oct, &padded)] {
+        let mut decoded = Vec::new();
+        let (result, warnings) = warning::collect(|| {
+            graph
+                .get_converter(&format, &Bytes)
+                .unwrap()
+                .convert(&mut Cursor::new(encoded), &mut decoded)
+        });
+        result?;
+        assert_eq!(decoded, data);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    // Without token-aware decoding, the unpadded output is ambiguous
+    assert!(FromTo(Oct, Bytes).output(&oct).is_err());
+    assert!(FromTo(Bin, Bytes).output(&bin).is_err());
+    // A stray digit does not make a byte of its own
+    assert!(FromTo(Bin, Bytes).output(b"0100100001101001 1").is_err());
+    assert!(FromTo(Bin, Bytes).output(b"01001000 01101001 0").is_err());
+
     Ok(())
 }
 
 #[test]
 fn test_all() -> BytaryResult<()> {
     use crate::format::Format;
+    use crate::testsupport::{ByteGenerator, corpus};
 
     let graph = ConversionGraph::default();
-
-    let data = [
-        0x00, 0xff, 0x01, 0x20, 0x17, 0x1b, 0x34, 0x41, 0x65, 0x8f, 0x0e,
-    ];
+    let seed = ByteGenerator::from_env().seed();
 
     let from = Format::default();
 
     for to in Format::iter() {
-        if to == from {
+        // The data is not valid UTF-8, text formats are tested in `crate::text`
+        if to == from || to.is_text() {
             continue;
         }
         let forward = graph.get_converter(&from, &to);
@@ -49,19 +219,273 @@ fn test_all() -> BytaryResult<()> {
         let forward = forward.unwrap();
         let backward = backward.unwrap();
 
-        let mut input = Vec::from(data);
-        let mut output = Vec::new();
+        for (name, data) in corpus::all(seed) {
+            let mut input = Vec::new();
+            let mut output = Vec::new();
 
-        forward(&mut Cursor::new(input.clone()), &mut output)?;
-        input.clear();
-        backward(&mut Cursor::new(output.clone()), &mut input)?;
+            forward.convert(&mut Cursor::new(&data), &mut output)?;
+            backward.convert(&mut Cursor::new(&output), &mut input)?;
 
-        assert_eq!(input, data);
+            assert!(
+                input == data,
+                "{} <-> {} changed {} (seed {})",
+                from,
+                to,
+                name,
+                seed
+            );
+        }
     }
 
     Ok(())
 }
 
+#[test]
+fn test_chunk_boundaries() -> BytaryResult<()> {
+    use crate::testsupport::{ByteGenerator, corpus};
+
+    let mut generator = ByteGenerator::from_env();
+    let graph = ConversionGraph::default();
+    for (name, data) in corpus::all(generator.seed()) {
+        // The output of the whole corpus is checked by `test_all`, a part is enough to cross many reads
+        let data = &data[..data.len().min(64 << 10)];
+        for (from, to, _, converter) in graph.edges() {
+            if *from != Format::Bytes || to.is_text() {
+                continue;
+            }
+            let mut expected = Vec::new();
+            converter.convert(&mut Cursor::new(data), &mut expected)?;
+
+            let sizes = (0..4)
+                .map(|_| 1 + generator.below(1500))
+                .collect::<Vec<usize>>();
+            let mut output = Vec::new();
+            converter.convert(&mut ChunkedReader::with_sizes(data, &sizes), &mut output)?;
+            assert!(
+                output == expected,
+                "{} => {} of {} in reads of {:?} (seed {})",
+                from,
+                to,
+                name,
+                sizes,
+                generator.seed()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_stats() -> BytaryResult<()> {
+    use crate::format::Format::*;
+
+    FromTo(Hex, Stats).expect_eq(
+        b"48656c6c6f",
+        concat!(
+            "Total: 5 bytes\n",
+            "Entropy: 1.9219 bits/byte\n",
+            "Printable: 5, non-printable: 0\n",
+            "Most frequent:\n",
+            "  6c 'l' 2 40.00%\n",
+            "  48 'H' 1 20.00%\n",
+            "  65 'e' 1 20.00%\n",
+            "  6f 'o' 1 20.00%\n",
+        )
+        .as_bytes(),
+    )?;
+
+    let report = String::from_utf8(FromTo(Bytes, Stats).output(&[0; 1000])?).unwrap();
+    assert!(report.contains("Entropy: 0.0000 bits/byte\n"), "{}", report);
+    assert!(report.contains("non-printable: 1000\n"), "{}", report);
+    assert!(
+        report.ends_with("Most frequent:\n  00     1000 100.00%\n"),
+        "{}",
+        report
+    );
+
+    // Every byte value equally often
+    let uniform = (0..4096).map(|i| i as u8).collect::<Vec<u8>>();
+    let report = String::from_utf8(FromTo(Bytes, Stats).output(&uniform)?).unwrap();
+    assert!(report.contains("Entropy: 8.0000 bits/byte\n"), "{}", report);
+    assert_eq!(report.lines().count(), 4 + 10);
+
+    // Pseudo-random bytes from xorshift
+    let mut state = 0x2545f491u32;
+    let random = (0..65536)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state >> 24) as u8
+        })
+        .collect::<Vec<u8>>();
+    let report = String::from_utf8(FromTo(Bytes, Stats).output(&random)?).unwrap();
+    let entropy = report.lines().nth(1).unwrap()["Entropy: ".len()..][..6]
+        .parse::<f64>()
+        .unwrap();
+    assert!(entropy > 7.99, "{}", report);
+
+    // Stats are not reversible, so they are never used as an intermediate step
+    assert!(
+        ConversionGraph::default()
+            .get_converter(&Stats, &Bytes)
+            .is_none()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_window_entropy() -> BytaryResult<()> {
+    let mut data = vec![0u8; 3000];
+    data.extend((0..=255).cycle().take(2500));
+
+    let mut output = Vec::new();
+    bytes_to_stats_with_options(StatsOptions { window: Some(1024) })(
+        &mut Cursor::new(&data),
+        &mut output,
+    )?;
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        concat!(
+            "00000000 0.0000\n",
+            "00000400 0.0000\n",
+            "00000800 0.7898\n",
+            "00000c00 8.0000\n",
+            "00001000 8.0000\n",
+            "00001400 7.9172\n",
+        )
+    );
+    Ok(())
+}
+
+/// A reader that returns the chunks sent to a channel, blocking until one arrives
+struct ChannelReader(std::sync::mpsc::Receiver<Vec<u8>>);
+impl std::io::Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.recv() {
+            Ok(chunk) => {
+                buf[..chunk.len()].copy_from_slice(&chunk);
+                Ok(chunk.len())
+            }
+            Err(_) => Ok(0),
+        }
+    }
+}
+
+/// A writer that appends to a buffer shared with another thread
+#[derive(Clone, Default)]
+struct SharedWriter(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+impl std::io::Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A reader that returns `error` before every chunk of at most 3 bytes
+struct FlakyReader<'a> {
+    data: &'a [u8],
+    error: std::io::ErrorKind,
+    failed: bool,
+}
+impl std::io::Read for FlakyReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.failed = !self.failed;
+        if self.failed && !self.data.is_empty() {
+            return Err(self.error.into());
+        }
+        let length = buf.len().min(self.data.len()).min(3);
+        buf[..length].copy_from_slice(&self.data[..length]);
+        self.data = &self.data[length..];
+        Ok(length)
+    }
+}
+
+#[test]
+fn test_interrupted_input() -> BytaryResult<()> {
+    use std::io::ErrorKind;
+
+    let graph = ConversionGraph::default();
+    let data = b"The quick brown fox 0123456789";
+    for (from, to, _, converter) in graph.edges() {
+        let mut expected = Vec::new();
+        let Ok(()) = converter.convert(&mut &data[..], &mut expected) else {
+            continue;
+        };
+        let mut output = Vec::new();
+        let mut input = FlakyReader {
+            data,
+            error: ErrorKind::Interrupted,
+            failed: false,
+        };
+        converter.convert(&mut input, &mut output)?;
+        assert_eq!(output, expected, "{} => {}", from, to);
+    }
+
+    let mut input = FlakyReader {
+        data,
+        error: ErrorKind::WouldBlock,
+        failed: false,
+    };
+    let err = hex_to_bytes(&mut input, &mut Vec::new()).unwrap_err();
+    assert!(
+        err.to_string().contains("read it in blocking mode"),
+        "{}",
+        err
+    );
+    Ok(())
+}
+
+#[test]
+fn test_paused_input() {
+    use crate::format::Format::*;
+    use std::time::{Duration, Instant};
+
+    for to in [Hex, Bin, Oct, Base64] {
+        let first = FromTo(Bytes, to.clone()).output(b"Hello, Wor").unwrap();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let output = SharedWriter::default();
+        let mut writer = output.clone();
+        let format = to.clone();
+        let thread = std::thread::spawn(move || {
+            let converter = ConversionGraph::default()
+                .get_converter(&Bytes, &format)
+                .unwrap();
+            converter
+                .convert(&mut ChannelReader(receiver), &mut writer)
+                .unwrap();
+        });
+
+        sender.send(b"Hello, Wor".to_vec()).unwrap();
+        let start = Instant::now();
+        // Base64 holds back the last byte, which does not fill a group of 3
+        let visible = match to {
+            Base64 => &first[..12],
+            _ => &first[..],
+        };
+        while !output.0.lock().unwrap().starts_with(visible) {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "{} output held back",
+                to
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        sender.send(b"ld!".to_vec()).unwrap();
+        drop(sender);
+        thread.join().unwrap();
+        assert_eq!(
+            *output.0.lock().unwrap(),
+            FromTo(Bytes, to).output(b"Hello, World!").unwrap()
+        );
+    }
+}
+
 struct FromTo(Format, Format);
 impl FromTo {
     fn output(&self, input: &[u8]) -> BytaryResult<Vec<u8>> {
@@ -69,7 +493,7 @@ impl FromTo {
             .get_converter(&self.0, &self.1)
             .unwrap();
         let mut output = Vec::new();
-        converter(&mut Cursor::new(input), &mut output)?;
+        converter.convert(&mut Cursor::new(input), &mut output)?;
         Ok(output)
     }
 
@@ -82,3 +506,906 @@ impl FromTo {
         Ok(())
     }
 }
+
+#[test]
+fn test_hex_styles() -> BytaryResult<()> {
+    use crate::format::Format::*;
+
+    let expected = [0x1b, 0x34, 0x8f];
+    FromTo(Hex, Bytes).expect_eq(b"0x1b,0x34,0x8f", &expected)?;
+    FromTo(Hex, Bytes).expect_eq(b"0x1b, 0X34, 0x8F\n", &expected)?;
+    FromTo(Hex, Bytes).expect_eq(b"\\x1b\\x34\\X8f", &expected)?;
+    FromTo(Hex, Bytes).expect_eq(b"1b:34:8f", &expected)?;
+    FromTo(Hex, Bytes).expect_eq(b"1b-34-8f", &expected)?;
+    FromTo(Hex, Bytes).expect_eq(b"1B 34 8F", &expected)?;
+    FromTo(Hex, Bytes).expect_eq(b"0x1b348f", &expected)?;
+
+    // A leading zero that is not followed by `x` is a digit
+    FromTo(Hex, Bytes).expect_eq(b"00 0e", &[0x00, 0x0e])?;
+    assert!(FromTo(Hex, Bytes).output(b"0").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_hex_invalid() {
+    use crate::format::Format::*;
+
+    let err = FromTo(Hex, Bytes).output(b"1b 3g").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: Invalid hex string: unexpected character 'g' at offset 4"
+    );
+    assert!(FromTo(Hex, Bytes).output(b"1x").is_err());
+    assert!(FromTo(Hex, Bytes).output(b"0x1b0x34").is_err());
+    assert!(FromTo(Hex, Bytes).output(b"1\\x2").is_err());
+    assert!(FromTo(Hex, Bytes).output(b"\\1b").is_err());
+    assert!(FromTo(Hex, Bytes).output(b"1b\\").is_err());
+    assert!(FromTo(Hex, Bytes).output(b"1b3").is_err());
+}
+
+#[test]
+fn test_hexswap() -> BytaryResult<()> {
+    use crate::format::Format::*;
+
+    FromTo(Bytes, HexNibbleSwapped).expect_eq(&[0x1b], b"b1")?;
+    FromTo(HexNibbleSwapped, Bytes).expect_eq(b"b1 43", &[0x1b, 0x34])?;
+    FromTo(Hex, HexNibbleSwapped).expect_eq(b"1B34\n8f", b"B143f8")?;
+    FromTo(HexNibbleSwapped, Hex).expect_eq(b"B143f8", b"1B348f")?;
+
+    let data = (0..=255).collect::<Vec<u8>>();
+    let graph = ConversionGraph::default();
+    let mut encoded = Vec::new();
+    graph
+        .get_converter(&Bytes, &HexNibbleSwapped)
+        .unwrap()
+        .convert(&mut &data[..], &mut encoded)?;
+    let mut decoded = Vec::new();
+    graph
+        .get_converter(&HexNibbleSwapped, &Bytes)
+        .unwrap()
+        .convert(&mut &encoded[..], &mut decoded)?;
+    assert_eq!(decoded, data);
+
+    // Swapping is one hop, not a decode and encode
+    assert_eq!(
+        graph.find_shortest_path(&Hex, &HexNibbleSwapped),
+        Some(vec![Hex, HexNibbleSwapped])
+    );
+
+    let err = FromTo(Hex, HexNibbleSwapped).output(b"1b3").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: Invalid hex string: incomplete unit at end of input (offset 3)"
+    );
+    let err = FromTo(Hex, HexNibbleSwapped).output(b"0x1b").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: Invalid hex string: unexpected character 'x' at offset 1"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_invalid_utf8() {
+    use crate::error::BytaryError;
+    use crate::format::Format::*;
+
+    // Found by the fuzz targets: these decoders read the input as text, and reported invalid UTF-8 as an IO error
+    for from in [Bin, Oct, Base64, Hex] {
+        let err = FromTo(from.clone(), Bytes).output(b"01\xff01").unwrap_err();
+        assert!(
+            matches!(
+                err,
+                BytaryError::InvalidInputData(_) | BytaryError::InvalidInputAt { .. }
+            ),
+            "{} gave {:?}",
+            from,
+            err
+        );
+    }
+    let err = pem_to_bytes_with_options(PemOptions::default())(&mut &b"\xc3"[..], &mut Vec::new())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: invalid UTF-8 at offset 0"
+    );
+}
+
+#[test]
+fn test_non_ascii_bytes() -> BytaryResult<()> {
+    use crate::builtins::{bin_to_hex, hex_to_bytes, oct_to_bytes};
+    use crate::utils::cleanup_pasted_text;
+    use crate::warning::{self, Warning};
+
+    // A no-break space in Latin-1, which is not valid UTF-8 on its own
+    let hex = b"48 \xa069\xa0";
+    for lenient in [false, true] {
+        let run = || hex_to_bytes(&mut &hex[..], &mut Vec::new());
+        let err = match lenient {
+            true => warning::collect(run).0,
+            false => run(),
+        }
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid input data: Invalid hex string: unexpected character '\u{fffd}' at offset 3"
+        );
+    }
+
+    // bin and oct name the byte, or the character
+    let (result, _) = warning::collect(|| bin_to_hex(&mut &b"0100\xa01000"[..], &mut Vec::new()));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Invalid input data: non-ASCII byte 0xa0 at offset 4"
+    );
+    let (result, _) =
+        warning::collect(|| oct_to_bytes(&mut "110 \u{2014}777".as_bytes(), &mut Vec::new()));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Invalid input data: unexpected character '\u{2014}' (U+2014) at offset 4"
+    );
+
+    // They are only skipped when asked, counting characters rather than bytes
+    let skip = |decode: fn(&mut dyn std::io::Read, &mut dyn std::io::Write) -> BytaryResult<()>,
+                input: &[u8]| {
+        let mut output = Vec::new();
+        let mut reader = cleanup_pasted_text(input).skip_non_ascii(true);
+        let (result, warnings) = warning::collect(|| decode(&mut reader, &mut output));
+        result.map(|_| (output, warnings))
+    };
+    let (output, warnings) = skip(hex_to_bytes, hex)?;
+    assert_eq!(output, b"Hi");
+    assert_eq!(warnings, [Warning::NonAscii { count: 2 }]);
+    let (output, warnings) = skip(bin_to_hex, "0100\u{2014}1000 \u{1f600}".as_bytes())?;
+    assert_eq!(output, b"48");
+    assert_eq!(warnings, [Warning::NonAscii { count: 2 }]);
+    let (output, warnings) = skip(oct_to_bytes, b"\xe9110 377")?;
+    assert_eq!(output, [0o110, 0o377]);
+    assert_eq!(warnings, [Warning::NonAscii { count: 1 }]);
+    Ok(())
+}
+
+#[test]
+fn test_lookalike_digits() -> BytaryResult<()> {
+    use crate::builtins::{bin_to_hex, hex_to_bytes, oct_to_bytes};
+    use crate::utils::cleanup_pasted_text;
+    use crate::warning::{self, Warning};
+
+    type Decode = fn(&mut dyn std::io::Read, &mut dyn std::io::Write) -> BytaryResult<()>;
+    let decode = |decode: Decode, input: &str| {
+        let mut output = Vec::new();
+        decode(&mut input.as_bytes(), &mut output).map(|_| output)
+    };
+
+    // Strict decoding names the character and what it looks like
+    let err = decode(hex_to_bytes, "ＡＢ１２").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: Invalid hex string: unexpected character 'Ａ' (U+FF21, full-width letter A) at offset 0"
+    );
+    let err = decode(hex_to_bytes, "ab١٢").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: Invalid hex string: unexpected character '١' (U+0661, Arabic-Indic digit 1) at offset 2"
+    );
+    let err = decode(bin_to_hex, "0100 ١٠٠٠").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: unexpected character '١' (U+0661, Arabic-Indic digit 1) at offset 5"
+    );
+    let err = decode(oct_to_bytes, "1²3").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: unexpected character '²' (U+00B2, superscript digit 2) at offset 1"
+    );
+    let err = decode(oct_to_bytes, "１１０").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("(U+FF11, full-width digit 1) at offset 0")
+    );
+
+    // Lenient decoding rejects them too
+    let lenient = |f: Decode, input: &str| warning::collect(|| decode(f, input));
+    for (f, input, offset) in [
+        (hex_to_bytes as Decode, "ＡＢ12", 0),
+        (bin_to_hex, "01001000 ⁰¹⁰⁰¹⁰⁰⁰", 9),
+        (oct_to_bytes, "110 ٣٧٧", 4),
+    ] {
+        let (result, warnings) = lenient(f, input);
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains(&format!(") at offset {}", offset)), "{}", err);
+        assert!(warnings.is_empty());
+    }
+
+    // Cleaning up pasted text replaces full-width forms, but not the other look-alikes
+    let cleaned = |f: Decode, input: &str| {
+        let mut output = Vec::new();
+        let mut reader = cleanup_pasted_text(input.as_bytes()).fullwidth(true);
+        let (result, warnings) = warning::collect(|| f(&mut reader, &mut output));
+        result.map(|_| (output, warnings))
+    };
+    let (output, warnings) = cleaned(hex_to_bytes, "ＡＢ１２")?;
+    assert_eq!(output, [0xab, 0x12]);
+    assert_eq!(warnings, [Warning::Transliterated { count: 4 }]);
+    assert_eq!(cleaned(bin_to_hex, "０１００\u{3000}１０００")?.0, b"48");
+    assert_eq!(cleaned(oct_to_bytes, "１１０")?.0, [0o110]);
+    let err = warning::strict(|| {
+        let mut reader = cleanup_pasted_text("12٣".as_bytes()).fullwidth(true);
+        hex_to_bytes(&mut reader, &mut Vec::new())
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("(U+0663, Arabic-Indic digit 3)"));
+    Ok(())
+}
+
+#[test]
+fn test_digits_out_of_range() {
+    use crate::format::Format::*;
+
+    // 3 octal digits can exceed a byte, and `[^0-9]` leaves 8 and 9 in binary input
+    for padding in [
+        Padding::Strict,
+        Padding::Left,
+        Padding::Right,
+        Padding::Token,
+    ] {
+        let graph = ConversionGraph::with_padding(padding);
+        for (from, input) in [(Oct, &b"777"[..]), (Oct, b"400"), (Bin, b"00000009")] {
+            let converter = graph.get_converter(&from, &Bytes).unwrap();
+            assert!(converter.convert(&mut &input[..], &mut Vec::new()).is_err());
+        }
+    }
+}
+
+#[test]
+fn test_oct_overflow() -> BytaryResult<()> {
+    use crate::builtins::{OctToBytes, bytes_to_oct, oct_to_bytes};
+    use crate::convert::Converter;
+    use crate::warning::{self, Warning};
+
+    let decode = |input: &[u8]| {
+        let mut output = Vec::new();
+        oct_to_bytes(&mut &input[..], &mut output).map(|_| output)
+    };
+    assert_eq!(decode(b"377")?, [0xff]);
+    for (input, message) in [
+        (&b"777"[..], "octal group 777 at offset 0"),
+        (b"400", "octal group 400 at offset 0"),
+        (b"001 377\n400", "octal group 400 at offset 8"),
+        (b"0 0 1\t 5 0 0", "octal group 500 at offset 7"),
+    ] {
+        let err = decode(input).unwrap_err().to_string();
+        assert!(err.contains(message), "{}", err);
+    }
+
+    // Offsets point into the input, not at the digits left after padding
+    let padded = |padding, input: &[u8]| {
+        OctToBytes {
+            padding,
+            mask: false,
+        }
+        .convert(&mut &input[..], &mut Vec::new())
+        .unwrap_err()
+        .to_string()
+    };
+    assert!(padded(Padding::Token, b"1 13 777").contains("at offset 5"));
+    assert!(padded(Padding::Left, b"1 7 7 7").contains("at offset 2"));
+    assert!(padded(Padding::Right, b"12 45").contains("group 500 at offset 4"));
+
+    // Masked groups keep their low 8 bits
+    let masked = OctToBytes {
+        padding: Padding::Strict,
+        mask: true,
+    };
+    let mut output = Vec::new();
+    let (result, warnings) =
+        warning::collect(|| masked.convert(&mut &b"777 400 377 016"[..], &mut output));
+    result?;
+    assert_eq!(output, [0xff, 0x00, 0xff, 0o16]);
+    assert_eq!(warnings, [Warning::Masked { count: 2 }]);
+
+    // Every byte is encoded as a group that decodes back to it
+    let all = (0..=255u8).collect::<Vec<u8>>();
+    let mut encoded = Vec::new();
+    bytes_to_oct(&mut &all[..], &mut encoded)?;
+    assert_eq!(decode(&encoded)?, all);
+    Ok(())
+}
+
+#[test]
+fn test_hex_prefix_across_chunks() -> BytaryResult<()> {
+    use crate::builtins::HexDecoder;
+
+    for input in [&b"0x1b 0x34"[..], b"\\x1b\\x34", b"1b:34"] {
+        for split in 0..=input.len() {
+            let mut output = Vec::new();
+            let mut decoder = HexDecoder::new();
+            decoder.feed(&input[..split], &mut output).unwrap();
+            decoder.feed(&input[split..], &mut output).unwrap();
+            decoder.finish(&mut output).unwrap();
+            assert_eq!(output, [0x1b, 0x34], "split {:?} at {}", input, split);
+        }
+    }
+
+    let converter = ConversionGraph::default()
+        .get_converter(&Format::Hex, &Format::Bytes)
+        .unwrap();
+    let mut output = Vec::new();
+    // The reader returns one byte at a time
+    converter.convert(&mut ChunkedReader::new(b"0x1b,0x34", 1), &mut output)?;
+    assert_eq!(output, [0x1b, 0x34]);
+
+    Ok(())
+}
+
+#[test]
+fn test_pem() -> BytaryResult<()> {
+    use crate::format::Format::*;
+
+    let certificate = include_bytes!("../../tests/fixtures/cert.pem");
+    let der = include_bytes!("../../tests/fixtures/cert.der");
+    FromTo(Pem, Bytes).expect_eq(certificate, der)?;
+
+    // Text before the armor is ignored, like in `openssl x509 -text` output
+    let mut with_garbage = b"Certificate:\n    Subject: CN=bytary test\n".to_vec();
+    with_garbage.extend_from_slice(certificate);
+    FromTo(Pem, Bytes).expect_eq(&with_garbage, der)?;
+
+    // Only the first block is decoded by default
+    let two_blocks =
+        b"-----BEGIN A-----\nSGk=\n-----END A-----\n-----BEGIN B-----\nIQ==\n-----END B-----\n";
+    FromTo(Pem, Bytes).expect_eq(two_blocks, b"Hi")?;
+    let mut output = Vec::new();
+    pem_to_bytes_with_options(PemOptions {
+        all_blocks: true,
+        ..PemOptions::default()
+    })(&mut &two_blocks[..], &mut output)?;
+    assert_eq!(output, b"Hi!");
+
+    let mut output = Vec::new();
+    bytes_to_pem_with_options(PemOptions {
+        label: "CERTIFICATE".to_string(),
+        ..PemOptions::default()
+    })(&mut &der[..], &mut output)?;
+    assert_eq!(output, certificate);
+
+    let error = FromTo(Pem, Bytes)
+        .output(b"-----BEGIN CERTIFICATE-----\nSGk=\n-----END PRIVATE KEY-----\n")
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: PEM END label 'PRIVATE KEY' does not match BEGIN label 'CERTIFICATE'"
+    );
+    let error = FromTo(Pem, Bytes)
+        .output(b"-----BEGIN DATA-----\nSGk=\n")
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: Missing PEM END line for 'DATA'"
+    );
+    assert!(FromTo(Pem, Bytes).output(b"SGk=\n").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_expect_eof() -> BytaryResult<()> {
+    let base64 = |input: &[u8]| {
+        let mut output = Vec::new();
+        base64_to_bytes_with_options(Base64Options {
+            expect_eof: true,
+            ..Base64Options::default()
+        })(&mut &input[..], &mut output)
+        .map(|_| output)
+    };
+    assert_eq!(base64(b"SGk=")?, b"Hi");
+    assert_eq!(base64(b"SGk=\n \r\n")?, b"Hi");
+    assert_eq!(base64(b"SGVs\nbG8h\n")?, b"Hello!");
+    assert_eq!(
+        base64(b"SGk=\njunk").unwrap_err().to_string(),
+        "Trailing data at offset 5"
+    );
+    assert_eq!(
+        base64(b"SQ==SQ==").unwrap_err().to_string(),
+        "Trailing data at offset 4"
+    );
+
+    let pem = |input: &[u8], all_blocks: bool| {
+        let mut output = Vec::new();
+        pem_to_bytes_with_options(PemOptions {
+            all_blocks,
+            expect_eof: true,
+            ..PemOptions::default()
+        })(&mut &input[..], &mut output)
+        .map(|_| output)
+    };
+    let block = b"-----BEGIN DATA-----\nSGk=\n-----END DATA-----";
+    assert_eq!(pem(block, false)?, b"Hi");
+    assert_eq!(pem(&[&block[..], b"\n\n  \n"].concat(), false)?, b"Hi");
+    let junk = [&block[..], b"\ngarbage\n"].concat();
+    assert_eq!(
+        pem(&junk, false).unwrap_err().to_string(),
+        "Trailing data at offset 45"
+    );
+    // Text between blocks is allowed, but not after the last one
+    let two_blocks = [&block[..], b"\nnote\n", &block[..], b"\n"].concat();
+    assert_eq!(pem(&two_blocks, true)?, b"HiHi");
+    assert!(pem(&two_blocks, false).is_err());
+    assert_eq!(
+        pem(&[&two_blocks[..], b"x"].concat(), true)
+            .unwrap_err()
+            .to_string(),
+        "Trailing data at offset 95"
+    );
+
+    // Trailing data is ignored without the option
+    FromTo(Format::Pem, Format::Bytes).expect_eq(&junk, b"Hi")?;
+    Ok(())
+}
+
+/// A writer that accepts `capacity` bytes, then fails as if the disk were full
+struct FullDisk {
+    written: usize,
+    capacity: usize,
+}
+
+impl std::io::Write for FullDisk {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written + buf.len() > self.capacity {
+            return Err(std::io::ErrorKind::StorageFull.into());
+        }
+        self.written += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_write_error() {
+    // Whole reads only, so that nothing is flushed on a short read
+    let data = vec![0x5au8; 4096];
+    let graph = ConversionGraph::default();
+    for to in [
+        Format::Bin,
+        Format::Hex,
+        Format::Oct,
+        Format::Base64,
+        Format::Pem,
+    ] {
+        let converter = graph.get_converter(&Format::Bytes, &to).unwrap();
+        let mut output = FullDisk {
+            written: 0,
+            capacity: 100,
+        };
+        let error = converter
+            .convert(&mut Cursor::new(&data), &mut output)
+            .unwrap_err();
+        assert!(
+            matches!(error, crate::error::BytaryError::Io(ref e) if e.kind() == std::io::ErrorKind::StorageFull),
+            "{}: {:?}",
+            to,
+            error
+        );
+    }
+}
+
+#[test]
+fn test_base64_alphabet() -> BytaryResult<()> {
+    let crypt = *b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    let encode = |alphabet: Base64Alphabet, input: &[u8]| {
+        let mut output = Vec::new();
+        BytesToBase64 { alphabet }
+            .convert(&mut &input[..], &mut output)
+            .map(|_| output)
+    };
+    let decode = |alphabet: Base64Alphabet, input: &[u8]| {
+        let mut output = Vec::new();
+        base64_to_bytes_with_options(Base64Options {
+            alphabet,
+            ..Base64Options::default()
+        })(&mut &input[..], &mut output)
+        .map(|_| output)
+    };
+
+    // Round trip with the crypt(3) alphabet
+    let alphabet = base64_with_alphabet(crypt, Some(b'='))?;
+    let data = (0..=255).collect::<Vec<u8>>();
+    let encoded = encode(alphabet, &data)?;
+    assert!(encoded.iter().all(|c| crypt.contains(c) || *c == b'='));
+    assert_eq!(decode(alphabet, &encoded)?, data);
+    assert_eq!(encode(alphabet, b"Hi")?, b"G4Y=");
+
+    // Standard base64 is not accepted
+    assert_eq!(
+        decode(alphabet, b"SGk+").unwrap_err().to_string(),
+        "Invalid input data: Invalid base64 string: unexpected character '+' at offset 3, which is not in the alphabet"
+    );
+
+    // Padding on and off
+    let unpadded = base64_with_alphabet(crypt, None)?;
+    assert_eq!(encode(unpadded, b"Hi")?, b"G4Y");
+    assert_eq!(encode(unpadded, b"Hi!")?, b"G4YV");
+    assert_eq!(decode(unpadded, b"G4Y")?, b"Hi");
+    assert!(decode(unpadded, b"G4Y=").is_err());
+    assert!(decode(alphabet, b"G4Y").is_err());
+    let dotted = base64_with_alphabet(*Base64Alphabet::default().chars(), Some(b'.'))?;
+    assert_eq!(encode(dotted, b"H")?, b"SA..");
+    assert_eq!(decode(dotted, b"SA..")?, b"H");
+
+    // Invalid alphabets
+    let mut duplicate = crypt;
+    duplicate[1] = b'.';
+    assert_eq!(
+        base64_with_alphabet(duplicate, None)
+            .unwrap_err()
+            .to_string(),
+        "Invalid argument: the base64 alphabet has '.' more than once"
+    );
+    assert!(base64_with_alphabet(crypt, Some(b'.')).is_err());
+    let mut spaced = crypt;
+    spaced[0] = b' ';
+    assert!(base64_with_alphabet(spaced, None).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_reformat_text() -> BytaryResult<()> {
+    use crate::builtins::reformat_text;
+    use crate::utils::FormattedWriter;
+    use crate::warning;
+
+    let hex = reformat_text("0123456789abcdefABCDEF");
+    let mut output = Vec::new();
+    hex(
+        &mut &b"DE AD\nBE EF 0"[..],
+        &mut FormattedWriter::new(&mut output, 4, 0),
+    )?;
+    assert_eq!(output, b"DEAD BEEF 0");
+
+    let err = hex(&mut &b"DE AD\n0x"[..], &mut Vec::new()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: unexpected character 'x' at offset 7"
+    );
+
+    // Nothing is skipped, since the input is not decoded to tell what was meant
+    let (result, warnings) =
+        warning::collect(|| reformat_text("01234567")(&mut &b"017 8"[..], &mut Vec::new()));
+    assert_eq!(result.unwrap_err().offset(), Some(4));
+    assert!(warnings.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_bit_order() -> BytaryResult<()> {
+    use crate::bits::BitOrder;
+    use crate::builtins::{BinToHex, BytesToBin};
+
+    let encode = |bit_order, input: &[u8]| -> BytaryResult<Vec<u8>> {
+        let mut output = Vec::new();
+        let options = BinOptions {
+            bit_order,
+            ..BinOptions::default()
+        };
+        BytesToBin { options }.convert(&mut &input[..], &mut output)?;
+        Ok(output)
+    };
+    let decode = |bit_order, input: &[u8]| -> BytaryResult<Vec<u8>> {
+        let mut output = Vec::new();
+        let padding = Padding::Strict;
+        BinToHex { padding, bit_order }.convert(&mut &input[..], &mut output)?;
+        Ok(output)
+    };
+    assert_eq!(encode(BitOrder::LsbFirst, &[0x01])?, b"10000000");
+    assert_eq!(encode(BitOrder::MsbFirst, &[0x01])?, b"00000001");
+    assert_eq!(decode(BitOrder::LsbFirst, b"11000000")?, b"03");
+
+    // Pseudo-random bytes from xorshift
+    let mut state = 0x2545f491u32;
+    let random = (0..4096)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state >> 24) as u8
+        })
+        .collect::<Vec<u8>>();
+    let mut hex = Vec::new();
+    bytes_to_hex(&mut &random[..], &mut hex)?;
+    for bit_order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+        let bin = encode(bit_order, &random)?;
+        assert_eq!(decode(bit_order, &bin)?, hex, "{:?}", bit_order);
+    }
+
+    // The orders are not interchangeable
+    let bin = encode(BitOrder::MsbFirst, &random)?;
+    assert_ne!(decode(BitOrder::LsbFirst, &bin)?, hex);
+    Ok(())
+}
+
+#[test]
+fn test_digit_decoders_match_regex() {
+    use crate::builtins::{BinToHex, OctToBytes};
+    use crate::error::BytaryError;
+    use crate::fast;
+    use crate::utils::{is_ignorable, is_separator};
+    use crate::warning;
+    use crate::warning::Warning;
+    use regex::Regex;
+
+    /// The bin and oct decoders as they were before the state machine, on ASCII text,
+    /// without warnings for the short groups of unpadded output
+    fn reference(
+        text: &str,
+        re: &Regex,
+        oct: bool,
+        padding: Padding,
+        mask: bool,
+    ) -> BytaryResult<Vec<u8>> {
+        let group = match oct {
+            true => 3,
+            false => 8,
+        };
+        let pad = |digits: String| -> BytaryResult<String> {
+            let zeros = "0".repeat((group - digits.len() % group) % group);
+            match padding {
+                _ if zeros.is_empty() => Ok(digits),
+                Padding::Strict => BytaryError::InvalidInputData("not a multiple".into()).err(),
+                Padding::Left | Padding::Token => Ok(zeros + &digits),
+                Padding::Right => Ok(digits + &zeros),
+            }
+        };
+        let is_blank = |c: char| c.is_ascii() && (is_ignorable(c as u8) || is_separator(c as u8));
+        if re
+            .find_iter(text)
+            .any(|m| !m.as_str().chars().all(is_blank))
+        {
+            return BytaryError::InvalidInputData("not a digit".into()).err();
+        }
+        let mut zeros = 0;
+        let mut digits = match padding {
+            Padding::Token => {
+                let mut digits = String::new();
+                for token in text.split(is_blank).filter(|token| !token.is_empty()) {
+                    let padded = pad(re.replace_all(token, "").into_owned())?;
+                    // Tokens of unpadded output are padded without a warning
+                    let short =
+                        token.len() < group && (token.len() == 1 || !token.starts_with('0'));
+                    if !short {
+                        zeros += padded.len() - token.len();
+                    }
+                    digits += &padded;
+                }
+                digits
+            }
+            _ => {
+                let digits = pad(re.replace_all(text, "").into_owned())?;
+                zeros = digits.len() - re.replace_all(text, "").len();
+                digits
+            }
+        }
+        .into_bytes();
+        if zeros > 0 {
+            warning::report(Warning::Padded { zeros });
+        }
+        if !oct {
+            let mut bytes = vec![0u8; fast::bin::decoded_len(digits.len())];
+            fast::bin::decode_into(&digits, &mut bytes)?;
+            return Ok(::hex::encode(bytes).into_bytes());
+        }
+        let mut masked = 0;
+        for group in digits.chunks_exact_mut(3) {
+            if group[0] > b'3' {
+                if !mask {
+                    return BytaryError::InvalidInputData("above 377".into()).err();
+                }
+                group[0] -= 4;
+                masked += 1;
+            }
+        }
+        if masked > 0 {
+            warning::report(Warning::Masked { count: masked });
+        }
+        let mut bytes = vec![0u8; fast::oct::decoded_len(digits.len())];
+        fast::oct::decode_into(&digits, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    // Pseudo-random text from xorshift, mostly digits with some separators and other characters
+    let mut state = 0x9e3779b9u32;
+    let mut next = move |bound: u32| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state % bound
+    };
+    let alphabet = b"0101010101234567012345670189  \n\t\x0b\r,_x";
+    let mut corpus = vec![String::new(), " \n".into(), "0".into(), "0b1010".into()];
+    for _ in 0..400 {
+        let length = next(40) as usize;
+        let text = (0..length)
+            .map(|_| alphabet[next(alphabet.len() as u32) as usize] as char)
+            .collect::<String>();
+        corpus.push(text);
+    }
+    // Long runs of valid digits, aligned to bytes or not
+    for length in [1, 7, 8, 9, 33] {
+        let bytes = (0..length).map(|_| next(256) as u8).collect::<Vec<u8>>();
+        let mut bin = Vec::new();
+        bytes_to_bin(&mut &bytes[..], &mut bin).unwrap();
+        let bin = String::from_utf8(bin).unwrap();
+        corpus.push(format!("1{}", bin));
+        corpus.push(format!("{} {}", &bin[..3], &bin[3..]));
+        corpus.push(format!("{}2{}", &bin[..length], &bin[length..]));
+        corpus.push(bin);
+    }
+
+    let paddings = [
+        Padding::Strict,
+        Padding::Left,
+        Padding::Right,
+        Padding::Token,
+    ];
+    let (bin_re, oct_re) = (
+        Regex::new(r"[^0-9]").unwrap(),
+        Regex::new(r"[^0-7]").unwrap(),
+    );
+    for text in &corpus {
+        for padding in paddings {
+            for (oct, mask) in [(false, false), (true, false), (true, true)] {
+                let converter: Box<dyn Converter> = match oct {
+                    true => Box::new(OctToBytes { padding, mask }),
+                    false => Box::new(BinToHex {
+                        padding,
+                        ..BinToHex::default()
+                    }),
+                };
+                let (expected, expected_warnings) = warning::collect(|| {
+                    let re = if oct { &oct_re } else { &bin_re };
+                    reference(text, re, oct, padding, mask)
+                });
+                let (actual, actual_warnings) = warning::collect(|| {
+                    let mut output = Vec::new();
+                    converter
+                        .convert(&mut text.as_bytes(), &mut output)
+                        .map(|_| output)
+                });
+                let case = format!("{:?} {:?} oct={} mask={}", text, padding, oct, mask);
+                match (expected, actual) {
+                    (Ok(expected), Ok(actual)) => assert_eq!(expected, actual, "{}", case),
+                    (Err(_), Err(_)) => {}
+                    (expected, actual) => panic!("{}: {:?} {:?}", case, expected, actual),
+                }
+                assert_eq!(expected_warnings, actual_warnings, "{}", case);
+            }
+        }
+    }
+
+    // Unlike before, the offset of a bad digit is in the input rather than in the digits
+    let mut output = Vec::new();
+    let e = crate::builtins::bin_to_hex(&mut &b"0100 0001 0120 0000"[..], &mut output).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "Invalid input data: unexpected character '2' at offset 12"
+    );
+}
+
+#[test]
+fn test_whitespace_conformance() {
+    use crate::builtins::{base64_to_bytes, bin_to_hex, oct_to_bytes};
+    use crate::error::BytaryResult;
+    use crate::utils::{is_ignorable, is_separator};
+    use crate::warning;
+    use std::io::{Read, Write};
+
+    type Decoder = fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()>;
+    // Each decoder, its input for "Hi" split in two, and its output
+    let decoders: [(&str, Decoder, [&str; 2], &[u8]); 4] = [
+        ("hex", hex_to_bytes, ["48", "69"], b"Hi"),
+        ("bin", bin_to_hex, ["01001000", "01101001"], b"4869"),
+        ("oct", oct_to_bytes, ["110", "151"], b"Hi"),
+        ("base64", base64_to_bytes, ["SG", "k="], b"Hi"),
+    ];
+    for byte in (0..0x20).chain([b' ', 0x7f]) {
+        for (name, decode, [head, tail], expected) in decoders {
+            for input in [
+                format!("{}{}{}", head, byte as char, tail),
+                format!("{}{}{}", head, tail, byte as char),
+            ] {
+                let run = || {
+                    let mut output = Vec::new();
+                    decode(&mut input.as_bytes(), &mut output).map(|_| output)
+                };
+                let case = format!("{} {:?}", name, input);
+                match warning::strict(run) {
+                    Ok(output) => {
+                        assert!(is_ignorable(byte), "{}", case);
+                        assert_eq!(output, expected, "{}", case);
+                    }
+                    Err(_) => assert!(!is_ignorable(byte), "{}", case),
+                }
+                // Collecting warnings does not make the decoders skip control characters
+                match warning::collect(run) {
+                    (Ok(output), warnings) => {
+                        assert!(is_ignorable(byte), "{}", case);
+                        assert_eq!(output, expected, "{}", case);
+                        assert!(warnings.is_empty(), "{}", case);
+                    }
+                    (Err(e), _) => {
+                        assert!(!is_ignorable(byte), "{}", case);
+                        assert!(e.offset().is_some(), "{}: {}", case, e);
+                    }
+                }
+            }
+        }
+    }
+
+    // Printable characters that are not digits are separators of every digit decoder, or an error
+    // of every one, even if warnings are collected
+    for byte in b"x#-:,_/\\g+.".iter().copied() {
+        for (name, decode, [head, tail], expected) in &decoders[..3] {
+            for input in [
+                format!("{}{}{}", head, byte as char, tail),
+                format!("{}{}{}", head, tail, byte as char),
+            ] {
+                let run = || {
+                    let mut output = Vec::new();
+                    decode(&mut input.as_bytes(), &mut output).map(|_| output)
+                };
+                let case = format!("{} {:?}", name, input);
+                for (result, warnings) in [(warning::strict(run), vec![]), warning::collect(run)] {
+                    match result {
+                        Ok(output) => {
+                            assert!(is_separator(byte), "{}", case);
+                            assert_eq!(output, *expected, "{}", case);
+                        }
+                        Err(e) => {
+                            assert!(!is_separator(byte), "{}", case);
+                            assert!(e.offset().is_some(), "{}: {}", case, e);
+                        }
+                    }
+                    assert!(warnings.is_empty(), "{}", case);
+                }
+            }
+        }
+    }
+
+    // Files end with a line break, on any platform
+    for (name, decode, [head, tail], expected) in decoders {
+        for ending in ["\n", "\r\n"] {
+            let input = format!("{}{}{}", head, tail, ending);
+            let mut output = Vec::new();
+            warning::strict(|| decode(&mut input.as_bytes(), &mut output)).unwrap();
+            assert_eq!(output, expected, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn test_conformance() {
+    crate::assert_conformant!(bytes_to_hex, hex_to_bytes);
+    crate::assert_conformant!(bytes_to_base64, base64_to_bytes);
+    crate::assert_conformant!(bytes_to_pem, pem_to_bytes);
+    crate::assert_conformant!(bytes_to_oct, oct_to_bytes);
+    crate::assert_conformant!(bytes_to_hexswap, hexswap_to_bytes);
+    crate::assert_conformant!(bytes_to_stats);
+    crate::assert_conformant!(bytes_to_byteset);
+}
+
+/// Every format of the graph that any bytes can be encoded to
+#[test]
+fn test_graph_conformance() {
+    let graph = ConversionGraph::default();
+    for format in Format::iter().filter(|format| !format.is_text()) {
+        let Some(encode) = graph.get_converter(&Format::Bytes, &format) else {
+            continue;
+        };
+        let decode = graph.get_converter(&format, &Format::Bytes);
+        let report = check_converter(&*encode, decode.as_deref(), ConformanceSpec::default());
+        assert!(report.passed(), "bytes <=> {}: {}", format, report);
+    }
+}
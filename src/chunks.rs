@@ -0,0 +1,419 @@
+use crate::builtins;
+use crate::builtins::HexDecoder;
+use crate::convert::{ConversionGraph, Converter};
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use crate::utils::{into_io_error, is_ignorable, is_separator};
+use std::io;
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+#[cfg(test)]
+mod test;
+
+/// Whitespace and separators, which are dropped from digit input.
+///
+/// Other characters are kept, so that the decoder rejects them as if the input was not chunked.
+fn is_blank(byte: &u8) -> bool {
+    is_ignorable(*byte) || is_separator(*byte)
+}
+
+/// A conversion between two adjacent formats that can be applied chunk by chunk.
+#[derive(Clone, Copy)]
+pub(crate) enum Edge {
+    /// The bytes that carry no data are dropped, and the rest is handed to the sync converter
+    /// in whole groups, so that no group is ever split between two calls.
+    Grouped {
+        /// Returns true for input bytes that should be dropped before grouping
+        skip: fn(&u8) -> bool,
+        /// Number of input bytes that are converted independently of the others
+        group: usize,
+        convert: fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()>,
+    },
+    /// Input is fed to a streaming hex decoder, which keeps its state between chunks
+    HexDecode,
+}
+
+impl Edge {
+    /// The chunked implementation of the conversion from `from` to `to` of `graph`, if it is a
+    /// built-in one that has one. Conversions that replaced a built-in one have none.
+    pub(crate) fn get(graph: &ConversionGraph, from: &Format, to: &Format) -> Option<Self> {
+        if !graph.is_chunked(from, to) {
+            return None;
+        }
+        let edge = |skip, group, convert| {
+            Some(Self::Grouped {
+                skip,
+                group,
+                convert,
+            })
+        };
+        match (from, to) {
+            (Format::Bytes, Format::Bin) => edge(|_| false, 1, builtins::bytes_to_bin),
+            (Format::Bin, Format::Hex) => edge(is_blank, 8, builtins::bin_to_hex),
+            (Format::Bytes, Format::Oct) => edge(|_| false, 1, builtins::bytes_to_oct),
+            (Format::Oct, Format::Bytes) => edge(is_blank, 3, builtins::oct_to_bytes),
+            (Format::Bytes, Format::Hex) => edge(|_| false, 1, builtins::bytes_to_hex),
+            (Format::Hex, Format::Bytes) => Some(Self::HexDecode),
+            _ => None,
+        }
+    }
+
+    /// Starts a conversion, which carries its state from one chunk to the next
+    pub(crate) fn start(&self) -> Step {
+        match *self {
+            Self::Grouped {
+                skip,
+                group,
+                convert,
+            } => Step::Grouped {
+                skip,
+                group,
+                convert,
+                pending: Vec::new(),
+            },
+            Self::HexDecode => Step::HexDecode(HexDecoder::new()),
+        }
+    }
+}
+
+/// A running conversion of an [`Edge`].
+pub(crate) enum Step {
+    Grouped {
+        skip: fn(&u8) -> bool,
+        group: usize,
+        convert: fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()>,
+        /// Input bytes that do not make a whole group yet
+        pending: Vec<u8>,
+    },
+    HexDecode(HexDecoder),
+}
+
+impl Step {
+    /// Converts the next chunk of input, appending what is complete to `output`
+    pub(crate) fn feed(&mut self, chunk: &[u8], output: &mut Vec<u8>) -> BytaryResult<()> {
+        match self {
+            Self::Grouped {
+                skip,
+                group,
+                convert,
+                pending,
+            } => {
+                pending.extend(chunk.iter().filter(|b| !skip(b)));
+                let ready = pending.len() - pending.len() % *group;
+                if ready > 0 {
+                    // The groups are converted apart, so their offsets are not those of the input
+                    convert(&mut &pending[..ready], output).map_err(BytaryError::without_offset)?;
+                    pending.drain(..ready);
+                }
+                Ok(())
+            }
+            Self::HexDecode(decoder) => decoder.feed(chunk, output).map_err(builtins::invalid_hex),
+        }
+    }
+
+    /// Ends the conversion, appending what was left to `output`
+    pub(crate) fn finish(&mut self, output: &mut Vec<u8>) -> BytaryResult<()> {
+        match self {
+            Self::Grouped {
+                convert, pending, ..
+            } => match pending.is_empty() {
+                true => Ok(()),
+                false => convert(&mut &std::mem::take(pending)[..], output)
+                    .map_err(BytaryError::without_offset),
+            },
+            Self::HexDecode(decoder) => decoder.finish(output).map_err(builtins::invalid_hex),
+        }
+    }
+}
+
+/// Converts the chunks of an iterator, see [`ConversionGraph::iter_converter`].
+pub struct ChunkConverter<I> {
+    input: I,
+    mode: Mode,
+    done: bool,
+}
+
+enum Mode {
+    /// Every chunk goes through all the steps as soon as it is read
+    Chunked(Vec<Step>),
+    /// The whole input is collected, then converted at once
+    Buffered {
+        converter: Rc<dyn Converter>,
+        input: Vec<u8>,
+    },
+}
+
+impl Mode {
+    /// Passes `chunk` through the steps, returning what comes out of the last one
+    fn feed(&mut self, chunk: &[u8]) -> BytaryResult<Vec<u8>> {
+        match self {
+            Mode::Chunked(steps) => feed_steps(steps, chunk),
+            Mode::Buffered { input, .. } => {
+                input.extend_from_slice(chunk);
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Ends the steps in order, what is left by each one going through the following ones
+    fn finish(&mut self) -> BytaryResult<Vec<u8>> {
+        match self {
+            Mode::Chunked(steps) => finish_steps(steps),
+            Mode::Buffered { converter, input } => {
+                let mut output = Vec::new();
+                converter.convert(&mut &std::mem::take(input)[..], &mut output)?;
+                Ok(output)
+            }
+        }
+    }
+}
+
+/// Passes `chunk` through `steps`, returning what comes out of the last one
+pub(crate) fn feed_steps(steps: &mut [Step], chunk: &[u8]) -> BytaryResult<Vec<u8>> {
+    let mut data = chunk.to_vec();
+    for (index, step) in steps.iter_mut().enumerate() {
+        let mut output = Vec::new();
+        step.feed(&data, &mut output)
+            .map_err(|e| step_error(index, e))?;
+        data = output;
+    }
+    Ok(data)
+}
+
+/// Ends `steps` in order, what is left by each one going through the following ones
+pub(crate) fn finish_steps(steps: &mut [Step]) -> BytaryResult<Vec<u8>> {
+    let mut data = Vec::new();
+    for (index, step) in steps.iter_mut().enumerate() {
+        let mut output = Vec::new();
+        step.feed(&data, &mut output)
+            .and_then(|_| step.finish(&mut output))
+            .map_err(|e| step_error(index, e))?;
+        data = output;
+    }
+    Ok(data)
+}
+
+/// The error of the step at `index`, whose offset is only that of the input for the first step
+fn step_error(index: usize, error: BytaryError) -> BytaryError {
+    match index {
+        0 => error,
+        _ => error.without_offset(),
+    }
+}
+
+impl<I> Iterator for ChunkConverter<I>
+where
+    I: Iterator<Item = io::Result<Vec<u8>>>,
+{
+    type Item = BytaryResult<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let result = match self.input.next() {
+                Some(Err(e)) => Err(e.into()),
+                Some(Ok(chunk)) => self.mode.feed(&chunk),
+                None => {
+                    self.done = true;
+                    self.mode.finish()
+                }
+            };
+            match result {
+                Ok(output) if output.is_empty() => continue,
+                Ok(output) => return Some(Ok(output)),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A writer that converts what is written to it, and writes the output to its target as it becomes ready,
+/// see [`ConversionGraph::chunk_writer`].
+///
+/// Errors of the conversion are returned as io errors, which [`BytaryError::from`](crate::error::BytaryError)
+/// turns back into the original error.
+pub struct ChunkWriter<W: Write> {
+    mode: Mode,
+    target: W,
+}
+
+impl<W: Write> ChunkWriter<W> {
+    /// Ends the conversion, writing what is left and flushing the target, which is returned
+    pub fn finish(mut self) -> BytaryResult<W> {
+        let output = self.mode.finish()?;
+        self.target.write_all(&output)?;
+        self.target.flush()?;
+        Ok(self.target)
+    }
+}
+
+impl<W: Write> Write for ChunkWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let output = self.mode.feed(buf).map_err(into_io_error)?;
+        self.target.write_all(&output)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+}
+
+/// A reader that converts what it reads from its input, see [`ConversionGraph::chunk_reader`].
+///
+/// Errors of the conversion are returned as io errors, like for [`ChunkWriter`].
+pub struct ChunkReader<R: Read> {
+    mode: Mode,
+    input: R,
+    /// Output that was not read yet, from `position`
+    output: Vec<u8>,
+    position: usize,
+    done: bool,
+}
+
+impl<R: Read> Read for ChunkReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.output.len() {
+            if self.done || buf.is_empty() {
+                return Ok(0);
+            }
+            let mut chunk = [0u8; 8192];
+            let length = self.input.read(&mut chunk)?;
+            self.output = match length {
+                0 => {
+                    self.done = true;
+                    self.mode.finish()
+                }
+                _ => self.mode.feed(&chunk[..length]),
+            }
+            .map_err(into_io_error)?;
+            self.position = 0;
+        }
+        let length = buf.len().min(self.output.len() - self.position);
+        buf[..length].copy_from_slice(&self.output[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+impl ConversionGraph {
+    /// Converts the chunks of `input` from `from` to `to`, yielding output chunks as they become ready.
+    ///
+    /// The built-in conversions between bytes, bin, hex and oct are done chunk by chunk, so output
+    /// comes out before the input is exhausted, unless they pad incomplete input. Any other conversion,
+    /// including one that replaced a built-in one, buffers the whole input, and yields its output
+    /// once the input ends.
+    ///
+    /// The first error, from the input or the conversion, is yielded in place of the chunk that caused it,
+    /// and ends the iteration.
+    ///
+    /// Fails if there is no path between the formats.
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    ///
+    /// let chunks = vec![Ok(b"4869".to_vec()), Ok(b"2021".to_vec())];
+    /// let output = ConversionGraph::default()
+    ///     .iter_converter(&Hex, &Bytes, chunks.into_iter())
+    ///     .unwrap()
+    ///     .collect::<Result<Vec<Vec<u8>>, _>>()
+    ///     .unwrap();
+    /// assert_eq!(output, [b"Hi".to_vec(), b" !".to_vec()]);
+    /// ```
+    pub fn iter_converter<I>(
+        &self,
+        from: &Format,
+        to: &Format,
+        input: I,
+    ) -> BytaryResult<ChunkConverter<I>>
+    where
+        I: Iterator<Item = io::Result<Vec<u8>>>,
+    {
+        let mode = self.chunk_mode(from, to)?;
+        Ok(ChunkConverter {
+            input,
+            mode,
+            done: false,
+        })
+    }
+
+    /// Like [`iter_converter`](ConversionGraph::iter_converter), but pushed to: converts what is written
+    /// to the returned writer, writing the output to `target` as it becomes ready.
+    ///
+    /// Call [`ChunkWriter::finish`] after the last write, to write what is left.
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    /// use std::io::Write;
+    ///
+    /// let mut writer = ConversionGraph::default().chunk_writer(&Bytes, &Bin, Vec::new()).unwrap();
+    /// writer.write_all(b"H").unwrap();
+    /// writer.write_all(b"i").unwrap();
+    /// assert_eq!(writer.finish().unwrap(), b"0100100001101001");
+    /// ```
+    pub fn chunk_writer<W: Write>(
+        &self,
+        from: &Format,
+        to: &Format,
+        target: W,
+    ) -> BytaryResult<ChunkWriter<W>> {
+        Ok(ChunkWriter {
+            mode: self.chunk_mode(from, to)?,
+            target,
+        })
+    }
+
+    /// Like [`chunk_writer`](ConversionGraph::chunk_writer), but pulled from: converts what is read
+    /// from `input`, a chunk at a time.
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    /// use std::io::Read;
+    ///
+    /// let mut reader = ConversionGraph::default().chunk_reader(&Hex, &Bytes, &b"48 69"[..]).unwrap();
+    /// let mut output = Vec::new();
+    /// reader.read_to_end(&mut output).unwrap();
+    /// assert_eq!(output, b"Hi");
+    /// ```
+    pub fn chunk_reader<R: Read>(
+        &self,
+        from: &Format,
+        to: &Format,
+        input: R,
+    ) -> BytaryResult<ChunkReader<R>> {
+        Ok(ChunkReader {
+            mode: self.chunk_mode(from, to)?,
+            input,
+            output: Vec::new(),
+            position: 0,
+            done: false,
+        })
+    }
+
+    /// Steps for each conversion of the path if they all have one, or else the whole converter
+    fn chunk_mode(&self, from: &Format, to: &Format) -> BytaryResult<Mode> {
+        let converter = self.try_get_converter(from, to)?;
+        let steps = match from == to {
+            true => Some(Vec::new()),
+            false => self.find_shortest_path(from, to).and_then(|path| {
+                path.windows(2)
+                    .map(|w| Edge::get(self, &w[0], &w[1]).map(|edge| edge.start()))
+                    .collect::<Option<Vec<Step>>>()
+            }),
+        };
+        Ok(match steps {
+            Some(steps) => Mode::Chunked(steps),
+            None => Mode::Buffered {
+                converter,
+                input: Vec::new(),
+            },
+        })
+    }
+}
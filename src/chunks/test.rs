@@ -0,0 +1,191 @@
+use crate::convert::ConversionGraph;
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use crate::format::Format::*;
+use crate::testsupport::ChunkedReader;
+use std::cell::Cell;
+use std::io;
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+/// Yields `chunks` one by one, counting how many were taken
+fn counted(chunks: &[&[u8]], taken: Rc<Cell<usize>>) -> impl Iterator<Item = io::Result<Vec<u8>>> {
+    let chunks = chunks.iter().map(|c| c.to_vec()).collect::<Vec<Vec<u8>>>();
+    chunks.into_iter().map(move |chunk| {
+        taken.set(taken.get() + 1);
+        Ok(chunk)
+    })
+}
+
+fn convert(from: &Format, to: &Format, chunks: &[&[u8]]) -> BytaryResult<Vec<u8>> {
+    let taken = Rc::new(Cell::new(0));
+    let output = ConversionGraph::default()
+        .iter_converter(from, to, counted(chunks, taken))?
+        .collect::<BytaryResult<Vec<Vec<u8>>>>()?;
+    Ok(output.concat())
+}
+
+#[test]
+fn test_incremental() -> BytaryResult<()> {
+    let taken = Rc::new(Cell::new(0));
+    let chunks: &[&[u8]] = &[b"\x1b\x34", b"\x8f", b"\xff\x00"];
+    let mut iter =
+        ConversionGraph::default().iter_converter(&Bytes, &Hex, counted(chunks, taken.clone()))?;
+
+    assert_eq!(iter.next().unwrap()?, b"1b34");
+    assert_eq!(taken.get(), 1);
+    assert_eq!(iter.next().unwrap()?, b"8f");
+    assert_eq!(taken.get(), 2);
+    assert_eq!(iter.next().unwrap()?, b"ff00");
+    assert!(iter.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn test_split_groups() -> BytaryResult<()> {
+    // Digits of one byte are split between chunks
+    assert_eq!(
+        convert(&Hex, &Bytes, &[b"4", b"86", b"9 0x", b"21"])?,
+        b"Hi!"
+    );
+    assert_eq!(
+        convert(&Bin, &Bytes, &[b"0100", b"1000 011", b"01001"])?,
+        b"Hi"
+    );
+    assert_eq!(convert(&Oct, &Bytes, &[b"11", b"0 15", b"1"])?, b"Hi");
+    assert_eq!(convert(&Bytes, &Bin, &[b"H", b"i"])?, b"0100100001101001");
+    assert_eq!(convert(&Hex, &Oct, &[b"4", b"869"])?, b"110151");
+    assert_eq!(convert(&Hex, &Hex, &[b"4", b"869"])?, b"4869");
+    // Separators are dropped like whitespace, and other characters are rejected as unchunked
+    assert_eq!(convert(&Bin, &Bytes, &[b"01001000-0", b"1101001"])?, b"Hi");
+    assert!(convert(&Bin, &Bytes, &[b"01001000 x0", b"1101001"]).is_err());
+    assert!(convert(&Oct, &Bytes, &[b"110 #15", b"1"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_error_at_chunk() -> BytaryResult<()> {
+    let taken = Rc::new(Cell::new(0));
+    let chunks: &[&[u8]] = &[b"4869", b"2z", b"21"];
+    let mut iter =
+        ConversionGraph::default().iter_converter(&Hex, &Bytes, counted(chunks, taken.clone()))?;
+
+    assert_eq!(iter.next().unwrap()?, b"Hi");
+    assert_eq!(
+        iter.next().unwrap().unwrap_err().to_string(),
+        "Invalid input data: Invalid hex string: unexpected character 'z' at offset 5"
+    );
+    assert_eq!(taken.get(), 2);
+    assert!(iter.next().is_none());
+
+    let failing = vec![Ok(b"48".to_vec()), Err(io::Error::other("unplugged"))];
+    let mut iter = ConversionGraph::default().iter_converter(&Hex, &Bytes, failing.into_iter())?;
+    assert_eq!(iter.next().unwrap()?, b"H");
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn test_buffered() -> BytaryResult<()> {
+    let taken = Rc::new(Cell::new(0));
+    let chunks: &[&[u8]] = &[b"H", b"i"];
+    let mut iter = ConversionGraph::default().iter_converter(
+        &Bytes,
+        &Base64,
+        counted(chunks, taken.clone()),
+    )?;
+
+    assert_eq!(iter.next().unwrap()?, b"SGk=");
+    assert_eq!(taken.get(), 2);
+    assert!(iter.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn test_chunk_writer() -> BytaryResult<()> {
+    let graph = ConversionGraph::default();
+    let mut writer = graph.chunk_writer(&Hex, &Bytes, Vec::new())?;
+    writer.write_all(b"486")?;
+    // Output is written as soon as whole bytes are decoded
+    writer.write_all(b"92")?;
+    writer.write_all(b"1")?;
+    assert_eq!(writer.finish()?, b"Hi!");
+
+    let mut writer = graph.chunk_writer(&Bytes, &Base64, Vec::new())?;
+    writer.write_all(b"H")?;
+    writer.write_all(b"i")?;
+    assert_eq!(writer.finish()?, b"SGk=");
+
+    // Conversion errors are carried through the io error
+    let mut writer = graph.chunk_writer(&Hex, &Bytes, Vec::new())?;
+    let e = writer.write_all(b"4z").unwrap_err();
+    assert!(matches!(
+        BytaryError::from(e),
+        BytaryError::InvalidInputAt { offset: 1, .. }
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_chunk_reader() -> BytaryResult<()> {
+    let graph = ConversionGraph::default();
+    for size in [1, 3, 7] {
+        let mut reader = graph.chunk_reader(&Hex, &Oct, ChunkedReader::new(b"1b348fff00", size))?;
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output)?;
+        assert_eq!(output, b"033064217377000", "chunks of {}", size);
+    }
+
+    let mut reader = graph.chunk_reader(&Hex, &Bytes, &b"48z"[..])?;
+    let e = reader.read_to_end(&mut Vec::new()).unwrap_err();
+    assert!(matches!(
+        BytaryError::from(e),
+        BytaryError::InvalidInputAt { offset: 2, .. }
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_replaced_builtin() -> BytaryResult<()> {
+    use crate::builtins::Padding;
+    use crate::convert::EdgeInfo;
+
+    // A conversion that replaced a built-in one is run as it is, on the whole input
+    let mut graph = ConversionGraph::default();
+    let info = EdgeInfo {
+        name: "upper_hex",
+        lossy: false,
+        cost: 1,
+    };
+    graph
+        .add_edge(Bytes, Hex, info, |input, output| {
+            let mut hex = Vec::new();
+            crate::builtins::bytes_to_hex(input, &mut hex)?;
+            Ok(output.write_all(hex.to_ascii_uppercase().as_slice())?)
+        })
+        .unwrap();
+    let chunks = vec![Ok(b"\x1b".to_vec()), Ok(b"\xff".to_vec())];
+    let output = graph
+        .iter_converter(&Bytes, &Hex, chunks.into_iter())?
+        .collect::<BytaryResult<Vec<Vec<u8>>>>()?;
+    assert_eq!(output, [b"1BFF".to_vec()]);
+
+    let mut writer = graph.chunk_writer(&Bytes, &Bin, Vec::new())?;
+    writer.write_all(b"\x1b")?;
+    assert_eq!(writer.finish()?, b"00011011");
+    let mut output = Vec::new();
+    graph
+        .chunk_reader(&Bytes, &Hex, ChunkedReader::new(b"\x1b\xff", 1))?
+        .read_to_end(&mut output)?;
+    assert_eq!(output, b"1BFF");
+
+    // Padding applies to the whole input
+    let graph = ConversionGraph::with_padding(Padding::Left);
+    let chunks = vec![Ok(b"1001".to_vec()), Ok(b"000".to_vec())];
+    let output = graph
+        .iter_converter(&Bin, &Bytes, chunks.into_iter())?
+        .collect::<BytaryResult<Vec<Vec<u8>>>>()?;
+    assert_eq!(output.concat(), b"H");
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use crate::utils::HexStyle;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod test;
+
+/// Named presets of a config file, like
+///
+/// ```toml
+/// [preset.mac]
+/// to = "hex"
+/// style = "mac"
+/// ```
+///
+/// ```rust
+/// use bytary::config::Config;
+/// use bytary::format::Format;
+///
+/// let config = Config::parse("[preset.mac]\nto = \"hex\"\nstyle = \"mac\"\n", "config.toml").unwrap();
+/// assert_eq!(config.preset("mac").unwrap().to, Some(Format::Hex));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Config {
+    /// The file the config was read from, named in errors
+    pub path: PathBuf,
+    pub presets: BTreeMap<String, Preset>,
+}
+
+/// Values of command line options, any of which may be unset.
+///
+/// Fields are named after the long options they stand for.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Preset {
+    pub to: Option<Format>,
+    pub from: Option<Format>,
+    pub space: Option<usize>,
+    pub wrap: Option<usize>,
+    pub wrap_bytes: Option<NonZeroUsize>,
+    pub style: Option<HexStyle>,
+    pub warnings_as_errors: Option<bool>,
+}
+
+/// The layout of a config file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    preset: BTreeMap<String, Preset>,
+}
+
+impl Config {
+    /// Parses the TOML `text` of the file at `path`, which is only used in errors
+    pub fn parse(text: &str, path: impl AsRef<Path>) -> BytaryResult<Config> {
+        let path = path.as_ref();
+        let file = toml::from_str::<ConfigFile>(text).map_err(|e| {
+            BytaryError::InvalidArgument(format!(
+                "invalid config file '{}': {}",
+                path.display(),
+                e.to_string().trim_end()
+            ))
+        })?;
+        Ok(Config {
+            path: path.to_path_buf(),
+            presets: file.preset,
+        })
+    }
+
+    /// Reads and parses the file at `path`
+    pub fn load(path: impl AsRef<Path>) -> BytaryResult<Config> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| {
+            BytaryError::InvalidArgument(format!(
+                "cannot read config file '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Config::parse(&text, path)
+    }
+
+    /// `$XDG_CONFIG_HOME/bytary/config.toml`, or `~/.config/bytary/config.toml` without `XDG_CONFIG_HOME`
+    pub fn default_path() -> Option<PathBuf> {
+        let home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(home.join("bytary").join("config.toml"))
+    }
+
+    /// The preset called `name`
+    pub fn preset(&self, name: &str) -> BytaryResult<&Preset> {
+        self.presets.get(name).ok_or_else(|| {
+            let names = self.presets.keys().cloned().collect::<Vec<String>>();
+            BytaryError::InvalidArgument(format!(
+                "unknown preset '{}' in '{}', available: {}",
+                name,
+                self.path.display(),
+                match names.is_empty() {
+                    true => "none".to_string(),
+                    false => names.join(", "),
+                }
+            ))
+        })
+    }
+}
+
+impl Preset {
+    /// The effective values of the options, those given in `explicit` overriding those of the preset
+    ///
+    /// ```rust
+    /// use bytary::config::Preset;
+    /// use bytary::format::Format;
+    ///
+    /// let preset = Preset { to: Some(Format::Hex), space: Some(2), ..Preset::default() };
+    /// let explicit = Preset { space: Some(4), ..Preset::default() };
+    /// let effective = preset.resolve(&explicit);
+    /// assert_eq!((effective.to, effective.space), (Some(Format::Hex), Some(4)));
+    /// ```
+    pub fn resolve(&self, explicit: &Preset) -> Preset {
+        Preset {
+            to: explicit.to.clone().or(self.to.clone()),
+            from: explicit.from.clone().or(self.from.clone()),
+            space: explicit.space.or(self.space),
+            wrap: explicit.wrap.or(self.wrap),
+            wrap_bytes: explicit.wrap_bytes.or(self.wrap_bytes),
+            style: explicit.style.or(self.style),
+            warnings_as_errors: explicit.warnings_as_errors.or(self.warnings_as_errors),
+        }
+    }
+}
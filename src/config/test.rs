@@ -0,0 +1,125 @@
+use crate::config::{Config, Preset};
+use crate::error::BytaryResult;
+use crate::format::Format;
+use crate::utils::HexStyle;
+use std::num::NonZeroUsize;
+
+const CONFIG: &str = r#"
+[preset.mac]
+to = "hex"
+style = "mac"
+
+[preset.dump]
+from = "bytes"
+to = "hex"
+space = 2
+wrap-bytes = 16
+warnings-as-errors = true
+"#;
+
+#[test]
+fn test_presets() -> BytaryResult<()> {
+    let config = Config::parse(CONFIG, "team.toml")?;
+    assert_eq!(config.presets.len(), 2);
+    assert_eq!(
+        config.preset("mac")?,
+        &Preset {
+            to: Some(Format::Hex),
+            style: Some(HexStyle::Mac),
+            ..Preset::default()
+        }
+    );
+    assert_eq!(
+        config.preset("dump")?,
+        &Preset {
+            from: Some(Format::Bytes),
+            to: Some(Format::Hex),
+            space: Some(2),
+            wrap_bytes: NonZeroUsize::new(16),
+            warnings_as_errors: Some(true),
+            ..Preset::default()
+        }
+    );
+    assert_eq!(Config::parse("", "empty.toml")?.presets.len(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_resolve() -> BytaryResult<()> {
+    let config = Config::parse(CONFIG, "team.toml")?;
+    let preset = config.preset("dump")?;
+    assert_eq!(&preset.resolve(&Preset::default()), preset);
+
+    let explicit = Preset {
+        to: Some(Format::Bin),
+        space: Some(8),
+        ..Preset::default()
+    };
+    assert_eq!(
+        preset.resolve(&explicit),
+        Preset {
+            from: Some(Format::Bytes),
+            to: Some(Format::Bin),
+            space: Some(8),
+            wrap_bytes: NonZeroUsize::new(16),
+            warnings_as_errors: Some(true),
+            ..Preset::default()
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_unknown_preset() -> BytaryResult<()> {
+    let config = Config::parse(CONFIG, "team.toml")?;
+    assert_eq!(
+        config.preset("mca").unwrap_err().to_string(),
+        "Invalid argument: unknown preset 'mca' in 'team.toml', available: dump, mac"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_invalid_config() {
+    let error = |text: &str| {
+        Config::parse(text, "/etc/bytary.toml")
+            .unwrap_err()
+            .to_string()
+    };
+
+    let malformed = error("[preset.mac\nto = \"hex\"\n");
+    assert!(
+        malformed.starts_with("Invalid argument: invalid config file '/etc/bytary.toml': "),
+        "{}",
+        malformed
+    );
+
+    let unknown_key = error("[preset.mac]\nspcae = 2\n");
+    assert!(unknown_key.contains("/etc/bytary.toml"), "{}", unknown_key);
+    assert!(
+        unknown_key.contains("unknown field `spcae`"),
+        "{}",
+        unknown_key
+    );
+
+    let unknown_table = error("[presets.mac]\n");
+    assert!(
+        unknown_table.contains("unknown field `presets`"),
+        "{}",
+        unknown_table
+    );
+
+    let unknown_format = error("[preset.mac]\nto = \"hx\"\n");
+    assert!(
+        unknown_format.contains("Invalid Format: 'hx'"),
+        "{}",
+        unknown_format
+    );
+
+    let unknown_style = error("[preset.mac]\nstyle = \"ipv6\"\n");
+    assert!(
+        unknown_style.contains("unknown hex style 'ipv6'"),
+        "{}",
+        unknown_style
+    );
+}
@@ -0,0 +1,247 @@
+use crate::convert::Converter;
+use crate::testsupport::{ByteGenerator, ChunkedReader, FaultyWriter, corpus};
+use crate::utils::plural;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+#[cfg(test)]
+mod test;
+
+/// What [`check_converter`] runs the converters with
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConformanceSpec {
+    /// Seed of the random inputs, from `BYTARY_TEST_SEED` by default
+    pub seed: u64,
+    /// Sizes of the reads that every input is cut into, on top of a single read of the whole input
+    pub chunk_sizes: Vec<usize>,
+}
+
+impl Default for ConformanceSpec {
+    fn default() -> Self {
+        Self {
+            seed: ByteGenerator::from_env().seed(),
+            chunk_sizes: vec![1, 7, 1024],
+        }
+    }
+}
+
+/// A case of [`check_converter`] that failed, like `decode all_byte_values in chunks of 7`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CaseFailure {
+    pub case: String,
+    pub problem: String,
+}
+
+impl Display for CaseFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.case, self.problem)
+    }
+}
+
+/// The result of [`check_converter`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConformanceReport {
+    pub seed: u64,
+    /// Number of cases that were run, including the failed ones
+    pub cases: usize,
+    pub failures: Vec<CaseFailure>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Whether the case of the given name failed
+    pub fn failed(&self, case: &str) -> bool {
+        self.failures.iter().any(|failure| failure.case == case)
+    }
+
+    fn check(&mut self, case: impl Into<String>, result: Result<(), String>) {
+        self.cases += 1;
+        if let Err(problem) = result {
+            self.failures.push(CaseFailure {
+                case: case.into(),
+                problem,
+            });
+        }
+    }
+}
+
+impl Display for ConformanceReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} failed (seed {})",
+            self.failures.len(),
+            plural(self.cases, "case"),
+            self.seed
+        )?;
+        for failure in &self.failures {
+            write!(f, "\n  {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `converter` on `input`, turning a panic into an error
+fn run(
+    converter: &dyn Converter,
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+) -> Result<(), String> {
+    match catch_unwind(AssertUnwindSafe(|| converter.convert(input, output))) {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(payload) => Err(match payload.downcast_ref::<&str>() {
+            Some(message) => format!("panicked: {}", message),
+            None => match payload.downcast_ref::<String>() {
+                Some(message) => format!("panicked: {}", message),
+                None => "panicked".to_string(),
+            },
+        }),
+    }
+}
+
+fn output(converter: &dyn Converter, input: &mut dyn Read) -> Result<Vec<u8>, String> {
+    let mut output = Vec::new();
+    run(converter, input, &mut output)?;
+    Ok(output)
+}
+
+fn expect_same(expected: &[u8], actual: &[u8]) -> Result<(), String> {
+    if expected == actual {
+        return Ok(());
+    }
+    match expected.iter().zip(actual).position(|(a, b)| a != b) {
+        Some(offset) => Err(format!("output differs at offset {}", offset)),
+        None => Err(format!(
+            "output has {} instead of {}",
+            plural(actual.len(), "byte"),
+            expected.len()
+        )),
+    }
+}
+
+/// Converts `data` to a writer that fails halfway through the writes of a successful run, which must fail
+fn expect_write_failure(converter: &dyn Converter, data: &[u8]) -> Result<(), String> {
+    let mut counter = FaultyWriter::new(Vec::new(), usize::MAX);
+    run(converter, &mut &data[..], &mut counter)?;
+    if counter.calls() == 0 {
+        return Ok(());
+    }
+    let mut failing = FaultyWriter::new(Vec::new(), counter.calls() / 2);
+    match run(converter, &mut &data[..], &mut failing) {
+        Ok(()) => Err(format!(
+            "succeeded although write {} of {} failed",
+            counter.calls() / 2,
+            counter.calls()
+        )),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Checks what every converter of bytes should do, whatever its format.
+///
+/// `encode` converts bytes to the format, and `decode`, if any, converts them back.
+/// Each input of the [`corpus`] must be converted without error, to the same output when the input
+/// is read [in chunks](ChunkedReader) of each of the sizes of `spec`. A failed write must make the
+/// conversion fail, and panics are reported as failures. With a decoder, the same is checked for it,
+/// and decoding must give back the input.
+///
+/// Cases are named after what they run and the input, like `encode empty`,
+/// `encode random_1mib in chunks of 7`, `decode all_byte_values to a failing writer` or `round trip single_byte`.
+///
+/// ```rust
+/// use bytary::builtins::{bytes_to_hex, hex_to_bytes};
+/// use bytary::conformance::{ConformanceSpec, check_converter};
+///
+/// let report = check_converter(&bytes_to_hex, Some(&hex_to_bytes), ConformanceSpec::default());
+/// assert!(report.passed(), "{}", report);
+/// ```
+pub fn check_converter(
+    encode: &dyn Converter,
+    decode: Option<&dyn Converter>,
+    spec: ConformanceSpec,
+) -> ConformanceReport {
+    let mut report = ConformanceReport {
+        seed: spec.seed,
+        ..Default::default()
+    };
+    for (name, data) in corpus::all(spec.seed) {
+        let encoded = output(encode, &mut &data[..]);
+        report.check(format!("encode {}", name), encoded.clone().map(|_| ()));
+        let Ok(encoded) = encoded else {
+            continue;
+        };
+        for size in &spec.chunk_sizes {
+            report.check(
+                format!("encode {} in chunks of {}", name, size),
+                output(encode, &mut ChunkedReader::new(&data, *size))
+                    .and_then(|chunked| expect_same(&encoded, &chunked)),
+            );
+        }
+        let Some(decode) = decode else {
+            continue;
+        };
+        report.check(
+            format!("round trip {}", name),
+            output(decode, &mut &encoded[..]).and_then(|decoded| expect_same(&data, &decoded)),
+        );
+        for size in &spec.chunk_sizes {
+            report.check(
+                format!("decode {} in chunks of {}", name, size),
+                output(decode, &mut ChunkedReader::new(&encoded, *size))
+                    .and_then(|decoded| expect_same(&data, &decoded)),
+            );
+        }
+    }
+
+    let data = corpus::all_byte_values();
+    report.check(
+        "encode all_byte_values to a failing writer",
+        expect_write_failure(encode, &data),
+    );
+    if let Some(decode) = decode
+        && let Ok(encoded) = output(encode, &mut &data[..])
+    {
+        report.check(
+            "decode all_byte_values to a failing writer",
+            expect_write_failure(decode, &encoded),
+        );
+    }
+    report
+}
+
+/// Panics with the [report](ConformanceReport) unless the converters pass [`check_converter`].
+///
+/// Takes the encoder, then optionally the decoder and a [`ConformanceSpec`].
+///
+/// ```rust
+/// use bytary::assert_conformant;
+/// use bytary::builtins::{bytes_to_base64, bytes_to_stats, base64_to_bytes};
+///
+/// assert_conformant!(bytes_to_stats);
+/// assert_conformant!(bytes_to_base64, base64_to_bytes);
+/// ```
+#[macro_export]
+macro_rules! assert_conformant {
+    (@check $encode:expr, $decode:expr, $spec:expr) => {{
+        let report = $crate::conformance::check_converter(&$encode, $decode, $spec);
+        assert!(
+            report.passed(),
+            "{} is not conformant: {}",
+            stringify!($encode),
+            report
+        );
+    }};
+    ($encode:expr $(,)?) => {
+        $crate::assert_conformant!(@check $encode, None, $crate::conformance::ConformanceSpec::default())
+    };
+    ($encode:expr, $decode:expr $(,)?) => {
+        $crate::assert_conformant!(@check $encode, Some(&$decode), $crate::conformance::ConformanceSpec::default())
+    };
+    ($encode:expr, $decode:expr, $spec:expr $(,)?) => {
+        $crate::assert_conformant!(@check $encode, Some(&$decode), $spec)
+    };
+}
@@ -0,0 +1,123 @@
+use crate::builtins::{bytes_to_hex, hex_to_bytes};
+use crate::conformance::{ConformanceSpec, check_converter};
+use crate::error::BytaryResult;
+use std::io::{Read, Write};
+
+fn spec() -> ConformanceSpec {
+    ConformanceSpec {
+        seed: 7,
+        chunk_sizes: vec![1, 7, 1024],
+    }
+}
+
+/// Hex encoder that stops at the first read shorter than its buffer
+fn stops_at_short_read(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    let mut buffer = [0u8; 64];
+    loop {
+        let length = input.read(&mut buffer)?;
+        bytes_to_hex(&mut &buffer[..length], output)?;
+        if length < buffer.len() {
+            return Ok(());
+        }
+    }
+}
+
+/// Hex encoder that ignores write errors
+fn ignores_write_errors(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+    for byte in data {
+        let _ = output.write_all(format!("{:02x}", byte).as_bytes());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_conformant() {
+    let report = check_converter(&bytes_to_hex, Some(&hex_to_bytes), spec());
+    assert!(report.passed(), "{}", report);
+    assert_eq!(report.seed, 7);
+    // 5 inputs, each encoded, round-tripped and converted in 3 chunk sizes both ways, then 2 failing writers
+    assert_eq!(report.cases, 5 * 8 + 2);
+}
+
+#[test]
+fn test_chunk_boundaries() {
+    let report = check_converter(&stops_at_short_read, Some(&hex_to_bytes), spec());
+    assert!(!report.passed());
+    assert!(report.failed("encode all_byte_values in chunks of 7"));
+    assert!(report.failed("encode random_1mib in chunks of 1"));
+    // Whole reads fill the buffer
+    assert!(!report.failed("encode all_byte_values"));
+    assert!(!report.failed("round trip all_byte_values"));
+    assert!(!report.failed("decode all_byte_values in chunks of 7"));
+
+    let failure = &report.failures[0];
+    assert_eq!(failure.case, "encode all_byte_values in chunks of 1");
+    assert_eq!(
+        failure.to_string(),
+        "encode all_byte_values in chunks of 1: output has 2 bytes instead of 512"
+    );
+    assert!(report.to_string().starts_with(&format!(
+        "{} of {} cases failed (seed 7)\n  encode all_byte_values",
+        report.failures.len(),
+        report.cases
+    )));
+}
+
+#[test]
+fn test_write_errors() {
+    let report = check_converter(&ignores_write_errors, None, spec());
+    assert_eq!(report.failures.len(), 1, "{}", report);
+    assert_eq!(
+        report.failures[0].to_string(),
+        "encode all_byte_values to a failing writer: succeeded although write 128 of 256 failed"
+    );
+}
+
+#[test]
+fn test_round_trip() {
+    let drops_last_byte = |input: &mut dyn Read, output: &mut dyn Write| {
+        let mut decoded = Vec::new();
+        hex_to_bytes(input, &mut decoded)?;
+        decoded.pop();
+        output.write_all(&decoded)?;
+        Ok(())
+    };
+    let report = check_converter(&bytes_to_hex, Some(&drops_last_byte), spec());
+    assert!(report.failed("round trip single_byte"));
+    assert!(report.failed("decode mostly_ff in chunks of 1024"));
+    // Empty input has no last byte to drop
+    assert!(!report.failed("round trip empty"));
+    assert!(!report.failed("encode single_byte"));
+}
+
+#[test]
+fn test_panics() {
+    let panics = |input: &mut dyn Read, _: &mut dyn Write| -> BytaryResult<()> {
+        let mut byte = [0u8; 1];
+        match input.read(&mut byte)? {
+            0 => Ok(()),
+            _ => panic!("unexpected byte {}", byte[0]),
+        }
+    };
+    let report = check_converter(&panics, None, spec());
+    assert!(!report.failed("encode empty"));
+    assert_eq!(
+        report.failures[0].to_string(),
+        "encode single_byte: panicked: unexpected byte 90"
+    );
+}
+
+#[test]
+fn test_assert_conformant() {
+    crate::assert_conformant!(bytes_to_hex);
+    crate::assert_conformant!(bytes_to_hex, hex_to_bytes);
+    crate::assert_conformant!(bytes_to_hex, hex_to_bytes, spec());
+}
+
+#[test]
+#[should_panic(expected = "stops_at_short_read is not conformant: ")]
+fn test_assert_conformant_fails() {
+    crate::assert_conformant!(stops_at_short_read, hex_to_bytes, spec());
+}
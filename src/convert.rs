@@ -1,15 +1,675 @@
-use crate::error::BytaryResult;
+use crate::error::{BytaryError, BytaryResult};
 use crate::format::Format;
+use crate::observe::{ConversionObserver, StageTiming};
+use crate::utils::{CountingReader, CountingWriter, LimitedReader, RetryingReader, flush_if_short};
+use crate::warning;
+use crate::warning::Warning;
 use pathfinding::prelude::dijkstra;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::fmt::{Display, Formatter};
 use std::io;
 use std::io::{Read, Write};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-/// A function that converts from one format to another.
-type ConvertFn = dyn Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()>;
+#[cfg(test)]
+mod test;
 
-/// A graph of conversion functions
+/// A conversion from one format to another, with metadata about how it behaves.
+///
+/// Converting empty input must produce empty output.
+///
+/// Streaming converters flush the output whenever a read returns less than they asked for,
+/// so that output of a paused input is not held back in buffers.
+///
+/// Converters write to the output directly and do not buffer it themselves, so that every
+/// write error reaches the caller. Callers that write to a file or socket should pass a
+/// [`BufWriter`](std::io::BufWriter) and flush it once the converter returns.
+///
+/// Plain functions and closures are converters too, with the default metadata:
+///
+/// ```rust
+/// use bytary::convert::Converter;
+/// use std::io::{Read, Write};
+///
+/// let upper = |input: &mut dyn Read, output: &mut dyn Write| {
+///     let mut text = String::new();
+///     input.read_to_string(&mut text)?;
+///     Ok(output.write_all(text.to_uppercase().as_bytes())?)
+/// };
+/// let mut output = Vec::new();
+/// upper.convert(&mut &b"hi"[..], &mut output).unwrap();
+/// assert_eq!(output, b"HI");
+/// assert_eq!(upper.name(), "");
+/// assert!(!upper.is_streaming());
+/// ```
+pub trait Converter {
+    /// Reads `input` to the end and writes the converted data to `output`
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()>;
+
+    /// Name of the converter, like `bytes_to_hex`. Empty if unnamed.
+    fn name(&self) -> &str {
+        ""
+    }
+
+    /// Bytes of output per byte of input, `None` if it depends on the data
+    fn expansion_ratio(&self) -> Option<f64> {
+        None
+    }
+
+    /// Whether output is written while the input is read, instead of once all of it has been read.
+    ///
+    /// Converters are assumed to buffer their whole input unless they say otherwise.
+    fn is_streaming(&self) -> bool {
+        false
+    }
+
+    /// How much of the data the converter holds in memory.
+    ///
+    /// By default, [`Streaming`](StreamingClass::Streaming) for streaming converters
+    /// and [`BuffersInput`](StreamingClass::BuffersInput) for the others.
+    fn streaming_class(&self) -> StreamingClass {
+        match self.is_streaming() {
+            true => StreamingClass::Streaming,
+            false => StreamingClass::BuffersInput,
+        }
+    }
+}
+
+/// How much of the data a conversion holds in memory, from best to worst
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum StreamingClass {
+    /// Memory stays bounded whatever the size of the input
+    Streaming,
+    /// The input is read as it comes, but the whole output is held before it is written
+    BuffersOutput,
+    /// The whole input is held before it is converted
+    BuffersInput,
+}
+
+impl Display for StreamingClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StreamingClass::Streaming => "streaming",
+            StreamingClass::BuffersOutput => "buffers output",
+            StreamingClass::BuffersInput => "buffers input",
+        })
+    }
+}
+
+/// Ends the conversion of a converter without error once its output reaches the limit of a
+/// [`HeadWriter`](crate::utils::HeadWriter), see [`ConversionGraph::try_get_converter`]
+struct StopAtOutputLimit(Rc<dyn Converter>);
+
+impl Converter for StopAtOutputLimit {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        match self.0.convert(input, output) {
+            Err(BytaryError::OutputLimitReached) => Ok(()),
+            result => result,
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn expansion_ratio(&self) -> Option<f64> {
+        self.0.expansion_ratio()
+    }
+
+    fn is_streaming(&self) -> bool {
+        self.0.is_streaming()
+    }
+
+    fn streaming_class(&self) -> StreamingClass {
+        self.0.streaming_class()
+    }
+}
+
+impl<F: Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()>> Converter for F {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        self(input, output)
+    }
+}
+
+/// Converters run one after another, each reading the whole output of the previous one.
+///
+/// Its name joins the names of the parts with `, `, and its expansion ratio is the product of theirs.
+/// It is only streaming if it has a single streaming part, since the output of every other part is buffered.
+pub struct ComposedConverter {
+    parts: Vec<Rc<dyn Converter>>,
+    name: String,
+}
+
+impl ComposedConverter {
+    pub fn new(parts: Vec<Rc<dyn Converter>>) -> Self {
+        let name = parts
+            .iter()
+            .map(|part| part.name())
+            .collect::<Vec<&str>>()
+            .join(", ");
+        Self { parts, name }
+    }
+
+    /// The converters that this one runs, in order
+    pub fn parts(&self) -> &[Rc<dyn Converter>] {
+        &self.parts
+    }
+
+    /// Like [`convert`](Converter::convert), but measures each part.
+    ///
+    /// Once a part succeeds, `on_stage` is called with its index, the bytes it read and wrote, and the time it took.
+    pub fn convert_timed(
+        &self,
+        input: &mut dyn Read,
+        output: &mut dyn Write,
+        on_stage: &mut dyn FnMut(usize, u64, u64, Duration),
+    ) -> BytaryResult<()> {
+        let mut prev_output: Box<dyn Read> = Box::new(input);
+
+        for (index, converter) in self.parts.iter().enumerate() {
+            let mut input = CountingReader::new(&mut prev_output);
+            let start = Instant::now();
+            if index + 1 == self.parts.len() {
+                let mut output = CountingWriter::new(&mut *output);
+                converter
+                    .convert(&mut input, &mut output)
+                    .map_err(|e| part_error(index, e))?;
+                on_stage(index, input.count(), output.count(), start.elapsed());
+                break;
+            }
+            let mut buffer = Vec::new();
+            converter
+                .convert(&mut input, &mut buffer)
+                .map_err(|e| part_error(index, e))?;
+            on_stage(index, input.count(), buffer.len() as u64, start.elapsed());
+            prev_output = Box::new(io::Cursor::new(buffer));
+        }
+
+        Ok(())
+    }
+}
+
+impl Converter for ComposedConverter {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut prev_output: Box<dyn Read> = Box::new(input);
+
+        for (index, converter) in self.parts.iter().enumerate() {
+            if index + 1 == self.parts.len() {
+                converter
+                    .convert(&mut prev_output, output)
+                    .map_err(|e| part_error(index, e))?;
+                break;
+            }
+            let mut buffer = Vec::new();
+            converter
+                .convert(&mut prev_output, &mut buffer)
+                .map_err(|e| part_error(index, e))?;
+            prev_output = Box::new(io::Cursor::new(buffer));
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn expansion_ratio(&self) -> Option<f64> {
+        self.parts
+            .iter()
+            .try_fold(1.0, |ratio, part| Some(ratio * part.expansion_ratio()?))
+    }
+
+    fn is_streaming(&self) -> bool {
+        match self.parts.as_slice() {
+            [part] => part.is_streaming(),
+            _ => false,
+        }
+    }
+
+    /// The worst class of the parts, and at least [`BuffersOutput`](StreamingClass::BuffersOutput)
+    /// if there are several, since each part but the last one buffers its output
+    fn streaming_class(&self) -> StreamingClass {
+        let worst = self.parts.iter().map(|part| part.streaming_class()).max();
+        match self.parts.len() {
+            0 => StreamingClass::Streaming,
+            1 => worst.unwrap_or(StreamingClass::Streaming),
+            _ => worst.map_or(StreamingClass::BuffersOutput, |class| {
+                class.max(StreamingClass::BuffersOutput)
+            }),
+        }
+    }
+}
+
+/// The error of the part at `index` of a [`ComposedConverter`], whose offset is only that of the
+/// input of the conversion for the first part
+fn part_error(index: usize, error: BytaryError) -> BytaryError {
+    match index {
+        0 => error,
+        _ => error.without_offset(),
+    }
+}
+
+/// A converter that copies the input to the output
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CopyConverter;
+
+impl Converter for CopyConverter {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut input = RetryingReader::new(input);
+        let mut buffer = [0u8; 8192];
+        loop {
+            let length = input.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            output.write_all(&buffer[..length])?;
+            flush_if_short(output, length, buffer.len())?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "copy"
+    }
+
+    fn expansion_ratio(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// A converter that remembers the outputs of the inner one for small inputs, reusing them
+/// when the same input comes again.
+///
+/// Inputs of at most `max_input_size` bytes are read into memory and looked up by content.
+/// The `capacity` most recently used outputs are kept. Larger inputs are streamed through
+/// the inner converter, after the bytes already read. Failed conversions are never cached.
+///
+/// It only suits converters whose output depends on nothing but their input.
+///
+/// ```rust
+/// use bytary::convert::{CachedConverter, Converter};
+/// use std::rc::Rc;
+///
+/// let cached = CachedConverter::new(Rc::new(bytary::builtins::bytes_to_hex), 16, 1024);
+/// for _ in 0..4 {
+///     let mut output = Vec::new();
+///     cached.convert(&mut &b"Hi"[..], &mut output).unwrap();
+///     assert_eq!(output, b"4869");
+/// }
+/// assert_eq!(cached.hit_rate(), 0.75);
+/// ```
+pub struct CachedConverter {
+    inner: Rc<dyn Converter>,
+    capacity: usize,
+    max_input_size: usize,
+    cache: RefCell<OutputCache>,
+}
+
+/// Outputs of a [`CachedConverter`] by input, with the order they were last used in
+#[derive(Default)]
+struct OutputCache {
+    /// Output and last use of each input
+    entries: HashMap<Rc<[u8]>, (Vec<u8>, u64)>,
+    /// Inputs by last use, from the least recent
+    uses: BTreeMap<u64, Rc<[u8]>>,
+    clock: u64,
+    hits: u64,
+    lookups: u64,
+}
+
+impl CachedConverter {
+    pub fn new(inner: Rc<dyn Converter>, capacity: usize, max_input_size: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            max_input_size,
+            cache: RefCell::new(OutputCache::default()),
+        }
+    }
+
+    /// The share of the cacheable inputs whose output was cached, 0 before any
+    ///
+    /// Inputs larger than `max_input_size` are not counted.
+    pub fn hit_rate(&self) -> f64 {
+        let cache = self.cache.borrow();
+        match cache.lookups {
+            0 => 0.0,
+            lookups => cache.hits as f64 / lookups as f64,
+        }
+    }
+
+    /// Number of outputs in the cache
+    pub fn len(&self) -> usize {
+        self.cache.borrow().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets every cached output and the hit rate
+    pub fn clear(&self) {
+        *self.cache.borrow_mut() = OutputCache::default();
+    }
+}
+
+impl OutputCache {
+    /// The output of `input`, marking it as the most recently used
+    fn get(&mut self, input: &[u8]) -> Option<&[u8]> {
+        self.lookups += 1;
+        self.clock += 1;
+        let (key, (_, used)) = self.entries.get_key_value(input)?;
+        let key = key.clone();
+        self.uses.remove(used);
+        self.uses.insert(self.clock, key);
+        self.hits += 1;
+        let (output, used) = self.entries.get_mut(input)?;
+        *used = self.clock;
+        Some(output)
+    }
+
+    /// Stores the output of `input`, dropping the least recently used ones beyond `capacity`
+    fn insert(&mut self, input: Vec<u8>, output: Vec<u8>, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= capacity {
+            match self.uses.pop_first() {
+                Some((_, oldest)) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+        let input: Rc<[u8]> = input.into();
+        self.uses.insert(self.clock, input.clone());
+        self.entries.insert(input, (output, self.clock));
+    }
+}
+
+impl Converter for CachedConverter {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut input = RetryingReader::new(input);
+        let mut buffer = Vec::new();
+        (&mut input)
+            .take((self.max_input_size as u64).saturating_add(1))
+            .read_to_end(&mut buffer)?;
+        if buffer.len() > self.max_input_size {
+            return self
+                .inner
+                .convert(&mut io::Cursor::new(buffer).chain(input), output);
+        }
+
+        if let Some(cached) = self.cache.borrow_mut().get(&buffer) {
+            output.write_all(cached)?;
+            return Ok(output.flush()?);
+        }
+        let mut converted = Vec::new();
+        let mut run = || self.inner.convert(&mut &buffer[..], &mut converted);
+        // Warnings are seen here to keep their outputs out of the cache, where they would be replayed
+        // without them, or in strict mode where the conversion fails
+        let (result, warnings) = match warning::collecting() {
+            true => warning::collect(run),
+            false => (run(), Vec::new()),
+        };
+        let warned = !warnings.is_empty();
+        for warning in warnings {
+            warning::report(warning);
+        }
+        output.write_all(&converted)?;
+        output.flush()?;
+        result?;
+        if warned {
+            return Ok(());
+        }
+        self.cache
+            .borrow_mut()
+            .insert(buffer, converted, self.capacity);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn expansion_ratio(&self) -> Option<f64> {
+        self.inner.expansion_ratio()
+    }
+
+    fn is_streaming(&self) -> bool {
+        self.inner.is_streaming()
+    }
+
+    /// The class of the inner converter, since only inputs of bounded size are held
+    fn streaming_class(&self) -> StreamingClass {
+        self.inner.streaming_class()
+    }
+}
+
+/// Metadata of a direct conversion in a [`ConversionGraph`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EdgeInfo {
+    /// Name of the converter, shown in verbose output. May be empty.
+    pub name: &'static str,
+    /// Whether the conversion loses information.
+    ///
+    /// Lossy conversions are only used as the last step of a path, never as an intermediate one.
+    pub lossy: bool,
+    pub cost: u32,
+}
+
+/// A direct conversion between two formats, as listed in [`Capabilities`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conversion {
+    pub from: Format,
+    pub to: Format,
+    pub cost: u32,
+}
+
+/// A summary of what a [`ConversionGraph`] can do
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capabilities {
+    /// Formats that appear in any conversion, sorted
+    pub formats: Vec<Format>,
+    /// All direct conversions, sorted by `from` then `to`
+    pub conversions: Vec<Conversion>,
+}
+
+/// The path that [`ConversionGraph::plan`] chose for a conversion
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConversionPlan {
+    /// Formats from the source to the destination, a single format if there is nothing to convert
+    pub path: Vec<Format>,
+    streaming_class: StreamingClass,
+}
+
+impl ConversionPlan {
+    /// How much of the data the conversion holds in memory, the worst class along the path.
+    ///
+    /// A path of several conversions buffers the output of each one but the last, see [`ComposedConverter`].
+    ///
+    /// ```rust
+    /// use bytary::convert::{ConversionGraph, StreamingClass};
+    /// use bytary::format::Format::*;
+    ///
+    /// let graph = ConversionGraph::default();
+    /// assert_eq!(graph.plan(&Hex, &Bytes).unwrap().streaming_class(), StreamingClass::Streaming);
+    /// assert_eq!(graph.plan(&Base64, &Bytes).unwrap().streaming_class(), StreamingClass::BuffersInput);
+    /// assert_eq!(graph.plan(&Hex, &Oct).unwrap().streaming_class(), StreamingClass::BuffersOutput);
+    /// ```
+    pub fn streaming_class(&self) -> StreamingClass {
+        self.streaming_class
+    }
+
+    /// The length of the output for `input_len` bytes of input, before any spacing or line wrapping.
+    ///
+    /// Multiplies through the ratios of each step, assuming the built-in converters.
+    /// `None` if the length of any step depends on the data, see [`Format::encoded_size_hint`].
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    ///
+    /// let graph = ConversionGraph::default();
+    /// assert_eq!(graph.plan(&Bin, &Hex).unwrap().output_size_hint(16), Some(4));
+    /// assert_eq!(graph.plan(&Bytes, &Base64).unwrap().output_size_hint(4), Some(8));
+    /// assert_eq!(graph.plan(&Bytes, &Stats).unwrap().output_size_hint(4), None);
+    /// ```
+    pub fn output_size_hint(&self, input_len: u64) -> Option<u64> {
+        self.path.windows(2).try_fold(input_len, |length, step| {
+            let bytes = step[0].decoded_size_hint(length)?;
+            step[1].encoded_size_hint(bytes)
+        })
+    }
+}
+
+impl Display for ConversionPlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let path = self
+            .path
+            .iter()
+            .map(Format::name)
+            .collect::<Vec<&str>>()
+            .join(" => ");
+        f.write_str(&path)
+    }
+}
+
+/// Options of a conversion run by [`ConversionGraph::convert_with_context`], and what it recorded
+///
+/// ```rust
+/// use bytary::convert::{ConversionContext, ConversionGraph};
+/// use bytary::format::Format::*;
+///
+/// let mut context = ConversionContext::new().with_timing(true);
+/// ConversionGraph::default()
+///     .convert_with_context(&mut context, &Bin, &Oct, &mut &b"01001000"[..], &mut Vec::new())
+///     .unwrap();
+/// let hops = context.stages().iter().map(|stage| stage.to.to_string()).collect::<Vec<_>>();
+/// assert_eq!(hops, ["hex", "bytes", "oct"]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ConversionContext {
+    timing: bool,
+    stages: Vec<StageTiming>,
+}
+
+impl ConversionContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to measure each hop of the conversion, off by default.
+    ///
+    /// With timing on, the hops are run one by one instead of through the converter of
+    /// [`get_converter`](ConversionGraph::get_converter), which is never measured.
+    pub fn with_timing(mut self, timing: bool) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    /// The hops of the last conversion, in order, if timing is on.
+    ///
+    /// If the conversion failed, only the hops that succeeded.
+    pub fn stages(&self) -> &[StageTiming] {
+        &self.stages
+    }
+}
+
+/// How [`ConversionGraph::merge`] resolves a conversion defined in both graphs
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MergeStrategy {
+    /// Keep the conversion already in the graph
+    PreferSelf,
+    /// Replace it with the conversion from the other graph
+    PreferOther,
+    /// Keep the conversion with the lower cost, or the one already in the graph if they cost the same
+    PreferCheaper,
+}
+
+/// What a [`ConversionGraph`] does with a conversion from a format to itself.
+///
+/// Such a conversion is never used, since converting a format to itself copies the input,
+/// so it is most likely a mistake.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SelfEdgePolicy {
+    /// Leave the conversion out of the graph, and report it with [`ConversionGraph::validate`]
+    #[default]
+    Ignore,
+    /// Fail with [`BytaryError::UnsupportedConversion`] when the conversion is added
+    Reject,
+}
+
+/// A problem found by [`ConversionGraph::validate`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GraphIssue {
+    /// A conversion from this format to itself was added, and ignored
+    SelfEdge(Format),
+    /// These formats, sorted, convert into each other through conversions that cost nothing,
+    /// so paths through them are as cheap as the shortest ones
+    ZeroCostCycle(Vec<Format>),
+    /// This format can be converted neither from nor to bytes
+    Unreachable(Format),
+    /// A direct conversion that costs the same as another path between the same formats,
+    /// so which one is used depends on the order of the formats
+    EqualCostPaths {
+        from: Format,
+        to: Format,
+        cost: u32,
+        /// The other path, from `from` to `to`
+        path: Vec<Format>,
+    },
+}
+
+impl Display for GraphIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let join = |formats: &[Format]| {
+            formats
+                .iter()
+                .map(Format::name)
+                .collect::<Vec<&str>>()
+                .join(" => ")
+        };
+        match self {
+            GraphIssue::SelfEdge(format) => write!(f, "ignored conversion {0} => {0}", format),
+            GraphIssue::ZeroCostCycle(formats) => {
+                write!(f, "zero-cost cycle between {}", join(formats))
+            }
+            GraphIssue::Unreachable(format) => {
+                write!(f, "{} cannot be converted from or to bytes", format)
+            }
+            GraphIssue::EqualCostPaths {
+                from,
+                to,
+                cost,
+                path,
+            } => write!(
+                f,
+                "{} => {} costs {}, the same as {}",
+                from,
+                to,
+                cost,
+                join(path)
+            ),
+        }
+    }
+}
+
+/// The default of [`ConversionGraph::set_max_path_length`]
+pub const DEFAULT_MAX_PATH_LENGTH: usize = 16;
+
+/// The direct conversions from one format, by destination format
+type Edges = BTreeMap<Format, (Rc<dyn Converter>, EdgeInfo)>;
+
+/// A graph of converters
 ///
 /// ```rust
 /// use bytary::convert::ConversionGraph;
@@ -19,80 +679,620 @@ type ConvertFn = dyn Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()>;
 /// let conv = graph.get_converter(&Bytes, &Hex).unwrap();
 /// ```
 pub struct ConversionGraph {
-    /// {Format -> {Format -> (ConvertFn, Cost)}}
-    graph: HashMap<Format, HashMap<Format, (Rc<ConvertFn>, u32)>>,
+    /// {Format -> {Format -> (Converter, EdgeInfo)}}, ordered so that iteration and path finding are deterministic
+    graph: BTreeMap<Format, Edges>,
+    /// Conversions whose converter is still the built-in one that [`chunks`](crate::chunks) can
+    /// also run chunk by chunk
+    chunked: BTreeSet<(Format, Format)>,
+    self_edge_policy: SelfEdgePolicy,
+    /// Formats whose conversion to themselves was ignored
+    ignored_self_edges: Vec<Format>,
+    /// Maximum number of conversions in a path
+    max_path_length: usize,
+    /// Maximum size of the input of [`ConversionGraph::convert`], in bytes
+    max_input_size: Option<u64>,
+    /// Composed converters already found, cleared whenever the graph changes
+    cache: RefCell<HashMap<(Format, Format), Rc<dyn Converter>>>,
+    /// Told about every conversion run by [`ConversionGraph::convert`]
+    observer: Option<Box<dyn ConversionObserver>>,
 }
 
 impl ConversionGraph {
-    /// Compose a list of converters into a single converter.
-    pub fn compose(converters: Vec<Rc<ConvertFn>>) -> Rc<ConvertFn> {
+    /// Compose a list of converters into a single converter, see [`ComposedConverter`].
+    ///
+    /// A single converter is returned as is.
+    pub fn compose(converters: Vec<Rc<dyn Converter>>) -> Rc<dyn Converter> {
         if converters.len() == 1 {
             return converters[0].clone();
         }
-
-        Rc::new(move |input: &mut dyn Read, output: &mut dyn Write| {
-            let mut prev_output: Box<dyn Read> = Box::new(input);
-
-            for converter in converters.iter().take(converters.len().saturating_sub(1)) {
-                let mut buffer = Vec::new();
-                converter(&mut prev_output, &mut buffer)?;
-                prev_output = Box::new(io::Cursor::new(buffer));
-            }
-
-            if let Some(last_processor) = converters.last() {
-                last_processor(&mut prev_output, output)?;
-            }
-
-            Ok(())
-        })
+        Rc::new(ComposedConverter::new(converters))
     }
 
     /// Get a converter that copies the input to the output without any conversion.
-    pub fn get_copy_converter() -> Rc<ConvertFn> {
-        Rc::new(|r, w| {
-            io::copy(r, w)?;
-            Ok(())
-        })
+    pub fn get_copy_converter() -> Rc<dyn Converter> {
+        Rc::new(CopyConverter)
     }
     /// Create a new empty [`ConversionGraph`]
     pub fn new() -> Self {
         Self {
-            graph: HashMap::new(),
+            graph: BTreeMap::new(),
+            chunked: BTreeSet::new(),
+            self_edge_policy: SelfEdgePolicy::default(),
+            ignored_self_edges: Vec::new(),
+            max_path_length: DEFAULT_MAX_PATH_LENGTH,
+            max_input_size: None,
+            cache: RefCell::default(),
+            observer: None,
+        }
+    }
+    /// Sets what happens to conversions from a format to itself added from now on
+    pub fn set_self_edge_policy(&mut self, policy: SelfEdgePolicy) {
+        self.self_edge_policy = policy;
+    }
+    /// Sets the maximum number of conversions in a path, beyond which
+    /// [`try_get_converter`](ConversionGraph::try_get_converter) fails with [`BytaryError::PathTooLong`]
+    pub fn set_max_path_length(&mut self, hops: usize) {
+        self.max_path_length = hops;
+        self.clear_cache();
+    }
+    /// Sets the maximum size in bytes of the input of [`convert`](ConversionGraph::convert) and
+    /// [`convert_with_report`](ConversionGraph::convert_with_report), beyond which they fail with
+    /// [`BytaryError::InputLimitExceeded`]. `None`, the default, means no limit.
+    ///
+    /// This bounds the memory of converters that read their whole input, like base64 decoding.
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::error::BytaryError;
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut graph = ConversionGraph::default();
+    /// graph.set_max_input_size(Some(4));
+    /// let result = graph.convert(&Base64, &Bytes, &mut &b"SGVsbG8="[..], &mut Vec::new());
+    /// assert!(matches!(result, Err(BytaryError::InputLimitExceeded(4))));
+    /// ```
+    pub fn set_max_input_size(&mut self, max_input_size: Option<u64>) {
+        self.max_input_size = max_input_size;
+    }
+    /// Forgets the converters cached by [`try_get_converter`](ConversionGraph::try_get_converter)
+    ///
+    /// The cache is cleared automatically whenever a conversion is added, so this only frees memory.
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+    /// Runs every conversion once over `sample` and sets its cost from the time it took,
+    /// so that path finding prefers the conversions that are fast on this machine.
+    ///
+    /// Each conversion gets the sample encoded to its source format. The fastest one costs 1,
+    /// and the others cost as many times more as they are slower. Conversions whose input can
+    /// not be made from the sample, or that fail on it, keep their cost.
+    ///
+    /// This is slow, as it converts the sample once per conversion in the graph.
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut graph = ConversionGraph::default();
+    /// graph.calibrate(b"Hello, world!");
+    /// assert!(graph.can_convert(&Bytes, &Hex));
+    /// ```
+    pub fn calibrate(&mut self, sample: &[u8]) {
+        let bytes = Format::default();
+        let mut timings = Vec::new();
+        for (from, edges) in &self.graph {
+            let input = match warning::strict(|| {
+                let mut input = Vec::new();
+                self.try_get_converter(&bytes, from)?
+                    .convert(&mut &sample[..], &mut input)
+                    .map(|_| input)
+            }) {
+                Ok(input) => input,
+                Err(_) => continue,
+            };
+            for (to, (converter, _)) in edges {
+                let start = Instant::now();
+                let result =
+                    warning::strict(|| converter.convert(&mut &input[..], &mut io::sink()));
+                let nanos = start.elapsed().as_nanos().max(1) as f64;
+                if result.is_ok() {
+                    timings.push((from.clone(), to.clone(), nanos));
+                }
+            }
+        }
+
+        let fastest = timings
+            .iter()
+            .map(|(_, _, nanos)| *nanos)
+            .fold(f64::INFINITY, f64::min);
+        for (from, to, nanos) in timings {
+            if let Some((_, info)) = self.graph.get_mut(&from).and_then(|map| map.get_mut(&to)) {
+                info.cost = (nanos / fastest).round().clamp(1.0, u32::MAX as f64) as u32;
+            }
         }
+        self.clear_cache();
     }
     /// Returns the number of conversions in the graph
     pub fn size(&self) -> usize {
-        self.graph.iter().map(|(_, h)| h.len()).sum()
+        self.graph.values().map(|h| h.len()).sum()
+    }
+    /// Returns the formats and direct conversions in the graph
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut graph = ConversionGraph::new();
+    /// graph.add_direct(Bytes, Hex, |_,_| Ok(()), 3).unwrap();
+    ///
+    /// let caps = graph.capabilities();
+    /// assert_eq!(caps.formats, vec![Bytes, Hex]);
+    /// assert_eq!(caps.conversions[0].cost, 3);
+    /// ```
+    pub fn capabilities(&self) -> Capabilities {
+        let conversions = self
+            .edges()
+            .map(|(from, to, info, _)| Conversion {
+                from: from.clone(),
+                to: to.clone(),
+                cost: info.cost,
+            })
+            .collect::<Vec<Conversion>>();
+
+        let mut formats = conversions
+            .iter()
+            .flat_map(|c| [c.from.clone(), c.to.clone()])
+            .collect::<Vec<Format>>();
+        formats.sort();
+        formats.dedup();
+
+        Capabilities {
+            formats,
+            conversions,
+        }
+    }
+    /// Returns every direct conversion as `(from, to, cost)`, sorted by `from` then `to`
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut graph = ConversionGraph::new();
+    /// graph.add_direct(Hex, Bytes, |_,_| Ok(()), 2).unwrap();
+    /// graph.add_direct(Bytes, Hex, |_,_| Ok(()), 3).unwrap();
+    ///
+    /// assert_eq!(graph.topology(), vec![(Bytes, Hex, 3), (Hex, Bytes, 2)]);
+    /// ```
+    pub fn topology(&self) -> Vec<(Format, Format, u32)> {
+        self.edges()
+            .map(|(from, to, info, _)| (from.clone(), to.clone(), info.cost))
+            .collect()
+    }
+    /// Returns the graph in Graphviz DOT, with a node per format and an edge per direct conversion.
+    ///
+    /// Edges are labeled with the converter name, if any, and the cost. Lossy conversions are dashed.
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut graph = ConversionGraph::new();
+    /// graph.add_direct(Bytes, Hex, |_,_| Ok(()), 3).unwrap();
+    ///
+    /// assert_eq!(
+    ///     graph.to_dot(),
+    ///     "digraph bytary {\n    \"bytes\";\n    \"hex\";\n    \"bytes\" -> \"hex\" [label=\"3\"];\n}\n"
+    /// );
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph bytary {\n");
+        for format in self.capabilities().formats {
+            dot.push_str(&format!("    \"{}\";\n", format));
+        }
+        for (from, to, info, _) in self.edges() {
+            let label = match info.name {
+                "" => info.cost.to_string(),
+                name => format!("{} ({})", name, info.cost),
+            };
+            let style = match info.lossy {
+                true => ", style=dashed",
+                false => "",
+            };
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"{}];\n",
+                from, to, label, style
+            ));
+        }
+        dot.push_str("}\n");
+        dot
     }
-    /// Adds a direct conversion to the graph
+    /// Adds an unnamed, lossless direct conversion to the graph.
+    ///
+    /// Fails only for a conversion from a format to itself with [`SelfEdgePolicy::Reject`].
     pub fn add_direct<T: Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static>(
         &mut self,
         from: Format,
         to: Format,
         converter: T,
         cost: u32,
+    ) -> BytaryResult<()> {
+        let info = EdgeInfo {
+            name: "",
+            lossy: false,
+            cost,
+        };
+        self.add_edge(from, to, info, converter)
+    }
+    /// Adds a direct conversion with metadata to the graph, failing like [`add_direct`](ConversionGraph::add_direct)
+    ///
+    /// ```rust
+    /// use bytary::convert::{ConversionGraph, EdgeInfo};
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut graph = ConversionGraph::new();
+    /// let info = EdgeInfo { name: "bytes_to_hex", lossy: false, cost: 1 };
+    /// graph.add_edge(Bytes, Hex, info, |_, _| Ok(())).unwrap();
+    ///
+    /// assert_eq!(graph.get_edge_info(&Bytes, &Hex).unwrap().name, "bytes_to_hex");
+    /// ```
+    pub fn add_edge<T: Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static>(
+        &mut self,
+        from: Format,
+        to: Format,
+        info: EdgeInfo,
+        converter: T,
+    ) -> BytaryResult<()> {
+        self.insert_edge(from, to, info, Rc::new(converter))
+    }
+    /// Adds a direct conversion with metadata to the graph, made by a [`Converter`] that is not
+    /// a closure, like the builtin ones. Fails like [`add_direct`](ConversionGraph::add_direct).
+    ///
+    /// ```rust
+    /// use bytary::builtins::BytesToHex;
+    /// use bytary::convert::{ConversionGraph, Converter, EdgeInfo};
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut graph = ConversionGraph::new();
+    /// let info = EdgeInfo { name: "bytes_to_hex", lossy: false, cost: 1 };
+    /// graph.add_converter(Bytes, Hex, info, BytesToHex).unwrap();
+    ///
+    /// assert!(graph.get_converter(&Bytes, &Hex).unwrap().is_streaming());
+    /// ```
+    pub fn add_converter<T: Converter + 'static>(
+        &mut self,
+        from: Format,
+        to: Format,
+        info: EdgeInfo,
+        converter: T,
+    ) -> BytaryResult<()> {
+        self.insert_edge(from, to, info, Rc::new(converter))
+    }
+    /// Like [`add_converter`](ConversionGraph::add_converter), for a built-in converter,
+    /// which is never from a format to itself
+    pub(crate) fn add_builtin_converter<T: Converter + 'static>(
+        &mut self,
+        from: Format,
+        to: Format,
+        info: EdgeInfo,
+        converter: T,
     ) {
+        debug_assert_ne!(from, to, "built-in conversions change the format");
+        self.put_edge(from, to, info, Rc::new(converter));
+    }
+    /// Like [`add_builtin_converter`](ConversionGraph::add_builtin_converter), for a converter that
+    /// [`chunks`](crate::chunks) also has a chunk by chunk implementation of
+    pub(crate) fn add_chunked_converter<T: Converter + 'static>(
+        &mut self,
+        from: Format,
+        to: Format,
+        info: EdgeInfo,
+        converter: T,
+    ) {
+        self.add_builtin_converter(from.clone(), to.clone(), info, converter);
+        self.chunked.insert((from, to));
+    }
+    /// Whether the conversion from `from` to `to` is still the one added by
+    /// [`add_chunked_converter`](ConversionGraph::add_chunked_converter)
+    pub(crate) fn is_chunked(&self, from: &Format, to: &Format) -> bool {
+        self.chunked.contains(&(from.clone(), to.clone()))
+    }
+    /// Creates a graph from direct conversions with metadata.
+    ///
+    /// If a conversion appears more than once, the last one wins.
+    pub fn from_edges<I: IntoIterator<Item = (Format, Format, EdgeInfo, Rc<dyn Converter>)>>(
+        edges: I,
+    ) -> Self {
+        let mut graph = Self::new();
+        for (from, to, info, converter) in edges {
+            graph
+                .insert_edge(from, to, info, converter)
+                .expect("conversions from a format to itself are ignored by default");
+        }
+        graph
+    }
+    /// Moves all conversions of `other` into this graph, resolving conflicts with `strategy`
+    ///
+    /// ```rust
+    /// use bytary::convert::{ConversionGraph, MergeStrategy};
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut graph = ConversionGraph::default();
+    /// let mut other = ConversionGraph::new();
+    /// other.add_direct(Bytes, Hex, |_, _| Ok(()), 5).unwrap();
+    /// other.add_direct(Hex, Dec, |_, _| Ok(()), 1).unwrap();
+    ///
+    /// graph.merge(other, MergeStrategy::PreferCheaper);
+    /// assert_eq!(graph.get_edge_info(&Bytes, &Hex).unwrap().cost, 1);
+    /// assert!(graph.can_convert(&Bytes, &Dec));
+    /// ```
+    pub fn merge(&mut self, other: ConversionGraph, strategy: MergeStrategy) {
+        self.ignored_self_edges.extend(other.ignored_self_edges);
+        let chunked = other.chunked;
+        for (from, map) in other.graph {
+            for (to, (converter, info)) in map {
+                let keep_self = match (self.get_edge_info(&from, &to), strategy) {
+                    (None, _) => false,
+                    (Some(_), MergeStrategy::PreferSelf) => true,
+                    (Some(_), MergeStrategy::PreferOther) => false,
+                    (Some(existing), MergeStrategy::PreferCheaper) => existing.cost <= info.cost,
+                };
+                if !keep_self {
+                    let key = (from.clone(), to);
+                    // A graph has no conversion from a format to itself
+                    self.put_edge(key.0.clone(), key.1.clone(), info, converter);
+                    if chunked.contains(&key) {
+                        self.chunked.insert(key);
+                    }
+                }
+            }
+        }
+    }
+    fn insert_edge(
+        &mut self,
+        from: Format,
+        to: Format,
+        info: EdgeInfo,
+        converter: Rc<dyn Converter>,
+    ) -> BytaryResult<()> {
+        if from == to {
+            return match self.self_edge_policy {
+                SelfEdgePolicy::Ignore => {
+                    self.ignored_self_edges.push(from);
+                    Ok(())
+                }
+                SelfEdgePolicy::Reject => BytaryError::UnsupportedConversion(from, to).err(),
+            };
+        }
+        self.put_edge(from, to, info, converter);
+        Ok(())
+    }
+    /// Adds a conversion between different formats, replacing the existing one
+    fn put_edge(&mut self, from: Format, to: Format, info: EdgeInfo, converter: Rc<dyn Converter>) {
+        self.clear_cache();
+        self.chunked.remove(&(from.clone(), to.clone()));
         self.graph
             .entry(from)
             .or_default()
-            .insert(to, (Rc::new(converter), cost));
+            .insert(to, (converter, info));
+    }
+    /// Iterates over all direct conversions in the graph, sorted by `from` then `to` in the order of [`Format`]
+    pub fn edges(&self) -> impl Iterator<Item = (&Format, &Format, &EdgeInfo, &Rc<dyn Converter>)> {
+        self.graph.iter().flat_map(|(from, map)| {
+            map.iter()
+                .map(move |(to, (converter, info))| (from, to, info, converter))
+        })
+    }
+    /// Get the metadata of the direct conversion from `from` to `to`
+    pub fn get_edge_info(&self, from: &Format, to: &Format) -> Option<&EdgeInfo> {
+        self.graph
+            .get(from)
+            .and_then(|map| map.get(to))
+            .map(|(_, info)| info)
     }
     /// Get a converter from `from` to `to`.
     ///
     /// If `to` is equals to `from`, return a converter that simply copies the input.
-    pub fn get_converter(&self, from: &Format, to: &Format) -> Option<Rc<ConvertFn>> {
+    /// Returns `None` if there is no path, or if it is too long, see [`ConversionGraph::try_get_converter`].
+    pub fn get_converter(&self, from: &Format, to: &Format) -> Option<Rc<dyn Converter>> {
+        self.try_get_converter(from, to).ok()
+    }
+    /// Like [`get_converter`](ConversionGraph::get_converter), but tells why there is no converter.
+    ///
+    /// Found converters are cached per pair of formats, so asking again does not search the graph again.
+    ///
+    /// Fails with [`BytaryError::UnsupportedConversion`] if there is no path, and with
+    /// [`BytaryError::PathTooLong`] if the path has more conversions than
+    /// [`set_max_path_length`](ConversionGraph::set_max_path_length) allows.
+    ///
+    /// The converter returns successfully, without reading the rest of its input, once its output
+    /// reaches the limit of a [`HeadWriter`](crate::utils::HeadWriter).
+    ///
+    /// ```rust
+    /// use bytary::builtins::{BytesToHex, HexToBytes};
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::error::BytaryError;
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut graph = ConversionGraph::default();
+    /// graph.set_max_path_length(2);
+    /// assert!(graph.try_get_converter(&Bin, &Bytes).is_ok());
+    /// assert!(matches!(
+    ///     graph.try_get_converter(&Bin, &Oct),
+    ///     Err(BytaryError::PathTooLong(_, _, 3, 2))
+    /// ));
+    /// ```
+    pub fn try_get_converter(&self, from: &Format, to: &Format) -> BytaryResult<Rc<dyn Converter>> {
         if to == from {
-            return Some(Self::get_copy_converter());
+            return Ok(Rc::new(StopAtOutputLimit(Self::get_copy_converter())));
         }
-        let path = self.find_shortest_path(from, to)?;
-
-        if path.len() <= 1 {
-            return None;
+        let key = (from.clone(), to.clone());
+        if let Some(converter) = self.cache.borrow().get(&key) {
+            return Ok(converter.clone());
         }
+        let path = self.try_find_path(from, to)?;
+        let converter: Rc<dyn Converter> = Rc::new(StopAtOutputLimit(Self::compose(
+            self.try_path_to_converters(&path)?,
+        )));
+        self.cache.borrow_mut().insert(key, converter.clone());
+        Ok(converter)
+    }
+    /// The path from `from` to `to`, if it is not too long
+    fn try_find_path(&self, from: &Format, to: &Format) -> BytaryResult<Vec<Format>> {
+        let path = self
+            .find_shortest_path(from, to)
+            .ok_or_else(|| BytaryError::UnsupportedConversion(from.clone(), to.clone()))?;
 
-        let converters = self.path_to_converters(&path).unwrap();
-
-        Some(Self::compose(converters))
+        let hops = path.len() - 1;
+        if hops > self.max_path_length {
+            return BytaryError::PathTooLong(from.clone(), to.clone(), hops, self.max_path_length)
+                .err();
+        }
+        Ok(path)
+    }
+    /// The converter of each hop of `path`, failing at the first hop that has none
+    fn try_path_to_converters(&self, path: &[Format]) -> BytaryResult<Vec<Rc<dyn Converter>>> {
+        path.windows(2)
+            .map(|w| {
+                self.get_direct_converter(&w[0], &w[1])
+                    .ok_or_else(|| BytaryError::UnsupportedConversion(w[0].clone(), w[1].clone()))
+            })
+            .collect()
+    }
+    /// Sets the observer told about every conversion run by [`convert`](ConversionGraph::convert),
+    /// replacing any previous one.
+    ///
+    /// The converters returned by [`get_converter`](ConversionGraph::get_converter) are not observed.
+    pub fn set_observer(&mut self, observer: Box<dyn ConversionObserver>) {
+        self.observer = Some(observer);
+    }
+    /// Converts `input` from `from` to `to`, telling the [observer](ConversionGraph::set_observer) if any.
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut output = Vec::new();
+    /// ConversionGraph::default().convert(&Hex, &Bytes, &mut &b"4869"[..], &mut output).unwrap();
+    /// assert_eq!(output, b"Hi");
+    /// ```
+    pub fn convert(
+        &self,
+        from: &Format,
+        to: &Format,
+        input: &mut dyn Read,
+        output: &mut dyn Write,
+    ) -> BytaryResult<()> {
+        self.convert_with_context(&mut ConversionContext::new(), from, to, input, output)
+    }
+    /// Like [`convert`](ConversionGraph::convert), with the options of `context`.
+    ///
+    /// With [timing](ConversionContext::with_timing) on, each hop is measured, recorded in
+    /// `context` and passed to [`on_stage`](ConversionObserver::on_stage) of the observer if any.
+    ///
+    /// ```rust
+    /// use bytary::convert::{ConversionContext, ConversionGraph};
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut context = ConversionContext::new().with_timing(true);
+    /// let mut output = Vec::new();
+    /// ConversionGraph::default()
+    ///     .convert_with_context(&mut context, &Bytes, &Hex, &mut &b"Hi"[..], &mut output)
+    ///     .unwrap();
+    /// assert_eq!(output, b"4869");
+    /// assert_eq!((context.stages()[0].bytes_in, context.stages()[0].bytes_out), (2, 4));
+    /// ```
+    pub fn convert_with_context(
+        &self,
+        context: &mut ConversionContext,
+        from: &Format,
+        to: &Format,
+        input: &mut dyn Read,
+        output: &mut dyn Write,
+    ) -> BytaryResult<()> {
+        context.stages.clear();
+        let mut input = self.limited(input);
+        let mut run = |input: &mut dyn Read, output: &mut dyn Write| match context.timing {
+            true => self.convert_timed(from, to, input, output, &mut context.stages),
+            false => self.try_get_converter(from, to)?.convert(input, output),
+        };
+        let Some(observer) = &self.observer else {
+            return run(&mut input, output);
+        };
+        observer.on_start(from, to);
+        let start = Instant::now();
+        let mut input = CountingReader::new(input);
+        let mut output = CountingWriter::new(output);
+        let result = run(&mut input, &mut output);
+        match &result {
+            Ok(()) => {
+                observer.on_complete(from, to, input.count(), output.count(), start.elapsed())
+            }
+            Err(e) => observer.on_error(from, to, e),
+        }
+        result
+    }
+    /// Converts `input` from `from` to `to`, returning the warnings reported by the converters.
+    ///
+    /// Converters that would fail on a recoverable issue, like a trailing unpaired hex digit,
+    /// complete the conversion instead and report a [`Warning`].
+    ///
+    /// ```rust
+    /// use bytary::convert::ConversionGraph;
+    /// use bytary::format::Format::*;
+    ///
+    /// let mut output = Vec::new();
+    /// let warnings = ConversionGraph::default()
+    ///     .convert_with_report(&Hex, &Bytes, &mut &b"48 69 2"[..], &mut output)
+    ///     .unwrap();
+    /// assert_eq!(output, b"Hi");
+    /// assert_eq!(warnings.len(), 1);
+    /// ```
+    pub fn convert_with_report(
+        &self,
+        from: &Format,
+        to: &Format,
+        input: &mut dyn Read,
+        output: &mut dyn Write,
+    ) -> BytaryResult<Vec<Warning>> {
+        let converter = self.try_get_converter(from, to)?;
+        let mut input = self.limited(input);
+        let (result, warnings) = warning::collect(|| converter.convert(&mut input, output));
+        result.map(|_| warnings)
+    }
+    /// Runs the hops from `from` to `to` one by one, recording each of them in `stages`
+    fn convert_timed(
+        &self,
+        from: &Format,
+        to: &Format,
+        input: &mut dyn Read,
+        output: &mut dyn Write,
+        stages: &mut Vec<StageTiming>,
+    ) -> BytaryResult<()> {
+        if from == to {
+            return CopyConverter.convert(input, output);
+        }
+        let path = self.try_find_path(from, to)?;
+        ComposedConverter::new(self.try_path_to_converters(&path)?).convert_timed(
+            input,
+            output,
+            &mut |index, bytes_in, bytes_out, duration| {
+                let stage = StageTiming {
+                    from: path[index].clone(),
+                    to: path[index + 1].clone(),
+                    bytes_in,
+                    bytes_out,
+                    duration,
+                };
+                if let Some(observer) = &self.observer {
+                    observer.on_stage(&stage);
+                }
+                stages.push(stage);
+            },
+        )
+    }
+    /// Wraps `input` to enforce the [maximum input size](ConversionGraph::set_max_input_size)
+    fn limited<'a>(&self, input: &'a mut dyn Read) -> LimitedReader<&'a mut dyn Read> {
+        LimitedReader::new(input, self.max_input_size.unwrap_or(u64::MAX))
     }
     /// ```rust
     /// use bytary::convert::ConversionGraph;
@@ -100,7 +1300,7 @@ impl ConversionGraph {
     ///
     /// let mut graph = ConversionGraph::new();
     ///
-    /// graph.add_direct(Bytes, Hex, |_,_| Ok(()), 1);
+    /// graph.add_direct(Bytes, Hex, |_,_| Ok(()), 1).unwrap();
     /// assert!(graph.can_convert(&Bytes, &Hex));
     /// assert!(!graph.can_convert(&Hex, &Bytes));
     /// ```
@@ -116,10 +1316,10 @@ impl ConversionGraph {
     ///
     /// let mut graph = ConversionGraph::new();
     ///
-    /// graph.add_direct(Bytes, Hex, |_,_| Ok(()), 1);
+    /// graph.add_direct(Bytes, Hex, |_,_| Ok(()), 1).unwrap();
     /// assert!(!graph.can_convert_between(&Bytes, &Hex));
     ///
-    /// graph.add_direct(Hex, Bytes, |_,_| Ok(()), 1);
+    /// graph.add_direct(Hex, Bytes, |_,_| Ok(()), 1).unwrap();
     /// assert!(graph.can_convert_between(&Bytes, &Hex));
     /// ```
     pub fn can_convert_between(&self, format1: &Format, format2: &Format) -> bool {
@@ -133,9 +1333,51 @@ impl ConversionGraph {
 
     /// Finds the shortest path between two formats.
     ///
+    /// Lossy conversions are only used as the last step of the path.
+    ///
+    /// Among paths of equal cost, the one with fewer conversions wins, then the one whose formats
+    /// come first in the order of [`Format`], so the same graph always gives the same path.
+    ///
     /// Returns a vector of formats representing the shortest path, or `None` if no path exists.
     pub fn find_shortest_path(&self, from: &Format, to: &Format) -> Option<Vec<Format>> {
-        Some(dijkstra(from, |n| self.successors(n), |f| f == to)?.0)
+        let mut queue = BinaryHeap::from([Reverse((0u64, 1, vec![from.clone()]))]);
+        let mut visited = BTreeSet::new();
+        // Paths compare by cost, then by length, then format by format
+        while let Some(Reverse((cost, _, path))) = queue.pop() {
+            let node = path.last()?.clone();
+            if node == *to {
+                return Some(path);
+            }
+            if !visited.insert(node.clone()) {
+                continue;
+            }
+            for (next, step) in self.successors(&node, to) {
+                if !visited.contains(&next) {
+                    let mut longer = path.clone();
+                    longer.push(next);
+                    queue.push(Reverse((cost + u64::from(step), longer.len(), longer)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Chooses the path of a conversion without running it, `None` if there is none.
+    ///
+    /// Converting a format to itself is a plan with a single format.
+    pub fn plan(&self, from: &Format, to: &Format) -> Option<ConversionPlan> {
+        let path = match from == to {
+            true => vec![from.clone()],
+            false => self.find_shortest_path(from, to)?,
+        };
+        let streaming_class = match path.len() {
+            1 => CopyConverter.streaming_class(),
+            _ => Self::compose(self.path_to_converters(&path)?).streaming_class(),
+        };
+        Some(ConversionPlan {
+            path,
+            streaming_class,
+        })
     }
 
     /// Get converters from given path
@@ -146,7 +1388,7 @@ impl ConversionGraph {
     ///
     /// ## Returns
     ///
-    /// A vector of converter functions. If any converter between formats is not found, it returns `None`.
+    /// A vector of converters. If any converter between formats is not found, it returns `None`.
     ///
     /// ## Examples
     ///
@@ -162,26 +1404,171 @@ impl ConversionGraph {
     ///
     /// 1. Converts bytes to binary representation.
     /// 2. Converts binary to hexadecimal representation.
-    pub fn path_to_converters(&self, path: &Vec<Format>) -> Option<Vec<Rc<ConvertFn>>> {
-        let converters = path
-            .windows(2)
-            .map_while(|w| Some(self.get_direct_converter(&w[0], &w[1])?))
-            .collect();
-        Some(converters)
+    pub fn path_to_converters(&self, path: &[Format]) -> Option<Vec<Rc<dyn Converter>>> {
+        self.try_path_to_converters(path).ok()
     }
 
-    fn get_direct_converter(&self, from: &Format, to: &Format) -> Option<Rc<ConvertFn>> {
+    fn get_direct_converter(&self, from: &Format, to: &Format) -> Option<Rc<dyn Converter>> {
         self.graph
             .get(from)
             .and_then(|map| map.get(to))
-            .map_or(None, |(f, _)| Some(f.clone()))
+            .map(|(f, _)| f.clone())
     }
-    fn successors(&self, n: &Format) -> Vec<(Format, u32)> {
-        self.graph
-            .get(&n)
-            .unwrap_or(&HashMap::new())
+    /// Looks for mistakes in the graph, which path finding would not report.
+    ///
+    /// ```rust
+    /// use bytary::convert::{ConversionGraph, GraphIssue};
+    /// use bytary::format::Format::*;
+    ///
+    /// assert_eq!(ConversionGraph::default().validate(), vec![]);
+    ///
+    /// let mut graph = ConversionGraph::default();
+    /// graph.add_direct(Hex, Hex, |_, _| Ok(()), 1).unwrap();
+    /// assert_eq!(graph.validate(), vec![GraphIssue::SelfEdge(Hex)]);
+    /// ```
+    pub fn validate(&self) -> Vec<GraphIssue> {
+        let mut issues = self
+            .ignored_self_edges
             .iter()
-            .map(|(format, (_, cost))| (format.clone(), *cost))
+            .map(|format| GraphIssue::SelfEdge(format.clone()))
+            .collect::<Vec<GraphIssue>>();
+
+        // Formats that reach each other through free conversions
+        let formats = self.capabilities().formats;
+        let mut seen = Vec::new();
+        for format in &formats {
+            if seen.contains(format) {
+                continue;
+            }
+            let mut cycle = formats
+                .iter()
+                .filter(|other| {
+                    *other != format
+                        && self.reaches_for_free(format, other)
+                        && self.reaches_for_free(other, format)
+                })
+                .cloned()
+                .collect::<Vec<Format>>();
+            if !cycle.is_empty() {
+                cycle.push(format.clone());
+                cycle.sort();
+                seen.extend(cycle.iter().cloned());
+                issues.push(GraphIssue::ZeroCostCycle(cycle));
+            }
+        }
+
+        let bytes = Format::default();
+        issues.extend(
+            formats
+                .iter()
+                .filter(|format| {
+                    **format != bytes
+                        && !self.can_convert(&bytes, format)
+                        && !self.can_convert(format, &bytes)
+                })
+                .map(|format| GraphIssue::Unreachable(format.clone())),
+        );
+
+        for (from, to, info, _) in self.edges() {
+            // The cheapest path that does not take this conversion
+            let other = dijkstra(
+                from,
+                |n| {
+                    let mut successors = self.successors(n, to);
+                    if n == from {
+                        successors.retain(|(format, _)| format != to);
+                    }
+                    successors
+                },
+                |f| f == to,
+            );
+            if let Some((path, cost)) = other
+                && cost == info.cost
+            {
+                issues.push(GraphIssue::EqualCostPaths {
+                    from: from.clone(),
+                    to: to.clone(),
+                    cost,
+                    path,
+                });
+            }
+        }
+        issues
+    }
+
+    /// Whether `to` is reachable from `from` through conversions that cost nothing
+    fn reaches_for_free(&self, from: &Format, to: &Format) -> bool {
+        let successors = |n: &Format| {
+            self.graph
+                .get(n)
+                .into_iter()
+                .flatten()
+                .filter(|(_, (_, info))| info.cost == 0)
+                .map(|(format, _)| format.clone())
+                .collect::<Vec<Format>>()
+        };
+        pathfinding::prelude::bfs(from, |n| successors(n), |f| f == to).is_some()
+    }
+
+    /// Get the formats directly reachable from `n`, skipping lossy conversions unless they lead to `target`
+    fn successors(&self, n: &Format, target: &Format) -> Vec<(Format, u32)> {
+        self.graph
+            .get(n)
+            .into_iter()
+            .flatten()
+            .filter(|(format, (_, info))| !info.lossy || *format == target)
+            .map(|(format, (_, info))| (format.clone(), info.cost))
             .collect::<Vec<(Format, u32)>>()
     }
 }
+
+/// Decodes `input` with each of the `candidates` in order, returning the first format that decodes it
+/// to bytes along with the bytes.
+///
+/// Decoding is strict: the input must pass [`Format::validate`] where it applies, and a candidate
+/// that would report warnings fails, even if warnings are [collected](warning::collect). Since a candidate only succeeds once all of the input is decoded,
+/// the input and its decoding are held in memory, so callers should limit how much they read.
+///
+/// Fails with [`BytaryError::NoFormatDecoded`] if no candidate decodes the input.
+///
+/// ```rust
+/// use bytary::convert::{ConversionGraph, decode_any};
+/// use bytary::format::Format::*;
+///
+/// let graph = ConversionGraph::default();
+/// let (format, bytes) = decode_any(&[Hex, Base64], b"SGk=", &graph).unwrap();
+/// assert_eq!((format, bytes), (Base64, b"Hi".to_vec()));
+///
+/// assert!(decode_any(&[Hex, Bin], b"SGk=", &graph).is_err());
+/// ```
+pub fn decode_any(
+    candidates: &[Format],
+    input: &[u8],
+    graph: &ConversionGraph,
+) -> BytaryResult<(Format, Vec<u8>)> {
+    let mut failures = Vec::new();
+    for candidate in candidates {
+        let mut output = Vec::new();
+        let result = validate(candidate, input).and_then(|_| {
+            let converter = graph.try_get_converter(candidate, &Format::Bytes)?;
+            warning::strict(|| converter.convert(&mut &input[..], &mut output))
+        });
+        match result {
+            Ok(()) => return Ok((candidate.clone(), output)),
+            Err(e) => failures.push((candidate.clone(), e)),
+        }
+    }
+    BytaryError::NoFormatDecoded(failures).err()
+}
+
+/// Fails if `input` is not well-formed for `format`, for the formats that can be validated
+fn validate(format: &Format, input: &[u8]) -> BytaryResult<()> {
+    match format.validate(&mut &input[..]) {
+        Ok(report) => match report.error {
+            Some(e) => BytaryError::InvalidInputData(e.to_string()).err(),
+            None => Ok(()),
+        },
+        Err(BytaryError::UnsupportedConversion(..)) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
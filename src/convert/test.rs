@@ -0,0 +1,919 @@
+use crate::convert::{
+    CachedConverter, ComposedConverter, ConversionContext, ConversionGraph, Converter,
+    CopyConverter, EdgeInfo, GraphIssue, MergeStrategy, SelfEdgePolicy, StreamingClass, decode_any,
+};
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use crate::format::Format::*;
+use crate::observe::{ConversionObserver, StageTiming};
+use crate::utils::{CountingReader, FormattedWriter, HeadWriter};
+use crate::warning;
+use crate::warning::Warning;
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::rc::Rc;
+use std::time::Duration;
+
+fn lossy(name: &'static str) -> EdgeInfo {
+    EdgeInfo {
+        name,
+        lossy: true,
+        cost: 1,
+    }
+}
+
+#[test]
+fn test_lossy_intermediate() {
+    let mut graph = ConversionGraph::new();
+    graph
+        .add_edge(Bytes, Hex, lossy("lowercase"), |_, _| Ok(()))
+        .unwrap();
+    graph.add_direct(Hex, Base64, |_, _| Ok(()), 1).unwrap();
+
+    assert_eq!(
+        graph.find_shortest_path(&Bytes, &Hex),
+        Some(vec![Bytes, Hex])
+    );
+    assert!(graph.get_converter(&Bytes, &Hex).is_some());
+
+    assert_eq!(graph.find_shortest_path(&Bytes, &Base64), None);
+    assert!(graph.get_converter(&Bytes, &Base64).is_none());
+    assert!(!graph.can_convert(&Bytes, &Base64));
+}
+
+#[test]
+fn test_lossy_avoided() {
+    let mut graph = ConversionGraph::new();
+    graph
+        .add_edge(Bytes, Hex, lossy("lowercase"), |_, _| Ok(()))
+        .unwrap();
+    graph.add_direct(Hex, Base64, |_, _| Ok(()), 1).unwrap();
+    graph.add_direct(Bytes, Bin, |_, _| Ok(()), 5).unwrap();
+    graph.add_direct(Bin, Base64, |_, _| Ok(()), 5).unwrap();
+
+    assert_eq!(
+        graph.find_shortest_path(&Bytes, &Base64),
+        Some(vec![Bytes, Bin, Base64])
+    );
+}
+
+#[test]
+fn test_edge_info() {
+    let graph = ConversionGraph::default();
+    let info = graph.get_edge_info(&Bytes, &Hex).unwrap();
+    assert_eq!(info.name, "bytes_to_hex");
+    assert!(!info.lossy);
+    assert!(graph.get_edge_info(&Hex, &Oct).is_none());
+
+    let mut graph = ConversionGraph::new();
+    graph.add_direct(Bytes, Hex, |_, _| Ok(()), 7).unwrap();
+    assert_eq!(
+        graph.get_edge_info(&Bytes, &Hex),
+        Some(&EdgeInfo {
+            name: "",
+            lossy: false,
+            cost: 7
+        })
+    );
+}
+
+/// Build a graph with a single conversion that writes `output`
+fn constant_graph(from: Format, to: Format, output: &'static [u8], cost: u32) -> ConversionGraph {
+    let info = EdgeInfo {
+        name: "",
+        lossy: false,
+        cost,
+    };
+    let converter: Rc<dyn Converter> =
+        Rc::new(move |_: &mut dyn Read, w: &mut dyn Write| Ok(w.write_all(output)?));
+    ConversionGraph::from_edges([(from, to, info, converter)])
+}
+
+fn convert(graph: &ConversionGraph, from: &Format, to: &Format) -> Vec<u8> {
+    let mut output = Vec::new();
+    graph
+        .get_converter(from, to)
+        .unwrap()
+        .convert(&mut &b""[..], &mut output)
+        .unwrap();
+    output
+}
+
+#[test]
+fn test_merge() {
+    for (strategy, expected) in [
+        (MergeStrategy::PreferSelf, b"self"),
+        (MergeStrategy::PreferOther, b"othr"),
+        (MergeStrategy::PreferCheaper, b"othr"),
+    ] {
+        let mut graph = constant_graph(Bytes, Hex, b"self", 3);
+        graph.add_direct(Hex, Bytes, |_, _| Ok(()), 1).unwrap();
+        let mut other = constant_graph(Bytes, Hex, b"othr", 2);
+        other.add_direct(Bytes, Dec, |_, _| Ok(()), 1).unwrap();
+
+        graph.merge(other, strategy);
+        assert_eq!(graph.size(), 3);
+        assert_eq!(convert(&graph, &Bytes, &Hex), expected, "{:?}", strategy);
+        assert!(graph.can_convert(&Hex, &Dec));
+    }
+
+    let mut graph = constant_graph(Bytes, Hex, b"self", 2);
+    graph.merge(
+        constant_graph(Bytes, Hex, b"othr", 2),
+        MergeStrategy::PreferCheaper,
+    );
+    assert_eq!(graph.size(), 1);
+    assert_eq!(convert(&graph, &Bytes, &Hex), b"self");
+}
+
+#[test]
+fn test_from_edges() {
+    let graph = ConversionGraph::default();
+    let copy = ConversionGraph::from_edges(
+        graph
+            .edges()
+            .map(|(from, to, info, f)| (from.clone(), to.clone(), info.clone(), f.clone())),
+    );
+    assert_eq!(copy.size(), graph.size());
+    assert_eq!(copy.capabilities(), graph.capabilities());
+    assert_eq!(ConversionGraph::from_edges([]).size(), 0);
+}
+
+#[test]
+fn test_dot() {
+    let graph = ConversionGraph::default();
+    let dot = graph.to_dot();
+    assert_eq!(dot, ConversionGraph::default().to_dot());
+    assert!(dot.starts_with("digraph bytary {\n"));
+    assert!(dot.ends_with("}\n"));
+
+    let topology = graph.topology();
+    assert_eq!(topology.len(), graph.size());
+    let mut sorted = topology.clone();
+    sorted.sort();
+    assert_eq!(topology, sorted);
+    for (from, to, cost) in &topology {
+        let prefix = format!("    \"{}\" -> \"{}\" [label=", from, to);
+        let lines = dot.lines().filter(|line| line.starts_with(&prefix));
+        assert_eq!(lines.count(), 1, "{} -> {}", from, to);
+        assert!(dot.contains(&format!("({})", cost)));
+    }
+    assert_eq!(dot.matches(" -> ").count(), topology.len());
+
+    let mut graph = ConversionGraph::new();
+    graph
+        .add_converter(Hex, Bytes, lossy("lossy"), CopyConverter)
+        .unwrap();
+    graph.add_direct(Bytes, Hex, |_, _| Ok(()), 2).unwrap();
+    assert_eq!(
+        graph.to_dot(),
+        "digraph bytary {\n    \"bytes\";\n    \"hex\";\n    \"bytes\" -> \"hex\" [label=\"2\"];\n    \"hex\" -> \"bytes\" [label=\"lossy (1)\", style=dashed];\n}\n"
+    );
+}
+
+#[test]
+fn test_edges_order() {
+    let edges = |graph: &ConversionGraph| {
+        graph
+            .edges()
+            .map(|(from, to, _, _)| (from.clone(), to.clone()))
+            .collect::<Vec<(Format, Format)>>()
+    };
+    let graph = ConversionGraph::default();
+    let first = edges(&graph);
+    assert_eq!(first, edges(&ConversionGraph::default()));
+
+    let mut sorted = first.clone();
+    sorted.sort();
+    assert_eq!(first, sorted);
+    assert_eq!(first[0], (Format::Bytes, Format::Bin));
+
+    // Paths of equal cost are chosen the same way every time
+    for _ in 0..10 {
+        assert_eq!(
+            ConversionGraph::default().find_shortest_path(&Format::Bin, &Format::Oct),
+            Some(vec![Format::Bin, Format::Hex, Format::Bytes, Format::Oct])
+        );
+    }
+}
+
+#[test]
+fn test_tie_break() {
+    let copy = |input: &mut dyn Read, output: &mut dyn Write| {
+        std::io::copy(input, output)?;
+        Ok(())
+    };
+    let mut graph = ConversionGraph::new();
+    graph.add_direct(Bytes, Hex, copy, 1).unwrap();
+    graph.add_direct(Hex, Oct, copy, 1).unwrap();
+    graph.add_direct(Bytes, Bin, copy, 1).unwrap();
+    graph.add_direct(Bin, Oct, copy, 1).unwrap();
+
+    // Equal cost and length, so the path through the format that comes first
+    for _ in 0..100 {
+        assert_eq!(
+            graph.find_shortest_path(&Bytes, &Oct),
+            Some(vec![Bytes, Bin, Oct])
+        );
+    }
+
+    // Equal cost, so the path with fewer conversions
+    graph.add_direct(Bytes, Oct, copy, 2).unwrap();
+    for _ in 0..100 {
+        assert_eq!(
+            graph.find_shortest_path(&Bytes, &Oct),
+            Some(vec![Bytes, Oct])
+        );
+    }
+}
+
+#[test]
+fn test_calibrate() {
+    let copy = |input: &mut dyn Read, output: &mut dyn Write| {
+        std::io::copy(input, output)?;
+        Ok(())
+    };
+    let slow = |input: &mut dyn Read, output: &mut dyn Write| {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::io::copy(input, output)?;
+        Ok(())
+    };
+    let mut graph = ConversionGraph::new();
+    graph.add_direct(Bytes, Hex, slow, 1).unwrap();
+    graph.add_direct(Bytes, Bin, copy, 1).unwrap();
+    graph.add_direct(Bin, Hex, copy, 1).unwrap();
+    assert_eq!(
+        graph.find_shortest_path(&Bytes, &Hex),
+        Some(vec![Bytes, Hex])
+    );
+    let before = graph.get_converter(&Bytes, &Hex).unwrap();
+
+    graph.calibrate(&[0x5a; 4096]);
+    assert!(graph.get_edge_info(&Bytes, &Hex).unwrap().cost > 2);
+    assert_eq!(
+        graph.find_shortest_path(&Bytes, &Hex),
+        Some(vec![Bytes, Bin, Hex])
+    );
+    assert!(!Rc::ptr_eq(
+        &before,
+        &graph.get_converter(&Bytes, &Hex).unwrap()
+    ));
+
+    // Conversions that fail on the sample keep their cost
+    graph
+        .add_direct(
+            Hex,
+            Oct,
+            |_, _| Err(BytaryError::InvalidInputData("no".to_string())),
+            7,
+        )
+        .unwrap();
+    graph.calibrate(b"sample");
+    assert_eq!(graph.get_edge_info(&Hex, &Oct).unwrap().cost, 7);
+}
+
+#[test]
+fn test_output_size_hint() {
+    let graph = ConversionGraph::default();
+    let pairs = [
+        (Bytes, Bytes),
+        (Bytes, Bin),
+        (Bytes, Hex),
+        (Bytes, Oct),
+        (Bytes, Base64),
+        (Bytes, Utf8),
+        (Hex, Bytes),
+        (Bin, Oct),
+        (Oct, Base64),
+        (Hex, Bin),
+    ];
+    for (from, to) in pairs {
+        let plan = graph.plan(&from, &to).unwrap();
+        for size in [0usize, 1, 2, 3, 4, 5, 63, 64, 1000] {
+            // Printable ASCII, which is also valid UTF-8
+            let bytes = (0..size)
+                .map(|i| b'a' + (i % 26) as u8)
+                .collect::<Vec<u8>>();
+            let mut input = Vec::new();
+            graph
+                .get_converter(&Bytes, &from)
+                .unwrap()
+                .convert(&mut &bytes[..], &mut input)
+                .unwrap();
+            let mut output = Vec::new();
+            graph
+                .get_converter(&from, &to)
+                .unwrap()
+                .convert(&mut &input[..], &mut output)
+                .unwrap();
+
+            assert_eq!(
+                plan.output_size_hint(input.len() as u64),
+                Some(output.len() as u64),
+                "{} with {} bytes",
+                plan,
+                size
+            );
+        }
+    }
+
+    for (from, to) in [
+        (Base64, Bytes),
+        (Bytes, Utf16Le),
+        (Bytes, Stats),
+        (Bytes, Pem),
+    ] {
+        let plan = graph.plan(&from, &to).unwrap();
+        assert_eq!(plan.output_size_hint(12), None, "{}", plan);
+    }
+    assert_eq!(
+        graph.plan(&Bytes, &Hex).unwrap().to_string(),
+        "bytes => hex"
+    );
+    assert!(graph.plan(&Stats, &Bytes).is_none());
+}
+
+#[test]
+fn test_converter_metadata() {
+    let graph = ConversionGraph::default();
+    // The built-in converters are named like their edges
+    for (from, to, info, converter) in graph.edges() {
+        assert_eq!(converter.name(), info.name, "{} => {}", from, to);
+    }
+
+    let hex = graph.get_converter(&Bytes, &Hex).unwrap();
+    assert_eq!(hex.name(), "bytes_to_hex");
+    assert_eq!(hex.expansion_ratio(), Some(2.0));
+    assert!(hex.is_streaming());
+
+    let copy = graph.get_converter(&Hex, &Hex).unwrap();
+    assert_eq!(copy.name(), "copy");
+    assert_eq!(copy.expansion_ratio(), Some(1.0));
+
+    // bin => hex => bytes => base64
+    let composed = graph.get_converter(&Bin, &Base64).unwrap();
+    assert_eq!(composed.name(), "bin_to_hex, hex_to_bytes, bytes_to_base64");
+    assert_eq!(composed.expansion_ratio(), Some(1.0 / 6.0));
+    assert!(!composed.is_streaming());
+    let mut output = Vec::new();
+    composed
+        .convert(&mut &b"01001000 01101001"[..], &mut output)
+        .unwrap();
+    assert_eq!(output, b"SGk=");
+
+    // Any step whose output length depends on the data makes the whole ratio unknown
+    let stats = graph.get_converter(&Hex, &Stats).unwrap();
+    assert_eq!(stats.name(), "hex_to_bytes, bytes_to_stats");
+    assert_eq!(stats.expansion_ratio(), None);
+
+    // Streaming parts compose into a converter that buffers between them
+    let parts: Vec<Rc<dyn Converter>> = vec![Rc::new(CopyConverter), Rc::new(CopyConverter)];
+    let composed = ComposedConverter::new(parts);
+    assert_eq!(composed.parts().len(), 2);
+    assert_eq!(composed.name(), "copy, copy");
+    assert!(!composed.is_streaming());
+    assert!(ConversionGraph::compose(vec![Rc::new(CopyConverter)]).is_streaming());
+    assert_eq!(composed.streaming_class(), StreamingClass::BuffersOutput);
+
+    // The worst class of the parts wins
+    let base64 = graph.get_converter(&Base64, &Bytes).unwrap();
+    assert_eq!(base64.streaming_class(), StreamingClass::BuffersInput);
+    let composed = ComposedConverter::new(vec![base64, hex]);
+    assert_eq!(composed.streaming_class(), StreamingClass::BuffersInput);
+
+    assert_eq!(
+        graph.plan(&Hex, &Hex).unwrap().streaming_class(),
+        StreamingClass::Streaming
+    );
+    assert_eq!(
+        graph.plan(&Bytes, &Stats).unwrap().streaming_class(),
+        StreamingClass::Streaming
+    );
+}
+
+#[test]
+fn test_error_offset() {
+    let graph = ConversionGraph::default();
+    let decode = |converter: Rc<dyn Converter>, input: &[u8]| {
+        converter
+            .convert(&mut &input[..], &mut Vec::new())
+            .unwrap_err()
+    };
+
+    // The first part reads the input of the conversion
+    let error = decode(graph.get_converter(&Base64, &Hex).unwrap(), b"SGVs\nbG8*");
+    assert_eq!(error.offset(), Some(8));
+
+    // The later parts do not
+    let hex = graph.get_converter(&Hex, &Bytes).unwrap();
+    let error = decode(
+        ConversionGraph::compose(vec![Rc::new(CopyConverter), hex]),
+        b"0g",
+    );
+    assert!(matches!(error, BytaryError::InvalidInputData(_)));
+    assert_eq!(error.offset(), None);
+}
+
+#[test]
+fn test_closure_converter() {
+    let mut graph = ConversionGraph::new();
+    graph
+        .add_direct(Bytes, Hex, |_, w| Ok(w.write_all(b"ok")?), 1)
+        .unwrap();
+    let converter = graph.get_converter(&Bytes, &Hex).unwrap();
+    assert_eq!(converter.name(), "");
+    assert_eq!(converter.expansion_ratio(), None);
+    assert!(!converter.is_streaming());
+    assert_eq!(convert(&graph, &Bytes, &Hex), b"ok");
+}
+
+#[test]
+fn test_validate_default() {
+    assert_eq!(ConversionGraph::default().validate(), vec![]);
+}
+
+#[test]
+fn test_self_edge() {
+    let mut graph = ConversionGraph::default();
+    let size = graph.size();
+    graph
+        .add_direct(Hex, Hex, |_, w| Ok(w.write_all(b"shadow")?), 1)
+        .unwrap();
+    assert_eq!(graph.size(), size);
+    assert_eq!(graph.validate(), vec![GraphIssue::SelfEdge(Hex)]);
+    // Converting a format to itself still copies
+    let mut output = Vec::new();
+    graph
+        .get_converter(&Hex, &Hex)
+        .unwrap()
+        .convert(&mut &b"1b"[..], &mut output)
+        .unwrap();
+    assert_eq!(output, b"1b");
+
+    // Ignored self edges are kept track of through merges
+    let mut merged = ConversionGraph::default();
+    merged.merge(graph, MergeStrategy::PreferSelf);
+    assert_eq!(merged.validate(), vec![GraphIssue::SelfEdge(Hex)]);
+}
+
+#[test]
+fn test_self_edge_rejected() {
+    let mut graph = ConversionGraph::new();
+    graph.set_self_edge_policy(SelfEdgePolicy::Reject);
+    let error = graph.add_direct(Hex, Hex, |_, _| Ok(()), 1).unwrap_err();
+    assert!(matches!(
+        error,
+        BytaryError::UnsupportedConversion(Hex, Hex)
+    ));
+    assert_eq!(error.to_string(), "Unsupported conversion: hex => hex");
+    // The graph is left as it was
+    assert_eq!(graph.size(), 0);
+    assert_eq!(graph.validate(), vec![]);
+    graph.add_direct(Bytes, Hex, |_, _| Ok(()), 1).unwrap();
+    assert!(graph.can_convert(&Bytes, &Hex));
+}
+
+#[test]
+fn test_validate_issues() {
+    let mut graph = ConversionGraph::new();
+    graph.add_direct(Bytes, Hex, |_, _| Ok(()), 1).unwrap();
+    graph.add_direct(Hex, Bytes, |_, _| Ok(()), 1).unwrap();
+    // Free both ways
+    graph.add_direct(Bin, Oct, |_, _| Ok(()), 0).unwrap();
+    graph.add_direct(Oct, Bin, |_, _| Ok(()), 0).unwrap();
+    graph.add_direct(Hex, Dec, |_, _| Ok(()), 3).unwrap();
+    graph.add_direct(Hex, Base64, |_, _| Ok(()), 1).unwrap();
+    graph.add_direct(Base64, Dec, |_, _| Ok(()), 2).unwrap();
+
+    assert_eq!(
+        graph.validate(),
+        vec![
+            GraphIssue::ZeroCostCycle(vec![Bin, Oct]),
+            GraphIssue::Unreachable(Bin),
+            GraphIssue::Unreachable(Oct),
+            GraphIssue::EqualCostPaths {
+                from: Hex,
+                to: Dec,
+                cost: 3,
+                path: vec![Hex, Base64, Dec],
+            },
+        ]
+    );
+    assert_eq!(
+        graph.validate()[3].to_string(),
+        "hex => dec costs 3, the same as hex => base64 => dec"
+    );
+}
+
+#[test]
+fn test_path_missing_hop() {
+    let graph = ConversionGraph::default();
+    assert_eq!(
+        graph.path_to_converters(&[Bytes, Bin, Hex]).unwrap().len(),
+        2
+    );
+    assert_eq!(graph.path_to_converters(&[Hex]).unwrap().len(), 0);
+
+    // A missing hop fails the whole path, instead of cutting it short
+    assert!(graph.path_to_converters(&[Bytes, Hex, Stats]).is_none());
+    assert!(graph.path_to_converters(&[Stats, Bytes, Hex]).is_none());
+    assert!(matches!(
+        graph.try_path_to_converters(&[Bytes, Hex, Stats, Bytes]),
+        Err(BytaryError::UnsupportedConversion(Hex, Stats))
+    ));
+}
+
+#[test]
+fn test_path_too_long() {
+    // bytes => bin => hex => oct => dec => base64
+    let mut graph = ConversionGraph::new();
+    let chain = [Bytes, Bin, Hex, Oct, Dec, Base64];
+    for step in chain.windows(2) {
+        graph
+            .add_direct(step[0].clone(), step[1].clone(), |_, _| Ok(()), 1)
+            .unwrap();
+    }
+    assert!(graph.try_get_converter(&Bytes, &Base64).is_ok());
+
+    graph.set_max_path_length(4);
+    assert!(graph.try_get_converter(&Bytes, &Dec).is_ok());
+    let error = graph.try_get_converter(&Bytes, &Base64).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Conversion path bytes => base64 takes 5 steps, more than the maximum of 4"
+    );
+    assert!(graph.get_converter(&Bytes, &Base64).is_none());
+    // The path is still found, it is only not composed
+    assert!(graph.can_convert(&Bytes, &Base64));
+
+    assert!(matches!(
+        graph.try_get_converter(&Base64, &Bytes),
+        Err(BytaryError::UnsupportedConversion(_, _))
+    ));
+}
+
+#[test]
+fn test_converter_cache() -> BytaryResult<()> {
+    let mut graph = ConversionGraph::new();
+    graph
+        .add_direct(Bytes, Hex, |_, output| Ok(output.write_all(b"h")?), 1)
+        .unwrap();
+    graph
+        .add_direct(Hex, Base64, |_, output| Ok(output.write_all(b"b")?), 1)
+        .unwrap();
+
+    // The second call returns the converter composed by the first
+    let first = graph.get_converter(&Bytes, &Base64).unwrap();
+    let second = graph.get_converter(&Bytes, &Base64).unwrap();
+    assert!(Rc::ptr_eq(&first, &second));
+
+    graph.clear_cache();
+    let third = graph.get_converter(&Bytes, &Base64).unwrap();
+    assert!(!Rc::ptr_eq(&first, &third));
+
+    // Adding a conversion invalidates the cached path
+    graph
+        .add_direct(Bytes, Base64, |_, output| Ok(output.write_all(b"d")?), 1)
+        .unwrap();
+    let mut output = Vec::new();
+    graph
+        .get_converter(&Bytes, &Base64)
+        .unwrap()
+        .convert(&mut &b""[..], &mut output)?;
+    assert_eq!(output, b"d");
+
+    let mut other = ConversionGraph::new();
+    other
+        .add_direct(Bytes, Base64, |_, output| Ok(output.write_all(b"m")?), 0)
+        .unwrap();
+    graph.merge(other, MergeStrategy::PreferCheaper);
+    let mut output = Vec::new();
+    graph
+        .get_converter(&Bytes, &Base64)
+        .unwrap()
+        .convert(&mut &b""[..], &mut output)?;
+    assert_eq!(output, b"m");
+    Ok(())
+}
+
+#[test]
+fn test_decode_any() {
+    let graph = ConversionGraph::default();
+
+    // Digits that are both bin and hex go to the first candidate
+    assert_eq!(
+        decode_any(&[Bin, Hex], b"01000001", &graph).unwrap(),
+        (Bin, b"A".to_vec())
+    );
+    assert_eq!(
+        decode_any(&[Hex, Bin], b"01000001", &graph).unwrap(),
+        (Hex, vec![0x01, 0x00, 0x00, 0x01])
+    );
+    // Bin digits that do not fill a byte are not bin
+    assert_eq!(
+        decode_any(&[Bin, Hex], b"0101", &graph).unwrap(),
+        (Hex, vec![0x01, 0x01])
+    );
+
+    assert_eq!(
+        decode_any(&[Hex, Base64], b"SGk=", &graph).unwrap(),
+        (Base64, b"Hi".to_vec())
+    );
+
+    assert_eq!(
+        decode_any(&[Hex, Bin], b"zz", &graph)
+            .unwrap_err()
+            .to_string(),
+        "No format decoded the input: \
+        hex: Invalid input data: unexpected character 'z' at offset 0; \
+        bin: Invalid input data: unexpected character 'z' at offset 0"
+    );
+    // A trailing digit fails even when warnings are collected
+    let (result, warnings) = crate::warning::collect(|| decode_any(&[Hex], b"4869f", &graph));
+    assert!(result.is_err());
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_max_input_size() -> BytaryResult<()> {
+    use crate::utils::CountingReader;
+
+    let base64 = b"SGVsbG8h".repeat(256);
+    let mut graph = ConversionGraph::default();
+    graph.set_max_input_size(Some(1024));
+
+    // The decoder reads its whole input, but stops just past the limit
+    let mut input = CountingReader::new(&base64[..]);
+    let result = graph.convert(&Base64, &Bytes, &mut input, &mut Vec::new());
+    assert!(matches!(result, Err(BytaryError::InputLimitExceeded(1024))));
+    assert_eq!(input.count(), 1025);
+
+    let result = graph.convert_with_report(&Base64, &Bytes, &mut &base64[..], &mut Vec::new());
+    assert!(matches!(result, Err(BytaryError::InputLimitExceeded(1024))));
+
+    // A limit of at least the input size changes nothing
+    for limit in [base64.len() as u64, 1 << 20] {
+        graph.set_max_input_size(Some(limit));
+        let mut output = Vec::new();
+        graph.convert(&Base64, &Bytes, &mut &base64[..], &mut output)?;
+        assert_eq!(output, b"Hello!".repeat(256));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_cached_converter() -> BytaryResult<()> {
+    use std::cell::Cell;
+
+    // Appends a number that changes on every call, so it shows which outputs come from the cache
+    let calls = Rc::new(Cell::new(0));
+    let inner = {
+        let calls = calls.clone();
+        move |input: &mut dyn Read, output: &mut dyn Write| {
+            let mut data = Vec::new();
+            input.read_to_end(&mut data)?;
+            calls.set(calls.get() + 1);
+            if data == b"bad" {
+                output.write_all(b"partial")?;
+                return BytaryError::InvalidInputData("bad".into()).err();
+            }
+            output.write_all(&data)?;
+            Ok(write!(output, "#{}", calls.get())?)
+        }
+    };
+    let cached = CachedConverter::new(Rc::new(inner), 2, 4);
+    let run = |input: &[u8]| {
+        let mut output = Vec::new();
+        cached
+            .convert(&mut &input[..], &mut output)
+            .map(|_| String::from_utf8(output).unwrap())
+    };
+    assert_eq!(cached.hit_rate(), 0.0);
+
+    assert_eq!(run(b"a")?, "a#1");
+    assert_eq!(run(b"a")?, "a#1");
+    assert_eq!(run(b"")?, "#2");
+    assert_eq!(run(b"")?, "#2");
+    assert_eq!(calls.get(), 2);
+    assert_eq!(cached.hit_rate(), 0.5);
+
+    // "a" was used last, so "" is evicted for "b"
+    assert_eq!(run(b"a")?, "a#1");
+    assert_eq!(run(b"b")?, "b#3");
+    assert_eq!(cached.len(), 2);
+    assert_eq!(run(b"a")?, "a#1");
+    assert_eq!(run(b"b")?, "b#3");
+    assert_eq!(run(b"")?, "#4");
+    assert_eq!(run(b"a")?, "a#5");
+
+    // Inputs over the size limit go through in one piece and are not counted
+    let rate = cached.hit_rate();
+    assert_eq!(run(b"large")?, "large#6");
+    assert_eq!(run(b"large")?, "large#7");
+    assert_eq!(cached.hit_rate(), rate);
+
+    // Failures are never cached, and their partial output is still written
+    let mut output = Vec::new();
+    assert!(cached.convert(&mut &b"bad"[..], &mut output).is_err());
+    assert_eq!(output, b"partial");
+    assert!(run(b"bad").is_err());
+    assert_eq!(calls.get(), 9);
+
+    cached.clear();
+    assert!(cached.is_empty());
+    assert_eq!(run(b"a")?, "a#10");
+    Ok(())
+}
+
+#[test]
+fn test_cached_converter_unlimited() -> BytaryResult<()> {
+    // No input is too large to be cached
+    let cached = CachedConverter::new(Rc::new(crate::builtins::bytes_to_hex), 16, usize::MAX);
+    for _ in 0..2 {
+        let mut output = Vec::new();
+        cached.convert(&mut &b"Hi"[..], &mut output)?;
+        assert_eq!(output, b"4869");
+    }
+    assert_eq!(cached.len(), 1);
+    assert_eq!(cached.hit_rate(), 0.5);
+    Ok(())
+}
+
+#[test]
+fn test_cached_converter_warnings() {
+    let cached = CachedConverter::new(Rc::new(crate::builtins::hex_to_bytes), 16, 1024);
+    let run = || {
+        let mut output = Vec::new();
+        cached
+            .convert(&mut &b"4869f"[..], &mut output)
+            .map(|_| output)
+    };
+    let trailing = || Warning::TrailingPartial {
+        digits: 1,
+        group: 2,
+    };
+
+    let (result, warnings) = warning::collect(run);
+    assert_eq!(result.unwrap(), b"Hi");
+    assert_eq!(warnings, [trailing()]);
+    // Not replayed from the cache, so strict mode still fails and warnings are reported again
+    assert!(run().is_err());
+    let (result, warnings) = warning::collect(run);
+    assert_eq!(result.unwrap(), b"Hi");
+    assert_eq!(warnings, [trailing()]);
+    assert!(cached.is_empty());
+}
+
+/// Writes each byte of the input `factor` times, after sleeping for `millis`
+fn repeating(
+    factor: usize,
+    millis: u64,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> {
+    move |input, output| {
+        std::thread::sleep(Duration::from_millis(millis));
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        for byte in bytes {
+            output.write_all(&vec![byte; factor])?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the stages it is told about
+#[derive(Clone, Default)]
+struct StageRecorder(Rc<RefCell<Vec<StageTiming>>>);
+
+impl ConversionObserver for StageRecorder {
+    fn on_stage(&self, stage: &StageTiming) {
+        self.0.borrow_mut().push(stage.clone());
+    }
+}
+
+#[test]
+fn test_stage_timing() -> BytaryResult<()> {
+    let mut graph = ConversionGraph::new();
+    graph.add_direct(Bytes, Hex, repeating(2, 0), 1).unwrap();
+    graph.add_direct(Hex, Bin, repeating(4, 100), 1).unwrap();
+    graph.add_direct(Bin, Oct, repeating(1, 0), 1).unwrap();
+    let recorder = StageRecorder::default();
+    graph.set_observer(Box::new(recorder.clone()));
+
+    let mut context = ConversionContext::new().with_timing(true);
+    let mut output = Vec::new();
+    graph.convert_with_context(&mut context, &Bytes, &Oct, &mut &b"abc"[..], &mut output)?;
+    assert_eq!(output.len(), 24);
+
+    let stages = context.stages();
+    let hops = stages
+        .iter()
+        .map(|stage| {
+            (
+                stage.from.clone(),
+                stage.to.clone(),
+                stage.bytes_in,
+                stage.bytes_out,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        hops,
+        [(Bytes, Hex, 3, 6), (Hex, Bin, 6, 24), (Bin, Oct, 24, 24)]
+    );
+    // The sleep is attributed to the hop that slept
+    assert!(stages[1].duration >= Duration::from_millis(100));
+    assert!(stages[0].duration < Duration::from_millis(100));
+    assert!(stages[2].duration < Duration::from_millis(100));
+    assert_eq!(*recorder.0.borrow(), stages);
+
+    // The hops that ran before a failure are kept
+    graph
+        .add_direct(
+            Bin,
+            Oct,
+            |_: &mut dyn Read, _: &mut dyn Write| Err(BytaryError::InvalidInputData("bad".into())),
+            1,
+        )
+        .unwrap();
+    assert!(
+        graph
+            .convert_with_context(
+                &mut context,
+                &Bytes,
+                &Oct,
+                &mut &b"abc"[..],
+                &mut Vec::new()
+            )
+            .is_err()
+    );
+    assert_eq!(context.stages().len(), 2);
+
+    // Without timing, nothing is recorded
+    let mut context = ConversionContext::new();
+    graph.convert_with_context(
+        &mut context,
+        &Bytes,
+        &Bin,
+        &mut &b"abc"[..],
+        &mut Vec::new(),
+    )?;
+    assert!(context.stages().is_empty());
+
+    // The bytes of each hop follow the ratios of the built-in converters
+    let mut context = ConversionContext::new().with_timing(true);
+    ConversionGraph::default().convert_with_context(
+        &mut context,
+        &Bin,
+        &Oct,
+        &mut &b"0100100001101001"[..],
+        &mut Vec::new(),
+    )?;
+    let hops = context
+        .stages()
+        .iter()
+        .map(|stage| (stage.to.clone(), stage.bytes_in, stage.bytes_out))
+        .collect::<Vec<_>>();
+    assert_eq!(hops, [(Hex, 16, 4), (Bytes, 4, 2), (Oct, 2, 6)]);
+    Ok(())
+}
+
+#[test]
+fn test_output_limit() -> BytaryResult<()> {
+    let graph = ConversionGraph::default();
+    let converter = graph.get_converter(&Bytes, &Bin).unwrap();
+
+    // The converter stops soon after the limit, instead of reading all 100 MiB
+    let size = 100 << 20;
+    let mut input = CountingReader::new(std::io::repeat(0x5a).take(size));
+    let mut head = HeadWriter::new(FormattedWriter::new(Vec::new(), 8, 0), 20);
+    converter.convert(&mut input, &mut head)?;
+    assert!(head.is_full());
+    assert!(input.count() < size, "read {} bytes", input.count());
+    assert_eq!(head.into_inner().into_inner()?, b"01011010 01011010 0101");
+
+    // The limit counts the formatted output when it is outside
+    let mut input = CountingReader::new(std::io::repeat(0x5a).take(size));
+    let mut formatted = FormattedWriter::new(HeadWriter::new(Vec::new(), 20), 8, 0);
+    converter.convert(&mut input, &mut formatted)?;
+    assert!(input.count() < size, "read {} bytes", input.count());
+    assert_eq!(
+        formatted.into_inner()?.into_inner(),
+        b"01011010 01011010 01"
+    );
+
+    // Converters of a single format too
+    let mut head = HeadWriter::new(Vec::new(), 2);
+    graph
+        .get_converter(&Hex, &Hex)
+        .unwrap()
+        .convert(&mut &b"4869"[..], &mut head)?;
+    assert_eq!(head.into_inner(), b"48");
+
+    // Other converters see the error
+    let copy = ConversionGraph::get_copy_converter();
+    assert!(matches!(
+        copy.convert(&mut &b"4869"[..], &mut HeadWriter::new(Vec::new(), 2)),
+        Err(BytaryError::OutputLimitReached)
+    ));
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use crate::error::BytaryResult;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{Read, Write};
+
+#[cfg(test)]
+mod test;
+
+/// Options of [`diff_streams`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiffOptions {
+    /// Maximum number of bytes shown for each side of a range, longer ranges are elided with `..`
+    pub max_shown: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self { max_shown: 16 }
+    }
+}
+
+/// The result of [`diff_streams`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DiffSummary {
+    /// Number of differing ranges reported
+    pub ranges: usize,
+    /// Number of differing bytes, counting bytes only present in one stream
+    pub bytes_differ: u64,
+    /// Length of the old stream
+    pub old_length: u64,
+    /// Length of the new stream
+    pub new_length: u64,
+}
+
+impl DiffSummary {
+    pub fn is_identical(&self) -> bool {
+        self.ranges == 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum RangeKind {
+    /// Bytes differ in both streams
+    Changed,
+    /// Bytes only in the old stream, which is longer
+    Removed,
+    /// Bytes only in the new stream, which is longer
+    Added,
+}
+
+impl Display for RangeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RangeKind::Changed => write!(f, "changed"),
+            RangeKind::Removed => write!(f, "removed"),
+            RangeKind::Added => write!(f, "added"),
+        }
+    }
+}
+
+/// A range of differing bytes, keeping at most `max_shown` bytes of each side
+struct Range {
+    kind: RangeKind,
+    offset: u64,
+    length: u64,
+    old: Vec<u8>,
+    new: Vec<u8>,
+}
+
+impl Range {
+    fn write(&self, w: &mut dyn Write) -> io::Result<()> {
+        let side = |bytes: &[u8]| match bytes.is_empty() {
+            true => "-".to_string(),
+            false if (bytes.len() as u64) < self.length => hex::encode(bytes) + "..",
+            false => hex::encode(bytes),
+        };
+        writeln!(
+            w,
+            "{:08x} {} {}: {} => {}",
+            self.offset,
+            self.kind,
+            self.length,
+            side(&self.old),
+            side(&self.new)
+        )
+    }
+}
+
+/// Collects differing bytes into ranges and writes each range once it ends
+struct Reporter<'a> {
+    output: &'a mut dyn Write,
+    max_shown: usize,
+    current: Option<Range>,
+    summary: DiffSummary,
+}
+
+impl Reporter<'_> {
+    /// Records a differing byte at `offset`, `None` meaning the byte is missing from that stream
+    fn differ(
+        &mut self,
+        offset: u64,
+        old: Option<u8>,
+        new: Option<u8>,
+        kind: RangeKind,
+    ) -> io::Result<()> {
+        let extends = matches!(&self.current, Some(range) if range.kind == kind && range.offset + range.length == offset);
+        if !extends {
+            self.end_range()?;
+            self.current = Some(Range {
+                kind,
+                offset,
+                length: 0,
+                old: Vec::new(),
+                new: Vec::new(),
+            });
+        }
+
+        let range = self.current.as_mut().unwrap();
+        range.length += 1;
+        if range.old.len() < self.max_shown {
+            range.old.extend(old);
+        }
+        if range.new.len() < self.max_shown {
+            range.new.extend(new);
+        }
+        self.summary.bytes_differ += 1;
+        Ok(())
+    }
+
+    fn end_range(&mut self) -> io::Result<()> {
+        if let Some(range) = self.current.take() {
+            range.write(self.output)?;
+            self.summary.ranges += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Reads until `buffer` is full or the input ends, returns the number of bytes read
+fn read_full(input: &mut dyn Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match input.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(length) => filled += length,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Compares two streams byte by byte, writing one line per range of differing bytes to `output`.
+///
+/// Adjacent differing bytes are coalesced into one range. If one stream is longer, its tail is reported
+/// as a single `added` or `removed` range. Memory use is bounded regardless of the stream lengths.
+///
+/// Each line has the offset in hex, the kind of range, its length, then the old and new bytes in hex.
+///
+/// ```rust
+/// use bytary::diff::{diff_streams, DiffOptions};
+///
+/// let mut output = Vec::new();
+/// let summary = diff_streams(
+///     &mut &b"Hello"[..],
+///     &mut &b"Hallo!"[..],
+///     &mut output,
+///     &DiffOptions::default(),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(summary.ranges, 2);
+/// assert_eq!(
+///     String::from_utf8(output).unwrap(),
+///     "00000001 changed 1: 65 => 61\n00000005 added 1: - => 21\n"
+/// );
+/// ```
+pub fn diff_streams(
+    old: &mut dyn Read,
+    new: &mut dyn Read,
+    output: &mut dyn Write,
+    options: &DiffOptions,
+) -> BytaryResult<DiffSummary> {
+    let mut reporter = Reporter {
+        output,
+        max_shown: options.max_shown,
+        current: None,
+        summary: DiffSummary::default(),
+    };
+    let mut old_buffer = [0u8; 4096];
+    let mut new_buffer = [0u8; 4096];
+    let mut offset = 0u64;
+
+    loop {
+        let old_length = read_full(old, &mut old_buffer)?;
+        let new_length = read_full(new, &mut new_buffer)?;
+        if old_length == 0 && new_length == 0 {
+            break;
+        }
+
+        for i in 0..old_length.max(new_length) {
+            let a = old_buffer[..old_length].get(i).copied();
+            let b = new_buffer[..new_length].get(i).copied();
+            let kind = match (a, b) {
+                (Some(a), Some(b)) if a == b => {
+                    reporter.end_range()?;
+                    offset += 1;
+                    continue;
+                }
+                (Some(_), Some(_)) => RangeKind::Changed,
+                (Some(_), None) => RangeKind::Removed,
+                _ => RangeKind::Added,
+            };
+            reporter.differ(offset, a, b, kind)?;
+            offset += 1;
+        }
+
+        reporter.summary.old_length += old_length as u64;
+        reporter.summary.new_length += new_length as u64;
+    }
+    reporter.end_range()?;
+
+    Ok(reporter.summary)
+}
@@ -0,0 +1,85 @@
+use crate::diff::{DiffOptions, DiffSummary, diff_streams};
+
+fn diff(old: &[u8], new: &[u8], max_shown: usize) -> (DiffSummary, String) {
+    let mut output = Vec::new();
+    let summary = diff_streams(
+        &mut &old[..],
+        &mut &new[..],
+        &mut output,
+        &DiffOptions { max_shown },
+    )
+    .unwrap();
+    (summary, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_identical() {
+    let data = (0..10000).map(|i| i as u8).collect::<Vec<u8>>();
+    let (summary, output) = diff(&data, &data, 16);
+    assert!(summary.is_identical());
+    assert_eq!(summary.old_length, 10000);
+    assert_eq!(summary.new_length, 10000);
+    assert_eq!(output, "");
+
+    let (summary, output) = diff(b"", b"", 16);
+    assert!(summary.is_identical());
+    assert_eq!(output, "");
+}
+
+#[test]
+fn test_single_byte() {
+    let old = vec![0u8; 100000];
+    let mut new = old.clone();
+    new[54321] = 0xff;
+
+    let (summary, output) = diff(&old, &new, 16);
+    assert_eq!(
+        summary,
+        DiffSummary {
+            ranges: 1,
+            bytes_differ: 1,
+            old_length: 100000,
+            new_length: 100000,
+        }
+    );
+    assert_eq!(output, "0000d431 changed 1: 00 => ff\n");
+}
+
+#[test]
+fn test_coalesce() {
+    // The range crosses the 4096-byte read boundary
+    let old = vec![0u8; 8192];
+    let mut new = old.clone();
+    new[4094..4099].copy_from_slice(b"\x01\x02\x03\x04\x05");
+    new[5000] = 1;
+
+    let (summary, output) = diff(&old, &new, 4);
+    assert_eq!(summary.ranges, 2);
+    assert_eq!(summary.bytes_differ, 6);
+    assert_eq!(
+        output,
+        "00000ffe changed 5: 00000000.. => 01020304..\n00001388 changed 1: 00 => 01\n"
+    );
+}
+
+#[test]
+fn test_unequal_length() {
+    let (summary, output) = diff(b"Hello, World", b"Hello", 16);
+    assert_eq!(summary.ranges, 1);
+    assert_eq!(summary.bytes_differ, 7);
+    assert_eq!(output, "00000005 removed 7: 2c20576f726c64 => -\n");
+
+    // A change right before the tail is reported separately
+    let (summary, output) = diff(b"Hi", b"Ho!!", 16);
+    assert_eq!(summary.ranges, 2);
+    assert_eq!(summary.old_length, 2);
+    assert_eq!(summary.new_length, 4);
+    assert_eq!(
+        output,
+        "00000001 changed 1: 69 => 6f\n00000002 added 2: - => 2121\n"
+    );
+
+    let (summary, output) = diff(b"", &[7u8; 5000], 2);
+    assert_eq!(summary.ranges, 1);
+    assert_eq!(output, "00000000 added 5000: - => 0707..\n");
+}
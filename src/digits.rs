@@ -0,0 +1,214 @@
+use std::fmt::{Display, Formatter};
+
+#[cfg(test)]
+mod test;
+
+/// Value of every byte as a digit of radix 36 or less, [`NOT_A_DIGIT`] for other bytes.
+///
+/// Only ASCII digits and letters have a value. Shared by the runtime decoders and the compile time
+/// ones of the `macros` feature.
+pub(crate) const DIGIT_VALUES: [u8; 256] = {
+    let mut table = [NOT_A_DIGIT; 256];
+    let mut i = 0;
+    while i < 10 {
+        table[b'0' as usize + i] = i as u8;
+        i += 1;
+    }
+    let mut i = 0;
+    while i < 26 {
+        table[b'a' as usize + i] = 10 + i as u8;
+        table[b'A' as usize + i] = 10 + i as u8;
+        i += 1;
+    }
+    table
+};
+
+/// The value of bytes that are not digits in [`DIGIT_VALUES`]
+pub(crate) const NOT_A_DIGIT: u8 = 0xff;
+
+/// Returns the value of an ASCII digit in `radix` (at most 36), accepting both cases of letters
+///
+/// ```rust
+/// use bytary::digits::digit_value;
+///
+/// assert_eq!(digit_value(b'F', 16), Some(15));
+/// assert_eq!(digit_value(b'8', 8), None);
+/// ```
+pub const fn digit_value(digit: u8, radix: u32) -> Option<u8> {
+    let value = DIGIT_VALUES[digit as usize];
+    match (value as u32) < radix {
+        true => Some(value),
+        false => None,
+    }
+}
+
+/// Whether `byte` is an ASCII digit in `radix` (at most 36)
+pub const fn is_digit(byte: u8, radix: u32) -> bool {
+    digit_value(byte, radix).is_some()
+}
+
+/// `0` or `1`
+pub const fn is_bin_digit(byte: u8) -> bool {
+    matches!(byte, b'0' | b'1')
+}
+
+/// `0` to `7`
+pub const fn is_oct_digit(byte: u8) -> bool {
+    matches!(byte, b'0'..=b'7')
+}
+
+/// `0` to `9`
+pub const fn is_dec_digit(byte: u8) -> bool {
+    byte.is_ascii_digit()
+}
+
+/// `0` to `9`, `a` to `f` and `A` to `F`
+pub const fn is_hex_digit(byte: u8) -> bool {
+    byte.is_ascii_hexdigit()
+}
+
+/// The first code point of each run of 10 decimal digits in other scripts, with the name of the script
+const DIGIT_RUNS: [(u32, &str); 30] = [
+    (0x0660, "Arabic-Indic"),
+    (0x06f0, "Extended Arabic-Indic"),
+    (0x07c0, "NKo"),
+    (0x0966, "Devanagari"),
+    (0x09e6, "Bengali"),
+    (0x0a66, "Gurmukhi"),
+    (0x0ae6, "Gujarati"),
+    (0x0b66, "Oriya"),
+    (0x0be6, "Tamil"),
+    (0x0c66, "Telugu"),
+    (0x0ce6, "Kannada"),
+    (0x0d66, "Malayalam"),
+    (0x0de6, "Sinhala"),
+    (0x0e50, "Thai"),
+    (0x0ed0, "Lao"),
+    (0x0f20, "Tibetan"),
+    (0x1040, "Myanmar"),
+    (0x17e0, "Khmer"),
+    (0x1810, "Mongolian"),
+    (0x1946, "Limbu"),
+    (0x19d0, "New Tai Lue"),
+    (0x1b50, "Balinese"),
+    (0x2080, "subscript"),
+    (0xa8d0, "Saurashtra"),
+    (0xa900, "Kayah Li"),
+    (0x1d7ce, "mathematical bold"),
+    (0x1d7d8, "mathematical double-struck"),
+    (0x1d7e2, "mathematical sans-serif"),
+    (0x1d7ec, "mathematical sans-serif bold"),
+    (0x1d7f6, "mathematical monospace"),
+];
+
+/// A non-ASCII character that looks like an ASCII one, which decoders reject like any other
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Lookalike {
+    pub character: char,
+    /// The ASCII character that it looks like
+    pub ascii: char,
+    /// Where it comes from, like `full-width`, `superscript` or `Arabic-Indic`
+    pub kind: &'static str,
+}
+
+impl Display for Lookalike {
+    /// Like `U+FF21, full-width letter A` or `U+0663, Arabic-Indic digit 3`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let what = match self.ascii {
+            c if c.is_ascii_digit() => "digit",
+            c if c.is_ascii_alphabetic() => "letter",
+            _ => {
+                return write!(
+                    f,
+                    "U+{:04X}, {} {:?}",
+                    self.character as u32, self.kind, self.ascii
+                );
+            }
+        };
+        write!(
+            f,
+            "U+{:04X}, {} {} {}",
+            self.character as u32, self.kind, what, self.ascii
+        )
+    }
+}
+
+/// The ASCII character of a full-width form, from U+FF01 to U+FF5E, and of the ideographic space U+3000
+///
+/// ```rust
+/// use bytary::digits::fullwidth_to_ascii;
+///
+/// assert_eq!(fullwidth_to_ascii('Ａ'), Some('A'));
+/// assert_eq!(fullwidth_to_ascii('１'), Some('1'));
+/// assert_eq!(fullwidth_to_ascii('\u{3000}'), Some(' '));
+/// assert_eq!(fullwidth_to_ascii('A'), None);
+/// ```
+pub fn fullwidth_to_ascii(c: char) -> Option<char> {
+    match c {
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0),
+        '\u{3000}' => Some(' '),
+        _ => None,
+    }
+}
+
+/// Recognizes full-width forms, superscript and subscript digits and the decimal digits of other
+/// scripts, which no decoder accepts as digits.
+///
+/// ```rust
+/// use bytary::digits::lookalike;
+///
+/// assert_eq!(lookalike('٣').unwrap().to_string(), "U+0663, Arabic-Indic digit 3");
+/// assert_eq!(lookalike('Ｂ').unwrap().to_string(), "U+FF22, full-width letter B");
+/// assert_eq!(lookalike('²').unwrap().ascii, '2');
+/// assert_eq!(lookalike('é'), None);
+/// ```
+pub fn lookalike(c: char) -> Option<Lookalike> {
+    let found = |ascii, kind| {
+        Some(Lookalike {
+            character: c,
+            ascii,
+            kind,
+        })
+    };
+    if let Some(ascii) = fullwidth_to_ascii(c) {
+        return found(ascii, "full-width");
+    }
+    let superscript = match c {
+        '\u{2070}' => Some(0),
+        '\u{b9}' => Some(1),
+        '\u{b2}' => Some(2),
+        '\u{b3}' => Some(3),
+        '\u{2074}'..='\u{2079}' => Some(c as u32 - 0x2070),
+        _ => None,
+    };
+    if let Some(value) = superscript {
+        return found(char::from_digit(value, 10)?, "superscript");
+    }
+    let code = c as u32;
+    DIGIT_RUNS
+        .iter()
+        .find(|(zero, _)| (*zero..*zero + 10).contains(&code))
+        .and_then(|(zero, kind)| found(char::from_digit(code - zero, 10)?, kind))
+}
+
+/// The code point of a non-ASCII character, and what it looks like if it is a [`Lookalike`],
+/// to explain why a decoder rejects it.
+///
+/// `None` for ASCII characters, and for U+FFFD that stands for invalid UTF-8.
+///
+/// ```rust
+/// use bytary::digits::describe_non_ascii;
+///
+/// assert_eq!(describe_non_ascii('³').unwrap(), "U+00B3, superscript digit 3");
+/// assert_eq!(describe_non_ascii('é').unwrap(), "U+00E9");
+/// assert_eq!(describe_non_ascii('3'), None);
+/// ```
+pub fn describe_non_ascii(c: char) -> Option<String> {
+    if c.is_ascii() || c == char::REPLACEMENT_CHARACTER {
+        return None;
+    }
+    Some(match lookalike(c) {
+        Some(lookalike) => lookalike.to_string(),
+        None => format!("U+{:04X}", c as u32),
+    })
+}
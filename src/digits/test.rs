@@ -0,0 +1,98 @@
+use crate::digits::{
+    Lookalike, describe_non_ascii, digit_value, fullwidth_to_ascii, is_bin_digit, is_dec_digit,
+    is_digit, is_hex_digit, is_oct_digit, lookalike,
+};
+
+#[test]
+fn test_ascii_only() {
+    for byte in 0..=255u8 {
+        let c = byte as char;
+        assert_eq!(is_bin_digit(byte), c.is_digit(2), "{:?}", c);
+        assert_eq!(is_oct_digit(byte), c.is_digit(8), "{:?}", c);
+        assert_eq!(is_dec_digit(byte), c.is_ascii_digit(), "{:?}", c);
+        assert_eq!(is_hex_digit(byte), c.is_ascii_hexdigit(), "{:?}", c);
+        for radix in 2..=36 {
+            assert_eq!(
+                digit_value(byte, radix).map(u32::from),
+                c.to_digit(radix),
+                "{:?} in base {}",
+                c,
+                radix
+            );
+            assert_eq!(is_digit(byte, radix), c.is_digit(radix));
+        }
+    }
+    // Every byte of a non-ASCII character is rejected on its own
+    for c in ['٣', '３', '³', 'Ａ'] {
+        let mut buffer = [0u8; 4];
+        for &byte in c.encode_utf8(&mut buffer).as_bytes() {
+            assert!(!is_digit(byte, 36), "{:?}", c);
+        }
+    }
+}
+
+#[test]
+fn test_lookalike() {
+    let ascii = |text: &str| {
+        text.chars()
+            .map(|c| lookalike(c).map(|lookalike| lookalike.ascii))
+            .collect::<Option<String>>()
+    };
+    assert_eq!(ascii("ＡＢ１２ｆｆ"), Some("AB12ff".to_string()));
+    assert_eq!(ascii("٠١٢٣٤٥٦٧٨٩"), Some("0123456789".to_string()));
+    assert_eq!(ascii("۰۹०९০৯๐๙"), Some("09090909".to_string()));
+    assert_eq!(ascii("⁰¹²³⁴⁵⁶⁷⁸⁹"), Some("0123456789".to_string()));
+    assert_eq!(ascii("₀₉𝟎𝟗𝟿"), Some("09099".to_string()));
+    // Neither digits nor full-width
+    for c in ['é', 'ⁱ', '½', '①', 'a', '1', '\u{fffd}'] {
+        assert_eq!(lookalike(c), None, "{:?}", c);
+    }
+
+    assert_eq!(
+        lookalike('３'),
+        Some(Lookalike {
+            character: '３',
+            ascii: '3',
+            kind: "full-width",
+        })
+    );
+    assert_eq!(lookalike('٣').unwrap().kind, "Arabic-Indic");
+    assert_eq!(lookalike('¹').unwrap().kind, "superscript");
+    assert_eq!(
+        lookalike('＋').unwrap().to_string(),
+        "U+FF0B, full-width '+'"
+    );
+    assert_eq!(
+        lookalike('𝟘').unwrap().to_string(),
+        "U+1D7D8, mathematical double-struck digit 0"
+    );
+}
+
+#[test]
+fn test_fullwidth_to_ascii() {
+    for c in '\u{ff01}'..='\u{ff5e}' {
+        let ascii = fullwidth_to_ascii(c).unwrap();
+        assert!(ascii.is_ascii_graphic(), "{:?}", c);
+        assert_eq!(ascii as u32 + 0xfee0, c as u32);
+    }
+    assert_eq!(fullwidth_to_ascii('\u{3000}'), Some(' '));
+    for c in ['\u{ff00}', '\u{ff5f}', '٣', '³', 'A', ' '] {
+        assert_eq!(fullwidth_to_ascii(c), None, "{:?}", c);
+    }
+}
+
+#[test]
+fn test_describe_non_ascii() {
+    assert_eq!(
+        describe_non_ascii('Ｆ').unwrap(),
+        "U+FF26, full-width letter F"
+    );
+    assert_eq!(
+        describe_non_ascii('٧').unwrap(),
+        "U+0667, Arabic-Indic digit 7"
+    );
+    assert_eq!(describe_non_ascii('\u{a0}').unwrap(), "U+00A0");
+    assert_eq!(describe_non_ascii('😀').unwrap(), "U+1F600");
+    assert_eq!(describe_non_ascii('g'), None);
+    assert_eq!(describe_non_ascii('\u{fffd}'), None);
+}
@@ -1,26 +1,166 @@
 use crate::format::Format;
+use crate::utils::{carried_error, input_limit, is_cancellation, is_output_limit};
 use std::io;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum BytaryError {
-    #[error("Invalid Format: '{0}'")]
-    InvalidFormat(String),
+    /// An unknown format name, with the formats from [`Format::suggest`]
+    #[error("Invalid Format: '{0}'{suggestions}", suggestions = did_you_mean(.1))]
+    InvalidFormat(String, Vec<Format>),
 
     #[error("Unsupported conversion: {0} => {1}")]
     UnsupportedConversion(Format, Format),
 
+    #[error(
+        "Conversion path {} => {} takes {}, more than the maximum of {}",
+        .0,
+        .1,
+        crate::utils::plural(*.2, "step"),
+        .3
+    )]
+    PathTooLong(Format, Format, usize, usize),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
     #[error("Invalid input data: {0}")]
     InvalidInputData(String),
 
+    /// Invalid input data at a byte offset of the input, set by the decoders that read it first.
+    ///
+    /// Conversions that feed a decoder something else than their own input, like the parts but
+    /// the first of a [`ComposedConverter`](crate::convert::ComposedConverter), turn it into
+    /// [`BytaryError::InvalidInputData`], see [`BytaryError::without_offset`].
+    #[error("Invalid input data: {message}")]
+    InvalidInputAt { offset: u64, message: String },
+
+    /// Data other than whitespace after the end of the input, see [`require_eof`](crate::utils::require_eof)
+    #[error("Trailing data at offset {offset}")]
+    TrailingData { offset: u64 },
+
+    #[error("Refusing to write raw bytes to a terminal, redirect the output or use --force")]
+    TerminalOutput,
+
+    #[error("Stage {0} ({1}) failed: {2}")]
+    StageFailed(usize, String, Box<BytaryError>),
+
+    #[error("Paragraph {0} failed: {1}")]
+    ParagraphFailed(usize, Box<BytaryError>),
+
+    /// A target of a [`BroadcastWriter`](crate::utils::BroadcastWriter) failed, with its name
+    #[error("Branch {0} failed: {1}")]
+    BranchFailed(String, Box<BytaryError>),
+
+    #[error(
+        "Output buffer too small: {} needed, {} available",
+        crate::utils::plural(*.0, "byte"),
+        .1
+    )]
+    BufferTooSmall(usize, usize),
+
+    #[error("Record larger than the maximum of {}", crate::utils::plural(*.0, "byte"))]
+    RecordTooLarge(usize),
+
+    #[error("Conversion cancelled")]
+    Cancelled,
+
+    /// The output reached the limit of a [`HeadWriter`](crate::utils::HeadWriter), which the converters of
+    /// [`try_get_converter`](crate::convert::ConversionGraph::try_get_converter) treat as success
+    #[error("Output limit reached")]
+    OutputLimitReached,
+
+    /// The input is larger than the limit of a [`LimitedReader`](crate::utils::LimitedReader), in bytes
+    #[error("Input larger than the limit of {}", crate::utils::plural(*.0, "byte"))]
+    InputLimitExceeded(u64),
+
+    #[error("{} of {} failed", .0, crate::utils::plural(*.1, "file"))]
+    BatchFailed(usize, usize),
+
+    #[error("{} treated as errors", crate::utils::plural(*.0, "warning"))]
+    WarningsAsErrors(usize),
+
+    #[error("{0} of {1} format pairs failed the self-test")]
+    SelfTestFailed(usize, usize),
+
+    /// Every candidate of [`decode_any`](crate::convert::decode_any) failed, with the error of each one
+    #[error("No format decoded the input: {}", failures(.0))]
+    NoFormatDecoded(Vec<(Format, BytaryError)>),
+
     #[error("IO error: {0}")]
-    Io(#[from] io::Error),
+    Io(#[source] io::Error),
+}
+
+impl From<io::Error> for BytaryError {
+    /// Reads cancelled with a [`CancelToken`](crate::utils::CancelToken) become [`BytaryError::Cancelled`],
+    /// reads past the limit of a [`LimitedReader`](crate::utils::LimitedReader) [`BytaryError::InputLimitExceeded`],
+    /// writes past the limit of a [`HeadWriter`](crate::utils::HeadWriter) [`BytaryError::OutputLimitReached`],
+    /// and errors from [`into_io_error`](crate::utils::into_io_error) the error they carry
+    fn from(e: io::Error) -> Self {
+        if is_cancellation(&e) {
+            return BytaryError::Cancelled;
+        }
+        if is_output_limit(&e) {
+            return BytaryError::OutputLimitReached;
+        }
+        if let Some(limit) = input_limit(&e) {
+            return BytaryError::InputLimitExceeded(limit);
+        }
+        carried_error(e).unwrap_or_else(BytaryError::Io)
+    }
 }
 
 impl BytaryError {
     pub fn err<T>(self) -> BytaryResult<T> {
         Err(self)
     }
+
+    /// Returns true if this is an IO error caused by writing to a closed pipe
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, BytaryError::Io(e) if e.kind() == io::ErrorKind::BrokenPipe)
+    }
+
+    /// The offset in the input of an error about the input data, if it has one
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            BytaryError::InvalidInputAt { offset, .. } | BytaryError::TrailingData { offset } => {
+                Some(*offset)
+            }
+            _ => None,
+        }
+    }
+
+    /// Drops the [offset](BytaryError::offset) of the error, for errors about an input that is not
+    /// the one of the conversion, keeping the message
+    pub fn without_offset(self) -> Self {
+        match self {
+            BytaryError::InvalidInputAt { message, .. } => BytaryError::InvalidInputData(message),
+            BytaryError::TrailingData { .. } => BytaryError::InvalidInputData(self.to_string()),
+            e => e,
+        }
+    }
 }
 
 pub type BytaryResult<T> = Result<T, BytaryError>;
+
+/// `, did you mean 'hex'?` for the suggested formats, or nothing
+fn did_you_mean(suggestions: &[Format]) -> String {
+    let names = suggestions
+        .iter()
+        .map(|format| format!("'{}'", format))
+        .collect::<Vec<String>>();
+    match names.split_last() {
+        None => String::new(),
+        Some((last, [])) => format!(", did you mean {}?", last),
+        Some((last, rest)) => format!(", did you mean {} or {}?", rest.join(", "), last),
+    }
+}
+
+/// `hex: <error>; base64: <error>` for the failed candidates
+fn failures(failures: &[(Format, BytaryError)]) -> String {
+    failures
+        .iter()
+        .map(|(format, error)| format!("{}: {}", format, error))
+        .collect::<Vec<String>>()
+        .join("; ")
+}
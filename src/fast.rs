@@ -0,0 +1,234 @@
+use crate::digits::digit_value;
+use crate::error::{BytaryError, BytaryResult};
+use crate::validate::{InvalidInput, char_at};
+
+#[cfg(feature = "simd")]
+mod simd;
+#[cfg(test)]
+mod test;
+
+const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Fails with [`BytaryError::BufferTooSmall`] if `dst` can not hold `needed` bytes
+fn check_len(needed: usize, dst: &[u8]) -> BytaryResult<()> {
+    match dst.len() < needed {
+        true => BytaryError::BufferTooSmall(needed, dst.len()).err(),
+        false => Ok(()),
+    }
+}
+
+/// Writes every byte of `src` as `digits` digits of `bits` bits each, most significant first
+fn encode_digits(src: &[u8], dst: &mut [u8], digits: usize, bits: u32) -> BytaryResult<usize> {
+    let needed = src.len() * digits;
+    check_len(needed, dst)?;
+
+    let mask = (1u8 << bits) - 1;
+    for (byte, chunk) in src.iter().zip(dst.chunks_exact_mut(digits)) {
+        for (i, digit) in chunk.iter_mut().enumerate() {
+            let shift = bits * (digits - 1 - i) as u32;
+            *digit = DIGITS[((byte >> shift) & mask) as usize];
+        }
+    }
+    Ok(needed)
+}
+
+/// Reads every `digits` digits of `src` in `radix` as one byte
+fn decode_digits(src: &[u8], dst: &mut [u8], digits: usize, radix: u32) -> BytaryResult<usize> {
+    let needed = src.len() / digits;
+    check_len(needed, dst)?;
+
+    for (k, (group, byte)) in src.chunks_exact(digits).zip(dst.iter_mut()).enumerate() {
+        let mut value = 0u32;
+        for (i, &digit) in group.iter().enumerate() {
+            let offset = k * digits + i;
+            let digit = digit_value(digit, radix).ok_or_else(|| {
+                invalid(InvalidInput {
+                    offset,
+                    character: char_at(&src[offset..]),
+                })
+            })?;
+            value = value * radix + digit as u32;
+        }
+        *byte = u8::try_from(value).map_err(|_| BytaryError::InvalidInputAt {
+            offset: (k * digits) as u64,
+            message: format!(
+                "value {} at offset {} does not fit in a byte",
+                value,
+                k * digits
+            ),
+        })?;
+    }
+
+    if !src.len().is_multiple_of(digits) {
+        return Err(invalid(InvalidInput {
+            offset: src.len(),
+            character: None,
+        }));
+    }
+    Ok(needed)
+}
+
+/// Decodes the pairs of hex digits at the start of `src`, up to the first character that is not one,
+/// appending the bytes to `dst`. Returns the number of digits decoded.
+pub(crate) fn decode_hex_pairs(src: &[u8], dst: &mut Vec<u8>) -> usize {
+    let start = dst.len();
+    dst.resize(start + src.len() / 2, 0);
+    #[cfg(feature = "simd")]
+    let done = simd::decode_hex(src, &mut dst[start..]);
+    #[cfg(not(feature = "simd"))]
+    let done = 0;
+
+    let mut decoded = done;
+    for (pair, byte) in src[done..]
+        .chunks_exact(2)
+        .zip(dst[start + done / 2..].iter_mut())
+    {
+        match (digit_value(pair[0], 16), digit_value(pair[1], 16)) {
+            (Some(high), Some(low)) => *byte = high << 4 | low,
+            _ => break,
+        }
+        decoded += 2;
+    }
+    dst.truncate(start + decoded / 2);
+    decoded
+}
+
+fn invalid(e: InvalidInput) -> BytaryError {
+    BytaryError::InvalidInputAt {
+        offset: e.offset as u64,
+        message: e.to_string(),
+    }
+}
+
+/// Lowercase hex, 2 digits per byte.
+///
+/// With the `simd` feature, long inputs are encoded and decoded with vector instructions
+/// when the CPU supports them.
+pub mod hex {
+    use crate::error::BytaryResult;
+
+    /// Number of digits that `n` bytes are encoded to
+    pub const fn encoded_len(n: usize) -> usize {
+        n * 2
+    }
+
+    /// Number of bytes that `n` digits are decoded to
+    pub const fn decoded_len(n: usize) -> usize {
+        n / 2
+    }
+
+    /// Encodes `src` into `dst`, returning the number of digits written
+    ///
+    /// ```rust
+    /// use bytary::fast::hex;
+    ///
+    /// let mut buffer = [0u8; hex::encoded_len(2)];
+    /// assert_eq!(hex::encode_into(b"\x1b\x34", &mut buffer).unwrap(), 4);
+    /// assert_eq!(&buffer, b"1b34");
+    /// ```
+    pub fn encode_into(src: &[u8], dst: &mut [u8]) -> BytaryResult<usize> {
+        #[cfg(feature = "simd")]
+        {
+            super::check_len(encoded_len(src.len()), dst)?;
+            let done = super::simd::encode_hex(src, dst);
+            super::encode_digits(&src[done..], &mut dst[done * 2..], 2, 4)?;
+            Ok(encoded_len(src.len()))
+        }
+        #[cfg(not(feature = "simd"))]
+        super::encode_digits(src, dst, 2, 4)
+    }
+
+    /// Decodes the digits in `src` into `dst`, returning the number of bytes written
+    ///
+    /// ```rust
+    /// use bytary::fast::hex;
+    ///
+    /// let mut buffer = [0u8; 2];
+    /// assert_eq!(hex::decode_into(b"1B34", &mut buffer).unwrap(), 2);
+    /// assert_eq!(buffer, [0x1b, 0x34]);
+    ///
+    /// assert!(hex::decode_into(b"1b 34", &mut buffer).is_err());
+    /// ```
+    pub fn decode_into(src: &[u8], dst: &mut [u8]) -> BytaryResult<usize> {
+        #[cfg(feature = "simd")]
+        {
+            super::check_len(decoded_len(src.len()), dst)?;
+            let done = super::simd::decode_hex(src, dst);
+            // Errors are rare, and decoding again from the start reports their offsets
+            if super::decode_digits(&src[done..], &mut dst[done / 2..], 2, 16).is_err() {
+                return super::decode_digits(src, dst, 2, 16);
+            }
+            Ok(decoded_len(src.len()))
+        }
+        #[cfg(not(feature = "simd"))]
+        super::decode_digits(src, dst, 2, 16)
+    }
+}
+
+/// Binary, 8 digits per byte
+pub mod bin {
+    use crate::error::BytaryResult;
+
+    /// Number of digits that `n` bytes are encoded to
+    pub const fn encoded_len(n: usize) -> usize {
+        n * 8
+    }
+
+    /// Number of bytes that `n` digits are decoded to
+    pub const fn decoded_len(n: usize) -> usize {
+        n / 8
+    }
+
+    /// Encodes `src` into `dst`, returning the number of digits written
+    ///
+    /// ```rust
+    /// use bytary::fast::bin;
+    ///
+    /// let mut buffer = [0u8; bin::encoded_len(1)];
+    /// assert_eq!(bin::encode_into(b"\x1b", &mut buffer).unwrap(), 8);
+    /// assert_eq!(&buffer, b"00011011");
+    /// ```
+    pub fn encode_into(src: &[u8], dst: &mut [u8]) -> BytaryResult<usize> {
+        super::encode_digits(src, dst, 8, 1)
+    }
+
+    /// Decodes the digits in `src` into `dst`, returning the number of bytes written
+    pub fn decode_into(src: &[u8], dst: &mut [u8]) -> BytaryResult<usize> {
+        super::decode_digits(src, dst, 8, 2)
+    }
+}
+
+/// Octal, 3 digits per byte
+pub mod oct {
+    use crate::error::BytaryResult;
+
+    /// Number of digits that `n` bytes are encoded to
+    pub const fn encoded_len(n: usize) -> usize {
+        n * 3
+    }
+
+    /// Number of bytes that `n` digits are decoded to
+    pub const fn decoded_len(n: usize) -> usize {
+        n / 3
+    }
+
+    /// Encodes `src` into `dst`, returning the number of digits written
+    ///
+    /// ```rust
+    /// use bytary::fast::oct;
+    ///
+    /// let mut buffer = [0u8; oct::encoded_len(2)];
+    /// assert_eq!(oct::encode_into(b"\x0e\xff", &mut buffer).unwrap(), 6);
+    /// assert_eq!(&buffer, b"016377");
+    /// ```
+    pub fn encode_into(src: &[u8], dst: &mut [u8]) -> BytaryResult<usize> {
+        super::encode_digits(src, dst, 3, 3)
+    }
+
+    /// Decodes the digits in `src` into `dst`, returning the number of bytes written.
+    ///
+    /// Groups above `377` do not fit in a byte and are rejected.
+    pub fn decode_into(src: &[u8], dst: &mut [u8]) -> BytaryResult<usize> {
+        super::decode_digits(src, dst, 3, 8)
+    }
+}
@@ -0,0 +1,197 @@
+//! Vectorized hex kernels, chosen at runtime from the features of the CPU.
+//!
+//! Each kernel handles whole blocks at the start of its input and returns how far it got,
+//! leaving the rest to the scalar code. On CPUs without the needed features, and on other
+//! architectures than x86_64, they handle nothing.
+
+#[cfg(test)]
+mod test;
+
+/// Encodes whole blocks at the start of `src` into `dst`, which must hold twice as many bytes,
+/// returning the number of bytes encoded
+pub(super) fn encode_hex(src: &[u8], dst: &mut [u8]) -> usize {
+    debug_assert!(dst.len() >= src.len() * 2);
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // Safety: the CPU supports AVX2
+            return unsafe { x86::encode_hex_avx2(src, dst) };
+        }
+        if is_x86_feature_detected!("ssse3") {
+            // Safety: the CPU supports SSSE3
+            return unsafe { x86::encode_hex_ssse3(src, dst) };
+        }
+    }
+    0
+}
+
+/// Decodes whole blocks of hex digits at the start of `src` into `dst`, which must hold half as many bytes,
+/// stopping at the first block with any other character. Returns the number of digits decoded.
+pub(super) fn decode_hex(src: &[u8], dst: &mut [u8]) -> usize {
+    debug_assert!(dst.len() >= src.len() / 2);
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // Safety: the CPU supports AVX2
+            return unsafe { x86::decode_hex_avx2(src, dst) };
+        }
+        if is_x86_feature_detected!("ssse3") {
+            // Safety: the CPU supports SSSE3
+            return unsafe { x86::decode_hex_ssse3(src, dst) };
+        }
+    }
+    0
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+    /// 32 bytes to 64 digits per iteration
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn encode_hex_avx2(src: &[u8], dst: &mut [u8]) -> usize {
+        let table = _mm256_broadcastsi128_si256(unsafe { _mm_loadu_si128(DIGITS.as_ptr().cast()) });
+        let nibble = _mm256_set1_epi8(0x0f);
+        let blocks = src.len() / 32;
+        for block in 0..blocks {
+            let bytes = unsafe { _mm256_loadu_si256(src.as_ptr().add(block * 32).cast()) };
+            let high =
+                _mm256_shuffle_epi8(table, _mm256_and_si256(_mm256_srli_epi16(bytes, 4), nibble));
+            let low = _mm256_shuffle_epi8(table, _mm256_and_si256(bytes, nibble));
+            // Unpacking works within each 128-bit lane, so the lanes are put back in order
+            let first = _mm256_unpacklo_epi8(high, low);
+            let second = _mm256_unpackhi_epi8(high, low);
+            let out = dst.as_mut_ptr().wrapping_add(block * 64);
+            unsafe {
+                _mm256_storeu_si256(out.cast(), _mm256_permute2x128_si256(first, second, 0x20));
+                _mm256_storeu_si256(
+                    out.add(32).cast(),
+                    _mm256_permute2x128_si256(first, second, 0x31),
+                );
+            }
+        }
+        blocks * 32
+    }
+
+    /// 16 bytes to 32 digits per iteration
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn encode_hex_ssse3(src: &[u8], dst: &mut [u8]) -> usize {
+        let table = unsafe { _mm_loadu_si128(DIGITS.as_ptr().cast()) };
+        let nibble = _mm_set1_epi8(0x0f);
+        let blocks = src.len() / 16;
+        for block in 0..blocks {
+            let bytes = unsafe { _mm_loadu_si128(src.as_ptr().add(block * 16).cast()) };
+            let high = _mm_shuffle_epi8(table, _mm_and_si128(_mm_srli_epi16(bytes, 4), nibble));
+            let low = _mm_shuffle_epi8(table, _mm_and_si128(bytes, nibble));
+            let out = dst.as_mut_ptr().wrapping_add(block * 32);
+            unsafe {
+                _mm_storeu_si128(out.cast(), _mm_unpacklo_epi8(high, low));
+                _mm_storeu_si128(out.add(16).cast(), _mm_unpackhi_epi8(high, low));
+            }
+        }
+        blocks * 16
+    }
+
+    /// 64 digits to 32 bytes per iteration
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn decode_hex_avx2(src: &[u8], dst: &mut [u8]) -> usize {
+        let blocks = src.len() / 64;
+        for block in 0..blocks {
+            let digits = src.as_ptr().wrapping_add(block * 64);
+            let (first, second) = unsafe {
+                (
+                    _mm256_loadu_si256(digits.cast()),
+                    _mm256_loadu_si256(digits.add(32).cast()),
+                )
+            };
+            let (Some(first), Some(second)) = (values_avx2(first), values_avx2(second)) else {
+                return block * 64;
+            };
+            // Each pair of digits as a 16-bit integer, then packed into bytes within each lane
+            let weights = _mm256_set1_epi16(0x0110);
+            let packed = _mm256_packus_epi16(
+                _mm256_maddubs_epi16(first, weights),
+                _mm256_maddubs_epi16(second, weights),
+            );
+            let bytes = _mm256_permute4x64_epi64(packed, 0b11_01_10_00);
+            unsafe { _mm256_storeu_si256(dst.as_mut_ptr().add(block * 32).cast(), bytes) };
+        }
+        blocks * 64
+    }
+
+    /// 32 digits to 16 bytes per iteration
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn decode_hex_ssse3(src: &[u8], dst: &mut [u8]) -> usize {
+        let blocks = src.len() / 32;
+        for block in 0..blocks {
+            let digits = src.as_ptr().wrapping_add(block * 32);
+            let (first, second) = unsafe {
+                (
+                    _mm_loadu_si128(digits.cast()),
+                    _mm_loadu_si128(digits.add(16).cast()),
+                )
+            };
+            let (Some(first), Some(second)) = (values_ssse3(first), values_ssse3(second)) else {
+                return block * 32;
+            };
+            let weights = _mm_set1_epi16(0x0110);
+            let bytes = _mm_packus_epi16(
+                _mm_maddubs_epi16(first, weights),
+                _mm_maddubs_epi16(second, weights),
+            );
+            unsafe { _mm_storeu_si128(dst.as_mut_ptr().add(block * 16).cast(), bytes) };
+        }
+        blocks * 32
+    }
+
+    /// The values of 32 hex digits, or `None` if any of them is not a hex digit
+    #[target_feature(enable = "avx2")]
+    fn values_avx2(chars: __m256i) -> Option<__m256i> {
+        // Signed comparisons, which bytes from 0x80 fail as they are negative
+        let between = |v: __m256i, low: u8, high: u8| {
+            _mm256_and_si256(
+                _mm256_cmpgt_epi8(v, _mm256_set1_epi8(low as i8 - 1)),
+                _mm256_cmpgt_epi8(_mm256_set1_epi8(high as i8 + 1), v),
+            )
+        };
+        let lower = _mm256_or_si256(chars, _mm256_set1_epi8(0x20));
+        let decimal = between(chars, b'0', b'9');
+        let letter = between(lower, b'a', b'f');
+        if _mm256_movemask_epi8(_mm256_or_si256(decimal, letter)) != -1 {
+            return None;
+        }
+        Some(_mm256_or_si256(
+            _mm256_and_si256(
+                decimal,
+                _mm256_sub_epi8(chars, _mm256_set1_epi8(b'0' as i8)),
+            ),
+            _mm256_and_si256(
+                letter,
+                _mm256_sub_epi8(lower, _mm256_set1_epi8(b'a' as i8 - 10)),
+            ),
+        ))
+    }
+
+    /// The values of 16 hex digits, or `None` if any of them is not a hex digit
+    #[target_feature(enable = "ssse3")]
+    fn values_ssse3(chars: __m128i) -> Option<__m128i> {
+        let between = |v: __m128i, low: u8, high: u8| {
+            _mm_and_si128(
+                _mm_cmpgt_epi8(v, _mm_set1_epi8(low as i8 - 1)),
+                _mm_cmpgt_epi8(_mm_set1_epi8(high as i8 + 1), v),
+            )
+        };
+        let lower = _mm_or_si128(chars, _mm_set1_epi8(0x20));
+        let decimal = between(chars, b'0', b'9');
+        let letter = between(lower, b'a', b'f');
+        if _mm_movemask_epi8(_mm_or_si128(decimal, letter)) != 0xffff {
+            return None;
+        }
+        Some(_mm_or_si128(
+            _mm_and_si128(decimal, _mm_sub_epi8(chars, _mm_set1_epi8(b'0' as i8))),
+            _mm_and_si128(letter, _mm_sub_epi8(lower, _mm_set1_epi8(b'a' as i8 - 10))),
+        ))
+    }
+}
@@ -0,0 +1,55 @@
+//! Each kernel of this CPU against the scalar code, as the dispatch only ever picks one of them
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn test_x86_kernels() {
+    use crate::fast::simd::x86;
+
+    type Kernels = (
+        unsafe fn(&[u8], &mut [u8]) -> usize,
+        unsafe fn(&[u8], &mut [u8]) -> usize,
+    );
+    let mut kernels: Vec<(&str, Kernels)> = Vec::new();
+    if is_x86_feature_detected!("avx2") {
+        kernels.push(("avx2", (x86::encode_hex_avx2, x86::decode_hex_avx2)));
+    }
+    if is_x86_feature_detected!("ssse3") {
+        kernels.push(("ssse3", (x86::encode_hex_ssse3, x86::decode_hex_ssse3)));
+    }
+
+    let data = (0..=255u8).cycle().skip(3).take(1000).collect::<Vec<u8>>();
+    let digits = ::hex::encode(&data).into_bytes();
+    for (name, (encode, decode)) in kernels {
+        for length in [0, 15, 16, 31, 32, 33, 64, 1000] {
+            let mut encoded = vec![0u8; length * 2];
+            // Safety: the CPU supports the kernel
+            let done = unsafe { encode(&data[..length], &mut encoded) };
+            assert!(
+                length - done < 32,
+                "{} encoded {} of {}",
+                name,
+                done,
+                length
+            );
+            assert_eq!(encoded[..done * 2], digits[..done * 2], "{}", name);
+
+            let mut decoded = vec![0u8; length];
+            let upper = digits[..length * 2].to_ascii_uppercase();
+            let done = unsafe { decode(&upper, &mut decoded) };
+            assert!(
+                length * 2 - done < 64,
+                "{} decoded {} of {}",
+                name,
+                done,
+                length * 2
+            );
+            assert_eq!(decoded[..done / 2], data[..done / 2], "{}", name);
+        }
+
+        // A block with an invalid character is left to the scalar code
+        let mut invalid = digits[..128].to_vec();
+        invalid[100] = b'g';
+        let done = unsafe { decode(&invalid, &mut [0u8; 64]) };
+        assert!(done <= 100, "{} decoded past the invalid character", name);
+    }
+}
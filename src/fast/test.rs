@@ -0,0 +1,143 @@
+use crate::error::BytaryError;
+use crate::fast::{bin, hex, oct};
+use crate::testsupport::{ByteGenerator, corpus};
+
+#[test]
+fn test_roundtrip() {
+    let data = corpus::all_byte_values();
+    let mut encoded = [0u8; bin::encoded_len(256)];
+    let mut decoded = [0u8; 256];
+
+    type Codec = (
+        fn(usize) -> usize,
+        fn(&[u8], &mut [u8]) -> crate::error::BytaryResult<usize>,
+        fn(&[u8], &mut [u8]) -> crate::error::BytaryResult<usize>,
+    );
+    let codecs: [Codec; 3] = [
+        (hex::encoded_len, hex::encode_into, hex::decode_into),
+        (bin::encoded_len, bin::encode_into, bin::decode_into),
+        (oct::encoded_len, oct::encode_into, oct::decode_into),
+    ];
+    for (encoded_len, encode, decode) in codecs {
+        let length = encode(&data, &mut encoded).unwrap();
+        assert_eq!(length, encoded_len(data.len()));
+        assert_eq!(decode(&encoded[..length], &mut decoded).unwrap(), 256);
+        assert_eq!(decoded, data.as_slice());
+    }
+
+    assert_eq!(hex::encode_into(&data, &mut encoded).unwrap(), 512);
+    assert_eq!(&encoded[..512], ::hex::encode(&data).as_bytes());
+    assert_eq!(hex::encode_into(b"", &mut []).unwrap(), 0);
+}
+
+#[test]
+fn test_buffer_too_small() {
+    let mut buffer = [0u8; 3];
+    let err = hex::encode_into(b"Hi", &mut buffer).unwrap_err();
+    assert!(matches!(err, BytaryError::BufferTooSmall(4, 3)));
+    assert_eq!(
+        err.to_string(),
+        "Output buffer too small: 4 bytes needed, 3 available"
+    );
+
+    assert!(bin::encode_into(b"\x01", &mut [0u8; 7]).is_err());
+    assert!(oct::encode_into(b"\x01", &mut [0u8; 2]).is_err());
+    assert!(matches!(
+        hex::decode_into(b"1b34", &mut [0u8; 1]),
+        Err(BytaryError::BufferTooSmall(2, 1))
+    ));
+    // Nothing is written on error
+    let mut buffer = [7u8; 3];
+    assert!(hex::encode_into(b"Hi", &mut buffer).is_err());
+    assert_eq!(buffer, [7; 3]);
+}
+
+#[test]
+fn test_decode_invalid() {
+    let mut buffer = [0u8; 8];
+    let err = hex::decode_into(b"1b3g", &mut buffer).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: unexpected character 'g' at offset 3"
+    );
+    let err = hex::decode_into(b"1b3", &mut buffer).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: incomplete unit at end of input (offset 3)"
+    );
+    assert!(bin::decode_into(b"00000002", &mut buffer).is_err());
+    let err = oct::decode_into(b"016777", &mut buffer).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid input data: value 511 at offset 3 does not fit in a byte"
+    );
+}
+
+/// Pseudo-random bytes, the same on every run
+fn noise(length: usize, seed: u64) -> Vec<u8> {
+    ByteGenerator::new(seed).bytes(length)
+}
+
+/// Lengths around the block sizes of the vector kernels, and a large one
+fn lengths() -> impl Iterator<Item = usize> {
+    (0..=64).chain([127, 128, 129, 4096 + 17])
+}
+
+#[test]
+fn test_hex_matches_scalar() {
+    for length in lengths() {
+        let data = noise(length, length as u64);
+        let mut encoded = vec![0u8; hex::encoded_len(length)];
+        let mut expected = vec![0u8; hex::encoded_len(length)];
+        assert_eq!(hex::encode_into(&data, &mut encoded).unwrap(), length * 2);
+        super::encode_digits(&data, &mut expected, 2, 4).unwrap();
+        assert_eq!(encoded, expected, "length {}", length);
+
+        // Both cases of the letters
+        let upper = encoded.to_ascii_uppercase();
+        for digits in [&encoded, &upper] {
+            let mut decoded = vec![0u8; length];
+            assert_eq!(hex::decode_into(digits, &mut decoded).unwrap(), length);
+            assert_eq!(decoded, data, "length {}", length);
+
+            let mut pairs = Vec::new();
+            assert_eq!(super::decode_hex_pairs(digits, &mut pairs), length * 2);
+            assert_eq!(pairs, data, "length {}", length);
+        }
+    }
+}
+
+#[test]
+fn test_hex_invalid_matches_scalar() {
+    // Characters next to the ranges of digits and letters, and one with the high bit set
+    let invalid = [b'/', b':', b'@', b'G', b'`', b'g', b' ', 0xb0];
+    for length in lengths().filter(|length| *length <= 128) {
+        let digits = ::hex::encode(noise(length, 7)).into_bytes();
+        for position in 0..digits.len() {
+            for &character in &invalid {
+                let mut input = digits.clone();
+                input[position] = character;
+
+                let mut decoded = vec![0u8; length];
+                let mut expected = vec![0u8; length];
+                let error = hex::decode_into(&input, &mut decoded).unwrap_err();
+                let scalar = super::decode_digits(&input, &mut expected, 2, 16).unwrap_err();
+                assert_eq!(error.to_string(), scalar.to_string());
+                assert!(error.to_string().contains(&format!("offset {}", position)));
+
+                let mut pairs = Vec::new();
+                assert_eq!(
+                    super::decode_hex_pairs(&input, &mut pairs),
+                    position / 2 * 2
+                );
+                assert_eq!(pairs, &expected[..position / 2]);
+            }
+        }
+    }
+
+    // An odd number of digits, after whole blocks
+    let digits = ::hex::encode(noise(40, 3)) + "a";
+    let error = hex::decode_into(digits.as_bytes(), &mut [0u8; 40]).unwrap_err();
+    let scalar = super::decode_digits(digits.as_bytes(), &mut [0u8; 40], 2, 16).unwrap_err();
+    assert_eq!(error.to_string(), scalar.to_string());
+}
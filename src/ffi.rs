@@ -0,0 +1,185 @@
+use crate::convert::ConversionGraph;
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use std::ffi::{CStr, c_char};
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::slice;
+
+#[cfg(test)]
+mod test;
+
+/// The conversion succeeded
+pub const BYTARY_OK: i32 = 0;
+/// A format name is not known, see [`BytaryError::InvalidFormat`]
+pub const BYTARY_ERR_INVALID_FORMAT: i32 = 1;
+/// There is no conversion between the formats, see [`BytaryError::UnsupportedConversion`]
+pub const BYTARY_ERR_UNSUPPORTED_CONVERSION: i32 = 2;
+/// The conversion takes too many steps, see [`BytaryError::PathTooLong`]
+pub const BYTARY_ERR_PATH_TOO_LONG: i32 = 3;
+/// A pointer is null, or a format name is not UTF-8, see [`BytaryError::InvalidArgument`]
+pub const BYTARY_ERR_INVALID_ARGUMENT: i32 = 4;
+/// The input is not valid for its format, see [`BytaryError::InvalidInputData`], [`BytaryError::InvalidInputAt`]
+/// and [`BytaryError::TrailingData`]
+pub const BYTARY_ERR_INVALID_INPUT_DATA: i32 = 5;
+/// The output buffer is too small, and the needed length was written to `out_len`,
+/// see [`BytaryError::BufferTooSmall`]
+pub const BYTARY_ERR_BUFFER_TOO_SMALL: i32 = 6;
+/// The length of the output depends on the data, so [`bytary_required_capacity`] does not know it
+pub const BYTARY_ERR_UNKNOWN_SIZE: i32 = 7;
+/// Any other error, like [`BytaryError::Io`]
+pub const BYTARY_ERR_OTHER: i32 = 8;
+/// Bytary panicked, which is a bug. The panic did not cross the FFI boundary.
+pub const BYTARY_ERR_PANIC: i32 = -1;
+
+thread_local! {
+    /// The default graph, built once per thread, whose converters are cached
+    static GRAPH: ConversionGraph = ConversionGraph::default();
+}
+
+/// Converts `input_len` bytes at `input` from the format named `from` to the format named `to`,
+/// with the default [`ConversionGraph`], writing the output to `out_buf`.
+///
+/// Returns [`BYTARY_OK`] and writes the length of the output to `out_len`,
+/// or returns one of the `BYTARY_ERR_*` codes. If the output does not fit in `out_cap` bytes,
+/// nothing is written to `out_buf`, the needed length is written to `out_len`
+/// and [`BYTARY_ERR_BUFFER_TOO_SMALL`] is returned, so that the caller can retry with a larger buffer.
+///
+/// Output is not spaced or wrapped. Format names are those of [`Format::try_from`].
+///
+/// # Safety
+///
+/// `from` and `to` must be nul-terminated strings. `input` must be readable for `input_len` bytes
+/// and `out_buf` writable for `out_cap` bytes, either may be null if its length is 0.
+/// `out_len` must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bytary_convert(
+    from: *const c_char,
+    to: *const c_char,
+    input: *const u8,
+    input_len: usize,
+    out_buf: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> i32 {
+    guard(|| {
+        let (from, to) = unsafe { (format(from)?, format(to)?) };
+        let input = unsafe { input_slice(input, input_len)? };
+        if out_len.is_null() || (out_buf.is_null() && out_cap > 0) {
+            return Err(null_pointer());
+        }
+        let mut output = Vec::new();
+        GRAPH.with(|graph| {
+            graph
+                .try_get_converter(&from, &to)?
+                .convert(&mut &input[..], &mut output)
+        })?;
+
+        unsafe { *out_len = output.len() };
+        if output.len() > out_cap {
+            return Err(BytaryError::BufferTooSmall(output.len(), out_cap));
+        }
+        if !output.is_empty() {
+            let out_buf = unsafe { slice::from_raw_parts_mut(out_buf, out_cap) };
+            out_buf[..output.len()].copy_from_slice(&output);
+        }
+        Ok(BYTARY_OK)
+    })
+}
+
+/// Writes to `out_capacity` the length of the output of converting `input_len` bytes
+/// from the format named `from` to the format named `to`, as [`bytary_convert`] would.
+///
+/// Returns [`BYTARY_OK`], or [`BYTARY_ERR_UNKNOWN_SIZE`] if the length depends on the data,
+/// in which case calling [`bytary_convert`] with a capacity of 0 tells the length.
+///
+/// # Safety
+///
+/// `from` and `to` must be nul-terminated strings and `out_capacity` must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bytary_required_capacity(
+    from: *const c_char,
+    to: *const c_char,
+    input_len: usize,
+    out_capacity: *mut usize,
+) -> i32 {
+    guard(|| {
+        let (from, to) = unsafe { (format(from)?, format(to)?) };
+        if out_capacity.is_null() {
+            return Err(null_pointer());
+        }
+        let plan = GRAPH
+            .with(|graph| graph.plan(&from, &to))
+            .ok_or(BytaryError::UnsupportedConversion(from, to))?;
+        match plan
+            .output_size_hint(input_len as u64)
+            .and_then(|size| usize::try_from(size).ok())
+        {
+            Some(size) => {
+                unsafe { *out_capacity = size };
+                Ok(BYTARY_OK)
+            }
+            None => Ok(BYTARY_ERR_UNKNOWN_SIZE),
+        }
+    })
+}
+
+/// The error code of `error`
+pub fn error_code(error: &BytaryError) -> i32 {
+    match error {
+        BytaryError::InvalidFormat(..) => BYTARY_ERR_INVALID_FORMAT,
+        BytaryError::UnsupportedConversion(..) => BYTARY_ERR_UNSUPPORTED_CONVERSION,
+        BytaryError::PathTooLong(..) => BYTARY_ERR_PATH_TOO_LONG,
+        BytaryError::InvalidArgument(_) => BYTARY_ERR_INVALID_ARGUMENT,
+        BytaryError::InvalidInputData(_)
+        | BytaryError::InvalidInputAt { .. }
+        | BytaryError::TrailingData { .. }
+        | BytaryError::NoFormatDecoded(_) => BYTARY_ERR_INVALID_INPUT_DATA,
+        BytaryError::BufferTooSmall(..) => BYTARY_ERR_BUFFER_TOO_SMALL,
+        BytaryError::StageFailed(_, _, inner)
+        | BytaryError::ParagraphFailed(_, inner)
+        | BytaryError::BranchFailed(_, inner) => error_code(inner),
+        BytaryError::TerminalOutput
+        | BytaryError::RecordTooLarge(_)
+        | BytaryError::InputLimitExceeded(_)
+        | BytaryError::Cancelled
+        | BytaryError::OutputLimitReached
+        | BytaryError::BatchFailed(..)
+        | BytaryError::WarningsAsErrors(_)
+        | BytaryError::SelfTestFailed(..)
+        | BytaryError::Io(_) => BYTARY_ERR_OTHER,
+    }
+}
+
+/// Runs `f`, returning its code or the code of its error, and [`BYTARY_ERR_PANIC`] if it panics
+fn guard(f: impl FnOnce() -> BytaryResult<i32>) -> i32 {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(code)) => code,
+        Ok(Err(e)) => error_code(&e),
+        Err(_) => BYTARY_ERR_PANIC,
+    }
+}
+
+fn null_pointer() -> BytaryError {
+    BytaryError::InvalidArgument("null pointer".to_string())
+}
+
+/// Parses the nul-terminated format name at `name`
+unsafe fn format(name: *const c_char) -> BytaryResult<Format> {
+    if name.is_null() {
+        return Err(null_pointer());
+    }
+    let name = unsafe { CStr::from_ptr(name) }
+        .to_str()
+        .map_err(|_| BytaryError::InvalidArgument("format name is not UTF-8".to_string()))?;
+    Format::try_from(name)
+}
+
+/// The `len` bytes at `data`, which may be null if `len` is 0
+unsafe fn input_slice<'a>(data: *const u8, len: usize) -> BytaryResult<&'a [u8]> {
+    match (data.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => Err(null_pointer()),
+        (false, _) => Ok(unsafe { slice::from_raw_parts(data, len) }),
+    }
+}
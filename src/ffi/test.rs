@@ -0,0 +1,171 @@
+use crate::error::BytaryError;
+use crate::ffi::*;
+use std::ffi::CString;
+use std::ptr;
+
+/// Calls [`bytary_convert`] with an output buffer of `cap` bytes, returning the code and the output
+fn convert(from: &str, to: &str, input: &[u8], cap: usize) -> (i32, usize, Vec<u8>) {
+    let (from, to) = (CString::new(from).unwrap(), CString::new(to).unwrap());
+    let mut buffer = vec![0u8; cap];
+    let mut length = usize::MAX;
+    let code = unsafe {
+        bytary_convert(
+            from.as_ptr(),
+            to.as_ptr(),
+            input.as_ptr(),
+            input.len(),
+            buffer.as_mut_ptr(),
+            cap,
+            &mut length,
+        )
+    };
+    (code, length, buffer)
+}
+
+fn required_capacity(from: &str, to: &str, input_len: usize) -> (i32, usize) {
+    let (from, to) = (CString::new(from).unwrap(), CString::new(to).unwrap());
+    let mut capacity = usize::MAX;
+    let code =
+        unsafe { bytary_required_capacity(from.as_ptr(), to.as_ptr(), input_len, &mut capacity) };
+    (code, capacity)
+}
+
+#[test]
+fn test_convert() {
+    let (code, length, buffer) = convert("bytes", "hex", b"Hi!", 8);
+    assert_eq!(code, BYTARY_OK);
+    assert_eq!(&buffer[..length], b"486921");
+    // The rest of the buffer is left alone
+    assert_eq!(&buffer[length..], [0, 0]);
+
+    let (code, length, buffer) = convert("hex", "base64", b"48 69 21", 4);
+    assert_eq!(code, BYTARY_OK);
+    assert_eq!(&buffer[..length], b"SGkh");
+
+    let (code, length, _) = convert("hex", "bytes", b"", 0);
+    assert_eq!((code, length), (BYTARY_OK, 0));
+}
+
+#[test]
+fn test_buffer_too_small() {
+    let (code, length, buffer) = convert("bytes", "hex", b"Hi!", 5);
+    assert_eq!(code, BYTARY_ERR_BUFFER_TOO_SMALL);
+    assert_eq!(length, 6);
+    assert_eq!(buffer, [0; 5]);
+
+    // A capacity of 0 with a null buffer asks for the length
+    let (from, to) = (CString::new("bytes").unwrap(), CString::new("hex").unwrap());
+    let mut length = 0;
+    let code = unsafe {
+        bytary_convert(
+            from.as_ptr(),
+            to.as_ptr(),
+            b"Hi".as_ptr(),
+            2,
+            ptr::null_mut(),
+            0,
+            &mut length,
+        )
+    };
+    assert_eq!((code, length), (BYTARY_ERR_BUFFER_TOO_SMALL, 4));
+}
+
+#[test]
+fn test_error_codes() {
+    assert_eq!(
+        convert("hexx", "bytes", b"00", 8).0,
+        BYTARY_ERR_INVALID_FORMAT
+    );
+    assert_eq!(
+        convert("bytes", "nonsense", b"00", 8).0,
+        BYTARY_ERR_INVALID_FORMAT
+    );
+    assert_eq!(
+        convert("hex", "bytes", b"4g", 8).0,
+        BYTARY_ERR_INVALID_INPUT_DATA
+    );
+    assert_eq!(
+        convert("stats", "bytes", b"", 8).0,
+        BYTARY_ERR_UNSUPPORTED_CONVERSION
+    );
+
+    let hex = CString::new("hex").unwrap();
+    let mut length = 0;
+    let code = unsafe {
+        bytary_convert(
+            ptr::null(),
+            hex.as_ptr(),
+            ptr::null(),
+            0,
+            ptr::null_mut(),
+            0,
+            &mut length,
+        )
+    };
+    assert_eq!(code, BYTARY_ERR_INVALID_ARGUMENT);
+    let code = unsafe {
+        bytary_convert(
+            hex.as_ptr(),
+            hex.as_ptr(),
+            ptr::null(),
+            4,
+            ptr::null_mut(),
+            0,
+            &mut length,
+        )
+    };
+    assert_eq!(code, BYTARY_ERR_INVALID_ARGUMENT);
+    let code = unsafe {
+        bytary_convert(
+            hex.as_ptr(),
+            hex.as_ptr(),
+            ptr::null(),
+            0,
+            ptr::null_mut(),
+            0,
+            ptr::null_mut(),
+        )
+    };
+    assert_eq!(code, BYTARY_ERR_INVALID_ARGUMENT);
+
+    assert_eq!(
+        error_code(&BytaryError::StageFailed(
+            1,
+            "hex_to_bytes".to_string(),
+            Box::new(BytaryError::InvalidInputData(String::new()))
+        )),
+        BYTARY_ERR_INVALID_INPUT_DATA
+    );
+}
+
+#[test]
+fn test_required_capacity() {
+    assert_eq!(required_capacity("bytes", "hex", 3), (BYTARY_OK, 6));
+    assert_eq!(required_capacity("hex", "base64", 6), (BYTARY_OK, 4));
+    assert_eq!(required_capacity("bytes", "bytes", 3), (BYTARY_OK, 3));
+    assert_eq!(
+        required_capacity("base64", "bytes", 4).0,
+        BYTARY_ERR_UNKNOWN_SIZE
+    );
+    assert_eq!(
+        required_capacity("bytes", "hexx", 4).0,
+        BYTARY_ERR_INVALID_FORMAT
+    );
+    assert_eq!(
+        required_capacity("stats", "bytes", 4).0,
+        BYTARY_ERR_UNSUPPORTED_CONVERSION
+    );
+
+    // The capacity is enough for the conversion
+    let input = b"Hello, World!";
+    let (_, capacity) = required_capacity("bytes", "oct", input.len());
+    let (code, length, _) = convert("bytes", "oct", input, capacity);
+    assert_eq!((code, length), (BYTARY_OK, capacity));
+}
+
+#[test]
+fn test_panic() {
+    // The panic hook still prints the message, but the panic is caught
+    assert_eq!(guard(|| panic!("caught")), BYTARY_ERR_PANIC);
+    assert_eq!(guard(|| Ok(BYTARY_OK)), BYTARY_OK);
+}
@@ -1,46 +1,443 @@
+use crate::builtins;
+use crate::builtins::Padding;
+use crate::convert::ConversionGraph;
 use crate::error::{BytaryError, BytaryResult};
+use crate::leb128::Leb128Options;
+use crate::text::TextOptions;
+use clap::ValueEnum;
+use clap::builder::PossibleValue;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::LazyLock;
 use strum::EnumIter;
 
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, EnumIter)]
+#[cfg(test)]
+mod test;
+
+/// A format of binary data.
+///
+/// Everything else known about a format, like its name and built-in conversions,
+/// is declared once in its [`FormatSpec`] in [`FORMATS`].
+///
+/// Formats are ordered as they are declared here, which is the order of every listing,
+/// like `--list-formats`, [`Capabilities`](crate::convert::Capabilities) and
+/// [`ConversionGraph::edges`](crate::convert::ConversionGraph::edges).
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, EnumIter)]
 pub enum Format {
+    #[default]
     Bytes,
     Bin,
+    /// Hex digits, also named `base16`
     Hex,
+    /// Hex with the low nibble of each byte first, as some hardware tools write it
+    HexNibbleSwapped,
     Oct,
-    Base32,
+    /// Decimal digits of a whole number, which only [`numeric`](crate::numeric) converts
+    Dec,
+    /// Unsigned or signed LEB128 integers, as in WebAssembly and protobuf varints
+    Leb128,
+    /// Hex lines of literal bytes, and `ff x 4096` lines for runs of the same byte
+    Rle,
     Base64,
+    /// Base64 between `-----BEGIN <label>-----` and `-----END <label>-----` lines
+    Pem,
+    /// Text encoded as UTF-8
+    Utf8,
+    /// Text encoded as little-endian UTF-16
+    Utf16Le,
+    /// Text encoded as big-endian UTF-16
+    Utf16Be,
+    /// The Unicode scalar values of UTF-8 text, as `U+0041 U+1F600` tokens
+    Codepoints,
+    /// A report of byte frequencies and entropy, which can only be encoded
+    Stats,
+    /// A report of which byte values occur, which can only be encoded
+    ByteSet,
 }
-impl Default for Format {
-    fn default() -> Self {
-        Format::Bytes
+
+impl Format {
+    /// The name of the format, as accepted by [`Format::try_from`]
+    pub fn name(&self) -> &'static str {
+        self.spec().name
+    }
+
+    /// The declaration of the format in [`FORMATS`]
+    ///
+    /// ```rust
+    /// use bytary::format::Format;
+    ///
+    /// assert_eq!(Format::Hex.spec().aliases, ["base16"]);
+    /// ```
+    pub fn spec(&self) -> &'static FormatSpec {
+        FORMATS.iter().find(|spec| spec.format == *self).unwrap()
+    }
+
+    /// How many characters encoding to this format writes for how many bytes, as `(chars, bytes)`.
+    ///
+    /// `None` for formats that are not written as fixed-width groups of characters.
+    ///
+    /// ```rust
+    /// use bytary::format::Format;
+    ///
+    /// assert_eq!(Format::Hex.chars_per_byte(), Some((2, 1)));
+    /// assert_eq!(Format::Base64.chars_per_byte(), Some((4, 3)));
+    /// assert_eq!(Format::Bytes.chars_per_byte(), None);
+    /// ```
+    pub fn chars_per_byte(&self) -> Option<(usize, usize)> {
+        self.spec().chars_per_byte
+    }
+
+    /// The exact length of encoding `input_len` bytes to this format with the built-in converters,
+    /// before any spacing or line wrapping.
+    ///
+    /// `None` if the length depends on the data, like for UTF-16, or is not known.
+    ///
+    /// ```rust
+    /// use bytary::format::Format;
+    ///
+    /// assert_eq!(Format::Hex.encoded_size_hint(5), Some(10));
+    /// assert_eq!(Format::Base64.encoded_size_hint(5), Some(8));
+    /// assert_eq!(Format::Utf16Le.encoded_size_hint(5), None);
+    /// ```
+    pub fn encoded_size_hint(&self, input_len: u64) -> Option<u64> {
+        match self.spec() {
+            FormatSpec { verbatim: true, .. } => Some(input_len),
+            FormatSpec {
+                chars_per_byte: Some((chars, bytes)),
+                ..
+            } => Some(input_len.div_ceil(*bytes as u64) * *chars as u64),
+            _ => None,
+        }
+    }
+
+    /// The length of the bytes decoded from `input_len` characters of this format,
+    /// assuming they are digits only, as counted by [`Format::encoded_size_hint`].
+    ///
+    /// `None` if the length depends on the data, like for padded base64, or is not known.
+    pub fn decoded_size_hint(&self, input_len: u64) -> Option<u64> {
+        match self.spec() {
+            FormatSpec { verbatim: true, .. } => Some(input_len),
+            FormatSpec {
+                chars_per_byte: Some((chars, 1)),
+                ..
+            } => Some(input_len / *chars as u64),
+            _ => None,
+        }
+    }
+
+    /// The formats whose name or alias is closest to a mistyped `name`, at most 2 edits away, in declaration order.
+    ///
+    /// Several formats are returned when they are equally close.
+    ///
+    /// ```rust
+    /// use bytary::format::Format;
+    ///
+    /// assert_eq!(Format::suggest("hexx"), [Format::Hex]);
+    /// assert_eq!(Format::suggest("bse16"), [Format::Hex]);
+    /// assert_eq!(Format::suggest("utf16"), [Format::Utf8, Format::Utf16Le, Format::Utf16Be]);
+    /// assert!(Format::suggest("nonsense").is_empty());
+    /// ```
+    pub fn suggest(name: &str) -> Vec<Format> {
+        let name = name.to_lowercase();
+        let distances = FORMATS
+            .iter()
+            .filter_map(|spec| {
+                let names = std::iter::once(&spec.name).chain(spec.aliases);
+                let distance = names.map(|alias| edit_distance(&name, alias)).min()?;
+                Some((&spec.format, distance))
+            })
+            .filter(|&(_, distance)| distance <= 2)
+            .collect::<Vec<(&Format, usize)>>();
+        let closest = distances.iter().map(|&(_, distance)| distance).min();
+        distances
+            .into_iter()
+            .filter(|&(_, distance)| Some(distance) == closest)
+            .map(|(format, _)| format.clone())
+            .collect()
+    }
+
+    /// Whether the format only carries text, so that only bytes which are valid UTF-8
+    /// can be converted to it.
+    pub fn is_text(&self) -> bool {
+        self.spec().text
+    }
+
+    /// Whether the format is written as UTF-8, so that spaces and line breaks must not split its characters.
+    ///
+    /// Every text format but UTF-16, and every format written with ASCII characters.
+    pub fn is_utf8(&self) -> bool {
+        self.spec().utf8
+    }
+}
+
+/// What the library knows about a [`Format`], declared once in [`FORMATS`].
+#[derive(Debug)]
+pub struct FormatSpec {
+    pub format: Format,
+    /// The name of the format, as printed and accepted by [`Format::try_from`]
+    pub name: &'static str,
+    /// Other names accepted by [`Format::try_from`]
+    pub aliases: &'static [&'static str],
+    /// See [`Format::chars_per_byte`]
+    pub chars_per_byte: Option<(usize, usize)>,
+    /// Encoding writes the bytes unchanged, so that encoded and decoded lengths are equal
+    pub verbatim: bool,
+    /// See [`Format::is_text`]
+    pub text: bool,
+    /// See [`Format::is_utf8`]
+    pub utf8: bool,
+    /// Adds the built-in conversions of the format to a graph whose decoders handle
+    /// incomplete input according to the [`Padding`], `None` for a format without any.
+    ///
+    /// A conversion between two formats is registered by only one of them.
+    pub register: Option<fn(&mut ConversionGraph, Padding)>,
+}
+
+/// Every format, in the order they are declared in [`Format`]
+pub static FORMATS: &[FormatSpec] = &[
+    FormatSpec {
+        format: Format::Bytes,
+        name: "bytes",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: true,
+        text: false,
+        utf8: false,
+        register: None,
+    },
+    FormatSpec {
+        format: Format::Bin,
+        name: "bin",
+        aliases: &[],
+        chars_per_byte: Some((8, 1)),
+        verbatim: false,
+        text: false,
+        utf8: true,
+        register: Some(builtins::add_bin_edges),
+    },
+    FormatSpec {
+        format: Format::Hex,
+        name: "hex",
+        aliases: &["base16"],
+        chars_per_byte: Some((2, 1)),
+        verbatim: false,
+        text: false,
+        utf8: true,
+        register: Some(builtins::add_hex_edges),
+    },
+    FormatSpec {
+        format: Format::HexNibbleSwapped,
+        name: "hexswap",
+        aliases: &[],
+        chars_per_byte: Some((2, 1)),
+        verbatim: false,
+        text: false,
+        utf8: true,
+        register: Some(builtins::add_hexswap_edges),
+    },
+    FormatSpec {
+        format: Format::Oct,
+        name: "oct",
+        aliases: &[],
+        chars_per_byte: Some((3, 1)),
+        verbatim: false,
+        text: false,
+        utf8: true,
+        register: Some(builtins::add_oct_edges),
+    },
+    FormatSpec {
+        format: Format::Dec,
+        name: "dec",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: false,
+        text: false,
+        utf8: true,
+        register: None,
+    },
+    FormatSpec {
+        format: Format::Leb128,
+        name: "leb128",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: false,
+        text: false,
+        utf8: false,
+        register: Some(|graph, _| graph.add_leb128_edges(Leb128Options::default())),
+    },
+    FormatSpec {
+        format: Format::Rle,
+        name: "rle",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: false,
+        text: false,
+        utf8: true,
+        register: Some(builtins::add_rle_edges),
+    },
+    FormatSpec {
+        format: Format::Base64,
+        name: "base64",
+        aliases: &[],
+        chars_per_byte: Some((4, 3)),
+        verbatim: false,
+        text: false,
+        utf8: true,
+        register: Some(builtins::add_base64_edges),
+    },
+    FormatSpec {
+        format: Format::Pem,
+        name: "pem",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: false,
+        text: false,
+        utf8: true,
+        register: Some(builtins::add_pem_edges),
+    },
+    FormatSpec {
+        format: Format::Utf8,
+        name: "utf8",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: true,
+        text: true,
+        utf8: true,
+        register: Some(|graph, _| graph.add_utf8_edges(&TextOptions::default())),
+    },
+    FormatSpec {
+        format: Format::Utf16Le,
+        name: "utf16le",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: false,
+        text: true,
+        utf8: false,
+        register: Some(|graph, _| graph.add_utf16_edges(Format::Utf16Le, &TextOptions::default())),
+    },
+    FormatSpec {
+        format: Format::Utf16Be,
+        name: "utf16be",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: false,
+        text: true,
+        utf8: false,
+        register: Some(|graph, _| graph.add_utf16_edges(Format::Utf16Be, &TextOptions::default())),
+    },
+    FormatSpec {
+        format: Format::Codepoints,
+        name: "codepoints",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: false,
+        text: true,
+        utf8: true,
+        register: Some(|graph, _| graph.add_codepoints_edges(&TextOptions::default())),
+    },
+    FormatSpec {
+        format: Format::Stats,
+        name: "stats",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: false,
+        text: false,
+        utf8: true,
+        register: Some(builtins::add_stats_edges),
+    },
+    FormatSpec {
+        format: Format::ByteSet,
+        name: "byteset",
+        aliases: &[],
+        chars_per_byte: None,
+        verbatim: false,
+        text: false,
+        utf8: true,
+        register: Some(builtins::add_byteset_edges),
+    },
+];
+
+/// The Levenshtein distance between `a` and `b`, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    // Distances from the part of `a` read so far to every prefix of `b`
+    let mut row = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
     }
+    row[b.len()]
 }
 
 impl TryFrom<&str> for Format {
     type Error = BytaryError;
     fn try_from(name: &str) -> BytaryResult<Self> {
-        match name.to_lowercase().as_str() {
-            "bytes" => Ok(Format::Bytes),
-            "bin" => Ok(Format::Bin),
-            "hex" => Ok(Format::Hex),
-            "oct" => Ok(Format::Oct),
-            "base32" => Ok(Format::Base32),
-            "base64" => Ok(Format::Base64),
-            _ => Err(BytaryError::InvalidFormat(name.to_string())),
-        }
+        let lower = name.to_lowercase();
+        FORMATS
+            .iter()
+            .find(|spec| spec.name == lower || spec.aliases.contains(&lower.as_str()))
+            .map(|spec| spec.format.clone())
+            .ok_or_else(|| BytaryError::InvalidFormat(name.to_string(), Format::suggest(name)))
+    }
+}
+
+impl TryFrom<String> for Format {
+    type Error = BytaryError;
+    fn try_from(name: String) -> BytaryResult<Self> {
+        Format::try_from(name.as_str())
+    }
+}
+
+impl TryFrom<&String> for Format {
+    type Error = BytaryError;
+    fn try_from(name: &String) -> BytaryResult<Self> {
+        Format::try_from(name.as_str())
+    }
+}
+
+impl FromStr for Format {
+    type Err = BytaryError;
+    fn from_str(name: &str) -> BytaryResult<Self> {
+        Format::try_from(name)
     }
 }
 
 impl Display for Format {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Format::Bytes => write!(f, "bytes"),
-            Format::Bin => write!(f, "bin"),
-            Format::Hex => write!(f, "hex"),
-            Format::Oct => write!(f, "oct"),
-            Format::Base32 => write!(f, "base32"),
-            Format::Base64 => write!(f, "base64"),
-        }
+        f.write_str(self.name())
+    }
+}
+
+/// Lets clap parse arguments as formats and list the possible values in help and completions
+impl ValueEnum for Format {
+    fn value_variants<'a>() -> &'a [Self] {
+        static ALL: LazyLock<Vec<Format>> =
+            LazyLock::new(|| FORMATS.iter().map(|spec| spec.format.clone()).collect());
+        &ALL
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let spec = self.spec();
+        Some(PossibleValue::new(spec.name).aliases(spec.aliases))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Format {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Format {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Format::try_from(name.as_str()).map_err(serde::de::Error::custom)
     }
 }
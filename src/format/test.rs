@@ -0,0 +1,139 @@
+use crate::format::Format;
+use clap::ValueEnum;
+use strum::IntoEnumIterator;
+
+#[test]
+fn test_name_roundtrip() {
+    for format in Format::iter() {
+        assert_eq!(
+            Format::try_from(format.to_string().as_str()).unwrap(),
+            format
+        );
+    }
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!("HEX".parse::<Format>().unwrap(), Format::Hex);
+    assert_eq!(Format::try_from("pem".to_string()).unwrap(), Format::Pem);
+    assert_eq!(Format::try_from(&"bin".to_string()).unwrap(), Format::Bin);
+    assert_eq!(
+        "base65".parse::<Format>().unwrap_err().to_string(),
+        "Invalid Format: 'base65', did you mean 'base64'?"
+    );
+
+    // Every format is offered to clap, in the same order
+    assert!(Format::value_variants().iter().cloned().eq(Format::iter()));
+    assert_eq!(Format::from_str("Base64", true).unwrap(), Format::Base64);
+
+    assert_eq!("base16".parse::<Format>().unwrap(), Format::Hex);
+    assert_eq!(Format::from_str("BASE16", true).unwrap(), Format::Hex);
+    assert_eq!(
+        "hexswap".parse::<Format>().unwrap(),
+        Format::HexNibbleSwapped
+    );
+}
+
+#[test]
+fn test_suggest() {
+    use crate::error::BytaryError;
+    use crate::format::Format::*;
+
+    assert_eq!(Format::suggest("hexx"), [Hex]);
+    assert_eq!(Format::suggest("bse64"), [Base64]);
+    assert_eq!(Format::suggest("byte"), [Bytes]);
+    // Equally close formats are all suggested
+    assert_eq!(Format::suggest("utf16"), [Utf8, Utf16Le, Utf16Be]);
+    // Aliases count as their format: base16 is one edit away, base64 two
+    assert_eq!(Format::suggest("BASE46"), [Hex]);
+    assert_eq!(Format::suggest("base16x"), [Hex]);
+    assert!(Format::suggest("qwertyuiop").is_empty());
+
+    let message = |name: &str| Format::try_from(name).unwrap_err().to_string();
+    assert_eq!(
+        message("hexx"),
+        "Invalid Format: 'hexx', did you mean 'hex'?"
+    );
+    assert_eq!(
+        message("utf16"),
+        "Invalid Format: 'utf16', did you mean 'utf8', 'utf16le' or 'utf16be'?"
+    );
+    assert_eq!(message("qwertyuiop"), "Invalid Format: 'qwertyuiop'");
+    assert!(matches!(
+        Format::try_from("byte"),
+        Err(BytaryError::InvalidFormat(name, suggestions)) if name == "byte" && suggestions == [Bytes]
+    ));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serde_roundtrip() {
+    for format in Format::iter() {
+        let json = serde_json::to_string(&format).unwrap();
+        assert_eq!(json, format!("\"{}\"", format));
+        assert_eq!(serde_json::from_str::<Format>(&json).unwrap(), format);
+    }
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serde_unknown_name() {
+    let err = serde_json::from_str::<Format>("\"base65\"").unwrap_err();
+    assert!(err.to_string().contains("base65"));
+    assert!(err.to_string().contains("did you mean 'base64'?"));
+}
+
+#[test]
+fn test_specs() {
+    use crate::convert::ConversionGraph;
+    use crate::format::FORMATS;
+
+    // Every format is declared exactly once, in the order of the enum
+    assert!(
+        FORMATS
+            .iter()
+            .map(|spec| spec.format.clone())
+            .eq(Format::iter())
+    );
+    for format in Format::iter() {
+        assert_eq!(format.spec().format, format);
+    }
+
+    // No name or alias is taken twice
+    let mut names = FORMATS
+        .iter()
+        .flat_map(|spec| spec.aliases.iter().copied().chain([spec.name]))
+        .collect::<Vec<&str>>();
+    let count = names.len();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), count);
+
+    // Every format offered to the CLI has conversions, whether it registers them or not
+    let graph = ConversionGraph::default();
+    for spec in FORMATS {
+        assert!(
+            Format::value_variants().contains(&spec.format),
+            "{}",
+            spec.name
+        );
+        assert!(
+            graph
+                .edges()
+                .any(|(from, to, _, _)| *from == spec.format || *to == spec.format),
+            "{}",
+            spec.name
+        );
+    }
+
+    // Formats declared as UTF-8 are written as UTF-8
+    let text = "Hi é 😀".as_bytes();
+    for spec in FORMATS.iter().filter(|spec| spec.utf8) {
+        let Some(converter) = graph.get_converter(&Format::Bytes, &spec.format) else {
+            continue;
+        };
+        let mut output = Vec::new();
+        converter.convert(&mut &text[..], &mut output).unwrap();
+        assert!(std::str::from_utf8(&output).is_ok(), "{}", spec.name);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::convert::ConversionGraph;
+use std::fmt::{Display, Formatter};
+
+#[cfg(test)]
+mod test;
+
+/// The optional cargo features of the crate
+pub const FEATURES: [&str; 9] = [
+    "async",
+    "config",
+    "ffi",
+    "json",
+    "macros",
+    "mmap",
+    "serde",
+    "simd",
+    "test-util",
+];
+
+/// How this build of the crate was made, from [`build_info`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BuildInfo {
+    /// The crate version, like `0.2.0`
+    pub version: String,
+    /// `git describe` of the checkout it was built from, `None` outside of one
+    pub git_describe: Option<String>,
+    /// The enabled features among [`FEATURES`]
+    pub features: Vec<String>,
+    /// Number of formats that the default [`ConversionGraph`] converts between
+    pub formats: usize,
+    /// Number of direct conversions in the default [`ConversionGraph`]
+    pub conversions: usize,
+}
+
+/// Describes this build of the crate, for diagnostics.
+///
+/// ```rust
+/// let info = bytary::build_info();
+/// assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+/// assert!(info.conversions > 0);
+/// println!("{}", info);
+/// ```
+pub fn build_info() -> BuildInfo {
+    let enabled = [
+        cfg!(feature = "async"),
+        cfg!(feature = "config"),
+        cfg!(feature = "ffi"),
+        cfg!(feature = "json"),
+        cfg!(feature = "macros"),
+        cfg!(feature = "mmap"),
+        cfg!(feature = "serde"),
+        cfg!(feature = "simd"),
+        cfg!(feature = "test-util"),
+    ];
+    let capabilities = ConversionGraph::default().capabilities();
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_describe: option_env!("BYTARY_GIT_DESCRIBE").map(str::to_string),
+        features: FEATURES
+            .iter()
+            .zip(enabled)
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        formats: capabilities.formats.len(),
+        conversions: capabilities.conversions.len(),
+    }
+}
+
+/// Several lines, listing every feature with `+` if it is enabled and `-` if not
+impl Display for BuildInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "bytary {}", self.version)?;
+        if let Some(describe) = &self.git_describe {
+            write!(f, " ({})", describe)?;
+        }
+        let features = FEATURES
+            .iter()
+            .map(
+                |name| match self.features.iter().any(|enabled| enabled == name) {
+                    true => format!("+{}", name),
+                    false => format!("-{}", name),
+                },
+            )
+            .collect::<Vec<String>>();
+        writeln!(f)?;
+        writeln!(f, "Features: {}", features.join(" "))?;
+        writeln!(f, "Formats: {}", self.formats)?;
+        write!(f, "Conversions: {}", self.conversions)
+    }
+}
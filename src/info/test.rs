@@ -0,0 +1,38 @@
+use crate::info::{BuildInfo, FEATURES, build_info};
+
+#[test]
+fn test_build_info() {
+    let info = build_info();
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    assert!(
+        info.features
+            .iter()
+            .all(|name| FEATURES.contains(&name.as_str()))
+    );
+    assert_eq!(
+        info.features.contains(&"simd".to_string()),
+        cfg!(feature = "simd")
+    );
+    assert!(info.formats >= 10, "{}", info.formats);
+    assert!(info.conversions >= info.formats, "{}", info.conversions);
+    assert!(
+        info.git_describe
+            .as_ref()
+            .is_none_or(|describe| !describe.is_empty())
+    );
+
+    let info = BuildInfo {
+        version: "1.2.3".into(),
+        git_describe: Some("v1.2.3-4-gabcdef0".into()),
+        features: vec!["mmap".into(), "simd".into()],
+        formats: 17,
+        conversions: 80,
+    };
+    assert_eq!(
+        info.to_string(),
+        "bytary 1.2.3 (v1.2.3-4-gabcdef0)\n\
+        Features: -async -config -ffi -json -macros +mmap -serde +simd -test-util\n\
+        Formats: 17\n\
+        Conversions: 80"
+    );
+}
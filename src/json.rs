@@ -0,0 +1,136 @@
+use crate::error::BytaryError;
+use serde_json::Value;
+use thiserror::Error;
+
+#[cfg(test)]
+mod test;
+
+/// Why a string could not be extracted from, or wrapped into, a JSON document
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum JsonError {
+    #[error("invalid JSON pointer '{0}': {1}")]
+    InvalidPointer(String, &'static str),
+
+    #[error("invalid JSON: {0}")]
+    InvalidJson(String),
+
+    #[error("JSON pointer '{0}' not found")]
+    PointerNotFound(String),
+
+    /// The value at the pointer, with its kind like `a number`
+    #[error("JSON value at '{pointer}' is {found}, not a string")]
+    NotAString {
+        pointer: String,
+        found: &'static str,
+    },
+}
+
+impl From<JsonError> for BytaryError {
+    /// An invalid pointer is an [`InvalidArgument`](BytaryError::InvalidArgument),
+    /// the other errors are about the input
+    fn from(e: JsonError) -> Self {
+        match e {
+            JsonError::InvalidPointer(..) => BytaryError::InvalidArgument(e.to_string()),
+            _ => BytaryError::InvalidInputData(e.to_string()),
+        }
+    }
+}
+
+/// Splits a JSON Pointer (RFC 6901) like `/data/0/blob` into its unescaped reference tokens.
+///
+/// The empty pointer refers to the whole document and has no tokens.
+///
+/// ```rust
+/// use bytary::json::parse_pointer;
+///
+/// assert_eq!(parse_pointer("/data/0/blob").unwrap(), ["data", "0", "blob"]);
+/// assert_eq!(parse_pointer("/a~1b/c~0d").unwrap(), ["a/b", "c~d"]);
+/// assert!(parse_pointer("").unwrap().is_empty());
+/// assert!(parse_pointer("data").is_err());
+/// ```
+pub fn parse_pointer(pointer: &str) -> Result<Vec<String>, JsonError> {
+    let invalid = |reason| JsonError::InvalidPointer(pointer.to_string(), reason);
+    let Some(tokens) = pointer.strip_prefix('/') else {
+        return match pointer.is_empty() {
+            true => Ok(Vec::new()),
+            false => Err(invalid("it must be empty or start with '/'")),
+        };
+    };
+    tokens
+        .split('/')
+        .map(|token| {
+            let mut unescaped = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                unescaped.push(match c {
+                    '~' => match chars.next() {
+                        Some('0') => '~',
+                        Some('1') => '/',
+                        _ => return Err(invalid("'~' must be followed by '0' or '1'")),
+                    },
+                    c => c,
+                });
+            }
+            Ok(unescaped)
+        })
+        .collect()
+}
+
+/// Extracts the string at `pointer` in the JSON `document`
+///
+/// ```rust
+/// use bytary::json::{JsonError, extract_string};
+///
+/// let document = br#"{"data": [{"blob": "1b348f"}], "size": 3}"#;
+/// assert_eq!(extract_string(document, "/data/0/blob").unwrap(), "1b348f");
+/// assert_eq!(
+///     extract_string(document, "/size"),
+///     Err(JsonError::NotAString { pointer: "/size".to_string(), found: "a number" })
+/// );
+/// ```
+pub fn extract_string(document: &[u8], pointer: &str) -> Result<String, JsonError> {
+    parse_pointer(pointer)?;
+    let mut value = serde_json::from_slice::<Value>(document)
+        .map_err(|e| JsonError::InvalidJson(e.to_string()))?;
+    match value.pointer_mut(pointer).map(Value::take) {
+        Some(Value::String(text)) => Ok(text),
+        Some(other) => Err(JsonError::NotAString {
+            pointer: pointer.to_string(),
+            found: kind(&other),
+        }),
+        None => Err(JsonError::PointerNotFound(pointer.to_string())),
+    }
+}
+
+/// The smallest JSON document with `text` at `pointer`, nesting an object for each token.
+///
+/// Tokens that look like array indices are object keys too, which [`extract_string`] finds all the same.
+///
+/// ```rust
+/// use bytary::json::{extract_string, wrap_string};
+///
+/// let document = wrap_string("1b348f", "/data/0/blob").unwrap();
+/// assert_eq!(document, r#"{"data":{"0":{"blob":"1b348f"}}}"#);
+/// assert_eq!(extract_string(document.as_bytes(), "/data/0/blob").unwrap(), "1b348f");
+/// ```
+pub fn wrap_string(text: &str, pointer: &str) -> Result<String, JsonError> {
+    let value = parse_pointer(pointer)?
+        .into_iter()
+        .rev()
+        .fold(Value::String(text.to_string()), |value, token| {
+            Value::Object([(token, value)].into_iter().collect())
+        });
+    Ok(value.to_string())
+}
+
+/// The kind of `value`, for errors
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
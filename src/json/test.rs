@@ -0,0 +1,103 @@
+use crate::error::BytaryError;
+use crate::json::{JsonError, extract_string, parse_pointer, wrap_string};
+
+const DOCUMENT: &[u8] = br#"{
+    "payload": "1b348f",
+    "data": [{"blob": "SGk="}, {"blob": "4869"}],
+    "a/b": {"c~d": "0110"},
+    "size": 3,
+    "flags": [true],
+    "none": null
+}"#;
+
+#[test]
+fn test_extract_string() {
+    assert_eq!(extract_string(DOCUMENT, "/payload").unwrap(), "1b348f");
+    assert_eq!(extract_string(DOCUMENT, "/data/0/blob").unwrap(), "SGk=");
+    assert_eq!(extract_string(DOCUMENT, "/data/1/blob").unwrap(), "4869");
+    assert_eq!(extract_string(DOCUMENT, "/a~1b/c~0d").unwrap(), "0110");
+    assert_eq!(extract_string(br#""48 69""#, "").unwrap(), "48 69");
+}
+
+#[test]
+fn test_extract_errors() {
+    let not_found = |pointer: &str| Err(JsonError::PointerNotFound(pointer.to_string()));
+    assert_eq!(extract_string(DOCUMENT, "/missing"), not_found("/missing"));
+    assert_eq!(
+        extract_string(DOCUMENT, "/data/2/blob"),
+        not_found("/data/2/blob")
+    );
+    assert_eq!(extract_string(DOCUMENT, "/data/x"), not_found("/data/x"));
+    assert_eq!(
+        extract_string(DOCUMENT, "/payload/0"),
+        not_found("/payload/0")
+    );
+
+    for (pointer, found) in [
+        ("/size", "a number"),
+        ("/flags", "an array"),
+        ("/flags/0", "a boolean"),
+        ("/none", "null"),
+        ("/data/0", "an object"),
+        ("", "an object"),
+    ] {
+        assert_eq!(
+            extract_string(DOCUMENT, pointer),
+            Err(JsonError::NotAString {
+                pointer: pointer.to_string(),
+                found
+            })
+        );
+    }
+
+    let error = extract_string(br#"{"payload": "1b"#, "/payload").unwrap_err();
+    assert!(matches!(error, JsonError::InvalidJson(_)), "{}", error);
+    assert!(matches!(
+        BytaryError::from(error),
+        BytaryError::InvalidInputData(_)
+    ));
+
+    // The pointer is checked before the document
+    let error = extract_string(b"not json", "payload").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid JSON pointer 'payload': it must be empty or start with '/'"
+    );
+    assert!(matches!(
+        BytaryError::from(error),
+        BytaryError::InvalidArgument(_)
+    ));
+}
+
+#[test]
+fn test_parse_pointer() {
+    assert_eq!(parse_pointer("/").unwrap(), [""]);
+    assert_eq!(parse_pointer("//a").unwrap(), ["", "a"]);
+    assert_eq!(parse_pointer("/~01").unwrap(), ["~1"]);
+    assert!(parse_pointer("/a~").is_err());
+    assert!(parse_pointer("/a~2").is_err());
+}
+
+#[test]
+fn test_wrap_string() {
+    assert_eq!(
+        wrap_string("1b348f", "/payload").unwrap(),
+        r#"{"payload":"1b348f"}"#
+    );
+    assert_eq!(wrap_string("a\"b\n", "").unwrap(), r#""a\"b\n""#);
+    assert_eq!(wrap_string("x", "/a~1b").unwrap(), r#"{"a/b":"x"}"#);
+    assert!(wrap_string("x", "a").is_err());
+
+    // Extracting from the wrapped document gives the text back
+    for pointer in ["", "/payload", "/data/0/blob", "/a~1b/c~0d", "/"] {
+        for text in ["", "48 69", "\"\\\u{1}é"] {
+            let document = wrap_string(text, pointer).unwrap();
+            assert_eq!(
+                extract_string(document.as_bytes(), pointer).unwrap(),
+                text,
+                "{}",
+                document
+            );
+        }
+    }
+}
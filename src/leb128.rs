@@ -0,0 +1,279 @@
+use crate::convert::{ConversionGraph, Converter, CopyConverter, EdgeInfo};
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use crate::utils::{RetryingReader, flush_if_short, is_ignorable};
+use crate::validate::{InvalidInput, char_at};
+use std::io;
+use std::io::{BufRead, Read, Write};
+
+#[cfg(test)]
+mod test;
+
+/// Options of the LEB128 converters
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Leb128Options {
+    /// Read and write signed values, as in two's complement, instead of unsigned ones
+    pub signed: bool,
+}
+
+/// Decodes a stream of unsigned LEB128 values into decimal numbers, one per line
+///
+/// A value that does not fit in 64 bits, or that the input ends in the middle of, is an error.
+///
+/// ```rust
+/// use bytary::leb128::leb128_to_dec;
+///
+/// let mut output = Vec::new();
+/// leb128_to_dec(&mut &[0x02, 0xe5, 0x8e, 0x26][..], &mut output).unwrap();
+/// assert_eq!(output, b"2\n624485\n");
+///
+/// assert!(leb128_to_dec(&mut &[0xe5, 0x8e][..], &mut Vec::new()).is_err());
+/// ```
+pub fn leb128_to_dec(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    Leb128ToDec::default().convert(input, output)
+}
+
+/// Encodes whitespace-separated unsigned decimal numbers as LEB128 values
+///
+/// ```rust
+/// use bytary::leb128::dec_to_leb128;
+///
+/// let mut output = Vec::new();
+/// dec_to_leb128(&mut &b"2\n624485\n"[..], &mut output).unwrap();
+/// assert_eq!(output, [0x02, 0xe5, 0x8e, 0x26]);
+/// ```
+pub fn dec_to_leb128(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    DecToLeb128::default().convert(input, output)
+}
+
+/// The [`Converter`] of [`leb128_to_dec`], signed with [`Leb128Options::signed`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Leb128ToDec {
+    pub options: Leb128Options,
+}
+
+impl Converter for Leb128ToDec {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut input = RetryingReader::new(input);
+        let mut buffer = [0u8; 1024];
+        let mut value = Value::new(self.options.signed);
+        let mut offset = 0;
+
+        loop {
+            let length = input.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            for &byte in &buffer[..length] {
+                if let Some(number) = value.push(byte, offset)? {
+                    writeln!(output, "{}", number)?;
+                }
+                offset += 1;
+            }
+            flush_if_short(output, length, buffer.len())?;
+        }
+        match value.length {
+            0 => Ok(()),
+            _ => BytaryError::InvalidInputData(format!(
+                "truncated LEB128 value at offset {}",
+                offset - value.length
+            ))
+            .err(),
+        }
+    }
+    fn name(&self) -> &str {
+        "leb128_to_dec"
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// A LEB128 value being decoded
+struct Value {
+    signed: bool,
+    bits: u64,
+    /// Number of bytes read so far
+    length: usize,
+}
+
+impl Value {
+    fn new(signed: bool) -> Self {
+        Self {
+            signed,
+            bits: 0,
+            length: 0,
+        }
+    }
+
+    /// Adds the byte at `offset` of the input, returning the value in decimal if it was the last one
+    fn push(&mut self, byte: u8, offset: usize) -> BytaryResult<Option<String>> {
+        let shift = self.length as u32 * 7;
+        let payload = byte & 0x7f;
+        let last = byte & 0x80 == 0;
+
+        // The 10th byte holds the 64th bit, and the rest of its bits must be unused
+        let fits = match (shift, self.signed) {
+            (0..63, _) => true,
+            (63, false) => last && payload <= 1,
+            (63, true) => last && (payload == 0 || payload == 0x7f),
+            _ => false,
+        };
+        if !fits {
+            return BytaryError::InvalidInputData(format!(
+                "LEB128 value at offset {} does not fit in 64 bits",
+                offset - self.length
+            ))
+            .err();
+        }
+        self.bits |= (payload as u64) << shift;
+        self.length += 1;
+        if !last {
+            return Ok(None);
+        }
+
+        let shift = shift + 7;
+        let number = match self.signed {
+            // Extend the sign bit of the last byte
+            true if shift < 64 && payload & 0x40 != 0 => (self.bits | (u64::MAX << shift)) as i64,
+            true => self.bits as i64,
+            false => return Ok(Some(self.take().to_string())),
+        };
+        self.take();
+        Ok(Some(number.to_string()))
+    }
+
+    /// Returns the bits of the value and starts the next one
+    fn take(&mut self) -> u64 {
+        self.length = 0;
+        std::mem::take(&mut self.bits)
+    }
+}
+
+/// The [`Converter`] of [`dec_to_leb128`], signed with [`Leb128Options::signed`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DecToLeb128 {
+    pub options: Leb128Options,
+}
+
+impl Converter for DecToLeb128 {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut reader = io::BufReader::new(RetryingReader::new(input));
+        let mut line = Vec::new();
+        let mut encoded = Vec::new();
+        let mut offset = 0;
+
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            for token in line.split(|&b| is_ignorable(b)) {
+                if token.is_empty() {
+                    continue;
+                }
+                let start = offset + (token.as_ptr() as usize - line.as_ptr() as usize);
+                // A non-ASCII character is named, so that look-alikes of digits are told apart
+                if let Some(i) = token.iter().position(|b| !b.is_ascii()) {
+                    return BytaryError::InvalidInputAt {
+                        offset: (start + i) as u64,
+                        message: InvalidInput {
+                            offset: start + i,
+                            character: char_at(&token[i..]),
+                        }
+                        .to_string(),
+                    }
+                    .err();
+                }
+                let invalid = || BytaryError::InvalidInputAt {
+                    offset: start as u64,
+                    message: format!(
+                        "invalid {} 64-bit number '{}' at offset {}",
+                        match self.options.signed {
+                            true => "signed",
+                            false => "unsigned",
+                        },
+                        String::from_utf8_lossy(token),
+                        start
+                    ),
+                };
+                let text = std::str::from_utf8(token).map_err(|_| invalid())?;
+                match self.options.signed {
+                    true => encode_signed(text.parse().map_err(|_| invalid())?, &mut encoded),
+                    false => encode_unsigned(text.parse().map_err(|_| invalid())?, &mut encoded),
+                }
+            }
+            output.write_all(&encoded)?;
+            encoded.clear();
+            offset += line.len();
+            line.clear();
+        }
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        "dec_to_leb128"
+    }
+}
+
+fn encode_unsigned(mut value: u64, output: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+fn encode_signed(mut value: i64, output: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        // Arithmetic shift, which keeps the sign
+        value >>= 7;
+        let sign = byte & 0x40 != 0;
+        if (value == 0 && !sign) || (value == -1 && sign) {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+impl ConversionGraph {
+    /// Adds the edges between LEB128 and decimal numbers, replacing existing ones.
+    ///
+    /// LEB128 values are also bytes as they are, copied by lossy edges to and from bytes.
+    /// Since a lossy edge can only end a path, other formats can be read as LEB128, like
+    /// `hex -> bytes -> leb128`, but LEB128 only converts to decimal numbers and bytes:
+    /// `bytary hex leb128` is unsupported, `bytary hex bytes` converts the same bytes, and
+    /// `bytary bytes hex | bytary dec leb128` decodes LEB128 given in hex.
+    pub fn add_leb128_edges(&mut self, options: Leb128Options) {
+        let edge = |name, lossy| EdgeInfo {
+            name,
+            lossy,
+            cost: 1,
+        };
+        self.add_builtin_converter(
+            Format::Leb128,
+            Format::Dec,
+            edge("leb128_to_dec", false),
+            Leb128ToDec { options },
+        );
+        self.add_builtin_converter(
+            Format::Dec,
+            Format::Leb128,
+            edge("dec_to_leb128", false),
+            DecToLeb128 { options },
+        );
+        self.add_builtin_converter(
+            Format::Leb128,
+            Format::Bytes,
+            edge("copy", true),
+            CopyConverter,
+        );
+        self.add_builtin_converter(
+            Format::Bytes,
+            Format::Leb128,
+            edge("copy", true),
+            CopyConverter,
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::convert::{ConversionGraph, Converter};
+use crate::error::BytaryResult;
+use crate::format::Format;
+use crate::leb128::{DecToLeb128, Leb128Options, Leb128ToDec};
+
+fn decode(input: &[u8], signed: bool) -> BytaryResult<String> {
+    let mut output = Vec::new();
+    Leb128ToDec {
+        options: Leb128Options { signed },
+    }
+    .convert(&mut &input[..], &mut output)?;
+    Ok(String::from_utf8(output).unwrap())
+}
+
+fn encode(input: &str, signed: bool) -> BytaryResult<Vec<u8>> {
+    let mut output = Vec::new();
+    DecToLeb128 {
+        options: Leb128Options { signed },
+    }
+    .convert(&mut input.as_bytes(), &mut output)?;
+    Ok(output)
+}
+
+/// Pairs of values and their encodings from the WebAssembly spec tests and the LEB128 article
+const UNSIGNED: &[(&str, &[u8])] = &[
+    ("0", &[0x00]),
+    ("1", &[0x01]),
+    ("127", &[0x7f]),
+    ("128", &[0x80, 0x01]),
+    ("624485", &[0xe5, 0x8e, 0x26]),
+    ("4294967295", &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+    (
+        "18446744073709551615",
+        &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+    ),
+];
+
+const SIGNED: &[(&str, &[u8])] = &[
+    ("0", &[0x00]),
+    ("-1", &[0x7f]),
+    ("63", &[0x3f]),
+    ("-64", &[0x40]),
+    ("64", &[0xc0, 0x00]),
+    ("-65", &[0xbf, 0x7f]),
+    ("-123456", &[0xc0, 0xbb, 0x78]),
+    ("-2147483648", &[0x80, 0x80, 0x80, 0x80, 0x78]),
+    (
+        "9223372036854775807",
+        &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00],
+    ),
+    (
+        "-9223372036854775808",
+        &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7f],
+    ),
+];
+
+#[test]
+fn test_vectors() -> BytaryResult<()> {
+    for (signed, vectors) in [(false, UNSIGNED), (true, SIGNED)] {
+        for &(number, bytes) in vectors {
+            assert_eq!(decode(bytes, signed)?, format!("{}\n", number));
+            assert_eq!(encode(number, signed)?, bytes, "{}", number);
+        }
+        let all = vectors.iter().flat_map(|(_, bytes)| *bytes);
+        let lines = vectors.iter().map(|(number, _)| format!("{}\n", number));
+        assert_eq!(
+            decode(&all.copied().collect::<Vec<u8>>(), signed)?,
+            lines.collect::<String>()
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_non_minimal() -> BytaryResult<()> {
+    // Padded encodings are valid, as in the WebAssembly spec tests
+    assert_eq!(decode(&[0x83, 0x80, 0x80, 0x80, 0x00], false)?, "3\n");
+    assert_eq!(decode(&[0xfe, 0xff, 0x7f], true)?, "-2\n");
+    Ok(())
+}
+
+#[test]
+fn test_truncated() {
+    for input in [&[0x80][..], &[0x01, 0xe5, 0x8e], &[0x00, 0xff, 0xff, 0xff]] {
+        let offset = if input.len() == 1 { 0 } else { 1 };
+        assert_eq!(
+            decode(input, false).unwrap_err().to_string(),
+            format!(
+                "Invalid input data: truncated LEB128 value at offset {}",
+                offset
+            )
+        );
+    }
+}
+
+#[test]
+fn test_overflow() {
+    let too_long = [0x80; 10]
+        .iter()
+        .chain(&[0x01])
+        .copied()
+        .collect::<Vec<u8>>();
+    for (input, signed) in [
+        // 2^64
+        (
+            &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x02][..],
+            false,
+        ),
+        (&too_long[..], false),
+        // Neither a sign extension of bit 63 for positive nor for negative values
+        (
+            &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01][..],
+            true,
+        ),
+        (
+            &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7e][..],
+            true,
+        ),
+    ] {
+        let mut prefixed = vec![0x05];
+        prefixed.extend_from_slice(input);
+        assert_eq!(
+            decode(&prefixed, signed).unwrap_err().to_string(),
+            "Invalid input data: LEB128 value at offset 1 does not fit in 64 bits"
+        );
+    }
+}
+
+#[test]
+fn test_invalid_numbers() {
+    assert_eq!(encode(" 1  2\n3\t\n", false).unwrap(), [1, 2, 3]);
+    assert_eq!(
+        encode("1\n2 -3", false).unwrap_err().to_string(),
+        "Invalid input data: invalid unsigned 64-bit number '-3' at offset 4"
+    );
+    assert_eq!(
+        encode("18446744073709551616", false)
+            .unwrap_err()
+            .to_string(),
+        "Invalid input data: invalid unsigned 64-bit number '18446744073709551616' at offset 0"
+    );
+    assert_eq!(
+        encode("-9223372036854775809", true)
+            .unwrap_err()
+            .to_string(),
+        "Invalid input data: invalid signed 64-bit number '-9223372036854775809' at offset 0"
+    );
+    // Look-alikes of digits are named
+    assert_eq!(
+        encode("12 3４", false).unwrap_err().to_string(),
+        "Invalid input data: unexpected character '４' (U+FF14, full-width digit 4) at offset 4"
+    );
+}
+
+#[test]
+fn test_graph() -> BytaryResult<()> {
+    let graph = ConversionGraph::default();
+    let mut output = Vec::new();
+    graph
+        .try_get_converter(&Format::Hex, &Format::Leb128)?
+        .convert(&mut &b"e58e26 01"[..], &mut output)?;
+    assert_eq!(output, [0xe5, 0x8e, 0x26, 0x01]);
+
+    let mut decimal = Vec::new();
+    graph
+        .try_get_converter(&Format::Leb128, &Format::Dec)?
+        .convert(&mut &output[..], &mut decimal)?;
+    assert_eq!(decimal, b"624485\n1\n");
+
+    // Bytes only become decimal numbers through LEB128 when asked to
+    assert!(
+        graph
+            .try_get_converter(&Format::Bytes, &Format::Dec)
+            .is_err()
+    );
+    assert!(graph.validate().is_empty());
+    Ok(())
+}
@@ -1,5 +1,109 @@
+pub mod adapter;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod bits;
 pub mod builtins;
+pub mod chunks;
+/// Named presets of command line options, read from a TOML config file.
+#[cfg(feature = "config")]
+pub mod config;
+/// A battery of checks for converters of bytes: empty, small and large inputs, reads cut in chunks,
+/// failing writes and round trips, with [`assert_conformant!`] to run it in a test.
+///
+/// Crates that add their own converters can enable the `test-util` feature in their dev-dependencies.
+#[cfg(any(test, feature = "test-util"))]
+pub mod conformance;
 pub mod convert;
+pub mod diff;
+/// ASCII-only classification of digits, shared by every decoder, and the non-ASCII characters
+/// that look like them, which decoders name in their errors.
+///
+/// ```rust
+/// use bytary::digits::{is_hex_digit, lookalike};
+///
+/// assert!(is_hex_digit(b'F'));
+/// assert_eq!(lookalike('Ｆ').unwrap().ascii, 'F');
+/// ```
+pub mod digits;
 pub mod error;
+/// Conversions between byte slices into caller-provided buffers, which never allocate.
+///
+/// Unlike the streaming converters in [`builtins`], decoding only accepts digits,
+/// without whitespace, prefixes or separators.
+///
+/// ```rust
+/// use bytary::fast::hex;
+///
+/// let mut buffer = [0u8; hex::encoded_len(2)];
+/// let length = hex::encode_into(b"Hi", &mut buffer).unwrap();
+/// assert_eq!(&buffer[..length], b"4869");
+/// ```
+pub mod fast;
+/// A C ABI over the default conversions, for other languages to call without a subprocess.
+///
+/// Every function returns `BYTARY_OK` or an error code, and catches panics. To build a shared library:
+///
+/// ```sh
+/// cargo rustc --lib --release --features ffi --crate-type cdylib
+/// ```
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod format;
+pub mod info;
+/// Strings extracted from, and wrapped into, JSON documents at a JSON Pointer like `/data/0/blob`.
+///
+/// ```rust
+/// use bytary::json::extract_string;
+///
+/// assert_eq!(extract_string(br#"{"payload":"1b348f"}"#, "/payload").unwrap(), "1b348f");
+/// ```
+#[cfg(feature = "json")]
+pub mod json;
+pub mod leb128;
+/// Hex and binary literals decoded at compile time, with the digit table of the runtime decoders.
+///
+/// ```rust
+/// const KEY: [u8; 4] = bytary::hexb!("de ad be ef");
+/// assert_eq!(KEY, 0xdeadbeef_u32.to_be_bytes());
+/// ```
+#[cfg(feature = "macros")]
+pub mod macros;
+pub mod numeric;
+pub mod observe;
+pub mod patch;
+pub mod pipeline;
+/// The types that most uses of the library need, to import with `use bytary::prelude::*`.
+///
+/// ```rust
+/// use bytary::prelude::*;
+///
+/// fn to_hex(data: &[u8]) -> BytaryResult<Vec<u8>> {
+///     let converter = ConversionGraph::default().try_get_converter(&Format::Bytes, &Format::Hex)?;
+///     let mut output = Vec::new();
+///     converter.convert(&mut &data[..], &mut FormattedWriter::new(&mut output, 2, 0))?;
+///     Ok(output)
+/// }
+///
+/// assert_eq!(to_hex(b"Hi").unwrap(), b"48 69");
+/// ```
+pub mod prelude;
+pub mod rle;
+pub mod selftest;
+/// Helpers to test converters: reproducible pseudo-random data, readers and writers that cut reads
+/// apart or fail on purpose, and a corpus of inputs.
+///
+/// Crates that implement their own converters can enable the `test-util` feature in their
+/// dev-dependencies, to check them with the same helpers as the built-in ones.
+#[cfg(any(test, feature = "test-util"))]
+pub mod testsupport;
+pub mod text;
+pub mod transform;
 pub mod utils;
+pub mod validate;
+pub mod warning;
+
+pub use convert::{ConversionGraph, Converter};
+pub use error::{BytaryError, BytaryResult};
+pub use format::Format;
+pub use info::{BuildInfo, build_info};
+pub use utils::FormattedWriter;
@@ -0,0 +1,229 @@
+use crate::digits::DIGIT_VALUES;
+use crate::utils::is_ignorable;
+
+#[cfg(test)]
+mod test;
+
+/// Decodes a hex literal at compile time into a `[u8; N]`, ignoring [whitespace](crate::utils::is_ignorable).
+///
+/// Decoding is strict: any other character, or an odd number of digits, is a compile error
+/// that tells the character and its offset in the literal.
+///
+/// ```rust
+/// const KEY: [u8; 3] = bytary::hexb!("1b 34 8F");
+/// assert_eq!(KEY, [0x1b, 0x34, 0x8f]);
+/// ```
+///
+/// ```rust,compile_fail
+/// let key = bytary::hexb!("1b 3g");
+/// ```
+///
+/// ```rust,compile_fail
+/// let key = bytary::hexb!("1b3");
+/// ```
+#[macro_export]
+macro_rules! hexb {
+    ($literal:expr) => {{
+        const BYTES: [u8; $crate::macros::hex_len($literal)] =
+            $crate::macros::decode_hex_const($literal);
+        BYTES
+    }};
+}
+
+/// Decodes a binary literal at compile time into a `[u8; N]`, ignoring [whitespace](crate::utils::is_ignorable).
+///
+/// Decoding is strict: any other character, or a number of digits that is not a multiple of 8,
+/// is a compile error that tells the character and its offset in the literal.
+///
+/// ```rust
+/// const MASK: [u8; 2] = bytary::binb!("0001 1011  1111 0000");
+/// assert_eq!(MASK, [0x1b, 0xf0]);
+/// ```
+///
+/// ```rust,compile_fail
+/// let mask = bytary::binb!("0001 1021");
+/// ```
+///
+/// ```rust,compile_fail
+/// let mask = bytary::binb!("0001 101");
+/// ```
+#[macro_export]
+macro_rules! binb {
+    ($literal:expr) => {{
+        const BYTES: [u8; $crate::macros::bin_len($literal)] =
+            $crate::macros::decode_bin_const($literal);
+        BYTES
+    }};
+}
+
+/// Number of bytes that the hex digits of `s` decode to, see [`hexb!`](crate::hexb).
+///
+/// Panics, which is a compile error in a constant, if `s` is not whitespace and pairs of hex digits.
+pub const fn hex_len(s: &str) -> usize {
+    decoded_len(s, &HEX)
+}
+
+/// Number of bytes that the binary digits of `s` decode to, see [`binb!`](crate::binb).
+///
+/// Panics, which is a compile error in a constant, if `s` is not whitespace and groups of 8 bits.
+pub const fn bin_len(s: &str) -> usize {
+    decoded_len(s, &BIN)
+}
+
+/// Decodes the hex digits of `s`, ignoring [whitespace](crate::utils::is_ignorable).
+///
+/// Panics if `s` is invalid, see [`hex_len`], or if it does not decode to exactly `N` bytes.
+///
+/// ```rust
+/// use bytary::macros::decode_hex_const;
+///
+/// const KEY: [u8; 2] = decode_hex_const("1b 34");
+/// assert_eq!(KEY, [0x1b, 0x34]);
+/// ```
+pub const fn decode_hex_const<const N: usize>(s: &str) -> [u8; N] {
+    decode(s, &HEX)
+}
+
+/// Decodes the binary digits of `s`, ignoring [whitespace](crate::utils::is_ignorable).
+///
+/// Panics if `s` is invalid, see [`bin_len`], or if it does not decode to exactly `N` bytes.
+pub const fn decode_bin_const<const N: usize>(s: &str) -> [u8; N] {
+    decode(s, &BIN)
+}
+
+/// How the digits of a literal are read
+struct Radix {
+    name: &'static str,
+    value: u8,
+    digits_per_byte: usize,
+}
+
+const HEX: Radix = Radix {
+    name: "hex",
+    value: 16,
+    digits_per_byte: 2,
+};
+
+const BIN: Radix = Radix {
+    name: "binary",
+    value: 2,
+    digits_per_byte: 8,
+};
+
+const fn decoded_len(s: &str, radix: &Radix) -> usize {
+    let bytes = s.as_bytes();
+    let mut digits = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if !is_ignorable(bytes[i]) {
+            if DIGIT_VALUES[bytes[i] as usize] >= radix.value {
+                invalid_character(s, i, radix);
+            }
+            digits += 1;
+        }
+        i += 1;
+    }
+    if digits % radix.digits_per_byte != 0 {
+        incomplete_byte(digits, radix);
+    }
+    digits / radix.digits_per_byte
+}
+
+const fn decode<const N: usize>(s: &str, radix: &Radix) -> [u8; N] {
+    if decoded_len(s, radix) != N {
+        panic!("the literal does not decode to the length of the array");
+    }
+    let bytes = s.as_bytes();
+    let mut output = [0u8; N];
+    let mut digits = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if !is_ignorable(bytes[i]) {
+            let byte = digits / radix.digits_per_byte;
+            output[byte] = output[byte] * radix.value + DIGIT_VALUES[bytes[i] as usize];
+            digits += 1;
+        }
+        i += 1;
+    }
+    output
+}
+
+/// Room for the error messages, which are short
+const MESSAGE_CAPACITY: usize = 128;
+
+/// An error message built at compile time
+struct Message {
+    buffer: [u8; MESSAGE_CAPACITY],
+    length: usize,
+}
+
+impl Message {
+    const fn new() -> Self {
+        Self {
+            buffer: [0; MESSAGE_CAPACITY],
+            length: 0,
+        }
+    }
+
+    const fn push(mut self, bytes: &[u8]) -> Self {
+        let mut i = 0;
+        while i < bytes.len() && self.length < MESSAGE_CAPACITY {
+            self.buffer[self.length] = bytes[i];
+            self.length += 1;
+            i += 1;
+        }
+        self
+    }
+
+    const fn push_number(self, mut n: usize) -> Self {
+        let mut digits = [0u8; 20];
+        let mut start = digits.len();
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (n % 10) as u8;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        self.push(digits.split_at(start).1)
+    }
+}
+
+/// Fails with the character of `s` at byte `offset`
+const fn invalid_character(s: &str, offset: usize, radix: &Radix) -> ! {
+    let bytes = s.as_bytes();
+    // Whole UTF-8 sequence of the character, as `s` is valid UTF-8
+    let mut end = offset + 1;
+    while end < bytes.len() && bytes[end] & 0xc0 == 0x80 {
+        end += 1;
+    }
+    let character = bytes.split_at(end).0.split_at(offset).1;
+    let message = Message::new()
+        .push(b"invalid ")
+        .push(radix.name.as_bytes())
+        .push(b" digit '")
+        .push(character)
+        .push(b"' at offset ")
+        .push_number(offset);
+    fail(message)
+}
+
+/// Fails because `digits` digits do not fill whole bytes
+const fn incomplete_byte(digits: usize, radix: &Radix) -> ! {
+    let message = Message::new()
+        .push_number(digits)
+        .push(b" ")
+        .push(radix.name.as_bytes())
+        .push(b" digits do not fill whole bytes of ")
+        .push_number(radix.digits_per_byte)
+        .push(b" digits");
+    fail(message)
+}
+
+const fn fail(message: Message) -> ! {
+    match core::str::from_utf8(message.buffer.split_at(message.length).0) {
+        Ok(text) => panic!("{}", text),
+        Err(_) => panic!("invalid literal"),
+    }
+}
@@ -0,0 +1,89 @@
+use crate::convert::ConversionGraph;
+use crate::format::Format;
+use crate::macros::{bin_len, decode_bin_const, decode_hex_const, hex_len};
+use std::panic::catch_unwind;
+
+/// Decodes `literal` with the streaming converter from `from` to bytes
+fn decode(from: Format, literal: &str) -> Vec<u8> {
+    let mut output = Vec::new();
+    ConversionGraph::default()
+        .get_converter(&from, &Format::Bytes)
+        .unwrap()
+        .convert(&mut literal.as_bytes(), &mut output)
+        .unwrap();
+    output
+}
+
+#[test]
+fn test_hexb() {
+    const LITERAL: &str = "00 1b 34 8F\tff\n7e";
+    assert_eq!(crate::hexb!(LITERAL), [0x00, 0x1b, 0x34, 0x8f, 0xff, 0x7e]);
+    assert_eq!(crate::hexb!(LITERAL).to_vec(), decode(Format::Hex, LITERAL));
+    assert_eq!(crate::hexb!(""), [0u8; 0]);
+    assert_eq!(crate::hexb!(" \n "), [0u8; 0]);
+
+    let all = (0..=255u8)
+        .map(|byte| format!("{:02x} ", byte))
+        .collect::<String>();
+    assert_eq!(
+        decode_hex_const::<256>(&all).to_vec(),
+        decode(Format::Hex, &all)
+    );
+}
+
+#[test]
+fn test_binb() {
+    const LITERAL: &str = "0001 1011\n11111111 00000000";
+    assert_eq!(crate::binb!(LITERAL), [0x1b, 0xff, 0x00]);
+    assert_eq!(crate::binb!(LITERAL).to_vec(), decode(Format::Bin, LITERAL));
+
+    let all = (0..=255u8)
+        .map(|byte| format!("{:08b} ", byte))
+        .collect::<String>();
+    assert_eq!(
+        decode_bin_const::<256>(&all).to_vec(),
+        decode(Format::Bin, &all)
+    );
+}
+
+#[test]
+fn test_invalid() {
+    let message = |f: fn()| {
+        let payload = catch_unwind(f).unwrap_err();
+        payload
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap()
+    };
+    assert_eq!(
+        message(|| {
+            hex_len("1b 3g");
+        }),
+        "invalid hex digit 'g' at offset 4"
+    );
+    assert_eq!(
+        message(|| {
+            hex_len("1b é");
+        }),
+        "invalid hex digit 'é' at offset 3"
+    );
+    assert_eq!(
+        message(|| {
+            hex_len("1b3");
+        }),
+        "3 hex digits do not fill whole bytes of 2 digits"
+    );
+    assert_eq!(
+        message(|| {
+            bin_len("0001 1021");
+        }),
+        "invalid binary digit '2' at offset 7"
+    );
+    assert_eq!(
+        message(|| {
+            decode_hex_const::<3>("1b34");
+        }),
+        "the literal does not decode to the length of the array"
+    );
+}
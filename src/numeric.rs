@@ -0,0 +1,185 @@
+use crate::convert::Converter;
+use crate::digits::{describe_non_ascii, digit_value};
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use crate::utils::is_ignorable;
+use std::io::{Read, Write};
+
+#[cfg(test)]
+mod test;
+
+/// Options of [`convert_number`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct NumericOptions {
+    /// Pad the output with leading zeros to the width that the largest number
+    /// with as many digits as the input takes, so that leading zeros of the input are kept.
+    ///
+    /// For example, `00ff` in hex is `0000000011111111` in bin, and `007` in dec is `007` in hex.
+    pub keep_width: bool,
+}
+
+/// The radix of the digits of `format`, for the formats that can be read as a whole number
+pub fn radix(format: &Format) -> BytaryResult<u32> {
+    match format {
+        Format::Bin => Ok(2),
+        Format::Oct => Ok(8),
+        Format::Dec => Ok(10),
+        Format::Hex => Ok(16),
+        _ => BytaryError::InvalidArgument(format!(
+            "numeric conversion only supports bin, oct, dec or hex, not {}",
+            format
+        ))
+        .err(),
+    }
+}
+
+/// Parses the digits of `text` in base `radix`, most significant first, without leading zeros.
+///
+/// Whitespace and `_` are skipped. Returns the digits and the number of digits in the input,
+/// counting leading zeros.
+fn parse_digits(text: &str, radix: u32) -> BytaryResult<(Vec<u8>, usize)> {
+    let mut digits = Vec::new();
+    let mut width = 0;
+    for (offset, c) in text.char_indices() {
+        if c.is_ascii() && is_ignorable(c as u8) || c == '_' {
+            continue;
+        }
+        let digit = u8::try_from(c)
+            .ok()
+            .and_then(|byte| digit_value(byte, radix))
+            .ok_or_else(|| BytaryError::InvalidInputAt {
+                offset: offset as u64,
+                message: match describe_non_ascii(c) {
+                    Some(description) => format!(
+                        "Invalid base {} digit '{}' ({}) at offset {}",
+                        radix, c, description, offset
+                    ),
+                    None => format!("Invalid base {} digit '{}' at offset {}", radix, c, offset),
+                },
+            })?;
+        width += 1;
+        if digit != 0 || !digits.is_empty() {
+            digits.push(digit);
+        }
+    }
+    if width == 0 {
+        return BytaryError::InvalidInputData("Empty input, expected a number".to_string()).err();
+    }
+    Ok((digits, width))
+}
+
+/// Converts digits in base `from` to base `to`, both most significant first, by repeated division.
+///
+/// Zero is an empty vector.
+fn rebase(mut digits: Vec<u8>, from: u32, to: u32) -> Vec<u8> {
+    let mut result = Vec::new();
+    while !digits.is_empty() {
+        let mut quotient = Vec::with_capacity(digits.len());
+        let mut remainder = 0u32;
+        for &digit in &digits {
+            let value = remainder * from + digit as u32;
+            if !quotient.is_empty() || value >= to {
+                quotient.push((value / to) as u8);
+            }
+            remainder = value % to;
+        }
+        result.push(remainder as u8);
+        digits = quotient;
+    }
+    result.reverse();
+    result
+}
+
+/// Converts the whole number in `text` from base `from` to base `to`,
+/// instead of converting byte by byte like the conversions of a
+/// [`ConversionGraph`](crate::convert::ConversionGraph).
+///
+/// The input may contain whitespace and `_` separators. Digits above 9 are written lowercase.
+/// Both radixes must be between 2 and 36.
+///
+/// ```rust
+/// use bytary::numeric::{NumericOptions, convert_number};
+///
+/// let options = NumericOptions::default();
+/// assert_eq!(convert_number("1_000_000", 10, 16, &options).unwrap(), "f4240");
+/// assert_eq!(convert_number("00ff", 16, 2, &options).unwrap(), "11111111");
+///
+/// let options = NumericOptions { keep_width: true };
+/// assert_eq!(convert_number("00ff", 16, 2, &options).unwrap(), "0000000011111111");
+/// ```
+pub fn convert_number(
+    text: &str,
+    from: u32,
+    to: u32,
+    options: &NumericOptions,
+) -> BytaryResult<String> {
+    for radix in [from, to] {
+        if !(2..=36).contains(&radix) {
+            return BytaryError::InvalidArgument(format!(
+                "radix must be between 2 and 36, not {}",
+                radix
+            ))
+            .err();
+        }
+    }
+    let (digits, width) = parse_digits(text, from)?;
+    let mut digits = rebase(digits, from, to);
+
+    let min_width = match options.keep_width {
+        // The number of digits of the largest number of the same width, like 999 for 3 dec digits
+        true => rebase(vec![(from - 1) as u8; width], from, to).len(),
+        false => 1,
+    };
+    if digits.len() < min_width {
+        digits.splice(0..0, std::iter::repeat_n(0, min_width - digits.len()));
+    }
+
+    Ok(digits
+        .into_iter()
+        .map(|digit| char::from_digit(digit as u32, to).unwrap_or('?'))
+        .collect())
+}
+
+/// Create a converter that reads its whole input as one number in the digits of `from`
+/// and writes it in the digits of `to`.
+///
+/// Unlike the built-in converters, empty input is an error, since it is not a number.
+pub fn numeric_converter(
+    from: &Format,
+    to: &Format,
+    options: NumericOptions,
+) -> BytaryResult<NumericConverter> {
+    Ok(NumericConverter {
+        from: radix(from)?,
+        to: radix(to)?,
+        options,
+    })
+}
+
+/// The [`Converter`] of [`numeric_converter`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NumericConverter {
+    from: u32,
+    to: u32,
+    options: NumericOptions,
+}
+
+impl Converter for NumericConverter {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        let text = String::from_utf8(bytes).map_err(|e| {
+            let offset = e.utf8_error().valid_up_to();
+            BytaryError::InvalidInputAt {
+                offset: offset as u64,
+                message: format!("Invalid digit at offset {}", offset),
+            }
+        })?;
+        let number = convert_number(&text, self.from, self.to, &self.options)?;
+        output.write_all(number.as_bytes())?;
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        "numeric"
+    }
+}
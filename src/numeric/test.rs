@@ -0,0 +1,95 @@
+use crate::convert::Converter;
+use crate::error::BytaryError;
+use crate::format::Format;
+use crate::numeric::{NumericOptions, convert_number, numeric_converter};
+
+fn convert(text: &str, from: u32, to: u32) -> String {
+    convert_number(text, from, to, &NumericOptions::default()).unwrap()
+}
+
+#[test]
+fn test_convert_number() {
+    assert_eq!(convert("255", 10, 16), "ff");
+    assert_eq!(convert("FF", 16, 10), "255");
+    assert_eq!(convert("1010_1010", 2, 8), "252");
+    assert_eq!(convert(" 12 34\n", 10, 10), "1234");
+    assert_eq!(convert("18446744073709551616", 10, 16), "10000000000000000");
+    assert_eq!(convert("777", 8, 2), "111111111");
+}
+
+#[test]
+fn test_zero() {
+    assert_eq!(convert("0", 10, 16), "0");
+    assert_eq!(convert("0000", 16, 2), "0");
+
+    let options = NumericOptions { keep_width: true };
+    assert_eq!(
+        convert_number("0000", 16, 2, &options).unwrap(),
+        "0".repeat(16)
+    );
+}
+
+#[test]
+fn test_keep_width() {
+    let options = NumericOptions { keep_width: true };
+    let convert = |text, from, to| convert_number(text, from, to, &options).unwrap();
+    assert_eq!(convert("00ff", 16, 2), "0000000011111111");
+    assert_eq!(convert("007", 10, 16), "007");
+    assert_eq!(convert("999", 10, 16), "3e7");
+    assert_eq!(convert("0001", 2, 16), "1");
+    assert_eq!(convert("00000001", 2, 16), "01");
+}
+
+#[test]
+fn test_invalid() {
+    for text in ["", " _ \n"] {
+        let error = convert_number(text, 10, 16, &NumericOptions::default()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid input data: Empty input, expected a number"
+        );
+    }
+    let error = convert_number("12a4", 10, 16, &NumericOptions::default()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: Invalid base 10 digit 'a' at offset 2"
+    );
+    let error = convert_number("12٣4", 10, 16, &NumericOptions::default()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: Invalid base 10 digit '٣' (U+0663, Arabic-Indic digit 3) at offset 2"
+    );
+
+    assert!(convert_number("1", 10, 37, &NumericOptions::default()).is_err());
+
+    let error = numeric_converter(&Format::Dec, &Format::Base64, NumericOptions::default())
+        .err()
+        .unwrap();
+    assert!(matches!(error, BytaryError::InvalidArgument(_)));
+}
+
+#[test]
+fn test_round_trip_1000_digits() {
+    // Deterministic digits that do not start with 0
+    let mut state = 7u32;
+    let decimal = (0..1000)
+        .map(|i| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let digit = (state >> 16) % 10;
+            char::from_digit(if i == 0 { digit.max(1) } else { digit }, 10).unwrap()
+        })
+        .collect::<String>();
+
+    let hex = convert(&decimal, 10, 16);
+    // 10^1000 needs about 830.5 hex digits
+    assert!(hex.len() == 830 || hex.len() == 831, "{}", hex.len());
+    assert_eq!(convert(&hex, 16, 10), decimal);
+    assert_eq!(convert(&convert(&hex, 16, 2), 2, 10), decimal);
+
+    let mut output = Vec::new();
+    numeric_converter(&Format::Dec, &Format::Oct, NumericOptions::default())
+        .unwrap()
+        .convert(&mut decimal.as_bytes(), &mut output)
+        .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), convert(&decimal, 10, 8));
+}
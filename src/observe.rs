@@ -0,0 +1,132 @@
+use crate::error::BytaryError;
+use crate::format::Format;
+use crate::utils::{format_size, plural};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+#[cfg(test)]
+mod test;
+
+/// Callbacks of the conversions run by [`ConversionGraph::convert`](crate::convert::ConversionGraph::convert),
+/// for metrics and logging.
+///
+/// Every conversion calls [`on_start`](ConversionObserver::on_start), then either
+/// [`on_complete`](ConversionObserver::on_complete) or [`on_error`](ConversionObserver::on_error).
+/// The methods do nothing by default.
+pub trait ConversionObserver {
+    fn on_start(&self, _from: &Format, _to: &Format) {}
+
+    /// Called once the conversion succeeded, with the bytes read from the input and written to the output
+    fn on_complete(
+        &self,
+        _from: &Format,
+        _to: &Format,
+        _bytes_in: u64,
+        _bytes_out: u64,
+        _duration: Duration,
+    ) {
+    }
+
+    /// Called if there is no converter, or the conversion failed
+    fn on_error(&self, _from: &Format, _to: &Format, _error: &BytaryError) {}
+
+    /// Called after each hop of a conversion run with timing,
+    /// see [`ConversionContext`](crate::convert::ConversionContext)
+    fn on_stage(&self, _stage: &StageTiming) {}
+}
+
+/// The time and bytes of one hop of a conversion, recorded when
+/// [timing](crate::convert::ConversionContext::with_timing) is on
+///
+/// ```rust
+/// use bytary::format::Format::*;
+/// use bytary::observe::StageTiming;
+/// use std::time::Duration;
+///
+/// let stage = StageTiming {
+///     from: Bytes,
+///     to: Bin,
+///     bytes_in: 1 << 30,
+///     bytes_out: 8 << 30,
+///     duration: Duration::from_secs(4),
+/// };
+/// assert_eq!(stage.to_string(), "bytes -> bin: 1 GiB in, 8 GiB out, 4.000s, 256 MiB/s");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StageTiming {
+    pub from: Format,
+    pub to: Format,
+    /// Bytes read from the previous hop, or from the input
+    pub bytes_in: u64,
+    /// Bytes written to the next hop, or to the output
+    pub bytes_out: u64,
+    pub duration: Duration,
+}
+
+impl StageTiming {
+    /// Input bytes per second, 0 if no time was measured
+    pub fn throughput(&self) -> f64 {
+        match self.duration.is_zero() {
+            true => 0.0,
+            false => self.bytes_in as f64 / self.duration.as_secs_f64(),
+        }
+    }
+}
+
+impl Display for StageTiming {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} -> {}: {} in, {} out, {:.3}s, {}/s",
+            self.from,
+            self.to,
+            format_size(self.bytes_in),
+            format_size(self.bytes_out),
+            self.duration.as_secs_f64(),
+            format_size(self.throughput() as u64)
+        )
+    }
+}
+
+/// An observer that prints a line to stderr for every conversion that ends
+///
+/// ```rust
+/// use bytary::convert::ConversionGraph;
+/// use bytary::format::Format::*;
+/// use bytary::observe::LoggingObserver;
+///
+/// let mut graph = ConversionGraph::default();
+/// graph.set_observer(Box::new(LoggingObserver));
+/// // Prints `bytary: bytes => hex: 2 bytes in, 4 bytes out, ...`
+/// graph.convert(&Bytes, &Hex, &mut &b"Hi"[..], &mut Vec::new()).unwrap();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoggingObserver;
+
+impl ConversionObserver for LoggingObserver {
+    fn on_complete(
+        &self,
+        from: &Format,
+        to: &Format,
+        bytes_in: u64,
+        bytes_out: u64,
+        duration: Duration,
+    ) {
+        eprintln!(
+            "bytary: {} => {}: {} in, {} out, {:.6}s",
+            from,
+            to,
+            plural(bytes_in, "byte"),
+            plural(bytes_out, "byte"),
+            duration.as_secs_f64()
+        );
+    }
+
+    fn on_error(&self, from: &Format, to: &Format, error: &BytaryError) {
+        eprintln!("bytary: {} => {} failed: {}", from, to, error);
+    }
+
+    fn on_stage(&self, stage: &StageTiming) {
+        eprintln!("bytary: {}", stage);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::convert::ConversionGraph;
+use crate::error::BytaryError;
+use crate::format::Format;
+use crate::format::Format::*;
+use crate::observe::ConversionObserver;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Records the callbacks as lines like `complete hex => bytes 4 2`
+#[derive(Clone, Default)]
+struct Recorder(Rc<RefCell<Vec<String>>>);
+
+impl ConversionObserver for Recorder {
+    fn on_start(&self, from: &Format, to: &Format) {
+        self.0
+            .borrow_mut()
+            .push(format!("start {} => {}", from, to));
+    }
+
+    fn on_complete(&self, from: &Format, to: &Format, bytes_in: u64, bytes_out: u64, _: Duration) {
+        self.0.borrow_mut().push(format!(
+            "complete {} => {} {} {}",
+            from, to, bytes_in, bytes_out
+        ));
+    }
+
+    fn on_error(&self, from: &Format, to: &Format, error: &BytaryError) {
+        self.0
+            .borrow_mut()
+            .push(format!("error {} => {}: {}", from, to, error));
+    }
+}
+
+#[test]
+fn test_observer() {
+    let recorder = Recorder::default();
+    let mut graph = ConversionGraph::default();
+    graph.set_observer(Box::new(recorder.clone()));
+
+    let mut output = Vec::new();
+    graph
+        .convert(&Hex, &Bytes, &mut &b"48 69"[..], &mut output)
+        .unwrap();
+    assert_eq!(output, b"Hi");
+
+    assert!(
+        graph
+            .convert(&Hex, &Bytes, &mut &b"4g"[..], &mut Vec::new())
+            .is_err()
+    );
+    assert!(
+        graph
+            .convert(&Stats, &Bytes, &mut &b""[..], &mut Vec::new())
+            .is_err()
+    );
+
+    // Converters from the graph are not observed
+    let converter = graph.get_converter(&Bytes, &Hex).unwrap();
+    converter.convert(&mut &b"Hi"[..], &mut Vec::new()).unwrap();
+
+    assert_eq!(
+        *recorder.0.borrow(),
+        [
+            "start hex => bytes",
+            "complete hex => bytes 5 2",
+            "start hex => bytes",
+            "error hex => bytes: Invalid input data: Invalid hex string: unexpected character 'g' at offset 1",
+            "start stats => bytes",
+            "error stats => bytes: Unsupported conversion: stats => bytes",
+        ]
+    );
+}
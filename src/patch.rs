@@ -0,0 +1,219 @@
+use crate::error::{BytaryError, BytaryResult};
+use crate::utils::{flush_if_short, plural};
+use std::io;
+use std::io::{Read, Write};
+
+#[cfg(test)]
+mod test;
+
+/// Bytes to write over the stream at `offset`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Patch {
+    pub offset: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// Options of [`apply_patches`] and [`PatchingWriter`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PatchOptions {
+    /// Allow patches past the end of the stream, which extend the output.
+    ///
+    /// A gap between the end of the stream and a patch is filled with zeros.
+    pub allow_extend: bool,
+}
+
+/// Parses patches, one per line, like `0x1A2B: de ad be ef`.
+///
+/// Offsets are hex, with or without `0x`, and bytes are hex with optional whitespace between them.
+/// Empty lines are skipped, and `#` starts a comment that extends to the end of the line.
+///
+/// ```rust
+/// use bytary::patch::{Patch, parse_patches};
+///
+/// let patches = parse_patches("# header\n0x10: de ad\n20: beef # magic\n").unwrap();
+/// assert_eq!(
+///     patches,
+///     [
+///         Patch { offset: 0x10, bytes: vec![0xde, 0xad] },
+///         Patch { offset: 0x20, bytes: vec![0xbe, 0xef] },
+///     ]
+/// );
+/// ```
+pub fn parse_patches(text: &str) -> BytaryResult<Vec<Patch>> {
+    let mut patches = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let invalid = |message: &str| {
+            BytaryError::InvalidArgument(format!("patch at line {}: {}", index + 1, message))
+        };
+
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (offset, bytes) = line
+            .split_once(':')
+            .ok_or_else(|| invalid("expected `offset: bytes`"))?;
+        let offset = offset.trim();
+        let offset = offset
+            .strip_prefix("0x")
+            .or_else(|| offset.strip_prefix("0X"))
+            .unwrap_or(offset);
+        let offset = u64::from_str_radix(offset, 16)
+            .map_err(|_| invalid(&format!("invalid offset '{}'", offset)))?;
+
+        let digits = bytes.split_whitespace().collect::<String>();
+        if digits.is_empty() {
+            return Err(invalid("no bytes"));
+        }
+        let bytes = hex::decode(&digits)
+            .map_err(|_| invalid(&format!("invalid hex bytes '{}'", bytes.trim())))?;
+
+        patches.push(Patch { offset, bytes });
+    }
+    Ok(patches)
+}
+
+/// Fails if the patches are not sorted by offset, or if any of them overlap
+fn check_patches(patches: &[Patch]) -> BytaryResult<()> {
+    for pair in patches.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        if b.offset < a.offset {
+            return BytaryError::InvalidArgument(format!(
+                "patches are not sorted: 0x{:x} comes after 0x{:x}",
+                b.offset, a.offset
+            ))
+            .err();
+        }
+        if b.offset < a.offset + a.bytes.len() as u64 {
+            return BytaryError::InvalidArgument(format!(
+                "patch at 0x{:x} overlaps the patch at 0x{:x}",
+                b.offset, a.offset
+            ))
+            .err();
+        }
+    }
+    Ok(())
+}
+
+/// A writer that applies patches to the bytes written through it, tracking the offset.
+///
+/// Call [`finish`](PatchingWriter::finish) at the end of the stream,
+/// which applies or rejects the patches past the end.
+pub struct PatchingWriter<'a, W: Write> {
+    target: W,
+    patches: &'a [Patch],
+    options: PatchOptions,
+    /// Index of the first patch that is not fully applied
+    next: usize,
+    position: u64,
+}
+
+impl<'a, W: Write> PatchingWriter<'a, W> {
+    /// Fails if the patches are not sorted or overlap
+    pub fn new(target: W, patches: &'a [Patch], options: PatchOptions) -> BytaryResult<Self> {
+        check_patches(patches)?;
+        Ok(Self {
+            target,
+            patches,
+            options,
+            next: 0,
+            position: 0,
+        })
+    }
+
+    /// Applies the remaining patches past the end of the stream if allowed, fails otherwise
+    pub fn finish(&mut self) -> BytaryResult<()> {
+        for patch in &self.patches[self.next..] {
+            if !self.options.allow_extend {
+                return BytaryError::InvalidInputData(format!(
+                    "patch at 0x{:x} extends past the end of the input ({})",
+                    patch.offset,
+                    plural(self.position, "byte")
+                ))
+                .err();
+            }
+            if patch.offset > self.position {
+                let gap = patch.offset - self.position;
+                io::copy(&mut io::repeat(0).take(gap), &mut self.target)?;
+                self.position = patch.offset;
+            }
+            let start = (self.position - patch.offset) as usize;
+            self.target.write_all(&patch.bytes[start..])?;
+            self.position += (patch.bytes.len() - start) as u64;
+        }
+        self.next = self.patches.len();
+        self.target.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for PatchingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let end = self.position + buf.len() as u64;
+        if !matches!(self.patches.get(self.next), Some(patch) if patch.offset < end) {
+            self.target.write_all(buf)?;
+            self.position = end;
+            return Ok(buf.len());
+        }
+
+        let mut chunk = buf.to_vec();
+        while let Some(patch) = self.patches.get(self.next) {
+            if patch.offset >= end {
+                break;
+            }
+            let patch_end = patch.offset + patch.bytes.len() as u64;
+            let start = patch.offset.max(self.position);
+            let stop = patch_end.min(end);
+            if start < stop {
+                chunk[(start - self.position) as usize..(stop - self.position) as usize]
+                    .copy_from_slice(
+                        &patch.bytes
+                            [(start - patch.offset) as usize..(stop - patch.offset) as usize],
+                    );
+            }
+            if patch_end > end {
+                break;
+            }
+            self.next += 1;
+        }
+        self.target.write_all(&chunk)?;
+        self.position = end;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+}
+
+/// Copies `input` to `output` while applying `patches`, without loading the whole input.
+///
+/// Patches must be sorted by offset and must not overlap.
+///
+/// ```rust
+/// use bytary::patch::{Patch, PatchOptions, apply_patches};
+///
+/// let patches = [Patch { offset: 1, bytes: b"a".to_vec() }];
+/// let mut output = Vec::new();
+/// apply_patches(&patches, &mut &b"Hello"[..], &mut output, &PatchOptions::default()).unwrap();
+/// assert_eq!(output, b"Hallo");
+/// ```
+pub fn apply_patches(
+    patches: &[Patch],
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    options: &PatchOptions,
+) -> BytaryResult<()> {
+    let mut writer = PatchingWriter::new(output, patches, options.clone())?;
+    let mut buffer = [0u8; 8192];
+    loop {
+        let length = input.read(&mut buffer)?;
+        if length == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..length])?;
+        flush_if_short(&mut writer, length, buffer.len())?;
+    }
+    writer.finish()
+}
@@ -0,0 +1,97 @@
+use crate::error::{BytaryError, BytaryResult};
+use crate::patch::{Patch, PatchOptions, apply_patches, parse_patches};
+
+fn patch(offset: u64, bytes: &[u8]) -> Patch {
+    Patch {
+        offset,
+        bytes: bytes.to_vec(),
+    }
+}
+
+fn apply(patches: &[Patch], input: &[u8], allow_extend: bool) -> BytaryResult<Vec<u8>> {
+    let mut output = Vec::new();
+    apply_patches(
+        patches,
+        &mut &input[..],
+        &mut output,
+        &PatchOptions { allow_extend },
+    )?;
+    Ok(output)
+}
+
+#[test]
+fn test_parse() {
+    let patches = parse_patches("0x1A2B: de ad be ef\n\n  # comment\n1a2f:00 # zero\n").unwrap();
+    assert_eq!(
+        patches,
+        [patch(0x1a2b, b"\xde\xad\xbe\xef"), patch(0x1a2f, b"\x00")]
+    );
+
+    for (text, message) in [
+        ("10 de ad", "patch at line 1: expected `offset: bytes`"),
+        ("\n0xzz: 00", "patch at line 2: invalid offset 'zz'"),
+        ("10:", "patch at line 1: no bytes"),
+        ("10: abc", "patch at line 1: invalid hex bytes 'abc'"),
+    ] {
+        let error = parse_patches(text).unwrap_err();
+        assert_eq!(error.to_string(), format!("Invalid argument: {}", message));
+    }
+}
+
+#[test]
+fn test_middle_of_large_stream() -> BytaryResult<()> {
+    let input = vec![0u8; 1 << 20];
+    // The first patch crosses the boundary of the 8192-byte reads
+    let patches = [patch(8190, b"\x01\x02\x03\x04"), patch(500000, b"\xff")];
+    let output = apply(&patches, &input, false)?;
+
+    let mut expected = input.clone();
+    expected[8190..8194].copy_from_slice(b"\x01\x02\x03\x04");
+    expected[500000] = 0xff;
+    assert_eq!(output, expected);
+    Ok(())
+}
+
+#[test]
+fn test_adjacent() -> BytaryResult<()> {
+    let patches = [patch(0, b"ab"), patch(2, b"cd"), patch(4, b"e")];
+    assert_eq!(apply(&patches, b"0123456", false)?, b"abcde56");
+    Ok(())
+}
+
+#[test]
+fn test_invalid_order() {
+    let error = apply(&[patch(0, b"abc"), patch(2, b"d")], b"0123", false).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid argument: patch at 0x2 overlaps the patch at 0x0"
+    );
+
+    let error = apply(&[patch(2, b"a"), patch(0, b"b")], b"0123", false).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid argument: patches are not sorted: 0x0 comes after 0x2"
+    );
+}
+
+#[test]
+fn test_past_end() -> BytaryResult<()> {
+    // A patch ending exactly at the end is fine
+    assert_eq!(apply(&[patch(2, b"ab")], b"0123", false)?, b"01ab");
+
+    let error = apply(&[patch(3, b"ab")], b"0123", false).unwrap_err();
+    assert!(matches!(error, BytaryError::InvalidInputData(_)));
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: patch at 0x3 extends past the end of the input (4 bytes)"
+    );
+    assert!(apply(&[patch(4, b"a")], b"0123", false).is_err());
+
+    assert_eq!(apply(&[patch(3, b"ab")], b"0123", true)?, b"012ab");
+    assert_eq!(apply(&[patch(4, b"a")], b"0123", true)?, b"0123a");
+    assert_eq!(
+        apply(&[patch(1, b"x"), patch(6, b"ab")], b"0123", true)?,
+        b"0x23\0\0ab"
+    );
+    Ok(())
+}
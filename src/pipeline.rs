@@ -0,0 +1,173 @@
+use crate::convert::{ConversionGraph, Converter};
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+#[cfg(test)]
+mod test;
+
+enum Stage {
+    /// Convert from the given format to bytes
+    Decode(Format),
+    /// Convert from bytes to the given format
+    Encode(Format),
+    /// Apply a custom converter
+    Transform(Rc<dyn Converter>),
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stage::Decode(format) => write!(f, "decode {}", format),
+            Stage::Encode(format) => write!(f, "encode {}", format),
+            Stage::Transform(_) => write!(f, "transform"),
+        }
+    }
+}
+
+/// A chain of conversions and transforms, run one after another.
+///
+/// Decode and encode stages are resolved against a [`ConversionGraph`],
+/// which is the built-in graph unless given with [`Pipeline::with_graph`].
+///
+/// If any stage fails, the error is wrapped in [`BytaryError::StageFailed`] with the 1-based index of the stage.
+///
+/// ```rust
+/// use bytary::format::Format::*;
+/// use bytary::pipeline::{xor, Pipeline};
+///
+/// let output = Pipeline::new()
+///     .decode(Hex)
+///     .transform(xor(b"\x01"))
+///     .encode(Hex)
+///     .run_bytes(b"4142")
+///     .unwrap();
+/// assert_eq!(output, b"4043");
+/// ```
+pub struct Pipeline {
+    graph: ConversionGraph,
+    stages: Vec<Stage>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pipeline {
+    /// Create an empty pipeline using the built-in conversions
+    pub fn new() -> Self {
+        Self::with_graph(ConversionGraph::default())
+    }
+    /// Create an empty pipeline using the conversions in `graph`
+    pub fn with_graph(graph: ConversionGraph) -> Self {
+        Self {
+            graph,
+            stages: Vec::new(),
+        }
+    }
+    /// Add a stage that converts from `format` to bytes
+    pub fn decode(mut self, format: Format) -> Self {
+        self.stages.push(Stage::Decode(format));
+        self
+    }
+    /// Add a stage that converts from bytes to `format`
+    pub fn encode(mut self, format: Format) -> Self {
+        self.stages.push(Stage::Encode(format));
+        self
+    }
+    /// Add a stage that runs `converter`
+    pub fn transform<T: Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static>(
+        mut self,
+        converter: T,
+    ) -> Self {
+        self.stages.push(Stage::Transform(Rc::new(converter)));
+        self
+    }
+
+    /// Resolve all stages and compose them into a single converter.
+    ///
+    /// An empty pipeline copies the input.
+    pub fn build(&self) -> BytaryResult<Rc<dyn Converter>> {
+        if self.stages.is_empty() {
+            return Ok(ConversionGraph::get_copy_converter());
+        }
+
+        let converters = self
+            .stages
+            .iter()
+            .enumerate()
+            .map(|(i, stage)| {
+                let index = i + 1;
+                let (from, to) = match stage {
+                    Stage::Decode(format) => (format.clone(), Format::Bytes),
+                    Stage::Encode(format) => (Format::Bytes, format.clone()),
+                    Stage::Transform(converter) => {
+                        return Ok(Self::attribute(index, stage, converter.clone()));
+                    }
+                };
+                let converter = self.graph.get_converter(&from, &to).ok_or_else(|| {
+                    BytaryError::StageFailed(
+                        index,
+                        stage.to_string(),
+                        Box::new(BytaryError::UnsupportedConversion(from, to)),
+                    )
+                })?;
+                Ok(Self::attribute(index, stage, converter))
+            })
+            .collect::<BytaryResult<Vec<Rc<dyn Converter>>>>()?;
+
+        Ok(ConversionGraph::compose(converters))
+    }
+
+    /// Run the pipeline from `input` to `output`
+    pub fn run(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        self.build()?.convert(input, output)
+    }
+
+    /// Run the pipeline on a byte slice, returning the output
+    pub fn run_bytes(&self, mut input: &[u8]) -> BytaryResult<Vec<u8>> {
+        let mut output = Vec::new();
+        self.run(&mut input, &mut output)?;
+        Ok(output)
+    }
+
+    /// Wrap errors returned by `converter` in [`BytaryError::StageFailed`]
+    fn attribute(index: usize, stage: &Stage, converter: Rc<dyn Converter>) -> Rc<dyn Converter> {
+        let name = stage.to_string();
+        Rc::new(move |input: &mut dyn Read, output: &mut dyn Write| {
+            converter
+                .convert(input, output)
+                .map_err(|e| BytaryError::StageFailed(index, name.clone(), Box::new(e)))
+        })
+    }
+}
+
+/// A transform that xors the input with `key`, repeating the key as needed.
+///
+/// An empty key leaves the input unchanged.
+pub fn xor(key: &[u8]) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    let key = key.to_vec();
+    move |input, output| {
+        let mut buffer = [0u8; 1024];
+        let mut position = 0;
+
+        loop {
+            let length = input.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            if !key.is_empty() {
+                for byte in &mut buffer[..length] {
+                    *byte ^= key[position % key.len()];
+                    position += 1;
+                }
+            }
+            output.write_all(&buffer[..length])?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use crate::convert::ConversionGraph;
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format::*;
+use crate::pipeline::{Pipeline, xor};
+
+#[test]
+fn test_chain() -> BytaryResult<()> {
+    let pipeline = Pipeline::new()
+        .decode(Hex)
+        .transform(xor(b"\x0f\xf0"))
+        .encode(Bin)
+        .encode(Hex);
+
+    // 1b34 ^ 0ff0 = 14c4, which is "0001010011000100" in bin, then hex-encoded as text
+    assert_eq!(
+        pipeline.run_bytes(b"1b 34")?,
+        hex::encode("0001010011000100").as_bytes()
+    );
+
+    let converter = pipeline.build()?;
+    let mut output = Vec::new();
+    converter.convert(&mut &b"1b34"[..], &mut output)?;
+    assert_eq!(output, pipeline.run_bytes(b"1b34")?);
+
+    Ok(())
+}
+
+#[test]
+fn test_empty() -> BytaryResult<()> {
+    assert_eq!(Pipeline::new().run_bytes(b"abc")?, b"abc");
+    assert_eq!(
+        Pipeline::new().transform(xor(b"")).run_bytes(b"abc")?,
+        b"abc"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_stage_error() {
+    let pipeline = Pipeline::new()
+        .decode(Hex)
+        .transform(xor(b"k"))
+        .encode(Oct)
+        .decode(Oct);
+
+    let err = pipeline.run_bytes(b"1g").unwrap_err();
+    assert!(matches!(&err, BytaryError::StageFailed(1, _, _)));
+    assert!(err.to_string().starts_with("Stage 1 (decode hex) failed: "));
+
+    let err = Pipeline::new()
+        .decode(Hex)
+        .transform(|_, _| BytaryError::InvalidInputData("bad".to_string()).err())
+        .encode(Hex)
+        .run_bytes(b"00")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Stage 2 (transform) failed: Invalid input data: bad"
+    );
+}
+
+#[test]
+fn test_unsupported_stage() {
+    let err = Pipeline::new()
+        .decode(Hex)
+        .transform(xor(b"k"))
+        .encode(Dec)
+        .run_bytes(b"00")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Stage 3 (encode dec) failed: Unsupported conversion: bytes => dec"
+    );
+}
+
+#[test]
+fn test_with_graph() -> BytaryResult<()> {
+    let mut graph = ConversionGraph::new();
+    graph
+        .add_direct(
+            Bytes,
+            Base64,
+            |r, w| {
+                std::io::copy(r, w)?;
+                w.write_all(b"=")?;
+                Ok(())
+            },
+            1,
+        )
+        .unwrap();
+
+    let pipeline = Pipeline::with_graph(graph)
+        .transform(xor(b"\x00"))
+        .encode(Base64)
+        .encode(Base64);
+    assert_eq!(pipeline.run_bytes(b"ab")?, b"ab==");
+    assert!(
+        Pipeline::with_graph(ConversionGraph::new())
+            .decode(Hex)
+            .build()
+            .is_err()
+    );
+    Ok(())
+}
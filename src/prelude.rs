@@ -0,0 +1,6 @@
+pub use crate::builtins::Padding;
+pub use crate::convert::{ConversionGraph, Converter, EdgeInfo};
+pub use crate::error::{BytaryError, BytaryResult};
+pub use crate::format::Format;
+pub use crate::utils::FormattedWriter;
+pub use crate::warning::Warning;
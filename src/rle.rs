@@ -0,0 +1,213 @@
+use crate::convert::Converter;
+use crate::digits::is_hex_digit;
+use crate::error::{BytaryError, BytaryResult};
+use crate::utils::{RetryingReader, flush_if_short};
+use std::io;
+use std::io::{BufRead, Read, Write};
+
+#[cfg(test)]
+mod test;
+
+/// Runs of at least this many identical bytes are written as `XX x N`
+pub const MIN_RUN: u64 = 4;
+
+/// Literal bytes per line
+pub const LINE_BYTES: usize = 16;
+
+/// Encodes bytes as lines of run-length encoded hex, see [`BytesToRle`].
+///
+/// ```rust
+/// use bytary::rle::bytes_to_rle;
+///
+/// let mut input = vec![0x1b, 0x34, 0x8f];
+/// input.extend([0xff; 4096]);
+/// let mut output = Vec::new();
+/// bytes_to_rle(&mut &input[..], &mut output).unwrap();
+/// assert_eq!(output, b"1b 34 8f\nff x 4096\n");
+/// ```
+pub fn bytes_to_rle(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    BytesToRle.convert(input, output)
+}
+
+/// Decodes the lines of [`bytes_to_rle`] back into bytes, see [`RleToBytes`].
+///
+/// ```rust
+/// use bytary::rle::rle_to_bytes;
+///
+/// let mut output = Vec::new();
+/// rle_to_bytes(&mut &b"1b 34\n00 x 3\n"[..], &mut output).unwrap();
+/// assert_eq!(output, [0x1b, 0x34, 0, 0, 0]);
+///
+/// let err = rle_to_bytes(&mut &b"1b 34\n00 x many\n"[..], &mut Vec::new()).unwrap_err();
+/// assert_eq!(err.to_string(), "Invalid input data: line 2: invalid run length 'many'");
+/// ```
+pub fn rle_to_bytes(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    RleToBytes.convert(input, output)
+}
+
+/// A [`Converter`] that writes runs of [`MIN_RUN`] or more identical bytes as lines like `ff x 4096`,
+/// and the other bytes as lines of up to [`LINE_BYTES`] space-separated hex bytes, streaming.
+///
+/// Runs are found across reads, so the output does not depend on how the input is chunked.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BytesToRle;
+
+impl Converter for BytesToRle {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut input = RetryingReader::new(input);
+        let mut buffer = [0u8; 1024];
+        let mut encoder = RleEncoder::default();
+        loop {
+            let length = input.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            for &byte in &buffer[..length] {
+                encoder.push(byte, output)?;
+            }
+            flush_if_short(output, length, buffer.len())?;
+        }
+        encoder.finish(output)
+    }
+    fn name(&self) -> &str {
+        "bytes_to_rle"
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The state of [`BytesToRle`] between reads
+#[derive(Default)]
+struct RleEncoder {
+    /// The byte of the current run, and how many times it occurred so far
+    run: Option<(u8, u64)>,
+    /// Bytes of runs too short to be written as runs, waiting for a full line
+    literals: Vec<u8>,
+}
+
+impl RleEncoder {
+    fn push(&mut self, byte: u8, output: &mut dyn Write) -> io::Result<()> {
+        match &mut self.run {
+            Some((current, count)) if *current == byte => *count += 1,
+            _ => {
+                self.end_run(output)?;
+                self.run = Some((byte, 1));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the current run, or moves it to the literals if it is too short
+    fn end_run(&mut self, output: &mut dyn Write) -> io::Result<()> {
+        match self.run.take() {
+            Some((byte, count)) if count >= MIN_RUN => {
+                self.write_literals(output, true)?;
+                writeln!(output, "{:02x} x {}", byte, count)
+            }
+            Some((byte, count)) => {
+                self.literals.extend((0..count).map(|_| byte));
+                self.write_literals(output, false)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the full lines of literals, and the last partial one if `all`
+    fn write_literals(&mut self, output: &mut dyn Write, all: bool) -> io::Result<()> {
+        let full = self.literals.len() / LINE_BYTES * LINE_BYTES;
+        let end = match all {
+            true => self.literals.len(),
+            false => full,
+        };
+        for line in self.literals[..end].chunks(LINE_BYTES) {
+            let hex = line
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<Vec<String>>();
+            writeln!(output, "{}", hex.join(" "))?;
+        }
+        self.literals.drain(..end);
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut dyn Write) -> BytaryResult<()> {
+        self.end_run(output)?;
+        self.write_literals(output, true)?;
+        Ok(())
+    }
+}
+
+/// A [`Converter`] that decodes the lines of [`BytesToRle`], streaming.
+///
+/// Blank lines are skipped. Any other line must be either hex bytes separated by whitespace,
+/// each of exactly 2 digits, or a run like `ff x 4096` of a positive length.
+/// Errors name the line, counted from 1.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RleToBytes;
+
+impl Converter for RleToBytes {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut reader = io::BufReader::new(RetryingReader::new(input));
+        let mut line = Vec::new();
+        let mut number = 0;
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            number += 1;
+            decode_line(&line, number, output)?;
+            line.clear();
+        }
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        "rle_to_bytes"
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// Writes the bytes of line `number`
+fn decode_line(line: &[u8], number: usize, output: &mut dyn Write) -> BytaryResult<()> {
+    let invalid =
+        |message: String| BytaryError::InvalidInputData(format!("line {}: {}", number, message));
+    let text = std::str::from_utf8(line).map_err(|_| invalid("invalid UTF-8".to_string()))?;
+    // Exactly 2 digits, which `from_str_radix` alone does not check, as it accepts a sign
+    let parse_byte = |token: &str| {
+        Some(token)
+            .filter(|token| token.len() == 2 && token.bytes().all(is_hex_digit))
+            .and_then(|token| u8::from_str_radix(token, 16).ok())
+            .ok_or_else(|| invalid(format!("invalid hex byte '{}'", token)))
+    };
+    let tokens = text.split_ascii_whitespace().collect::<Vec<&str>>();
+    match tokens[..] {
+        [byte, "x", count] => {
+            let byte = parse_byte(byte)?;
+            let count = count
+                .parse::<u64>()
+                .ok()
+                .filter(|&count| count > 0)
+                .ok_or_else(|| invalid(format!("invalid run length '{}'", count)))?;
+            write_run(byte, count, output)?;
+        }
+        _ => {
+            let bytes = tokens
+                .iter()
+                .map(|token| parse_byte(token))
+                .collect::<BytaryResult<Vec<u8>>>()?;
+            output.write_all(&bytes)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes `count` times `byte`, without holding them all in memory
+fn write_run(byte: u8, count: u64, output: &mut dyn Write) -> io::Result<()> {
+    let chunk = [byte; 1024];
+    let mut remaining = count;
+    while remaining > 0 {
+        let length = remaining.min(chunk.len() as u64) as usize;
+        output.write_all(&chunk[..length])?;
+        remaining -= length as u64;
+    }
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use crate::convert::{ConversionGraph, Converter};
+use crate::error::BytaryResult;
+use crate::format::Format;
+use crate::rle::{BytesToRle, RleToBytes};
+use crate::testsupport::ChunkedReader;
+
+fn encode(input: &[u8]) -> BytaryResult<Vec<u8>> {
+    let mut output = Vec::new();
+    BytesToRle.convert(&mut &input[..], &mut output)?;
+    Ok(output)
+}
+
+fn decode(input: &[u8]) -> BytaryResult<Vec<u8>> {
+    let mut output = Vec::new();
+    RleToBytes.convert(&mut &input[..], &mut output)?;
+    Ok(output)
+}
+
+#[test]
+fn test_all_ff() -> BytaryResult<()> {
+    let input = vec![0xff; 1 << 20];
+    let encoded = encode(&input)?;
+    assert_eq!(encoded, b"ff x 1048576\n");
+    assert_eq!(decode(&encoded)?, input);
+    Ok(())
+}
+
+#[test]
+fn test_no_runs() -> BytaryResult<()> {
+    let input = (0..40).map(|i| [0xaa, 0x55][i % 2]).collect::<Vec<u8>>();
+    let encoded = String::from_utf8(encode(&input)?).unwrap();
+    let lines = encoded.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "aa 55 ".repeat(8).trim_end());
+    assert_eq!(lines[2], "aa 55 aa 55 aa 55 aa 55");
+    assert_eq!(decode(encoded.as_bytes())?, input);
+    Ok(())
+}
+
+#[test]
+fn test_short_runs() -> BytaryResult<()> {
+    // Runs shorter than 4 stay literal, and literal lines end before a run
+    let input = [1, 1, 1, 2, 2, 2, 2, 3, 0, 0, 0, 0, 0];
+    assert_eq!(encode(&input)?, b"01 01 01\n02 x 4\n03\n00 x 5\n");
+    assert_eq!(decode(&encode(&input)?)?, input);
+    assert!(encode(&[])?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_run_across_chunks() -> BytaryResult<()> {
+    // A run from 1000 to 1100 crosses the boundary of the 1024-byte reads
+    let mut input = (0..3000).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    input[1000..1100].fill(0x42);
+    let encoded = encode(&input)?;
+    assert!(String::from_utf8_lossy(&encoded).contains("\n42 x 100\n"));
+    assert_eq!(decode(&encoded)?, input);
+
+    // The output does not depend on how the input is read
+    for size in [1, 7, 1023, 4096] {
+        let mut output = Vec::new();
+        BytesToRle.convert(&mut ChunkedReader::new(&input, size), &mut output)?;
+        assert_eq!(output, encoded, "reads of {} bytes", size);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_invalid() {
+    let error = |input: &[u8]| decode(input).unwrap_err().to_string();
+    assert_eq!(
+        error(b"1b 34\n\nff x 0\n"),
+        "Invalid input data: line 3: invalid run length '0'"
+    );
+    assert_eq!(
+        error(b"1b 3\n"),
+        "Invalid input data: line 1: invalid hex byte '3'"
+    );
+    assert_eq!(
+        error(b"00\n+f\n"),
+        "Invalid input data: line 2: invalid hex byte '+f'"
+    );
+    assert_eq!(
+        error(b"ff x -1"),
+        "Invalid input data: line 1: invalid run length '-1'"
+    );
+    assert_eq!(
+        error(b"ff y 3"),
+        "Invalid input data: line 1: invalid hex byte 'y'"
+    );
+    assert_eq!(
+        error(b"fff x 3"),
+        "Invalid input data: line 1: invalid hex byte 'fff'"
+    );
+    // Blank lines and CRLF are fine
+    assert_eq!(
+        decode(b"\r\n1B 34\r\n  \r\n00 x 2").unwrap(),
+        [0x1b, 0x34, 0, 0]
+    );
+}
+
+#[test]
+fn test_graph() -> BytaryResult<()> {
+    let graph = ConversionGraph::default();
+    let mut output = Vec::new();
+    graph
+        .try_get_converter(&Format::Hex, &Format::Rle)?
+        .convert(&mut &b"1b 00000000"[..], &mut output)?;
+    assert_eq!(output, b"1b\n00 x 4\n");
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use crate::convert::ConversionGraph;
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use crate::utils::plural;
+use std::fmt::{Display, Formatter};
+
+#[cfg(test)]
+mod test;
+
+/// The result of round-tripping one pair of formats
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairResult {
+    pub a: Format,
+    pub b: Format,
+    /// The first failing case, `None` if all of them passed
+    pub failure: Option<String>,
+}
+
+impl PairResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl Display for PairResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.failure {
+            None => write!(f, "{} <=> {}: ok", self.a, self.b),
+            Some(failure) => write!(f, "{} <=> {}: FAILED, {}", self.a, self.b, failure),
+        }
+    }
+}
+
+/// The result of [`run`], one entry per pair of formats
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SelfTestReport {
+    pub pairs: Vec<PairResult>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.pairs.iter().all(PairResult::passed)
+    }
+
+    /// The pairs that failed
+    pub fn failed(&self) -> impl Iterator<Item = &PairResult> {
+        self.pairs.iter().filter(|pair| !pair.passed())
+    }
+}
+
+/// Buffers that every pair is round-tripped with, by name
+fn corpus() -> Vec<(&'static str, Vec<u8>)> {
+    // xorshift, so that the random buffers are the same in every run
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut random = |length: usize| {
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect::<Vec<u8>>()
+    };
+
+    vec![
+        ("empty", Vec::new()),
+        ("one byte", vec![0x1b]),
+        ("zero", vec![0]),
+        ("all byte values", (0..=255).collect()),
+        ("text", b"Hello, World!\n".to_vec()),
+        (
+            "multilingual text",
+            "Grüße, мир, 世界 😀\r\n".as_bytes().to_vec(),
+        ),
+        ("random 1000", random(1000)),
+        ("random 10000", random(10000)),
+    ]
+}
+
+fn convert(
+    graph: &ConversionGraph,
+    from: &Format,
+    to: &Format,
+    data: &[u8],
+) -> BytaryResult<Vec<u8>> {
+    let converter = graph
+        .get_converter(from, to)
+        .ok_or_else(|| BytaryError::UnsupportedConversion(from.clone(), to.clone()))?;
+    let mut output = Vec::new();
+    converter.convert(&mut &data[..], &mut output)?;
+    Ok(output)
+}
+
+/// Encodes `data` to `a`, converts it to `b` and back, then decodes it and compares it with `data`
+fn round_trip(graph: &ConversionGraph, a: &Format, b: &Format, data: &[u8]) -> BytaryResult<bool> {
+    let encoded = convert(graph, &Format::Bytes, a, data)?;
+    let there = convert(graph, a, b, &encoded)?;
+    let back = convert(graph, b, a, &there)?;
+    Ok(convert(graph, a, &Format::Bytes, &back)? == data)
+}
+
+/// Round-trips a fixed corpus through every pair of formats that `graph` converts in both directions.
+///
+/// Only formats that can be converted to and from bytes take part, since the corpus is made of bytes.
+/// Pairs with a [text](Format::is_text) format only use the buffers that are valid UTF-8.
+/// Downstream graphs with custom converters can be checked the same way.
+///
+/// ```rust
+/// use bytary::convert::ConversionGraph;
+/// use bytary::selftest;
+///
+/// let report = selftest::run(&ConversionGraph::default());
+/// assert!(report.passed());
+/// ```
+pub fn run(graph: &ConversionGraph) -> SelfTestReport {
+    let corpus = corpus();
+    let formats = graph
+        .capabilities()
+        .formats
+        .into_iter()
+        .filter(|format| {
+            graph.can_convert_between(&Format::Bytes, format) || *format == Format::Bytes
+        })
+        .collect::<Vec<Format>>();
+
+    let mut report = SelfTestReport::default();
+    for (i, a) in formats.iter().enumerate() {
+        for b in &formats[i + 1..] {
+            if !graph.can_convert_between(a, b) {
+                continue;
+            }
+            let text = a.is_text() || b.is_text();
+            let failure = corpus
+                .iter()
+                .filter(|(_, data)| !text || std::str::from_utf8(data).is_ok())
+                .find_map(|(name, data)| {
+                    let problem = match round_trip(graph, a, b, data) {
+                        Ok(true) => return None,
+                        Ok(false) => "output differs".to_string(),
+                        Err(e) => e.to_string(),
+                    };
+                    Some(format!(
+                        "{} ({}): {}",
+                        name,
+                        plural(data.len(), "byte"),
+                        problem
+                    ))
+                });
+            report.pairs.push(PairResult {
+                a: a.clone(),
+                b: b.clone(),
+                failure,
+            });
+        }
+    }
+    report
+}
@@ -0,0 +1,61 @@
+use crate::convert::{ConversionGraph, EdgeInfo};
+use crate::format::Format;
+use crate::selftest::run;
+use std::io::{Read, Write};
+
+#[test]
+fn test_default_graph() {
+    let report = run(&ConversionGraph::default());
+    assert!(report.passed(), "{:?}", report);
+
+    let pairs = report
+        .pairs
+        .iter()
+        .map(|pair| format!("{}", pair))
+        .collect::<Vec<String>>();
+    assert!(pairs.contains(&"bytes <=> hex: ok".to_string()));
+    assert!(pairs.contains(&"bin <=> base64: ok".to_string()));
+    assert!(pairs.contains(&"hex <=> utf16le: ok".to_string()));
+    // Stats can not be decoded
+    assert!(!pairs.iter().any(|pair| pair.contains("stats")));
+}
+
+#[test]
+fn test_broken_converter() {
+    let mut graph = ConversionGraph::default();
+    graph
+        .add_edge(
+            Format::Base64,
+            Format::Bytes,
+            EdgeInfo {
+                name: "drops_last_byte",
+                lossy: false,
+                cost: 1,
+            },
+            |input: &mut dyn Read, output: &mut dyn Write| {
+                let mut decoded = Vec::new();
+                crate::builtins::base64_to_bytes(input, &mut decoded)?;
+                decoded.pop();
+                output.write_all(&decoded)?;
+                Ok(())
+            },
+        )
+        .unwrap();
+
+    let report = run(&graph);
+    assert!(!report.passed());
+    let failed = report.failed().collect::<Vec<_>>();
+    assert_eq!(failed[0].a, Format::Bytes);
+    assert_eq!(failed[0].b, Format::Base64);
+    assert_eq!(
+        failed[0].to_string(),
+        "bytes <=> base64: FAILED, one byte (1 byte): output differs"
+    );
+    // Pairs that do not go through base64 still pass
+    assert!(
+        report
+            .pairs
+            .iter()
+            .any(|pair| pair.a == Format::Bytes && pair.b == Format::Hex && pair.passed())
+    );
+}
@@ -0,0 +1,295 @@
+use std::io;
+use std::io::{Read, Write};
+
+#[cfg(test)]
+mod test;
+
+/// Seed of [`ByteGenerator::from_env`] when `BYTARY_TEST_SEED` is not set, so that runs are reproducible by default
+pub const DEFAULT_SEED: u64 = 0x6279_7461_7279;
+
+/// A seedable generator of pseudo-random bytes, the same for the same seed on every platform.
+///
+/// If the thread panics while a generator is alive, like on a failed assertion,
+/// the generator prints its seed to stderr so that the failure can be reproduced.
+///
+/// ```rust
+/// use bytary::testsupport::ByteGenerator;
+///
+/// let mut generator = ByteGenerator::new(42);
+/// let data = generator.bytes(16);
+/// assert_eq!(data, ByteGenerator::new(42).bytes(16));
+/// assert_ne!(data, ByteGenerator::new(43).bytes(16));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ByteGenerator {
+    seed: u64,
+    state: u64,
+}
+
+impl ByteGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            // xorshift never leaves a zero state
+            state: seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1,
+        }
+    }
+
+    /// A generator seeded from the `BYTARY_TEST_SEED` environment variable, or [`DEFAULT_SEED`]
+    ///
+    /// # Panics
+    ///
+    /// If the variable is set but is not a number.
+    pub fn from_env() -> Self {
+        match std::env::var("BYTARY_TEST_SEED") {
+            Ok(seed) => {
+                Self::new(seed.trim().parse().unwrap_or_else(|_| {
+                    panic!("BYTARY_TEST_SEED must be a number, not {:?}", seed)
+                }))
+            }
+            Err(_) => Self::new(DEFAULT_SEED),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The next 64 pseudo-random bits, from xorshift64
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    pub fn fill(&mut self, buffer: &mut [u8]) {
+        for chunk in buffer.chunks_mut(8) {
+            let bits = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bits[..chunk.len()]);
+        }
+    }
+
+    pub fn bytes(&mut self, length: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; length];
+        self.fill(&mut bytes);
+        bytes
+    }
+
+    /// A number below `bound`, to pick lengths or chunk sizes
+    pub fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "the bound must not be 0");
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+impl Drop for ByteGenerator {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            eprintln!(
+                "Test data seed: {} (run with BYTARY_TEST_SEED={} to reproduce)",
+                self.seed, self.seed
+            );
+        }
+    }
+}
+
+/// A reader over a slice that returns chunks of the given sizes, in turn, to cut units apart.
+///
+/// Reads are also cut at the size of the buffer, and the sizes start over after the last one.
+///
+/// ```rust
+/// use bytary::testsupport::ChunkedReader;
+/// use std::io::Read;
+///
+/// let mut reader = ChunkedReader::with_sizes(b"abcdef", &[1, 2]);
+/// let mut buffer = [0u8; 8];
+/// assert_eq!(reader.read(&mut buffer).unwrap(), 1);
+/// assert_eq!(reader.read(&mut buffer).unwrap(), 2);
+/// assert_eq!(reader.read(&mut buffer).unwrap(), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ChunkedReader<'a> {
+    data: &'a [u8],
+    sizes: Vec<usize>,
+    index: usize,
+}
+
+impl<'a> ChunkedReader<'a> {
+    /// Returns at most `size` bytes per read
+    pub fn new(data: &'a [u8], size: usize) -> Self {
+        Self::with_sizes(data, &[size])
+    }
+
+    /// # Panics
+    ///
+    /// If `sizes` is empty or contains 0, which would end the input early.
+    pub fn with_sizes(data: &'a [u8], sizes: &[usize]) -> Self {
+        assert!(
+            !sizes.is_empty() && !sizes.contains(&0),
+            "chunk sizes must not be empty or 0"
+        );
+        Self {
+            data,
+            sizes: sizes.to_vec(),
+            index: 0,
+        }
+    }
+}
+
+impl Read for ChunkedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.sizes[self.index % self.sizes.len()];
+        self.index += 1;
+        let length = self.data.len().min(buf.len()).min(size);
+        buf[..length].copy_from_slice(&self.data[..length]);
+        self.data = &self.data[length..];
+        Ok(length)
+    }
+}
+
+/// A reader that fails on the call to `read` of the given 0-based index, and reads from `inner` otherwise
+///
+/// ```rust
+/// use bytary::testsupport::FaultyReader;
+/// use std::io::{ErrorKind, Read};
+///
+/// let mut reader = FaultyReader::new(&b"Hi"[..], 1).with_kind(ErrorKind::BrokenPipe);
+/// let mut buffer = [0u8; 1];
+/// assert!(reader.read(&mut buffer).is_ok());
+/// assert_eq!(reader.read(&mut buffer).unwrap_err().kind(), ErrorKind::BrokenPipe);
+/// assert!(reader.read(&mut buffer).is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct FaultyReader<R: Read> {
+    inner: R,
+    fail_at: usize,
+    calls: usize,
+    kind: io::ErrorKind,
+}
+
+impl<R: Read> FaultyReader<R> {
+    /// Fails with [`ErrorKind::Other`](io::ErrorKind::Other) unless given [`with_kind`](FaultyReader::with_kind)
+    pub fn new(inner: R, fail_at: usize) -> Self {
+        Self {
+            inner,
+            fail_at,
+            calls: 0,
+            kind: io::ErrorKind::Other,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: io::ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Number of calls to `read` so far, including the failed one
+    pub fn calls(&self) -> usize {
+        self.calls
+    }
+}
+
+impl<R: Read> Read for FaultyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.calls += 1;
+        match self.calls - 1 == self.fail_at {
+            true => Err(io::Error::new(self.kind, "injected read failure")),
+            false => self.inner.read(buf),
+        }
+    }
+}
+
+/// A writer that fails on the call to `write` of the given 0-based index, and writes to `inner` otherwise.
+///
+/// Flushes are passed through and not counted.
+#[derive(Clone, Debug)]
+pub struct FaultyWriter<W: Write> {
+    inner: W,
+    fail_at: usize,
+    calls: usize,
+    kind: io::ErrorKind,
+}
+
+impl<W: Write> FaultyWriter<W> {
+    /// Fails with [`ErrorKind::Other`](io::ErrorKind::Other) unless given [`with_kind`](FaultyWriter::with_kind)
+    pub fn new(inner: W, fail_at: usize) -> Self {
+        Self {
+            inner,
+            fail_at,
+            calls: 0,
+            kind: io::ErrorKind::Other,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: io::ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Number of calls to `write` so far, including the failed one
+    pub fn calls(&self) -> usize {
+        self.calls
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for FaultyWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.calls += 1;
+        match self.calls - 1 == self.fail_at {
+            true => Err(io::Error::new(self.kind, "injected write failure")),
+            false => self.inner.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Inputs that every converter of bytes should handle
+pub mod corpus {
+    use super::ByteGenerator;
+
+    pub fn empty() -> Vec<u8> {
+        Vec::new()
+    }
+
+    pub fn single_byte() -> Vec<u8> {
+        vec![0x5a]
+    }
+
+    /// Each value from 0 to 255 once, in order
+    pub fn all_byte_values() -> Vec<u8> {
+        (0..=255).collect()
+    }
+
+    /// 1 MiB of pseudo-random bytes from `seed`
+    pub fn random_1mib(seed: u64) -> Vec<u8> {
+        ByteGenerator::new(seed).bytes(1 << 20)
+    }
+
+    /// 4 KiB of 0xff with a few other bytes, for long runs and all-ones digits
+    pub fn mostly_ff() -> Vec<u8> {
+        let mut bytes = vec![0xff; 4096];
+        for (i, byte) in [0x00, 0x7f, 0xfe, 0x80].into_iter().enumerate() {
+            bytes[1000 * i + 3] = byte;
+        }
+        bytes
+    }
+
+    /// Every input above by name, the random one from `seed`
+    pub fn all(seed: u64) -> Vec<(&'static str, Vec<u8>)> {
+        vec![
+            ("empty", empty()),
+            ("single_byte", single_byte()),
+            ("all_byte_values", all_byte_values()),
+            ("random_1mib", random_1mib(seed)),
+            ("mostly_ff", mostly_ff()),
+        ]
+    }
+}
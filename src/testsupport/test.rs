@@ -0,0 +1,95 @@
+use crate::testsupport::{ByteGenerator, ChunkedReader, FaultyReader, FaultyWriter, corpus};
+use std::io::{ErrorKind, Read, Write};
+
+#[test]
+fn test_byte_generator() {
+    // The sequence is part of the contract, so that seeds printed by old failures still reproduce them
+    assert_eq!(
+        ByteGenerator::new(1).bytes(8),
+        ByteGenerator::new(1).next_u64().to_le_bytes()
+    );
+    let mut generator = ByteGenerator::new(7);
+    let first = generator.bytes(13);
+    assert_eq!(first, ByteGenerator::new(7).bytes(13));
+    assert_ne!(first, generator.bytes(13));
+    assert_eq!(generator.seed(), 7);
+
+    let mut generator = ByteGenerator::new(0);
+    assert!(generator.bytes(64).iter().any(|&byte| byte != 0));
+    assert!((0..100).all(|_| generator.below(3) < 3));
+}
+
+#[test]
+fn test_chunked_reader() {
+    let data = (0..20).collect::<Vec<u8>>();
+    let mut reader = ChunkedReader::with_sizes(&data, &[1, 4, 7]);
+    let mut buffer = [0u8; 5];
+    let mut lengths = Vec::new();
+    let mut output = Vec::new();
+    loop {
+        let length = reader.read(&mut buffer).unwrap();
+        if length == 0 {
+            break;
+        }
+        lengths.push(length);
+        output.extend_from_slice(&buffer[..length]);
+    }
+    assert_eq!(lengths, [1, 4, 5, 1, 4, 5]);
+    assert_eq!(output, data);
+
+    let mut output = Vec::new();
+    ChunkedReader::new(&data, 3)
+        .read_to_end(&mut output)
+        .unwrap();
+    assert_eq!(output, data);
+}
+
+#[test]
+fn test_faulty_reader() {
+    let mut reader = FaultyReader::new(&b"abc"[..], 2).with_kind(ErrorKind::Interrupted);
+    let mut buffer = [0u8; 1];
+    assert_eq!(reader.read(&mut buffer).unwrap(), 1);
+    assert_eq!(reader.read(&mut buffer).unwrap(), 1);
+    let error = reader.read(&mut buffer).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Interrupted);
+    assert_eq!(reader.read(&mut buffer).unwrap(), 1);
+    assert_eq!(reader.calls(), 4);
+}
+
+#[test]
+fn test_faulty_writer() {
+    let mut writer = FaultyWriter::new(Vec::new(), 1);
+    writer.write_all(b"ab").unwrap();
+    assert_eq!(writer.write(b"cd").unwrap_err().kind(), ErrorKind::Other);
+    writer.write_all(b"ef").unwrap();
+    writer.flush().unwrap();
+    assert_eq!(writer.calls(), 3);
+    assert_eq!(writer.into_inner(), b"abef");
+}
+
+#[test]
+fn test_corpus() {
+    let inputs = corpus::all(1);
+    let lengths = inputs
+        .iter()
+        .map(|(name, bytes)| (*name, bytes.len()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lengths,
+        [
+            ("empty", 0),
+            ("single_byte", 1),
+            ("all_byte_values", 256),
+            ("random_1mib", 1 << 20),
+            ("mostly_ff", 4096)
+        ]
+    );
+    assert_eq!(corpus::random_1mib(1), inputs[3].1);
+    assert_ne!(corpus::random_1mib(2), inputs[3].1);
+    assert!(
+        corpus::all_byte_values()
+            .iter()
+            .enumerate()
+            .all(|(i, &b)| i == b as usize)
+    );
+}
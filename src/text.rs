@@ -0,0 +1,627 @@
+use crate::convert::{ConversionGraph, Converter, EdgeInfo};
+use crate::digits::{digit_value, is_hex_digit};
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use crate::utils::{RetryingReader, flush_if_short, is_ignorable};
+use crate::warning::{self, Warning};
+use std::io::{Read, Write};
+use std::mem;
+
+#[cfg(test)]
+mod test;
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Byte order of UTF-16 code units
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ByteOrder {
+    Little,
+    Big,
+}
+
+/// Options of the UTF-8 and UTF-16 converters
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TextOptions {
+    /// Replace invalid sequences with U+FFFD instead of failing
+    pub replace_invalid: bool,
+    /// Drop a byte order mark at the start of the input
+    pub strip_bom: bool,
+}
+
+/// Replaces an invalid sequence with U+FFFD if allowed, fails with `message` otherwise
+fn invalid(options: &TextOptions, out: &mut String, message: String) -> BytaryResult<()> {
+    if !options.replace_invalid {
+        return BytaryError::InvalidInputData(message).err();
+    }
+    warning::report(Warning::Replaced { count: 1 });
+    out.push(char::REPLACEMENT_CHARACTER);
+    Ok(())
+}
+
+/// Decodes UTF-8 fed in chunks, keeping a sequence split between chunks until it is complete
+struct Utf8Decoder {
+    options: TextOptions,
+    /// Bytes of an incomplete sequence at the end of the last chunk
+    carry: Vec<u8>,
+    /// Offset of the first byte of `carry` in the input
+    offset: usize,
+    at_start: bool,
+}
+
+impl Utf8Decoder {
+    fn new(options: TextOptions) -> Self {
+        Self {
+            options,
+            carry: Vec::new(),
+            offset: 0,
+            at_start: true,
+        }
+    }
+
+    /// Decodes `chunk` into `out`, `last` tells that the input ends after it
+    fn feed(&mut self, chunk: &[u8], last: bool, out: &mut String) -> BytaryResult<()> {
+        let mut data = mem::take(&mut self.carry);
+        data.extend_from_slice(chunk);
+        let mut rest = &data[..];
+
+        if self.at_start && self.options.strip_bom {
+            if !last && rest.len() < UTF8_BOM.len() && UTF8_BOM.starts_with(rest) {
+                self.carry = data;
+                return Ok(());
+            }
+            if rest.starts_with(UTF8_BOM) {
+                rest = &rest[UTF8_BOM.len()..];
+                self.offset += UTF8_BOM.len();
+            }
+        }
+        self.at_start = false;
+
+        loop {
+            let error = match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    out.push_str(text);
+                    self.offset += rest.len();
+                    return Ok(());
+                }
+                Err(error) => error,
+            };
+            let valid = error.valid_up_to();
+            out.push_str(std::str::from_utf8(&rest[..valid]).unwrap_or_default());
+            self.offset += valid;
+            rest = &rest[valid..];
+
+            let length = match error.error_len() {
+                Some(length) => length,
+                None if !last => {
+                    self.carry = rest.to_vec();
+                    return Ok(());
+                }
+                None => rest.len(),
+            };
+            invalid(
+                &self.options,
+                out,
+                format!("Invalid UTF-8 at offset {}", self.offset),
+            )?;
+            self.offset += length;
+            rest = &rest[length..];
+        }
+    }
+}
+
+/// Decodes UTF-16 fed in chunks, keeping a code unit or surrogate pair split between chunks
+struct Utf16Decoder {
+    order: ByteOrder,
+    options: TextOptions,
+    /// The first byte of a code unit split between chunks
+    pending: Option<u8>,
+    /// A high surrogate waiting for its low surrogate, with its offset
+    high: Option<(u16, usize)>,
+    offset: usize,
+    at_start: bool,
+}
+
+impl Utf16Decoder {
+    fn new(order: ByteOrder, options: TextOptions) -> Self {
+        Self {
+            order,
+            options,
+            pending: None,
+            high: None,
+            offset: 0,
+            at_start: true,
+        }
+    }
+
+    /// Decodes `chunk` into `out`, `last` tells that the input ends after it
+    fn feed(&mut self, chunk: &[u8], last: bool, out: &mut String) -> BytaryResult<()> {
+        for &byte in chunk {
+            let Some(first) = self.pending.take() else {
+                self.pending = Some(byte);
+                continue;
+            };
+            let offset = self.offset;
+            self.offset += 2;
+
+            if mem::take(&mut self.at_start) {
+                // A byte order mark overrides the byte order of the format
+                let order = match [first, byte] {
+                    [0xff, 0xfe] => Some(ByteOrder::Little),
+                    [0xfe, 0xff] => Some(ByteOrder::Big),
+                    _ => None,
+                };
+                if let Some(order) = order {
+                    self.order = order;
+                    if !self.options.strip_bom {
+                        out.push('\u{feff}');
+                    }
+                    continue;
+                }
+            }
+
+            let unit = match self.order {
+                ByteOrder::Little => u16::from_le_bytes([first, byte]),
+                ByteOrder::Big => u16::from_be_bytes([first, byte]),
+            };
+            self.unit(unit, offset, out)?;
+        }
+
+        if last {
+            if let Some((high, offset)) = self.high.take() {
+                self.lone_surrogate(high, offset, out)?;
+            }
+            if self.pending.take().is_some() {
+                invalid(
+                    &self.options,
+                    out,
+                    format!(
+                        "Invalid UTF-16 at offset {}: odd number of bytes",
+                        self.offset
+                    ),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn unit(&mut self, unit: u16, offset: usize, out: &mut String) -> BytaryResult<()> {
+        match (self.high.take(), unit) {
+            (Some((high, _)), 0xdc00..=0xdfff) => {
+                let code = 0x10000 + (((high as u32) - 0xd800) << 10) + (unit as u32 - 0xdc00);
+                out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            (Some((high, high_offset)), _) => {
+                self.lone_surrogate(high, high_offset, out)?;
+                self.unit(unit, offset, out)?;
+            }
+            (None, 0xd800..=0xdbff) => self.high = Some((unit, offset)),
+            (None, 0xdc00..=0xdfff) => self.lone_surrogate(unit, offset, out)?,
+            (None, _) => {
+                out.push(char::from_u32(unit as u32).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+        }
+        Ok(())
+    }
+
+    fn lone_surrogate(&self, unit: u16, offset: usize, out: &mut String) -> BytaryResult<()> {
+        invalid(
+            &self.options,
+            out,
+            format!(
+                "Invalid UTF-16 at offset {}: lone surrogate 0x{:04x}",
+                offset, unit
+            ),
+        )
+    }
+}
+
+/// Reads `input` in chunks, calling `feed` with each of them and a buffer for the decoded text,
+/// then calls `write` with the text.
+fn transcode(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    mut feed: impl FnMut(&[u8], bool, &mut String) -> BytaryResult<()>,
+    write: impl Fn(&str, &mut dyn Write) -> BytaryResult<()>,
+) -> BytaryResult<()> {
+    let mut input = RetryingReader::new(input);
+    let mut buffer = [0u8; 1024];
+    let mut text = String::new();
+    loop {
+        let length = input.read(&mut buffer)?;
+        text.clear();
+        feed(&buffer[..length], length == 0, &mut text)?;
+        write(&text, output)?;
+        if length == 0 {
+            break;
+        }
+        flush_if_short(output, length, buffer.len())?;
+    }
+    Ok(())
+}
+
+fn encode_utf16(order: ByteOrder, text: &str, output: &mut dyn Write) -> BytaryResult<()> {
+    for unit in text.encode_utf16() {
+        match order {
+            ByteOrder::Little => output.write_all(&unit.to_le_bytes())?,
+            ByteOrder::Big => output.write_all(&unit.to_be_bytes())?,
+        }
+    }
+    Ok(())
+}
+
+/// Copies UTF-8 text, failing at the first invalid sequence
+pub fn validate_utf8(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    validate_utf8_with_options(TextOptions::default())(input, output)
+}
+
+/// Create a UTF-8 validating converter with the given options
+pub fn validate_utf8_with_options(
+    options: TextOptions,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    let converter = ValidateUtf8 { options };
+    move |input, output| converter.convert(input, output)
+}
+
+/// Create a UTF-8 to UTF-16 converter with the given byte order and options.
+///
+/// No byte order mark is written.
+pub fn utf8_to_utf16_with_options(
+    order: ByteOrder,
+    options: TextOptions,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    let converter = Utf8ToUtf16 { order, options };
+    move |input, output| converter.convert(input, output)
+}
+
+/// Create a UTF-16 to UTF-8 converter with the given byte order and options.
+///
+/// A byte order mark at the start of the input overrides `order`.
+pub fn utf16_to_utf8_with_options(
+    order: ByteOrder,
+    options: TextOptions,
+) -> impl Fn(&mut dyn Read, &mut dyn Write) -> BytaryResult<()> + 'static {
+    let converter = Utf16ToUtf8 { order, options };
+    move |input, output| converter.convert(input, output)
+}
+
+/// The [`Converter`] of [`validate_utf8_with_options`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidateUtf8 {
+    pub options: TextOptions,
+}
+
+impl Converter for ValidateUtf8 {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut decoder = Utf8Decoder::new(self.options.clone());
+        transcode(
+            input,
+            output,
+            |chunk, last, out| decoder.feed(chunk, last, out),
+            |text, output| Ok(output.write_all(text.as_bytes())?),
+        )
+    }
+    fn name(&self) -> &str {
+        "validate_utf8"
+    }
+    /// Replacing invalid sequences or dropping a byte order mark changes the length
+    fn expansion_ratio(&self) -> Option<f64> {
+        (self.options == TextOptions::default()).then_some(1.0)
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`utf8_to_utf16_with_options`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Utf8ToUtf16 {
+    pub order: ByteOrder,
+    pub options: TextOptions,
+}
+
+impl Converter for Utf8ToUtf16 {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut decoder = Utf8Decoder::new(self.options.clone());
+        transcode(
+            input,
+            output,
+            |chunk, last, out| decoder.feed(chunk, last, out),
+            |text, output| encode_utf16(self.order, text, output),
+        )
+    }
+    fn name(&self) -> &str {
+        match self.order {
+            ByteOrder::Little => "utf8_to_utf16le",
+            ByteOrder::Big => "utf8_to_utf16be",
+        }
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`utf16_to_utf8_with_options`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Utf16ToUtf8 {
+    pub order: ByteOrder,
+    pub options: TextOptions,
+}
+
+impl Converter for Utf16ToUtf8 {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut decoder = Utf16Decoder::new(self.order, self.options.clone());
+        transcode(
+            input,
+            output,
+            |chunk, last, out| decoder.feed(chunk, last, out),
+            |text, output| Ok(output.write_all(text.as_bytes())?),
+        )
+    }
+    fn name(&self) -> &str {
+        match self.order {
+            ByteOrder::Little => "utf16le_to_utf8",
+            ByteOrder::Big => "utf16be_to_utf8",
+        }
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// Encodes UTF-8 text as UTF-16LE
+pub fn utf8_to_utf16le(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    utf8_to_utf16_with_options(ByteOrder::Little, TextOptions::default())(input, output)
+}
+
+/// Encodes UTF-8 text as UTF-16BE
+pub fn utf8_to_utf16be(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    utf8_to_utf16_with_options(ByteOrder::Big, TextOptions::default())(input, output)
+}
+
+/// Decodes UTF-16LE to UTF-8 text
+pub fn utf16le_to_utf8(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    utf16_to_utf8_with_options(ByteOrder::Little, TextOptions::default())(input, output)
+}
+
+/// Decodes UTF-16BE to UTF-8 text
+pub fn utf16be_to_utf8(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    utf16_to_utf8_with_options(ByteOrder::Big, TextOptions::default())(input, output)
+}
+
+/// The [`Converter`] of [`bytes_to_codepoints`], writing the scalar values of UTF-8 text
+/// as `U+XXXX` tokens separated by spaces, with at least 4 uppercase hex digits
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BytesToCodepoints {
+    pub options: TextOptions,
+}
+
+impl Converter for BytesToCodepoints {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut decoder = Utf8Decoder::new(self.options.clone());
+        let first = std::cell::Cell::new(true);
+        transcode(
+            input,
+            output,
+            |chunk, last, out| decoder.feed(chunk, last, out),
+            |text, output| {
+                for c in text.chars() {
+                    let separator = match first.replace(false) {
+                        true => "",
+                        false => " ",
+                    };
+                    write!(output, "{}U+{:04X}", separator, c as u32)?;
+                }
+                Ok(())
+            },
+        )
+    }
+    fn name(&self) -> &str {
+        "bytes_to_codepoints"
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// The [`Converter`] of [`codepoints_to_bytes`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CodepointsToBytes;
+
+impl Converter for CodepointsToBytes {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut input = RetryingReader::new(input);
+        let mut buffer = [0u8; 1024];
+        // The token being read, which may be split between reads, and its offset
+        let mut token = Vec::new();
+        let mut start = 0;
+        let mut offset = 0;
+        let mut bytes = Vec::new();
+        loop {
+            let length = input.read(&mut buffer)?;
+            for &byte in &buffer[..length] {
+                match is_ignorable(byte) {
+                    true if !token.is_empty() => {
+                        push_codepoint(&token, start, &mut bytes)?;
+                        token.clear();
+                    }
+                    true => {}
+                    false => {
+                        if token.is_empty() {
+                            start = offset;
+                        }
+                        token.push(byte);
+                    }
+                }
+                offset += 1;
+            }
+            if length == 0 {
+                if !token.is_empty() {
+                    push_codepoint(&token, start, &mut bytes)?;
+                }
+                output.write_all(&bytes)?;
+                break;
+            }
+            output.write_all(&bytes)?;
+            bytes.clear();
+            flush_if_short(output, length, buffer.len())?;
+        }
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        "codepoints_to_bytes"
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// Appends the UTF-8 encoding of the codepoint `token` at `offset` to `bytes`
+fn push_codepoint(token: &[u8], offset: usize, bytes: &mut Vec<u8>) -> BytaryResult<()> {
+    let text = String::from_utf8_lossy(token);
+    let invalid = |reason: &str| {
+        BytaryError::InvalidInputAt {
+            offset: offset as u64,
+            message: format!(
+                "invalid codepoint '{}' at offset {}: {}",
+                text, offset, reason
+            ),
+        }
+        .err()
+    };
+    let (digits, range) = match token {
+        [b'U' | b'u', b'+', digits @ ..] => (digits, 4..=6),
+        digits => (digits, 1..=6),
+    };
+    if !range.contains(&digits.len()) || !digits.iter().all(|&digit| is_hex_digit(digit)) {
+        return match token.starts_with(b"U+") || token.starts_with(b"u+") {
+            true => invalid("expected 4 to 6 hex digits after U+"),
+            false => invalid("expected U+ and 4 to 6 hex digits, or 1 to 6 hex digits"),
+        };
+    }
+    let value = digits.iter().fold(0, |value, &digit| {
+        value << 4 | u32::from(digit_value(digit, 16).unwrap_or(0))
+    });
+    match char::from_u32(value) {
+        Some(c) => bytes.extend_from_slice(c.encode_utf8(&mut [0u8; 4]).as_bytes()),
+        None if (0xd800..=0xdfff).contains(&value) => {
+            return invalid("surrogates are not characters");
+        }
+        None => return invalid("above U+10FFFF"),
+    }
+    Ok(())
+}
+
+/// Writes the scalar values of UTF-8 text as `U+XXXX` tokens separated by spaces,
+/// failing at the first invalid sequence
+///
+/// ```rust
+/// use bytary::text::bytes_to_codepoints;
+///
+/// let mut output = Vec::new();
+/// bytes_to_codepoints(&mut "A😀".as_bytes(), &mut output).unwrap();
+/// assert_eq!(output, b"U+0041 U+1F600");
+/// ```
+pub fn bytes_to_codepoints(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    BytesToCodepoints::default().convert(input, output)
+}
+
+/// Encodes whitespace-separated codepoints as UTF-8.
+///
+/// Codepoints are `U+` followed by 4 to 6 hex digits, or 1 to 6 hex digits alone, in any case.
+/// Surrogates and values above `U+10FFFF` are rejected.
+///
+/// ```rust
+/// use bytary::text::codepoints_to_bytes;
+///
+/// let mut output = Vec::new();
+/// codepoints_to_bytes(&mut &b"U+0041 u+1f600 e9"[..], &mut output).unwrap();
+/// assert_eq!(output, "A😀é".as_bytes());
+/// ```
+pub fn codepoints_to_bytes(input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+    CodepointsToBytes.convert(input, output)
+}
+
+impl ConversionGraph {
+    /// Adds the edges between bytes, UTF-8, UTF-16 and codepoints, replacing existing ones.
+    ///
+    /// Bytes are taken as UTF-8 text, so `Bytes -> Utf8` only validates. UTF-16 also
+    /// has direct edges to and from UTF-8, which skip that validation step.
+    pub fn add_text_edges(&mut self, options: TextOptions) {
+        self.add_utf8_edges(&options);
+        self.add_utf16_edges(Format::Utf16Le, &options);
+        self.add_utf16_edges(Format::Utf16Be, &options);
+        self.add_codepoints_edges(&options);
+    }
+
+    /// Adds the validating edges between bytes and UTF-8
+    pub(crate) fn add_utf8_edges(&mut self, options: &TextOptions) {
+        for from in [Format::Bytes, Format::Utf8] {
+            let to = match from {
+                Format::Bytes => Format::Utf8,
+                _ => Format::Bytes,
+            };
+            self.add_builtin_converter(
+                from,
+                to,
+                edge("validate_utf8"),
+                ValidateUtf8 {
+                    options: options.clone(),
+                },
+            );
+        }
+    }
+
+    /// Adds the edges between `format`, which is [`Format::Utf16Le`] or [`Format::Utf16Be`], and both bytes and UTF-8
+    pub(crate) fn add_utf16_edges(&mut self, format: Format, options: &TextOptions) {
+        let (order, encode, decode) = match format {
+            Format::Utf16Le => (ByteOrder::Little, "utf8_to_utf16le", "utf16le_to_utf8"),
+            _ => (ByteOrder::Big, "utf8_to_utf16be", "utf16be_to_utf8"),
+        };
+        for text in [Format::Bytes, Format::Utf8] {
+            self.add_builtin_converter(
+                text.clone(),
+                format.clone(),
+                edge(encode),
+                Utf8ToUtf16 {
+                    order,
+                    options: options.clone(),
+                },
+            );
+            self.add_builtin_converter(
+                format.clone(),
+                text,
+                edge(decode),
+                Utf16ToUtf8 {
+                    order,
+                    options: options.clone(),
+                },
+            );
+        }
+    }
+
+    /// Adds the edges between bytes and codepoints
+    pub(crate) fn add_codepoints_edges(&mut self, options: &TextOptions) {
+        self.add_builtin_converter(
+            Format::Bytes,
+            Format::Codepoints,
+            edge("bytes_to_codepoints"),
+            BytesToCodepoints {
+                options: options.clone(),
+            },
+        );
+        self.add_builtin_converter(
+            Format::Codepoints,
+            Format::Bytes,
+            edge("codepoints_to_bytes"),
+            CodepointsToBytes,
+        );
+    }
+}
+
+fn edge(name: &'static str) -> EdgeInfo {
+    EdgeInfo {
+        name,
+        lossy: false,
+        cost: 1,
+    }
+}
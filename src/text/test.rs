@@ -0,0 +1,263 @@
+use crate::convert::ConversionGraph;
+use crate::convert::Converter;
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use crate::testsupport::ChunkedReader;
+use crate::text::{
+    ByteOrder, BytesToCodepoints, CodepointsToBytes, TextOptions, utf8_to_utf16le,
+    utf16_to_utf8_with_options, utf16be_to_utf8, utf16le_to_utf8, validate_utf8,
+    validate_utf8_with_options,
+};
+use crate::warning::{self, Warning};
+
+const TEXT: &str = "Hello, Grüße, мир, 世界, 😀👍🏽!\n";
+
+fn convert(from: Format, to: Format, input: &[u8]) -> BytaryResult<Vec<u8>> {
+    let mut output = Vec::new();
+    let converter = ConversionGraph::default()
+        .get_converter(&from, &to)
+        .ok_or(BytaryError::UnsupportedConversion(from, to))?;
+    converter.convert(&mut &input[..], &mut output)?;
+    Ok(output)
+}
+
+#[test]
+fn test_round_trip() -> BytaryResult<()> {
+    let utf16le = TEXT
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<u8>>();
+    let utf16be = TEXT
+        .encode_utf16()
+        .flat_map(u16::to_be_bytes)
+        .collect::<Vec<u8>>();
+
+    assert_eq!(
+        convert(Format::Bytes, Format::Utf16Le, TEXT.as_bytes())?,
+        utf16le
+    );
+    assert_eq!(
+        convert(Format::Utf8, Format::Utf16Be, TEXT.as_bytes())?,
+        utf16be
+    );
+    assert_eq!(
+        convert(Format::Utf16Le, Format::Utf8, &utf16le)?,
+        TEXT.as_bytes()
+    );
+    assert_eq!(
+        convert(Format::Utf16Be, Format::Bytes, &utf16be)?,
+        TEXT.as_bytes()
+    );
+    assert_eq!(
+        convert(Format::Utf16Le, Format::Utf16Be, &utf16le)?,
+        utf16be
+    );
+    assert_eq!(
+        convert(Format::Bytes, Format::Utf8, TEXT.as_bytes())?,
+        TEXT.as_bytes()
+    );
+    assert_eq!(convert(Format::Utf8, Format::Bytes, b"")?, b"");
+    assert_eq!(convert(Format::Utf16Le, Format::Utf8, b"")?, b"");
+    Ok(())
+}
+
+#[test]
+fn test_split_chunks() -> BytaryResult<()> {
+    let utf16le = TEXT
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<u8>>();
+    // Every chunk size splits code units, surrogate pairs or UTF-8 sequences somewhere
+    for size in 1..8 {
+        let mut output = Vec::new();
+        utf16le_to_utf8(&mut ChunkedReader::new(&utf16le, size), &mut output)?;
+        assert_eq!(output, TEXT.as_bytes(), "chunks of {}", size);
+
+        let mut output = Vec::new();
+        utf8_to_utf16le(&mut ChunkedReader::new(TEXT.as_bytes(), size), &mut output)?;
+        assert_eq!(output, utf16le, "chunks of {}", size);
+
+        let mut output = Vec::new();
+        validate_utf8(&mut ChunkedReader::new(TEXT.as_bytes(), size), &mut output)?;
+        assert_eq!(output, TEXT.as_bytes(), "chunks of {}", size);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_bom() -> BytaryResult<()> {
+    // The byte order mark overrides the byte order of the format
+    let mut output = Vec::new();
+    utf16be_to_utf8(&mut &b"\xff\xfeh\x00i\x00"[..], &mut output)?;
+    assert_eq!(output, "\u{feff}hi".as_bytes());
+
+    let strip = TextOptions {
+        strip_bom: true,
+        ..TextOptions::default()
+    };
+    for size in 1..4 {
+        let mut output = Vec::new();
+        utf16_to_utf8_with_options(ByteOrder::Little, strip.clone())(
+            &mut ChunkedReader::new(b"\xfe\xff\x00h\x00i", size),
+            &mut output,
+        )?;
+        assert_eq!(output, b"hi");
+
+        let mut output = Vec::new();
+        validate_utf8_with_options(strip.clone())(
+            &mut ChunkedReader::new(b"\xef\xbb\xbfhi", size),
+            &mut output,
+        )?;
+        assert_eq!(output, b"hi");
+    }
+
+    // Only a mark at the start is stripped
+    let mut output = Vec::new();
+    validate_utf8_with_options(strip)(&mut &b"h\xef\xbb\xbfi"[..], &mut output)?;
+    assert_eq!(output, "h\u{feff}i".as_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_invalid() {
+    let error = convert(Format::Utf16Le, Format::Utf8, b"a\x00\x3d\xd8b\x00").unwrap_err();
+    assert!(matches!(error, BytaryError::InvalidInputData(_)));
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: Invalid UTF-16 at offset 2: lone surrogate 0xd83d"
+    );
+    let error = convert(Format::Utf16Be, Format::Utf8, b"\x00a\xde\x00").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: Invalid UTF-16 at offset 2: lone surrogate 0xde00"
+    );
+    let error = convert(Format::Utf16Le, Format::Utf8, b"a\x00b").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: Invalid UTF-16 at offset 2: odd number of bytes"
+    );
+    let error = convert(Format::Bytes, Format::Utf16Le, b"ab\xffc").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: Invalid UTF-8 at offset 2"
+    );
+    // A sequence cut off by the end of the input
+    let error = convert(
+        Format::Bytes,
+        Format::Utf8,
+        "a😀".as_bytes().split_last().unwrap().1,
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: Invalid UTF-8 at offset 1"
+    );
+}
+
+#[test]
+fn test_replace_invalid() -> BytaryResult<()> {
+    let options = TextOptions {
+        replace_invalid: true,
+        ..TextOptions::default()
+    };
+
+    let mut output = Vec::new();
+    let (result, warnings) = warning::collect(|| {
+        utf16_to_utf8_with_options(ByteOrder::Little, options.clone())(
+            &mut &b"\x3d\xd8a\x00\x00\xdc"[..],
+            &mut output,
+        )
+    });
+    result?;
+    assert_eq!(output, "\u{fffd}a\u{fffd}".as_bytes());
+    assert_eq!(
+        warnings,
+        [
+            Warning::Replaced { count: 1 },
+            Warning::Replaced { count: 1 }
+        ]
+    );
+
+    let mut output = Vec::new();
+    validate_utf8_with_options(options)(&mut &b"a\xffb\xe4\xb8"[..], &mut output)?;
+    assert_eq!(output, "a\u{fffd}b\u{fffd}".as_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_codepoints() -> BytaryResult<()> {
+    assert_eq!(
+        convert(Format::Bytes, Format::Codepoints, "A😀".as_bytes())?,
+        b"U+0041 U+1F600"
+    );
+    // A combining acute accent stays a codepoint of its own
+    let combining = "e\u{301}👍🏽";
+    assert_eq!(
+        convert(Format::Bytes, Format::Codepoints, combining.as_bytes())?,
+        b"U+0065 U+0301 U+1F44D U+1F3FD"
+    );
+    assert_eq!(convert(Format::Bytes, Format::Codepoints, b"")?, b"");
+
+    for text in [TEXT, combining] {
+        let codepoints = convert(Format::Utf8, Format::Codepoints, text.as_bytes())?;
+        assert_eq!(
+            convert(Format::Codepoints, Format::Utf8, &codepoints)?,
+            text.as_bytes()
+        );
+
+        // Sequences and tokens split between reads
+        let mut output = Vec::new();
+        BytesToCodepoints::default()
+            .convert(&mut ChunkedReader::new(text.as_bytes(), 3), &mut output)?;
+        assert_eq!(output, codepoints);
+        let mut output = Vec::new();
+        CodepointsToBytes.convert(&mut ChunkedReader::new(&codepoints, 5), &mut output)?;
+        assert_eq!(output, text.as_bytes());
+    }
+
+    // Any case, bare hex and any whitespace
+    assert_eq!(
+        convert(
+            Format::Codepoints,
+            Format::Bytes,
+            b" u+0041\n\tU+1f600  e9 10FFFF\n"
+        )?,
+        "A😀é\u{10ffff}".as_bytes()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_invalid_codepoints() {
+    let error = |input: &[u8]| {
+        convert(Format::Codepoints, Format::Bytes, input)
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        error(b"U+0041 U+D800"),
+        "Invalid input data: invalid codepoint 'U+D800' at offset 7: surrogates are not characters"
+    );
+    assert_eq!(
+        error(b"U+110000"),
+        "Invalid input data: invalid codepoint 'U+110000' at offset 0: above U+10FFFF"
+    );
+    assert_eq!(
+        error(b"U+41"),
+        "Invalid input data: invalid codepoint 'U+41' at offset 0: expected 4 to 6 hex digits after U+"
+    );
+    assert_eq!(
+        error(b"41 U+1234567"),
+        "Invalid input data: invalid codepoint 'U+1234567' at offset 3: expected 4 to 6 hex digits after U+"
+    );
+    assert_eq!(
+        error(b"0041 x41"),
+        "Invalid input data: invalid codepoint 'x41' at offset 5: expected U+ and 4 to 6 hex digits, or 1 to 6 hex digits"
+    );
+
+    let error = convert(Format::Bytes, Format::Codepoints, b"ab\xffc").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid input data: Invalid UTF-8 at offset 2"
+    );
+}
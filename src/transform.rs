@@ -0,0 +1,271 @@
+use crate::convert::Converter;
+use crate::error::{BytaryError, BytaryResult};
+use crate::utils::{flush_if_short, plural};
+use crate::warning;
+use crate::warning::Warning;
+use std::io;
+use std::io::{Read, Write};
+
+#[cfg(test)]
+mod test;
+
+/// Size of the chunks read from the input
+const CHUNK_SIZE: usize = 1024;
+
+/// Largest frame of [`interleave`] and [`deinterleave`], 1 GiB
+pub const MAX_FRAME_SIZE: usize = 1 << 30;
+
+/// The size of a frame of `channels` units of `unit` bytes, at most [`MAX_FRAME_SIZE`]
+fn frame_size(channels: usize, unit: usize) -> BytaryResult<usize> {
+    match channels.checked_mul(unit) {
+        Some(size) if size <= MAX_FRAME_SIZE => Ok(size),
+        _ => BytaryError::InvalidArgument(format!(
+            "frames of {} of {} are larger than {} bytes",
+            plural(channels, "channel"),
+            plural(unit, "byte"),
+            MAX_FRAME_SIZE
+        ))
+        .err(),
+    }
+}
+
+/// Keeps channel `select` of a stream of `channels` interleaved channels,
+/// each taking `unit` bytes per frame, see [`Deinterleave`].
+///
+/// ```rust
+/// use bytary::convert::Converter;
+/// use bytary::transform::deinterleave;
+///
+/// // Two 16-bit channels, left then right
+/// let mut output = Vec::new();
+/// deinterleave(2, 2, 1)
+///     .unwrap()
+///     .convert(&mut &[0x4c, 0x30, 0x52, 0x30, 0x4c, 0x31, 0x52, 0x31][..], &mut output)
+///     .unwrap();
+/// assert_eq!(output, [0x52, 0x30, 0x52, 0x31]);
+///
+/// assert!(deinterleave(2, 2, 2).is_err());
+/// ```
+pub fn deinterleave(channels: usize, unit: usize, select: usize) -> BytaryResult<Deinterleave> {
+    if channels == 0 || unit == 0 {
+        return BytaryError::InvalidArgument(
+            "channels and their unit must be at least 1".to_string(),
+        )
+        .err();
+    }
+    if select >= channels {
+        return BytaryError::InvalidArgument(format!(
+            "channel {} does not exist, there are {} counted from 0",
+            select,
+            plural(channels, "channel")
+        ))
+        .err();
+    }
+    frame_size(channels, unit)?;
+    Ok(Deinterleave {
+        channels,
+        unit,
+        select,
+    })
+}
+
+/// Weaves `inputs` together, writing `unit` bytes of each of them in turn, which undoes [`deinterleave`].
+///
+/// The output ends with the shortest input. If the inputs do not all end there, the last frame
+/// is incomplete and is dropped with a [`Warning::PartialFrame`], or is an error outside of
+/// [`warning::collect`].
+///
+/// ```rust
+/// use bytary::transform::interleave;
+/// use std::io::Read;
+///
+/// let inputs: Vec<Box<dyn Read>> = vec![Box::new(&b"L0L1"[..]), Box::new(&b"R0R1"[..])];
+/// let mut output = Vec::new();
+/// interleave(inputs, 2, &mut output).unwrap();
+/// assert_eq!(output, b"L0R0L1R1");
+/// ```
+pub fn interleave(
+    mut inputs: Vec<Box<dyn Read>>,
+    unit: usize,
+    output: &mut dyn Write,
+) -> BytaryResult<()> {
+    if inputs.is_empty() || unit == 0 {
+        return BytaryError::InvalidArgument(
+            "interleaving needs at least 1 input and a unit of at least 1".to_string(),
+        )
+        .err();
+    }
+    let frame_size = frame_size(inputs.len(), unit)?;
+    let mut frame = vec![0u8; frame_size];
+    loop {
+        let mut filled = 0;
+        for (input, unit) in inputs.iter_mut().zip(frame.chunks_mut(unit)) {
+            filled += read_full(input, unit)?;
+        }
+        if filled < frame_size {
+            return partial_frame(filled, frame_size);
+        }
+        output.write_all(&frame)?;
+    }
+}
+
+/// A [`Converter`] that writes the units of one of several interleaved channels, streaming.
+///
+/// The input is a sequence of frames of `channels * unit` bytes, each holding one unit of every channel
+/// in order. Units are kept whole across reads. If the input ends in the middle of a frame,
+/// the frame is dropped with a [`Warning::PartialFrame`], or is an error outside of [`warning::collect`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Deinterleave {
+    pub channels: usize,
+    pub unit: usize,
+    pub select: usize,
+}
+
+impl Converter for Deinterleave {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let frame_size = frame_size(self.channels, self.unit)?;
+        // Below the frame size, as the selected channel is one of them
+        let selected = self.select * self.unit..(self.select + 1) * self.unit;
+        let mut buffer = [0u8; CHUNK_SIZE];
+        // Position in the current frame, and the selected unit of it so far
+        let mut position = 0;
+        let mut pending = Vec::new();
+        let mut units = Vec::with_capacity(CHUNK_SIZE);
+
+        loop {
+            let length = input.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            for &byte in &buffer[..length] {
+                if selected.contains(&position) {
+                    pending.push(byte);
+                }
+                position += 1;
+                // The unit is only written once its frame is complete
+                if position == frame_size {
+                    units.append(&mut pending);
+                    position = 0;
+                }
+            }
+            output.write_all(&units)?;
+            units.clear();
+            flush_if_short(output, length, buffer.len())?;
+        }
+        match position {
+            0 => Ok(()),
+            _ => partial_frame(position, frame_size),
+        }
+    }
+    fn name(&self) -> &str {
+        "deinterleave"
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// Reverses the order of the bytes in each word of `width` bytes, which is 2, 4 or 8, see [`ByteSwap`].
+///
+/// ```rust
+/// use bytary::convert::Converter;
+/// use bytary::transform::byteswap;
+///
+/// let mut output = Vec::new();
+/// byteswap(4)
+///     .unwrap()
+///     .convert(&mut &[0x01, 0x02, 0x03, 0x04, 0x0a, 0x0b, 0x0c, 0x0d][..], &mut output)
+///     .unwrap();
+/// assert_eq!(output, [0x04, 0x03, 0x02, 0x01, 0x0d, 0x0c, 0x0b, 0x0a]);
+///
+/// assert!(byteswap(3).is_err());
+/// ```
+pub fn byteswap(width: usize) -> BytaryResult<ByteSwap> {
+    match width {
+        2 | 4 | 8 => Ok(ByteSwap { width }),
+        _ => BytaryError::InvalidArgument(format!(
+            "cannot swap words of {}, only of 2, 4 or 8",
+            plural(width, "byte")
+        ))
+        .err(),
+    }
+}
+
+/// A [`Converter`] that swaps the endianness of words of `width` bytes, streaming.
+///
+/// Words are kept whole across reads. If the input ends in the middle of a word, its bytes are written
+/// unchanged with a [`Warning::PartialWord`], or are an error outside of [`warning::collect`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ByteSwap {
+    pub width: usize,
+}
+
+impl Converter for ByteSwap {
+    fn convert(&self, input: &mut dyn Read, output: &mut dyn Write) -> BytaryResult<()> {
+        let mut buffer = [0u8; CHUNK_SIZE];
+        // Bytes read but not swapped yet, the start of a word split between reads
+        let mut pending = Vec::with_capacity(CHUNK_SIZE + self.width);
+
+        loop {
+            let length = input.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            pending.extend_from_slice(&buffer[..length]);
+            let ready = pending.len() - pending.len() % self.width;
+            for word in pending[..ready].chunks_exact_mut(self.width) {
+                word.reverse();
+            }
+            output.write_all(&pending[..ready])?;
+            pending.drain(..ready);
+            flush_if_short(output, length, buffer.len())?;
+        }
+        if !pending.is_empty() {
+            let bytes = pending.len();
+            if !warning::report(Warning::PartialWord {
+                bytes,
+                width: self.width,
+            }) {
+                return BytaryError::InvalidInputData(format!(
+                    "the input ends with a partial word of {}, a word has {}",
+                    plural(bytes, "byte"),
+                    self.width
+                ))
+                .err();
+            }
+            output.write_all(&pending)?;
+        }
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        "byteswap"
+    }
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// Reports the `bytes` of a partial frame, failing if nobody collects warnings
+fn partial_frame(bytes: usize, frame: usize) -> BytaryResult<()> {
+    match bytes == 0 || warning::report(Warning::PartialFrame { bytes, frame }) {
+        true => Ok(()),
+        false => BytaryError::InvalidInputData(format!(
+            "the input ends with a partial frame of {}, a frame has {}",
+            plural(bytes, "byte"),
+            frame
+        ))
+        .err(),
+    }
+}
+
+/// Reads until `buffer` is full or the input ends, returning the number of bytes read
+fn read_full(input: &mut dyn Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match input.read(&mut buffer[filled..])? {
+            0 => break,
+            length => filled += length,
+        }
+    }
+    Ok(filled)
+}
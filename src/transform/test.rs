@@ -0,0 +1,144 @@
+use crate::convert::Converter;
+use crate::error::BytaryResult;
+use crate::testsupport::ChunkedReader;
+use crate::transform::{Deinterleave, MAX_FRAME_SIZE, byteswap, deinterleave, interleave};
+use crate::warning;
+use crate::warning::Warning;
+use std::io::Read;
+
+fn channel(input: &[u8], channels: usize, unit: usize, select: usize) -> BytaryResult<Vec<u8>> {
+    let mut output = Vec::new();
+    deinterleave(channels, unit, select)?
+        .convert(&mut ChunkedReader::new(input, 3), &mut output)?;
+    Ok(output)
+}
+
+/// A 2-channel 16-bit signal, the left channel counting up and the right one down
+fn stereo(frames: u16) -> Vec<u8> {
+    (0..frames)
+        .flat_map(|i| [i.to_le_bytes(), (1000 - i).to_le_bytes()])
+        .flatten()
+        .collect()
+}
+
+#[test]
+fn test_deinterleave() -> BytaryResult<()> {
+    let input = stereo(100);
+    let left = (0..100u16).flat_map(u16::to_le_bytes).collect::<Vec<u8>>();
+    let right = (0..100u16)
+        .flat_map(|i| (1000 - i).to_le_bytes())
+        .collect::<Vec<u8>>();
+    assert_eq!(channel(&input, 2, 2, 0)?, left);
+    assert_eq!(channel(&input, 2, 2, 1)?, right);
+
+    // Byte-wise
+    assert_eq!(channel(b"aAbBcC", 2, 1, 1)?, b"ABC");
+    assert_eq!(channel(b"", 2, 1, 1)?, b"");
+    Ok(())
+}
+
+#[test]
+fn test_invalid_channel() {
+    assert_eq!(
+        deinterleave(2, 2, 2).unwrap_err().to_string(),
+        "Invalid argument: channel 2 does not exist, there are 2 channels counted from 0"
+    );
+    assert!(deinterleave(0, 1, 0).is_err());
+    assert!(deinterleave(2, 0, 0).is_err());
+
+    // Frames must fit in memory, and their size in a usize
+    assert_eq!(
+        deinterleave(2, MAX_FRAME_SIZE, 0).unwrap_err().to_string(),
+        "Invalid argument: frames of 2 channels of 1073741824 bytes are larger than 1073741824 bytes"
+    );
+    assert!(deinterleave(usize::MAX, 2, 0).is_err());
+    assert!(deinterleave(1, MAX_FRAME_SIZE, 0).is_ok());
+    let oversized = Deinterleave {
+        channels: usize::MAX,
+        unit: usize::MAX,
+        select: 0,
+    };
+    assert!(oversized.convert(&mut &b"ab"[..], &mut Vec::new()).is_err());
+}
+
+#[test]
+fn test_partial_frame() {
+    // The last frame has the whole unit of channel 0, but not the one of channel 1
+    let input = &stereo(3)[..10];
+    assert_eq!(
+        channel(input, 2, 2, 0).unwrap_err().to_string(),
+        "Invalid input data: the input ends with a partial frame of 2 bytes, a frame has 4"
+    );
+
+    let (result, warnings) = warning::collect(|| channel(input, 2, 2, 0));
+    assert_eq!(result.unwrap(), [0, 0, 1, 0]);
+    assert_eq!(warnings, [Warning::PartialFrame { bytes: 2, frame: 4 }]);
+}
+
+#[test]
+fn test_round_trip() -> BytaryResult<()> {
+    let input = (0..=255u8).cycle().take(3 * 4 * 50).collect::<Vec<u8>>();
+    let channels = (0..3)
+        .map(|select| channel(&input, 3, 4, select))
+        .collect::<BytaryResult<Vec<Vec<u8>>>>()?;
+    let inputs = channels
+        .into_iter()
+        .map(|data| Box::new(std::io::Cursor::new(data)) as Box<dyn Read>)
+        .collect();
+    let mut output = Vec::new();
+    interleave(inputs, 4, &mut output)?;
+    assert_eq!(output, input);
+    Ok(())
+}
+
+#[test]
+fn test_interleave_uneven() {
+    let inputs = || -> Vec<Box<dyn Read>> { vec![Box::new(&b"aaa"[..]), Box::new(&b"b"[..])] };
+    assert!(interleave(inputs(), 1, &mut Vec::new()).is_err());
+
+    let mut output = Vec::new();
+    let (result, warnings) = warning::collect(|| interleave(inputs(), 1, &mut output));
+    assert!(result.is_ok());
+    assert_eq!(output, b"ab");
+    assert_eq!(warnings, [Warning::PartialFrame { bytes: 1, frame: 2 }]);
+}
+
+fn swap(input: &[u8], width: usize, chunk: usize) -> BytaryResult<Vec<u8>> {
+    let mut output = Vec::new();
+    byteswap(width)?.convert(&mut ChunkedReader::new(input, chunk), &mut output)?;
+    Ok(output)
+}
+
+#[test]
+fn test_byteswap() -> BytaryResult<()> {
+    // 1 MiB, against swapping each word with from_le_bytes and to_be_bytes
+    let input = (0..1 << 18)
+        .flat_map(|i: u32| i.wrapping_mul(2654435761).to_le_bytes())
+        .collect::<Vec<u8>>();
+    let expected = input
+        .chunks_exact(4)
+        .flat_map(|word| u32::from_le_bytes(word.try_into().unwrap()).to_be_bytes())
+        .collect::<Vec<u8>>();
+    assert_eq!(swap(&input, 4, 1024)?, expected);
+    // Words split between reads
+    assert_eq!(swap(&input[..4096], 4, 3)?, expected[..4096]);
+
+    assert_eq!(swap(b"aAbB", 2, 1)?, b"AaBb");
+    assert_eq!(swap(b"01234567", 8, 5)?, b"76543210");
+    assert_eq!(swap(b"", 8, 5)?, b"");
+    assert!(byteswap(3).is_err());
+    assert!(byteswap(0).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_byteswap_partial_word() {
+    assert_eq!(
+        swap(b"abcdef", 4, 2).unwrap_err().to_string(),
+        "Invalid input data: the input ends with a partial word of 2 bytes, a word has 4"
+    );
+
+    let (result, warnings) = warning::collect(|| swap(b"abcdef", 4, 2));
+    assert_eq!(result.unwrap(), b"dcbaef");
+    assert_eq!(warnings, [Warning::PartialWord { bytes: 2, width: 4 }]);
+}
@@ -1,11 +1,176 @@
+use crate::digits::{digit_value, fullwidth_to_ascii, is_hex_digit, lookalike};
+use crate::error::{BytaryError, BytaryResult};
+use crate::warning;
+use crate::warning::Warning;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::fs;
 use std::io;
-use std::io::Write;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 
+#[cfg(test)]
+mod test;
+
+/// Flushes `writer` if the last read returned `length` bytes, less than the `capacity` of the buffer.
+///
+/// A short read means the input has no more data right now, e.g. a producer that paused,
+/// so whatever was converted so far should reach the output instead of waiting in buffers.
+pub(crate) fn flush_if_short(
+    writer: &mut dyn Write,
+    length: usize,
+    capacity: usize,
+) -> io::Result<()> {
+    match length < capacity {
+        true => writer.flush(),
+        false => Ok(()),
+    }
+}
+
+/// Whether text decoders skip `byte` as whitespace: a space, tab, carriage return or line feed.
+///
+/// Every text format uses this definition, in strict and lenient modes, so a file ending with
+/// a line break always decodes. Other control characters, like form feeds, are not whitespace.
+///
+/// ```rust
+/// use bytary::utils::is_ignorable;
+///
+/// assert!(is_ignorable(b'\n'));
+/// assert!(!is_ignorable(0x0c));
+/// ```
+pub const fn is_ignorable(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n')
+}
+
+/// Whether digit decoders accept `byte` between tokens besides whitespace: `:`, `-` or `,`.
+///
+/// Hex, bin and oct all accept them, and reject any other character that is not one of their digits.
+///
+/// ```rust
+/// use bytary::utils::is_separator;
+///
+/// assert!(is_separator(b'-'));
+/// assert!(!is_separator(b'#'));
+/// ```
+pub const fn is_separator(byte: u8) -> bool {
+    matches!(byte, b':' | b'-' | b',')
+}
+
+/// Reads the rest of `reader`, which starts at `offset` of the input, and fails with
+/// [`BytaryError::TrailingData`] at the first byte that is not [ignorable](is_ignorable).
+///
+/// ```rust
+/// use bytary::utils::require_eof;
+///
+/// assert!(require_eof(&mut &b" \r\n"[..], 4).is_ok());
+///
+/// let err = require_eof(&mut &b"\nxyz"[..], 4).unwrap_err();
+/// assert_eq!(err.to_string(), "Trailing data at offset 5");
+/// ```
+pub fn require_eof(reader: &mut dyn Read, offset: u64) -> BytaryResult<()> {
+    let mut buffer = [0u8; 1024];
+    let mut offset = offset;
+    loop {
+        let length = reader.read(&mut buffer)?;
+        if length == 0 {
+            return Ok(());
+        }
+        if let Some(index) = buffer[..length].iter().position(|&b| !is_ignorable(b)) {
+            return BytaryError::TrailingData {
+                offset: offset + index as u64,
+            }
+            .err();
+        }
+        offset += length as u64;
+    }
+}
+
+/// Finds where UTF-8 characters end in bytes given one at a time, across any number of writes.
+///
+/// Bytes that can not be part of a valid character, and the first byte after a truncated character,
+/// end a character on their own, so that invalid input never holds back a boundary for long.
+///
+/// ```rust
+/// use bytary::utils::Utf8Boundary;
+///
+/// let mut boundary = Utf8Boundary::new();
+/// let ends = "aé".bytes().map(|byte| boundary.push(byte)).collect::<Vec<bool>>();
+/// assert_eq!(ends, [true, false, true]);
+/// assert!(boundary.at_boundary());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Utf8Boundary {
+    /// Continuation bytes still expected in the current character
+    pending: u8,
+}
+
+impl Utf8Boundary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the next byte, returning true if it ends a character
+    pub fn push(&mut self, byte: u8) -> bool {
+        if self.pending > 0 && (0x80..=0xbf).contains(&byte) {
+            self.pending -= 1;
+            return self.pending == 0;
+        }
+        self.pending = match byte {
+            0xc2..=0xdf => 1,
+            0xe0..=0xef => 2,
+            0xf0..=0xf4 => 3,
+            _ => 0,
+        };
+        self.pending == 0
+    }
+
+    /// Whether the bytes so far end on a character boundary, with no character split
+    pub fn at_boundary(&self) -> bool {
+        self.pending == 0
+    }
+}
+
+/// Writes a space after every `space_interval` bytes and a line break after every `wrap_interval` bytes,
+/// 0 meaning never.
+///
+/// A space is only written once the next byte comes, and not at all where a line breaks, so that no
+/// line ends with one.
+///
+/// With [`utf8`](FormattedWriter::utf8), the intervals count characters instead, so that no space or
+/// line break is written inside a character, even one split between two writes.
+/// With [`timestamps`](FormattedWriter::timestamps), every line starts with the time its first input byte arrived.
+///
+/// ```rust
+/// use bytary::utils::FormattedWriter;
+/// use std::io::Write;
+///
+/// let mut formatted = FormattedWriter::new(Vec::new(), 2, 0);
+/// formatted.write_all(b"4869").unwrap();
+/// assert_eq!(formatted.position(), 4);
+///
+/// // The next document starts its spacing over
+/// formatted.get_mut().write_all(b"\n").unwrap();
+/// formatted.reset();
+/// formatted.write_all(b"216").unwrap();
+/// assert_eq!(formatted.into_inner().unwrap(), b"48 69\n21 6");
+/// ```
 pub struct FormattedWriter<W: Write> {
     target: W,
     space_interval: usize,
     wrap_interval: usize,
     current_position: usize,
+    /// Only set for UTF-8 output, whose positions count characters
+    boundary: Option<Utf8Boundary>,
+    timestamps: Option<LineTimestamps>,
+    /// Whether something was written on the current line, after which it needs no timestamp
+    line_started: bool,
+    /// Whether a space is due before the next byte
+    space_due: bool,
 }
 
 impl<W: Write> FormattedWriter<W> {
@@ -15,24 +180,817 @@ impl<W: Write> FormattedWriter<W> {
             space_interval,
             wrap_interval,
             current_position: 0,
+            boundary: None,
+            timestamps: None,
+            line_started: false,
+            space_due: false,
         }
     }
+
+    /// Count the output in UTF-8 characters instead of bytes, for text output
+    ///
+    /// ```rust
+    /// use bytary::utils::FormattedWriter;
+    /// use std::io::Write;
+    ///
+    /// let mut formatted = FormattedWriter::new(Vec::new(), 0, 2).utf8(true);
+    /// formatted.write_all("Hé!".as_bytes()).unwrap();
+    /// assert_eq!(formatted.into_inner().unwrap(), "Hé\n!".as_bytes());
+    /// ```
+    pub fn utf8(mut self, utf8: bool) -> Self {
+        self.boundary = utf8.then(Utf8Boundary::new);
+        self
+    }
+
+    /// Start every line with a timestamp and a space, written with its first byte
+    ///
+    /// ```rust
+    /// use bytary::utils::{Arrivals, FormattedWriter, LineTimestamps, TimestampStyle};
+    /// use std::io::Write;
+    /// use std::time::Duration;
+    ///
+    /// let arrivals = Arrivals::default();
+    /// arrivals.record(0, Duration::from_millis(5));
+    /// arrivals.record(2, Duration::from_millis(1250));
+    ///
+    /// // 2 hex digits per input byte
+    /// let timestamps = LineTimestamps::new(arrivals, TimestampStyle::Relative).ratio(Some(2.0));
+    /// let mut formatted = FormattedWriter::new(Vec::new(), 0, 4).timestamps(timestamps);
+    /// formatted.write_all(b"48692121").unwrap();
+    /// assert_eq!(formatted.into_inner().unwrap(), b"+0.005s 4869\n+1.250s 2121\n");
+    /// ```
+    pub fn timestamps(mut self, timestamps: LineTimestamps) -> Self {
+        self.timestamps = Some(timestamps);
+        self
+    }
+
+    /// Flushes the target, returning the error that dropping the writer would not report
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+
+    /// Flushes the target and returns it
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.finish()?;
+        Ok(self.target)
+    }
+
+    /// Starts the spacing and wrapping over, as if nothing had been written yet
+    pub fn reset(&mut self) {
+        self.current_position = 0;
+        self.space_due = false;
+    }
+
+    /// Number of bytes, or characters with [`utf8`](FormattedWriter::utf8), written since creation
+    /// or the last [`reset`](FormattedWriter::reset), not counting the spaces and line breaks
+    pub fn position(&self) -> usize {
+        self.current_position
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.target
+    }
+
+    /// Writing to the target directly does not move the [`position`](FormattedWriter::position)
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.target
+    }
 }
 
 impl<W: Write> Write for FormattedWriter<W> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         for &byte in buf {
+            if !self.line_started
+                && let Some(timestamps) = &self.timestamps
+            {
+                let prefix = timestamps.prefix(self.current_position);
+                self.target.write_all(prefix.as_bytes())?;
+            }
+            self.line_started = true;
+            if self.space_due {
+                self.target.write_all(b" ")?;
+                self.space_due = false;
+            }
             self.target.write_all(&[byte])?;
+            if let Some(boundary) = &mut self.boundary
+                && !boundary.push(byte)
+            {
+                continue;
+            }
             self.current_position += 1;
 
-            if self.space_interval > 0 && self.current_position % self.space_interval == 0 {
-                self.target.write_all(b" ")?;
+            if self.wrap_interval > 0 && self.current_position.is_multiple_of(self.wrap_interval) {
+                self.target.write_all(b"\n")?;
+                self.line_started = false;
+            } else if self.space_interval > 0
+                && self.current_position.is_multiple_of(self.space_interval)
+            {
+                self.space_due = true;
             }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+}
+
+/// Writes encoded bytes in lines of `bytes_per_line` bytes, each followed by two spaces
+/// and the printable ASCII rendering of the same bytes, with `.` for the others.
+///
+/// It is fed the raw bytes along with their encoding, which must take `chars_per_byte`
+/// characters per byte, like hex, bin or oct. Spaces are inserted every `space_interval`
+/// characters within a line, and the last line is padded so that its ASCII column is aligned.
+///
+/// ```rust
+/// use bytary::utils::AnnotatedFormatter;
+///
+/// let mut output = Vec::new();
+/// let mut formatter = AnnotatedFormatter::new(&mut output, 2, 2, 4);
+/// formatter.write_annotated(b"Hello", b"48656c6c6f").unwrap();
+/// formatter.finish().unwrap();
+/// assert_eq!(output, b"48 65 6c 6c  Hell\n6f           o");
+/// ```
+pub struct AnnotatedFormatter<W: Write> {
+    target: W,
+    chars_per_byte: usize,
+    space_interval: usize,
+    bytes_per_line: usize,
+    /// Raw bytes of the current line
+    line: Vec<u8>,
+}
+
+impl<W: Write> AnnotatedFormatter<W> {
+    pub fn new(
+        target: W,
+        chars_per_byte: usize,
+        space_interval: usize,
+        bytes_per_line: usize,
+    ) -> Self {
+        Self {
+            target,
+            chars_per_byte,
+            space_interval,
+            bytes_per_line: bytes_per_line.max(1),
+            line: Vec::new(),
+        }
+    }
 
-            if self.wrap_interval > 0 && self.current_position % self.wrap_interval == 0 {
+    /// Writes `encoded`, the encoding of `raw`, annotating each completed line
+    pub fn write_annotated(&mut self, raw: &[u8], encoded: &[u8]) -> io::Result<()> {
+        if encoded.len() != raw.len() * self.chars_per_byte {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "got {} for {}",
+                    plural(encoded.len(), "encoded character"),
+                    plural(raw.len(), "byte")
+                ),
+            ));
+        }
+        for (&byte, digits) in raw.iter().zip(encoded.chunks(self.chars_per_byte)) {
+            let position = self.line.len() * self.chars_per_byte;
+            for (i, &digit) in digits.iter().enumerate() {
+                if self.space_interval > 0
+                    && position + i > 0
+                    && (position + i).is_multiple_of(self.space_interval)
+                {
+                    self.target.write_all(b" ")?;
+                }
+                self.target.write_all(&[digit])?;
+            }
+            self.line.push(byte);
+            if self.line.len() == self.bytes_per_line {
+                self.write_ascii()?;
                 self.target.write_all(b"\n")?;
             }
         }
+        Ok(())
+    }
+
+    /// Flushes the target, leaving the current line open
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+
+    /// Pads and annotates the last line if it is partial, then flushes the target
+    pub fn finish(&mut self) -> io::Result<()> {
+        if !self.line.is_empty() {
+            let width = self.width(self.bytes_per_line);
+            let written = self.width(self.line.len());
+            self.target.write_all(&b" ".repeat(width - written))?;
+            self.write_ascii()?;
+        }
+        self.target.flush()
+    }
+
+    /// Width of the encoding of `bytes` bytes at the start of a line, with its spaces
+    fn width(&self, bytes: usize) -> usize {
+        let chars = bytes * self.chars_per_byte;
+        match self.space_interval {
+            0 => chars,
+            interval => chars + chars.saturating_sub(1) / interval,
+        }
+    }
+
+    fn write_ascii(&mut self) -> io::Result<()> {
+        let ascii = self
+            .line
+            .drain(..)
+            .map(|byte| match byte {
+                0x20..=0x7e => byte,
+                _ => b'.',
+            })
+            .collect::<Vec<u8>>();
+        self.target.write_all(b"  ")?;
+        self.target.write_all(&ascii)
+    }
+}
+
+/// A repeating pattern of group sizes and the separator between groups, which a [`GroupedWriter`]
+/// writes and a [`GroupCheckingReader`] checks.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GroupSchedule {
+    /// Sizes of the groups in characters, in order. Empty means no groups.
+    pub groups: Vec<usize>,
+    /// Written between two groups, never after the last one
+    pub separator: String,
+    /// Start over with the first group after the last one.
+    ///
+    /// Otherwise, the schedule is filled by exactly [`GroupSchedule::total`] characters.
+    pub repeat: bool,
+}
+
+impl GroupSchedule {
+    /// The number of characters that fill a schedule that does not repeat
+    pub fn total(&self) -> Option<usize> {
+        match self.repeat || self.groups.is_empty() {
+            true => None,
+            false => Some(self.groups.iter().sum()),
+        }
+    }
+
+    /// The number of separators written between `count` characters
+    pub fn separators(&self, count: u64) -> u64 {
+        let cycle = self.groups.iter().sum::<usize>() as u64;
+        if count == 0 || cycle == 0 {
+            return 0;
+        }
+        let (full, mut rest) = match self.repeat {
+            true => (count / cycle, count % cycle),
+            false => (0, count.min(cycle)),
+        };
+        // Groups that hold at least one of the characters
+        let mut groups = full * self.groups.len() as u64;
+        for &size in &self.groups {
+            if rest == 0 {
+                break;
+            }
+            rest = rest.saturating_sub(size as u64);
+            groups += 1;
+        }
+        groups - 1
+    }
+
+    /// The size of the group at `index`, `None` past the end of a schedule that does not repeat
+    fn group(&self, index: usize) -> Option<usize> {
+        match self.repeat {
+            true if !self.groups.is_empty() => Some(self.groups[index % self.groups.len()]),
+            _ => self.groups.get(index).copied(),
+        }
+    }
+}
+
+/// Shapes of hex output, see [`HexStyle::schedule`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HexStyle {
+    /// Pairs joined by `:`, like `1b:34:8f:ff:00:0e`
+    Mac,
+    /// Groups of 8, 4, 4, 4 and 12 digits joined by `-`, which fit exactly 16 bytes
+    Uuid,
+    /// Pairs joined by spaces, like `1b 34 8f`
+    Spaced,
+    /// Digits only, like `1b348f`
+    Continuous,
+}
+
+impl HexStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HexStyle::Mac => "mac",
+            HexStyle::Uuid => "uuid",
+            HexStyle::Spaced => "spaced",
+            HexStyle::Continuous => "continuous",
+        }
+    }
+
+    pub fn schedule(&self) -> GroupSchedule {
+        let (groups, separator, repeat) = match self {
+            HexStyle::Mac => (vec![2], ":", true),
+            HexStyle::Uuid => (vec![8, 4, 4, 4, 12], "-", false),
+            HexStyle::Spaced => (vec![2], " ", true),
+            HexStyle::Continuous => (vec![], "", true),
+        };
+        GroupSchedule {
+            groups,
+            separator: separator.to_string(),
+            repeat,
+        }
+    }
+}
+
+impl TryFrom<&str> for HexStyle {
+    type Error = BytaryError;
+    fn try_from(name: &str) -> BytaryResult<Self> {
+        match name {
+            "mac" => Ok(HexStyle::Mac),
+            "uuid" => Ok(HexStyle::Uuid),
+            "spaced" => Ok(HexStyle::Spaced),
+            "continuous" => Ok(HexStyle::Continuous),
+            _ => BytaryError::InvalidArgument(format!("unknown hex style '{}'", name)).err(),
+        }
+    }
+}
+
+impl Display for HexStyle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HexStyle {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        HexStyle::try_from(name.as_str()).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A writer that splits the characters written through it into groups, following a [`GroupSchedule`].
+///
+/// Unlike [`FormattedWriter`], separators only go between groups. Past the end of a schedule that
+/// does not repeat, characters are written without separators. The output of such a schedule is held
+/// back until [`finish`](GroupedWriter::finish), so that the caller can check [`GroupedWriter::count`]
+/// first and write nothing if the schedule is not filled exactly.
+///
+/// ```rust
+/// use bytary::utils::{GroupedWriter, HexStyle};
+/// use std::io::Write;
+///
+/// let mut output = Vec::new();
+/// let mut writer = GroupedWriter::new(&mut output, HexStyle::Mac.schedule());
+/// writer.write_all(b"1b348f").unwrap();
+/// writer.finish().unwrap();
+/// assert_eq!(output, b"1b:34:8f");
+/// ```
+pub struct GroupedWriter<W: Write> {
+    target: W,
+    schedule: GroupSchedule,
+    /// Index of the current group
+    index: usize,
+    /// Characters in the current group
+    filled: usize,
+    count: usize,
+    /// Output held back until `finish`, for schedules that do not repeat
+    held: Option<Vec<u8>>,
+}
+
+impl<W: Write> GroupedWriter<W> {
+    pub fn new(target: W, schedule: GroupSchedule) -> Self {
+        let held = schedule.total().map(Vec::with_capacity);
+        Self {
+            target,
+            schedule,
+            index: 0,
+            filled: 0,
+            count: 0,
+            held,
+        }
+    }
+
+    /// The number of characters written so far, without separators
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Writes the held back output and flushes the target
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(held) = self.held.take() {
+            self.target.write_all(&held)?;
+        }
+        self.target.flush()
+    }
+}
+
+impl<W: Write> Write for GroupedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut chunk = Vec::with_capacity(buf.len() * 2);
+        for &byte in buf {
+            if self.schedule.group(self.index) == Some(self.filled) {
+                self.index += 1;
+                self.filled = 0;
+                if self.schedule.group(self.index).is_some() {
+                    chunk.extend_from_slice(self.schedule.separator.as_bytes());
+                }
+            }
+            chunk.push(byte);
+            self.filled += 1;
+        }
+        self.count += buf.len();
+        match &mut self.held {
+            Some(held) => held.extend_from_slice(&chunk),
+            None => self.target.write_all(&chunk)?,
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.held {
+            Some(_) => Ok(()),
+            None => self.target.flush(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum GroupState {
+    /// Reading the digits of the current group
+    Group(usize),
+    /// Reading a separator, of which this many bytes matched
+    Separator(usize),
+    /// Reading whitespace after the last group
+    Trailing,
+}
+
+/// A reader that checks that hex digits read through it are grouped as a [`GroupSchedule`] says.
+///
+/// Whitespace is allowed after the last group. At the first mismatch, reading fails with
+/// [`io::ErrorKind::InvalidData`] and [`GroupCheckingReader::mismatch`] tells what was wrong.
+///
+/// ```rust
+/// use bytary::utils::{GroupCheckingReader, HexStyle};
+/// use std::io::Read;
+///
+/// let mut reader = GroupCheckingReader::new(&b"1b:34:8f\n"[..], HexStyle::Mac.schedule());
+/// assert!(reader.read_to_end(&mut Vec::new()).is_ok());
+///
+/// let mut reader = GroupCheckingReader::new(&b"1b:348f"[..], HexStyle::Mac.schedule());
+/// assert!(reader.read_to_end(&mut Vec::new()).is_err());
+/// assert_eq!(reader.mismatch(), Some("expected ':' at offset 5"));
+/// ```
+pub struct GroupCheckingReader<R: Read> {
+    inner: R,
+    schedule: GroupSchedule,
+    state: GroupState,
+    /// Index of the current group
+    index: usize,
+    offset: usize,
+    digits: usize,
+    mismatch: Option<String>,
+}
+
+impl<R: Read> GroupCheckingReader<R> {
+    pub fn new(inner: R, schedule: GroupSchedule) -> Self {
+        Self {
+            inner,
+            schedule,
+            state: GroupState::Group(0),
+            index: 0,
+            offset: 0,
+            digits: 0,
+            mismatch: None,
+        }
+    }
+
+    /// What did not match the schedule, `None` if everything read so far matched
+    pub fn mismatch(&self) -> Option<&str> {
+        self.mismatch.as_deref()
+    }
+
+    fn check(&mut self, byte: u8) -> Result<(), String> {
+        let offset = self.offset;
+        self.offset += 1;
+        let separator = self.schedule.separator.as_bytes();
+
+        match self.state {
+            GroupState::Trailing if is_ignorable(byte) => Ok(()),
+            GroupState::Trailing => Err(format!(
+                "unexpected '{}' after the last group at offset {}",
+                byte as char, offset
+            )),
+            GroupState::Separator(matched) if byte == separator[matched] => {
+                self.state = match matched + 1 == separator.len() {
+                    true => GroupState::Group(0),
+                    false => GroupState::Separator(matched + 1),
+                };
+                Ok(())
+            }
+            GroupState::Separator(matched) => Err(format!(
+                "expected '{}' at offset {}",
+                self.schedule.separator,
+                offset - matched
+            )),
+            GroupState::Group(filled) => {
+                let size = self.schedule.group(self.index);
+                if size == Some(filled) {
+                    let next = self.schedule.group(self.index + 1).is_some();
+                    if next && byte == separator[0] {
+                        self.index += 1;
+                        self.state = match separator.len() {
+                            1 => GroupState::Group(0),
+                            _ => GroupState::Separator(1),
+                        };
+                        return Ok(());
+                    }
+                    if is_ignorable(byte) {
+                        self.state = GroupState::Trailing;
+                        return Ok(());
+                    }
+                    return Err(match next {
+                        true => format!(
+                            "expected '{}' at offset {}",
+                            self.schedule.separator, offset
+                        ),
+                        false => format!(
+                            "unexpected '{}' after the last group at offset {}",
+                            byte as char, offset
+                        ),
+                    });
+                }
+                if is_hex_digit(byte) {
+                    self.state = GroupState::Group(filled + 1);
+                    self.digits += 1;
+                    return Ok(());
+                }
+                if size.is_none() && is_ignorable(byte) {
+                    self.state = GroupState::Trailing;
+                    return Ok(());
+                }
+                Err(format!("expected a hex digit at offset {}", offset))
+            }
+        }
+    }
+
+    /// Checks that the input ended with a complete group
+    fn check_end(&self) -> Result<(), String> {
+        let last = match self.schedule.total() {
+            Some(_) => self.index + 1 == self.schedule.groups.len(),
+            None => true,
+        };
+        let complete = match self.state {
+            GroupState::Trailing => last,
+            GroupState::Separator(_) => false,
+            // Nothing at all, which repeating schedules allow
+            GroupState::Group(0) if self.digits == 0 => self.schedule.total().is_none(),
+            // A trailing separator made of whitespace, like the space after the last pair
+            GroupState::Group(0) if self.schedule.separator.trim().is_empty() => {
+                self.schedule.total().is_none()
+            }
+            GroupState::Group(filled) => {
+                last && self
+                    .schedule
+                    .group(self.index)
+                    .is_none_or(|size| size == filled)
+            }
+        };
+        match complete && self.digits.is_multiple_of(2) {
+            true => Ok(()),
+            false => Err(format!(
+                "incomplete group at the end, offset {}",
+                self.offset
+            )),
+        }
+    }
+}
+
+impl<R: Read> Read for GroupCheckingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(mismatch) = &self.mismatch {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, mismatch.clone()));
+        }
+        let length = self.inner.read(buf)?;
+        let result = match length {
+            0 => self.check_end(),
+            _ => buf[..length].iter().try_for_each(|&byte| self.check(byte)),
+        };
+        match result {
+            Ok(()) => Ok(length),
+            Err(mismatch) => {
+                self.mismatch = Some(mismatch.clone());
+                Err(io::Error::new(io::ErrorKind::InvalidData, mismatch))
+            }
+        }
+    }
+}
+
+/// A writer that colors the digits of each byte in hex, bin or oct output with ANSI escape sequences.
+///
+/// NUL is dim, printable ASCII green, `0xff` red and other bytes with the high bit set yellow,
+/// the remaining bytes are not colored. Only digits are colored, so whitespace inserted by a
+/// [`FormattedWriter`] in front of this writer stays plain, even in the middle of a byte.
+///
+/// ```rust
+/// use bytary::utils::ColoredWriter;
+/// use std::io::Write;
+///
+/// let mut output = Vec::new();
+/// let mut writer = ColoredWriter::new(&mut output, 16, Some(2));
+/// writer.write_all(b"00 41").unwrap();
+/// writer.finish().unwrap();
+/// assert_eq!(output, b"\x1b[2m00\x1b[0m \x1b[32m41\x1b[0m");
+/// ```
+pub struct ColoredWriter<W: Write> {
+    target: W,
+    radix: u32,
+    /// Digits per byte, or `None` if each whitespace-separated token is one byte
+    digits: Option<usize>,
+    /// Output of the current byte, digits and the whitespace between them
+    pending: Vec<u8>,
+    pending_digits: usize,
+}
+
+impl<W: Write> ColoredWriter<W> {
+    pub fn new(target: W, radix: u32, digits: Option<usize>) -> Self {
+        Self {
+            target,
+            radix,
+            digits,
+            pending: Vec::new(),
+            pending_digits: 0,
+        }
+    }
+
+    fn color(byte: u8) -> Option<&'static [u8]> {
+        match byte {
+            0x00 => Some(b"\x1b[2m"),
+            0x20..=0x7e => Some(b"\x1b[32m"),
+            0xff => Some(b"\x1b[31m"),
+            0x80.. => Some(b"\x1b[33m"),
+            _ => None,
+        }
+    }
+
+    /// Writes the pending byte, wrapping each run of its digits in its color
+    fn write_pending(&mut self) -> io::Result<()> {
+        let value = self.pending.iter().try_fold(0u32, |value, &digit| {
+            match digit_value(digit, self.radix) {
+                Some(digit) => value.checked_mul(self.radix)?.checked_add(digit as u32),
+                None => Some(value),
+            }
+        });
+        let color = value
+            .and_then(|value| u8::try_from(value).ok())
+            .and_then(Self::color);
+
+        match color {
+            Some(color) => {
+                for run in self
+                    .pending
+                    .chunk_by(|a, b| a.is_ascii_whitespace() == b.is_ascii_whitespace())
+                {
+                    match run[0].is_ascii_whitespace() {
+                        true => self.target.write_all(run)?,
+                        false => {
+                            self.target.write_all(color)?;
+                            self.target.write_all(run)?;
+                            self.target.write_all(b"\x1b[0m")?;
+                        }
+                    }
+                }
+            }
+            None => self.target.write_all(&self.pending)?,
+        }
+        self.pending.clear();
+        self.pending_digits = 0;
+        Ok(())
+    }
+
+    /// Writes the last byte, which may be incomplete
+    pub fn finish(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            self.write_pending()?;
+        }
+        self.target.flush()
+    }
+}
+
+impl<W: Write> Write for ColoredWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            if digit_value(byte, self.radix).is_some() {
+                self.pending.push(byte);
+                self.pending_digits += 1;
+                if self.digits == Some(self.pending_digits) {
+                    self.write_pending()?;
+                }
+            } else if byte.is_ascii_whitespace() && self.digits.is_some() && self.pending_digits > 0
+            {
+                self.pending.push(byte);
+            } else {
+                if !self.pending.is_empty() {
+                    self.write_pending()?;
+                }
+                self.target.write_all(&[byte])?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+}
+
+/// A writer that surrounds the payload with a prefix and a suffix.
+///
+/// The prefix is written before the first payload byte, and the suffix by [`DelimitedWriter::finish`],
+/// or when the writer is dropped. If the payload is empty, neither is written unless `always` is set.
+pub struct DelimitedWriter<W: Write> {
+    target: W,
+    prefix: Vec<u8>,
+    suffix: Vec<u8>,
+    always: bool,
+    /// Write a line break before the suffix, unless the payload ends with one
+    suffix_on_new_line: bool,
+    /// Leave out the suffix if the payload ends with a line break
+    suffix_unless_new_line: bool,
+    last_byte: Option<u8>,
+    finished: bool,
+}
+
+impl<W: Write> DelimitedWriter<W> {
+    pub fn new(target: W, prefix: &[u8], suffix: &[u8], always: bool) -> Self {
+        Self {
+            target,
+            prefix: prefix.to_vec(),
+            suffix: suffix.to_vec(),
+            always,
+            suffix_on_new_line: false,
+            suffix_unless_new_line: false,
+            last_byte: None,
+            finished: false,
+        }
+    }
+
+    /// Make sure the suffix starts on a new line, like the end line of a PEM block
+    pub fn suffix_on_new_line(mut self) -> Self {
+        self.suffix_on_new_line = true;
+        self
+    }
+
+    /// Leave out the suffix if the payload already ends with a line break, like a line terminator
+    ///
+    /// ```rust
+    /// use bytary::utils::DelimitedWriter;
+    /// use std::io::Write;
+    ///
+    /// let mut output = Vec::new();
+    /// let mut writer = DelimitedWriter::new(&mut output, b"", b"\n", false).suffix_unless_new_line();
+    /// writer.write_all(b"4142\n").unwrap();
+    /// drop(writer);
+    /// assert_eq!(output, b"4142\n");
+    /// ```
+    pub fn suffix_unless_new_line(mut self) -> Self {
+        self.suffix_unless_new_line = true;
+        self
+    }
+
+    /// Writes the suffix, and the prefix if it has not been written yet and `always` is set.
+    ///
+    /// Calling this more than once has no effect.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        if self.last_byte.is_none() {
+            if !self.always {
+                return self.target.flush();
+            }
+            self.target.write_all(&self.prefix)?;
+        }
+        if self.suffix_on_new_line && self.last_byte.is_some_and(|b| b != b'\n') {
+            self.target.write_all(b"\n")?;
+        }
+        if !(self.suffix_unless_new_line && self.last_byte == Some(b'\n')) {
+            self.target.write_all(&self.suffix)?;
+        }
+        self.target.flush()
+    }
+}
+
+impl<W: Write> Write for DelimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(&last) = buf.last() else {
+            return Ok(0);
+        };
+        if self.last_byte.is_none() {
+            self.target.write_all(&self.prefix)?;
+        }
+        self.target.write_all(buf)?;
+        self.last_byte = Some(last);
         Ok(buf.len())
     }
 
@@ -40,3 +998,1821 @@ impl<W: Write> Write for FormattedWriter<W> {
         self.target.flush()
     }
 }
+
+impl<W: Write> Drop for DelimitedWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// A reader that removes comments from text input, from `#` or `//` to the end of the line.
+///
+/// Line breaks are kept, and everything outside comments is passed through unchanged,
+/// so invalid characters are still reported by the decoder.
+pub struct CommentStrippingReader<R: Read> {
+    inner: R,
+    in_comment: bool,
+    /// The last byte was a `/` that may start a comment
+    slash: bool,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> CommentStrippingReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            in_comment: false,
+            slash: false,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Reads and filters the next chunk, returns false at the end of the input
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut chunk = [0u8; 4096];
+        self.buffer.clear();
+        self.position = 0;
+
+        while self.buffer.is_empty() {
+            let length = self.inner.read(&mut chunk)?;
+            if length == 0 {
+                if std::mem::take(&mut self.slash) {
+                    self.buffer.push(b'/');
+                }
+                return Ok(!self.buffer.is_empty());
+            }
+
+            for &byte in &chunk[..length] {
+                if self.in_comment {
+                    if byte == b'\n' {
+                        self.in_comment = false;
+                        self.buffer.push(byte);
+                    }
+                    continue;
+                }
+                if std::mem::take(&mut self.slash) {
+                    if byte == b'/' {
+                        self.in_comment = true;
+                        continue;
+                    }
+                    self.buffer.push(b'/');
+                }
+                match byte {
+                    b'#' => self.in_comment = true,
+                    b'/' => self.slash = true,
+                    _ => self.buffer.push(byte),
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for CommentStrippingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.position >= self.buffer.len() && !self.fill()? {
+            return Ok(0);
+        }
+        let length = buf.len().min(self.buffer.len() - self.position);
+        buf[..length].copy_from_slice(&self.buffer[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+/// Characters that rich-text editors add to pasted text, removed by [`cleanup_pasted_text`]:
+/// the byte order mark, no-break space, soft hyphen, zero-width space, zero-width non-joiner,
+/// zero-width joiner, word joiner and the curly single and double quotes.
+pub const PASTE_NUISANCES: [char; 11] = [
+    '\u{feff}', '\u{a0}', '\u{ad}', '\u{200b}', '\u{200c}', '\u{200d}', '\u{2060}', '\u{2018}',
+    '\u{2019}', '\u{201c}', '\u{201d}',
+];
+
+/// Removes the [`PASTE_NUISANCES`] from text, leaving everything else to the decoder.
+///
+/// Once the input ends, the number of removed characters is reported as a [`Warning::Cleaned`],
+/// if warnings are [collected](warning::collect). Full-width forms are only replaced when asked
+/// with [`fullwidth`](PasteCleanupReader::fullwidth), and other non-ASCII characters only skipped
+/// when asked with [`skip_non_ascii`](PasteCleanupReader::skip_non_ascii).
+///
+/// ```rust
+/// use bytary::utils::cleanup_pasted_text;
+/// use std::io::Read;
+///
+/// let mut output = String::new();
+/// cleanup_pasted_text("\u{feff}\u{201c}1b\u{a0}34\u{201d}".as_bytes())
+///     .read_to_string(&mut output)
+///     .unwrap();
+/// assert_eq!(output, "1b34");
+/// ```
+pub fn cleanup_pasted_text<R: Read>(reader: R) -> PasteCleanupReader<R> {
+    PasteCleanupReader {
+        inner: reader,
+        pending: Vec::new(),
+        fullwidth: false,
+        skip_non_ascii: false,
+        removed: 0,
+        replaced: 0,
+        skipped: 0,
+        reported: false,
+        buffer: Vec::new(),
+        position: 0,
+    }
+}
+
+/// The reader of [`cleanup_pasted_text`]
+pub struct PasteCleanupReader<R: Read> {
+    inner: R,
+    /// The start of a character that the last read split
+    pending: Vec<u8>,
+    fullwidth: bool,
+    skip_non_ascii: bool,
+    removed: usize,
+    replaced: usize,
+    skipped: usize,
+    /// Whether the end of the input was reached and the changes reported
+    reported: bool,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> PasteCleanupReader<R> {
+    /// Also replaces the [full-width forms](fullwidth_to_ascii) of ASCII characters, like `ＡＢ１２`,
+    /// with the ASCII ones, reported as a [`Warning::Transliterated`].
+    ///
+    /// ```rust
+    /// use bytary::utils::cleanup_pasted_text;
+    /// use std::io::Read;
+    ///
+    /// let mut output = String::new();
+    /// cleanup_pasted_text("ＡＢ１２\u{3000}34".as_bytes())
+    ///     .fullwidth(true)
+    ///     .read_to_string(&mut output)
+    ///     .unwrap();
+    /// assert_eq!(output, "AB12 34");
+    /// ```
+    pub fn fullwidth(mut self, fullwidth: bool) -> Self {
+        self.fullwidth = fullwidth;
+        self
+    }
+
+    /// Also skips the non-ASCII characters that are neither removed nor replaced, reported as a
+    /// [`Warning::NonAscii`] that counts characters, and every byte that is not valid UTF-8.
+    ///
+    /// [Look-alikes](crate::digits::lookalike) of ASCII characters are kept for the decoder to
+    /// reject, since they are more likely data than noise.
+    ///
+    /// ```rust
+    /// use bytary::utils::cleanup_pasted_text;
+    /// use std::io::Read;
+    ///
+    /// let mut output = String::new();
+    /// cleanup_pasted_text("1b\u{2014}34 \u{2192}".as_bytes())
+    ///     .skip_non_ascii(true)
+    ///     .read_to_string(&mut output)
+    ///     .unwrap();
+    /// assert_eq!(output, "1b34 ");
+    /// ```
+    pub fn skip_non_ascii(mut self, skip_non_ascii: bool) -> Self {
+        self.skip_non_ascii = skip_non_ascii;
+        self
+    }
+
+    /// Number of characters removed so far
+    pub fn removed(&self) -> usize {
+        self.removed
+    }
+
+    /// Number of full-width characters replaced so far
+    pub fn replaced(&self) -> usize {
+        self.replaced
+    }
+
+    /// Number of non-ASCII characters skipped so far
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Reads and cleans the next chunk, returns false at the end of the input
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut chunk = [0u8; 4096];
+        self.buffer.clear();
+        self.position = 0;
+
+        while self.buffer.is_empty() {
+            let length = self.inner.read(&mut chunk)?;
+            if length == 0 {
+                // A truncated character is left for the decoder to reject
+                self.buffer.append(&mut self.pending);
+                if self.buffer.is_empty() && !self.reported {
+                    self.reported = true;
+                    if self.removed > 0 {
+                        warning::report(Warning::Cleaned {
+                            count: self.removed,
+                        });
+                    }
+                    if self.replaced > 0 {
+                        warning::report(Warning::Transliterated {
+                            count: self.replaced,
+                        });
+                    }
+                    if self.skipped > 0 {
+                        warning::report(Warning::NonAscii {
+                            count: self.skipped,
+                        });
+                    }
+                }
+                return Ok(!self.buffer.is_empty());
+            }
+
+            let mut data = std::mem::take(&mut self.pending);
+            data.extend_from_slice(&chunk[..length]);
+            let mut i = 0;
+            while i < data.len() {
+                let mut width = match data[i] {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 1,
+                };
+                if i + width > data.len() {
+                    self.pending = data[i..].to_vec();
+                    break;
+                }
+                let character = match std::str::from_utf8(&data[i..i + width]) {
+                    Ok(c) => c.chars().next().filter(|c| !c.is_ascii()),
+                    // Not a character, the bytes after the first are looked at on their own
+                    Err(_) => {
+                        width = 1;
+                        None
+                    }
+                };
+                let ascii = character
+                    .filter(|_| self.fullwidth)
+                    .and_then(fullwidth_to_ascii);
+                match (character, ascii) {
+                    (Some(c), _) if PASTE_NUISANCES.contains(&c) => self.removed += 1,
+                    (_, Some(ascii)) => {
+                        self.replaced += 1;
+                        self.buffer.push(ascii as u8);
+                    }
+                    (Some(c), _) if self.skip_non_ascii && lookalike(c).is_none() => {
+                        self.skipped += 1
+                    }
+                    (None, _) if self.skip_non_ascii && !data[i].is_ascii() => self.skipped += 1,
+                    _ => self.buffer.extend_from_slice(&data[i..i + width]),
+                }
+                i += width;
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for PasteCleanupReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.position >= self.buffer.len() && !self.fill()? {
+            return Ok(0);
+        }
+        let length = buf.len().min(self.buffer.len() - self.position);
+        buf[..length].copy_from_slice(&self.buffer[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+/// A reader that counts the bytes read through it
+pub struct CountingReader<R: Read> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    /// Returns the number of bytes read so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.inner.read(buf)?;
+        self.count += length as u64;
+        Ok(length)
+    }
+}
+
+/// A writer that counts the bytes written through it
+pub struct CountingWriter<W: Write> {
+    target: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    pub fn new(target: W) -> Self {
+        Self { target, count: 0 }
+    }
+
+    /// Returns the number of bytes written so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let length = self.target.write(buf)?;
+        self.count += length as u64;
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+}
+
+/// A writer that compares what is written with an expected value, without keeping any of it.
+///
+/// Every byte is compared whether earlier ones differed or not, and differences are only accumulated,
+/// so the time taken tells the lengths but not where the first difference is.
+///
+/// ```rust
+/// use bytary::utils::ExpectWriter;
+/// use std::io::Write;
+///
+/// let mut writer = ExpectWriter::new(b"4869".to_vec());
+/// writer.write_all(b"48").unwrap();
+/// writer.write_all(b"69").unwrap();
+/// assert!(writer.matches());
+/// ```
+pub struct ExpectWriter {
+    expected: Vec<u8>,
+    count: u64,
+    /// The bits that differed in any byte so far
+    difference: u8,
+}
+
+impl ExpectWriter {
+    pub fn new(expected: Vec<u8>) -> Self {
+        Self {
+            expected,
+            count: 0,
+            difference: 0,
+        }
+    }
+
+    /// Returns the number of bytes written so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the length of the expected value
+    pub fn expected_len(&self) -> u64 {
+        self.expected.len() as u64
+    }
+
+    /// Whether the bytes written so far are exactly the expected value
+    pub fn matches(&self) -> bool {
+        (self.difference == 0) & (self.count == self.expected_len())
+    }
+}
+
+impl Write for ExpectWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            // Bytes past the end are compared with 0, the lengths differ anyway
+            let expected = self.expected.get(self.count as usize).copied().unwrap_or(0);
+            self.difference |= byte ^ expected;
+            self.count += 1;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A handle to cancel conversions reading through a [`CancellableReader`], shared between threads.
+///
+/// ```rust
+/// use bytary::convert::ConversionGraph;
+/// use bytary::error::BytaryError;
+/// use bytary::format::Format::*;
+/// use bytary::utils::{CancelToken, CancellableReader};
+///
+/// let token = CancelToken::new();
+/// let mut input = CancellableReader::new(std::io::repeat(0), token.clone());
+/// token.cancel();
+///
+/// let converter = ConversionGraph::default().get_converter(&Bytes, &Hex).unwrap();
+/// let result = converter.convert(&mut input, &mut std::io::sink());
+/// assert!(matches!(result, Err(BytaryError::Cancelled)));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes every following read of the readers using this token fail
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The payload of the io error returned by a cancelled [`CancellableReader`]
+#[derive(Debug)]
+struct CancelledError;
+
+impl Display for CancelledError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for CancelledError {}
+
+/// Returns true if `e` was returned by a cancelled [`CancellableReader`]
+pub(crate) fn is_cancellation(e: &io::Error) -> bool {
+    e.get_ref()
+        .is_some_and(|inner| inner.is::<CancelledError>())
+}
+
+/// A reader that fails once its [`CancelToken`] is cancelled.
+///
+/// Converters propagate the error, which becomes [`BytaryError::Cancelled`].
+pub struct CancellableReader<R: Read> {
+    inner: R,
+    token: CancelToken,
+}
+
+impl<R: Read> CancellableReader<R> {
+    pub fn new(inner: R, token: CancelToken) -> Self {
+        Self { inner, token }
+    }
+}
+
+impl<R: Read> Read for CancellableReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.token.is_cancelled() {
+            return Err(io::Error::other(CancelledError));
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// The payload of the io error returned by a [`LimitedReader`] whose input is too large
+#[derive(Debug)]
+struct LimitExceededError(u64);
+
+impl Display for LimitExceededError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "input larger than {}", plural(self.0, "byte"))
+    }
+}
+
+impl std::error::Error for LimitExceededError {}
+
+/// The limit of the [`LimitedReader`] that returned `e`, if it did
+pub(crate) fn input_limit(e: &io::Error) -> Option<u64> {
+    e.get_ref()
+        .and_then(|inner| inner.downcast_ref::<LimitExceededError>())
+        .map(|LimitExceededError(limit)| *limit)
+}
+
+/// The payload of the io error returned by [`into_io_error`]
+#[derive(Debug)]
+struct CarriedError(BytaryError);
+
+impl Display for CarriedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for CarriedError {}
+
+/// Carries `e` through an io error, for writers and readers that convert, which
+/// [`BytaryError::from`] turns back into `e`. IO errors are returned as they are.
+pub fn into_io_error(e: BytaryError) -> io::Error {
+    match e {
+        BytaryError::Io(e) => e,
+        e => io::Error::other(CarriedError(e)),
+    }
+}
+
+/// The error carried by `e` if it was returned by [`into_io_error`], or else `e` itself
+pub(crate) fn carried_error(e: io::Error) -> Result<BytaryError, io::Error> {
+    match e.get_ref().is_some_and(|inner| inner.is::<CarriedError>()) {
+        true => match e.into_inner().map(|inner| inner.downcast::<CarriedError>()) {
+            Some(Ok(carried)) => Ok(carried.0),
+            _ => unreachable!("the payload was checked"),
+        },
+        false => Err(e),
+    }
+}
+
+/// A reader that fails as soon as its input turns out to be larger than `limit` bytes,
+/// to bound the memory of converters that buffer their input.
+///
+/// It never reads more than one byte past the limit. Converters propagate the error,
+/// which becomes [`BytaryError::InputLimitExceeded`].
+///
+/// ```rust
+/// use bytary::error::BytaryError;
+/// use bytary::utils::LimitedReader;
+///
+/// let mut output = Vec::new();
+/// let result = bytary::builtins::base64_to_bytes(&mut LimitedReader::new(&b"SGk="[..], 3), &mut output);
+/// assert!(matches!(result, Err(BytaryError::InputLimitExceeded(3))));
+/// ```
+pub struct LimitedReader<R: Read> {
+    inner: R,
+    limit: u64,
+    count: u64,
+}
+
+impl<R: Read> LimitedReader<R> {
+    pub fn new(inner: R, limit: u64) -> Self {
+        Self {
+            inner,
+            limit,
+            count: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Once exceeded, the limit stays exceeded without reading any further
+        if self.count > self.limit {
+            return Err(io::Error::other(LimitExceededError(self.limit)));
+        }
+        // One byte more than allowed tells an input of exactly `limit` bytes from a larger one
+        let allowed = (self.limit - self.count).saturating_add(1);
+        let length = buf
+            .len()
+            .min(usize::try_from(allowed).unwrap_or(usize::MAX));
+        let length = self.inner.read(&mut buf[..length])?;
+        self.count += length as u64;
+        match self.count > self.limit {
+            true => Err(io::Error::other(LimitExceededError(self.limit))),
+            false => Ok(length),
+        }
+    }
+}
+
+/// The payload of the io error returned by a full [`HeadWriter`]
+#[derive(Debug)]
+struct OutputLimitError;
+
+impl Display for OutputLimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "output limit reached")
+    }
+}
+
+impl std::error::Error for OutputLimitError {}
+
+/// Returns true if `e` was returned by a full [`HeadWriter`]
+pub(crate) fn is_output_limit(e: &io::Error) -> bool {
+    e.get_ref()
+        .is_some_and(|inner| inner.is::<OutputLimitError>())
+}
+
+/// A writer that writes the first `limit` bytes to its target, then fails to tell the converter to stop.
+///
+/// The error becomes [`BytaryError::OutputLimitReached`], which the converters of
+/// [`try_get_converter`](crate::convert::ConversionGraph::try_get_converter) treat as success,
+/// so that they return without reading the rest of the input.
+///
+/// ```rust
+/// use bytary::convert::ConversionGraph;
+/// use bytary::format::Format::*;
+/// use bytary::utils::HeadWriter;
+///
+/// let converter = ConversionGraph::default().get_converter(&Bytes, &Hex).unwrap();
+/// let mut head = HeadWriter::new(Vec::new(), 3);
+/// converter.convert(&mut &b"Hello"[..], &mut head).unwrap();
+/// assert!(head.is_full());
+/// assert_eq!(head.into_inner(), b"486");
+/// ```
+pub struct HeadWriter<W: Write> {
+    target: W,
+    remaining: u64,
+}
+
+impl<W: Write> HeadWriter<W> {
+    pub fn new(target: W, limit: u64) -> Self {
+        Self {
+            target,
+            remaining: limit,
+        }
+    }
+
+    /// Whether the limit was reached, so that nothing more is written
+    pub fn is_full(&self) -> bool {
+        self.remaining == 0
+    }
+
+    pub fn into_inner(self) -> W {
+        self.target
+    }
+}
+
+impl<W: Write> Write for HeadWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            return Err(io::Error::other(OutputLimitError));
+        }
+        let length = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let length = self.target.write(&buf[..length])?;
+        self.remaining -= length as u64;
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+}
+
+/// A writer that keeps the last `size` bytes written to it, and writes them to its target on
+/// [`finish`](TailWriter::finish), holding no more than `size` bytes however much is written, and
+/// no more than was written however large `size` is.
+///
+/// ```rust
+/// use bytary::utils::TailWriter;
+/// use std::io::Write;
+///
+/// let mut tail = TailWriter::new(Vec::new(), 3);
+/// tail.write_all(b"Hel").unwrap();
+/// tail.write_all(b"lo").unwrap();
+/// tail.finish().unwrap();
+/// assert_eq!(tail.into_inner(), b"llo");
+/// ```
+pub struct TailWriter<W: Write> {
+    target: W,
+    size: usize,
+    kept: VecDeque<u8>,
+}
+
+impl<W: Write> TailWriter<W> {
+    pub fn new(target: W, size: usize) -> Self {
+        Self {
+            target,
+            size,
+            kept: VecDeque::new(),
+        }
+    }
+
+    /// Writes the bytes kept so far to the target and flushes it
+    pub fn finish(&mut self) -> io::Result<()> {
+        let (front, back) = self.kept.as_slices();
+        self.target.write_all(front)?;
+        self.target.write_all(back)?;
+        self.kept.clear();
+        self.target.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.target
+    }
+}
+
+impl<W: Write> Write for TailWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let kept = &buf[buf.len().saturating_sub(self.size)..];
+        let excess = (self.kept.len() + kept.len()).saturating_sub(self.size);
+        self.kept.drain(..excess);
+        self.kept.extend(kept);
+        Ok(buf.len())
+    }
+
+    /// Does nothing, since the bytes kept may still be pushed out by later ones
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A reader that retries reads interrupted by a signal, which the converters use for their input.
+///
+/// `Ok(0)` from the inner reader is the end of the input, as for any [`Read`].
+/// A non-blocking source that has no data yet returns [`io::ErrorKind::WouldBlock`] instead,
+/// which fails with an error asking for blocking mode rather than ending the input early.
+///
+/// ```rust
+/// use bytary::utils::RetryingReader;
+/// use std::io::{self, Read};
+///
+/// struct NonBlocking;
+///
+/// impl Read for NonBlocking {
+///     fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+///         Err(io::ErrorKind::WouldBlock.into())
+///     }
+/// }
+///
+/// let err = RetryingReader::new(NonBlocking).read(&mut [0; 4]).unwrap_err();
+/// assert!(err.to_string().contains("blocking mode"));
+/// ```
+pub struct RetryingReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> RetryingReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner }
+    }
+}
+
+impl<R: Read> Read for RetryingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.inner.read(buf) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WouldBlock,
+                        "the input is non-blocking and has no data yet, read it in blocking mode",
+                    ));
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Which part of a stream a [`PatternWindowReader`] passes through
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PatternWindow {
+    /// The data starts at this pattern. Empty means the start of the stream.
+    pub start: Vec<u8>,
+    /// The data ends at the first occurrence of this pattern after the start. Empty means the end of the stream.
+    pub end: Vec<u8>,
+    /// Whether the start pattern is part of the data
+    pub include_start: bool,
+    /// Whether the end pattern is part of the data
+    pub include_end: bool,
+    /// Start at the `nth` non-overlapping occurrence of the start pattern, counting from 1
+    pub nth: usize,
+}
+
+impl Default for PatternWindow {
+    fn default() -> Self {
+        Self {
+            start: Vec::new(),
+            end: Vec::new(),
+            include_start: true,
+            include_end: true,
+            nth: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum WindowState {
+    /// Looking for the start pattern, which was found this many times
+    Seeking(usize),
+    /// Passing data through while looking for the end pattern
+    Passing,
+    /// Passing the rest of the buffer through, then reporting EOF
+    Draining,
+}
+
+/// A reader that only passes through the data between a start and an end pattern, then reports EOF.
+///
+/// Patterns are found across the chunks read from the inner reader,
+/// which is never read further than needed to find the end pattern.
+/// A pattern that is not found before the end of the input is reported as a [`Warning::PatternNotFound`].
+///
+/// ```rust
+/// use bytary::utils::{PatternWindow, PatternWindowReader};
+/// use std::io::Read;
+///
+/// let window = PatternWindow {
+///     start: b"\xff\xd8".to_vec(),
+///     end: b"\xff\xd9".to_vec(),
+///     ..PatternWindow::default()
+/// };
+/// let mut reader = PatternWindowReader::new(&b"junk\xff\xd8image\xff\xd9junk"[..], window);
+/// let mut output = Vec::new();
+/// reader.read_to_end(&mut output).unwrap();
+/// assert_eq!(output, b"\xff\xd8image\xff\xd9");
+/// assert!(reader.found_start() && reader.found_end());
+/// ```
+pub struct PatternWindowReader<R: Read> {
+    inner: R,
+    window: PatternWindow,
+    state: WindowState,
+    /// Data read from the inner reader, not passed through or discarded yet
+    buffer: Vec<u8>,
+    /// While passing, the number of bytes at the start of `buffer` that can not begin the end pattern
+    scanned: usize,
+    eof: bool,
+    found_start: bool,
+    found_end: bool,
+    /// Whether the end of the input was reached and a missing pattern reported
+    reported: bool,
+}
+
+impl<R: Read> PatternWindowReader<R> {
+    pub fn new(inner: R, window: PatternWindow) -> Self {
+        Self {
+            inner,
+            window,
+            state: WindowState::Seeking(0),
+            buffer: Vec::new(),
+            scanned: 0,
+            eof: false,
+            found_start: false,
+            found_end: false,
+            reported: false,
+        }
+    }
+
+    /// Whether the start pattern was found, always true if it is empty
+    pub fn found_start(&self) -> bool {
+        self.found_start
+    }
+
+    /// Whether the end pattern was found, always false if it is empty
+    pub fn found_end(&self) -> bool {
+        self.found_end
+    }
+
+    /// Reads a chunk from the inner reader into the buffer
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; 8192];
+        let length = loop {
+            match self.inner.read(&mut chunk) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+        self.buffer.extend_from_slice(&chunk[..length]);
+        self.eof = length == 0;
+        Ok(())
+    }
+
+    /// Reports the pattern that was not found, once the end of the input is reached
+    fn report_missing(&mut self) {
+        if self.reported {
+            return;
+        }
+        self.reported = true;
+        let start = match (self.found_start, self.found_end) {
+            (false, _) => true,
+            (true, false) if !self.window.end.is_empty() => false,
+            _ => return,
+        };
+        warning::report(Warning::PatternNotFound { start });
+    }
+
+    fn find(haystack: &[u8], pattern: &[u8]) -> Option<usize> {
+        haystack
+            .windows(pattern.len())
+            .position(|window| window == pattern)
+    }
+}
+
+impl<R: Read> Read for PatternWindowReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.state {
+                WindowState::Seeking(_) if self.window.start.is_empty() => {
+                    self.found_start = true;
+                    self.state = WindowState::Passing;
+                }
+                WindowState::Seeking(found) => {
+                    let start = &self.window.start;
+                    if let Some(i) = Self::find(&self.buffer, start) {
+                        if found + 1 < self.window.nth {
+                            self.buffer.drain(..i + start.len());
+                            self.state = WindowState::Seeking(found + 1);
+                            continue;
+                        }
+                        let (begin, scanned) = match self.window.include_start {
+                            true => (i, start.len()),
+                            false => (i + start.len(), 0),
+                        };
+                        self.buffer.drain(..begin);
+                        self.scanned = scanned;
+                        self.found_start = true;
+                        self.state = WindowState::Passing;
+                        continue;
+                    }
+
+                    // Keep what may be the beginning of a match
+                    let keep = (start.len() - 1).min(self.buffer.len());
+                    self.buffer.drain(..self.buffer.len() - keep);
+                    if self.eof {
+                        self.buffer.clear();
+                        self.report_missing();
+                        return Ok(0);
+                    }
+                    self.fill()?;
+                }
+                WindowState::Passing => {
+                    let end = &self.window.end;
+                    if end.is_empty() {
+                        self.scanned = self.buffer.len();
+                    } else if let Some(j) = Self::find(&self.buffer[self.scanned..], end) {
+                        let j = self.scanned + j;
+                        let stop = match self.window.include_end {
+                            true => j + end.len(),
+                            false => j,
+                        };
+                        self.buffer.truncate(stop);
+                        self.found_end = true;
+                        self.state = WindowState::Draining;
+                        continue;
+                    } else {
+                        let safe = self.buffer.len().saturating_sub(end.len() - 1);
+                        self.scanned = self.scanned.max(safe);
+                    }
+
+                    if self.scanned > 0 {
+                        let length = self.scanned.min(buf.len());
+                        buf[..length].copy_from_slice(&self.buffer[..length]);
+                        self.buffer.drain(..length);
+                        self.scanned -= length;
+                        return Ok(length);
+                    }
+                    if self.eof {
+                        self.state = WindowState::Draining;
+                        continue;
+                    }
+                    self.fill()?;
+                }
+                WindowState::Draining => {
+                    if self.buffer.is_empty() && self.eof {
+                        self.report_missing();
+                    }
+                    let length = self.buffer.len().min(buf.len());
+                    buf[..length].copy_from_slice(&self.buffer[..length]);
+                    self.buffer.drain(..length);
+                    return Ok(length);
+                }
+            }
+        }
+    }
+}
+
+/// A writer that flushes its target whenever `interval` has passed since the last flush.
+///
+/// The time is checked on every write. Flushes of this writer are passed to the target immediately.
+pub struct IntervalFlushWriter<W: Write> {
+    target: W,
+    interval: Duration,
+    last_flush: Instant,
+}
+
+impl<W: Write> IntervalFlushWriter<W> {
+    pub fn new(target: W, interval: Duration) -> Self {
+        Self {
+            target,
+            interval,
+            last_flush: Instant::now(),
+        }
+    }
+}
+
+impl<W: Write> Write for IntervalFlushWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let length = self.target.write(buf)?;
+        if self.last_flush.elapsed() >= self.interval {
+            self.flush()?;
+        }
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.last_flush = Instant::now();
+        self.target.flush()
+    }
+}
+
+/// A source of monotonic time, which tests replace with scripted instants
+pub trait Clock {
+    /// Time since an origin of the clock's choosing, which never goes back
+    fn now(&self) -> Duration;
+}
+
+/// The monotonic clock of the system, from the creation of this one
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock(Instant);
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self(Instant::now())
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+/// When each chunk read through a [`TimedReader`] arrived, by the offset of its first byte.
+///
+/// Clones share the same record, so that the writer formatting the output can look up the
+/// times that the reader records.
+#[derive(Clone, Debug, Default)]
+pub struct Arrivals(Rc<RefCell<VecDeque<(u64, Duration)>>>);
+
+impl Arrivals {
+    /// Records that the chunk starting at `offset` arrived at `time`
+    pub fn record(&self, offset: u64, time: Duration) {
+        self.0.borrow_mut().push_back((offset, time));
+    }
+
+    /// The arrival time of the byte at `offset`, `None` before anything was recorded.
+    ///
+    /// Offsets must be looked up in increasing order: the chunks before the one of `offset` are forgotten,
+    /// so that following a live input does not keep the time of every chunk.
+    pub fn time_of(&self, offset: u64) -> Option<Duration> {
+        let mut chunks = self.0.borrow_mut();
+        let after = chunks.partition_point(|&(start, _)| start <= offset);
+        chunks.drain(..after.saturating_sub(1));
+        chunks.front().map(|&(_, time)| time)
+    }
+}
+
+/// A reader that records when each chunk of its input arrived, in its [`Arrivals`]
+///
+/// ```rust
+/// use bytary::utils::{Clock, TimedReader};
+/// use std::cell::Cell;
+/// use std::io::Read;
+/// use std::time::Duration;
+///
+/// struct Scripted(Cell<u64>);
+/// impl Clock for Scripted {
+///     fn now(&self) -> Duration {
+///         self.0.set(self.0.get() + 100);
+///         Duration::from_millis(self.0.get())
+///     }
+/// }
+///
+/// let mut reader = TimedReader::with_clock(&b"Hello"[..], Scripted(Cell::new(0)));
+/// let arrivals = reader.arrivals();
+/// reader.read_exact(&mut [0u8; 2]).unwrap();
+/// reader.read_exact(&mut [0u8; 3]).unwrap();
+/// assert_eq!(arrivals.time_of(1), Some(Duration::from_millis(100)));
+/// assert_eq!(arrivals.time_of(4), Some(Duration::from_millis(200)));
+/// ```
+pub struct TimedReader<R: Read, C: Clock = SystemClock> {
+    inner: R,
+    clock: C,
+    offset: u64,
+    arrivals: Arrivals,
+}
+
+impl<R: Read> TimedReader<R> {
+    /// Times the chunks from now on, with the [`SystemClock`]
+    pub fn new(inner: R) -> Self {
+        Self::with_clock(inner, SystemClock::new())
+    }
+}
+
+impl<R: Read, C: Clock> TimedReader<R, C> {
+    pub fn with_clock(inner: R, clock: C) -> Self {
+        Self {
+            inner,
+            clock,
+            offset: 0,
+            arrivals: Arrivals::default(),
+        }
+    }
+
+    /// The record of arrival times, shared with this reader
+    pub fn arrivals(&self) -> Arrivals {
+        self.arrivals.clone()
+    }
+}
+
+impl<R: Read, C: Clock> Read for TimedReader<R, C> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.inner.read(buf)?;
+        if length > 0 {
+            self.arrivals.record(self.offset, self.clock.now());
+            self.offset += length as u64;
+        }
+        Ok(length)
+    }
+}
+
+/// How [`LineTimestamps`] writes a time
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TimestampStyle {
+    /// Seconds since the origin of the clock, like `+12.034s`
+    Relative,
+    /// UTC date and time in RFC 3339, like `2024-05-01T09:30:12.034Z`, given the wall clock time
+    /// of the origin of the clock
+    Absolute(SystemTime),
+}
+
+impl TimestampStyle {
+    /// Writes `time`, measured from the origin of the clock
+    pub fn format(&self, time: Duration) -> String {
+        match self {
+            TimestampStyle::Relative => {
+                format!("+{}.{:03}s", time.as_secs(), time.subsec_millis())
+            }
+            TimestampStyle::Absolute(origin) => format_rfc3339(*origin + time),
+        }
+    }
+}
+
+/// `time` as an RFC 3339 UTC date and time with milliseconds, the epoch for times before it
+fn format_rfc3339(time: SystemTime) -> String {
+    let since_epoch = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+
+    // Civil date from days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let (era, day_of_era) = (z / 146097, z % 146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = match shifted_month < 10 {
+        true => shifted_month + 3,
+        false => shifted_month - 9,
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// The timestamps of [`FormattedWriter::timestamps`]: the arrival time of the input byte that
+/// each line starts with, looked up in [`Arrivals`].
+#[derive(Clone, Debug)]
+pub struct LineTimestamps {
+    arrivals: Arrivals,
+    style: TimestampStyle,
+    ratio: Option<f64>,
+}
+
+impl LineTimestamps {
+    pub fn new(arrivals: Arrivals, style: TimestampStyle) -> Self {
+        Self {
+            arrivals,
+            style,
+            ratio: None,
+        }
+    }
+
+    /// Output bytes per input byte, to find the input byte of each line from its position in the output.
+    ///
+    /// Without it, a line gets the arrival time of the last chunk read when it starts,
+    /// which is the same for streaming converters that write as soon as they read.
+    pub fn ratio(mut self, ratio: Option<f64>) -> Self {
+        self.ratio = ratio.filter(|ratio| *ratio > 0.0);
+        self
+    }
+
+    /// The timestamp and space of a line whose first byte is at `position` in the output
+    fn prefix(&self, position: usize) -> String {
+        let time = match self.ratio {
+            // Rounded so that the first digit of a byte maps to that byte despite floating-point errors
+            Some(ratio) => self
+                .arrivals
+                .time_of((position as f64 / ratio + 1e-9).floor() as u64),
+            // The last chunk, forgetting the others
+            None => self.arrivals.time_of(u64::MAX),
+        };
+        format!("{} ", self.style.format(time.unwrap_or_default()))
+    }
+}
+
+/// A writer that writes everything to two targets, like `tee`.
+///
+/// Errors of either target name it, keeping their kind. With [`TeeWriter::lenient`], the first target
+/// being closed is not an error: writing goes on to the second one alone.
+///
+/// ```rust
+/// use bytary::utils::TeeWriter;
+/// use std::io::Write;
+///
+/// let mut tee = TeeWriter::new(Vec::new(), "stdout", Vec::new(), "copy.hex");
+/// tee.write_all(b"4869").unwrap();
+/// let (first, second) = tee.into_inner();
+/// assert_eq!(first, second);
+/// ```
+pub struct TeeWriter<A: Write, B: Write> {
+    first: A,
+    first_name: String,
+    second: B,
+    second_name: String,
+    lenient: bool,
+    first_closed: bool,
+}
+
+impl<A: Write, B: Write> TeeWriter<A, B> {
+    /// Writes to `first` and `second`, called `first_name` and `second_name` in errors
+    pub fn new(
+        first: A,
+        first_name: impl Into<String>,
+        second: B,
+        second_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            first,
+            first_name: first_name.into(),
+            second,
+            second_name: second_name.into(),
+            lenient: false,
+            first_closed: false,
+        }
+    }
+
+    /// Keep writing to the second target once the first fails with [`io::ErrorKind::BrokenPipe`]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Whether the first target was closed, and is no longer written to
+    pub fn first_closed(&self) -> bool {
+        self.first_closed
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+
+    /// Applies `f` to the first target unless it was closed, then to the second
+    fn both(&mut self, mut f: impl FnMut(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+        if !self.first_closed {
+            match f(&mut self.first) {
+                Err(e) if self.lenient && e.kind() == io::ErrorKind::BrokenPipe => {
+                    self.first_closed = true
+                }
+                result => result.map_err(|e| named(e, &self.first_name))?,
+            }
+        }
+        f(&mut self.second).map_err(|e| named(e, &self.second_name))
+    }
+}
+
+/// Prefixes the message of `e` with the `name` of what failed
+fn named(e: io::Error, name: &str) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", name, e))
+}
+
+impl<A: Write, B: Write> Write for TeeWriter<A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.both(|target| target.write_all(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.both(|target| target.flush())
+    }
+}
+
+/// A writer that writes everything to any number of named targets.
+///
+/// Errors of a target become [`BytaryError::BranchFailed`] with its name, carried through the io error
+/// like by [`into_io_error`]. Nothing more is written to the targets after one fails.
+///
+/// ```rust
+/// use bytary::utils::BroadcastWriter;
+/// use std::io::Write;
+///
+/// let mut broadcast = BroadcastWriter::new();
+/// broadcast.push("first", Vec::new());
+/// broadcast.push("second", Vec::new());
+/// broadcast.write_all(b"Hi").unwrap();
+/// for (_, target) in broadcast.into_inner() {
+///     assert_eq!(target, b"Hi");
+/// }
+/// ```
+pub struct BroadcastWriter<W: Write> {
+    targets: Vec<(String, W)>,
+    failed: bool,
+}
+
+impl<W: Write> BroadcastWriter<W> {
+    pub fn new() -> Self {
+        Self {
+            targets: Vec::new(),
+            failed: false,
+        }
+    }
+
+    /// Adds a target, called `name` in errors
+    pub fn push(&mut self, name: impl Into<String>, target: W) {
+        self.targets.push((name.into(), target));
+    }
+
+    /// The targets with their names, in the order they were pushed
+    pub fn into_inner(self) -> Vec<(String, W)> {
+        self.targets
+    }
+
+    /// Applies `f` to every target in turn, up to the first one that fails
+    fn each(&mut self, mut f: impl FnMut(&mut W) -> io::Result<()>) -> io::Result<()> {
+        if self.failed {
+            return Err(io::Error::other("a target of the broadcast failed"));
+        }
+        for (name, target) in &mut self.targets {
+            if let Err(e) = f(target) {
+                self.failed = true;
+                return Err(into_io_error(BytaryError::BranchFailed(
+                    name.clone(),
+                    Box::new(e.into()),
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Default for BroadcastWriter<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write> Write for BroadcastWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.each(|target| target.write_all(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.each(|target| target.flush())
+    }
+}
+
+/// A reader that writes everything it reads to `copy` as well, like [`TeeWriter`] on the input side.
+///
+/// Errors of `copy` are returned by `read`, after the bytes read are written to it.
+///
+/// ```rust
+/// use bytary::utils::TeeReader;
+/// use std::io::Read;
+///
+/// let mut copy = Vec::new();
+/// let mut read = String::new();
+/// TeeReader::new(&b"Hi"[..], &mut copy).read_to_string(&mut read).unwrap();
+/// assert_eq!(copy, read.as_bytes());
+/// ```
+pub struct TeeReader<R: Read, W: Write> {
+    inner: R,
+    copy: W,
+}
+
+impl<R: Read, W: Write> TeeReader<R, W> {
+    pub fn new(inner: R, copy: W) -> Self {
+        Self { inner, copy }
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.inner, self.copy)
+    }
+}
+
+impl<R: Read, W: Write> Read for TeeReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.inner.read(buf)?;
+        self.copy.write_all(&buf[..length])?;
+        Ok(length)
+    }
+}
+
+/// How [`FramedWriter`] separates records
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Framing {
+    /// Write a line break after each record
+    Newline,
+    /// Write a NUL byte after each record
+    Nul,
+    /// Write the length of each record as 4 big-endian bytes before it.
+    ///
+    /// Records are buffered to know their length, so they may not be larger than `max_size` bytes.
+    Len32 { max_size: usize },
+}
+
+/// A writer that frames the bytes written between [`FramedWriter::begin_record`] and [`FramedWriter::end_record`]
+pub struct FramedWriter<W: Write> {
+    target: W,
+    framing: Framing,
+    /// The current record, only buffered for [`Framing::Len32`]
+    record: Vec<u8>,
+    overflowed: bool,
+}
+
+impl<W: Write> FramedWriter<W> {
+    pub fn new(target: W, framing: Framing) -> Self {
+        Self {
+            target,
+            framing,
+            record: Vec::new(),
+            overflowed: false,
+        }
+    }
+
+    pub fn begin_record(&mut self) {
+        self.record.clear();
+        self.overflowed = false;
+    }
+
+    /// Writes the framing of the current record, and the record itself if it was buffered.
+    ///
+    /// Fails with [`BytaryError::RecordTooLarge`] if the record exceeded the maximum size.
+    pub fn end_record(&mut self) -> BytaryResult<()> {
+        match self.framing {
+            Framing::Len32 { max_size } if self.overflowed => {
+                return BytaryError::RecordTooLarge(max_size).err();
+            }
+            Framing::Newline => self.target.write_all(b"\n")?,
+            Framing::Nul => self.target.write_all(b"\0")?,
+            Framing::Len32 { max_size } => {
+                let length = u32::try_from(self.record.len())
+                    .map_err(|_| BytaryError::RecordTooLarge(max_size))?;
+                self.target.write_all(&length.to_be_bytes())?;
+                self.target.write_all(&self.record)?;
+                self.record.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the current record was rejected for exceeding the maximum size
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+}
+
+impl<W: Write> Write for FramedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.framing {
+            Framing::Len32 { max_size } => {
+                if self.record.len() + buf.len() > max_size {
+                    self.overflowed = true;
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        BytaryError::RecordTooLarge(max_size),
+                    ));
+                }
+                self.record.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            _ => self.target.write(buf),
+        }
+    }
+
+    /// Flushes the target, a buffered record is only written by [`FramedWriter::end_record`]
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+}
+
+/// A writer that writes to numbered files `PREFIX.000`, `PREFIX.001`, ..., moving on to the next file
+/// each time `size` bytes were written to the current one.
+///
+/// Numbers have 3 digits, and more past `999`. The first file is created at once,
+/// so that an empty output is one empty file. [`SequenceReader`] reads the files back as one stream.
+pub struct SplittingWriter {
+    prefix: PathBuf,
+    size: u64,
+    file: fs::File,
+    index: usize,
+    /// Bytes written to the current file
+    written: u64,
+}
+
+impl SplittingWriter {
+    pub fn new(prefix: impl Into<PathBuf>, size: u64) -> io::Result<Self> {
+        if size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the size of split files must be at least 1",
+            ));
+        }
+        let prefix = prefix.into();
+        let file = fs::File::create(Self::path(&prefix, 0))?;
+        Ok(Self {
+            prefix,
+            size,
+            file,
+            index: 0,
+            written: 0,
+        })
+    }
+
+    /// The path of file `index` of `prefix`, like `out.007`
+    pub fn path(prefix: &Path, index: usize) -> PathBuf {
+        let mut path = prefix.as_os_str().to_owned();
+        path.push(format!(".{:03}", index));
+        PathBuf::from(path)
+    }
+
+    /// The number of files created so far
+    pub fn count(&self) -> usize {
+        self.index + 1
+    }
+}
+
+impl Write for SplittingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.written == self.size {
+            self.file.flush()?;
+            self.file = fs::File::create(Self::path(&self.prefix, self.index + 1))?;
+            self.index += 1;
+            self.written = 0;
+        }
+        let length = buf.len().min((self.size - self.written) as usize);
+        let written = self.file.write(&buf[..length])?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// A reader of the files `PREFIX.000`, `PREFIX.001`, ... of a [`SplittingWriter`], concatenated in order
+pub struct SequenceReader {
+    prefix: PathBuf,
+    count: usize,
+    /// Index of the next file to open
+    index: usize,
+    file: Option<fs::File>,
+}
+
+impl SequenceReader {
+    /// Finds the files of `prefix`, failing if there are none or if one is missing in the sequence
+    pub fn new(prefix: impl Into<PathBuf>) -> BytaryResult<Self> {
+        let prefix = prefix.into();
+        let dir = match prefix.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let stem = prefix
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned() + ".")
+            .unwrap_or_default();
+
+        let mut indices = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let name = entry?.file_name();
+            let index = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(&stem))
+                .filter(|digits| digits.len() >= 3 && digits.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|digits| digits.parse::<usize>().ok())
+                // Only the names that the writer gives, not `out.0007`
+                .filter(|&index| {
+                    SplittingWriter::path(&prefix, index).file_name() == Some(name.as_os_str())
+                });
+            indices.extend(index);
+        }
+        indices.sort_unstable();
+
+        if indices.is_empty() {
+            return BytaryError::InvalidArgument(format!(
+                "no file {} to join",
+                SplittingWriter::path(&prefix, 0).display()
+            ))
+            .err();
+        }
+        if let Some(missing) = (0..indices.len()).find(|&i| indices[i] != i) {
+            return BytaryError::InvalidArgument(format!(
+                "missing {}, the files to join go up to {}",
+                SplittingWriter::path(&prefix, missing).display(),
+                SplittingWriter::path(&prefix, indices[indices.len() - 1]).display()
+            ))
+            .err();
+        }
+        Ok(Self {
+            prefix,
+            count: indices.len(),
+            index: 0,
+            file: None,
+        })
+    }
+
+    /// The number of files in the sequence
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl Read for SequenceReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let file = match &mut self.file {
+                Some(file) => file,
+                None if self.index == self.count => return Ok(0),
+                None => {
+                    let file = fs::File::open(SplittingWriter::path(&self.prefix, self.index))?;
+                    self.index += 1;
+                    self.file.insert(file)
+                }
+            };
+            match file.read(buf)? {
+                0 => self.file = None,
+                length => return Ok(length),
+            }
+        }
+    }
+}
+
+/// Why [`parse_size`] rejected a size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SizeParseError {
+    Empty,
+    Negative,
+    InvalidNumber(String),
+    UnknownSuffix(String),
+    Overflow(String),
+}
+
+impl Display for SizeParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SizeParseError::Empty => write!(f, "empty size"),
+            SizeParseError::Negative => write!(f, "size must not be negative"),
+            SizeParseError::InvalidNumber(size) => write!(f, "invalid size '{}'", size),
+            SizeParseError::UnknownSuffix(suffix) => write!(
+                f,
+                "unknown size suffix '{}', expected one of k, M, G, T, P, E, optionally followed by i or B",
+                suffix
+            ),
+            SizeParseError::Overflow(size) => write!(f, "size '{}' is too large", size),
+        }
+    }
+}
+
+impl std::error::Error for SizeParseError {}
+
+/// The binary units of [`parse_size`] and [`format_size`], from the largest
+const SIZE_UNITS: [(&str, u32); 6] = [
+    ("E", 60),
+    ("P", 50),
+    ("T", 40),
+    ("G", 30),
+    ("M", 20),
+    ("K", 10),
+];
+
+/// Parses a number of bytes like `4096`, `64k`, `1.5MiB` or `2 GB`.
+///
+/// Suffixes are case-insensitive and always binary: `k`, `Ki`, `KB` and `KiB` all mean 1024.
+/// Fractional values are rounded to the nearest byte, so `1.5k` is 1536 and `0.1k` is 102.
+///
+/// ```rust
+/// use bytary::utils::parse_size;
+///
+/// assert_eq!(parse_size("64k"), Ok(64 << 10));
+/// assert_eq!(parse_size("1.5MiB"), Ok(3 << 19));
+/// assert!(parse_size("-1").is_err());
+/// assert!(parse_size("20E").is_err());
+/// ```
+pub fn parse_size(size: &str) -> Result<u64, SizeParseError> {
+    let trimmed = size.trim();
+    if trimmed.is_empty() {
+        return Err(SizeParseError::Empty);
+    }
+    if trimmed.starts_with('-') {
+        return Err(SizeParseError::Negative);
+    }
+    let number_end = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, suffix) = trimmed.split_at(number_end);
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() || fraction.contains('.') {
+        return Err(SizeParseError::InvalidNumber(size.to_string()));
+    }
+
+    let suffix = suffix.trim_start().to_ascii_uppercase();
+    let unit = suffix.strip_suffix('B').unwrap_or(&suffix);
+    let unit = unit
+        .strip_suffix('I')
+        .filter(|unit| !unit.is_empty())
+        .unwrap_or(unit);
+    let shift = match unit {
+        "" => 0,
+        _ => match SIZE_UNITS.iter().find(|(name, _)| *name == unit) {
+            Some((_, shift)) => *shift,
+            None => return Err(SizeParseError::UnknownSuffix(suffix)),
+        },
+    };
+
+    let overflow = || SizeParseError::Overflow(size.to_string());
+    let whole = match whole {
+        "" => 0,
+        _ => whole.parse::<u128>().map_err(|_| overflow())?,
+    };
+    // Digits beyond the 19th can't change the rounded number of bytes, even in exbibytes
+    let fraction = &fraction[..fraction.len().min(19)];
+    let fraction_bytes = match fraction {
+        "" => 0,
+        _ => {
+            let scale = 10u128.pow(fraction.len() as u32);
+            let numerator = fraction.parse::<u128>().unwrap() << shift;
+            (numerator + scale / 2) / scale
+        }
+    };
+    let bytes = whole
+        .checked_mul(1 << shift)
+        .and_then(|bytes| bytes.checked_add(fraction_bytes))
+        .ok_or_else(overflow)?;
+    u64::try_from(bytes).map_err(|_| overflow())
+}
+
+/// Formats a number of bytes for people, like `512 B`, `64 KiB` or `1.5 MiB`.
+///
+/// Sizes that aren't a whole number of units have one decimal, and [`parse_size`] reads
+/// all of them back, exactly when there is no decimal.
+///
+/// ```rust
+/// use bytary::utils::format_size;
+///
+/// assert_eq!(format_size(512), "512 B");
+/// assert_eq!(format_size(64 << 10), "64 KiB");
+/// assert_eq!(format_size(3 << 19), "1.5 MiB");
+/// ```
+pub fn format_size(bytes: u64) -> String {
+    match SIZE_UNITS.iter().find(|(_, shift)| bytes >> shift != 0) {
+        Some((name, shift)) if bytes.is_multiple_of(1 << shift) => {
+            format!("{} {}iB", bytes >> shift, name)
+        }
+        Some((name, shift)) => {
+            format!("{:.1} {}iB", bytes as f64 / (1u64 << shift) as f64, name)
+        }
+        None => format!("{} B", bytes),
+    }
+}
+
+/// Formats `count` followed by `noun`, with an `s` unless there is exactly one, like `1 digit` or `3 digits`.
+///
+/// ```rust
+/// use bytary::utils::plural;
+///
+/// assert_eq!(plural(1, "digit"), "1 digit");
+/// assert_eq!(plural(0, "trailing digit"), "0 trailing digits");
+/// assert_eq!(plural(4096u64, "byte"), "4096 bytes");
+/// ```
+pub fn plural<N: Display + PartialEq + From<u8>>(count: N, noun: &str) -> String {
+    match count == N::from(1) {
+        true => format!("{} {}", count, noun),
+        false => format!("{} {}s", count, noun),
+    }
+}
@@ -0,0 +1,1061 @@
+use crate::builtins::{bytes_to_hex, hex_to_bytes};
+use crate::convert::ConversionGraph;
+use crate::error::BytaryError;
+use crate::format::Format;
+use crate::testsupport::{ByteGenerator, ChunkedReader, FaultyWriter};
+use crate::utils::{
+    AnnotatedFormatter, BroadcastWriter, CancelToken, CancellableReader, Clock, ColoredWriter,
+    CommentStrippingReader, CountingReader, CountingWriter, DelimitedWriter, ExpectWriter,
+    FormattedWriter, GroupCheckingReader, GroupedWriter, HeadWriter, HexStyle, IntervalFlushWriter,
+    LimitedReader, LineTimestamps, PASTE_NUISANCES, PatternWindow, PatternWindowReader,
+    SequenceReader, SizeParseError, SplittingWriter, TailWriter, TeeReader, TeeWriter, TimedReader,
+    TimestampStyle, Utf8Boundary, cleanup_pasted_text, format_size, into_io_error, parse_size,
+};
+use crate::warning;
+use crate::warning::Warning;
+use std::cell::Cell;
+use std::io::{Read, Write};
+use std::sync::mpsc;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn test_formatted_writer() {
+    let mut output = Vec::new();
+    let mut writer = FormattedWriter::new(&mut output, 2, 4);
+    writer.write_all(b"1b348f").unwrap();
+    assert_eq!(output, b"1b 34\n8f");
+}
+
+#[test]
+fn test_formatted_writer_empty() {
+    let mut output = Vec::new();
+    let mut writer = FormattedWriter::new(&mut output, 2, 4);
+    writer.write_all(b"").unwrap();
+    writer.flush().unwrap();
+    assert!(output.is_empty());
+}
+
+#[test]
+fn test_formatted_writer_reset() {
+    let mut writer = FormattedWriter::new(Vec::new(), 2, 4);
+    writer.write_all(b"1b3").unwrap();
+    assert_eq!(writer.position(), 3);
+    assert_eq!(writer.get_ref(), b"1b 3");
+
+    writer.get_mut().write_all(b"\n").unwrap();
+    writer.reset();
+    assert_eq!(writer.position(), 0);
+    writer.write_all(b"4f8").unwrap();
+    assert_eq!(writer.position(), 3);
+    assert_eq!(writer.into_inner().unwrap(), b"1b 3\n4f 8");
+}
+
+/// A clock that moves 100ms forward every time it is read
+struct ScriptedClock(Cell<u64>);
+
+impl Clock for ScriptedClock {
+    fn now(&self) -> Duration {
+        self.0.set(self.0.get() + 100);
+        Duration::from_millis(self.0.get())
+    }
+}
+
+/// Hex of `data` arriving in chunks of 3 bytes, wrapped every 4 bytes
+fn timestamped_hex(data: &[u8], style: TimestampStyle, ratio: Option<f64>) -> String {
+    let mut reader =
+        TimedReader::with_clock(ChunkedReader::new(data, 3), ScriptedClock(Cell::new(0)));
+    let timestamps = LineTimestamps::new(reader.arrivals(), style).ratio(ratio);
+    let mut writer = FormattedWriter::new(Vec::new(), 2, 8).timestamps(timestamps);
+    bytes_to_hex(&mut reader, &mut writer).unwrap();
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+#[test]
+fn test_line_timestamps() {
+    // Lines start at bytes 0, 4 and 8, in the chunks starting at 0, 3 and 6
+    assert_eq!(
+        timestamped_hex(b"Hello, world", TimestampStyle::Relative, Some(2.0)),
+        "+0.100s 48 65 6c 6c\n+0.200s 6f 2c 20 77\n+0.300s 6f 72 6c 64\n"
+    );
+    let origin = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    assert_eq!(
+        timestamped_hex(b"Hi", TimestampStyle::Absolute(origin), Some(2.0)),
+        "2023-11-14T22:13:20.100Z 48 69"
+    );
+    // Without a ratio, lines take the time of the last chunk read, the same for a converter that
+    // writes each chunk as it reads it
+    assert_eq!(
+        timestamped_hex(b"Hello, world", TimestampStyle::Relative, None),
+        "+0.100s 48 65 6c 6c\n+0.200s 6f 2c 20 77\n+0.300s 6f 72 6c 64\n"
+    );
+    assert_eq!(
+        timestamped_hex(b"", TimestampStyle::Relative, Some(2.0)),
+        ""
+    );
+}
+
+#[test]
+fn test_timestamp_style() {
+    let relative = TimestampStyle::Relative;
+    assert_eq!(relative.format(Duration::ZERO), "+0.000s");
+    assert_eq!(
+        relative.format(Duration::from_micros(12_034_999)),
+        "+12.034s"
+    );
+    assert_eq!(relative.format(Duration::from_secs(3600)), "+3600.000s");
+
+    let absolute = TimestampStyle::Absolute(UNIX_EPOCH);
+    assert_eq!(absolute.format(Duration::ZERO), "1970-01-01T00:00:00.000Z");
+    assert_eq!(
+        absolute.format(Duration::from_secs(951_782_400)),
+        "2000-02-29T00:00:00.000Z"
+    );
+    assert_eq!(
+        absolute.format(Duration::from_millis(4_102_444_799_999)),
+        "2099-12-31T23:59:59.999Z"
+    );
+}
+
+#[test]
+fn test_utf8_boundary() {
+    // Every character ends at its last byte, and nowhere before
+    let mut buffer = [0u8; 4];
+    for c in (0..=0x10ffff).filter_map(char::from_u32) {
+        let bytes = c.encode_utf8(&mut buffer).as_bytes();
+        let mut boundary = Utf8Boundary::new();
+        let ends = bytes
+            .iter()
+            .map(|&byte| boundary.push(byte))
+            .collect::<Vec<bool>>();
+        assert_eq!(
+            ends.iter().position(|&end| end),
+            Some(bytes.len() - 1),
+            "{:?}",
+            c
+        );
+        assert!(boundary.at_boundary());
+    }
+
+    // Bytes that can not be part of a character end one on their own
+    let mut boundary = Utf8Boundary::new();
+    for byte in [0x80, 0xbf, 0xc0, 0xc1, 0xf5, 0xff] {
+        assert!(boundary.push(byte), "{:02x}", byte);
+    }
+    // A truncated character ends with the next byte
+    assert!(!boundary.push(0xe2));
+    assert!(!boundary.push(0x82));
+    assert!(!boundary.at_boundary());
+    assert!(boundary.push(b'a'));
+}
+
+#[test]
+fn test_formatted_writer_utf8() {
+    // Characters of 1 to 4 bytes, each split between two writes at every point
+    let text = "aé€😀z";
+    for split in 0..=text.len() {
+        let mut writer = FormattedWriter::new(Vec::new(), 0, 1).utf8(true);
+        writer.write_all(&text.as_bytes()[..split]).unwrap();
+        writer.write_all(&text.as_bytes()[split..]).unwrap();
+        assert_eq!(writer.position(), 5);
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(output, "a\né\n€\n😀\nz\n", "split at {}", split);
+    }
+
+    let mut writer = FormattedWriter::new(Vec::new(), 2, 4).utf8(true);
+    for byte in "ééééé".bytes() {
+        writer.write_all(&[byte]).unwrap();
+    }
+    assert_eq!(writer.into_inner().unwrap(), "éé éé\né".as_bytes());
+
+    // Without it, the positions are bytes
+    let mut writer = FormattedWriter::new(Vec::new(), 0, 1);
+    writer.write_all("é".as_bytes()).unwrap();
+    assert_eq!(writer.into_inner().unwrap(), b"\xc3\n\xa9\n");
+}
+
+/// Hex of `raw` written in chunks of `chunk` bytes to an [`AnnotatedFormatter`]
+fn annotated(raw: &[u8], chunk: usize, space_interval: usize, bytes_per_line: usize) -> String {
+    let mut output = Vec::new();
+    let mut formatter = AnnotatedFormatter::new(&mut output, 2, space_interval, bytes_per_line);
+    for part in raw.chunks(chunk) {
+        formatter
+            .write_annotated(part, hex::encode(part).as_bytes())
+            .unwrap();
+    }
+    formatter.finish().unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_annotated_formatter() {
+    // The lines do not depend on how the bytes are written
+    for chunk in [1, 3, 100] {
+        assert_eq!(
+            annotated(b"Hello, World!", chunk, 2, 4),
+            concat!(
+                "48 65 6c 6c  Hell\n",
+                "6f 2c 20 57  o, W\n",
+                "6f 72 6c 64  orld\n",
+                "21           !",
+            )
+        );
+    }
+    // Full last line, no spaces
+    assert_eq!(annotated(b"abcd", 2, 0, 2), "6162  ab\n6364  cd\n");
+    assert_eq!(annotated(b"abc", 2, 0, 2), "6162  ab\n63    c");
+    // Spaces between groups of bytes
+    assert_eq!(
+        annotated(b"abcde", 5, 4, 4),
+        "6162 6364  abcd\n65         e"
+    );
+    assert_eq!(annotated(b"", 1, 2, 4), "");
+}
+
+#[test]
+fn test_annotated_formatter_non_printable() {
+    let raw = (0x00..0x20)
+        .chain([0x7e, 0x7f, 0x80, 0xff])
+        .collect::<Vec<u8>>();
+    let output = annotated(&raw, 7, 2, 16);
+    let lines = output.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines[0],
+        "00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f  ................"
+    );
+    assert_eq!(
+        lines[1],
+        "10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f  ................"
+    );
+    assert_eq!(
+        lines[2],
+        "7e 7f 80 ff                                      ~..."
+    );
+    // The ASCII column starts at the same place on every line
+    assert!(lines.iter().all(|line| line.rfind("  ") == Some(47)));
+
+    let mut output = Vec::new();
+    let err = AnnotatedFormatter::new(&mut output, 2, 0, 4)
+        .write_annotated(b"ab", b"616")
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_delimited_writer() {
+    let mut output = Vec::new();
+    let mut writer = DelimitedWriter::new(&mut output, b"[", b"]\n", false);
+    writer.write_all(b"1b").unwrap();
+    writer.write_all(b"").unwrap();
+    writer.write_all(b"34").unwrap();
+    writer.finish().unwrap();
+    writer.finish().unwrap();
+    drop(writer);
+    assert_eq!(output, b"[1b34]\n");
+}
+
+#[test]
+fn test_delimited_writer_empty() {
+    let mut output = Vec::new();
+    let mut writer = DelimitedWriter::new(&mut output, b"[", b"]", false);
+    writer.write_all(b"").unwrap();
+    writer.finish().unwrap();
+    drop(writer);
+    assert!(output.is_empty());
+
+    let mut output = Vec::new();
+    DelimitedWriter::new(&mut output, b"[", b"]", true)
+        .finish()
+        .unwrap();
+    assert_eq!(output, b"[]");
+}
+
+#[test]
+fn test_delimited_writer_drop() {
+    let mut output = Vec::new();
+    let mut writer = DelimitedWriter::new(&mut output, b"\"", b"\"", false);
+    writer.write_all(b"abc").unwrap();
+    drop(writer);
+    assert_eq!(output, b"\"abc\"");
+}
+
+#[test]
+fn test_delimited_writer_new_line() {
+    for (payload, expected) in [(&b"ab"[..], &b"<ab\n>"[..]), (b"ab\n", b"<ab\n>")] {
+        let mut output = Vec::new();
+        let mut writer = DelimitedWriter::new(&mut output, b"<", b">", false).suffix_on_new_line();
+        writer.write_all(payload).unwrap();
+        drop(writer);
+        assert_eq!(output, expected);
+    }
+}
+
+#[test]
+fn test_delimited_writer_unless_new_line() {
+    for (payload, expected) in [(&b"ab"[..], &b"ab\r\n"[..]), (b"ab\n", b"ab\n"), (b"", b"")] {
+        let mut output = Vec::new();
+        let mut writer =
+            DelimitedWriter::new(&mut output, b"", b"\r\n", false).suffix_unless_new_line();
+        writer.write_all(payload).unwrap();
+        drop(writer);
+        assert_eq!(output, expected);
+    }
+    let mut output = Vec::new();
+    DelimitedWriter::new(&mut output, b"", b"\n", true)
+        .suffix_unless_new_line()
+        .finish()
+        .unwrap();
+    assert_eq!(output, b"\n");
+}
+
+fn strip_comments(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    CommentStrippingReader::new(input)
+        .read_to_end(&mut output)
+        .unwrap();
+    output
+}
+
+#[test]
+fn test_comment_stripping_reader() {
+    assert_eq!(
+        strip_comments(b"# header\n1b 34 // field\n8f#ff\n"),
+        b"\n1b 34 \n8f\n"
+    );
+    assert_eq!(strip_comments(b"1b/34/"), b"1b/34/");
+    assert_eq!(strip_comments(b"1b // no line break"), b"1b ");
+    assert_eq!(strip_comments(b""), b"");
+}
+
+/// Yields one byte per read, so that anything longer is split across reads
+struct OneByte<'a>(&'a [u8]);
+
+impl Read for OneByte<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let length = buf.len().min(self.0.len()).min(1);
+        buf[..length].copy_from_slice(&self.0[..length]);
+        self.0 = &self.0[length..];
+        Ok(length)
+    }
+}
+
+#[test]
+fn test_comment_stripping_reader_chunks() {
+    let mut output = Vec::new();
+    CommentStrippingReader::new(OneByte(b"1b/ //34\n/8f"))
+        .read_to_end(&mut output)
+        .unwrap();
+    assert_eq!(output, b"1b/ \n/8f");
+}
+
+#[test]
+fn test_paste_cleanup() {
+    let pasted = "\u{feff}\u{201c}1b\u{a0}34\u{200b}8f\u{200c}00\u{200d}\u{2060}ff\u{ad}0e\u{2018}12\u{2019}\u{201d}";
+    let decode = |input: &str| {
+        let mut output = Vec::new();
+        let mut reader = cleanup_pasted_text(OneByte(input.as_bytes()));
+        let (result, warnings) =
+            warning::collect(|| hex_to_bytes(&mut reader, &mut output).map(|_| output));
+        (result, warnings, reader.removed())
+    };
+
+    let (result, warnings, removed) = decode(pasted);
+    assert_eq!(result.unwrap(), [0x1b, 0x34, 0x8f, 0x00, 0xff, 0x0e, 0x12]);
+    assert_eq!(removed, PASTE_NUISANCES.len());
+    assert_eq!(warnings, [Warning::Cleaned { count: 11 }]);
+
+    // Other characters are still rejected
+    let (result, _, removed) = decode("1b\u{a0}\u{2014}34");
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Invalid input data: Invalid hex string: unexpected character '—' (U+2014) at offset 2"
+    );
+    assert_eq!(removed, 1);
+
+    let (result, warnings, _) = decode("1b 34");
+    assert_eq!(result.unwrap(), [0x1b, 0x34]);
+    assert!(warnings.is_empty());
+
+    // Full-width forms are only replaced when asked
+    let mut output = String::new();
+    let mut reader = cleanup_pasted_text(OneByte("\u{a0}１ｂ\u{3000}3４".as_bytes()));
+    reader.read_to_string(&mut output).unwrap();
+    assert_eq!(output, "１ｂ\u{3000}3４");
+    assert_eq!(reader.replaced(), 0);
+
+    let mut output = String::new();
+    let mut reader =
+        cleanup_pasted_text(OneByte("\u{a0}１ｂ\u{3000}3４".as_bytes())).fullwidth(true);
+    let (result, warnings) = warning::collect(|| reader.read_to_string(&mut output));
+    result.unwrap();
+    assert_eq!(output, "1b 34");
+    assert_eq!((reader.removed(), reader.replaced()), (1, 4));
+    assert_eq!(
+        warnings,
+        [
+            Warning::Cleaned { count: 1 },
+            Warning::Transliterated { count: 4 }
+        ]
+    );
+
+    // Other non-ASCII characters are only skipped when asked, except look-alikes
+    let mut output = String::new();
+    let mut reader =
+        cleanup_pasted_text(OneByte("1b\u{a0}\u{2014}é34²".as_bytes())).skip_non_ascii(true);
+    let (result, warnings) = warning::collect(|| reader.read_to_string(&mut output));
+    result.unwrap();
+    assert_eq!(output, "1b34²");
+    assert_eq!((reader.removed(), reader.skipped()), (1, 2));
+    assert_eq!(
+        warnings,
+        [
+            Warning::Cleaned { count: 1 },
+            Warning::NonAscii { count: 2 }
+        ]
+    );
+    let mut output = Vec::new();
+    let mut reader = cleanup_pasted_text(OneByte(&b"1b\xe9\xa034"[..])).skip_non_ascii(true);
+    reader.read_to_end(&mut output).unwrap();
+    assert_eq!(output, b"1b34");
+    assert_eq!(reader.skipped(), 2);
+}
+
+#[test]
+fn test_counting() {
+    let mut reader = CountingReader::new(&b"1b348f"[..]);
+    let mut output = Vec::new();
+    let mut writer = CountingWriter::new(&mut output);
+    {
+        let mut formatted = FormattedWriter::new(&mut writer, 2, 4);
+        std::io::copy(&mut reader, &mut formatted).unwrap();
+    }
+    assert_eq!(reader.count(), 6);
+    // Separators count too
+    assert_eq!(writer.count(), 8);
+    assert_eq!(output, b"1b 34\n8f");
+}
+
+/// A writer that counts how often it is flushed
+#[derive(Default)]
+struct FlushCounter(usize);
+impl Write for FlushCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0 += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_interval_flush_writer() {
+    let mut target = FlushCounter::default();
+    let mut writer = IntervalFlushWriter::new(&mut target, Duration::ZERO);
+    writer.write_all(b"1b").unwrap();
+    writer.write_all(b"34").unwrap();
+    assert_eq!(target.0, 2);
+
+    let mut target = FlushCounter::default();
+    let mut writer = IntervalFlushWriter::new(&mut target, Duration::from_secs(3600));
+    writer.write_all(b"1b").unwrap();
+    writer.write_all(b"34").unwrap();
+    writer.flush().unwrap();
+    assert_eq!(target.0, 1);
+}
+
+#[test]
+fn test_cancel() {
+    let token = CancelToken::new();
+    let (sender, receiver) = mpsc::channel();
+
+    let thread_token = token.clone();
+    std::thread::spawn(move || {
+        let converter = ConversionGraph::default()
+            .get_converter(&Format::Bytes, &Format::Base64)
+            .unwrap();
+        let mut input = CancellableReader::new(std::io::repeat(0x1b), thread_token);
+        sender
+            .send(converter.convert(&mut input, &mut std::io::sink()))
+            .unwrap();
+    });
+
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(receiver.try_recv().is_err(), "the input is infinite");
+    token.cancel();
+    assert!(token.is_cancelled());
+
+    let result = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(
+        matches!(result, Err(BytaryError::Cancelled)),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_colored_writer() {
+    let colored = |input: &[u8], radix: u32, digits: Option<usize>| {
+        let mut output = Vec::new();
+        let mut writer = ColoredWriter::new(&mut output, radix, digits);
+        for chunk in input.chunks(3) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.finish().unwrap();
+        String::from_utf8(output).unwrap()
+    };
+
+    // A line break in the middle of a byte stays outside the escape sequences
+    assert_eq!(
+        colored(b"0ff\nf41", 16, Some(2)),
+        "0f\x1b[31mf\x1b[0m\n\x1b[31mf\x1b[0m\x1b[32m41\x1b[0m"
+    );
+    // Without a fixed number of digits, whitespace ends a byte
+    assert_eq!(
+        colored(b"0 11111111", 2, None),
+        "\x1b[2m0\x1b[0m \x1b[31m11111111\x1b[0m"
+    );
+    // An incomplete byte at the end is colored by its value so far
+    assert_eq!(colored(b"4", 16, Some(2)), "4");
+}
+
+fn extract(input: &[u8], chunk: usize, window: PatternWindow) -> (Vec<u8>, bool, bool) {
+    let mut reader = PatternWindowReader::new(ChunkedReader::new(input, chunk), window);
+    let mut output = Vec::new();
+    // Small reads make the reader hand out its buffer in pieces
+    let mut buffer = [0u8; 7];
+    loop {
+        let length = reader.read(&mut buffer).unwrap();
+        if length == 0 {
+            break;
+        }
+        output.extend_from_slice(&buffer[..length]);
+    }
+    (output, reader.found_start(), reader.found_end())
+}
+
+fn jpeg_window() -> PatternWindow {
+    PatternWindow {
+        start: b"\xff\xd8".to_vec(),
+        end: b"\xff\xd9".to_vec(),
+        ..PatternWindow::default()
+    }
+}
+
+#[test]
+fn test_pattern_window_across_chunks() {
+    // Both patterns are split by the 1024-byte chunks
+    let mut input = vec![0u8; 4096];
+    input[1023..1025].copy_from_slice(b"\xff\xd8");
+    input[2047..2049].copy_from_slice(b"\xff\xd9");
+
+    let (output, found_start, found_end) = extract(&input, 1024, jpeg_window());
+    assert_eq!(output, &input[1023..2049]);
+    assert!(found_start && found_end);
+
+    for chunk in 1..=5 {
+        let input = b"..\xff\xd8\xff\xd9..";
+        assert_eq!(extract(input, chunk, jpeg_window()).0, b"\xff\xd8\xff\xd9");
+
+        let window = PatternWindow {
+            include_start: false,
+            include_end: false,
+            ..jpeg_window()
+        };
+        assert_eq!(extract(b"\xff\xd8ab\xff\xd9", chunk, window).0, b"ab");
+    }
+}
+
+#[test]
+fn test_pattern_window_missing() {
+    let ((output, found_start, found_end), warnings) =
+        warning::collect(|| extract(b"..\xff\xd8abc", 2, jpeg_window()));
+    assert_eq!(output, b"\xff\xd8abc");
+    assert!(found_start && !found_end);
+    assert_eq!(warnings, [Warning::PatternNotFound { start: false }]);
+
+    let ((output, found_start, _), warnings) =
+        warning::collect(|| extract(b"..\xff\xd9..", 2, jpeg_window()));
+    assert_eq!(output, b"");
+    assert!(!found_start);
+    assert_eq!(warnings, [Warning::PatternNotFound { start: true }]);
+
+    // Without patterns, everything is passed through
+    let ((output, found_start, found_end), warnings) =
+        warning::collect(|| extract(b"abc", 2, PatternWindow::default()));
+    assert_eq!(output, b"abc");
+    assert!(found_start && !found_end);
+    assert!(warnings.is_empty());
+
+    // Found patterns are not reported
+    let (_, warnings) = warning::collect(|| extract(b"\xff\xd8\xff\xd9", 1, jpeg_window()));
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_pattern_window_nth() {
+    let input = b"\xff\xd8one\xff\xd9 \xff\xd8two\xff\xd9 \xff\xd8three\xff\xd9";
+    let window = |nth| PatternWindow {
+        include_start: false,
+        include_end: false,
+        nth,
+        ..jpeg_window()
+    };
+    assert_eq!(extract(input, 3, window(2)).0, b"two");
+    assert_eq!(extract(input, 3, window(3)).0, b"three");
+    assert!(!extract(input, 3, window(4)).1);
+
+    // Occurrences do not overlap
+    let window = PatternWindow {
+        start: b"aa".to_vec(),
+        nth: 2,
+        ..PatternWindow::default()
+    };
+    assert_eq!(extract(b"aaab-aac", 1, window).0, b"aac");
+}
+
+fn grouped(style: HexStyle, digits: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut writer = GroupedWriter::new(&mut output, style.schedule());
+    // One digit at a time, so that groups span writes
+    for digit in digits {
+        writer.write_all(&[*digit]).unwrap();
+    }
+    writer.finish().unwrap();
+    output
+}
+
+#[test]
+fn test_grouped_writer() {
+    let uuid = b"123e4567e89b12d3a456426614174000";
+    assert_eq!(
+        grouped(HexStyle::Mac, b"1b348fff000e"),
+        b"1b:34:8f:ff:00:0e"
+    );
+    assert_eq!(grouped(HexStyle::Spaced, b"1b348f"), b"1b 34 8f");
+    assert_eq!(grouped(HexStyle::Continuous, b"1b348f"), b"1b348f");
+    assert_eq!(
+        grouped(HexStyle::Uuid, uuid),
+        b"123e4567-e89b-12d3-a456-426614174000"
+    );
+    assert_eq!(grouped(HexStyle::Mac, b""), b"");
+
+    for style in [
+        HexStyle::Mac,
+        HexStyle::Uuid,
+        HexStyle::Spaced,
+        HexStyle::Continuous,
+    ] {
+        let schedule = style.schedule();
+        for length in [0, 2, 4, 30, 32, 34] {
+            let output = grouped(style, &uuid.repeat(2)[..length]);
+            assert_eq!(
+                schedule.separators(length as u64) as usize * schedule.separator.len() + length,
+                output.len(),
+                "{} with {} digits",
+                style,
+                length
+            );
+        }
+    }
+}
+
+#[test]
+fn test_grouped_writer_holds_back() {
+    // The output of a schedule that does not repeat is only written by finish
+    let mut output = Vec::new();
+    let mut writer = GroupedWriter::new(&mut output, HexStyle::Uuid.schedule());
+    writer.write_all(b"123e4567e89b").unwrap();
+    writer.flush().unwrap();
+    assert_eq!(writer.count(), 12);
+    drop(writer);
+    assert_eq!(output, b"");
+}
+
+fn check_style(style: HexStyle, input: &str) -> Result<(), String> {
+    let mut reader = GroupCheckingReader::new(input.as_bytes(), style.schedule());
+    match reader.read_to_end(&mut Vec::new()) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(reader.mismatch().unwrap().to_string()),
+    }
+}
+
+#[test]
+fn test_group_checking_reader() {
+    assert_eq!(check_style(HexStyle::Mac, "1b:34:8f\n"), Ok(()));
+    assert_eq!(check_style(HexStyle::Mac, ""), Ok(()));
+    assert_eq!(check_style(HexStyle::Spaced, "1b 34 8f "), Ok(()));
+    assert_eq!(check_style(HexStyle::Continuous, "1b348f\n"), Ok(()));
+    assert_eq!(
+        check_style(HexStyle::Uuid, "123e4567-e89b-12d3-a456-426614174000\n"),
+        Ok(())
+    );
+
+    assert_eq!(
+        check_style(HexStyle::Mac, "1b-34"),
+        Err("expected ':' at offset 2".to_string())
+    );
+    assert_eq!(
+        check_style(HexStyle::Mac, "1b:3g"),
+        Err("expected a hex digit at offset 4".to_string())
+    );
+    assert_eq!(
+        check_style(HexStyle::Mac, "1b:3"),
+        Err("incomplete group at the end, offset 4".to_string())
+    );
+    assert_eq!(
+        check_style(HexStyle::Spaced, "1b 34\nx"),
+        Err("unexpected 'x' after the last group at offset 6".to_string())
+    );
+    assert_eq!(
+        check_style(HexStyle::Continuous, "1b3"),
+        Err("incomplete group at the end, offset 3".to_string())
+    );
+    assert_eq!(
+        check_style(HexStyle::Uuid, "123e4567-e89b-12d3-a456"),
+        Err("incomplete group at the end, offset 23".to_string())
+    );
+    assert_eq!(
+        check_style(HexStyle::Uuid, "123e4567-e89b-12d3-a456-42661417400011"),
+        Err("unexpected '1' after the last group at offset 36".to_string())
+    );
+    assert_eq!(
+        check_style(HexStyle::Uuid, ""),
+        Err("incomplete group at the end, offset 0".to_string())
+    );
+}
+
+/// An empty directory for the files of a test
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("bytary-utils-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_split_and_join() {
+    let dir = temp_dir("split");
+    let prefix = dir.join("out");
+    let data = (0..5 << 19)
+        .map(|i: u32| (i % 251) as u8)
+        .collect::<Vec<u8>>();
+
+    let mut writer = SplittingWriter::new(&prefix, 1 << 20).unwrap();
+    // Chunks that do not line up with the files
+    for chunk in data.chunks(1000) {
+        writer.write_all(chunk).unwrap();
+    }
+    writer.flush().unwrap();
+    assert_eq!(writer.count(), 3);
+    drop(writer);
+
+    let sizes = ["out.000", "out.001", "out.002"]
+        .map(|name| std::fs::metadata(dir.join(name)).unwrap().len());
+    assert_eq!(sizes, [1 << 20, 1 << 20, 1 << 19]);
+    assert!(!dir.join("out.003").exists());
+
+    let mut reader = SequenceReader::new(&prefix).unwrap();
+    assert_eq!(reader.count(), 3);
+    let mut joined = Vec::new();
+    reader.read_to_end(&mut joined).unwrap();
+    assert_eq!(joined, data);
+
+    // A missing file in the middle
+    std::fs::remove_file(dir.join("out.001")).unwrap();
+    assert_eq!(
+        SequenceReader::new(&prefix).err().unwrap().to_string(),
+        format!(
+            "Invalid argument: missing {}, the files to join go up to {}",
+            dir.join("out.001").display(),
+            dir.join("out.002").display()
+        )
+    );
+    assert!(SequenceReader::new(dir.join("none")).is_err());
+}
+
+#[test]
+fn test_split_numbering() {
+    let dir = temp_dir("numbering");
+    let prefix = dir.join("chunk");
+    assert_eq!(SplittingWriter::path(&prefix, 7), dir.join("chunk.007"));
+    assert_eq!(SplittingWriter::path(&prefix, 1234), dir.join("chunk.1234"));
+
+    // An exact multiple of the size does not start an empty file, and an empty output is one empty file
+    let mut writer = SplittingWriter::new(&prefix, 2).unwrap();
+    writer.write_all(b"abcd").unwrap();
+    assert_eq!(writer.count(), 2);
+    assert!(SplittingWriter::new(dir.join("empty"), 2).is_ok());
+    assert_eq!(std::fs::metadata(dir.join("empty.000")).unwrap().len(), 0);
+    assert!(SplittingWriter::new(dir.join("zero"), 0).is_err());
+}
+
+#[test]
+fn test_expect_writer() {
+    let expect = |expected: &[u8], chunks: &[&[u8]]| {
+        let mut writer = ExpectWriter::new(expected.to_vec());
+        for chunk in chunks {
+            writer.write_all(chunk).unwrap();
+        }
+        (writer.matches(), writer.count())
+    };
+    assert_eq!(expect(b"secret", &[b"sec", b"ret"]), (true, 6));
+    assert_eq!(expect(b"", &[]), (true, 0));
+    assert_eq!(expect(b"secret", &[b"secreT"]), (false, 6));
+    assert_eq!(expect(b"secret", &[b"Secret"]), (false, 6));
+    assert_eq!(expect(b"secret", &[b"secre"]), (false, 5));
+    assert_eq!(expect(b"secret", &[b"secret", b"\0"]), (false, 7));
+    assert_eq!(expect(b"secret\0", &[b"secret"]), (false, 6));
+}
+
+#[test]
+fn test_tee_writer() {
+    let mut tee = TeeWriter::new(Vec::new(), "first", Vec::new(), "second");
+    let mut writer = FormattedWriter::new(&mut tee, 2, 4);
+    writer.write_all(b"1b348fff").unwrap();
+    writer.flush().unwrap();
+    let (first, second) = tee.into_inner();
+    assert_eq!(first, b"1b 34\n8f ff\n");
+    assert_eq!(first, second);
+
+    // Errors name the target that failed
+    let mut full = [0u8; 2];
+    let mut tee = TeeWriter::new(Vec::new(), "first", &mut full[..], "second");
+    let e = tee.write_all(b"1b34").unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::WriteZero);
+    assert!(e.to_string().starts_with("second: "), "{}", e);
+}
+
+#[test]
+fn test_tee_writer_closed() {
+    /// Fails as if the reader had closed the pipe
+    struct Closed;
+    impl Write for Closed {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut tee = TeeWriter::new(Closed, "first", Vec::new(), "second").lenient(true);
+    tee.write_all(b"1b").unwrap();
+    tee.write_all(b"34").unwrap();
+    tee.flush().unwrap();
+    assert!(tee.first_closed());
+    assert_eq!(tee.into_inner().1, b"1b34");
+
+    let mut tee = TeeWriter::new(Closed, "first", Vec::new(), "second");
+    let e = tee.write_all(b"1b").unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::BrokenPipe);
+    assert!(e.to_string().starts_with("first: "), "{}", e);
+}
+
+#[test]
+fn test_broadcast_writer() {
+    let mut broadcast = BroadcastWriter::new();
+    broadcast.push("first", Vec::new());
+    broadcast.push("second", Vec::new());
+    let mut read = Vec::new();
+    TeeReader::new(ChunkedReader::new(b"1b348fff", 3), &mut broadcast)
+        .read_to_end(&mut read)
+        .unwrap();
+    assert_eq!(read, b"1b348fff");
+    for (_, target) in broadcast.into_inner() {
+        assert_eq!(target, read);
+    }
+
+    // Errors name the target that failed, and stop the writing
+    let mut broadcast = BroadcastWriter::new();
+    broadcast.push("first", FaultyWriter::new(Vec::new(), usize::MAX));
+    broadcast.push("second", FaultyWriter::new(Vec::new(), 1));
+    broadcast.write_all(b"1b").unwrap();
+    let e = broadcast.write_all(b"34").unwrap_err();
+    match BytaryError::from(e) {
+        BytaryError::BranchFailed(name, e) => {
+            assert_eq!(name, "second");
+            assert!(matches!(*e, BytaryError::Io(_)));
+        }
+        e => panic!("{}", e),
+    }
+    assert!(broadcast.write_all(b"8f").is_err());
+    let targets = broadcast.into_inner();
+    assert_eq!(targets[0].1.calls(), 2);
+    assert_eq!(targets[1].1.calls(), 2);
+}
+
+#[test]
+fn test_carried_error() {
+    let e = into_io_error(BytaryError::RecordTooLarge(4));
+    assert!(matches!(
+        BytaryError::from(e),
+        BytaryError::RecordTooLarge(4)
+    ));
+
+    // IO errors are not wrapped
+    let e = into_io_error(BytaryError::Io(std::io::ErrorKind::BrokenPipe.into()));
+    assert_eq!(e.kind(), std::io::ErrorKind::BrokenPipe);
+    assert!(BytaryError::from(e).is_broken_pipe());
+}
+
+#[test]
+fn test_head_writer() {
+    let mut head = HeadWriter::new(Vec::new(), 3);
+    assert_eq!(head.write(b"").unwrap(), 0);
+    assert_eq!(head.write(b"48").unwrap(), 2);
+    // A write past the limit is cut, and the next one fails
+    assert_eq!(head.write(b"69").unwrap(), 1);
+    assert!(head.is_full());
+    let e = head.write(b"9").unwrap_err();
+    assert!(matches!(
+        BytaryError::from(e),
+        BytaryError::OutputLimitReached
+    ));
+    head.flush().unwrap();
+    assert_eq!(head.into_inner(), b"486");
+}
+
+#[test]
+fn test_limited_reader() {
+    // Exactly the limit
+    let mut output = Vec::new();
+    LimitedReader::new(&b"Hello"[..], 5)
+        .read_to_end(&mut output)
+        .unwrap();
+    assert_eq!(output, b"Hello");
+
+    let mut inner = CountingReader::new(&b"Hello, world"[..]);
+    let mut limited = LimitedReader::new(&mut inner, 4);
+    let mut buffer = [0u8; 3];
+    assert_eq!(limited.read(&mut buffer).unwrap(), 3);
+    assert!(limited.read(&mut buffer).is_err());
+    // Reading again after the error still fails, without reading any further
+    assert!(limited.read(&mut buffer).is_err());
+    assert!(limited.read(&mut buffer).is_err());
+    assert_eq!(inner.count(), 5);
+}
+
+#[test]
+fn test_tail_writer() {
+    let mut generator = ByteGenerator::from_env();
+    let data = generator.bytes(10000);
+    for size in [0, 1, 7, 4096, 20000] {
+        let mut tail = TailWriter::new(Vec::new(), size);
+        let mut rest = &data[..];
+        while !rest.is_empty() {
+            let length = rest.len().min(generator.below(3000) + 1);
+            tail.write_all(&rest[..length]).unwrap();
+            rest = &rest[length..];
+        }
+        // Nothing is written before the end
+        tail.flush().unwrap();
+        tail.finish().unwrap();
+        let expected = &data[data.len().saturating_sub(size)..];
+        assert_eq!(tail.into_inner(), expected, "last {} bytes", size);
+    }
+
+    let mut tail = TailWriter::new(Vec::new(), 6);
+    let mut formatted = FormattedWriter::new(&mut tail, 2, 0);
+    formatted.write_all(b"1b348fff").unwrap();
+    tail.finish().unwrap();
+    assert_eq!(tail.into_inner(), b" 8f ff");
+}
+
+#[test]
+fn test_parse_size() {
+    assert_eq!(parse_size("0"), Ok(0));
+    assert_eq!(parse_size("4096"), Ok(4096));
+    assert_eq!(parse_size(" 12 B "), Ok(12));
+    for (unit, shift) in [("k", 10), ("M", 20), ("G", 30)] {
+        for suffix in ["", "i", "B", "iB"] {
+            for name in [
+                format!("{}{}", unit, suffix),
+                format!("{}{}", unit, suffix).to_lowercase(),
+                format!("{}{}", unit, suffix).to_uppercase(),
+            ] {
+                assert_eq!(
+                    parse_size(&format!("3{}", name)),
+                    Ok(3 << shift),
+                    "{}",
+                    name
+                );
+                assert_eq!(
+                    parse_size(&format!("3 {}", name)),
+                    Ok(3 << shift),
+                    "{}",
+                    name
+                );
+            }
+        }
+    }
+    assert_eq!(parse_size("15E"), Ok(15 << 60));
+    assert_eq!(parse_size("0k"), Ok(0));
+
+    assert_eq!(parse_size("1.5M"), Ok(3 << 19));
+    assert_eq!(parse_size(".5k"), Ok(512));
+    assert_eq!(parse_size("0.1k"), Ok(102));
+    assert_eq!(parse_size("1.0009765625k"), Ok(1025));
+    assert_eq!(parse_size("2.4"), Ok(2));
+    assert_eq!(parse_size("2.5"), Ok(3));
+
+    assert_eq!(parse_size(""), Err(SizeParseError::Empty));
+    assert_eq!(parse_size("-1"), Err(SizeParseError::Negative));
+    assert_eq!(parse_size("-1k"), Err(SizeParseError::Negative));
+    assert_eq!(
+        parse_size("k"),
+        Err(SizeParseError::InvalidNumber("k".into()))
+    );
+    assert_eq!(
+        parse_size("1.2.3"),
+        Err(SizeParseError::InvalidNumber("1.2.3".into()))
+    );
+    assert_eq!(
+        parse_size("3x"),
+        Err(SizeParseError::UnknownSuffix("X".into()))
+    );
+    assert_eq!(
+        parse_size("3iB"),
+        Err(SizeParseError::UnknownSuffix("IB".into()))
+    );
+    assert_eq!(
+        parse_size("20E"),
+        Err(SizeParseError::Overflow("20E".into()))
+    );
+    assert_eq!(
+        parse_size("18446744073709551616"),
+        Err(SizeParseError::Overflow("18446744073709551616".into()))
+    );
+    assert_eq!(parse_size("18446744073709551615"), Ok(u64::MAX));
+    assert!(parse_size("99999999999999999999999999999999999999999").is_err());
+}
+
+#[test]
+fn test_format_size() {
+    assert_eq!(format_size(0), "0 B");
+    assert_eq!(format_size(1023), "1023 B");
+    assert_eq!(format_size(1024), "1 KiB");
+    assert_eq!(format_size(1536), "1.5 KiB");
+    assert_eq!(format_size(16 << 20), "16 MiB");
+    assert_eq!(format_size(u64::MAX), "16.0 EiB");
+
+    for size in [
+        0,
+        1,
+        1000,
+        1024,
+        3 << 19,
+        5 << 30,
+        7 << 60,
+        123_456_789,
+        u64::MAX / 3,
+    ] {
+        let formatted = format_size(size);
+        let parsed = parse_size(&formatted).unwrap();
+        if formatted.contains('.') {
+            assert!(parsed.abs_diff(size) <= size / 20, "{} {}", size, formatted);
+        } else {
+            assert_eq!(parsed, size, "{}", formatted);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use crate::builtins::{HexDecoder, Padding};
+use crate::digits::{describe_non_ascii, is_bin_digit, is_oct_digit};
+use crate::error::{BytaryError, BytaryResult};
+use crate::format::Format;
+use crate::utils::{is_ignorable, is_separator};
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::Read;
+
+#[cfg(test)]
+mod test;
+
+/// The result of [`Format::validate`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidationReport {
+    /// Number of complete logical units (bytes) found before the end of input, or before the error
+    pub units: usize,
+    /// The first problem found in the input, if any
+    pub error: Option<InvalidInput>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn invalid(units: usize, error: InvalidInput) -> Self {
+        Self {
+            units,
+            error: Some(error),
+        }
+    }
+}
+
+/// The position of a problem found by [`Format::validate`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidInput {
+    /// Byte offset in the input
+    pub offset: usize,
+    /// The offending character, or `None` if the input ended in the middle of a unit
+    pub character: Option<char>,
+}
+
+impl Display for InvalidInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.character {
+            Some(c) => match describe_non_ascii(c) {
+                Some(description) => write!(
+                    f,
+                    "unexpected character {:?} ({}) at offset {}",
+                    c, description, self.offset
+                ),
+                None => write!(f, "unexpected character {:?} at offset {}", c, self.offset),
+            },
+            None => write!(
+                f,
+                "incomplete unit at end of input (offset {})",
+                self.offset
+            ),
+        }
+    }
+}
+
+impl Format {
+    /// Checks that the input is well-formed for this format, without decoding it.
+    ///
+    /// Validation is strict: bin and oct only accept their own digits, [ignorable](is_ignorable) whitespace
+    /// and [separators](is_separator),
+    /// and the number of digits must fill whole bytes.
+    /// Hex accepts exactly what the hex decoder accepts.
+    ///
+    /// Returns [`BytaryError::UnsupportedConversion`] for formats that can not be validated.
+    ///
+    /// ```rust
+    /// use bytary::format::Format;
+    ///
+    /// let report = Format::Hex.validate(&mut &b"1b 3g"[..]).unwrap();
+    /// assert_eq!(report.units, 1);
+    /// assert_eq!(report.error.unwrap().offset, 4);
+    /// ```
+    pub fn validate(&self, reader: &mut dyn Read) -> BytaryResult<ValidationReport> {
+        self.validate_with_padding(reader, Padding::Strict)
+    }
+
+    /// Checks that the input is well-formed for this format, accepting the bin and oct input that the
+    /// decoders of [`ConversionGraph::with_padding`](crate::convert::ConversionGraph::with_padding)
+    /// accept with `padding`, and counting the bytes they decode from it.
+    ///
+    /// ```rust
+    /// use bytary::builtins::Padding;
+    /// use bytary::format::Format;
+    ///
+    /// let report = Format::Oct.validate_with_padding(&mut &b"1 13 377"[..], Padding::Token).unwrap();
+    /// assert!(report.is_valid());
+    /// assert_eq!(report.units, 3);
+    /// ```
+    pub fn validate_with_padding(
+        &self,
+        reader: &mut dyn Read,
+        padding: Padding,
+    ) -> BytaryResult<ValidationReport> {
+        // The highest digit that can start a byte
+        let (group, is_digit, max_lead): (usize, fn(u8) -> bool, u8) = match self {
+            Format::Bytes => {
+                return Ok(ValidationReport {
+                    units: io::copy(reader, &mut io::sink())? as usize,
+                    error: None,
+                });
+            }
+            Format::Hex => return validate_hex(reader),
+            Format::Bin => (8, is_bin_digit, b'1'),
+            // A byte is at most 0o377
+            Format::Oct => (3, is_oct_digit, b'3'),
+            _ => return BytaryError::UnsupportedConversion(self.clone(), Format::Bytes).err(),
+        };
+
+        // Zeros are inserted before the digits of a part, so where its bytes start is only known at its end
+        let shifted = matches!(padding, Padding::Left | Padding::Token);
+        let mut buffer = [0u8; 1024];
+        let mut offset = 0;
+        // Units of the parts before the current one, which is a token with Padding::Token,
+        // or else the whole input
+        let mut units = 0;
+        let mut part = DigitPart::default();
+
+        loop {
+            let length = reader.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+
+            for (i, &byte) in buffer[..length].iter().enumerate() {
+                if is_ignorable(byte) || is_separator(byte) {
+                    if padding == Padding::Token && part.digits > 0 {
+                        match part.end(group, shifted) {
+                            Ok(part_units) => units += part_units,
+                            Err(error) => return Ok(ValidationReport::invalid(units, error)),
+                        }
+                    }
+                    continue;
+                }
+                if !is_digit(byte) {
+                    let error = InvalidInput {
+                        offset: offset + i,
+                        character: char_at(&buffer[i..length]),
+                    };
+                    return Ok(ValidationReport::invalid(
+                        units + part.digits / group,
+                        error,
+                    ));
+                }
+                if byte > max_lead {
+                    part.push_above(group, offset + i, byte);
+                }
+                part.digits += 1;
+                if !shifted && let Some(error) = part.above[0].take() {
+                    return Ok(ValidationReport::invalid(
+                        units + part.digits / group,
+                        error,
+                    ));
+                }
+            }
+            offset += length;
+        }
+
+        if padding == Padding::Strict && part.digits % group != 0 {
+            let error = InvalidInput {
+                offset,
+                character: None,
+            };
+            return Ok(ValidationReport::invalid(
+                units + part.digits / group,
+                error,
+            ));
+        }
+        Ok(match part.end(group, shifted) {
+            Ok(part_units) => ValidationReport {
+                units: units + part_units,
+                error: None,
+            },
+            Err(error) => ValidationReport::invalid(units, error),
+        })
+    }
+}
+
+/// The digits of bin or oct input that are padded together, see [`Format::validate_with_padding`]
+#[derive(Default)]
+struct DigitPart {
+    digits: usize,
+    /// For each number of zeros that may be inserted before the part, the first digit that would
+    /// then start a byte above 0xff
+    above: [Option<InvalidInput>; 8],
+}
+
+impl DigitPart {
+    /// Records the digit `byte` at `offset`, which is too high to start a byte
+    fn push_above(&mut self, group: usize, offset: usize, byte: u8) {
+        for zeros in 0..group {
+            if (self.digits + zeros).is_multiple_of(group) && self.above[zeros].is_none() {
+                self.above[zeros] = Some(InvalidInput {
+                    offset,
+                    character: Some(byte as char),
+                });
+            }
+        }
+    }
+
+    /// Ends the part, returning its number of bytes once padded, or the digit that starts one above 0xff
+    fn end(&mut self, group: usize, shifted: bool) -> Result<usize, InvalidInput> {
+        let part = std::mem::take(self);
+        let zeros = match shifted {
+            true => (group - part.digits % group) % group,
+            false => 0,
+        };
+        match part.above.into_iter().nth(zeros).flatten() {
+            Some(error) => Err(error),
+            None => Ok(part.digits.div_ceil(group)),
+        }
+    }
+}
+
+fn validate_hex(reader: &mut dyn Read) -> BytaryResult<ValidationReport> {
+    let mut buffer = [0u8; 1024];
+    let mut bytes = Vec::new();
+    let mut decoder = HexDecoder::new();
+    let mut units = 0;
+
+    loop {
+        let length = reader.read(&mut buffer)?;
+        if length == 0 {
+            break;
+        }
+        let result = decoder.feed(&buffer[..length], &mut bytes);
+        units += bytes.len();
+        bytes.clear();
+
+        if let Err(e) = result {
+            return Ok(ValidationReport {
+                units,
+                error: Some(e),
+            });
+        }
+    }
+
+    let result = decoder.finish(&mut bytes);
+    Ok(ValidationReport {
+        units: units + bytes.len(),
+        error: result.err(),
+    })
+}
+
+/// Decodes the character at the start of `bytes`, or U+FFFD if it is not valid UTF-8
+pub(crate) fn char_at(bytes: &[u8]) -> Option<char> {
+    String::from_utf8_lossy(&bytes[..bytes.len().min(4)])
+        .chars()
+        .next()
+}
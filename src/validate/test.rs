@@ -0,0 +1,152 @@
+use crate::builtins::Padding;
+use crate::convert::ConversionGraph;
+use crate::error::BytaryResult;
+use crate::format::Format;
+use crate::format::Format::*;
+use crate::validate::{InvalidInput, ValidationReport};
+use crate::warning;
+
+fn validate(format: Format, input: &[u8]) -> BytaryResult<ValidationReport> {
+    format.validate(&mut &input[..])
+}
+
+fn invalid(units: usize, offset: usize, character: Option<char>) -> ValidationReport {
+    ValidationReport {
+        units,
+        error: Some(InvalidInput { offset, character }),
+    }
+}
+
+fn valid(units: usize) -> ValidationReport {
+    ValidationReport { units, error: None }
+}
+
+#[test]
+fn test_valid() -> BytaryResult<()> {
+    assert_eq!(validate(Hex, b"1b34 8fFf\n000e\n")?, valid(6));
+    assert_eq!(validate(Hex, b"0x1b,0x34 \\x8f 1b:34")?, valid(5));
+    assert_eq!(validate(Bin, b"0001 1011\n0011 0100\n")?, valid(2));
+    assert_eq!(validate(Oct, b"016 070 377")?, valid(3));
+    assert_eq!(validate(Bytes, b"\xff\x00 anything")?, valid(11));
+    Ok(())
+}
+
+#[test]
+fn test_empty() -> BytaryResult<()> {
+    for format in [Hex, Bin, Oct, Bytes] {
+        assert_eq!(validate(format, b"")?, valid(0));
+    }
+    assert_eq!(validate(Hex, b" \n\t")?, valid(0));
+    Ok(())
+}
+
+#[test]
+fn test_invalid_character() -> BytaryResult<()> {
+    assert_eq!(validate(Hex, b"1b 3g")?, invalid(1, 4, Some('g')));
+    assert_eq!(validate(Hex, b"0x1b 1x")?, invalid(1, 6, Some('x')));
+    assert_eq!(validate(Bin, b"00000001 2")?, invalid(1, 9, Some('2')));
+    assert_eq!(validate(Oct, b"016 078")?, invalid(1, 6, Some('8')));
+    assert_eq!(validate(Oct, b"016 400")?, invalid(1, 4, Some('4')));
+    assert_eq!(
+        validate(Hex, "1b\u{e9}".as_bytes())?,
+        invalid(1, 2, Some('\u{e9}'))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_invalid_offset_across_chunks() -> BytaryResult<()> {
+    let mut input = b"00".repeat(1000);
+    input.push(b'z');
+    assert_eq!(validate(Hex, &input)?, invalid(1000, 2000, Some('z')));
+    Ok(())
+}
+
+#[test]
+fn test_incomplete() -> BytaryResult<()> {
+    assert_eq!(validate(Hex, b"1b3")?, invalid(1, 3, None));
+    assert_eq!(validate(Bin, b"101\n")?, invalid(0, 4, None));
+    assert_eq!(validate(Oct, b"0160")?, invalid(1, 4, None));
+    Ok(())
+}
+
+#[test]
+fn test_padding() -> BytaryResult<()> {
+    let validate = |format: Format, input: &[u8], padding| {
+        format.validate_with_padding(&mut &input[..], padding)
+    };
+    assert_eq!(
+        validate(Bin, b"1 10", Padding::Strict)?,
+        invalid(0, 4, None)
+    );
+    assert_eq!(validate(Bin, b"1 10", Padding::Token)?, valid(2));
+    assert_eq!(validate(Oct, b"1 10", Padding::Left)?, valid(1));
+    assert_eq!(validate(Oct, b"1 10 3", Padding::Right)?, valid(2));
+    // Where a byte starts depends on the zeros inserted before the digits
+    assert_eq!(validate(Oct, b"4 1", Padding::Left)?, valid(1));
+    assert_eq!(
+        validate(Oct, b"4 1", Padding::Right)?,
+        invalid(0, 0, Some('4'))
+    );
+    assert_eq!(
+        validate(Oct, b"1 400", Padding::Token)?,
+        invalid(1, 2, Some('4'))
+    );
+    assert_eq!(validate(Oct, b"1 40", Padding::Token)?, valid(2));
+    Ok(())
+}
+
+#[test]
+fn test_matches_decoders() -> BytaryResult<()> {
+    let inputs: [&[u8]; 12] = [
+        b"1 10",
+        b"0 13 377",
+        b"0 1011 11111111",
+        b"0100100001101001 1",
+        b"01001000 01101001 0",
+        b"0100100 01101001",
+        b"0001 1011\n1 1",
+        b"1 400",
+        b"4 1",
+        b"37 7",
+        b"13:7,1-1",
+        b"",
+    ];
+    let paddings = [
+        Padding::Strict,
+        Padding::Left,
+        Padding::Right,
+        Padding::Token,
+    ];
+    for padding in paddings {
+        let graph = ConversionGraph::with_padding(padding);
+        for format in [Bin, Oct] {
+            for input in inputs {
+                let report = format.validate_with_padding(&mut &input[..], padding)?;
+                let mut output = Vec::new();
+                let (result, _) = warning::collect(|| {
+                    graph
+                        .get_converter(&format, &Bytes)
+                        .unwrap()
+                        .convert(&mut &input[..], &mut output)
+                });
+                let case = format!(
+                    "{:?} {} {:?}",
+                    String::from_utf8_lossy(input),
+                    format,
+                    padding
+                );
+                assert_eq!(report.is_valid(), result.is_ok(), "{}: {:?}", case, report);
+                if report.is_valid() {
+                    assert_eq!(report.units, output.len(), "{}", case);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_unsupported() {
+    assert!(validate(Base64, b"").is_err());
+}
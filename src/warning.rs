@@ -0,0 +1,175 @@
+use crate::utils::plural;
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+
+#[cfg(test)]
+mod test;
+
+/// A recoverable issue found by a converter, which still completes the conversion
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Warning {
+    /// The input ended with digits that do not fill a whole byte, which were dropped
+    TrailingPartial {
+        /// Number of dropped digits
+        digits: usize,
+        /// Number of digits in a byte
+        group: usize,
+    },
+    /// Zeros were added to fill incomplete bytes, according to [`Padding`](crate::builtins::Padding)
+    Padded { zeros: usize },
+    /// Invalid text was replaced with U+FFFD, according to [`TextOptions`](crate::text::TextOptions)
+    Replaced { count: usize },
+    /// Octal groups above `377` were reduced to their low 8 bits, according to [`OctToBytes::mask`](crate::builtins::OctToBytes::mask)
+    Masked { count: usize },
+    /// The input ended in the middle of a frame of interleaved channels, which was dropped,
+    /// see [`transform`](crate::transform)
+    PartialFrame {
+        /// Number of dropped bytes
+        bytes: usize,
+        /// Number of bytes in a frame
+        frame: usize,
+    },
+    /// The input ended in the middle of a word to byte-swap, which was passed through unchanged,
+    /// see [`transform`](crate::transform)
+    PartialWord {
+        /// Number of bytes passed through
+        bytes: usize,
+        /// Number of bytes in a word
+        width: usize,
+    },
+    /// Characters that editors add to pasted text were removed, see
+    /// [`cleanup_pasted_text`](crate::utils::cleanup_pasted_text)
+    Cleaned { count: usize },
+    /// Full-width forms of ASCII characters were replaced with them, see
+    /// [`PasteCleanupReader::fullwidth`](crate::utils::PasteCleanupReader::fullwidth)
+    Transliterated { count: usize },
+    /// Non-ASCII characters were skipped, see
+    /// [`PasteCleanupReader::skip_non_ascii`](crate::utils::PasteCleanupReader::skip_non_ascii)
+    NonAscii { count: usize },
+    /// The input ended before the start or the end pattern of a
+    /// [`PatternWindowReader`](crate::utils::PatternWindowReader) was found
+    PatternNotFound {
+        /// Whether it is the start pattern, so that nothing was passed through,
+        /// or else the end pattern, so that the rest of the input was
+        start: bool,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::TrailingPartial { digits, group } => write!(
+                f,
+                "dropped {}, a byte needs {}",
+                plural(*digits, "trailing digit"),
+                group
+            ),
+            Warning::Padded { zeros } => {
+                write!(f, "padded the input with {}", plural(*zeros, "zero"))
+            }
+            Warning::Replaced { count } => write!(
+                f,
+                "replaced {} with U+FFFD",
+                plural(*count, "invalid sequence")
+            ),
+            Warning::Masked { count } => write!(
+                f,
+                "kept the low 8 bits of {} above 377",
+                plural(*count, "octal group")
+            ),
+            Warning::PartialFrame { bytes, frame } => write!(
+                f,
+                "dropped a partial frame of {}, a frame has {}",
+                plural(*bytes, "byte"),
+                frame
+            ),
+            Warning::PartialWord { bytes, width } => write!(
+                f,
+                "left a partial word of {} unswapped, a word has {}",
+                plural(*bytes, "byte"),
+                width
+            ),
+            Warning::Cleaned { count } => write!(
+                f,
+                "removed {} like no-break spaces and curly quotes",
+                plural(*count, "pasted character")
+            ),
+            Warning::Transliterated { count } => write!(
+                f,
+                "replaced {} with their ASCII forms",
+                plural(*count, "full-width character")
+            ),
+            Warning::PatternNotFound { start } => match start {
+                true => write!(f, "start pattern not found"),
+                false => write!(f, "end pattern not found"),
+            },
+            Warning::NonAscii { count } => {
+                write!(f, "skipped {}", plural(*count, "non-ASCII character"))
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// Warnings reported on this thread, `None` if nobody collects them
+    static SINK: RefCell<Option<Vec<Warning>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, collecting the warnings that converters report on this thread meanwhile.
+///
+/// Converters called outside of `collect` behave as before warnings existed,
+/// failing where they would otherwise report a recoverable issue.
+///
+/// ```rust
+/// use bytary::builtins::hex_to_bytes;
+/// use bytary::warning::{Warning, collect};
+///
+/// let mut output = Vec::new();
+/// let (result, warnings) = collect(|| hex_to_bytes(&mut &b"4869f"[..], &mut output));
+/// assert!(result.is_ok());
+/// assert_eq!(output, b"Hi");
+/// assert_eq!(warnings, [Warning::TrailingPartial { digits: 1, group: 2 }]);
+///
+/// assert!(hex_to_bytes(&mut &b"4869f"[..], &mut Vec::new()).is_err());
+/// ```
+pub fn collect<T>(f: impl FnOnce() -> T) -> (T, Vec<Warning>) {
+    let outer = SINK.with(|sink| sink.replace(Some(Vec::new())));
+    let result = f();
+    let warnings = SINK.with(|sink| sink.replace(outer)).unwrap_or_default();
+    (result, warnings)
+}
+
+/// Runs `f` as if outside of any [`collect`], so that converters fail instead of reporting warnings.
+///
+/// ```rust
+/// use bytary::builtins::hex_to_bytes;
+/// use bytary::warning::{collect, strict};
+///
+/// let (result, warnings) = collect(|| strict(|| hex_to_bytes(&mut &b"4869f"[..], &mut Vec::new())));
+/// assert!(result.is_err());
+/// assert!(warnings.is_empty());
+/// ```
+pub fn strict<T>(f: impl FnOnce() -> T) -> T {
+    let outer = SINK.with(|sink| sink.replace(None));
+    let result = f();
+    SINK.with(|sink| sink.replace(outer));
+    result
+}
+
+/// Reports a warning to the enclosing [`collect`].
+///
+/// Returns false if there is none, in which case a converter should fail instead of recovering.
+pub fn report(warning: Warning) -> bool {
+    SINK.with(|sink| match sink.borrow_mut().as_mut() {
+        Some(warnings) => {
+            warnings.push(warning);
+            true
+        }
+        None => false,
+    })
+}
+
+/// Returns true inside a [`collect`], where converters recover from issues instead of failing.
+pub fn collecting() -> bool {
+    SINK.with(|sink| sink.borrow().is_some())
+}
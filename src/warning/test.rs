@@ -0,0 +1,102 @@
+use crate::builtins::Padding;
+use crate::convert::ConversionGraph;
+use crate::error::BytaryResult;
+use crate::format::Format::*;
+use crate::warning::{Warning, collect, report};
+
+#[test]
+fn test_odd_hex() -> BytaryResult<()> {
+    let mut input = "0123456789abcdef".repeat(1000);
+    input.push('f');
+
+    let mut output = Vec::new();
+    let warnings = ConversionGraph::default().convert_with_report(
+        &Hex,
+        &Bytes,
+        &mut input.as_bytes(),
+        &mut output,
+    )?;
+    assert_eq!(output, hex::decode(&input[..input.len() - 1]).unwrap());
+    assert_eq!(
+        warnings,
+        [Warning::TrailingPartial {
+            digits: 1,
+            group: 2
+        }]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "dropped 1 trailing digit, a byte needs 2"
+    );
+
+    // Without collecting warnings, it is an error
+    let converter = ConversionGraph::default()
+        .get_converter(&Hex, &Bytes)
+        .unwrap();
+    assert!(
+        converter
+            .convert(&mut input.as_bytes(), &mut Vec::new())
+            .is_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_not_skipped() {
+    // Characters that are not digits are rejected rather than skipped with a warning
+    let (result, warnings) = collect(|| {
+        ConversionGraph::default().convert(
+            &Oct,
+            &Bytes,
+            &mut &b"110,070;\n101"[..],
+            &mut Vec::new(),
+        )
+    });
+    assert!(result.is_err());
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_padded() -> BytaryResult<()> {
+    let mut output = Vec::new();
+    let warnings = ConversionGraph::with_padding(Padding::Token).convert_with_report(
+        &Oct,
+        &Bytes,
+        &mut &b"01 13 377"[..],
+        &mut output,
+    )?;
+    assert_eq!(output, [1, 0o13, 0o377]);
+    // Only the token with a leading zero is incomplete, the others are unpadded output
+    assert_eq!(warnings, [Warning::Padded { zeros: 1 }]);
+
+    // Well-formed input has no warnings
+    let warnings = ConversionGraph::default().convert_with_report(
+        &Hex,
+        &Bytes,
+        &mut &b"1b 34"[..],
+        &mut Vec::new(),
+    )?;
+    assert!(warnings.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_nested() {
+    assert!(!report(Warning::Masked { count: 1 }));
+
+    let ((inner, outer_report), outer) = collect(|| {
+        report(Warning::Masked { count: 1 });
+        let ((), inner) = collect(|| {
+            report(Warning::Padded { zeros: 2 });
+        });
+        (inner, report(Warning::Masked { count: 3 }))
+    });
+    assert_eq!(inner, [Warning::Padded { zeros: 2 }]);
+    assert!(outer_report);
+    assert_eq!(
+        outer,
+        [Warning::Masked { count: 1 }, Warning::Masked { count: 3 }]
+    );
+
+    assert!(!report(Warning::Masked { count: 1 }));
+}
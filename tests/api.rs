@@ -0,0 +1,66 @@
+//! Uses the library only through `bytary::prelude` and the crate root,
+//! so that removing anything from them breaks this test.
+
+use bytary::prelude::*;
+use std::io::Write;
+
+#[test]
+fn test_prelude() -> BytaryResult<()> {
+    let mut graph = ConversionGraph::default();
+    graph
+        .add_edge(
+            Format::Hex,
+            Format::Dec,
+            EdgeInfo {
+                name: "hex_to_dec",
+                lossy: false,
+                cost: 1,
+            },
+            |_: &mut dyn std::io::Read, output: &mut dyn Write| Ok(output.write_all(b"255")?),
+        )
+        .unwrap();
+    let converter: std::rc::Rc<dyn Converter> =
+        graph.try_get_converter(&Format::Bytes, &Format::Hex)?;
+
+    let mut output = Vec::new();
+    converter.convert(
+        &mut &b"Hi!"[..],
+        &mut FormattedWriter::new(&mut output, 0, 4),
+    )?;
+    assert_eq!(output, b"4869\n21");
+
+    let graph = ConversionGraph::with_padding(Padding::Left);
+    let (result, warnings) = bytary::warning::collect(|| {
+        graph
+            .get_converter(&Format::Bin, &Format::Hex)
+            .unwrap()
+            .convert(&mut &b"101"[..], &mut Vec::new())
+    });
+    result?;
+    assert_eq!(warnings, [Warning::Padded { zeros: 5 }]);
+
+    assert!(matches!(
+        graph.try_get_converter(&Format::Stats, &Format::Bytes),
+        Err(BytaryError::UnsupportedConversion(_, _))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_crate_root() -> bytary::BytaryResult<()> {
+    let graph = bytary::ConversionGraph::default();
+    let converter = graph.try_get_converter(&bytary::Format::Hex, &bytary::Format::Bytes)?;
+
+    let mut output = Vec::new();
+    bytary::Converter::convert(converter.as_ref(), &mut &b"4869"[..], &mut output)?;
+    assert_eq!(output, b"Hi");
+
+    let mut formatted = bytary::FormattedWriter::new(Vec::new(), 1, 0);
+    formatted.write_all(b"ab")?;
+    assert_eq!(formatted.into_inner()?, b"a b");
+    assert!(matches!(
+        graph.try_get_converter(&bytary::Format::Stats, &bytary::Format::Hex),
+        Err(bytary::BytaryError::UnsupportedConversion(_, _))
+    ));
+    Ok(())
+}
@@ -0,0 +1,2130 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+fn bytary() -> Command {
+    Command::cargo_bin("bytary").unwrap()
+}
+
+#[test]
+fn test_bytes_to_piped_stdout() {
+    bytary()
+        .args(["bytes", "hex"])
+        .write_stdin("48 69")
+        .assert()
+        .success()
+        .stdout("Hi");
+}
+
+#[test]
+fn test_bytes_to_file() {
+    let path = std::env::temp_dir().join(format!("bytary-test-{}.bin", std::process::id()));
+
+    bytary()
+        .args(["bytes", "hex", "-o"])
+        .arg(&path)
+        .write_stdin("48 69")
+        .assert()
+        .success()
+        .stdout("");
+
+    assert_eq!(fs::read(&path).unwrap(), b"Hi");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_force() {
+    bytary()
+        .args(["bytes", "hex", "--force"])
+        .write_stdin("48 69")
+        .assert()
+        .success()
+        .stdout("Hi");
+}
+
+/// Conversions of the files in `tests/fixtures/golden`, as (arguments, input, expected output)
+const GOLDEN: &[(&[&str], &str, &str)] = &[
+    (&["hex"], "sample.bin", "sample.hex"),
+    (
+        &["hex", "-s", "2", "-w", "32"],
+        "sample.bin",
+        "sample.s2w32.hex",
+    ),
+    (
+        &["bin", "-s", "8", "-w", "72"],
+        "sample.bin",
+        "sample.s8w72.bin.txt",
+    ),
+    (&["oct", "-s", "3"], "sample.bin", "sample.s3.oct"),
+    (&["base64", "-w", "76"], "sample.bin", "sample.w76.base64"),
+    (
+        &["pem", "--pem-label", "SAMPLE"],
+        "sample.bin",
+        "sample.pem",
+    ),
+    (&["stats"], "sample.bin", "sample.stats"),
+    (&["bytes", "hex"], "sample.s2w32.hex", "sample.bin"),
+    (&["bytes", "base64"], "sample.w76.base64", "sample.bin"),
+    (&["hex", "pem"], "sample.pem", "sample.hex"),
+];
+
+/// Compares the output of every conversion in [`GOLDEN`] byte by byte with its golden file.
+///
+/// Set `BYTARY_UPDATE_GOLDEN=1` to write the outputs to the golden files instead.
+#[test]
+fn test_golden() {
+    let dir = Path::new("tests/fixtures/golden");
+    let update = std::env::var_os("BYTARY_UPDATE_GOLDEN").is_some();
+
+    for (args, input, golden) in GOLDEN {
+        let input = fs::read(dir.join(input)).unwrap();
+        let output = bytary()
+            .args(*args)
+            .write_stdin(input)
+            .assert()
+            .success()
+            .stderr("")
+            .get_output()
+            .stdout
+            .clone();
+
+        let path = dir.join(golden);
+        if update {
+            fs::write(&path, &output).unwrap();
+            continue;
+        }
+        let expected = fs::read(&path).unwrap();
+        assert!(
+            output == expected,
+            "`bytary {}` differs from {}:\n{}",
+            args.join(" "),
+            path.display(),
+            String::from_utf8_lossy(&output)
+        );
+    }
+}
+
+#[test]
+fn test_list_formats() {
+    bytary()
+        .arg("--list-formats")
+        .assert()
+        .success()
+        .stdout("Available formats: bytes, bin, hex, hexswap, oct, leb128, rle, base64, pem, utf8, utf16le, utf16be, codepoints\n")
+        .stderr("");
+}
+
+#[test]
+fn test_unsupported_conversion() {
+    bytary()
+        .args(["stats", "hex"])
+        .write_stdin("41")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Total: 1 byte\n"));
+    bytary()
+        .args(["hex", "stats"])
+        .write_stdin("anything")
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr("Unsupported conversion: stats => hex\n");
+    bytary()
+        .args(["hex", "Nope"])
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(predicate::str::starts_with(
+            "error: invalid value 'Nope' for '[FROM]': Invalid Format: 'Nope'\n",
+        ));
+}
+
+#[test]
+fn test_exit_status() {
+    // Invalid input data
+    bytary()
+        .args(["bytes", "bin"])
+        .write_stdin("012")
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::starts_with("Invalid input data: "));
+    // Writing the output fails
+    let dir = temp_dir("exit-status");
+    bytary()
+        .args(["hex", "-o"])
+        .arg(dir.join("missing").join("out.hex"))
+        .write_stdin("Hi")
+        .assert()
+        .code(3)
+        .stderr(predicate::str::starts_with("IO error: "));
+    // Reading an input file fails
+    bytary()
+        .args(["hex", "-i"])
+        .arg(dir.join("missing.bin"))
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("failed  IO error: "));
+    // Any error with --compare, since 1 means that the inputs differ
+    bytary()
+        .args(["bytes", "bin", "--compare"])
+        .arg(dir.join("missing.bin"))
+        .write_stdin("0")
+        .assert()
+        .code(2);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_formatting_flags() {
+    for (args, expected) in [
+        (&["hex"][..], "00017f80ff"),
+        (&["hex", "-s", "2"], "00 01 7f 80 ff"),
+        (&["hex", "-w", "4"], "0001\n7f80\nff"),
+        (&["hex", "-s", "2", "-w", "4"], "00 01\n7f 80\nff"),
+        (&["hex", "-s", "2", "-w", "8"], "00 01 7f 80\nff"),
+        (
+            &["bin", "-s", "8", "-w", "16"],
+            "00000000 00000001\n01111111 10000000\n11111111",
+        ),
+        (
+            &["oct", "--wrap-bytes", "2", "-s", "3"],
+            "000 001\n177 200\n377",
+        ),
+        // Groups straddle the line breaks, but no line ends with a space
+        (
+            &["hex", "-s", "3", "-w", "2", "--allow-odd-formatting"],
+            "00\n0 1\n7f\n80\nf f\n",
+        ),
+        (&["base64", "-w", "4"], "AAF/\ngP8=\n"),
+    ] {
+        bytary()
+            .args(args)
+            .write_stdin(&b"\x00\x01\x7f\x80\xff"[..])
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+}
+
+#[test]
+fn test_error_exit_code() {
+    let assert = bytary().args(["bytes", "nonsense"]).assert().code(2);
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    assert!(
+        stderr.starts_with(
+            "error: invalid value 'nonsense' for '[FROM]': Invalid Format: 'nonsense'\n"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("[possible values: bytes, bin, hex, hexswap, oct, dec, leb128, rle, base64, pem, utf8, utf16le, utf16be, codepoints, stats, byteset]"),
+        "{}",
+        stderr
+    );
+
+    bytary()
+        .args(["bytes", "hex"])
+        .write_stdin("zz")
+        .assert()
+        .code(1);
+
+    // Mistyped formats and aliases get the suggestions of Format::suggest
+    for (args, suggestion) in [
+        (&["hexx"][..], "'hexx', did you mean 'hex'?"),
+        (&["bytes", "bse16"], "'bse16', did you mean 'hex'?"),
+        (
+            &["--from-any", "hex,bse64", "hex"],
+            "'bse64', did you mean 'base64'?",
+        ),
+        (
+            &["utf16"],
+            "'utf16', did you mean 'utf8', 'utf16le' or 'utf16be'?",
+        ),
+    ] {
+        bytary()
+            .args(args)
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains(suggestion));
+    }
+    // Names are not case sensitive
+    bytary()
+        .args(["HEX", "Bytes", "--data", "A"])
+        .assert()
+        .success()
+        .stdout("41");
+}
+
+#[test]
+fn test_check() {
+    bytary()
+        .args(["bytes", "hex", "--check"])
+        .write_stdin("1b 34\n")
+        .assert()
+        .success()
+        .stdout("");
+
+    bytary()
+        .args(["bytes", "bin", "--check"])
+        .write_stdin("101")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("Invalid input data: incomplete unit at end of input (offset 3)\n");
+
+    // The bytes that the decoders with --pad read
+    bytary()
+        .args(["hex", "oct", "--check", "-v", "--pad", "token"])
+        .write_stdin("1 10")
+        .assert()
+        .success()
+        .stderr(predicate::str::ends_with("Valid oct input: 2 bytes\n"));
+    bytary()
+        .args(["hex", "oct", "--pad", "token"])
+        .write_stdin("1 10")
+        .assert()
+        .success()
+        .stdout("0108");
+    bytary()
+        .args(["hex", "bin", "--check"])
+        .write_stdin("1 10")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_comments() {
+    for (from, data) in [
+        ("hex", "48 69"),
+        ("bin", "01001000 01101001"),
+        ("oct", "110 151"),
+    ] {
+        let input = format!("# hi\n{} // greeting\n", data);
+        bytary()
+            .args(["bytes", from, "--comments"])
+            .write_stdin(input.as_str())
+            .assert()
+            .success()
+            .stdout("Hi");
+        // Comments are only stripped when asked, whatever the warnings
+        bytary()
+            .args(["bytes", from])
+            .write_stdin(input.as_str())
+            .assert()
+            .code(1)
+            .stdout("")
+            .stderr(predicate::str::contains(
+                "unexpected character '#' at offset 0",
+            ));
+    }
+}
+
+/// Creates an empty directory for one test
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("bytary-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+#[cfg(feature = "mmap")]
+fn test_mmap() {
+    let dir = temp_dir("mmap");
+    // A few MB, larger than any buffer of the converters
+    let data = (0..3 << 20)
+        .map(|i: u32| (i.wrapping_mul(2654435761) >> 13) as u8)
+        .collect::<Vec<u8>>();
+    fs::write(dir.join("data.bin"), &data).unwrap();
+    fs::write(dir.join("empty.bin"), b"").unwrap();
+
+    for args in [["hex", "bytes"], ["base64", "bytes"], ["stats", "bytes"]] {
+        for name in ["data.bin", "empty.bin"] {
+            let run = |mmap: bool| {
+                let mut command = bytary();
+                command.args(args).arg("-i").arg(dir.join(name));
+                if mmap {
+                    command.arg("--mmap");
+                }
+                command.assert().success().get_output().stdout.clone()
+            };
+            assert_eq!(run(true), run(false), "{:?} of {}", args, name);
+        }
+    }
+    let hex = bytary()
+        .args(["hex", "-i"])
+        .arg(dir.join("data.bin"))
+        .arg("--mmap")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(hex, hex::encode(&data).into_bytes());
+
+    // Not a regular file, read as usual
+    #[cfg(unix)]
+    bytary()
+        .args(["hex", "-i", "/dev/stdin", "--mmap"])
+        .write_stdin("Hi")
+        .assert()
+        .success()
+        .stdout("4869");
+
+    bytary().args(["hex", "--mmap"]).assert().code(2);
+}
+
+#[test]
+#[cfg(feature = "config")]
+fn test_preset() {
+    let dir = temp_dir("preset");
+    let config = dir.join("config.toml");
+    fs::write(
+        &config,
+        "[preset.mac]\nto = \"hex\"\nstyle = \"mac\"\n\n[preset.dump]\nto = \"hex\"\nspace = 2\n\n\
+         [preset.lines]\nto = \"hex\"\nwrap-bytes = 2\n\n[preset.decode]\nfrom = \"hex\"\n",
+    )
+    .unwrap();
+
+    bytary()
+        .args(["--data", "Hi!", "--preset", "mac", "--config"])
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout("48:69:21");
+    // Options given on the command line win
+    bytary()
+        .args(["bin", "--data", "Hi", "--preset", "dump", "--config"])
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout("01 00 10 00 01 10 10 01");
+    bytary()
+        .args(["--data", "Hi", "--preset", "dump", "-s", "4", "--config"])
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout("4869");
+    // Options of the preset are validated with the given ones
+    bytary()
+        .args(["--data", "Hi!!", "--preset", "lines", "-w", "4", "--config"])
+        .arg(&config)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+    bytary()
+        .args([
+            "--data",
+            "Hi!!",
+            "--preset",
+            "lines",
+            "--with-ascii",
+            "--config",
+        ])
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout("4869  Hi\n2121  !!\n");
+    bytary()
+        .args(["--data", "4869", "--preset", "decode", "--config"])
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout("Hi");
+    // The default config file
+    fs::create_dir_all(dir.join("bytary")).unwrap();
+    fs::copy(&config, dir.join("bytary").join("config.toml")).unwrap();
+    bytary()
+        .args(["--data", "Hi", "--preset", "mac"])
+        .env("XDG_CONFIG_HOME", &dir)
+        .assert()
+        .success()
+        .stdout("48:69");
+
+    bytary()
+        .args(["--data", "Hi", "--preset", "nope", "--config"])
+        .arg(&config)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("unknown preset 'nope' in"))
+        .stderr(predicate::str::contains(
+            "available: decode, dump, lines, mac",
+        ));
+
+    let broken = dir.join("broken.toml");
+    fs::write(&broken, "[preset.mac\n").unwrap();
+    bytary()
+        .args(["--data", "Hi", "--preset", "mac", "--config"])
+        .arg(&broken)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(format!(
+            "invalid config file '{}'",
+            broken.display()
+        )));
+}
+
+#[test]
+fn test_batch_output_dir() {
+    let dir = temp_dir("batch");
+    // Created with its parents
+    let out = dir.join("out").join("bin");
+    fs::write(dir.join("a.hex"), "48 69").unwrap();
+    fs::write(dir.join("b.hex"), "4g").unwrap();
+    fs::write(dir.join("c.hex"), "0x21").unwrap();
+
+    let assert = bytary()
+        .args(["bytes", "hex", "-i"])
+        .args(["a.hex", "b.hex", "c.hex", "missing.hex"].map(|name| dir.join(name)))
+        .arg("--output-dir")
+        .arg(&out)
+        .assert()
+        .failure()
+        .stdout("");
+
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    assert!(stderr.contains("4 files, 2 failed\n"), "{}", stderr);
+    assert!(stderr.ends_with("2 of 4 files failed\n"), "{}", stderr);
+
+    assert_eq!(fs::read(out.join("a.bin")).unwrap(), b"Hi");
+    assert_eq!(fs::read(out.join("c.bin")).unwrap(), b"!");
+    assert!(!out.join("b.bin").exists());
+    assert!(!out.join("missing.bin").exists());
+
+    // Errors name the file that cannot be created
+    fs::remove_file(out.join("a.bin")).unwrap();
+    fs::create_dir(out.join("a.bin")).unwrap();
+    bytary()
+        .args(["bytes", "hex", "-i"])
+        .arg(dir.join("a.hex"))
+        .arg("--output-dir")
+        .arg(&out)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "IO error: {}: ",
+            out.join("a.bin").display()
+        )))
+        .stderr(predicate::str::contains("1 file, 1 failed\n"))
+        .stderr(predicate::str::ends_with("1 of 1 file failed\n"));
+
+    // Errors name the directory that cannot be created
+    bytary()
+        .args(["bytes", "hex", "-i"])
+        .arg(dir.join("a.hex"))
+        .arg("--output-dir")
+        .arg(dir.join("a.hex").join("out"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(format!(
+            "IO error: {}: ",
+            dir.join("a.hex").join("out").display()
+        )));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_batch_output_ext() {
+    let dir = temp_dir("batch-ext");
+    fs::write(dir.join("a.txt"), "Hi").unwrap();
+    fs::write(dir.join("b.txt"), "!").unwrap();
+
+    bytary()
+        .args(["hex", "-i"])
+        .args([dir.join("a.txt"), dir.join("b.txt")])
+        .arg("--output-dir")
+        .arg(&dir)
+        .assert()
+        .success();
+    assert_eq!(fs::read(dir.join("a.hex")).unwrap(), b"4869");
+
+    bytary()
+        .args(["hex", "-i"])
+        .arg(dir.join("a.txt"))
+        .arg("--output-dir")
+        .arg(&dir)
+        .args(["--output-ext", "dump"])
+        .assert()
+        .success();
+    assert_eq!(fs::read(dir.join("a.dump")).unwrap(), b"4869");
+
+    // Without --output-dir, the outputs of several files would run together
+    bytary()
+        .args(["hex", "-i"])
+        .args([dir.join("a.txt"), dir.join("b.txt")])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("use --output-dir"));
+    bytary()
+        .args(["bytes", "--check", "-i"])
+        .args([dir.join("a.txt"), dir.join("b.txt")])
+        .assert()
+        .success();
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_stats() {
+    let assert = bytary()
+        .args(["hex", "--space", "2", "--stats"])
+        .write_stdin("Hi")
+        .assert()
+        .success()
+        .stdout("48 69");
+
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    assert!(
+        stderr.starts_with("Stats: path=bytes,hex in=2 out=5 time="),
+        "{}",
+        stderr
+    );
+    assert!(stderr.ends_with("B/s\n"), "{}", stderr);
+}
+
+#[test]
+fn test_stats_hops() {
+    let assert = bytary()
+        .args(["oct", "bin", "--stats"])
+        .write_stdin("0100100001101001")
+        .assert()
+        .success()
+        .stdout("110151");
+
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    let lines = stderr.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 4, "{}", stderr);
+    assert!(lines[0].starts_with("Stats: path=bin,hex,bytes,oct in=16 out=6 "));
+    assert!(lines[1].starts_with("bin -> hex: 16 B in, 4 B out, "));
+    assert!(lines[2].starts_with("hex -> bytes: 4 B in, 2 B out, "));
+    assert!(lines[3].starts_with("bytes -> oct: 2 B in, 6 B out, "));
+    assert!(lines[1..].iter().all(|line| line.ends_with("B/s")));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_stats() {
+    let assert = bytary()
+        .args(["bytes", "hex", "--json-stats", "--prefix", "<"])
+        .write_stdin("48 69")
+        .assert()
+        .success()
+        .stdout("<Hi");
+
+    let stats: serde_json::Value = serde_json::from_slice(&assert.get_output().stderr).unwrap();
+    assert_eq!(stats["path"], serde_json::json!(["hex", "bytes"]));
+    assert_eq!(stats["bytes_in"], 5);
+    assert_eq!(stats["bytes_out"], 3);
+    assert!(stats["seconds"].is_f64());
+    assert!(stats["throughput"].is_f64());
+}
+
+#[test]
+fn test_compare() {
+    let dir = temp_dir("compare");
+    let old = dir.join("old.bin");
+    fs::write(&old, b"Hello, World").unwrap();
+
+    bytary()
+        .arg("--compare")
+        .arg(&old)
+        .write_stdin("Hello, World")
+        .assert()
+        .code(0)
+        .stdout("");
+
+    bytary()
+        .arg("--compare")
+        .arg(&old)
+        .write_stdin("Hallo, World!")
+        .assert()
+        .code(1)
+        .stdout("00000001 changed 1: 65 => 61\n0000000c added 1: - => 21\n");
+
+    bytary()
+        .arg("--compare")
+        .arg(dir.join("missing.bin"))
+        .write_stdin("")
+        .assert()
+        .code(2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_frame() {
+    let dir = temp_dir("frame");
+    let input = dir.join("records.hex");
+    fs::write(&input, "48 69\n21\n\n0a 0b 0c\n").unwrap();
+
+    let assert = bytary()
+        .args(["bytes", "hex", "--frame", "len32", "-i"])
+        .arg(&input)
+        .assert()
+        .success();
+    assert_eq!(
+        assert.get_output().stdout,
+        b"\0\0\0\x02Hi\0\0\0\x01!\0\0\0\x03\x0a\x0b\x0c"
+    );
+
+    bytary()
+        .args(["bytes", "hex", "--frame", "nul"])
+        .write_stdin("48 69\n21\n")
+        .assert()
+        .success()
+        .stdout("Hi\0!\0");
+
+    bytary()
+        .args(["bytes", "hex", "--frame", "len32", "--max-record-size", "1"])
+        .write_stdin("48 69\n")
+        .assert()
+        .failure()
+        .stderr("Record larger than the maximum of 1 byte\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_dump_graph() {
+    let snapshot = fs::read("tests/fixtures/graph.dot").unwrap();
+    bytary()
+        .arg("--dump-graph")
+        .assert()
+        .success()
+        .stdout(snapshot);
+}
+
+#[test]
+fn test_pem() {
+    let certificate = fs::read("tests/fixtures/cert.pem").unwrap();
+    let der = fs::read("tests/fixtures/cert.der").unwrap();
+
+    bytary()
+        .args(["bytes", "pem"])
+        .write_stdin(certificate.clone())
+        .assert()
+        .success()
+        .stdout(der.clone());
+    bytary()
+        .args(["pem", "--pem-label", "CERTIFICATE"])
+        .write_stdin(der)
+        .assert()
+        .success()
+        .stdout(certificate);
+
+    bytary()
+        .args(["bytes", "pem", "--all-blocks"])
+        .write_stdin(
+            "-----BEGIN A-----\nSGk=\n-----END A-----\n-----BEGIN B-----\nIQ==\n-----END B-----\n",
+        )
+        .assert()
+        .success()
+        .stdout("Hi!");
+    bytary()
+        .args(["hex", "bytes", "--all-blocks"])
+        .write_stdin("Hi")
+        .assert()
+        .code(2)
+        .stderr("Invalid argument: --all-blocks only applies to pem input, not bytes\n");
+}
+
+#[test]
+fn test_text() {
+    let text = "Grüße, мир 😀\n";
+    let utf16le = text
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<u8>>();
+
+    bytary()
+        .args(["utf16le", "utf8"])
+        .write_stdin(text)
+        .assert()
+        .success()
+        .stdout(utf16le.clone());
+    bytary()
+        .args(["utf8", "utf16le"])
+        .write_stdin(utf16le.clone())
+        .assert()
+        .success()
+        .stdout(text);
+
+    let mut with_bom = b"\xff\xfe".to_vec();
+    with_bom.extend(&utf16le);
+    bytary()
+        .args(["utf8", "utf16be", "--strip-bom"])
+        .write_stdin(with_bom)
+        .assert()
+        .success()
+        .stdout(text);
+
+    bytary()
+        .args(["utf8", "utf16le"])
+        .write_stdin(b"a\x00\x00\xdc".to_vec())
+        .assert()
+        .code(1)
+        .stderr("Invalid input data: Invalid UTF-16 at offset 2: lone surrogate 0xdc00\n");
+    bytary()
+        .args(["utf8", "utf16le", "--replace-invalid"])
+        .write_stdin(b"a\x00\x00\xdc".to_vec())
+        .assert()
+        .success()
+        .stdout("a\u{fffd}")
+        .stderr("Warning: replaced 1 invalid sequence with U+FFFD\n");
+    bytary()
+        .args(["hex", "--strip-bom"])
+        .write_stdin("Hi")
+        .assert()
+        .code(2)
+        .stderr(
+            "Invalid argument: --strip-bom only applies to utf8, utf16le, utf16be or codepoints input or output, not bytes to hex\n",
+        );
+}
+
+#[test]
+fn test_wrap_text() {
+    // Two bytes per line would split every character but the first and the last
+    bytary()
+        .args(["utf8", "codepoints", "--wrap", "2"])
+        .write_stdin("U+0048 U+00E9 U+20AC U+1F600 U+0021")
+        .assert()
+        .success()
+        .stdout("Hé\n€😀\n!");
+    bytary()
+        .args(["utf8", "utf16le", "--space", "1"])
+        .write_stdin(b"\xe9\x00\x3d\xd8\x00\xde".to_vec())
+        .assert()
+        .success()
+        .stdout("é 😀");
+}
+
+#[test]
+fn test_dry_run() {
+    let dir = temp_dir("dry-run");
+    for size in [0usize, 1, 100, 1000] {
+        let path = dir.join(format!("{}.bin", size));
+        fs::write(&path, vec![0xa5u8; size]).unwrap();
+
+        for line in [
+            "hex",
+            "hex -s 2 -w 32",
+            "base64 --prefix x\\n",
+            "bin --wrap-bytes 4",
+            "hex --eol lf",
+            "hex -w 4 --eol crlf",
+            "hex --eol lf --eol-always",
+            "base64 --prefix x\\n --eol crlf",
+            "hex --suffix \\n --always-delimit --eol lf",
+        ] {
+            let args = line.split_whitespace().collect::<Vec<&str>>();
+            let output = bytary().args(&args).arg("-i").arg(&path).output().unwrap();
+            let length = output.stdout.len();
+
+            let format = args[0];
+            bytary()
+                .args(&args)
+                .arg("-i")
+                .arg(&path)
+                .arg("--dry-run")
+                .assert()
+                .success()
+                .stdout(format!(
+                    "Path: bytes => {}\nMemory: streaming\nOutput size of {}: {}\n",
+                    format,
+                    path.display(),
+                    bytary::utils::plural(length, "byte")
+                ));
+        }
+    }
+
+    bytary()
+        .args(["utf16le", "--dry-run"])
+        .write_stdin("Hi")
+        .assert()
+        .success()
+        .stdout("Path: bytes => utf16le\nMemory: streaming\nOutput size: unknown\n");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_numeric() {
+    bytary()
+        .args(["hex", "dec", "--numeric"])
+        .write_stdin("115_792_089_237_316_195_423_570_985_008_687_907_853_269_984_665_640_564_039_457_584_007_913_129_639_935\n")
+        .assert()
+        .success()
+        .stdout("f".repeat(64));
+    bytary()
+        .args(["bin", "hex", "--numeric", "--keep-width"])
+        .write_stdin("00 ff")
+        .assert()
+        .success()
+        .stdout("0000000011111111");
+    bytary()
+        .args(["dec", "oct", "--numeric"])
+        .write_stdin("0000")
+        .assert()
+        .success()
+        .stdout("0");
+
+    bytary()
+        .args(["hex", "dec", "--numeric"])
+        .write_stdin("")
+        .assert()
+        .code(1)
+        .stderr("Invalid input data: Empty input, expected a number\n");
+    bytary()
+        .args(["hex", "dec"])
+        .write_stdin("12")
+        .assert()
+        .code(2)
+        .stderr("Unsupported conversion: dec => hex\n");
+    bytary()
+        .args(["base64", "dec", "--numeric"])
+        .write_stdin("12")
+        .assert()
+        .code(2)
+        .stderr(
+            "Invalid argument: --numeric only supports bin, oct, dec or hex, not dec to base64\n",
+        );
+}
+
+#[test]
+fn test_patch() {
+    let dir = temp_dir("patch");
+    let patches = dir.join("patches.txt");
+    fs::write(&patches, "# greeting\n0x1: 61\n5: 21 21\n").unwrap();
+
+    bytary()
+        .args(["bytes", "--patch"])
+        .arg(&patches)
+        .write_stdin("Hello")
+        .assert()
+        .failure()
+        .stderr("Invalid input data: patch at 0x5 extends past the end of the input (5 bytes)\n");
+    bytary()
+        .args(["bytes", "--allow-extend", "--patch"])
+        .arg(&patches)
+        .write_stdin("Hello")
+        .assert()
+        .success()
+        .stdout("Hallo!!");
+    bytary()
+        .args(["bytes", "hex", "--patch"])
+        .arg(&patches)
+        .write_stdin("48 65 6c 6c 6f 20 20")
+        .assert()
+        .success()
+        .stdout("Hallo!!");
+    bytary()
+        .args(["hex", "--patch"])
+        .arg(&patches)
+        .write_stdin("Hello")
+        .assert()
+        .failure()
+        .stderr("Invalid argument: --patch requires bytes output, not hex\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_patterns() {
+    let blob = b"header\xff\xd8image\xff\xd9trailer\xff\xd8thumb\xff\xd9";
+
+    bytary()
+        .args(["hex", "--start-pattern", "ff d8", "--end-pattern", "ffd9"])
+        .write_stdin(&blob[..])
+        .assert()
+        .success()
+        .stdout("ffd8696d616765ffd9");
+    bytary()
+        .args(["bytes", "--start-pattern", "ffd8", "--end-pattern", "ffd9"])
+        .args(["--nth", "2", "--exclude-start", "--exclude-end", "--force"])
+        .write_stdin(&blob[..])
+        .assert()
+        .success()
+        .stdout("thumb");
+
+    bytary()
+        .args(["hex", "--start-pattern", "ff d8", "--end-pattern", "00"])
+        .write_stdin(&blob[..20])
+        .assert()
+        .success()
+        .stdout("ffd8696d616765ffd9747261696c")
+        .stderr("Warning: end pattern not found\n");
+    bytary()
+        .args(["hex", "--start-pattern", "ff d8", "--end-pattern", "00"])
+        .arg("--warnings-as-errors")
+        .write_stdin(&blob[..20])
+        .assert()
+        .failure()
+        .stderr("Warning: end pattern not found\n1 warning treated as errors\n");
+    bytary()
+        .args(["hex", "--end-pattern", "00", "--require-patterns"])
+        .write_stdin("abc")
+        .assert()
+        .failure()
+        .stderr("Invalid input data: end pattern not found\n");
+}
+
+#[test]
+fn test_self_test() {
+    let assert = bytary().arg("--self-test").assert().success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    assert!(stdout.starts_with("bytes <=> bin: ok\n"), "{}", stdout);
+    assert!(
+        stdout.lines().all(|line| line.ends_with(": ok")),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_warnings() {
+    bytary()
+        .args(["bytes", "hex"])
+        .write_stdin("48 69 2")
+        .assert()
+        .success()
+        .stdout("Hi")
+        .stderr("Warning: dropped 1 trailing digit, a byte needs 2\n");
+    bytary()
+        .args(["hex", "oct", "--mask-overflow", "--warnings-as-errors"])
+        .write_stdin("110,777")
+        .assert()
+        .code(1)
+        .stdout("48ff")
+        .stderr(
+            "Warning: kept the low 8 bits of 1 octal group above 377\n1 warning treated as errors\n",
+        );
+
+    // Characters that are not digits are not skipped with a warning, by any digit decoder
+    for (from, input) in [
+        ("hex", "1b x34"),
+        ("bin", "00011011 x00110100"),
+        ("oct", "033 x064"),
+        ("oct", "110,070;101"),
+    ] {
+        bytary()
+            .args(["bytes", from])
+            .write_stdin(input)
+            .assert()
+            .code(1)
+            .stdout("")
+            .stderr(predicate::str::contains("unexpected character"));
+    }
+}
+
+#[test]
+fn test_style() {
+    let uuid = b"\x12\x3e\x45\x67\xe8\x9b\x12\xd3\xa4\x56\x42\x66\x14\x17\x40\x00";
+    for (style, input, output) in [
+        ("mac", &b"\x1b\x34\x8f\xff\x00\x0e"[..], "1b:34:8f:ff:00:0e"),
+        ("spaced", b"\x1b\x34\x8f", "1b 34 8f"),
+        ("continuous", b"\x1b\x34\x8f", "1b348f"),
+        ("uuid", uuid, "123e4567-e89b-12d3-a456-426614174000"),
+    ] {
+        bytary()
+            .args(["hex", "--style", style])
+            .write_stdin(input)
+            .assert()
+            .success()
+            .stdout(output);
+        // The output has the shape it is expected to have, and converts back
+        bytary()
+            .args(["bytes", "hex", "--expect-style", style])
+            .write_stdin(output)
+            .assert()
+            .success()
+            .stdout(predicate::eq(input));
+    }
+
+    // uuid needs exactly 16 bytes, and writes nothing otherwise
+    for length in [15, 17] {
+        bytary()
+            .args(["hex", "--style", "uuid"])
+            .write_stdin(vec![0u8; length])
+            .assert()
+            .code(1)
+            .stdout("")
+            .stderr(format!(
+                "Invalid input data: --style uuid needs exactly 16 bytes, not {}\n",
+                length
+            ));
+    }
+
+    bytary()
+        .args(["bytes", "hex", "--expect-style", "mac"])
+        .write_stdin("1b:348f")
+        .assert()
+        .code(1)
+        .stderr(
+            "Invalid input data: input does not match the mac style: expected ':' at offset 5\n",
+        );
+    bytary()
+        .args(["bytes", "hex", "--expect-style", "uuid"])
+        .write_stdin("123e4567e89b12d3a456426614174000")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("does not match the uuid style"));
+
+    bytary()
+        .args(["base64", "--style", "mac"])
+        .assert()
+        .code(2)
+        .stderr("Invalid argument: --style mac only applies to hex output, not base64\n");
+    bytary()
+        .args(["hex", "--style", "mac", "-s", "2"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_paragraph() {
+    bytary()
+        .args(["bytes", "base64", "--paragraph", "--frame", "nul"])
+        .write_stdin("SGVs\nbG8=\n\n\nIQ==\n\n")
+        .assert()
+        .success()
+        .stdout(predicate::eq(&b"Hello\0!\0"[..]));
+    bytary()
+        .args(["bytes", "hex", "--paragraph"])
+        .write_stdin("41\n\n4g\n")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Paragraph 2 failed"));
+}
+
+#[test]
+fn test_bit_slice() {
+    // Bits 4..=13 of ab cd are 1011 110011
+    bytary()
+        .args([
+            "bin",
+            "hex",
+            "--data",
+            "abcd",
+            "--bit-skip",
+            "4",
+            "--bit-length",
+            "10",
+        ])
+        .assert()
+        .success()
+        .stdout("1011110011000000");
+    bytary()
+        .args(["hex", "--data-hex", "abcd", "--bit-skip", "4"])
+        .assert()
+        .success()
+        .stdout("bcd0");
+    bytary()
+        .args([
+            "hex",
+            "--data-hex",
+            "abcd",
+            "--bit-skip",
+            "4",
+            "--lsb-first",
+        ])
+        .assert()
+        .success()
+        .stdout("da0c");
+    bytary()
+        .args(["hex", "--data-hex", "abcd", "--bit-length", "0"])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn test_byteswap() {
+    bytary()
+        .args(["hex", "hex", "--data", "01020304 0a0b0c", "--byteswap", "4"])
+        .assert()
+        .success()
+        .stdout("040302010a0b0c")
+        .stderr("Warning: left a partial word of 3 bytes unswapped, a word has 4\n");
+    bytary()
+        .args(["hex", "hex", "--data", "0102", "--byteswap", "3"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_channel() {
+    // Left and right 16-bit samples
+    bytary()
+        .args([
+            "hex",
+            "hex",
+            "--data",
+            "0100ffff 0200feff",
+            "--channel",
+            "1/2:2",
+        ])
+        .assert()
+        .success()
+        .stdout("fffffeff");
+    bytary()
+        .args(["bytes", "bytes", "--data", "aAbBcC", "--channel", "1/2"])
+        .assert()
+        .success()
+        .stdout("ABC");
+    bytary()
+        .args(["hex", "hex", "--data", "0100ffff 02", "--channel", "0/2:2"])
+        .assert()
+        .success()
+        .stdout("0100")
+        .stderr(predicate::str::contains(
+            "Warning: dropped a partial frame of 1 byte, a frame has 4",
+        ));
+    bytary()
+        .args(["hex", "hex", "--data", "0100", "--channel", "2/2"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "channel 2 does not exist, there are 2 channels counted from 0",
+        ));
+    bytary()
+        .args(["hex", "hex", "--data", "0100", "--channel", "1"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid channel '1', expected SELECT/CHANNELS[:UNIT]",
+        ));
+    // Too large to multiply or to hold, rejected before reading anything
+    for channel in ["0/4294967296:4294967296", "0/1:1000000000000000"] {
+        bytary()
+            .args(["hex", "hex", "--data", "0100", "--channel", channel])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("are larger than 1073741824 bytes"));
+    }
+}
+
+#[test]
+fn test_leb128() {
+    bytary()
+        .args(["dec", "leb128", "--data-hex", "e58e26 7f"])
+        .assert()
+        .success()
+        .stdout("624485\n127\n");
+    bytary()
+        .args(["dec", "leb128", "--data-hex", "c0bb78 7f", "--signed"])
+        .assert()
+        .success()
+        .stdout("-123456\n-1\n");
+    bytary()
+        .args(["leb128", "dec", "--data=-123456 -1", "--signed"])
+        .assert()
+        .success()
+        .stdout(vec![0xc0, 0xbb, 0x78, 0x7f]);
+    bytary()
+        .args(["dec", "leb128", "--data-hex", "01 e58e"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "truncated LEB128 value at offset 1",
+        ));
+    bytary()
+        .args(["hex", "bytes", "--data", "a", "--signed"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "--signed only applies to leb128 input or output, not bytes to hex",
+        ));
+
+    // Other formats are read as LEB128 through bytes, but LEB128 only converts to dec and bytes
+    bytary()
+        .args(["leb128", "hex", "--data", "e58e26", "--verbose"])
+        .assert()
+        .success()
+        .stdout(vec![0xe5, 0x8e, 0x26])
+        .stderr(predicate::str::contains(
+            "Operation: hex => bytes => leb128\n",
+        ));
+    bytary()
+        .args(["hex", "leb128", "--data-hex", "e58e26"])
+        .assert()
+        .code(2)
+        .stderr("Unsupported conversion: leb128 => hex\n");
+    bytary()
+        .args(["hex", "bytes", "--data-hex", "e58e26"])
+        .assert()
+        .success()
+        .stdout("e58e26");
+}
+
+/// A localhost address with a port that was free a moment ago
+fn free_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+/// Connects to `addr` once bytary listens on it
+fn connect_when_ready(addr: &str) -> TcpStream {
+    for _ in 0..500 {
+        if let Ok(stream) = TcpStream::connect(addr) {
+            return stream;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("bytary did not listen on {}", addr);
+}
+
+#[test]
+fn test_listen() {
+    let addr = free_addr();
+    let sender = {
+        let addr = addr.clone();
+        thread::spawn(move || {
+            let mut stream = connect_when_ready(&addr);
+            stream.write_all(b"48 69\n").unwrap();
+            stream.write_all(b"21").unwrap();
+        })
+    };
+    bytary()
+        .args(["bytes", "hex", "--listen", &addr])
+        .assert()
+        .success()
+        .stdout("Hi!");
+    sender.join().unwrap();
+}
+
+#[test]
+fn test_listen_timeout() {
+    let addr = free_addr();
+    let (done, wait) = mpsc::channel::<()>();
+    let sender = {
+        let addr = addr.clone();
+        // Keeps the connection open without sending anything
+        thread::spawn(move || {
+            let _stream = connect_when_ready(&addr);
+            wait.recv().unwrap();
+        })
+    };
+    bytary()
+        .args(["bytes", "hex", "--listen", &addr, "--timeout", "100"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("socket timed out after 100 ms"));
+    done.send(()).unwrap();
+    sender.join().unwrap();
+}
+
+#[test]
+fn test_connect() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let receiver = thread::spawn(move || {
+        let mut received = Vec::new();
+        listener
+            .accept()
+            .unwrap()
+            .0
+            .read_to_end(&mut received)
+            .unwrap();
+        received
+    });
+    bytary()
+        .args(["hex", "--data", "Hi", "--connect", &addr])
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(receiver.join().unwrap(), b"4869");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix_socket() {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let dir = temp_dir("socket");
+    let path = dir.join("out.sock");
+    let listener = UnixListener::bind(&path).unwrap();
+    let receiver = thread::spawn(move || {
+        let mut received = Vec::new();
+        listener
+            .accept()
+            .unwrap()
+            .0
+            .read_to_end(&mut received)
+            .unwrap();
+        received
+    });
+    bytary()
+        .args(["hex", "--data", "Hi", "--connect"])
+        .arg(&path)
+        .assert()
+        .success();
+    assert_eq!(receiver.join().unwrap(), b"4869");
+
+    let path = dir.join("in.sock");
+    let sender = {
+        let path = path.clone();
+        thread::spawn(move || {
+            for _ in 0..500 {
+                if let Ok(mut stream) = UnixStream::connect(&path) {
+                    return stream.write_all(b"4869").unwrap();
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            panic!("bytary did not listen on {}", path.display());
+        })
+    };
+    bytary()
+        .args(["bytes", "hex", "--listen"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout("Hi");
+    sender.join().unwrap();
+    // The socket file is removed once the connection is accepted
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_data() {
+    // The argument is the whole input, stdin is not read
+    bytary()
+        .args(["bytes", "hex", "--data", "1b34"])
+        .write_stdin("ffff")
+        .assert()
+        .success()
+        .stdout(predicate::eq(&b"\x1b\x34"[..]));
+    // No trailing newline is added
+    bytary()
+        .args(["hex", "-d", "Hi"])
+        .assert()
+        .success()
+        .stdout("4869");
+    bytary()
+        .args(["hex", "--data-hex", "00ff0a"])
+        .assert()
+        .success()
+        .stdout("00ff0a");
+    bytary()
+        .args(["hex", "-d", "Hello", "--dry-run"])
+        .assert()
+        .success()
+        .stdout("Path: bytes => hex\nMemory: streaming\nOutput size: 10 bytes\n");
+
+    bytary()
+        .args(["hex", "--data-hex", "1b3"])
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(predicate::str::starts_with(
+            "Invalid argument: --data-hex: ",
+        ));
+    bytary()
+        .args(["hex", "--data", "1b", "-i", "input.bin"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--data <STRING>' cannot be used with '--input <FILE>...'",
+        ));
+    bytary()
+        .args(["hex", "--data", "1b", "--data-hex", "1b"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_verbose_path_cost() {
+    bytary()
+        .args(["oct", "bin", "--verbose", "--data", "01000001"])
+        .assert()
+        .success()
+        .stdout("101")
+        .stderr(predicate::str::contains(
+            "Operation: bin => hex => bytes => oct\nConverters: bin_to_hex, hex_to_bytes, bytes_to_oct\nCost: 3\n",
+        ))
+        .stderr(predicate::str::contains("Warning").not());
+}
+
+#[test]
+fn test_expect_eof() {
+    bytary()
+        .args(["hex", "base64", "--data", "SGk= \n", "--expect-eof"])
+        .assert()
+        .success()
+        .stdout("4869");
+    bytary()
+        .args(["hex", "base64", "--data", "SGk=SGk=", "--expect-eof"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Trailing data at offset 4"));
+}
+
+#[test]
+fn test_tee() {
+    let dir = temp_dir("tee");
+    let copy = dir.join("copy.hex");
+    let output = bytary()
+        .args(["hex", "--data", "Hi", "--style", "spaced", "--tee"])
+        .arg(&copy)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(output, b"48 69");
+    assert_eq!(fs::read(&copy).unwrap(), output);
+    let _ = fs::remove_dir_all(&dir);
+
+    #[cfg(target_os = "linux")]
+    bytary()
+        .args(["hex", "--data", "Hi", "--tee", "/dev/full"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("/dev/full: "));
+}
+
+#[test]
+fn test_head_tail_bytes() {
+    let data = (0..=255u8).cycle().take(100_000).collect::<Vec<u8>>();
+    let hex = hex::encode(&data);
+    bytary()
+        .args(["hex", "--head-bytes", "8"])
+        .write_stdin(data.clone())
+        .assert()
+        .success()
+        .stdout("00010203");
+    // The limit counts the output as written, and the delimiters are cut too
+    bytary()
+        .args(["hex", "--space", "2", "--prefix", "[", "--head-bytes", "5"])
+        .write_stdin(data.clone())
+        .assert()
+        .success()
+        .stdout("[00 0");
+    bytary()
+        .args(["hex", "--tail-bytes", "1KiB"])
+        .write_stdin(data.clone())
+        .assert()
+        .success()
+        .stdout(hex[hex.len() - 1024..].to_string());
+    // Only what is written is held, however large the tail
+    for size in ["1M", "100G", "1T"] {
+        bytary()
+            .args(["hex", "--tail-bytes", size])
+            .write_stdin("Hi")
+            .assert()
+            .success()
+            .stdout("4869");
+    }
+
+    bytary()
+        .args(["hex", "--head-bytes", "8", "--tail-bytes", "8"])
+        .write_stdin("Hi")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_also() {
+    let dir = temp_dir("also");
+    let bin = dir.join("data.bin");
+    let stats = dir.join("data.txt");
+    let data = (0..20000u32)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect::<Vec<u8>>();
+    // Standard input is a pipe, read once
+    let output = bytary()
+        .args(["hex", "--space", "0", "--also"])
+        .arg(format!("bin:{}", bin.display()))
+        .arg("--also")
+        .arg(format!("stats:{}", stats.display()))
+        .write_stdin(data.clone())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(output, hex::encode(&data).as_bytes());
+
+    let bin = fs::read_to_string(&bin).unwrap();
+    assert_eq!(bin.len(), data.len() * 8);
+    assert_eq!(&bin[..16], "0000000000000111");
+    let stats = fs::read_to_string(&stats).unwrap();
+    assert!(stats.starts_with("Total: 20000 bytes\n"), "{}", stats);
+
+    // The branches get the decoded input
+    let copy = dir.join("copy.b64");
+    bytary()
+        .args(["bin", "hex", "--also"])
+        .arg(format!("base64:{}", copy.display()))
+        .write_stdin("48 69")
+        .assert()
+        .success()
+        .stdout("0100100001101001");
+    assert_eq!(fs::read(&copy).unwrap(), b"SGk=");
+
+    // Decoding honours the options that replace a built-in conversion
+    let oct = dir.join("copy.oct");
+    bytary()
+        .args(["hex", "bin", "--pad", "left", "--also"])
+        .arg(format!("oct:{}", oct.display()))
+        .write_stdin("1001000")
+        .assert()
+        .success()
+        .stdout("48");
+    assert_eq!(fs::read(&oct).unwrap(), b"110");
+
+    bytary()
+        .args(["hex", "--data", "Hi", "--also", "bin:"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("expected FORMAT:PATH"));
+
+    let missing = dir.join("missing").join("data.bin");
+    bytary()
+        .args(["hex", "--data", "Hi", "--also"])
+        .arg(format!("bin:{}", missing.display()))
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains(format!(
+            "Branch bin:{} failed",
+            missing.display()
+        )));
+    let _ = fs::remove_dir_all(&dir);
+
+    #[cfg(target_os = "linux")]
+    bytary()
+        .args([
+            "hex",
+            "--data",
+            "Hi",
+            "--also",
+            "oct:/dev/null",
+            "--also",
+            "bin:/dev/full",
+        ])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("Branch bin:/dev/full failed"));
+}
+
+#[test]
+fn test_error_context() {
+    let dir = temp_dir("context");
+    let path = dir.join("data.hex");
+    fs::write(&path, "1b 34\r\n8f ff\r\n00 0g\r\n").unwrap();
+    bytary()
+        .args(["bytes", "hex", "-i"])
+        .arg(&path)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(format!(
+            "{}:3:5\n    00 0g\n        ^\n",
+            path.display()
+        )));
+
+    // Offsets count the line breaks that base64 skips
+    let path = dir.join("data.b64");
+    fs::write(&path, "SGVs\nbG8*\n").unwrap();
+    bytary()
+        .args(["bytes", "base64", "-i"])
+        .arg(&path)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(format!(
+            "{}:2:4\n    bG8*\n       ^\n",
+            path.display()
+        )));
+
+    // Offsets in the input without its comments are not those of the file
+    let path = dir.join("comments.hex");
+    fs::write(&path, "# 1b\n0g\n").unwrap();
+    bytary()
+        .args(["bytes", "hex", "--comments", "-i"])
+        .arg(&path)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("at offset 2"))
+        .stderr(predicate::str::contains(path.display().to_string() + ":").not());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_paste_cleanup() {
+    bytary()
+        .args(["hex", "hex", "--paste-cleanup", "--style", "spaced"])
+        .write_stdin("\u{feff}\u{201c}1b\u{a0}34\u{200b}8f\u{201d}")
+        .assert()
+        .success()
+        .stdout("1b 34 8f")
+        .stderr("Warning: removed 5 pasted characters like no-break spaces and curly quotes\n");
+    bytary()
+        .args(["bytes", "hex", "--paste-cleanup"])
+        .write_stdin("1b\u{a0}3g")
+        .assert()
+        .code(1);
+    bytary()
+        .args(["hex", "--paste-cleanup"])
+        .write_stdin("Hi")
+        .assert()
+        .code(2);
+
+    // Other non-ASCII characters are an error, unless skipped explicitly
+    bytary()
+        .args(["bytes", "hex", "--data", "1b\u{2014}34"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "unexpected character '\u{2014}' (U+2014) at offset 2",
+        ));
+    bytary()
+        .args(["bytes", "hex", "--paste-cleanup", "--skip-non-ascii"])
+        .write_stdin("1b\u{a0}\u{2014}34\u{2192}")
+        .assert()
+        .success()
+        .stdout(&[0x1b, 0x34][..])
+        .stderr(
+            "Warning: removed 1 pasted character like no-break spaces and curly quotes\n\
+             Warning: skipped 2 non-ASCII characters\n",
+        );
+    bytary()
+        .args(["bytes", "bin", "--paste-cleanup", "--skip-non-ascii"])
+        .write_stdin(&b"0100\xa01000"[..])
+        .assert()
+        .success()
+        .stdout("H")
+        .stderr("Warning: skipped 1 non-ASCII character\n");
+    bytary()
+        .args(["bytes", "hex", "--skip-non-ascii", "--data", "1b"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_lookalikes() {
+    // An error with the default lenient decoding, naming the code point
+    for (args, input, error) in [
+        (
+            &["bytes", "hex"][..],
+            "１２３４",
+            "'１' (U+FF11, full-width digit 1) at offset 0",
+        ),
+        (
+            &["hex", "bin"],
+            "01001000 ⁰¹⁰⁰¹⁰⁰⁰",
+            "'⁰' (U+2070, superscript digit 0) at offset 9",
+        ),
+        (
+            &["hex", "oct"],
+            "110 ٣٧٧",
+            "(U+0663, Arabic-Indic digit 3) at offset 4",
+        ),
+        (
+            &["leb128", "dec"],
+            "12 3４",
+            "(U+FF14, full-width digit 4) at offset 4",
+        ),
+        (
+            &["bytes", "hex", "--check"],
+            "ab１２",
+            "(U+FF11, full-width digit 1) at offset 2",
+        ),
+    ] {
+        bytary()
+            .args(args)
+            .write_stdin(input)
+            .assert()
+            .code(1)
+            .stdout("")
+            .stderr(predicate::str::contains(error));
+    }
+}
+
+#[test]
+fn test_control_characters() {
+    // Every decoder rejects a form feed with its offset, though the CLI collects warnings
+    for (format, input) in [
+        ("hex", "48\x0c69"),
+        ("bin", "01001000\x0c01101001"),
+        ("oct", "110\x0c151"),
+        ("base64", "SG\x0ck="),
+    ] {
+        let offset = input.find('\x0c').unwrap();
+        bytary()
+            .args(["bytes", format])
+            .write_stdin(input)
+            .assert()
+            .code(1)
+            .stdout("")
+            .stderr(predicate::str::contains(format!(
+                "'\\u{{c}}' at offset {}",
+                offset
+            )));
+    }
+}
+
+#[test]
+fn test_fold_fullwidth() {
+    bytary()
+        .args(["bytes", "hex", "--paste-cleanup", "--fold-fullwidth"])
+        .write_stdin("ＡＢ\u{3000}１２")
+        .assert()
+        .success()
+        .stdout(&[0xab, 0x12][..])
+        .stderr("Warning: replaced 5 full-width characters with their ASCII forms\n");
+    // An error without it, never skipped nor decoded
+    bytary()
+        .args(["bytes", "hex", "--paste-cleanup", "--skip-non-ascii"])
+        .write_stdin("ＡＢ12")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "(U+FF21, full-width letter A) at offset 0",
+        ));
+    bytary()
+        .args(["bytes", "hex", "--paste-cleanup", "--fold-fullwidth"])
+        .write_stdin("ab١٢")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "(U+0661, Arabic-Indic digit 1) at offset 2",
+        ));
+    bytary()
+        .args(["bytes", "hex", "--fold-fullwidth"])
+        .write_stdin("ＡＢ")
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_calibrate() {
+    bytary()
+        .args(["bytes", "hex", "--calibrate", "--data", "4869"])
+        .assert()
+        .success()
+        .stdout("Hi");
+    bytary()
+        .args([
+            "oct",
+            "bin",
+            "--calibrate",
+            "--dry-run",
+            "--data",
+            "0100100001101001",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Path: bin => "));
+}
+
+#[test]
+fn test_require_streaming() {
+    bytary()
+        .args(["bytes", "hex", "--require-streaming", "--data", "4869"])
+        .assert()
+        .success()
+        .stdout("Hi");
+    bytary()
+        .args(["bytes", "base64", "--require-streaming", "--data", "SGk="])
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(
+            "Invalid argument: --require-streaming refuses the conversion from base64 to bytes, which buffers input\n",
+        );
+    bytary()
+        .args(["oct", "hex", "--dry-run", "--data", "4869"])
+        .assert()
+        .success()
+        .stdout("Path: hex => bytes => oct\nMemory: buffers output\nOutput size: 6 bytes\n");
+}
+
+#[test]
+fn test_from_any() {
+    bytary()
+        .args(["bytes", "--from-any", "hex,base64", "--data", "SGk=", "-v"])
+        .assert()
+        .success()
+        .stdout("Hi")
+        .stderr(predicate::str::contains("Input format: base64\n"));
+    bytary()
+        .args(["hex", "--from-any", "bin,hex", "--data", "01000001"])
+        .assert()
+        .success()
+        .stdout("41");
+    bytary()
+        .args(["hex", "--from-any", "hex,bin", "--data", "zz"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(
+            "No format decoded the input: \
+            hex: Invalid input data: unexpected character 'z' at offset 0; \
+            bin: Invalid input data: unexpected character 'z' at offset 0\n",
+        );
+    bytary()
+        .args(["hex", "hex", "--from-any", "hex"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_expect() {
+    bytary()
+        .args([
+            "bytes",
+            "base64",
+            "--data",
+            "aHVudGVyMg==",
+            "--expect",
+            "hunter2",
+        ])
+        .assert()
+        .success()
+        .stdout("match, 7 bytes\n")
+        .stderr("");
+    bytary()
+        .args([
+            "bytes",
+            "base64",
+            "--data",
+            "aHVudGVyMg==",
+            "--expect",
+            "hunter3",
+        ])
+        .assert()
+        .code(1)
+        .stdout("mismatch, expected 7 bytes, got 7\n")
+        .stderr(predicate::str::contains("hunter").not());
+    bytary()
+        .args([
+            "bytes",
+            "base64",
+            "--data",
+            "aHVudGVyMg==",
+            "--expect",
+            "hunter2",
+        ])
+        .args(["--output", "secret"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_b64_alphabet() {
+    let crypt = "./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    bytary()
+        .args([
+            "base64",
+            "--no-pad",
+            "--b64-alphabet",
+            crypt,
+            "--data",
+            "Hi!?",
+        ])
+        .assert()
+        .success()
+        .stdout("G4YVDk");
+    bytary()
+        .args(["hex", "base64", "--b64-alphabet", crypt, "--data", "SGk+"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "unexpected character '+' at offset 3, which is not in the alphabet",
+        ));
+}
+
+#[test]
+fn test_no_pad_round_trip() {
+    for (format, encoded) in [("oct", "0 13 10 377"), ("bin", "0 1011 1000 11111111")] {
+        bytary()
+            .args([format, "--no-pad", "--space", "1", "--data-hex", "000b08ff"])
+            .assert()
+            .success()
+            .stdout(encoded);
+        // Decoded with --pad token, without warnings
+        bytary()
+            .args(["hex", format, "--pad", "token"])
+            .write_stdin(encoded)
+            .assert()
+            .success()
+            .stdout("000b08ff")
+            .stderr("");
+        bytary()
+            .args(["hex", format])
+            .write_stdin(encoded)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("which is not a multiple of"));
+    }
+}
+
+#[test]
+fn test_split_and_join() {
+    let dir = temp_dir("split");
+    let prefix = dir.join("out");
+    let data = (0..5 << 19)
+        .map(|i: u32| (i % 253) as u8)
+        .collect::<Vec<u8>>();
+
+    // Split after decoding hex, in one pass
+    bytary()
+        .args(["bytes", "hex", "--split-size", "1048576", "-o"])
+        .arg(&prefix)
+        .write_stdin(hex::encode(&data))
+        .assert()
+        .success();
+    let sizes =
+        ["out.000", "out.001", "out.002"].map(|name| fs::metadata(dir.join(name)).unwrap().len());
+    assert_eq!(sizes, [1 << 20, 1 << 20, 1 << 19]);
+
+    let joined = bytary()
+        .args(["--join"])
+        .arg(&prefix)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(joined, data);
+
+    fs::remove_file(dir.join("out.001")).unwrap();
+    bytary()
+        .args(["--join"])
+        .arg(&prefix)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("missing"))
+        .stderr(predicate::str::contains("out.001"));
+
+    bytary().args(["--split-size", "10"]).assert().code(2);
+}
+
+/// Windows translates line breaks and stops at 0x1A in text mode
+#[cfg(windows)]
+#[test]
+fn test_binary_stdio_round_trip() {
+    let data = b"\x0a\x0d\x1a\x00A\r\n\x1aB\n\xff".to_vec();
+    let hex = bytary()
+        .arg("hex")
+        .write_stdin(data.clone())
+        .assert()
+        .success()
+        .stdout("0a0d1a00410d0a1a420aff")
+        .get_output()
+        .stdout
+        .clone();
+    bytary()
+        .args(["bytes", "hex"])
+        .write_stdin(hex)
+        .assert()
+        .success()
+        .stdout(data.clone());
+    bytary()
+        .args(["bytes", "bytes"])
+        .write_stdin(data.clone())
+        .assert()
+        .success()
+        .stdout(data);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_field() {
+    bytary()
+        .args(["base64", "hex", "--json-field", "/data/0/blob"])
+        .write_stdin(r#"{"data": [{"blob": "1b348f"}]}"#)
+        .assert()
+        .success()
+        .stdout("GzSP");
+
+    let wrapped = bytary()
+        .args(["hex", "--json-wrap", "/data/0/blob"])
+        .write_stdin("Hi")
+        .assert()
+        .success()
+        .stdout(r#"{"data":{"0":{"blob":"4869"}}}"#)
+        .get_output()
+        .stdout
+        .clone();
+    bytary()
+        .args(["bytes", "hex", "--json-field", "/data/0/blob"])
+        .write_stdin(wrapped)
+        .assert()
+        .success()
+        .stdout("Hi");
+
+    for (input, pointer, error) in [
+        (r#"{"p": "4"#, "/p", "Invalid input data: invalid JSON: "),
+        (
+            r#"{"q": "48"}"#,
+            "/p",
+            "Invalid input data: JSON pointer '/p' not found",
+        ),
+        (
+            r#"{"p": [48]}"#,
+            "/p",
+            "Invalid input data: JSON value at '/p' is an array, not a string",
+        ),
+        ("{}", "p", "Invalid argument: invalid JSON pointer 'p'"),
+    ] {
+        bytary()
+            .args(["bytes", "hex", "--json-field", pointer])
+            .write_stdin(input)
+            .assert()
+            .failure()
+            .stderr(predicate::str::starts_with(error));
+    }
+}
+
+#[test]
+fn test_timestamps() {
+    bytary()
+        .args(["hex", "bytes", "--timestamps", "-s", "2", "-w", "8"])
+        .write_stdin("Hello, world")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"^(\+0\.\d{3}s [0-9a-f ]{11}\n){3}$")
+                .unwrap()
+                .and(predicate::str::contains("s 6f 2c 20 77\n")),
+        );
+    bytary()
+        .args(["hex", "bytes", "--timestamps=absolute", "-w", "4"])
+        .write_stdin("Hi")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d{3}Z 4869\n$")
+                .unwrap(),
+        );
+    for (args, error) in [
+        (
+            vec!["hex", "bytes", "--timestamps"],
+            "Invalid argument: --timestamps needs wrapped output",
+        ),
+        (
+            vec!["bytes", "hex", "--timestamps", "-w", "4"],
+            "Invalid argument: --timestamps only applies to text output",
+        ),
+    ] {
+        bytary()
+            .args(args)
+            .write_stdin("48")
+            .assert()
+            .failure()
+            .stderr(predicate::str::starts_with(error));
+    }
+    bytary()
+        .args(["hex", "bytes", "--timestamps=later", "-w", "4"])
+        .assert()
+        .failure();
+}
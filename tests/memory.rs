@@ -0,0 +1,118 @@
+//! Runs every edge of the default graph that says it streams on 32 MiB of input,
+//! checking that the memory it allocates stays small.
+//!
+//! This is the only test of this binary, so that no other test allocates meanwhile.
+
+use bytary::convert::{ConversionGraph, StreamingClass};
+use bytary::format::Format;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io;
+use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the bytes allocated now and the most allocated at once
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const INPUT_SIZE: usize = 32 << 20;
+
+/// Memory that a streaming edge may allocate besides its input
+const BOUND: usize = 1 << 20;
+
+/// Reads `unit` over and over until `remaining` bytes were read, without allocating
+struct Repeat<'a> {
+    unit: &'a [u8],
+    offset: usize,
+    remaining: usize,
+}
+
+impl Read for Repeat<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = buf
+            .len()
+            .min(self.remaining)
+            .min(self.unit.len() - self.offset);
+        buf[..length].copy_from_slice(&self.unit[self.offset..self.offset + length]);
+        self.offset = (self.offset + length) % self.unit.len();
+        self.remaining -= length;
+        Ok(length)
+    }
+}
+
+/// Input of `format` that can be repeated: text encoded to `format`, followed by a separator if
+/// encodings can not be put back to back. `None` if no such input can be made from bytes.
+fn unit(graph: &ConversionGraph, format: &Format) -> Option<Vec<u8>> {
+    let text = b"The quick brown fox jumps over the lazy dog. 0123456789\n".repeat(1200);
+    let converter = graph.get_converter(&Format::Bytes, format)?;
+    let mut encoded = Vec::new();
+    converter.convert(&mut &text[..], &mut encoded).ok()?;
+
+    let decoder = graph.get_converter(format, &Format::Bytes)?;
+    [&b""[..], b" ", b"\n"].into_iter().find_map(|separator| {
+        let unit = [&encoded[..], separator].concat();
+        let twice = unit.repeat(2);
+        let mut decoded = Vec::new();
+        decoder.convert(&mut &twice[..], &mut decoded).ok()?;
+        (decoded == text.repeat(2)).then_some(unit)
+    })
+}
+
+#[test]
+fn test_streaming_edges_bounded() {
+    let graph = ConversionGraph::default();
+    let mut failures = Vec::new();
+
+    for (from, to, info, converter) in graph.edges() {
+        if converter.streaming_class() != StreamingClass::Streaming {
+            continue;
+        }
+        let Some(unit) = unit(&graph, from) else {
+            failures.push(format!(
+                "{} says it streams, but no {} input can be made",
+                info.name, from
+            ));
+            continue;
+        };
+        let mut input = Repeat {
+            unit: &unit,
+            offset: 0,
+            remaining: INPUT_SIZE - INPUT_SIZE % unit.len(),
+        };
+
+        let base = CURRENT.load(Ordering::SeqCst);
+        PEAK.store(base, Ordering::SeqCst);
+        let result = converter.convert(&mut input, &mut io::sink());
+        let used = PEAK.load(Ordering::SeqCst) - base;
+
+        if let Err(e) = result {
+            failures.push(format!("{} {} => {} failed: {}", info.name, from, to, e));
+        } else if used > BOUND {
+            failures.push(format!(
+                "{} {} => {} says it streams, but allocated {} bytes at once",
+                info.name, from, to, used
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}